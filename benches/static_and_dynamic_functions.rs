@@ -90,6 +90,39 @@ pub fn run_basic_static_function(store: &Store, compiler_name: &str, c: &mut Cri
             })
         },
     );
+
+    let f_imported: NativeFunc<(i32, i32), i32> = instance
+        .exports
+        .get_native_function("double_then_add")
+        .unwrap();
+    c.bench_function(&format!("basic static host call {}", compiler_name), |b| {
+        b.iter(|| {
+            let result = black_box(f_imported.call(4, 6).unwrap());
+            assert_eq!(result, 20);
+        })
+    });
+
+    let infallible_import_object = imports! {
+        "env" => {
+            "multiply" => unsafe {
+                Function::new_native_infallible(&store, |a: i32, b: i32| a.wrapping_mul(b))
+            },
+        },
+    };
+    let infallible_instance = Instance::new(&module, &infallible_import_object).unwrap();
+    let f_infallible: NativeFunc<(i32, i32), i32> = infallible_instance
+        .exports
+        .get_native_function("double_then_add")
+        .unwrap();
+    c.bench_function(
+        &format!("basic static infallible host call {}", compiler_name),
+        |b| {
+            b.iter(|| {
+                let result = black_box(f_infallible.call(4, 6).unwrap());
+                assert_eq!(result, 20);
+            })
+        },
+    );
 }
 
 pub fn run_basic_dynamic_function(store: &Store, compiler_name: &str, c: &mut Criterion) {
@@ -109,6 +142,64 @@ pub fn run_basic_dynamic_function(store: &Store, compiler_name: &str, c: &mut Cr
         })
     });
 
+    let mut prepared = dyn_f.prepare_call().unwrap();
+    let mut results = [Val::I32(0)];
+    c.bench_function(&format!("basic prepared dynfunc {}", compiler_name), |b| {
+        b.iter(|| {
+            prepared
+                .call(&[Val::I32(4), Val::I32(6)], &mut results)
+                .unwrap();
+            assert_eq!(black_box(&results)[0], Val::I32(10));
+        })
+    });
+
+    // The same host call through a dynamic function, with and without
+    // checking its results against the signature.
+    let multiply_signature = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    let multiply = |args: &[Val]| {
+        Ok(vec![Val::I32(
+            args[0].unwrap_i32().wrapping_mul(args[1].unwrap_i32()),
+        )])
+    };
+    let checked_import_object = imports! {
+        "env" => {
+            "multiply" => Function::new(&store, &multiply_signature, multiply),
+        },
+    };
+    let checked_instance = Instance::new(&module, &checked_import_object).unwrap();
+    let f_checked: NativeFunc<(i32, i32), i32> = checked_instance
+        .exports
+        .get_native_function("double_then_add")
+        .unwrap();
+    c.bench_function(&format!("basic dynamic host call {}", compiler_name), |b| {
+        b.iter(|| {
+            let result = black_box(f_checked.call(4, 6).unwrap());
+            assert_eq!(result, 20);
+        })
+    });
+
+    let unchecked_import_object = imports! {
+        "env" => {
+            "multiply" => unsafe {
+                Function::new_dynamic_unchecked(&store, &multiply_signature, multiply)
+            },
+        },
+    };
+    let unchecked_instance = Instance::new(&module, &unchecked_import_object).unwrap();
+    let f_unchecked: NativeFunc<(i32, i32), i32> = unchecked_instance
+        .exports
+        .get_native_function("double_then_add")
+        .unwrap();
+    c.bench_function(
+        &format!("basic unchecked dynamic host call {}", compiler_name),
+        |b| {
+            b.iter(|| {
+                let result = black_box(f_unchecked.call(4, 6).unwrap());
+                assert_eq!(result, 20);
+            })
+        },
+    );
+
     let dyn_f_many: &Function = instance.exports.get("add20").unwrap();
     c.bench_function(
         &format!("basic dynfunc with many args {}", compiler_name),
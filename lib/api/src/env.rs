@@ -82,6 +82,59 @@ impl From<ExportError> for HostEnvInitError {
 /// When implementing the trait manually, it's important to get a "weak" export to
 /// prevent a cyclic reference leaking memory. You can access a "weak" export with
 /// a method like `get_with_generics_weak`.
+///
+/// # Ownership
+///
+/// A host function owns the environment it is created with, and every
+/// instance importing the function owns a clone of it. The original is
+/// dropped with the last clone of the [`Function`](crate::Function),
+/// and the clone of an instance once the instance and its exports are
+/// dropped. Resources that must only be released once, when all of
+/// them are gone, should be shared through an `Arc` or
+/// [`Store::register_env`](crate::Store::register_env).
+///
+/// # Calling back into WebAssembly
+///
+/// A host function can call the exported functions of the instance that
+/// imports it, for example a callback registered by the guest, by keeping
+/// them in its environment. Such calls can be nested arbitrarily deep:
+/// every call into WebAssembly saves and restores the trap handling state
+/// of the calls it's nested in. A trap in the re-entered code is returned
+/// to the host function as a [`RuntimeError`], and returning that error
+/// from the host function propagates the trap, with its trap code, to the
+/// outer caller.
+///
+/// ```
+/// # use wasmer::{imports, Function, Instance, LazyInit, Module, NativeFunc, Store, WasmerEnv};
+/// # fn main() -> anyhow::Result<()> {
+/// # let store = Store::default();
+/// #[derive(WasmerEnv, Clone)]
+/// struct Env {
+///     #[wasmer(export)]
+///     callback: LazyInit<NativeFunc<i32, i32>>,
+/// }
+///
+/// let module = Module::new(&store, r#"(module
+///     (import "host" "call_callback" (func $call_callback (param i32) (result i32)))
+///     (func (export "callback") (param i32) (result i32)
+///         (i32.mul (local.get 0) (i32.const 2)))
+///     (func (export "run") (param i32) (result i32)
+///         (call $call_callback (local.get 0))))"#)?;
+/// let env = Env { callback: LazyInit::new() };
+/// let call_callback = Function::new_native_with_env(&store, env, |env: &Env, value: i32| {
+///     env.callback_ref().unwrap().call(value)
+/// });
+/// let instance = Instance::new(&module, &imports! {
+///     "host" => { "call_callback" => call_callback },
+/// })?;
+///
+/// let run: NativeFunc<i32, i32> = instance.exports.get_native_function("run")?;
+/// assert_eq!(run.call(21)?, 42);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`RuntimeError`]: crate::RuntimeError
 pub trait WasmerEnv: Clone + Send + Sync {
     /// The function that Wasmer will call on your type to let it finish
     /// setting up the environment with data from the `Instance`.
@@ -133,6 +186,46 @@ impl<T: WasmerEnv> WasmerEnv for ::std::sync::Arc<::std::sync::Mutex<T>> {
     }
 }
 
+/// An environment shared by several host functions, registered in a
+/// [`Store`](crate::Store) with [`Store::register_env`](crate::Store::register_env).
+///
+/// Cloning a `SharedEnv` only clones a reference to the environment,
+/// which is allocated once, and lives until the store and the functions
+/// using it are dropped. The functions only get a shared reference to
+/// it, so mutable state needs interior mutability, such as a `Mutex`
+/// or atomics.
+///
+/// The environment is not initialized with the instances importing the
+/// functions, which may be many: it can't hold [`LazyInit`] exports.
+#[derive(Debug)]
+pub struct SharedEnv<T> {
+    inner: std::sync::Arc<T>,
+}
+
+impl<T> SharedEnv<T> {
+    pub(crate) fn new(inner: std::sync::Arc<T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T> Clone for SharedEnv<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> std::ops::Deref for SharedEnv<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Send + Sync> WasmerEnv for SharedEnv<T> {}
+
 /// Lazily init an item
 pub struct LazyInit<T: Sized> {
     /// The data to be initialized
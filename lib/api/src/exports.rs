@@ -1,7 +1,7 @@
 use crate::externals::{Extern, Function, Global, Memory, Table};
 use crate::import_object::LikeNamespace;
 use crate::native::NativeFunc;
-use crate::WasmTypeList;
+use crate::{FunctionType, WasmTypeList};
 use indexmap::IndexMap;
 use loupe::MemoryUsage;
 use std::fmt;
@@ -179,6 +179,32 @@ impl Exports {
         Ok(out)
     }
 
+    /// Get all the exported functions whose signature is exactly `ty`,
+    /// along with their export names, in export order.
+    pub fn get_functions_of_type(&self, ty: &FunctionType) -> Vec<(&String, &Function)> {
+        self.map
+            .iter()
+            .filter_map(|(name, extern_)| match extern_ {
+                Extern::Function(function) if function.ty() == ty => Some((name, function)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Get all the exported functions whose name starts with `prefix`,
+    /// along with their export names, in export order.
+    pub fn get_functions_with_prefix(&self, prefix: &str) -> Vec<(String, Function)> {
+        self.map
+            .iter()
+            .filter_map(|(name, extern_)| match extern_ {
+                Extern::Function(function) if name.starts_with(prefix) => {
+                    Some((name.clone(), function.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
     /// Get an export as an `Extern`.
     pub fn get_extern(&self, name: &str) -> Option<&Extern> {
         self.map.get(name)
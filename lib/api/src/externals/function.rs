@@ -1,23 +1,27 @@
 use crate::exports::{ExportError, Exportable};
 use crate::externals::Extern;
-use crate::store::Store;
-use crate::types::{Val, ValFuncRef};
+use crate::metrics::{measure, CallMetrics, HostCallGuard};
+use crate::store::{Store, StoreFrozen};
+use crate::types::{Val, ValFuncRef, ValType};
 use crate::FunctionType;
+use crate::Memory;
 use crate::NativeFunc;
 use crate::RuntimeError;
+use crate::Type;
 use crate::WasmerEnv;
 pub use inner::{FromToNativeWasmType, HostFunction, WasmTypeList, WithEnv, WithoutEnv};
 
 use loupe::MemoryUsage;
 use std::cmp::max;
+use std::convert::TryFrom;
 use std::ffi::c_void;
 use std::fmt;
 use std::sync::Arc;
 use wasmer_engine::{Export, ExportFunction, ExportFunctionMetadata};
 use wasmer_vm::{
     raise_user_trap, resume_panic, wasmer_call_trampoline, ImportInitializerFuncPtr,
-    VMCallerCheckedAnyfunc, VMDynamicFunctionContext, VMFuncRef, VMFunction, VMFunctionBody,
-    VMFunctionEnvironment, VMFunctionKind, VMTrampoline,
+    VMCallerCheckedAnyfunc, VMContext, VMDynamicFunctionContext, VMFuncRef, VMFunction,
+    VMFunctionBody, VMFunctionEnvironment, VMFunctionKind, VMTrampoline,
 };
 
 /// A WebAssembly `function` instance.
@@ -41,6 +45,50 @@ use wasmer_vm::{
 pub struct Function {
     pub(crate) store: Store,
     pub(crate) exported: ExportFunction,
+    #[loupe(skip)]
+    pub(crate) label: FunctionLabel,
+    /// How to copy the context of a dynamic host function with another
+    /// label, see [`Function::with_label`].
+    #[loupe(skip)]
+    relabel: Option<Relabel>,
+}
+
+/// The label of a [`Function`], see [`Function::with_label`].
+#[derive(Clone, Default)]
+pub(crate) struct FunctionLabel(Option<Arc<str>>);
+
+impl FunctionLabel {
+    fn get(&self) -> Option<String> {
+        self.0.as_deref().map(str::to_string)
+    }
+
+    /// Returns the label quoted after `prefix` and a space, to identify
+    /// the function in an error message, or nothing if it has no label.
+    pub(crate) fn describe(&self, prefix: &str) -> String {
+        match self.get() {
+            Some(label) => format!("{} `{}`", prefix, label),
+            None => String::new(),
+        }
+    }
+}
+
+/// Copies the export of a dynamic host function, giving the copy of its
+/// context another label.
+#[derive(Clone, Copy)]
+pub(crate) struct Relabel(fn(&ExportFunction, &FunctionLabel) -> ExportFunction);
+
+// Like labels, it doesn't tell functions apart.
+impl PartialEq for Relabel {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+// Labels are only used for diagnostics: they don't tell functions apart.
+impl PartialEq for FunctionLabel {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
 }
 
 impl wasmer_types::WasmValueType for Function {
@@ -116,6 +164,11 @@ impl Function {
     /// If you know the signature of the host function at compile time,
     /// consider using [`Function::new_native`] for less runtime overhead.
     ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`].
+    /// [`Function::try_new`] returns an error instead.
+    ///
     /// # Examples
     ///
     /// ```
@@ -154,12 +207,29 @@ impl Function {
         Self::new_with_env(store, ty, WithoutEnv, wrapped_func)
     }
 
+    /// Like [`Function::new`], but returns an error instead of
+    /// panicking if the store has been frozen.
+    pub fn try_new<FT, F>(store: &Store, ty: FT, func: F) -> Result<Self, StoreFrozen>
+    where
+        FT: Into<FunctionType>,
+        F: Fn(&[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
+    {
+        let wrapped_func =
+            move |_env: &WithoutEnv, args: &[Val]| -> Result<Vec<Val>, RuntimeError> { func(args) };
+        Self::try_new_with_env(store, ty, WithoutEnv, wrapped_func)
+    }
+
     /// Creates a new host `Function` (dynamic) with the provided signature and environment.
     ///
     /// If you know the signature of the host function at compile time,
     /// consider using [`Function::new_native_with_env`] for less runtime
     /// overhead.
     ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`].
+    /// [`Function::try_new_with_env`] returns an error instead.
+    ///
     /// # Examples
     ///
     /// ```
@@ -198,25 +268,167 @@ impl Function {
     ///     Ok(vec![Value::I32(result)])
     /// });
     /// ```
-    #[allow(clippy::cast_ptr_alignment)]
     pub fn new_with_env<FT, F, Env>(store: &Store, ty: FT, env: Env, func: F) -> Self
     where
         FT: Into<FunctionType>,
         F: Fn(&Env, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
         Env: Sized + WasmerEnv + 'static,
     {
-        let ty: FunctionType = ty.into();
-        let dynamic_ctx: VMDynamicFunctionContext<DynamicFunction<Env>> =
-            VMDynamicFunctionContext::from_context(DynamicFunction {
+        Self::unwrap_not_frozen(Self::try_new_with_env(store, ty, env, func))
+    }
+
+    /// Like [`Function::new_with_env`], but returns an error instead of
+    /// panicking if the store has been frozen.
+    pub fn try_new_with_env<FT, F, Env>(
+        store: &Store,
+        ty: FT,
+        env: Env,
+        func: F,
+    ) -> Result<Self, StoreFrozen>
+    where
+        FT: Into<FunctionType>,
+        F: Fn(&Env, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
+        Env: Sized + WasmerEnv + 'static,
+    {
+        Self::new_dynamic(
+            store,
+            ty.into(),
+            env,
+            Arc::new(move |env: &Env, _caller: &Caller, args: &[Val]| func(env, args)),
+            true,
+        )
+    }
+
+    /// Creates a new host `Function` (dynamic) with the provided
+    /// signature, whose results aren't checked against the signature.
+    ///
+    /// This is the same as [`Function::new`], except that the types of
+    /// the values returned by the host function aren't compared to the
+    /// results of the signature on every call, which makes calls from
+    /// Wasm cheaper for functions with many results.
+    ///
+    /// # Safety
+    ///
+    /// The host function must always return as many values as the
+    /// signature has results, of the same types, in the same order, or
+    /// return an `Err`. Anything else is undefined behavior.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Function, FunctionType, Type, Store, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let signature = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    ///
+    /// let f = unsafe {
+    ///     Function::new_dynamic_unchecked(&store, &signature, |args| {
+    ///         let sum = args[0].unwrap_i32().wrapping_add(args[1].unwrap_i32());
+    ///         Ok(vec![Value::I32(sum)])
+    ///     })
+    /// };
+    /// ```
+    pub unsafe fn new_dynamic_unchecked<FT, F>(store: &Store, ty: FT, func: F) -> Self
+    where
+        FT: Into<FunctionType>,
+        F: Fn(&[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
+    {
+        Self::unwrap_not_frozen(Self::new_dynamic(
+            store,
+            ty.into(),
+            WithoutEnv,
+            Arc::new(move |_env: &WithoutEnv, _caller: &Caller, args: &[Val]| func(args)),
+            false,
+        ))
+    }
+
+    /// Creates a new host `Function` (dynamic) with the provided
+    /// signature, that receives the [`Caller`] along with the arguments.
+    ///
+    /// The caller gives access to the memories of the instance calling
+    /// the function, for example to read the data a guest pointer
+    /// argument points to.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmer::{Function, FunctionType, Type, Store, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let signature = FunctionType::new(vec![Type::I32], vec![Type::I32]);
+    ///
+    /// let f = Function::new_with_caller(&store, &signature, |caller, args| {
+    ///     let memory = caller.memory(0).expect("the caller has no memory");
+    ///     let byte = memory.view::<u8>()[args[0].unwrap_i32() as usize].get();
+    ///     Ok(vec![Value::I32(byte as i32)])
+    /// });
+    /// ```
+    pub fn new_with_caller<FT, F>(store: &Store, ty: FT, func: F) -> Self
+    where
+        FT: Into<FunctionType>,
+        F: Fn(&Caller, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
+    {
+        Self::unwrap_not_frozen(Self::new_dynamic(
+            store,
+            ty.into(),
+            WithoutEnv,
+            Arc::new(move |_env: &WithoutEnv, caller: &Caller, args: &[Val]| func(caller, args)),
+            true,
+        ))
+    }
+
+    #[allow(clippy::cast_ptr_alignment)]
+    #[allow(clippy::type_complexity)]
+    fn new_dynamic<Env>(
+        store: &Store,
+        ty: FunctionType,
+        env: Env,
+        func: Arc<
+            dyn Fn(&Env, &Caller, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
+        >,
+        checks_results: bool,
+    ) -> Result<Self, StoreFrozen>
+    where
+        Env: Sized + WasmerEnv + 'static,
+    {
+        store.check_not_frozen()?;
+        Ok(Self {
+            store: store.clone(),
+            exported: Self::export_dynamic(DynamicFunction {
                 env: Box::new(env),
-                func: Arc::new(func),
+                func,
                 store: store.clone(),
-                function_type: ty.clone(),
-            });
+                function_type: ty,
+                caller_memories: Vec::new(),
+                checks_results,
+                label: FunctionLabel::default(),
+            }),
+            label: FunctionLabel::default(),
+            relabel: Some(Relabel(Self::relabel_dynamic::<Env>)),
+        })
+    }
+
+    /// Builds the export of a dynamic host function.
+    fn export_dynamic<Env>(dynamic: DynamicFunction<Env>) -> ExportFunction
+    where
+        Env: Sized + WasmerEnv + 'static,
+    {
+        let ty = dynamic.function_type.clone();
+        let dynamic_ctx: VMDynamicFunctionContext<DynamicFunction<Env>> =
+            VMDynamicFunctionContext::from_context(dynamic);
 
         let import_init_function_ptr: for<'a> fn(&'a mut _, &'a _) -> Result<(), _> =
             |env: &mut VMDynamicFunctionContext<DynamicFunction<Env>>,
              instance: &crate::Instance| {
+                env.ctx.caller_memories = instance.memories_weak();
                 Env::init_with_instance(&mut *env.ctx.env, instance)
             };
 
@@ -230,27 +442,46 @@ impl Function {
         let address = std::ptr::null() as *const VMFunctionBody;
         let vmctx = VMFunctionEnvironment { host_env };
 
-        Self {
-            store: store.clone(),
-            exported: ExportFunction {
-                metadata: Some(Arc::new(metadata)),
-                vm_function: VMFunction {
-                    address,
-                    kind: VMFunctionKind::Dynamic,
-                    vmctx,
-                    signature: ty,
-                    call_trampoline: None,
-                    instance_ref: None,
-                },
+        ExportFunction {
+            metadata: Some(Arc::new(metadata)),
+            vm_function: VMFunction {
+                address,
+                kind: VMFunctionKind::Dynamic,
+                vmctx,
+                signature: ty,
+                call_trampoline: None,
+                instance_ref: None,
             },
         }
     }
 
+    /// Copies the export of a dynamic host function built by
+    /// [`Function::export_dynamic`], with `label` in its context.
+    fn relabel_dynamic<Env>(exported: &ExportFunction, label: &FunctionLabel) -> ExportFunction
+    where
+        Env: Sized + WasmerEnv + 'static,
+    {
+        // Safety: the context of the function was built for `Env`.
+        let ctx = unsafe {
+            &*(exported.vm_function.vmctx.host_env
+                as *const VMDynamicFunctionContext<DynamicFunction<Env>>)
+        };
+        let mut dynamic = ctx.ctx.clone();
+        dynamic.caller_memories = Vec::new();
+        dynamic.label = label.clone();
+        Self::export_dynamic(dynamic)
+    }
+
     /// Creates a new host `Function` from a native function.
     ///
     /// The function signature is automatically retrieved using the
     /// Rust typing system.
     ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`].
+    /// [`Function::try_new_native`] returns an error instead.
+    ///
     /// # Example
     ///
     /// ```
@@ -264,6 +495,18 @@ impl Function {
     /// let f = Function::new_native(&store, sum);
     /// ```
     pub fn new_native<F, Args, Rets, Env>(store: &Store, func: F) -> Self
+    where
+        F: HostFunction<Args, Rets, WithoutEnv, Env>,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        Env: Sized + 'static,
+    {
+        Self::unwrap_not_frozen(Self::try_new_native(store, func))
+    }
+
+    /// Like [`Function::new_native`], but returns an error instead of
+    /// panicking if the store has been frozen.
+    pub fn try_new_native<F, Args, Rets, Env>(store: &Store, func: F) -> Result<Self, StoreFrozen>
     where
         F: HostFunction<Args, Rets, WithoutEnv, Env>,
         Args: WasmTypeList,
@@ -274,13 +517,145 @@ impl Function {
             Self::closures_unsupported_panic();
         }
         let function = inner::Function::<Args, Rets>::new(func);
+        Self::from_static_function(store, function)
+    }
+
+    /// Creates a new host `Function` from a native function that is
+    /// guaranteed not to panic.
+    ///
+    /// This is the same as [`Function::new_native`], except that the
+    /// host function isn't wrapped in [`std::panic::catch_unwind`],
+    /// which makes calls from Wasm cheaper. Returning an `Err` from the
+    /// host function still raises a trap as usual.
+    ///
+    /// # Safety
+    ///
+    /// The host function must never panic: a panic would unwind
+    /// through Wasm frames, which is undefined behavior.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Store, Function};
+    /// # let store = Store::default();
+    /// #
+    /// fn sum(a: i32, b: i32) -> i32 {
+    ///     a.wrapping_add(b)
+    /// }
+    ///
+    /// let f = unsafe { Function::new_native_infallible(&store, sum) };
+    /// ```
+    pub unsafe fn new_native_infallible<F, Args, Rets, Env>(store: &Store, func: F) -> Self
+    where
+        F: HostFunction<Args, Rets, WithoutEnv, Env>,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        Env: Sized + 'static,
+    {
+        if std::mem::size_of::<F>() != 0 {
+            Self::closures_unsupported_panic();
+        }
+        let function = inner::Function::<Args, Rets>::new_infallible(func);
+        Self::unwrap_not_frozen(Self::from_static_function(store, function))
+    }
+
+    /// Creates a new host `Function` from a raw function pointer and its
+    /// signature, such as a C function.
+    ///
+    /// A [`VMFunctionKind::Static`] function is called with `vmctx`
+    /// as its first argument, followed by the parameters of `ty`, and
+    /// returns the results of `ty`, following the calling convention
+    /// of the host. For example, `extern "C" fn(*mut VMContext, i32,
+    /// i32) -> i32` matches `[I32, I32] -> [I32]`.
+    ///
+    /// # Safety
+    ///
+    /// - `ptr` must be a function with exactly that ABI. A mismatch
+    ///   with `ty` is undefined behavior when the function is called.
+    /// - `vmctx` is passed as it is, and must stay valid for as long
+    ///   as the function can be called.
+    /// - A [`VMFunctionKind::Dynamic`] function must be the address
+    ///   and environment of a dynamic function created by Wasmer, for
+    ///   example one of an existing `Function`.
+    /// - The function must not unwind: it can report errors with
+    ///   [`raise_user_trap`](crate::raise_user_trap).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Function, FunctionType, Store, Type};
+    /// # use wasmer::vm::{VMContext, VMFunctionBody, VMFunctionKind};
+    /// # let store = Store::default();
+    /// #
+    /// extern "C" fn sum(_vmctx: *mut VMContext, a: i32, b: i32) -> i32 {
+    ///     a.wrapping_add(b)
+    /// }
+    ///
+    /// let signature = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+    /// let f = unsafe {
+    ///     Function::from_raw_parts(
+    ///         &store,
+    ///         &signature,
+    ///         sum as *const VMFunctionBody,
+    ///         std::ptr::null_mut(),
+    ///         VMFunctionKind::Static,
+    ///     )
+    /// };
+    ///
+    /// assert_eq!(f.native::<(i32, i32), i32>().unwrap().call(1, 2).unwrap(), 3);
+    /// ```
+    pub unsafe fn from_raw_parts(
+        store: &Store,
+        ty: &FunctionType,
+        ptr: *const VMFunctionBody,
+        vmctx: *mut VMContext,
+        kind: VMFunctionKind,
+    ) -> Self {
+        if let Err(e) = store.check_not_frozen() {
+            panic!("{}", e);
+        }
+        Self {
+            store: store.clone(),
+            exported: ExportFunction {
+                metadata: None,
+                vm_function: VMFunction {
+                    address: ptr,
+                    vmctx: VMFunctionEnvironment { vmctx },
+                    signature: ty.clone(),
+                    kind,
+                    call_trampoline: None,
+                    instance_ref: None,
+                },
+            },
+            label: FunctionLabel::default(),
+            relabel: None,
+        }
+    }
+
+    fn from_static_function<Args, Rets>(
+        store: &Store,
+        function: inner::Function<Args, Rets>,
+    ) -> Result<Self, StoreFrozen>
+    where
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+    {
+        store.check_not_frozen()?;
         let address = function.address() as *const VMFunctionBody;
         let vmctx = VMFunctionEnvironment {
             host_env: std::ptr::null_mut() as *mut _,
         };
         let signature = function.ty();
 
-        Self {
+        Ok(Self {
             store: store.clone(),
             exported: ExportFunction {
                 // TODO: figure out what's going on in this function: it takes an `Env`
@@ -295,7 +670,9 @@ impl Function {
                     instance_ref: None,
                 },
             },
-        }
+            label: FunctionLabel::default(),
+            relabel: None,
+        })
     }
 
     /// Creates a new host `Function` from a native function and a provided environment.
@@ -303,6 +680,11 @@ impl Function {
     /// The function signature is automatically retrieved using the
     /// Rust typing system.
     ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`].
+    /// [`Function::try_new_native_with_env`] returns an error instead.
+    ///
     /// # Example
     ///
     /// ```
@@ -322,6 +704,22 @@ impl Function {
     /// let f = Function::new_native_with_env(&store, env, sum_and_multiply);
     /// ```
     pub fn new_native_with_env<F, Args, Rets, Env>(store: &Store, env: Env, func: F) -> Self
+    where
+        F: HostFunction<Args, Rets, WithEnv, Env>,
+        Args: WasmTypeList,
+        Rets: WasmTypeList,
+        Env: Sized + WasmerEnv + 'static,
+    {
+        Self::unwrap_not_frozen(Self::try_new_native_with_env(store, env, func))
+    }
+
+    /// Like [`Function::new_native_with_env`], but returns an error
+    /// instead of panicking if the store has been frozen.
+    pub fn try_new_native_with_env<F, Args, Rets, Env>(
+        store: &Store,
+        env: Env,
+        func: F,
+    ) -> Result<Self, StoreFrozen>
     where
         F: HostFunction<Args, Rets, WithEnv, Env>,
         Args: WasmTypeList,
@@ -331,6 +729,7 @@ impl Function {
         if std::mem::size_of::<F>() != 0 {
             Self::closures_unsupported_panic();
         }
+        store.check_not_frozen()?;
         let function = inner::Function::<Args, Rets>::new(func);
         let address = function.address();
 
@@ -353,6 +752,8 @@ impl Function {
                     instance_ref: None,
                 },
             },
+            label: FunctionLabel::default(),
+            relabel: None,
         }
     }
 
@@ -382,11 +783,69 @@ impl Function {
         &self.store
     }
 
+    /// Attaches a label to the `Function`, such as the name it is
+    /// imported with, to identify it in the errors about it.
+    ///
+    /// The label is mentioned when the function is called with
+    /// parameters of the wrong types, or converted to a [`NativeFunc`]
+    /// of the wrong type. For a dynamic host function, it is also
+    /// mentioned when the host function returns values of the wrong
+    /// types, which helps finding the faulty function among the many
+    /// imports with the same signature. The errors returned by the host
+    /// function itself are left as they are.
+    ///
+    /// The returned `Function` has its own label: the clones of the
+    /// original `Function` keep theirs, including in the instances that
+    /// imported them. The functions exported by an instance have no
+    /// label, even if they are imported host functions.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Function, FunctionType, Store, Type};
+    /// # let store = Store::default();
+    /// #
+    /// let signature = FunctionType::new(vec![Type::I32, Type::I32], vec![]);
+    /// let print = Function::new(&store, &signature, |_args| Ok(vec![])).with_label("env::print");
+    ///
+    /// assert_eq!(print.label().as_deref(), Some("env::print"));
+    /// ```
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = FunctionLabel(Some(label.into().into()));
+        if let Some(Relabel(relabel)) = self.relabel {
+            self.exported = relabel(&self.exported, &self.label);
+        }
+        self
+    }
+
+    /// Returns the label of the `Function`, if any.
+    ///
+    /// See [`Function::with_label`].
+    pub fn label(&self) -> Option<String> {
+        self.label.get()
+    }
+
     fn call_wasm(
         &self,
         trampoline: VMTrampoline,
         params: &[Val],
         results: &mut [Val],
+    ) -> Result<(), RuntimeError> {
+        let mut values_vec = vec![0; max(params.len(), results.len())];
+        self.call_wasm_with_buffer(trampoline, params, results, &mut values_vec)
+    }
+
+    /// Calls the function through `trampoline`, using `values_vec` to pass
+    /// the arguments in and the results out.
+    ///
+    /// `values_vec` must have room for at least as many values as the
+    /// largest of `params` and `results`.
+    fn call_wasm_with_buffer(
+        &self,
+        trampoline: VMTrampoline,
+        params: &[Val],
+        results: &mut [Val],
+        values_vec: &mut [i128],
     ) -> Result<(), RuntimeError> {
         let format_types_for_error_message = |items: &[Val]| {
             items
@@ -398,29 +857,32 @@ impl Function {
         let signature = self.ty();
         if signature.params().len() != params.len() {
             return Err(RuntimeError::new(format!(
-                "Parameters of type [{}] did not match signature {}",
+                "Parameters of type [{}] did not match signature {}{}",
                 format_types_for_error_message(params),
-                &signature
+                &signature,
+                self.label.describe(" of"),
             )));
         }
         if signature.results().len() != results.len() {
             return Err(RuntimeError::new(format!(
-                "Results of type [{}] did not match signature {}",
+                "Results of type [{}] did not match signature {}{}",
                 format_types_for_error_message(results),
                 &signature,
+                self.label.describe(" of"),
             )));
         }
-
-        let mut values_vec = vec![0; max(params.len(), results.len())];
+        debug_assert!(values_vec.len() >= max(params.len(), results.len()));
 
         // Store the argument values into `values_vec`.
         let param_tys = signature.params().iter();
-        for ((arg, slot), ty) in params.iter().zip(&mut values_vec).zip(param_tys) {
+        for ((arg, slot), ty) in params.iter().zip(values_vec.iter_mut()).zip(param_tys) {
             if arg.ty() != *ty {
                 let param_types = format_types_for_error_message(params);
                 return Err(RuntimeError::new(format!(
-                    "Parameters of type [{}] did not match signature {}",
-                    param_types, &signature,
+                    "Parameters of type [{}] did not match signature {}{}",
+                    param_types,
+                    &signature,
+                    self.label.describe(" of"),
                 )));
             }
             unsafe {
@@ -531,15 +993,211 @@ impl Function {
         unimplemented!("The function definition isn't supported for the moment");
     }
 
+    /// Call the `Function` function like [`Function::call`], converting
+    /// the parameters to the types of the signature where this loses
+    /// nothing.
+    ///
+    /// This is meant for embeddings where values come from a source
+    /// that isn't aware of the signature, like a REPL. `extension` tells
+    /// whether an `i32` or `i64` value holds a signed or an unsigned
+    /// integer, when it matters. The coercions are, by parameter type:
+    ///
+    /// | Value | `i32` | `i64` | `f32` | `f64` |
+    /// |-------|-------|-------|-------|-------|
+    /// | `i32` | kept | extended | if exact | converted |
+    /// | `i64` | if the integer fits | kept | if exact | if exact |
+    /// | `f32` | rejected | rejected | kept | converted |
+    /// | `f64` | rejected | rejected | if exact | kept |
+    ///
+    /// "If exact" conversions are rejected when the value can't be
+    /// represented exactly in the parameter type. Floats are never
+    /// coerced to integers, even when they have no fractional part.
+    /// Values of any other type must have the type of their parameter.
+    ///
+    /// The results are returned as the function produces them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmer::{imports, wat2wasm, Function, Instance, IntegerExtension, Module, Store, Value};
+    /// # let store = Store::default();
+    /// # let wasm_bytes = wat2wasm(r#"
+    /// # (module
+    /// #   (func (export "add") (param i64 f64) (result f64)
+    /// #     (f64.add (f64.convert_i64_s (local.get 0)) (local.get 1))))
+    /// # "#.as_bytes()).unwrap();
+    /// # let module = Module::new(&store, wasm_bytes).unwrap();
+    /// # let instance = Instance::new(&module, &imports! {}).unwrap();
+    /// #
+    /// let add = instance.exports.get_function("add").unwrap();
+    /// let params = [Value::I32(-1), Value::I32(2)];
+    ///
+    /// assert!(add.call(&params).is_err());
+    /// assert_eq!(
+    ///     add.call_coerced(&params, IntegerExtension::Signed).unwrap().to_vec(),
+    ///     vec![Value::F64(1.0)],
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the parameter if a value can't be
+    /// coerced, and the errors of [`Function::call`] otherwise.
+    pub fn call_coerced(
+        &self,
+        params: &[Val],
+        extension: IntegerExtension,
+    ) -> Result<Box<[Val]>, RuntimeError> {
+        let ty = self.ty();
+        if ty.params().len() != params.len() {
+            // Let `call` report the mismatch.
+            return self.call(params);
+        }
+        let params = params
+            .iter()
+            .zip(ty.params())
+            .enumerate()
+            .map(|(index, (value, ty))| {
+                coerce(value, *ty, extension).ok_or_else(|| {
+                    RuntimeError::new(format!(
+                        "cannot coerce parameter {} from {:?} to {}",
+                        index, value, ty
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        self.call(&params)
+    }
+
+    /// Call the `Function` function, measuring how long the call took.
+    ///
+    /// This behaves like [`Function::call`], but also returns
+    /// [`CallMetrics`] separating the total wall time of the call from
+    /// the time spent inside host imports, which helps telling guest
+    /// compute apart from host IO when profiling.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use wasmer::{imports, wat2wasm, Function, Instance, Module, Store, Type, Value};
+    /// # let store = Store::default();
+    /// # let wasm_bytes = wat2wasm(r#"
+    /// # (module
+    /// #   (func (export "sum") (param $x i32) (param $y i32) (result i32)
+    /// #     local.get $x
+    /// #     local.get $y
+    /// #     i32.add
+    /// #   ))
+    /// # "#.as_bytes()).unwrap();
+    /// # let module = Module::new(&store, wasm_bytes).unwrap();
+    /// # let import_object = imports! {};
+    /// # let instance = Instance::new(&module, &import_object).unwrap();
+    /// #
+    /// let sum = instance.exports.get_function("sum").unwrap();
+    /// let (results, metrics) = sum.call_with_metrics(&[Value::I32(1), Value::I32(2)]).unwrap();
+    ///
+    /// assert_eq!(results.to_vec(), vec![Value::I32(3)]);
+    /// assert!(metrics.host_import_time <= metrics.wall_time);
+    /// ```
+    pub fn call_with_metrics(
+        &self,
+        params: &[Val],
+    ) -> Result<(Box<[Val]>, CallMetrics), RuntimeError> {
+        let (results, metrics) = measure(|| self.call(params));
+        Ok((results?, metrics))
+    }
+
+    /// Prepares this `Function` to be called repeatedly.
+    ///
+    /// [`Function::call`] allocates a buffer to pass the arguments and
+    /// the results on every call. The returned [`PreparedCall`] owns a
+    /// buffer sized for this function's signature instead, and writes the
+    /// results into a slice provided by the caller, so calling it in a
+    /// loop doesn't allocate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{imports, wat2wasm, Function, Instance, Module, Store, Type, Value};
+    /// # let store = Store::default();
+    /// # let wasm_bytes = wat2wasm(r#"
+    /// # (module
+    /// #   (func (export "sum") (param $x i32) (param $y i32) (result i32)
+    /// #     local.get $x
+    /// #     local.get $y
+    /// #     i32.add
+    /// #   ))
+    /// # "#.as_bytes()).unwrap();
+    /// # let module = Module::new(&store, wasm_bytes).unwrap();
+    /// # let import_object = imports! {};
+    /// # let instance = Instance::new(&module, &import_object).unwrap();
+    /// #
+    /// let sum = instance.exports.get_function("sum").unwrap();
+    /// let mut prepared = sum.prepare_call().unwrap();
+    /// let mut results = [Value::I32(0)];
+    ///
+    /// for i in 0..10 {
+    ///     prepared.call(&[Value::I32(i), Value::I32(1)], &mut results).unwrap();
+    ///     assert_eq!(results[0], Value::I32(i + 1));
+    /// }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the function can't be called from the host,
+    /// either because it has no call trampoline or because the instance
+    /// it belongs to was dropped.
+    pub fn prepare_call(&self) -> Result<PreparedCall, RuntimeError> {
+        let trampoline = self.exported.vm_function.call_trampoline.ok_or_else(|| {
+            RuntimeError::new("the function definition can't be called from the host")
+        })?;
+        let function = self
+            .try_clone()
+            .ok_or_else(|| RuntimeError::new("the instance of the function was dropped"))?;
+        Ok(PreparedCall {
+            values_vec: vec![0; max(self.param_arity(), self.result_arity())],
+            function,
+            trampoline,
+        })
+    }
+
     pub(crate) fn from_vm_export(store: &Store, wasmer_export: ExportFunction) -> Self {
+        Self::from_export(store.clone(), wasmer_export, FunctionLabel::default(), None)
+    }
+
+    /// Builds a `Function` from its export and its label, for example
+    /// when converting a [`NativeFunc`] back into a `Function`.
+    pub(crate) fn from_export(
+        store: Store,
+        exported: ExportFunction,
+        label: FunctionLabel,
+        relabel: Option<Relabel>,
+    ) -> Self {
         Self {
-            store: store.clone(),
-            exported: wasmer_export,
+            store,
+            exported,
+            label,
+            relabel,
         }
     }
 
+    /// Clones the function, unless it only keeps a weak reference to
+    /// its instance and that instance was dropped.
+    pub(crate) fn try_clone(&self) -> Option<Self> {
+        let mut exported = self.exported.clone();
+        exported.vm_function.upgrade_instance_ref()?;
+        Some(Self {
+            store: self.store.clone(),
+            exported,
+            label: self.label.clone(),
+            relabel: self.relabel,
+        })
+    }
+
     pub(crate) fn vm_funcref(&self) -> VMFuncRef {
         let engine = self.store.engine();
+        // The func ref lives as long as the engine, so its signature
+        // must stay registered as long, too.
         let vmsignature = engine.register_signature(&self.exported.vm_function.signature);
         engine.register_function_metadata(VMCallerCheckedAnyfunc {
             func_ptr: self.exported.vm_function.address,
@@ -551,6 +1209,12 @@ impl Function {
     /// Transform this WebAssembly function into a function with the
     /// native ABI. See [`NativeFunc`] to learn more.
     ///
+    /// This works for host functions too, whether they are created
+    /// with [`Function::new_native`] or [`Function::new`]: calling the
+    /// returned `NativeFunc` calls the host function directly, and
+    /// checks the values returned by a dynamic host function against
+    /// its signature.
+    ///
     /// # Examples
     ///
     /// ```
@@ -635,8 +1299,9 @@ impl Function {
 
             if expected != given {
                 return Err(RuntimeError::new(format!(
-                    "given types (`{:?}`) for the function arguments don't match the actual types (`{:?}`)",
+                    "given types (`{:?}`) for the function{} arguments don't match the actual types (`{:?}`)",
                     given,
+                    self.label.describe(""),
                     expected,
                 )));
             }
@@ -649,14 +1314,20 @@ impl Function {
             if expected != given {
                 // todo: error result types don't match
                 return Err(RuntimeError::new(format!(
-                    "given types (`{:?}`) for the function results don't match the actual types (`{:?}`)",
+                    "given types (`{:?}`) for the function{} results don't match the actual types (`{:?}`)",
                     given,
+                    self.label.describe(""),
                     expected,
                 )));
             }
         }
 
-        Ok(NativeFunc::new(self.store.clone(), self.exported.clone()))
+        Ok(NativeFunc::new(
+            self.store.clone(),
+            self.exported.clone(),
+            self.label.clone(),
+            self.relabel,
+        ))
     }
 
     #[track_caller]
@@ -664,6 +1335,12 @@ impl Function {
         unimplemented!("Closures (functions with captured environments) are currently unsupported with native functions. See: https://github.com/wasmerio/wasmer/issues/1840")
     }
 
+    /// Unwraps the result of a constructor, panicking if the store has
+    /// been frozen, see [`Store::freeze`].
+    fn unwrap_not_frozen(result: Result<Self, StoreFrozen>) -> Self {
+        result.unwrap_or_else(|e| panic!("{}", e))
+    }
+
     /// Get access to the backing VM value for this extern. This function is for
     /// tests it should not be called by users of the Wasmer API.
     ///
@@ -700,13 +1377,7 @@ impl<'a> Exportable<'a> for Function {
 
 impl Clone for Function {
     fn clone(&self) -> Self {
-        let mut exported = self.exported.clone();
-        exported.vm_function.upgrade_instance_ref().unwrap();
-
-        Self {
-            store: self.store.clone(),
-            exported,
-        }
+        self.try_clone().unwrap()
     }
 }
 
@@ -715,15 +1386,116 @@ impl fmt::Debug for Function {
         formatter
             .debug_struct("Function")
             .field("ty", &self.ty())
+            .field("label", &self.label())
+            .finish()
+    }
+}
+
+/// How [`Function::call_coerced`] interprets the bits of integer
+/// values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IntegerExtension {
+    /// Integers are signed: an `i32` is sign-extended to an `i64`.
+    Signed,
+    /// Integers are unsigned: an `i32` is zero-extended to an `i64`.
+    Unsigned,
+}
+
+/// Converts `value` to `ty` if it loses nothing, see
+/// [`Function::call_coerced`].
+fn coerce(value: &Val, ty: ValType, extension: IntegerExtension) -> Option<Val> {
+    if value.ty() == ty {
+        return Some(value.clone());
+    }
+    let integer = match (value, extension) {
+        (Val::I32(x), IntegerExtension::Signed) => Some(i128::from(*x)),
+        (Val::I32(x), IntegerExtension::Unsigned) => Some(i128::from(*x as u32)),
+        (Val::I64(x), IntegerExtension::Signed) => Some(i128::from(*x)),
+        (Val::I64(x), IntegerExtension::Unsigned) => Some(i128::from(*x as u64)),
+        _ => None,
+    };
+    match (integer, value, ty) {
+        (Some(x), _, ValType::I32) => match extension {
+            IntegerExtension::Signed => i32::try_from(x).ok().map(Val::I32),
+            IntegerExtension::Unsigned => u32::try_from(x).ok().map(|x| Val::I32(x as i32)),
+        },
+        (Some(x), _, ValType::I64) => match extension {
+            IntegerExtension::Signed => i64::try_from(x).ok().map(Val::I64),
+            IntegerExtension::Unsigned => u64::try_from(x).ok().map(|x| Val::I64(x as i64)),
+        },
+        (Some(x), _, ValType::F32) if x as f32 as i128 == x => Some(Val::F32(x as f32)),
+        (Some(x), _, ValType::F64) if x as f64 as i128 == x => Some(Val::F64(x as f64)),
+        (None, Val::F32(x), ValType::F64) => Some(Val::F64(f64::from(*x))),
+        (None, Val::F64(x), ValType::F32) if f64::from(*x as f32).to_bits() == x.to_bits() => {
+            Some(Val::F32(*x as f32))
+        }
+        _ => None,
+    }
+}
+
+/// A [`Function`] prepared to be called repeatedly without allocating.
+///
+/// Created with [`Function::prepare_call`].
+pub struct PreparedCall {
+    function: Function,
+    trampoline: VMTrampoline,
+    values_vec: Vec<i128>,
+}
+
+impl PreparedCall {
+    /// Returns the [`Function`] this call was prepared for.
+    pub fn function(&self) -> &Function {
+        &self.function
+    }
+
+    /// Calls the function with `params`, writing its results into `results`.
+    ///
+    /// This behaves like [`Function::call`], except that `results` must
+    /// have exactly [`Function::result_arity`] elements.
+    pub fn call(&mut self, params: &[Val], results: &mut [Val]) -> Result<(), RuntimeError> {
+        self.function
+            .call_wasm_with_buffer(self.trampoline, params, results, &mut self.values_vec)
+    }
+}
+
+impl fmt::Debug for PreparedCall {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("PreparedCall")
+            .field("function", &self.function)
             .finish()
     }
 }
 
+/// The instance calling a host function created with
+/// [`Function::new_with_caller`].
+///
+/// A `Caller` only lives for the duration of the call it is given to.
+pub struct Caller<'a> {
+    memories: &'a [Memory],
+}
+
+impl<'a> Caller<'a> {
+    /// Returns the memory of the calling instance with the given index,
+    /// exported or not, `0` being its default memory.
+    ///
+    /// Returns `None` if the instance has no such memory, or if the
+    /// function isn't called by an instance, for example when it is
+    /// called directly from the host.
+    pub fn memory(&self, index: u32) -> Option<&'a Memory> {
+        self.memories.get(index as usize)
+    }
+}
+
 /// This trait is one that all dynamic functions must fulfill.
 pub(crate) trait VMDynamicFunction: Send + Sync {
-    fn call(&self, args: &[Val]) -> Result<Vec<Val>, RuntimeError>;
+    fn call(&self, caller: &Caller, args: &[Val]) -> Result<Vec<Val>, RuntimeError>;
+    fn caller(&self) -> Caller<'_>;
     fn function_type(&self) -> &FunctionType;
     fn store(&self) -> &Store;
+    /// Whether the results are checked against the function type.
+    fn checks_results(&self) -> bool;
+    fn label(&self) -> &FunctionLabel;
 }
 
 pub(crate) struct DynamicFunction<Env>
@@ -732,9 +1504,18 @@ where
 {
     function_type: FunctionType,
     #[allow(clippy::type_complexity)]
-    func: Arc<dyn Fn(&Env, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync>,
+    func: Arc<
+        dyn Fn(&Env, &Caller, &[Val]) -> Result<Vec<Val>, RuntimeError> + 'static + Send + Sync,
+    >,
     store: Store,
     env: Box<Env>,
+    /// The memories of the instance importing the function, set when
+    /// the environment of the import is initialized.
+    caller_memories: Vec<Memory>,
+    /// Whether the results are checked against `function_type`, see
+    /// [`Function::new_dynamic_unchecked`].
+    checks_results: bool,
+    label: FunctionLabel,
 }
 
 impl<Env: Sized + Clone + 'static + Send + Sync> Clone for DynamicFunction<Env> {
@@ -744,6 +1525,9 @@ impl<Env: Sized + Clone + 'static + Send + Sync> Clone for DynamicFunction<Env>
             function_type: self.function_type.clone(),
             store: self.store.clone(),
             func: self.func.clone(),
+            caller_memories: self.caller_memories.clone(),
+            checks_results: self.checks_results,
+            label: self.label.clone(),
         }
     }
 }
@@ -752,8 +1536,13 @@ impl<Env> VMDynamicFunction for DynamicFunction<Env>
 where
     Env: Sized + 'static + Send + Sync,
 {
-    fn call(&self, args: &[Val]) -> Result<Vec<Val>, RuntimeError> {
-        (*self.func)(&*self.env, &args)
+    fn call(&self, caller: &Caller, args: &[Val]) -> Result<Vec<Val>, RuntimeError> {
+        (*self.func)(&*self.env, caller, &args)
+    }
+    fn caller(&self) -> Caller<'_> {
+        Caller {
+            memories: &self.caller_memories,
+        }
     }
     fn function_type(&self) -> &FunctionType {
         &self.function_type
@@ -761,6 +1550,12 @@ where
     fn store(&self) -> &Store {
         &self.store
     }
+    fn checks_results(&self) -> bool {
+        self.checks_results
+    }
+    fn label(&self) -> &FunctionLabel {
+        &self.label
+    }
 }
 
 trait VMDynamicFunctionCall<T: VMDynamicFunction> {
@@ -791,31 +1586,36 @@ impl<T: VMDynamicFunction> VMDynamicFunctionCall<T> for VMDynamicFunctionContext
     ) {
         use std::panic::{self, AssertUnwindSafe};
         let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            let _guard = HostCallGuard::enter();
             let func_ty = self.ctx.function_type();
             let mut args = Vec::with_capacity(func_ty.params().len());
             let store = self.ctx.store();
             for (i, ty) in func_ty.params().iter().enumerate() {
                 args.push(Val::read_value_from(store, values_vec.add(i), *ty));
             }
-            let returns = self.ctx.call(&args)?;
+            let returns = self.ctx.call(&self.ctx.caller(), &args)?;
 
             // We need to dynamically check that the returns
-            // match the expected types, as well as expected length.
-            let return_types = returns.iter().map(|ret| ret.ty()).collect::<Vec<_>>();
-            if return_types != func_ty.results() {
-                return Err(RuntimeError::new(format!(
-                    "Dynamic function returned wrong signature. Expected {:?} but got {:?}",
-                    func_ty.results(),
-                    return_types
-                )));
+            // match the expected types, as well as expected length,
+            // unless the function was created unchecked.
+            if self.ctx.checks_results() {
+                let return_types = returns.iter().map(|ret| ret.ty()).collect::<Vec<_>>();
+                if return_types != func_ty.results() {
+                    return Err(RuntimeError::new(format!(
+                        "Dynamic function{} returned wrong signature. Expected {:?} but got {:?}",
+                        self.ctx.label().describe(""),
+                        func_ty.results(),
+                        return_types
+                    )));
+                }
             }
             for (i, ret) in returns.iter().enumerate() {
                 ret.write_value_to(values_vec.add(i));
             }
+            // The arguments own the references passed by the caller, which
+            // are released here, while the caller owns the written results.
             Ok(())
-        })); // We get extern ref drops at the end of this block that we don't need.
-             // By preventing extern ref incs in the code above we can save the work of
-             // incrementing and decrementing. However the logic as-is is correct.
+        }));
 
         match result {
             Ok(Ok(())) => {}
@@ -828,6 +1628,7 @@ impl<T: VMDynamicFunction> VMDynamicFunctionCall<T> for VMDynamicFunctionContext
 /// This private inner module contains the low-level implementation
 /// for `Function` and its siblings.
 mod inner {
+    use crate::metrics::HostCallGuard;
     use std::array::TryFromSliceError;
     use std::convert::{Infallible, TryInto};
     use std::error::Error;
@@ -1112,6 +1913,20 @@ mod inner {
     {
         /// Get the pointer to the function body.
         fn function_body_ptr(self) -> *const VMFunctionBody;
+
+        /// Get the pointer to a function body that doesn't catch
+        /// panics raised by the host function.
+        ///
+        /// By default, this is [`HostFunction::function_body_ptr`],
+        /// which does catch them.
+        ///
+        /// # Safety
+        ///
+        /// Panicking inside the returned function is undefined
+        /// behavior: the panic would unwind through Wasm frames.
+        unsafe fn infallible_function_body_ptr(self) -> *const VMFunctionBody {
+            self.function_body_ptr()
+        }
     }
 
     /// Empty trait to specify the kind of `HostFunction`: With or
@@ -1165,6 +1980,23 @@ mod inner {
             }
         }
 
+        /// Creates a new `Function` whose wrapper doesn't catch panics.
+        ///
+        /// # Safety
+        ///
+        /// See [`HostFunction::infallible_function_body_ptr`].
+        pub unsafe fn new_infallible<F, T, E>(function: F) -> Self
+        where
+            F: HostFunction<Args, Rets, T, E>,
+            T: HostFunctionKind,
+            E: Sized,
+        {
+            Self {
+                address: function.infallible_function_body_ptr(),
+                _phantom: PhantomData,
+            }
+        }
+
         /// Get the function type of this `Function`.
         pub fn ty(&self) -> FunctionType {
             FunctionType::new(Args::wasm_types(), Rets::wasm_types())
@@ -1297,6 +2129,7 @@ mod inner {
                     {
                         let func: &Func = unsafe { &*(&() as *const () as *const Func) };
                         let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                            let _guard = HostCallGuard::enter();
                             func( $( FromToNativeWasmType::from_native($x) ),* ).into_result()
                         }));
 
@@ -1309,6 +2142,32 @@ mod inner {
 
                     func_wrapper::< $( $x, )* Rets, RetsAsResult, Self > as *const VMFunctionBody
                 }
+
+                #[allow(non_snake_case)]
+                unsafe fn infallible_function_body_ptr(self) -> *const VMFunctionBody {
+                    /// Same as the wrapper above, minus the
+                    /// `catch_unwind` around the host function.
+                    extern fn func_wrapper<$( $x, )* Rets, RetsAsResult, Func>( _: usize, $( $x: $x::Native, )* ) -> Rets::CStruct
+                    where
+                        $( $x: FromToNativeWasmType, )*
+                        Rets: WasmTypeList,
+                        RetsAsResult: IntoResult<Rets>,
+                        Func: Fn( $( $x ),* ) -> RetsAsResult + 'static
+                    {
+                        let func: &Func = unsafe { &*(&() as *const () as *const Func) };
+                        let result = {
+                            let _guard = HostCallGuard::enter();
+                            func( $( FromToNativeWasmType::from_native($x) ),* ).into_result()
+                        };
+
+                        match result {
+                            Ok(result) => result.into_c_struct(),
+                            Err(trap) => unsafe { raise_user_trap(Box::new(trap)) },
+                        }
+                    }
+
+                    func_wrapper::< $( $x, )* Rets, RetsAsResult, Self > as *const VMFunctionBody
+                }
             }
 
             // Implement `HostFunction` for a function that has the same arity than the tuple.
@@ -1341,6 +2200,7 @@ mod inner {
                         let func: &Func = unsafe { &*(&() as *const () as *const Func) };
 
                         let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                            let _guard = HostCallGuard::enter();
                             func(env, $( FromToNativeWasmType::from_native($x) ),* ).into_result()
                         }));
 
@@ -1353,6 +2213,33 @@ mod inner {
 
                     func_wrapper::< $( $x, )* Rets, RetsAsResult, Env, Self > as *const VMFunctionBody
                 }
+
+                #[allow(non_snake_case)]
+                unsafe fn infallible_function_body_ptr(self) -> *const VMFunctionBody {
+                    /// Same as the wrapper above, minus the
+                    /// `catch_unwind` around the host function.
+                    extern fn func_wrapper<$( $x, )* Rets, RetsAsResult, Env, Func>( env: &Env, $( $x: $x::Native, )* ) -> Rets::CStruct
+                    where
+                        $( $x: FromToNativeWasmType, )*
+                        Rets: WasmTypeList,
+                        RetsAsResult: IntoResult<Rets>,
+                        Env: Sized,
+                        Func: Fn(&Env, $( $x ),* ) -> RetsAsResult + 'static
+                    {
+                        let func: &Func = unsafe { &*(&() as *const () as *const Func) };
+                        let result = {
+                            let _guard = HostCallGuard::enter();
+                            func(env, $( FromToNativeWasmType::from_native($x) ),* ).into_result()
+                        };
+
+                        match result {
+                            Ok(result) => result.into_c_struct(),
+                            Err(trap) => unsafe { raise_user_trap(Box::new(trap)) },
+                        }
+                    }
+
+                    func_wrapper::< $( $x, )* Rets, RetsAsResult, Env, Self > as *const VMFunctionBody
+                }
             }
         };
     }
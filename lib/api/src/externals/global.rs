@@ -6,6 +6,7 @@ use crate::GlobalType;
 use crate::Mutability;
 use crate::RuntimeError;
 use loupe::MemoryUsage;
+use std::any::Any;
 use std::fmt;
 use std::sync::Arc;
 use wasmer_engine::Export;
@@ -26,6 +27,12 @@ pub struct Global {
 impl Global {
     /// Create a new `Global` with the initial value [`Val`].
     ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`], or
+    /// if `val` is a reference from another store. [`Global::try_new`]
+    /// returns an error instead.
+    ///
     /// # Example
     ///
     /// ```
@@ -41,8 +48,24 @@ impl Global {
         Self::from_value(store, val, Mutability::Const).unwrap()
     }
 
+    /// Like [`Global::new`], but returns an error instead of panicking.
+    ///
+    /// If the store has been frozen, the error wraps [`StoreFrozen`],
+    /// which [`RuntimeError::downcast`] recovers.
+    ///
+    /// [`StoreFrozen`]: crate::StoreFrozen
+    pub fn try_new(store: &Store, val: Val) -> Result<Self, RuntimeError> {
+        Self::from_value(store, val, Mutability::Const)
+    }
+
     /// Create a mutable `Global` with the initial value [`Val`].
     ///
+    /// # Panics
+    ///
+    /// Panics if the store has been frozen, see [`Store::freeze`], or
+    /// if `val` is a reference from another store.
+    /// [`Global::try_new_mut`] returns an error instead.
+    ///
     /// # Example
     ///
     /// ```
@@ -58,8 +81,86 @@ impl Global {
         Self::from_value(store, val, Mutability::Var).unwrap()
     }
 
+    /// Like [`Global::new_mut`], but returns an error instead of
+    /// panicking, like [`Global::try_new`].
+    pub fn try_new_mut(store: &Store, val: Val) -> Result<Self, RuntimeError> {
+        Self::from_value(store, val, Mutability::Var)
+    }
+
+    /// Create a `Global` of type `ty` with the initial value `init`,
+    /// whose values set by the host are first checked by `validator`.
+    ///
+    /// Every call to [`Global::set`], through any handle to the global,
+    /// calls `validator` with the current and the new value. If it
+    /// returns an error, the global keeps its current value and `set`
+    /// returns a [`RuntimeError`] with the message of the validator. The
+    /// initial value isn't checked.
+    ///
+    /// Note that writes done by WebAssembly code with `global.set` go
+    /// directly to the global's storage and are **not** checked: this
+    /// only guards the configuration set by the host.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Global, GlobalType, Mutability, Store, Type, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let log_level = Global::with_validator(
+    ///     &store,
+    ///     GlobalType::new(Type::I32, Mutability::Var),
+    ///     Value::I32(2),
+    ///     |_old, new| match new.unwrap_i32() {
+    ///         0..=5 => Ok(()),
+    ///         level => Err(format!("invalid log level {}", level)),
+    ///     },
+    /// )
+    /// .unwrap();
+    ///
+    /// log_level.set(Value::I32(4)).unwrap();
+    /// assert!(log_level.set(Value::I32(9)).is_err());
+    /// assert_eq!(log_level.get(), Value::I32(4));
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `init` isn't of the type of the global.
+    pub fn with_validator<F>(
+        store: &Store,
+        ty: GlobalType,
+        init: Val,
+        validator: F,
+    ) -> Result<Self, RuntimeError>
+    where
+        F: Fn(&Val, &Val) -> Result<(), String> + Send + Sync + 'static,
+    {
+        if init.ty() != ty.ty {
+            return Err(RuntimeError::new(format!(
+                "the initial value {:?} is not of type {}",
+                init, ty.ty
+            )));
+        }
+        let global = Self::from_value(store, init, ty.mutability)?;
+        let store = store.clone();
+        global.vm_global.from.set_validator(Arc::new(
+            move |global: &RuntimeGlobal, new: &dyn Any| {
+                let new = new
+                    .downcast_ref::<Val>()
+                    .expect("globals are set with `Val`s");
+                // Safety: the validator is called with the lock of the
+                // global held.
+                let old = unsafe { global.get_unchecked(&store) };
+                validator(&old, new)
+            },
+        ));
+        Ok(global)
+    }
+
     /// Create a `Global` with the initial value [`Val`] and the provided [`Mutability`].
     fn from_value(store: &Store, val: Val, mutability: Mutability) -> Result<Self, RuntimeError> {
+        store
+            .check_not_frozen()
+            .map_err(|e| RuntimeError::user(Box::new(e)))?;
         if !val.comes_from_same_store(store) {
             return Err(RuntimeError::new("cross-`Store` globals are not supported"));
         }
@@ -186,6 +287,64 @@ impl Global {
         Ok(())
     }
 
+    /// Checks that [`Global::set`] would accept `val`, without setting
+    /// it.
+    pub(crate) fn validate(&self, val: &Val) -> Result<(), RuntimeError> {
+        if !val.comes_from_same_store(&self.store) {
+            return Err(RuntimeError::new("cross-`Store` values are not supported"));
+        }
+        self.vm_global
+            .from
+            .validate(val)
+            .map_err(|e| RuntimeError::new(format!("{}", e)))
+    }
+
+    /// Registers a callback that is called with the new value every time
+    /// the host successfully sets this global with [`Global::set`].
+    ///
+    /// The callback gets the value passed to that `set`, even if another
+    /// thread has set the global again by the time it runs.
+    ///
+    /// The callback is attached to the global itself, so it also fires
+    /// when the value is set through another handle to the same global
+    /// (for example one obtained again from an instance's exports).
+    /// Failed sets, such as on an immutable global or with a value of the
+    /// wrong type, don't call it.
+    ///
+    /// Note that writes done by WebAssembly code with `global.set` go
+    /// directly to the global's storage and are **not** observed: this is
+    /// only a way to get notified of host-side changes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::sync::{Arc, Mutex};
+    /// # use wasmer::{Global, Store, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let g = Global::new_mut(&store, Value::I32(1));
+    /// let seen = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let seen_in_callback = seen.clone();
+    /// g.on_host_set(move |value| seen_in_callback.lock().unwrap().push(value.unwrap_i32()));
+    /// g.set(Value::I32(2)).unwrap();
+    ///
+    /// assert_eq!(*seen.lock().unwrap(), vec![2]);
+    /// ```
+    pub fn on_host_set<F>(&self, callback: F)
+    where
+        F: Fn(&Val) + Send + Sync + 'static,
+    {
+        self.vm_global
+            .from
+            .on_host_set(Arc::new(move |value: &dyn Any| {
+                let value = value
+                    .downcast_ref::<Val>()
+                    .expect("globals are set with `Val`s");
+                callback(value);
+            }));
+    }
+
     pub(crate) fn from_vm_export(store: &Store, vm_global: VMGlobal) -> Self {
         Self {
             store: store.clone(),
@@ -193,6 +352,41 @@ impl Global {
         }
     }
 
+    /// Creates a new `Global` owned by `store`, with the same type and
+    /// mutability as this one, and initialized with its current value.
+    ///
+    /// The new global is independent: setting one doesn't change the
+    /// other.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Global, Mutability, Store, Value};
+    /// # let store = Store::default();
+    /// # let other_store = Store::default();
+    /// #
+    /// let g = Global::new_mut(&store, Value::I32(1));
+    /// let cloned = g.clone_into(&other_store).unwrap();
+    ///
+    /// assert_eq!(cloned.get(), Value::I32(1));
+    /// assert_eq!(cloned.store(), &other_store);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the global holds a reference, which can't be
+    /// used outside of the store it points into.
+    pub fn clone_into(&self, store: &Store) -> Result<Self, RuntimeError> {
+        let ty = self.ty();
+        if !ty.ty.is_num() {
+            return Err(RuntimeError::new(format!(
+                "globals of type {} can't be cloned into another `Store`",
+                ty.ty
+            )));
+        }
+        Self::from_value(store, self.get(), ty.mutability)
+    }
+
     /// Returns whether or not these two globals refer to the same data.
     ///
     /// # Example
@@ -4,11 +4,45 @@ use crate::store::Store;
 use crate::{MemoryType, MemoryView};
 use loupe::MemoryUsage;
 use std::convert::TryInto;
+use std::fmt;
+use std::mem;
+use std::ops::Range;
+use std::ptr;
 use std::slice;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use thiserror::Error;
 use wasmer_engine::Export;
 use wasmer_types::{Pages, ValueType};
-use wasmer_vm::{MemoryError, VMMemory};
+use wasmer_vm::{
+    AtomicWaitResult, Memory as RuntimeMemory, MemoryAccess, MemoryAccessKind, MemoryError,
+    MemoryStyle, VMMemory,
+};
+
+/// An error while accessing the contents of a [`Memory`] from the host.
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryAccessError {
+    /// The access is outside of the bounds of the memory.
+    #[error("out of bounds memory access")]
+    HeapOutOfBounds,
+    /// The address calculation overflowed.
+    #[error("address calculation overflow")]
+    Overflow,
+    /// No NUL terminator was found within the maximum length of a
+    /// C string.
+    #[error("no NUL terminator found within the maximum string length")]
+    NulNotFound,
+    /// A C string read from the memory isn't valid UTF-8.
+    #[error("string is not valid UTF-8")]
+    NonUtf8String,
+    /// A string written as a C string contains a NUL byte.
+    #[error("string contains an interior NUL byte")]
+    InteriorNul,
+    /// An atomic or typed access isn't naturally aligned.
+    #[error("unaligned memory access")]
+    Unaligned,
+}
 
 /// A WebAssembly `memory` instance.
 ///
@@ -45,9 +79,91 @@ impl Memory {
     /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
     /// ```
     pub fn new(store: &Store, ty: MemoryType) -> Result<Self, MemoryError> {
-        let tunables = store.tunables();
-        let style = tunables.memory_style(&ty);
-        let memory = tunables.create_host_memory(&ty, &style)?;
+        let style = store.tunables().memory_style(&ty);
+        Self::new_with_style(store, ty, style)
+    }
+
+    /// Creates a new host `Memory` of `min` pages, which can grow up
+    /// to `max` pages if there is one.
+    ///
+    /// This is a shorthand for [`Memory::new`] with a [`MemoryType`]
+    /// built from the arguments. An error is returned if `max` is less
+    /// than `min`, or if the memory is `shared` but has no maximum,
+    /// since shared memories must be bounded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, Pages, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::with_pages(&store, 1, Some(4), false).unwrap();
+    ///
+    /// assert_eq!(m.size(), Pages(1));
+    /// assert_eq!(m.ty().maximum, Some(Pages(4)));
+    /// ```
+    pub fn with_pages(
+        store: &Store,
+        min: u32,
+        max: Option<u32>,
+        shared: bool,
+    ) -> Result<Self, MemoryError> {
+        if let Some(max) = max {
+            if max < min {
+                return Err(MemoryError::InvalidMemory {
+                    reason: format!(
+                        "the maximum ({} pages) is less than the minimum ({} pages)",
+                        max, min
+                    ),
+                });
+            }
+        } else if shared {
+            return Err(MemoryError::InvalidMemory {
+                reason: "a shared memory must have a maximum".to_string(),
+            });
+        }
+        Self::new(store, MemoryType::new(min, max, shared))
+    }
+
+    /// Creates a new host `Memory` from the provided [`MemoryType`],
+    /// using the given [`MemoryStyle`] instead of the one the store
+    /// tunables would pick.
+    ///
+    /// A [`MemoryStyle::Static`] memory reserves `bound` pages of
+    /// address space up front and never moves, while a
+    /// [`MemoryStyle::Dynamic`] memory only reserves what it uses and
+    /// may be moved when it grows. An error is returned if the static
+    /// bound is smaller than the minimum, or if the reservation doesn't
+    /// fit in the address space of the host.
+    ///
+    /// Modules are compiled for the memory style of the tunables: importing
+    /// the memory in a module expecting a static memory with a larger bound
+    /// or offset guard, or a static memory while this one is dynamic, fails
+    /// with [`ImportError::IncompatibleMemoryStyle`].
+    ///
+    /// [`ImportError::IncompatibleMemoryStyle`]: crate::ImportError::IncompatibleMemoryStyle
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store};
+    /// # use wasmer::vm::MemoryStyle;
+    /// # let store = Store::default();
+    /// #
+    /// let style = MemoryStyle::Dynamic { offset_guard_size: 0 };
+    /// let m = Memory::new_with_style(&store, MemoryType::new(1, None, false), style.clone()).unwrap();
+    ///
+    /// assert_eq!(m.style(), &style);
+    /// ```
+    pub fn new_with_style(
+        store: &Store,
+        ty: MemoryType,
+        style: MemoryStyle,
+    ) -> Result<Self, MemoryError> {
+        store
+            .check_not_frozen()
+            .map_err(|_| MemoryError::StoreFrozen)?;
+        let memory = store.tunables().create_host_memory(&ty, &style)?;
 
         Ok(Self {
             store: store.clone(),
@@ -77,6 +193,34 @@ impl Memory {
         self.vm_memory.from.ty()
     }
 
+    /// Returns whether the `Memory` can be shared between multiple
+    /// threads.
+    ///
+    /// This is reported by the allocation backing the memory, so it
+    /// also holds for memories created by custom [`Tunables`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, Some(1), true)).unwrap();
+    ///
+    /// assert!(m.is_shared());
+    /// ```
+    ///
+    /// [`Tunables`]: crate::Tunables
+    pub fn is_shared(&self) -> bool {
+        self.vm_memory.from.is_shared()
+    }
+
+    /// Returns the [`MemoryStyle`] of the allocation backing the
+    /// `Memory`.
+    pub fn style(&self) -> &MemoryStyle {
+        self.vm_memory.from.style()
+    }
+
     /// Returns the [`Store`] where the `Memory` belongs.
     ///
     /// # Example
@@ -120,6 +264,44 @@ impl Memory {
         slice::from_raw_parts_mut(def.base, def.current_length.try_into().unwrap())
     }
 
+    /// Calls `f` with a slice of the memory contents.
+    ///
+    /// Contrary to [`Memory::data_unchecked`], the slice can't outlive
+    /// the closure, which keeps the borrow as short as possible.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.view::<u8>()[0].set(42);
+    ///
+    /// let first = unsafe { m.with_data(|data| data[0]) };
+    /// assert_eq!(first, 42);
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The same rules as for [`Memory::data_unchecked`] apply for the
+    /// duration of the closure: `f` must not grow the memory or call a
+    /// wasm function that writes to it.
+    pub unsafe fn with_data<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
+        f(self.data_unchecked())
+    }
+
+    /// Calls `f` with a mutable slice of the memory contents.
+    ///
+    /// # Safety
+    ///
+    /// The same rules as for [`Memory::data_unchecked_mut`] apply for the
+    /// duration of the closure: `f` must not grow the memory or call a
+    /// wasm function that reads or writes it.
+    pub unsafe fn with_data_mut<R>(&self, f: impl FnOnce(&mut [u8]) -> R) -> R {
+        f(self.data_unchecked_mut())
+    }
+
     /// Returns the pointer to the raw bytes of the `Memory`.
     pub fn data_ptr(&self) -> *mut u8 {
         let definition = self.vm_memory.from.vmmemory();
@@ -134,6 +316,461 @@ impl Memory {
         def.current_length.into()
     }
 
+    /// Copies the bytes of the `Memory` in `range` into an owned buffer.
+    ///
+    /// Contrary to a [`MemoryView`], the snapshot doesn't borrow the
+    /// memory, so it can be kept across calls that grow or mutate the
+    /// memory, or sent to another thread.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.view::<u8>()[0].set(42);
+    ///
+    /// assert_eq!(m.snapshot(0..2).unwrap(), vec![42, 0]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::HeapOutOfBounds`] if `range` is not
+    /// fully contained in the memory.
+    pub fn snapshot(&self, range: Range<u64>) -> Result<Vec<u8>, MemoryAccessError> {
+        let range = self.checked_range(range)?;
+        self.log_access(MemoryAccessKind::Read, &range);
+        // Safety: the range has been checked to be in bounds, and the slice
+        // is only alive for the duration of the copy.
+        Ok(unsafe { self.data_unchecked() }[range].to_vec())
+    }
+
+    /// Faults in the pages of the `Memory` covering `range` for writing,
+    /// so that neither the first read nor the first write to them pays
+    /// for a page fault.
+    ///
+    /// This is purely a performance hint: the contents of the memory
+    /// are left unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value, WASM_PAGE_SIZE};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.grow(1).unwrap();
+    ///
+    /// m.prefault(WASM_PAGE_SIZE as u64..m.data_size()).unwrap();
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::HeapOutOfBounds`] if `range` is not
+    /// fully contained in the memory.
+    pub fn prefault(&self, range: Range<u64>) -> Result<(), MemoryAccessError> {
+        let range = self.checked_range(range)?;
+        // Safety: the range has been checked to be in bounds.
+        unsafe { wasmer_vm::prefault(self.data_ptr().add(range.start), range.len()) };
+        Ok(())
+    }
+
+    /// Reads the NUL-terminated string starting at `offset`, whose
+    /// length, not counting the terminator, is at most `max_len` bytes.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store, Type, Value};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.write_cstr(8, "hello").unwrap();
+    ///
+    /// assert_eq!(m.read_cstr(8, 256).unwrap(), "hello");
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::NulNotFound`] if there is no NUL
+    /// within `max_len` bytes, [`MemoryAccessError::HeapOutOfBounds`]
+    /// if the memory ends before the NUL, and
+    /// [`MemoryAccessError::NonUtf8String`] if the string isn't valid
+    /// UTF-8.
+    pub fn read_cstr(&self, offset: u64, max_len: usize) -> Result<String, MemoryAccessError> {
+        self.with_cstr(offset, max_len, |bytes| {
+            std::str::from_utf8(bytes)
+                .map(str::to_owned)
+                .map_err(|_| MemoryAccessError::NonUtf8String)
+        })?
+    }
+
+    /// Like [`Memory::read_cstr`], but invalid UTF-8 sequences are
+    /// replaced with `U+FFFD REPLACEMENT CHARACTER` instead of being
+    /// reported.
+    pub fn read_cstr_lossy(
+        &self,
+        offset: u64,
+        max_len: usize,
+    ) -> Result<String, MemoryAccessError> {
+        self.with_cstr(offset, max_len, |bytes| {
+            String::from_utf8_lossy(bytes).into_owned()
+        })
+    }
+
+    /// Writes `string` at `offset`, followed by a NUL terminator.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::InteriorNul`] if `string` contains a
+    /// NUL byte, and [`MemoryAccessError::HeapOutOfBounds`] if the string
+    /// and its terminator don't fit in the memory.
+    pub fn write_cstr(&self, offset: u64, string: &str) -> Result<(), MemoryAccessError> {
+        if string.as_bytes().contains(&0) {
+            return Err(MemoryAccessError::InteriorNul);
+        }
+        let end = offset
+            .checked_add(string.len() as u64 + 1)
+            .ok_or(MemoryAccessError::Overflow)?;
+        let range = self.checked_range(offset..end)?;
+        self.log_access(MemoryAccessKind::Write, &range);
+        // Safety: the range has been checked to be in bounds, and the slice
+        // is only alive for the duration of the copy.
+        let data = unsafe { &mut self.data_unchecked_mut()[range] };
+        data[..string.len()].copy_from_slice(string.as_bytes());
+        data[string.len()] = 0;
+        Ok(())
+    }
+
+    /// Reads the `T` stored at `offset`.
+    ///
+    /// The bytes are interpreted in the host byte order. WebAssembly is
+    /// little-endian, so on big-endian hosts multi-byte fields read
+    /// with this method must be byte-swapped to match what the guest
+    /// wrote.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store, ValueType};
+    /// # let store = Store::default();
+    /// #
+    /// #[derive(Clone, Copy, Debug, PartialEq)]
+    /// #[repr(C)]
+    /// struct Point {
+    ///     x: i32,
+    ///     y: i32,
+    /// }
+    ///
+    /// unsafe impl ValueType for Point {}
+    ///
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.write_struct(16, &Point { x: 1, y: -2 }).unwrap();
+    ///
+    /// assert_eq!(m.read_struct::<Point>(16).unwrap(), Point { x: 1, y: -2 });
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::Unaligned`] if `offset` isn't aligned
+    /// for `T` and [`MemoryAccessError::HeapOutOfBounds`] if the value
+    /// isn't contained in the memory.
+    pub fn read_struct<T: ValueType>(&self, offset: u64) -> Result<T, MemoryAccessError> {
+        let ptr = self.typed_ptr::<T>(offset, 1, MemoryAccessKind::Read)?;
+        // Safety: the value has been checked to be aligned and in bounds,
+        // and any bit pattern is a valid `ValueType`.
+        Ok(unsafe { ptr::read(ptr) })
+    }
+
+    /// Writes `value` at `offset`.
+    ///
+    /// Like [`Memory::read_struct`], the value is written in the host
+    /// byte order, which only matches what the guest expects on
+    /// little-endian hosts.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::Unaligned`] if `offset` isn't aligned
+    /// for `T` and [`MemoryAccessError::HeapOutOfBounds`] if the value
+    /// doesn't fit in the memory.
+    pub fn write_struct<T: ValueType>(
+        &self,
+        offset: u64,
+        value: &T,
+    ) -> Result<(), MemoryAccessError> {
+        let ptr = self.typed_ptr::<T>(offset, 1, MemoryAccessKind::Write)?;
+        // Safety: the value has been checked to be aligned and in bounds.
+        unsafe { ptr::write(ptr, *value) };
+        Ok(())
+    }
+
+    /// Reads the `count` values of type `T` stored one after the other
+    /// at `offset`, like an array of `T`.
+    ///
+    /// Like [`Memory::read_struct`], the values are read in the host
+    /// byte order.
+    ///
+    /// # Example
+    ///
+    /// Reading an array passed by the guest as its length followed by
+    /// its elements:
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.write_struct(16, &3u32).unwrap();
+    /// m.write_slice(20, &[10u32, 20, 30]).unwrap();
+    ///
+    /// let len = m.read_struct::<u32>(16).unwrap();
+    /// assert_eq!(m.read_slice::<u32>(20, len as usize).unwrap(), vec![10, 20, 30]);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::Unaligned`] if `offset` isn't aligned
+    /// for `T`, [`MemoryAccessError::Overflow`] if the end of the array
+    /// can't be represented and [`MemoryAccessError::HeapOutOfBounds`]
+    /// if the array isn't contained in the memory.
+    pub fn read_slice<T: ValueType>(
+        &self,
+        offset: u64,
+        count: usize,
+    ) -> Result<Vec<T>, MemoryAccessError> {
+        let ptr = self.typed_ptr::<T>(offset, count, MemoryAccessKind::Read)?;
+        let mut values = Vec::with_capacity(count);
+        // Safety: the values have been checked to be aligned and in bounds,
+        // any bit pattern is a valid `ValueType`, and the vector has room
+        // for them.
+        unsafe {
+            ptr::copy_nonoverlapping(ptr, values.as_mut_ptr(), count);
+            values.set_len(count);
+        }
+        Ok(values)
+    }
+
+    /// Writes `values` one after the other at `offset`, like an array of
+    /// `T`.
+    ///
+    /// Like [`Memory::write_struct`], the values are written in the host
+    /// byte order.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::Unaligned`] if `offset` isn't aligned
+    /// for `T`, [`MemoryAccessError::Overflow`] if the end of the array
+    /// can't be represented and [`MemoryAccessError::HeapOutOfBounds`]
+    /// if the array doesn't fit in the memory.
+    pub fn write_slice<T: ValueType>(
+        &self,
+        offset: u64,
+        values: &[T],
+    ) -> Result<(), MemoryAccessError> {
+        let ptr = self.typed_ptr::<T>(offset, values.len(), MemoryAccessKind::Write)?;
+        // Safety: the values have been checked to be aligned and in bounds.
+        // `values` may borrow from the memory itself, hence `ptr::copy`.
+        unsafe { ptr::copy(values.as_ptr(), ptr, values.len()) };
+        Ok(())
+    }
+
+    /// Returns a pointer to the `count` values of type `T` at `offset`,
+    /// checking that they are aligned and contained in the memory, and
+    /// logging their access.
+    fn typed_ptr<T: ValueType>(
+        &self,
+        offset: u64,
+        count: usize,
+        kind: MemoryAccessKind,
+    ) -> Result<*mut T, MemoryAccessError> {
+        if offset % mem::align_of::<T>() as u64 != 0 {
+            return Err(MemoryAccessError::Unaligned);
+        }
+        let len = (mem::size_of::<T>() as u64)
+            .checked_mul(count as u64)
+            .ok_or(MemoryAccessError::Overflow)?;
+        let end = offset.checked_add(len).ok_or(MemoryAccessError::Overflow)?;
+        let range = self.checked_range(offset..end)?;
+        self.log_access(kind, &range);
+        // Safety: the range has been checked to be in bounds.
+        Ok(unsafe { self.data_ptr().add(range.start) } as *mut T)
+    }
+
+    /// Calls `f` with the bytes of the NUL-terminated string starting at
+    /// `offset`, without the terminator.
+    fn with_cstr<R>(
+        &self,
+        offset: u64,
+        max_len: usize,
+        f: impl FnOnce(&[u8]) -> R,
+    ) -> Result<R, MemoryAccessError> {
+        let start = self.checked_range(offset..offset)?.start;
+        // Safety: the slice is only alive for the duration of the scan and
+        // of `f`, which can't access the memory.
+        let data = unsafe { &self.data_unchecked()[start..] };
+        let scanned = &data[..data.len().min(max_len.saturating_add(1))];
+        match scanned.iter().position(|&byte| byte == 0) {
+            Some(len) => {
+                self.log_access(MemoryAccessKind::Read, &(start..start + len + 1));
+                Ok(f(&scanned[..len]))
+            }
+            None if scanned.len() == data.len() => Err(MemoryAccessError::HeapOutOfBounds),
+            None => Err(MemoryAccessError::NulNotFound),
+        }
+    }
+
+    /// Blocks the current thread until another thread calls
+    /// [`Memory::atomic_notify`] on `addr`, provided the 32-bit value at
+    /// `addr` equals `expected`, as `memory.atomic.wait32` does.
+    ///
+    /// A `timeout` of `None` waits forever. A non-shared memory can't be
+    /// notified from another thread, so waiting on it returns
+    /// immediately with [`AtomicWaitResult::TimedOut`] (or
+    /// [`AtomicWaitResult::NotEqual`]).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use std::time::Duration;
+    /// # use wasmer::{AtomicWaitResult, Memory, MemoryType, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, Some(1), true)).unwrap();
+    ///
+    /// assert_eq!(m.atomic_wait32(0, 1, None).unwrap(), AtomicWaitResult::NotEqual);
+    /// assert_eq!(
+    ///     m.atomic_wait32(0, 0, Some(Duration::from_millis(1))).unwrap(),
+    ///     AtomicWaitResult::TimedOut
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::Unaligned`] if `addr` isn't 4-byte
+    /// aligned and [`MemoryAccessError::HeapOutOfBounds`] if the value
+    /// isn't contained in the memory.
+    pub fn atomic_wait32(
+        &self,
+        addr: u64,
+        expected: u32,
+        timeout: Option<Duration>,
+    ) -> Result<AtomicWaitResult, MemoryAccessError> {
+        let ptr = self.atomic_ptr(addr, 4)? as *const AtomicU32;
+        // Safety: the value has been checked to be aligned and in bounds.
+        let matches = || unsafe { &*ptr }.load(Ordering::SeqCst) == expected;
+        Ok(self.atomic_wait(addr, matches, timeout))
+    }
+
+    /// Like [`Memory::atomic_wait32`], but compares the 64-bit value at
+    /// `addr`, as `memory.atomic.wait64` does.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::Unaligned`] if `addr` isn't 8-byte
+    /// aligned and [`MemoryAccessError::HeapOutOfBounds`] if the value
+    /// isn't contained in the memory.
+    pub fn atomic_wait64(
+        &self,
+        addr: u64,
+        expected: u64,
+        timeout: Option<Duration>,
+    ) -> Result<AtomicWaitResult, MemoryAccessError> {
+        let ptr = self.atomic_ptr(addr, 8)? as *const AtomicU64;
+        // Safety: the value has been checked to be aligned and in bounds.
+        let matches = || unsafe { &*ptr }.load(Ordering::SeqCst) == expected;
+        Ok(self.atomic_wait(addr, matches, timeout))
+    }
+
+    /// Wakes up to `count` threads waiting on `addr` with
+    /// [`Memory::atomic_wait32`] or [`Memory::atomic_wait64`], and
+    /// returns how many were woken, as `memory.atomic.notify` does.
+    ///
+    /// Nobody can wait on a non-shared memory, so this always returns 0
+    /// for them.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`MemoryAccessError::Unaligned`] if `addr` isn't 4-byte
+    /// aligned and [`MemoryAccessError::HeapOutOfBounds`] if it isn't
+    /// contained in the memory.
+    pub fn atomic_notify(&self, addr: u64, count: u32) -> Result<u32, MemoryAccessError> {
+        self.atomic_ptr(addr, 4)?;
+        if !self.is_shared() {
+            return Ok(0);
+        }
+        Ok(wasmer_vm::atomic_notify(self.atomic_wait_key(addr), count))
+    }
+
+    /// Waits on `addr` if `matches` returns `true`.
+    fn atomic_wait(
+        &self,
+        addr: u64,
+        matches: impl FnOnce() -> bool,
+        timeout: Option<Duration>,
+    ) -> AtomicWaitResult {
+        if !self.is_shared() {
+            return if matches() {
+                AtomicWaitResult::TimedOut
+            } else {
+                AtomicWaitResult::NotEqual
+            };
+        }
+        wasmer_vm::atomic_wait(self.atomic_wait_key(addr), matches, timeout)
+    }
+
+    /// Returns the key identifying `addr` in this memory for waiters.
+    ///
+    /// The runtime memory is used rather than the base of its data, as
+    /// the latter can move when the memory grows.
+    fn atomic_wait_key(&self, addr: u64) -> wasmer_vm::AtomicWaitKey {
+        (
+            Arc::as_ptr(&self.vm_memory.from) as *const u8 as usize,
+            addr,
+        )
+    }
+
+    /// Returns a pointer to the `size` bytes at `addr`, checking that
+    /// they are naturally aligned and contained in the memory.
+    fn atomic_ptr(&self, addr: u64, size: u64) -> Result<*mut u8, MemoryAccessError> {
+        if addr % size != 0 {
+            return Err(MemoryAccessError::Unaligned);
+        }
+        let end = addr.checked_add(size).ok_or(MemoryAccessError::Overflow)?;
+        let range = self.checked_range(addr..end)?;
+        // Safety: the range has been checked to be in bounds.
+        Ok(unsafe { self.data_ptr().add(range.start) })
+    }
+
+    /// Checks that `range` is contained in the memory and converts it
+    /// to host offsets.
+    fn checked_range(&self, range: Range<u64>) -> Result<Range<usize>, MemoryAccessError> {
+        if range.start > range.end || range.end > self.data_size() {
+            return Err(MemoryAccessError::HeapOutOfBounds);
+        }
+        let start: usize = range
+            .start
+            .try_into()
+            .map_err(|_| MemoryAccessError::Overflow)?;
+        let end: usize = range
+            .end
+            .try_into()
+            .map_err(|_| MemoryAccessError::Overflow)?;
+        Ok(start..end)
+    }
+
+    /// Reports an access of the host to `range` to the access log of the
+    /// memory, if it has one.
+    fn log_access(&self, kind: MemoryAccessKind, range: &Range<usize>) {
+        if let Some(log) = self.vm_memory.from.access_log() {
+            log(MemoryAccess::new(
+                kind,
+                range.start as u64,
+                range.len() as u64,
+            ));
+        }
+    }
+
     /// Returns the size (in [`Pages`]) of the `Memory`.
     ///
     /// # Example
@@ -187,6 +824,210 @@ impl Memory {
         self.vm_memory.from.grow(delta.into())
     }
 
+    /// Like [`Memory::grow`], but returns `None` if the memory can't be
+    /// grown, as the `memory.grow` instruction returns -1, rather than
+    /// an error.
+    ///
+    /// This suits host code emulating what a guest would do.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, Some(2), false)).unwrap();
+    ///
+    /// assert_eq!(m.try_grow(1), Some(Pages(1)));
+    /// assert_eq!(m.try_grow(1), None);
+    /// assert_eq!(m.size(), Pages(2));
+    /// ```
+    pub fn try_grow<IntoPages>(&self, delta: IntoPages) -> Option<Pages>
+    where
+        IntoPages: Into<Pages>,
+    {
+        self.grow(delta).ok()
+    }
+
+    /// Grows the memory like [`Memory::grow`], and returns its new size
+    /// along with a view of the grown memory.
+    ///
+    /// Growing the memory may move it, leaving the views taken before
+    /// dangling. Getting the view from the growth itself avoids reusing
+    /// one of them by mistake.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// let (size, view) = m.grow_and_view::<u8>(Pages(1)).unwrap();
+    ///
+    /// assert_eq!(size, Pages(2));
+    /// view[Pages(1).bytes().0].set(42);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if memory can't be grown by the specified amount
+    /// of pages, as [`Memory::grow`] does.
+    pub fn grow_and_view<T: ValueType>(
+        &self,
+        delta: Pages,
+    ) -> Result<(Pages, MemoryView<T>), MemoryError> {
+        let previous = self.grow(delta)?;
+        Ok((previous + delta, self.view()))
+    }
+
+    /// Registers `callback` to be called after every successful growth
+    /// of the memory, with the previous size, the new size and the new
+    /// base address of the memory.
+    ///
+    /// Growing the memory may move it, which invalidates the pointers
+    /// returned by [`Memory::data_ptr`] and the views of the memory. The
+    /// callback is called whether the memory is grown by the host or by
+    /// WebAssembly code, on the thread that grew it, so that host code
+    /// caching them can refresh them. It must not grow the memory
+    /// itself: doing so fails with an error.
+    ///
+    /// The callback stays registered until the returned
+    /// [`GrowObserverHandle`] is dropped, or for the lifetime of the
+    /// memory with [`GrowObserverHandle::detach`]. The handle doesn't
+    /// keep the memory alive, but the memory keeps the callback alive:
+    /// a detached callback holding the memory leaks it.
+    ///
+    /// # Panics
+    ///
+    /// If `callback` panics, the memory stays grown and the remaining
+    /// callbacks aren't called. When the memory was grown by
+    /// WebAssembly code, the panic is handled like a panic in a host
+    /// function: it is resumed in the host code that called into
+    /// WebAssembly, or turned into a trap if the store catches host
+    /// panics, see [`Store::set_catch_host_panics`].
+    ///
+    /// [`Store::set_catch_host_panics`]: crate::Store::set_catch_host_panics
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store};
+    /// # use std::sync::{Arc, Mutex};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// let sizes = Arc::new(Mutex::new(Vec::new()));
+    /// let observed = sizes.clone();
+    /// let observer = m
+    ///     .on_grow(move |old, new, _base| observed.lock().unwrap().push((old, new)))
+    ///     .unwrap();
+    ///
+    /// m.grow(2).unwrap();
+    /// assert_eq!(*sizes.lock().unwrap(), vec![(Pages(1), Pages(3))]);
+    ///
+    /// // Dropping the handle unregisters the callback.
+    /// drop(observer);
+    /// m.grow(1).unwrap();
+    /// assert_eq!(sizes.lock().unwrap().len(), 1);
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memory, created by custom
+    /// [`Tunables`](crate::Tunables), doesn't support grow callbacks.
+    pub fn on_grow<F>(&self, callback: F) -> Result<GrowObserverHandle, MemoryError>
+    where
+        F: Fn(Pages, Pages, *mut u8) + Send + Sync + 'static,
+    {
+        let id = self.vm_memory.from.add_grow_observer(Box::new(callback))?;
+        Ok(GrowObserverHandle {
+            memory: Arc::downgrade(&self.vm_memory.from),
+            id,
+        })
+    }
+
+    /// Reports every access of the host to the memory to `sink`, with
+    /// the offset and length of the bytes accessed and the backtrace of
+    /// the host code accessing them, to debug host functions.
+    ///
+    /// The accesses are those of [`Memory::snapshot`],
+    /// [`Memory::read_cstr`], [`Memory::read_cstr_lossy`],
+    /// [`Memory::write_cstr`], [`Memory::read_struct`],
+    /// [`Memory::write_struct`], [`Memory::read_slice`] and
+    /// [`Memory::write_slice`], through any handle to the memory.
+    /// Enabling the log again replaces the previous sink.
+    ///
+    /// Accesses through a [`MemoryView`], including those of
+    /// [`WasmPtr`](crate::WasmPtr) which reads and writes through
+    /// views, or through [`Memory::data_ptr`] aren't reported: they
+    /// access the memory directly, without going through the memory
+    /// object. Neither are the accesses of WebAssembly code.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryAccessKind, MemoryType, Store};
+    /// # use std::sync::{Arc, Mutex};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// let accesses = Arc::new(Mutex::new(Vec::new()));
+    /// let log = accesses.clone();
+    /// m.enable_access_log(move |access| {
+    ///     log.lock().unwrap().push((access.kind, access.offset, access.len))
+    /// })
+    /// .unwrap();
+    ///
+    /// m.write_struct(8, &42u32).unwrap();
+    /// assert_eq!(
+    ///     *accesses.lock().unwrap(),
+    ///     vec![(MemoryAccessKind::Write, 8, 4)]
+    /// );
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the memory, created by custom
+    /// [`Tunables`](crate::Tunables), doesn't support access logs.
+    pub fn enable_access_log<F>(&self, sink: F) -> Result<(), MemoryError>
+    where
+        F: Fn(MemoryAccess) + Send + Sync + 'static,
+    {
+        self.vm_memory.from.set_access_log(Some(Arc::new(sink)))
+    }
+
+    /// Stops reporting the accesses of the host to the memory, see
+    /// [`Memory::enable_access_log`].
+    pub fn disable_access_log(&self) -> Result<(), MemoryError> {
+        self.vm_memory.from.set_access_log(None)
+    }
+
+    /// Resets the contents of the memory to zero, for example to reuse it
+    /// without leaking data from one use to the next.
+    ///
+    /// The size of the memory is unchanged: WebAssembly memories can't
+    /// shrink. Rather than writing zeros over the memory, its pages are
+    /// given back to the OS where possible, and zero-filled again on
+    /// their next access, which is much faster for large memories.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Memory, MemoryType, Pages, Store};
+    /// # let store = Store::default();
+    /// #
+    /// let m = Memory::new(&store, MemoryType::new(1, None, false)).unwrap();
+    /// m.view::<u8>()[0].set(42);
+    /// m.zero().unwrap();
+    ///
+    /// assert_eq!(m.view::<u8>()[0].get(), 0);
+    /// assert_eq!(m.size(), Pages(1));
+    /// ```
+    pub fn zero(&self) -> Result<(), MemoryError> {
+        self.vm_memory.from.zero()
+    }
+
     /// Return a "view" of the currently accessible memory. By
     /// default, the view is unsynchronized, using regular memory
     /// accesses. You can force a memory view to use atomic accesses
@@ -246,7 +1087,7 @@ impl Memory {
     /// assert!(m.same(&m));
     /// ```
     pub fn same(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.vm_memory.from, &other.vm_memory.from)
+        self.vm_memory.same(&other.vm_memory)
     }
 
     /// Get access to the backing VM value for this extern. This function is for
@@ -262,6 +1103,37 @@ impl Memory {
     }
 }
 
+/// A callback registered with [`Memory::on_grow`], unregistered when
+/// the handle is dropped.
+#[must_use = "the callback is unregistered when the handle is dropped"]
+pub struct GrowObserverHandle {
+    memory: Weak<dyn RuntimeMemory>,
+    id: u64,
+}
+
+impl GrowObserverHandle {
+    /// Keeps the callback registered for the lifetime of the memory.
+    pub fn detach(self) {
+        mem::forget(self);
+    }
+}
+
+impl fmt::Debug for GrowObserverHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GrowObserverHandle")
+            .field("id", &self.id)
+            .finish()
+    }
+}
+
+impl Drop for GrowObserverHandle {
+    fn drop(&mut self) {
+        if let Some(memory) = self.memory.upgrade() {
+            memory.remove_grow_observer(self.id);
+        }
+    }
+}
+
 impl Clone for Memory {
     fn clone(&self) -> Self {
         let mut vm_memory = self.vm_memory.clone();
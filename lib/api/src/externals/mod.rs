@@ -4,12 +4,13 @@ mod memory;
 mod table;
 
 pub use self::function::{
-    FromToNativeWasmType, Function, HostFunction, WasmTypeList, WithEnv, WithoutEnv,
+    Caller, FromToNativeWasmType, Function, HostFunction, IntegerExtension, PreparedCall,
+    WasmTypeList, WithEnv, WithoutEnv,
 };
 
 pub use self::global::Global;
-pub use self::memory::Memory;
-pub use self::table::Table;
+pub use self::memory::{GrowObserverHandle, Memory, MemoryAccessError};
+pub use self::table::{Table, TableGrowBeyondMaximum};
 
 use crate::exports::{ExportError, Exportable};
 use crate::store::{Store, StoreObject};
@@ -5,10 +5,28 @@ use crate::types::{Val, ValFuncRef};
 use crate::RuntimeError;
 use crate::TableType;
 use loupe::MemoryUsage;
-use std::sync::Arc;
+use thiserror::Error;
 use wasmer_engine::Export;
 use wasmer_vm::{Table as RuntimeTable, TableElement, VMTable};
 
+/// The error returned by [`Table::grow`] when growing the table would
+/// exceed the maximum of its [`TableType`].
+///
+/// It is wrapped in a [`RuntimeError`], from which it can be recovered
+/// with [`RuntimeError::downcast`].
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error(
+    "the table could not grow by {requested} elements: current size {current}, maximum {maximum}"
+)]
+pub struct TableGrowBeyondMaximum {
+    /// The current size of the table, in elements.
+    pub current: u32,
+    /// The maximum size of the table, in elements.
+    pub maximum: u32,
+    /// The number of elements the table was asked to grow by.
+    pub requested: u32,
+}
+
 /// A WebAssembly `table` instance.
 ///
 /// The `Table` struct is an array-like structure representing a WebAssembly Table,
@@ -38,20 +56,50 @@ impl Table {
     /// All the elements in the table will be set to the `init` value.
     ///
     /// This function will construct the `Table` using the store
-    /// [`BaseTunables`][crate::tunables::BaseTunables].
+    /// [`BaseTunables`][crate::tunables::BaseTunables], which refuse
+    /// to create tables larger than their `max_table_elements`.
     pub fn new(store: &Store, ty: TableType, init: Val) -> Result<Self, RuntimeError> {
         let item = init.into_table_reference(store)?;
+        let table = Self::new_null(store, ty)?;
+
+        // Fresh tables are already filled with null references.
+        if !item.is_null() {
+            let num_elements = table.size();
+            for i in 0..num_elements {
+                set_table_item(table.vm_table.from.as_ref(), i, item.clone())?;
+            }
+        }
+
+        Ok(table)
+    }
+
+    /// Creates a new `Table` with the provided [`TableType`] definition,
+    /// whose elements are all null references.
+    ///
+    /// This is the usual way to create a table the guest fills in, such
+    /// as a dispatch table for `call_indirect`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Store, Table, TableType, Type};
+    /// # let store = Store::default();
+    /// #
+    /// let table = Table::new_null(&store, TableType::new(Type::FuncRef, 4, None)).unwrap();
+    ///
+    /// assert_eq!(table.size(), 4);
+    /// assert!(table.get(0).unwrap().unwrap_funcref().is_none());
+    /// ```
+    pub fn new_null(store: &Store, ty: TableType) -> Result<Self, RuntimeError> {
+        store
+            .check_not_frozen()
+            .map_err(|e| RuntimeError::user(Box::new(e)))?;
         let tunables = store.tunables();
         let style = tunables.table_style(&ty);
         let table = tunables
             .create_host_table(&ty, &style)
             .map_err(RuntimeError::new)?;
 
-        let num_elements = table.size();
-        for i in 0..num_elements {
-            set_table_item(table.as_ref(), i, item.clone())?;
-        }
-
         Ok(Self {
             store: store.clone(),
             vm_table: VMTable {
@@ -83,6 +131,22 @@ impl Table {
         set_table_item(self.vm_table.from.as_ref(), index, item)
     }
 
+    /// Returns an iterator over the elements of the `Table`, in order.
+    ///
+    /// The iterator captures the size of the `Table` when it is
+    /// created: elements added by growing the table afterwards are
+    /// not yielded.
+    pub fn iter(&self) -> impl Iterator<Item = Val> + '_ {
+        (0..self.size()).filter_map(move |index| self.get(index))
+    }
+
+    /// Collects the elements of the `Table` into a `Vec`.
+    ///
+    /// See [`Table::iter`].
+    pub fn to_vec(&self) -> Vec<Val> {
+        self.iter().collect()
+    }
+
     /// Retrieves the size of the `Table` (in elements)
     pub fn size(&self) -> u32 {
         self.vm_table.from.size()
@@ -96,8 +160,23 @@ impl Table {
     ///
     /// # Errors
     ///
-    /// Returns an error if the `delta` is out of bounds for the table.
+    /// Returns a [`TableGrowBeyondMaximum`] error if the new size would
+    /// exceed the maximum of the table type, or another error if the
+    /// table can't be grown otherwise.
     pub fn grow(&self, delta: u32, init: Val) -> Result<u32, RuntimeError> {
+        if let Some(maximum) = self.ty().maximum {
+            let current = self.size();
+            if current
+                .checked_add(delta)
+                .map_or(true, |size| size > maximum)
+            {
+                return Err(RuntimeError::user(Box::new(TableGrowBeyondMaximum {
+                    current,
+                    maximum,
+                    requested: delta,
+                })));
+            }
+        }
         let item = init.into_table_reference(&self.store)?;
         self.vm_table
             .from
@@ -144,7 +223,7 @@ impl Table {
 
     /// Returns whether or not these two tables refer to the same data.
     pub fn same(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.vm_table.from, &other.vm_table.from)
+        self.vm_table.same(&other.vm_table)
     }
 
     /// Get access to the backing VM value for this extern. This function is for
@@ -1,9 +1,12 @@
 //! The import module contains the implementation data structures and helper functions used to
 //! manipulate and access a wasm module's imports including memories, tables, globals, and
 //! functions.
+use crate::store::StoreObject;
+use crate::{Exports, Extern, ImportType, Module, Store};
 use std::borrow::{Borrow, BorrowMut};
 use std::collections::VecDeque;
-use std::collections::{hash_map::Entry, HashMap};
+use std::collections::{hash_map::Entry, HashMap, HashSet};
+use std::error::Error;
 use std::fmt;
 use std::sync::{Arc, Mutex};
 use wasmer_engine::{Export, NamedResolver};
@@ -18,6 +21,28 @@ pub trait LikeNamespace {
     fn get_namespace_exports(&self) -> Vec<(String, Export)>;
 }
 
+/// The error returned by [`ImportObject::with_defaults_for`] when
+/// some imports of the module couldn't be provided.
+#[derive(Debug, Clone)]
+pub struct UnresolvedImportsError {
+    /// The imports that weren't provided, in the order the module
+    /// declares them.
+    pub imports: Vec<ImportType>,
+}
+
+impl fmt::Display for UnresolvedImportsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unresolved imports:")?;
+        for (i, import) in self.imports.iter().enumerate() {
+            let separator = if i == 0 { "" } else { "," };
+            write!(f, "{} `{}`.`{}`", separator, import.module(), import.name())?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for UnresolvedImportsError {}
+
 /// All of the import data used when instantiating.
 ///
 /// It's suggested that you use the [`imports!`] macro
@@ -50,6 +75,71 @@ impl ImportObject {
         Default::default()
     }
 
+    /// Creates an `ImportObject` holding every import of `module`,
+    /// as returned by `provider`.
+    ///
+    /// `provider` is called once for each distinct `(module, name)`
+    /// pair the module imports. Imports for which it returns `None`,
+    /// or an extern coming from another store than `store`, are
+    /// collected and reported together in the error.
+    ///
+    /// # Usage
+    /// ```
+    /// # use wasmer::{ExternType, Function, ImportObject, Module, Store};
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, r#"(module (import "env" "answer" (func (result i32))))"#)?;
+    /// let import_object = ImportObject::with_defaults_for(&module, &store, |import| {
+    ///     match (import.name(), import.ty()) {
+    ///         ("answer", ExternType::Function(_)) => {
+    ///             Some(Function::new_native(&store, || 42).into())
+    ///         }
+    ///         _ => None,
+    ///     }
+    /// })?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_defaults_for<F>(
+        module: &Module,
+        store: &Store,
+        provider: F,
+    ) -> Result<Self, UnresolvedImportsError>
+    where
+        F: Fn(&ImportType) -> Option<Extern>,
+    {
+        let mut namespaces: HashMap<String, Exports> = HashMap::new();
+        let mut seen = HashSet::new();
+        let mut unresolved = Vec::new();
+
+        for import in module.imports() {
+            if !seen.insert((import.module().to_string(), import.name().to_string())) {
+                continue;
+            }
+            match provider(&import) {
+                Some(extern_) if extern_.comes_from_same_store(store) => {
+                    namespaces
+                        .entry(import.module().to_string())
+                        .or_default()
+                        .insert(import.name(), extern_);
+                }
+                _ => unresolved.push(import),
+            }
+        }
+
+        if !unresolved.is_empty() {
+            return Err(UnresolvedImportsError {
+                imports: unresolved,
+            });
+        }
+
+        let mut import_object = Self::new();
+        for (name, namespace) in namespaces {
+            import_object.register(name, namespace);
+        }
+        Ok(import_object)
+    }
+
     /// Gets an export given a module and a name
     ///
     /// # Usage
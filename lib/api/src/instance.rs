@@ -1,14 +1,19 @@
-use crate::exports::Exports;
-use crate::externals::Extern;
+use crate::exports::{Exportable, Exports};
+use crate::externals::{Extern, Global, Memory, Table};
 use crate::module::Module;
 use crate::store::Store;
+use crate::types::{ExternType, ImportType, Val};
 use crate::{HostEnvInitError, LinkError, RuntimeError};
 use loupe::MemoryUsage;
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
 use std::fmt;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
 use thiserror::Error;
-use wasmer_engine::Resolver;
-use wasmer_vm::{InstanceHandle, VMContext};
+use wasmer_engine::{Export, InstanceOverrides, Resolver};
+use wasmer_types::entity::EntityRef;
+use wasmer_types::{DataIndex, ExportIndex, GlobalIndex, MemoryIndex, TableIndex};
+use wasmer_vm::{InstanceHandle, Trap, TrapCode, VMContext, VMExtern};
 
 /// A WebAssembly Instance is a stateful, executable
 /// instance of a WebAssembly [`Module`].
@@ -22,10 +27,30 @@ use wasmer_vm::{InstanceHandle, VMContext};
 pub struct Instance {
     handle: Arc<Mutex<InstanceHandle>>,
     module: Module,
+    /// The values of the mutable globals right after instantiation, if
+    /// they were kept, see [`InstanceBuilder::keep_initial_globals`].
+    #[loupe(skip)]
+    initial_globals: Option<Arc<GlobalsSnapshot>>,
     /// The exports for an instance.
     pub exports: Exports,
 }
 
+/// The values of the mutable globals of an [`Instance`] at some point,
+/// taken with [`Instance::snapshot_globals`].
+#[derive(Clone)]
+pub struct GlobalsSnapshot {
+    instance: Weak<Mutex<InstanceHandle>>,
+    values: Vec<(GlobalIndex, Val)>,
+}
+
+impl fmt::Debug for GlobalsSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GlobalsSnapshot")
+            .field("values", &self.values)
+            .finish()
+    }
+}
+
 #[cfg(test)]
 mod send_test {
     use super::*;
@@ -58,9 +83,86 @@ pub enum InstantiationError {
     #[error(transparent)]
     Start(RuntimeError),
 
+    /// An active data segment doesn't fit in its memory. The segments
+    /// before it have already been written to their memories, which
+    /// is visible if a memory is imported.
+    #[error(transparent)]
+    DataSegmentOutOfBounds(DataSegmentOutOfBounds),
+
     /// Error occurred when initializing the host environment.
     #[error(transparent)]
     HostEnvInitialization(HostEnvInitError),
+
+    /// The instance would exceed a limit set on the store, such as
+    /// [`Store::set_max_instances`].
+    ///
+    /// [`Store::set_max_instances`]: crate::Store::set_max_instances
+    #[error("resource limit exceeded: {0}")]
+    ResourceLimitExceeded(String),
+}
+
+/// An instantiation of a module, given to the observer set with
+/// [`Store::set_instantiation_observer`].
+///
+/// The module is identified by its name, and by its hash when the
+/// engine recorded it, see [`Module::source_hash`].
+///
+/// [`Store::set_instantiation_observer`]: crate::Store::set_instantiation_observer
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct InstantiationEvent<'a> {
+    /// The instantiated module.
+    pub module: &'a Module,
+    /// The imports of the module, in order, with what the resolver
+    /// provided for each of them.
+    pub imports: &'a [ResolvedImport],
+    /// The error of the instantiation, `None` if it succeeded.
+    pub error: Option<&'a InstantiationError>,
+}
+
+/// An import of a module, with what the resolver provided for it, as
+/// reported by [`InstantiationEvent::imports`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct ResolvedImport {
+    /// The import, as declared by the module.
+    pub import: ImportType,
+    /// The type of the extern the resolver provided, `None` if it
+    /// provided none. It may not match the type of the import, which
+    /// fails the instantiation.
+    pub ty: Option<ExternType>,
+    /// Whether the extern was created by the host, rather than
+    /// exported by an instance. `false` if the resolver provided none.
+    pub from_host: bool,
+}
+
+impl ResolvedImport {
+    fn new(import: ImportType, export: Option<Export>) -> Self {
+        let (ty, from_host) = match export {
+            Some(Export::Function(function)) => (
+                Some(ExternType::Function(function.vm_function.signature)),
+                function.vm_function.instance_ref.is_none(),
+            ),
+            Some(Export::Table(table)) => (
+                Some(ExternType::Table(*table.from.ty())),
+                table.instance_ref.is_none(),
+            ),
+            Some(Export::Memory(memory)) => (
+                Some(ExternType::Memory(memory.from.ty())),
+                memory.instance_ref.is_none(),
+            ),
+            Some(Export::Global(global)) => (
+                Some(ExternType::Global(*global.from.ty())),
+                global.instance_ref.is_none(),
+            ),
+            None => (None, false),
+        };
+        Self {
+            import,
+            ty,
+            from_host,
+        }
+    }
 }
 
 impl From<wasmer_engine::InstantiationError> for InstantiationError {
@@ -68,6 +170,9 @@ impl From<wasmer_engine::InstantiationError> for InstantiationError {
         match other {
             wasmer_engine::InstantiationError::Link(e) => Self::Link(e),
             wasmer_engine::InstantiationError::Start(e) => Self::Start(e),
+            wasmer_engine::InstantiationError::DataSegmentOutOfBounds(e) => {
+                Self::DataSegmentOutOfBounds(e)
+            }
         }
     }
 }
@@ -113,8 +218,64 @@ impl Instance {
     ///  * Link errors that happen when plugging the imports into the instance
     ///  * Runtime errors that happen when running the module `start` function.
     pub fn new(module: &Module, resolver: &dyn Resolver) -> Result<Self, InstantiationError> {
+        Self::new_with(module, resolver, true, &InstanceOverrides::default(), false)
+    }
+
+    /// Creates a new `Instance` like [`Instance::new`], but without
+    /// running the start function of the module.
+    ///
+    /// The instance is otherwise fully initialized, and the start
+    /// function can be run later with [`Instance::invoke_start_function`],
+    /// or not at all.
+    pub fn new_deferring_start(
+        module: &Module,
+        resolver: &dyn Resolver,
+    ) -> Result<Self, InstantiationError> {
+        Self::new_with(
+            module,
+            resolver,
+            false,
+            &InstanceOverrides::default(),
+            false,
+        )
+    }
+
+    fn new_with(
+        module: &Module,
+        resolver: &dyn Resolver,
+        run_start: bool,
+        overrides: &InstanceOverrides,
+        keep_initial_globals: bool,
+    ) -> Result<Self, InstantiationError> {
+        let result =
+            Self::instantiate(module, resolver, run_start, overrides, keep_initial_globals);
+        if let Some(observer) = module.store().instantiation_observer() {
+            let imports = module
+                .imports()
+                .enumerate()
+                .map(|(index, import)| {
+                    let export = resolver.resolve(index as u32, import.module(), import.name());
+                    ResolvedImport::new(import, export)
+                })
+                .collect::<Vec<_>>();
+            observer(&InstantiationEvent {
+                module,
+                imports: &imports,
+                error: result.as_ref().err(),
+            });
+        }
+        result
+    }
+
+    fn instantiate(
+        module: &Module,
+        resolver: &dyn Resolver,
+        run_start: bool,
+        overrides: &InstanceOverrides,
+        keep_initial_globals: bool,
+    ) -> Result<Self, InstantiationError> {
         let store = module.store();
-        let handle = module.instantiate(resolver)?;
+        let handle = module.instantiate(resolver, run_start, overrides)?;
         let exports = module
             .exports()
             .map(|export| {
@@ -125,9 +286,24 @@ impl Instance {
             })
             .collect::<Exports>();
 
+        let handle = Arc::new(Mutex::new(handle));
+        let initial_globals = if keep_initial_globals {
+            let mut initial_globals = Self::snapshot_globals_of(&handle, store);
+            // The snapshot is kept by the instance: functions must not
+            // keep it alive.
+            for (_, value) in initial_globals.values.iter_mut() {
+                if let Val::FuncRef(Some(function)) = value {
+                    function.into_weak_instance_ref();
+                }
+            }
+            Some(Arc::new(initial_globals))
+        } else {
+            None
+        };
         let instance = Self {
-            handle: Arc::new(Mutex::new(handle)),
+            handle,
             module: module.clone(),
+            initial_globals,
             exports,
         };
 
@@ -160,12 +336,367 @@ impl Instance {
         self.module.store()
     }
 
+    /// Runs the start function of the module, if it has one.
+    ///
+    /// This is meant for instances created with
+    /// [`Instance::new_deferring_start`]: nothing prevents running the
+    /// start function more than once.
+    pub fn invoke_start_function(&self) -> Result<(), RuntimeError> {
+        self.handle
+            .lock()
+            .unwrap()
+            .invoke_start_function(self.store())
+            .map_err(RuntimeError::from_trap)
+    }
+
+    /// Returns the current values of the mutable globals defined by the
+    /// instance, exported or not, to restore them later with
+    /// [`Instance::restore_globals`].
+    ///
+    /// The globals the instance imports aren't part of the snapshot:
+    /// they are shared with whoever provides them. Reference values are
+    /// cloned, and kept alive by the snapshot.
+    pub fn snapshot_globals(&self) -> GlobalsSnapshot {
+        Self::snapshot_globals_of(&self.handle, self.store())
+    }
+
+    /// Sets the mutable globals of the instance back to the values of
+    /// `snapshot`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without setting any global, if the snapshot was
+    /// taken from another instance, or if it refers to a function of an
+    /// instance that has been dropped since.
+    pub fn restore_globals(&self, snapshot: &GlobalsSnapshot) -> Result<(), RuntimeError> {
+        if !Weak::ptr_eq(&snapshot.instance, &Arc::downgrade(&self.handle)) {
+            return Err(RuntimeError::new(
+                "the globals snapshot was taken from another instance",
+            ));
+        }
+        let mut values = Vec::with_capacity(snapshot.values.len());
+        for (index, value) in &snapshot.values {
+            let value = match value {
+                // Cloning would panic on a function whose instance is gone.
+                Val::FuncRef(Some(function)) => {
+                    Val::FuncRef(Some(function.try_clone().ok_or_else(|| {
+                        RuntimeError::new(
+                            "the globals snapshot refers to a function of a dropped instance",
+                        )
+                    })?))
+                }
+                value => value.clone(),
+            };
+            values.push((*index, value));
+        }
+        let handle = self.handle.lock().unwrap();
+        for (index, value) in values {
+            match handle.lookup_by_declaration(&ExportIndex::Global(index)) {
+                VMExtern::Global(vm_global) => {
+                    Global::from_vm_export(self.store(), vm_global).set(value)?
+                }
+                _ => unreachable!("a global index always refers to a global"),
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the mutable globals of the instance back to the values they
+    /// had when the instance was created, for example to reuse it for
+    /// another job without instantiating the module again.
+    ///
+    /// This is [`Instance::restore_globals`] with a snapshot taken when
+    /// the instance was created, after the start function ran. The
+    /// snapshot is only taken for the instances created with
+    /// [`InstanceBuilder::keep_initial_globals`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, r#"(module
+    ///     (global $count (export "count") (mut i32) (i32.const 0))
+    ///     (func (export "tick") (global.set $count (i32.add (global.get $count) (i32.const 1)))))"#)?;
+    /// let instance = InstanceBuilder::new(&module)
+    ///     .keep_initial_globals(true)
+    ///     .build(&imports! {})?;
+    /// let count = instance.exports.get_global("count")?;
+    ///
+    /// instance.exports.get_function("tick")?.call(&[])?;
+    /// assert_eq!(count.get(), Value::I32(1));
+    /// instance.reset_globals()?;
+    /// assert_eq!(count.get(), Value::I32(0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the instance didn't keep its initial globals,
+    /// and the errors of [`Instance::restore_globals`].
+    pub fn reset_globals(&self) -> Result<(), RuntimeError> {
+        match &self.initial_globals {
+            Some(initial_globals) => self.restore_globals(initial_globals),
+            None => Err(RuntimeError::new(
+                "the instance didn't keep its initial globals, create it with \
+                 `InstanceBuilder::keep_initial_globals` to reset them",
+            )),
+        }
+    }
+
+    /// Returns the current values of the mutable globals exported by the
+    /// instance, with their export names, to apply them later with
+    /// [`Instance::apply_globals`].
+    ///
+    /// Unlike [`Instance::snapshot_globals`], the values are keyed by
+    /// name, so they can be stored or applied to another instance of
+    /// the same module. Globals that aren't exported aren't part of it,
+    /// while exported globals that the instance imports are.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, r#"(module
+    ///     (global (export "a") (mut i32) (i32.const 1))
+    ///     (global (export "b") i32 (i32.const 2)))"#)?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    ///
+    /// assert_eq!(
+    ///     instance.globals_snapshot(),
+    ///     vec![("a".to_string(), Value::I32(1))],
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn globals_snapshot(&self) -> Vec<(String, Val)> {
+        self.exports
+            .iter()
+            .filter_map(|(name, extern_)| match extern_ {
+                Extern::Global(global) if global.ty().mutability.is_mutable() => {
+                    Some((name.clone(), global.get()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Sets the exported globals named in `values`, typically taken
+    /// with [`Instance::globals_snapshot`].
+    ///
+    /// Immutable globals are skipped with a warning, since their value
+    /// can't have changed since instantiation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error, without setting any global, if a name isn't an
+    /// exported global, or if a value doesn't have the type of its
+    /// global, comes from another store, or is rejected by the
+    /// validator of its global, see [`Global::with_validator`]. The
+    /// values are all checked before any is set. A global set
+    /// concurrently may still make its validator reject a value it
+    /// accepted, leaving the globals before it set.
+    pub fn apply_globals(&self, values: &[(String, Val)]) -> Result<(), RuntimeError> {
+        let mut globals = Vec::with_capacity(values.len());
+        for (name, value) in values {
+            let global = self.exports.get_global(name).map_err(|_| {
+                RuntimeError::new(format!("the instance exports no global named `{}`", name))
+            })?;
+            let ty = global.ty();
+            if !ty.mutability.is_mutable() {
+                tracing::warn!("skipping the immutable global `{}`", name);
+                continue;
+            }
+            if value.ty() != ty.ty {
+                return Err(RuntimeError::new(format!(
+                    "the global `{}` has type {} but the value has type {}",
+                    name,
+                    ty.ty,
+                    value.ty()
+                )));
+            }
+            global.validate(value).map_err(|e| {
+                RuntimeError::new(format!(
+                    "the global `{}` can't be set: {}",
+                    name,
+                    e.message()
+                ))
+            })?;
+            globals.push((global, value));
+        }
+        for (global, value) in globals {
+            global.set(value.clone())?;
+        }
+        Ok(())
+    }
+
+    fn snapshot_globals_of(handle: &Arc<Mutex<InstanceHandle>>, store: &Store) -> GlobalsSnapshot {
+        let instance = Arc::downgrade(handle);
+        let handle = handle.lock().unwrap();
+        let module = handle.module();
+        let values = module
+            .globals
+            .iter()
+            .filter(|(index, ty)| {
+                module.local_global_index(*index).is_some() && ty.mutability.is_mutable()
+            })
+            .map(
+                |(index, _)| match handle.lookup_by_declaration(&ExportIndex::Global(index)) {
+                    VMExtern::Global(vm_global) => {
+                        (index, Global::from_vm_export(store, vm_global).get())
+                    }
+                    _ => unreachable!("a global index always refers to a global"),
+                },
+            )
+            .collect();
+        GlobalsSnapshot { instance, values }
+    }
+
+    /// Returns the default memory of the instance, the one with index
+    /// `0`, whether it is exported or not and whatever its export name.
+    ///
+    /// This is the memory guest pointers point into. It may be imported
+    /// by the module rather than defined by it. Returns `None` if the
+    /// module has no memory.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, "(module (memory 1))")?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    ///
+    /// let memory = instance.default_memory().unwrap();
+    /// assert_eq!(memory.size(), Pages(1));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn default_memory(&self) -> Option<Memory> {
+        let handle = self.handle.lock().unwrap();
+        let index = handle.module().memories.keys().next()?;
+        match handle.lookup_by_declaration(&ExportIndex::Memory(index)) {
+            VMExtern::Memory(vm_memory) => Some(Memory::from_vm_export(self.store(), vm_memory)),
+            _ => unreachable!("a memory index always refers to a memory"),
+        }
+    }
+
+    /// Copies `len` bytes of the passive data segment `segment`, from
+    /// offset `src`, to the default memory at offset `dst`, like the
+    /// `memory.init` instruction.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the module has no memory, if `segment` isn't
+    /// a passive data segment of the module or has been dropped with
+    /// [`Instance::data_drop`], and a [`TrapCode::HeapAccessOutOfBounds`]
+    /// trap if either range is out of bounds. Nothing is copied then.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, r#"(module (memory 1) (data "hello"))"#)?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    ///
+    /// instance.memory_init(0, 16, 1, 3)?;
+    /// let memory = instance.default_memory().unwrap();
+    /// assert_eq!(memory.snapshot(16..19)?, b"ell");
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`TrapCode::HeapAccessOutOfBounds`]: crate::TrapCode::HeapAccessOutOfBounds
+    pub fn memory_init(
+        &self,
+        segment: u32,
+        dst: u64,
+        src: u32,
+        len: u32,
+    ) -> Result<(), RuntimeError> {
+        let handle = self.handle.lock().unwrap();
+        let memory_index = handle
+            .module()
+            .memories
+            .keys()
+            .next()
+            .ok_or_else(|| RuntimeError::new("the module has no memory"))?;
+        let data_index = passive_data_index(&handle, segment)?;
+        if !handle.has_passive_data(data_index) {
+            return Err(RuntimeError::new(format!(
+                "the passive data segment {} has been dropped",
+                segment
+            )));
+        }
+        let dst = u32::try_from(dst)
+            .map_err(|_| RuntimeError::from_trap(Trap::lib(TrapCode::HeapAccessOutOfBounds)))?;
+        handle
+            .memory_init(memory_index, data_index, dst, src, len)
+            .map_err(RuntimeError::from_trap)
+    }
+
+    /// Drops the passive data segment `segment`, like the `data.drop`
+    /// instruction, after which [`Instance::memory_init`] fails with it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `segment` isn't a passive data segment of the
+    /// module. Dropping a segment again does nothing.
+    pub fn data_drop(&self, segment: u32) -> Result<(), RuntimeError> {
+        let handle = self.handle.lock().unwrap();
+        handle.data_drop(passive_data_index(&handle, segment)?);
+        Ok(())
+    }
+
+    /// Returns all the memories of the instance, exported or not, in
+    /// the order the module declares them. They only hold a weak
+    /// reference to the instance.
+    pub(crate) fn memories_weak(&self) -> Vec<Memory> {
+        let handle = self.handle.lock().unwrap();
+        handle
+            .module()
+            .memories
+            .keys()
+            .map(
+                |index| match handle.lookup_by_declaration(&ExportIndex::Memory(index)) {
+                    VMExtern::Memory(vm_memory) => {
+                        let mut memory = Memory::from_vm_export(self.store(), vm_memory);
+                        memory.into_weak_instance_ref();
+                        memory
+                    }
+                    _ => unreachable!("a memory index always refers to a memory"),
+                },
+            )
+            .collect()
+    }
+
     #[doc(hidden)]
     pub fn vmctx_ptr(&self) -> *mut VMContext {
         self.handle.lock().unwrap().vmctx_ptr()
     }
 }
 
+/// Returns the index of the passive data segment `segment` of the
+/// module of `handle`, dropped or not.
+fn passive_data_index(handle: &InstanceHandle, segment: u32) -> Result<DataIndex, RuntimeError> {
+    let data_index = DataIndex::from_u32(segment);
+    if handle.module().passive_data.contains_key(&data_index) {
+        Ok(data_index)
+    } else {
+        Err(RuntimeError::new(format!(
+            "the module has no passive data segment {}",
+            segment
+        )))
+    }
+}
+
 impl fmt::Debug for Instance {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Instance")
@@ -173,3 +704,172 @@ impl fmt::Debug for Instance {
             .finish()
     }
 }
+
+/// A builder for an [`Instance`] using the given memories, tables and
+/// globals for the ones the module defines, instead of creating them.
+///
+/// This lets embedders reuse memories between instances, for example
+/// to pool them. The memories, tables and globals that are not
+/// provided are created as usual.
+///
+/// A provided extern must belong to the store of the module, have a
+/// type compatible with the declared one, following the import
+/// matching rules, and must not belong to another instance. The code
+/// of the instance keeps a copy of the location and size of its
+/// memories and tables, so a provided memory must be static, and a
+/// provided table must be at its maximum size. The contents of a
+/// provided memory are kept, apart from what the data segments of the
+/// module overwrite, and the value of a provided global is set by the
+/// module, like for any global it defines.
+///
+/// ```
+/// # use wasmer::*;
+/// # fn main() -> anyhow::Result<()> {
+/// let store = Store::default();
+/// let module = Module::new(&store, "(module (memory (export \"mem\") 1 1))")?;
+/// let memory = Memory::new(&store, MemoryType::new(1, Some(1), false))?;
+/// let instance = InstanceBuilder::new(&module)
+///     .memory(MemoryIndex::from_u32(0), &memory)
+///     .build(&imports! {})?;
+/// assert!(instance.exports.get_memory("mem")?.same(&memory));
+/// # Ok(())
+/// # }
+/// ```
+pub struct InstanceBuilder<'a> {
+    module: &'a Module,
+    memories: BTreeMap<MemoryIndex, Memory>,
+    tables: BTreeMap<TableIndex, Table>,
+    globals: BTreeMap<GlobalIndex, Global>,
+    keep_initial_globals: bool,
+}
+
+impl<'a> InstanceBuilder<'a> {
+    /// Creates a builder for an instance of `module`, with nothing
+    /// provided yet.
+    pub fn new(module: &'a Module) -> Self {
+        Self {
+            module,
+            memories: BTreeMap::new(),
+            tables: BTreeMap::new(),
+            globals: BTreeMap::new(),
+            keep_initial_globals: false,
+        }
+    }
+
+    /// Uses `memory` for the memory the module defines at `index`.
+    pub fn memory(mut self, index: MemoryIndex, memory: &Memory) -> Self {
+        self.memories.insert(index, memory.clone());
+        self
+    }
+
+    /// Uses `table` for the table the module defines at `index`.
+    pub fn table(mut self, index: TableIndex, table: &Table) -> Self {
+        self.tables.insert(index, table.clone());
+        self
+    }
+
+    /// Uses `global` for the global the module defines at `index`.
+    pub fn global(mut self, index: GlobalIndex, global: &Global) -> Self {
+        self.globals.insert(index, global.clone());
+        self
+    }
+
+    /// Keeps the values of the mutable globals of the instance once it is
+    /// created, so that [`Instance::reset_globals`] can set them back.
+    ///
+    /// This is off by default, since it reads every mutable global on
+    /// each instantiation.
+    pub fn keep_initial_globals(mut self, keep: bool) -> Self {
+        self.keep_initial_globals = keep;
+        self
+    }
+
+    /// Instantiates the module with the imports resolved by `resolver`,
+    /// like [`Instance::new`].
+    ///
+    /// ## Errors
+    ///
+    /// Besides the errors of [`Instance::new`], a provided extern that
+    /// can't be used for the module is reported as a
+    /// [`LinkError::Override`].
+    pub fn build(self, resolver: &dyn Resolver) -> Result<Instance, InstantiationError> {
+        let store = self.module.store();
+        let invalid = |kind: &str, index: usize, reason: &str| {
+            InstantiationError::Link(LinkError::Override(format!(
+                "the {} provided for index {} {}",
+                kind, index, reason
+            )))
+        };
+        let mut overrides = InstanceOverrides::default();
+        for (index, memory) in self.memories {
+            if !Store::same(memory.store(), store) {
+                return Err(invalid("memory", index.index(), "belongs to another store"));
+            }
+            match memory.to_export() {
+                Export::Memory(vm_memory) if vm_memory.instance_ref.is_none() => {
+                    overrides.memories.insert(index, vm_memory.from);
+                }
+                _ => return Err(invalid("memory", index.index(), "belongs to an instance")),
+            }
+        }
+        for (index, table) in self.tables {
+            if !Store::same(table.store(), store) {
+                return Err(invalid("table", index.index(), "belongs to another store"));
+            }
+            match table.to_export() {
+                Export::Table(vm_table) if vm_table.instance_ref.is_none() => {
+                    overrides.tables.insert(index, vm_table.from);
+                }
+                _ => return Err(invalid("table", index.index(), "belongs to an instance")),
+            }
+        }
+        for (index, global) in self.globals {
+            if !Store::same(global.store(), store) {
+                return Err(invalid("global", index.index(), "belongs to another store"));
+            }
+            match global.to_export() {
+                Export::Global(vm_global) => {
+                    overrides.globals.insert(index, vm_global.from);
+                }
+                _ => unreachable!("a global is always exported as a global"),
+            }
+        }
+        Instance::new_with(
+            self.module,
+            resolver,
+            true,
+            &overrides,
+            self.keep_initial_globals,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::imports;
+
+    #[test]
+    fn restore_globals_rejects_dead_functions() {
+        let store = Store::default();
+        let module = Module::new(&store, r#"(module (func (export "f")))"#).unwrap();
+        let instance = Instance::new(&module, &imports! {}).unwrap();
+        let mut function = instance.exports.get_function("f").unwrap().clone();
+        function.into_weak_instance_ref();
+        drop(instance);
+
+        let module = Module::new(
+            &store,
+            r#"(module (global (export "ref") (mut funcref) (ref.null func)))"#,
+        )
+        .unwrap();
+        let instance = Instance::new(&module, &imports! {}).unwrap();
+        let snapshot = GlobalsSnapshot {
+            instance: Arc::downgrade(&instance.handle),
+            values: vec![(GlobalIndex::new(0), Val::FuncRef(Some(function)))],
+        };
+        assert!(instance.restore_globals(&snapshot).is_err());
+        let global = instance.exports.get_global("ref").unwrap();
+        assert!(global.get().unwrap_funcref().is_none());
+    }
+}
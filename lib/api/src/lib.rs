@@ -259,6 +259,8 @@ mod exports;
 mod externals;
 mod import_object;
 mod instance;
+mod limits;
+mod metrics;
 mod module;
 mod native;
 mod ptr;
@@ -283,21 +285,32 @@ pub mod internals {
 }
 
 pub use crate::cell::WasmCell;
-pub use crate::env::{HostEnvInitError, LazyInit, WasmerEnv};
+pub use crate::env::{HostEnvInitError, LazyInit, SharedEnv, WasmerEnv};
 pub use crate::exports::{ExportError, Exportable, Exports, ExportsIterator};
 pub use crate::externals::{
-    Extern, FromToNativeWasmType, Function, Global, HostFunction, Memory, Table, WasmTypeList,
+    Caller, Extern, FromToNativeWasmType, Function, Global, GrowObserverHandle, HostFunction,
+    IntegerExtension, Memory, MemoryAccessError, PreparedCall, Table, TableGrowBeyondMaximum,
+    WasmTypeList,
 };
-pub use crate::import_object::{ImportObject, ImportObjectIterator, LikeNamespace};
-pub use crate::instance::{Instance, InstantiationError};
-pub use crate::module::Module;
+pub use crate::import_object::{
+    ImportObject, ImportObjectIterator, LikeNamespace, UnresolvedImportsError,
+};
+pub use crate::instance::{
+    GlobalsSnapshot, Instance, InstanceBuilder, InstantiationError, InstantiationEvent,
+    ResolvedImport,
+};
+pub use crate::metrics::CallMetrics;
+pub use crate::module::{DataSegmentInfo, DataSegmentMode, Module, ModuleManifest, RecompileError};
 pub use crate::native::NativeFunc;
 pub use crate::ptr::{Array, Item, WasmPtr};
-pub use crate::store::{Store, StoreObject};
+pub use crate::store::{
+    InstantiationObserverFn, PanicHookFn, Store, StoreFrozen, StoreMemoryReport, StoreObject,
+    TrapObserverFn,
+};
 pub use crate::tunables::BaseTunables;
 pub use crate::types::{
-    ExportType, ExternType, FunctionType, GlobalType, ImportType, MemoryType, Mutability,
-    TableType, Val, ValType,
+    ExportType, ExternType, ExternTypeMismatch, FunctionType, FunctionTypeBuilder, GlobalType,
+    ImportType, MemoryType, Mutability, TableType, Val, ValType,
 };
 pub use crate::types::{Val as Value, ValType as Type};
 pub use crate::utils::is_wasm;
@@ -308,32 +321,38 @@ pub use wasmer_compiler::{
     ModuleMiddleware,
 };
 pub use wasmer_compiler::{
-    CompileError, CpuFeature, Features, ParseCpuFeatureError, Target, WasmError, WasmResult,
+    CompilationLimit, CompilationLimits, CompileError, CpuFeature, Features, ParseCpuFeatureError,
+    Target, WasmError, WasmProposal, WasmResult,
 };
 pub use wasmer_engine::{
-    ChainableNamedResolver, DeserializeError, Engine, Export, FrameInfo, LinkError, NamedResolver,
-    NamedResolverChain, Resolver, RuntimeError, SerializeError, Tunables,
+    ChainableNamedResolver, DeserializeError, Engine, Export, FrameInfo, ImportError, LinkError,
+    ModuleFootprint, NamedResolver, NamedResolverChain, Resolver, RuntimeError, SerializeError,
+    TrapInfo, Tunables,
 };
 #[cfg(feature = "experimental-reference-types-extern-ref")]
 pub use wasmer_types::ExternRef;
 pub use wasmer_types::{
-    Atomically, Bytes, ExportIndex, GlobalInit, LocalFunctionIndex, MemoryView, Pages, ValueType,
+    Atomically, Bytes, DataIndex, ExportIndex, FunctionIndex, GlobalIndex, GlobalInit,
+    LocalFunctionIndex, MemoryIndex, MemoryView, MemoryViewError, Pages, TableIndex, ValueType,
     WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
 };
 
 // TODO: should those be moved into wasmer::vm as well?
-pub use wasmer_vm::{raise_user_trap, MemoryError};
+pub use wasmer_vm::{
+    raise_user_trap, AtomicWaitResult, DataSegmentOutOfBounds, MemoryAccess, MemoryAccessKind,
+    MemoryError, TrapCode,
+};
 pub mod vm {
     //! The vm module re-exports wasmer-vm types.
 
     pub use wasmer_vm::{
-        Memory, MemoryError, MemoryStyle, Table, TableStyle, VMExtern, VMMemoryDefinition,
-        VMTableDefinition,
+        InstanceHandle, Memory, MemoryError, MemoryStyle, Table, TableStyle, Trap, VMContext,
+        VMExtern, VMFunctionBody, VMFunctionKind, VMMemoryDefinition, VMTableDefinition,
     };
 }
 
 #[cfg(feature = "wat")]
-pub use wat::parse_bytes as wat2wasm;
+pub use wat_parser::parse_bytes as wat2wasm;
 
 // The compilers are mutually exclusive
 #[cfg(any(
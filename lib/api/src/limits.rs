@@ -0,0 +1,419 @@
+//! Accounting of the instances, and of the memory pages of those
+//! instances, that are alive in a [`Store`], and of the memory they use.
+//!
+//! [`Store`]: crate::Store
+
+use crate::store::StoreMemoryReport;
+use crate::{GlobalType, MemoryType, Pages, TableType};
+use loupe::{MemoryUsage, MemoryUsageTracker};
+use std::cell::Cell;
+use std::collections::BTreeSet;
+use std::mem;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use wasmer_engine::{Artifact, Tunables};
+use wasmer_vm::{
+    Global, Memory, MemoryAccessLogFn, MemoryError, MemoryGrowObserverFn, MemoryStyle, Table,
+    TableStyle, VMFuncRef, VMGlobalDefinition, VMMemoryDefinition, VMTableDefinition,
+};
+
+/// The limits configured on a store, and the resources currently
+/// counted against them.
+///
+/// A limit of `usize::MAX` means there's no limit.
+#[derive(Debug)]
+pub(crate) struct ResourceLimiter {
+    max_instances: AtomicUsize,
+    instances: AtomicUsize,
+    max_memory_pages: AtomicUsize,
+    memory_pages: AtomicUsize,
+    /// The resources of the instances, dead ones included until the
+    /// next reservation or report.
+    resources: Mutex<Vec<Weak<InstanceResources>>>,
+}
+
+impl ResourceLimiter {
+    pub(crate) fn new() -> Self {
+        Self {
+            max_instances: AtomicUsize::new(usize::MAX),
+            instances: AtomicUsize::new(0),
+            max_memory_pages: AtomicUsize::new(usize::MAX),
+            memory_pages: AtomicUsize::new(0),
+            resources: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn set_max_instances(&self, max: Option<usize>) {
+        self.max_instances
+            .store(max.unwrap_or(usize::MAX), Ordering::SeqCst);
+    }
+
+    pub(crate) fn max_instances(&self) -> Option<usize> {
+        match self.max_instances.load(Ordering::SeqCst) {
+            usize::MAX => None,
+            max => Some(max),
+        }
+    }
+
+    pub(crate) fn instances(&self) -> usize {
+        self.instances.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn set_max_memory_pages(&self, max: Option<Pages>) {
+        self.max_memory_pages.store(
+            max.map_or(usize::MAX, |pages| pages.0 as usize),
+            Ordering::SeqCst,
+        );
+    }
+
+    pub(crate) fn max_memory_pages(&self) -> Option<Pages> {
+        match self.max_memory_pages.load(Ordering::SeqCst) {
+            usize::MAX => None,
+            max => Some(Pages(max as u32)),
+        }
+    }
+
+    pub(crate) fn memory_pages(&self) -> Pages {
+        Pages(self.memory_pages.load(Ordering::SeqCst) as u32)
+    }
+
+    /// Counts one more instance of `artifact`, unless that would exceed
+    /// the limit.
+    ///
+    /// The instance is counted, and its resources reported, until the
+    /// returned reservation is dropped.
+    pub(crate) fn reserve_instance(
+        self: &Arc<Self>,
+        artifact: Arc<dyn Artifact>,
+    ) -> Result<InstanceReservation, String> {
+        let max = self.max_instances.load(Ordering::SeqCst);
+        Self::reserve(&self.instances, max, 1).map_err(|live| {
+            format!(
+                "the store already has {} live instances, the maximum is {}",
+                live, max
+            )
+        })?;
+        let resources = Arc::new(InstanceResources {
+            artifact,
+            memories: Mutex::new(Vec::new()),
+            tables: Mutex::new(Vec::new()),
+            globals: Mutex::new(Vec::new()),
+        });
+        let mut all_resources = self.resources.lock().unwrap();
+        all_resources.retain(|resources| resources.strong_count() > 0);
+        all_resources.push(Arc::downgrade(&resources));
+        Ok(InstanceReservation {
+            limiter: self.clone(),
+            resources,
+        })
+    }
+
+    /// Totals the memory used by the instances alive right now.
+    pub(crate) fn memory_report(&self) -> StoreMemoryReport {
+        let live_resources = {
+            let mut all_resources = self.resources.lock().unwrap();
+            all_resources.retain(|resources| resources.strong_count() > 0);
+            all_resources
+                .iter()
+                .filter_map(Weak::upgrade)
+                .collect::<Vec<_>>()
+        };
+        let mut report = StoreMemoryReport::default();
+        // Instances of the same module share its code.
+        let mut artifacts = BTreeSet::new();
+        for resources in live_resources.iter() {
+            report.instances += 1;
+            report.memory_bytes += live(&resources.memories)
+                .iter()
+                .map(|memory| memory.size().bytes().0)
+                .sum::<usize>();
+            report.table_bytes += live(&resources.tables)
+                .iter()
+                .map(|table| table.size() as usize * mem::size_of::<VMFuncRef>())
+                .sum::<usize>();
+            report.global_bytes +=
+                live(&resources.globals).len() * mem::size_of::<VMGlobalDefinition>();
+            if artifacts.insert(Arc::as_ptr(&resources.artifact) as *const ()) {
+                report.code_bytes += resources.artifact.memory_footprint().code_bytes;
+            }
+        }
+        report
+    }
+
+    /// Counts `pages` more memory pages, unless that would exceed the
+    /// limit.
+    fn reserve_memory_pages(&self, pages: usize) -> Result<(), MemoryError> {
+        let max = self.max_memory_pages.load(Ordering::SeqCst);
+        Self::reserve(&self.memory_pages, max, pages).map_err(|committed| {
+            MemoryError::Generic(format!(
+                "the instances of the store already have {} memory pages, \
+                 {} more would exceed the maximum of {}",
+                committed, pages, max
+            ))
+        })
+    }
+
+    fn release_memory_pages(&self, pages: usize) {
+        self.memory_pages.fetch_sub(pages, Ordering::SeqCst);
+    }
+
+    /// Adds `amount` to `counter` if the result doesn't exceed `max`.
+    /// Otherwise, returns the current value of the counter.
+    fn reserve(counter: &AtomicUsize, max: usize, amount: usize) -> Result<(), usize> {
+        counter
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                current.checked_add(amount).filter(|&new| new <= max)
+            })
+            .map(|_| ())
+    }
+}
+
+/// An instance counted by a [`ResourceLimiter`], released on drop.
+///
+/// It is kept in the host state of the instance, so the instance is
+/// counted for as long as the VM keeps it alive.
+pub(crate) struct InstanceReservation {
+    limiter: Arc<ResourceLimiter>,
+    resources: Arc<InstanceResources>,
+}
+
+impl InstanceReservation {
+    /// Returns the resources of the instance, to be filled while it is
+    /// created.
+    pub(crate) fn resources(&self) -> &Arc<InstanceResources> {
+        &self.resources
+    }
+}
+
+impl Drop for InstanceReservation {
+    fn drop(&mut self) {
+        self.limiter.instances.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// The artifact of an instance, and the memories, tables and globals
+/// created for it, as reported by [`ResourceLimiter::memory_report`].
+///
+/// The resources are only referenced weakly, so that reporting them
+/// doesn't change when they are freed.
+pub(crate) struct InstanceResources {
+    artifact: Arc<dyn Artifact>,
+    memories: Mutex<Vec<Weak<dyn Memory>>>,
+    tables: Mutex<Vec<Weak<dyn Table>>>,
+    globals: Mutex<Vec<Weak<Global>>>,
+}
+
+/// Returns the resources of `resources` that are still alive.
+fn live<T: ?Sized>(resources: &Mutex<Vec<Weak<T>>>) -> Vec<Arc<T>> {
+    resources
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect()
+}
+
+/// The [`Tunables`] used to instantiate a module in a store with
+/// limits: the memories of the instance are created by the store's
+/// tunables, and their pages counted against the limit. The memories,
+/// tables and globals are recorded in the resources of the instance.
+pub(crate) struct LimitingTunables<'a> {
+    tunables: &'a dyn Tunables,
+    limiter: &'a Arc<ResourceLimiter>,
+    resources: Arc<InstanceResources>,
+    exceeded: Cell<Option<String>>,
+}
+
+impl<'a> LimitingTunables<'a> {
+    pub(crate) fn new(
+        tunables: &'a dyn Tunables,
+        limiter: &'a Arc<ResourceLimiter>,
+        resources: Arc<InstanceResources>,
+    ) -> Self {
+        Self {
+            tunables,
+            limiter,
+            resources,
+            exceeded: Cell::new(None),
+        }
+    }
+
+    /// Returns why creating a memory failed, if it was because of the
+    /// limit.
+    pub(crate) fn exceeded(&self) -> Option<String> {
+        self.exceeded.take()
+    }
+}
+
+impl MemoryUsage for LimitingTunables<'_> {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        mem::size_of_val(self) + self.tunables.size_of_val(tracker)
+    }
+}
+
+impl Tunables for LimitingTunables<'_> {
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.tunables.memory_style(memory)
+    }
+
+    fn static_memory_guard_size(&self, memory: &MemoryType) -> u64 {
+        self.tunables.static_memory_guard_size(memory)
+    }
+
+    fn dynamic_memory_guard_size(&self, memory: &MemoryType) -> u64 {
+        self.tunables.dynamic_memory_guard_size(memory)
+    }
+
+    fn table_style(&self, table: &TableType) -> TableStyle {
+        self.tunables.table_style(table)
+    }
+
+    fn create_host_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        self.tunables.create_host_memory(ty, style)
+    }
+
+    unsafe fn create_vm_memory(
+        &self,
+        ty: &MemoryType,
+        style: &MemoryStyle,
+        vm_definition_location: NonNull<VMMemoryDefinition>,
+    ) -> Result<Arc<dyn Memory>, MemoryError> {
+        let pages = ty.minimum.0 as usize;
+        if let Err(error) = self.limiter.reserve_memory_pages(pages) {
+            self.exceeded.set(Some(error.to_string()));
+            return Err(error);
+        }
+        match self
+            .tunables
+            .create_vm_memory(ty, style, vm_definition_location)
+        {
+            Ok(memory) => {
+                let memory: Arc<dyn Memory> = Arc::new(LimitedMemory {
+                    memory,
+                    limiter: self.limiter.clone(),
+                    pages: AtomicUsize::new(pages),
+                });
+                let weak = Arc::downgrade(&memory);
+                self.resources.memories.lock().unwrap().push(weak);
+                Ok(memory)
+            }
+            Err(error) => {
+                self.limiter.release_memory_pages(pages);
+                Err(error)
+            }
+        }
+    }
+
+    fn create_host_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+    ) -> Result<Arc<dyn Table>, String> {
+        self.tunables.create_host_table(ty, style)
+    }
+
+    unsafe fn create_vm_table(
+        &self,
+        ty: &TableType,
+        style: &TableStyle,
+        vm_definition_location: NonNull<VMTableDefinition>,
+    ) -> Result<Arc<dyn Table>, String> {
+        let table = self
+            .tunables
+            .create_vm_table(ty, style, vm_definition_location)?;
+        let weak = Arc::downgrade(&table);
+        self.resources.tables.lock().unwrap().push(weak);
+        Ok(table)
+    }
+
+    fn create_global(&self, ty: GlobalType) -> Result<Arc<Global>, String> {
+        let global = self.tunables.create_global(ty)?;
+        let weak = Arc::downgrade(&global);
+        self.resources.globals.lock().unwrap().push(weak);
+        Ok(global)
+    }
+}
+
+/// A memory whose pages, including the ones it grows by, are counted
+/// by a [`ResourceLimiter`] until it is dropped.
+#[derive(Debug)]
+struct LimitedMemory {
+    memory: Arc<dyn Memory>,
+    limiter: Arc<ResourceLimiter>,
+    pages: AtomicUsize,
+}
+
+impl MemoryUsage for LimitedMemory {
+    fn size_of_val(&self, tracker: &mut dyn MemoryUsageTracker) -> usize {
+        mem::size_of_val(self) + self.memory.size_of_val(tracker)
+    }
+}
+
+impl Memory for LimitedMemory {
+    fn ty(&self) -> MemoryType {
+        self.memory.ty()
+    }
+
+    fn style(&self) -> &MemoryStyle {
+        self.memory.style()
+    }
+
+    fn is_shared(&self) -> bool {
+        self.memory.is_shared()
+    }
+
+    fn size(&self) -> Pages {
+        self.memory.size()
+    }
+
+    fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let pages = delta.0 as usize;
+        self.limiter.reserve_memory_pages(pages)?;
+        match self.memory.grow(delta) {
+            Ok(previous) => {
+                self.pages.fetch_add(pages, Ordering::SeqCst);
+                Ok(previous)
+            }
+            Err(error) => {
+                self.limiter.release_memory_pages(pages);
+                Err(error)
+            }
+        }
+    }
+
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        self.memory.vmmemory()
+    }
+
+    fn zero(&self) -> Result<(), MemoryError> {
+        self.memory.zero()
+    }
+
+    fn add_grow_observer(&self, observer: Box<MemoryGrowObserverFn>) -> Result<u64, MemoryError> {
+        self.memory.add_grow_observer(observer)
+    }
+
+    fn remove_grow_observer(&self, id: u64) {
+        self.memory.remove_grow_observer(id)
+    }
+
+    fn set_access_log(&self, log: Option<Arc<MemoryAccessLogFn>>) -> Result<(), MemoryError> {
+        self.memory.set_access_log(log)
+    }
+
+    fn access_log(&self) -> Option<Arc<MemoryAccessLogFn>> {
+        self.memory.access_log()
+    }
+}
+
+impl Drop for LimitedMemory {
+    fn drop(&mut self) {
+        self.limiter
+            .release_memory_pages(self.pages.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,90 @@
+//! Opt-in timing of calls into WebAssembly.
+//!
+//! See [`Function::call_with_metrics`](crate::Function::call_with_metrics).
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+/// Timing measurements of a single call made with
+/// [`Function::call_with_metrics`](crate::Function::call_with_metrics).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CallMetrics {
+    /// Total wall time spent in the call, host imports included.
+    pub wall_time: Duration,
+    /// Wall time spent inside host imports called during the call.
+    ///
+    /// Host imports reentering WebAssembly which in turn call other
+    /// host imports are only counted once, by the outermost import.
+    pub host_import_time: Duration,
+}
+
+/// The accumulator of the call being measured on the current thread.
+#[derive(Default)]
+struct Tracker {
+    host_import_time: Duration,
+    /// Number of host imports currently on the stack.
+    depth: u32,
+    /// When the outermost host import on the stack was entered.
+    entered: Option<Instant>,
+}
+
+thread_local! {
+    static TRACKER: RefCell<Option<Tracker>> = RefCell::new(None);
+}
+
+/// Runs `f` while accumulating the time spent in host imports, and
+/// returns its result along with the measured metrics.
+///
+/// Measurements nest: the tracker of an enclosing measured call is set
+/// aside while `f` runs, and restored afterwards.
+pub(crate) fn measure<T>(f: impl FnOnce() -> T) -> (T, CallMetrics) {
+    let previous = TRACKER.with(|tracker| tracker.replace(Some(Tracker::default())));
+    let start = Instant::now();
+    let result = f();
+    let wall_time = start.elapsed();
+    let tracker = TRACKER.with(|tracker| tracker.replace(previous));
+    let metrics = CallMetrics {
+        wall_time,
+        host_import_time: tracker.map(|t| t.host_import_time).unwrap_or_default(),
+    };
+    (result, metrics)
+}
+
+/// Marks the current thread as running a host import until dropped.
+///
+/// This is a no-op when no call is being measured.
+pub(crate) struct HostCallGuard(());
+
+impl HostCallGuard {
+    pub(crate) fn enter() -> Self {
+        TRACKER.with(|tracker| {
+            if let Some(tracker) = tracker.borrow_mut().as_mut() {
+                if tracker.depth == 0 {
+                    tracker.entered = Some(Instant::now());
+                }
+                tracker.depth += 1;
+            }
+        });
+        Self(())
+    }
+}
+
+impl Drop for HostCallGuard {
+    fn drop(&mut self) {
+        TRACKER.with(|tracker| {
+            if let Some(tracker) = tracker.borrow_mut().as_mut() {
+                // The tracker may have been installed while this import
+                // was running, by a measured call made from the host.
+                if tracker.depth == 0 {
+                    return;
+                }
+                tracker.depth -= 1;
+                if tracker.depth == 0 {
+                    if let Some(entered) = tracker.entered.take() {
+                        tracker.host_import_time += entered.elapsed();
+                    }
+                }
+            }
+        });
+    }
+}
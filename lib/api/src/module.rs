@@ -1,16 +1,27 @@
+use crate::limits::LimitingTunables;
 use crate::store::Store;
-use crate::types::{ExportType, ImportType};
-use crate::InstantiationError;
+use crate::types::{ExportType, FunctionType, GlobalType, ImportType, MemoryType, TableType, Val};
+use crate::{FunctionIndex, LocalFunctionIndex, MemoryIndex};
+use crate::{Instance, InstantiationError, RuntimeError};
 use loupe::MemoryUsage;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::borrow::Cow;
 use std::fmt;
-use std::io;
+use std::io::{self, Read, Write};
+#[cfg(feature = "compiler")]
+use std::ops::Range;
 use std::path::Path;
 use std::sync::Arc;
 use thiserror::Error;
-use wasmer_compiler::CompileError;
 #[cfg(feature = "wat")]
 use wasmer_compiler::WasmError;
-use wasmer_engine::{Artifact, DeserializeError, Resolver, SerializeError};
+use wasmer_compiler::{CompileError, Features, WasmProposal};
+use wasmer_engine::{
+    Artifact, DeserializeError, InstanceOverrides, ModuleFootprint, Resolver, SerializeError,
+};
+use wasmer_types::entity::EntityRef;
+use wasmer_types::DataIndex;
 use wasmer_vm::{ExportsIterator, ImportsIterator, InstanceHandle, ModuleInfo};
 
 #[derive(Error, Debug)]
@@ -23,6 +34,76 @@ pub enum IoCompileError {
     Compile(#[from] CompileError),
 }
 
+/// The error returned by [`Module::recompile`].
+#[derive(Error, Debug)]
+pub enum RecompileError {
+    /// The module doesn't retain its Wasm binary, it has to be created
+    /// with [`Module::new_retaining_bytes`] to be recompiled.
+    #[error(
+        "the module doesn't retain its Wasm binary, create it with \
+         `Module::new_retaining_bytes` to recompile it"
+    )]
+    BytesNotRetained,
+    /// The Wasm binary can't be compiled for the new store.
+    #[error(transparent)]
+    Compile(#[from] CompileError),
+}
+
+/// A data segment of a [`Module`], as returned by
+/// [`Module::data_segments`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataSegmentInfo {
+    /// Whether the segment is active or passive.
+    pub mode: DataSegmentMode,
+    /// The length of the segment, in bytes.
+    pub len: usize,
+}
+
+/// How a data segment is copied into a memory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataSegmentMode {
+    /// The segment is copied into a memory when the module is
+    /// instantiated.
+    Active {
+        /// The memory the segment is copied into.
+        memory_index: MemoryIndex,
+        /// The offset the segment is copied at, or `None` if it is
+        /// given by a `global.get`, whose value isn't known before
+        /// instantiation.
+        offset: Option<usize>,
+    },
+    /// The segment is only copied by `memory.init` instructions.
+    Passive,
+}
+
+/// A summary of the interface of a [`Module`], as returned by
+/// [`Module::manifest`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModuleManifest {
+    /// The name of the module, if it has one.
+    pub name: Option<String>,
+    /// The features the module was compiled with.
+    pub features: Features,
+    /// The features of the proposals the module uses, see
+    /// [`Module::required_proposals`].
+    pub used_features: Features,
+    /// The imports of the module, in order.
+    pub imports: Vec<ImportType>,
+    /// The exports of the module, in order.
+    pub exports: Vec<ExportType>,
+    /// The memories the module defines, imported ones excluded.
+    pub memories: Vec<MemoryType>,
+    /// The tables the module defines, imported ones excluded.
+    pub tables: Vec<TableType>,
+    /// The globals the module defines, imported ones excluded.
+    pub globals: Vec<GlobalType>,
+    /// Whether the module has a start function.
+    pub has_start_function: bool,
+    /// The names of the custom sections of the module, in order.
+    #[serde(default)]
+    pub custom_sections: Vec<String>,
+}
+
 /// A WebAssembly Module contains stateless WebAssembly
 /// code that has already been compiled and can be instantiated
 /// multiple times.
@@ -35,6 +116,12 @@ pub enum IoCompileError {
 pub struct Module {
     store: Store,
     artifact: Arc<dyn Artifact>,
+    wasm_bytes: Option<Arc<[u8]>>,
+    /// The ranges of the bodies of the functions in `wasm_bytes`, by
+    /// local index, recorded when the binary is retained.
+    #[cfg(feature = "compiler")]
+    #[loupe(skip)]
+    function_bodies: Arc<[Range<usize>]>,
 }
 
 impl Module {
@@ -97,17 +184,180 @@ impl Module {
     /// # Ok(())
     /// # }
     /// ```
-    #[allow(unreachable_code)]
     pub fn new(store: &Store, bytes: impl AsRef<[u8]>) -> Result<Self, CompileError> {
+        let bytes = Self::to_binary(bytes.as_ref())?;
+        Self::from_binary(store, &bytes)
+    }
+
+    /// Creates a new WebAssembly Module like [`Module::new`], but keeps
+    /// a copy of the Wasm binary around so it can be retrieved with
+    /// [`Module::wasm_bytes`].
+    ///
+    /// If the text format was provided, the retained bytes are the
+    /// binary it was converted to.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let bytes = wat2wasm(b"(module)")?;
+    /// let module = Module::new_retaining_bytes(&store, &bytes)?;
+    /// assert_eq!(module.wasm_bytes(), Some(&bytes[..]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new_retaining_bytes(
+        store: &Store,
+        bytes: impl AsRef<[u8]>,
+    ) -> Result<Self, CompileError> {
+        let bytes = Self::to_binary(bytes.as_ref())?;
+        let mut module = Self::from_binary(store, &bytes)?;
+        #[cfg(feature = "compiler")]
+        {
+            module.function_bodies = function_body_ranges(&bytes);
+        }
+        module.wasm_bytes = Some(bytes.into());
+        Ok(module)
+    }
+
+    /// Creates a new WebAssembly Module like [`Module::new`], but only
+    /// compiles the functions reachable from the exported functions named
+    /// in `roots`.
+    ///
+    /// Every other function is replaced by a stub that traps with
+    /// [`TrapCode::FunctionPruned`] when called, which makes large modules
+    /// of which only a few exports are used faster to compile and smaller
+    /// to serialize. Functions referenced by element segments or global
+    /// initializers, and the start function, are always kept. The pruned
+    /// functions are listed in the [`PRUNED_FUNCTIONS_SECTION`] custom
+    /// section of the module.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module
+    ///     (func (export "kept") (result i32) i32.const 42)
+    ///     (func (export "pruned") (result i32) i32.const 0))"#;
+    /// let module = Module::new_with_roots(&store, wat, &["kept"])?;
+    /// let instance = Instance::new(&module, &imports! {})?;
+    ///
+    /// let pruned = instance.exports.get_function("pruned")?;
+    /// assert_eq!(pruned.call(&[]).unwrap_err().to_trap(), Some(TrapCode::FunctionPruned));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Returns an error if the module is invalid, or if a root isn't the
+    /// name of an exported function.
+    ///
+    /// [`TrapCode::FunctionPruned`]: crate::TrapCode::FunctionPruned
+    /// [`PRUNED_FUNCTIONS_SECTION`]: wasmer_compiler::PRUNED_FUNCTIONS_SECTION
+    #[cfg(feature = "compiler")]
+    pub fn new_with_roots(
+        store: &Store,
+        bytes: impl AsRef<[u8]>,
+        roots: &[&str],
+    ) -> Result<Self, CompileError> {
+        let bytes = Self::to_binary(bytes.as_ref())?;
+        Self::validate(store, &bytes)?;
+        let pruned = wasmer_compiler::prune_functions(&bytes, roots)?;
+        // Safety: replacing function bodies by stubs keeps the module valid.
+        unsafe { Self::from_binary_unchecked(store, &pruned) }
+    }
+
+    /// Compiles this module again for `store`, which can use another
+    /// engine or compiler than the store of this module.
+    ///
+    /// The module is compiled from the Wasm binary it retains, so it must
+    /// have been created with [`Module::new_retaining_bytes`]. Modules
+    /// created any other way, including with [`Module::new`] and by
+    /// deserializing an artifact, don't keep the function bodies needed
+    /// to generate code again. The recompiled module retains the binary
+    /// too, and keeps the name of this module.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// # let other_store = Store::default();
+    /// let module = Module::new_retaining_bytes(&store, "(module)")?;
+    /// let recompiled = module.recompile(&other_store)?;
+    /// assert!(Store::same(recompiled.store(), &other_store));
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// ## Errors
+    ///
+    /// Returns [`RecompileError::BytesNotRetained`] if the module doesn't
+    /// retain its Wasm binary, and [`RecompileError::Compile`] if the
+    /// binary can't be compiled for `store`.
+    pub fn recompile(&self, store: &Store) -> Result<Self, RecompileError> {
+        let bytes = self
+            .wasm_bytes
+            .as_ref()
+            .ok_or(RecompileError::BytesNotRetained)?;
+        let mut module = Self::from_binary(store, bytes)?;
+        module.wasm_bytes = Some(bytes.clone());
+        #[cfg(feature = "compiler")]
+        {
+            module.function_bodies = self.function_bodies.clone();
+        }
+        if let Some(name) = self.name() {
+            module.set_name(name);
+        }
+        Ok(module)
+    }
+
+    /// Converts the text format to a Wasm binary, if the "wat" feature
+    /// is enabled for this crate.
+    #[allow(clippy::unnecessary_wraps)]
+    fn to_binary(bytes: &[u8]) -> Result<Cow<[u8]>, CompileError> {
         #[cfg(feature = "wat")]
-        let bytes = wat::parse_bytes(bytes.as_ref()).map_err(|e| {
-            CompileError::Wasm(WasmError::Generic(format!(
-                "Error when converting wat: {}",
-                e
-            )))
-        })?;
+        {
+            if !bytes.starts_with(b"\0asm") {
+                let text = std::str::from_utf8(bytes).map_err(|e| {
+                    CompileError::Wasm(WasmError::Generic(format!(
+                        "Error when converting wat: input bytes aren't valid utf-8: {}",
+                        e
+                    )))
+                })?;
+                let bytes = Self::parse_wat(text).map_err(CompileError::Wasm)?;
+                return Ok(Cow::Owned(bytes));
+            }
+        }
 
-        Self::from_binary(store, bytes.as_ref())
+        Ok(Cow::Borrowed(bytes))
+    }
+
+    /// Parses the text format, keeping the position of syntax errors.
+    ///
+    /// This goes through `wast` rather than `wat`, whose errors only
+    /// expose the position through their rendering.
+    #[cfg(feature = "wat")]
+    fn parse_wat(text: &str) -> Result<Vec<u8>, WasmError> {
+        let parse = || {
+            let buffer = wast::parser::ParseBuffer::new(text)?;
+            let mut wat = wast::parser::parse::<wast::Wat>(&buffer)?;
+            wat.module.encode()
+        };
+        parse().map_err(|error: wast::Error| {
+            let (line, column) = error.span().linecol_in(text);
+            WasmError::Parse {
+                message: error.message(),
+                line: line + 1,
+                column: column + 1,
+            }
+        })
     }
 
     /// Creates a new WebAssembly module from a file path.
@@ -123,6 +373,35 @@ impl Module {
         Ok(module)
     }
 
+    /// Creates a new WebAssembly module from the Wasm binary or, if the
+    /// "wat" feature is enabled for this crate, the text format read from
+    /// `reader`.
+    ///
+    /// The input is a binary if it starts with the `\0asm` magic number.
+    /// Anything else, including inputs shorter than the magic number, is
+    /// parsed as text.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use std::io::Cursor;
+    /// use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::from_reader(&store, Cursor::new("(module)"))?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_reader(store: &Store, mut reader: impl Read) -> Result<Self, IoCompileError> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if bytes.starts_with(b"\0asm") {
+            Ok(Self::from_binary(store, &bytes)?)
+        } else {
+            Ok(Self::new(store, &bytes)?)
+        }
+    }
+
     /// Creates a new WebAssembly module from a binary.
     ///
     /// Opposed to [`Module::new`], this function is not compatible with
@@ -158,6 +437,38 @@ impl Module {
         store.engine().validate(binary)
     }
 
+    /// Validates a new WebAssembly Module against an explicit set of
+    /// `features`, on top of the configuration in the Store.
+    ///
+    /// This is useful to reject modules using proposals that the
+    /// Store Engine supports but that are not allowed by a given
+    /// policy. The error names the feature that is disabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module (func (param v128)))"#;
+    /// let mut features = Features::new();
+    /// features.simd(false);
+    /// let error = Module::validate_with_features(&store, &wat2wasm(wat.as_bytes())?, &features)
+    ///     .unwrap_err();
+    /// assert!(error.to_string().contains("SIMD"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "compiler")]
+    pub fn validate_with_features(
+        store: &Store,
+        binary: &[u8],
+        features: &Features,
+    ) -> Result<(), CompileError> {
+        wasmer_compiler::validate_module(features, binary)?;
+        Self::validate(store, binary)
+    }
+
     fn compile(store: &Store, binary: &[u8]) -> Result<Self, CompileError> {
         let artifact = store.engine().compile(binary, store.tunables())?;
         Ok(Self::from_artifact(store, artifact))
@@ -199,6 +510,37 @@ impl Module {
         self.artifact.serialize_to_file(path.as_ref())
     }
 
+    /// Serializes a module into `writer`, in the same format as
+    /// [`Module::serialize`].
+    ///
+    /// The errors of the writer are returned as [`SerializeError::Io`],
+    /// which lets callers tell them apart from a module that can't be
+    /// serialized.
+    pub fn serialize_to_writer(&self, mut writer: impl Write) -> Result<(), SerializeError> {
+        let serialized = self.artifact.serialize()?;
+        writer.write_all(&serialized)?;
+        Ok(())
+    }
+
+    /// Serializes a module into `buffer`, in the same format as
+    /// [`Module::serialize`], and returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`SerializeError::Capacity`], leaving `buffer` untouched,
+    /// if it is too small for the serialized module.
+    pub fn serialize_into(&self, buffer: &mut [u8]) -> Result<usize, SerializeError> {
+        let serialized = self.artifact.serialize()?;
+        if serialized.len() > buffer.len() {
+            return Err(SerializeError::Capacity {
+                required: serialized.len(),
+                available: buffer.len(),
+            });
+        }
+        buffer[..serialized.len()].copy_from_slice(&serialized);
+        Ok(serialized.len())
+    }
+
     /// Deserializes a serialized Module binary into a `Module`.
     /// > Note: the module has to be serialized before with the `serialize` method.
     ///
@@ -256,27 +598,103 @@ impl Module {
         Self {
             store: store.clone(),
             artifact,
+            wasm_bytes: None,
+            #[cfg(feature = "compiler")]
+            function_bodies: Arc::new([]),
         }
     }
 
+    /// Instantiates the module with the imports of `resolver` and calls
+    /// its exported function `name` with `params`.
+    ///
+    /// This is a shortcut for creating an [`Instance`] and calling one
+    /// of its exports. The instance is created anew on every call, and
+    /// dropped once the function returns.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module
+    ///     (func (export "add") (param i32 i32) (result i32)
+    ///         (i32.add (local.get 0) (local.get 1))))"#;
+    /// let module = Module::new(&store, wat)?;
+    /// let results = module.call_export(&imports! {}, "add", &[Val::I32(1), Val::I32(2)])?;
+    /// assert_eq!(results.to_vec(), vec![Val::I32(3)]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Besides the errors of the call itself, an error is returned if
+    /// the instantiation fails, or if the module doesn't export a
+    /// function named `name`.
+    pub fn call_export(
+        &self,
+        resolver: &dyn Resolver,
+        name: &str,
+        params: &[Val],
+    ) -> Result<Box<[Val]>, RuntimeError> {
+        let instance = Instance::new(self, resolver).map_err(|error| match error {
+            InstantiationError::Start(error) => error,
+            error => RuntimeError::new(format!("failed to instantiate the module: {}", error)),
+        })?;
+        let function = instance.exports.get_function(name).map_err(|error| {
+            RuntimeError::new(format!("cannot call export `{}`: {}", name, error))
+        })?;
+        function.call(params)
+    }
+
+    /// Instantiates the module, leaving the start function to the
+    /// caller unless `run_start` is set.
     pub(crate) fn instantiate(
         &self,
         resolver: &dyn Resolver,
+        run_start: bool,
+        overrides: &InstanceOverrides,
     ) -> Result<InstanceHandle, InstantiationError> {
+        let limiter = self.store.limiter();
+        let reservation = limiter
+            .reserve_instance(self.artifact.clone())
+            .map_err(InstantiationError::ResourceLimitExceeded)?;
+        let tunables = LimitingTunables::new(
+            self.store.tunables(),
+            limiter,
+            reservation.resources().clone(),
+        );
+        let host_state: Box<dyn Any> =
+            Box::new((self.store.clone(), self.artifact.clone(), reservation));
         unsafe {
-            let instance_handle = self.artifact.instantiate(
-                self.store.tunables(),
-                resolver,
-                Box::new((self.store.clone(), self.artifact.clone())),
-            )?;
+            let host_state = Box::new(move |_: &InstanceHandle| host_state);
+            // Without backtraces, only what is needed to recognize traps
+            // is registered, the rest only symbolicates their frames.
+            let instantiated = if self.store.capture_backtraces() {
+                self.artifact
+                    .instantiate_with_overrides(&tunables, resolver, host_state, overrides)
+            } else {
+                self.artifact
+                    .instantiate_with_trap_info(&tunables, resolver, host_state, overrides)
+            };
+            let instance_handle = instantiated.map_err(|error| match tunables.exceeded() {
+                Some(reason) => InstantiationError::ResourceLimitExceeded(reason),
+                None => error.into(),
+            })?;
 
             // After the instance handle is created, we need to initialize
             // the data, call the start function and so. However, if any
             // of this steps traps, we still need to keep the instance alive
             // as some of the Instance elements may have placed in other
             // instance tables.
-            self.artifact
-                .finish_instantiation(&self.store, &instance_handle)?;
+            if run_start {
+                self.artifact
+                    .finish_instantiation(&self.store, &instance_handle)?;
+            } else {
+                self.artifact
+                    .finish_instantiation_without_start(&instance_handle)?;
+            }
 
             Ok(instance_handle)
         }
@@ -334,6 +752,235 @@ impl Module {
             .unwrap_or(false)
     }
 
+    /// Returns the name of a function, as recorded in the `name`
+    /// custom section of the module.
+    ///
+    /// These names are also the ones shown in the trace of a
+    /// [`RuntimeError`](crate::RuntimeError).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = "(module (func $answer (result i32) i32.const 42))";
+    /// let module = Module::new(&store, wat)?;
+    /// assert_eq!(module.function_name(FunctionIndex::from_u32(0)), Some("answer"));
+    /// assert_eq!(module.function_name(FunctionIndex::from_u32(1)), None);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn function_name(&self, index: FunctionIndex) -> Option<&str> {
+        self.artifact
+            .module_ref()
+            .function_names
+            .get(&index)
+            .map(String::as_str)
+    }
+
+    /// Returns the index of the start function of the module, or `None`
+    /// if the module has no start section.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = "(module (func $init) (start $init))";
+    /// let module = Module::new(&store, wat)?;
+    /// assert_eq!(module.start_function(), Some(FunctionIndex::from_u32(0)));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn start_function(&self) -> Option<FunctionIndex> {
+        self.artifact.module_ref().start_function
+    }
+
+    /// Returns the number of imports of the module.
+    ///
+    /// This is the length of [`Module::imports`], without iterating.
+    pub fn num_imports(&self) -> usize {
+        self.artifact.module_ref().imports.len()
+    }
+
+    /// Returns the number of functions imported by the module.
+    pub fn num_imported_functions(&self) -> usize {
+        self.artifact.module_ref().num_imported_functions
+    }
+
+    /// Returns the number of functions defined by the module, imported
+    /// ones excluded.
+    pub fn num_local_functions(&self) -> usize {
+        let module_info = self.artifact.module_ref();
+        module_info.functions.len() - module_info.num_imported_functions
+    }
+
+    /// Converts the index of a function in the module to its index
+    /// among the functions the module defines.
+    ///
+    /// The imported functions come first in the function index space,
+    /// followed by the local ones. This returns `None` for an imported
+    /// function, or if `index` is past the last function.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module (import "env" "f" (func)) (func))"#;
+    /// let module = Module::new(&store, wat)?;
+    /// assert_eq!(module.local_function_index(FunctionIndex::from_u32(0)), None);
+    /// assert_eq!(
+    ///     module.local_function_index(FunctionIndex::from_u32(1)),
+    ///     Some(LocalFunctionIndex::from_u32(0))
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn local_function_index(&self, index: FunctionIndex) -> Option<LocalFunctionIndex> {
+        let module_info = self.artifact.module_ref();
+        if index.index() >= module_info.functions.len() {
+            return None;
+        }
+        module_info.local_func_index(index)
+    }
+
+    /// Returns the number of tables imported by the module.
+    pub fn num_imported_tables(&self) -> usize {
+        self.artifact.module_ref().num_imported_tables
+    }
+
+    /// Returns the number of memories imported by the module.
+    pub fn num_imported_memories(&self) -> usize {
+        self.artifact.module_ref().num_imported_memories
+    }
+
+    /// Returns the number of globals imported by the module.
+    pub fn num_imported_globals(&self) -> usize {
+        self.artifact.module_ref().num_imported_globals
+    }
+
+    /// Returns the number of exports of the module.
+    ///
+    /// This is the length of [`Module::exports`], without iterating.
+    pub fn num_exports(&self) -> usize {
+        self.artifact.module_ref().exports.len()
+    }
+
+    /// Returns the data segments of the module, in the order they are
+    /// declared in the WebAssembly bytecode.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module (memory 1) (data (i32.const 16) "hello"))"#;
+    /// let module = Module::new(&store, wat)?;
+    /// assert_eq!(
+    ///     module.data_segments(),
+    ///     vec![DataSegmentInfo {
+    ///         mode: DataSegmentMode::Active {
+    ///             memory_index: MemoryIndex::from_u32(0),
+    ///             offset: Some(16),
+    ///         },
+    ///         len: 5,
+    ///     }]
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn data_segments(&self) -> Vec<DataSegmentInfo> {
+        let module_info = self.artifact.module_ref();
+        // Active segments don't keep their index: they fill the
+        // indices that aren't taken by passive segments, in order.
+        let mut active = self.artifact.data_initializers().iter();
+        let num_segments = active.len() + module_info.passive_data.len();
+
+        (0..num_segments)
+            .map(|index| {
+                if let Some(data) = module_info.passive_data.get(&DataIndex::new(index)) {
+                    return DataSegmentInfo {
+                        mode: DataSegmentMode::Passive,
+                        len: data.len(),
+                    };
+                }
+                let initializer = active
+                    .next()
+                    .expect("active and passive data segments don't add up");
+                let location = &initializer.location;
+                let offset = match location.base {
+                    None => Some(location.offset),
+                    Some(_) => None,
+                };
+                DataSegmentInfo {
+                    mode: DataSegmentMode::Active {
+                        memory_index: location.memory_index,
+                        offset,
+                    },
+                    len: initializer.data.len(),
+                }
+            })
+            .collect()
+    }
+
+    /// Returns a summary of the interface of the module: its imports,
+    /// exports, locally defined memories, tables and globals, and the
+    /// features it was compiled with.
+    ///
+    /// The manifest can be serialized, for example to JSON for a step
+    /// generating bindings.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module (memory (export "memory") 1) (start $main) (func $main))"#;
+    /// let module = Module::new(&store, wat)?;
+    /// let manifest = module.manifest();
+    /// assert_eq!(manifest.exports[0].name(), "memory");
+    /// assert_eq!(manifest.memories, vec![MemoryType::new(1, None, false)]);
+    /// assert!(manifest.has_start_function);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn manifest(&self) -> ModuleManifest {
+        let info = self.info();
+        ModuleManifest {
+            name: self.name().map(str::to_string),
+            features: self.artifact.features().clone(),
+            used_features: info.used_features.clone(),
+            imports: self.imports().collect(),
+            exports: self.exports().collect(),
+            memories: info
+                .memories
+                .values()
+                .skip(info.num_imported_memories)
+                .cloned()
+                .collect(),
+            tables: info
+                .tables
+                .values()
+                .skip(info.num_imported_tables)
+                .cloned()
+                .collect(),
+            globals: info
+                .globals
+                .values()
+                .skip(info.num_imported_globals)
+                .cloned()
+                .collect(),
+            has_start_function: info.start_function.is_some(),
+            custom_sections: info.custom_sections.keys().cloned().collect(),
+        }
+    }
+
     /// Returns an iterator over the imported types in the Module.
     ///
     /// The order of the imports is guaranteed to be the same as in the
@@ -389,6 +1036,117 @@ impl Module {
         self.artifact.module_ref().exports()
     }
 
+    /// Returns the exported functions whose signature is exactly `ty`.
+    ///
+    /// The order of the exports is the same as in [`Module::exports`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = r#"(module
+    ///     (func (export "init_a"))
+    ///     (func (export "add") (param i32 i32) (result i32) (i32.const 0))
+    ///     (func (export "init_b"))
+    /// )"#;
+    /// let module = Module::new(&store, wat)?;
+    /// let initializers = module.exported_functions_of_type(&FunctionType::new(vec![], vec![]));
+    ///
+    /// let names = initializers.iter().map(|export| export.name()).collect::<Vec<_>>();
+    /// assert_eq!(names, vec!["init_a", "init_b"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn exported_functions_of_type(&self, ty: &FunctionType) -> Vec<ExportType<FunctionType>> {
+        self.exports()
+            .functions()
+            .filter(|export| export.ty() == ty)
+            .collect()
+    }
+
+    /// Prepares the module to be instantiated and called, so that the
+    /// first instance doesn't pay for it.
+    ///
+    /// This is meant for modules that are instantiated repeatedly, for
+    /// example to serve requests: it registers the information used to
+    /// symbolicate traps, which the first instantiation does otherwise,
+    /// and faults in the compiled code with the Universal engine.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, "(module (func (export \"run\")))")?;
+    /// module.warm_up();
+    /// let instance = Instance::new(&module, &imports! {})?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn warm_up(&self) {
+        self.artifact.warm_up();
+    }
+
+    /// Returns the memory held by the compiled module: its code, frame
+    /// information, data segments and metadata.
+    ///
+    /// This is what keeping the module loaded costs, as opposed to the
+    /// size of [`Module::serialize`]. Instances of the module hold
+    /// their own memories, tables and globals on top of it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, "(module (func (export \"f\")))")?;
+    /// let footprint = module.memory_footprint();
+    /// assert!(footprint.code_bytes > 0);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn memory_footprint(&self) -> ModuleFootprint {
+        self.artifact.memory_footprint()
+    }
+
+    /// Attaches `value` to the compiled module under `key`, replacing
+    /// the value already attached under that key, if any.
+    ///
+    /// The metadata is serialized with the module, so that embedders
+    /// can keep their own data with it. It belongs to the compiled
+    /// module, which clones of this `Module` share.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the engine can't attach metadata to its
+    /// artifacts.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let module = Module::new(&store, "(module)")?;
+    /// module.set_metadata("signature", vec![1, 2, 3])?;
+    /// assert_eq!(module.metadata("signature"), Some(vec![1, 2, 3]));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_metadata(&self, key: &str, value: Vec<u8>) -> Result<(), SerializeError> {
+        self.artifact.set_metadata(key, value)
+    }
+
+    /// Returns the metadata attached to the compiled module under
+    /// `key`, if any.
+    pub fn metadata(&self, key: &str) -> Option<Vec<u8>> {
+        self.artifact.get_metadata(key)
+    }
+
     /// Get the custom sections of the module given a `name`.
     ///
     /// # Important
@@ -424,6 +1182,101 @@ impl Module {
     pub fn artifact(&self) -> &Arc<dyn Artifact> {
         &self.artifact
     }
+
+    /// Returns the Wasm binary this module was compiled from, if it was
+    /// created with [`Module::new_retaining_bytes`].
+    ///
+    /// Contrary to [`Module::serialize`], this is the source module and
+    /// not the compiled artifact.
+    pub fn wasm_bytes(&self) -> Option<&[u8]> {
+        self.wasm_bytes.as_deref()
+    }
+
+    /// Returns the Wasm bytecode of the body of a function the module
+    /// defines, as it appears in the code section: its local
+    /// declarations followed by its instructions, up to and including
+    /// the final `end`, without the size that prefixes it.
+    ///
+    /// It is `None` if the module doesn't retain its Wasm binary (see
+    /// [`Module::new_retaining_bytes`]), or if `index` is past the last
+    /// function the module defines. An imported function has no body,
+    /// and no local index, see [`Module::local_function_index`].
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = "(module (func (result i32) i32.const 7))";
+    /// let module = Module::new_retaining_bytes(&store, wat)?;
+    /// assert_eq!(
+    ///     module.function_wasm_body(LocalFunctionIndex::from_u32(0)),
+    ///     // No locals, `i32.const 7`, `end`.
+    ///     Some(&[0x00, 0x41, 0x07, 0x0b][..])
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "compiler")]
+    pub fn function_wasm_body(&self, index: LocalFunctionIndex) -> Option<&[u8]> {
+        let range = self.function_bodies.get(index.index())?;
+        self.wasm_bytes()?.get(range.clone())
+    }
+
+    /// Returns the WebAssembly proposals this module uses, among the
+    /// ones enabled by the features it was compiled with.
+    ///
+    /// Contrary to the features, which say what a module may use, this
+    /// is what it does use, to tell which runtimes can run it. The
+    /// proposals are recorded when the module is translated, from its
+    /// declarations and instructions, and are serialized along with
+    /// it.
+    ///
+    /// ## Example
+    ///
+    /// ```
+    /// use wasmer::*;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let store = Store::default();
+    /// let wat = "(module (func (export \"pair\") (result i32 i32) i32.const 1 i32.const 2))";
+    /// let module = Module::new(&store, wat)?;
+    /// assert_eq!(module.required_proposals(), vec![WasmProposal::MultiValue]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn required_proposals(&self) -> Vec<WasmProposal> {
+        let used_features = &self.info().used_features;
+        WasmProposal::ALL
+            .iter()
+            .copied()
+            .filter(|proposal| proposal.is_enabled(used_features))
+            .collect()
+    }
+
+    /// Returns the BLAKE3 hash of the Wasm binary this module was
+    /// compiled from, which identifies it across processes.
+    ///
+    /// It is `None` if the engine didn't record the hash, see
+    /// `Universal::source_hash`.
+    pub fn source_hash(&self) -> Option<[u8; 32]> {
+        self.artifact.source_hash()
+    }
+
+    /// Checks that this module was compiled from `wasm_bytes`, which
+    /// may also be in the text format.
+    ///
+    /// This compares the hash of the binary with the one recorded when
+    /// the module was compiled, and that is serialized along with it.
+    /// It is `false` if the engine didn't record the hash, or if it was
+    /// built without the `source-hash` feature needed to check it, see
+    /// `Universal::source_hash`.
+    pub fn verify_source(&self, wasm_bytes: &[u8]) -> bool {
+        match Self::to_binary(wasm_bytes) {
+            Ok(bytes) => self.artifact.verify_source(&bytes),
+            Err(_) => false,
+        }
+    }
 }
 
 impl fmt::Debug for Module {
@@ -433,3 +1286,24 @@ impl fmt::Debug for Module {
             .finish()
     }
 }
+
+/// Returns the ranges of the bodies of the functions defined by the
+/// Wasm binary `bytes`, as returned by [`Module::function_wasm_body`].
+///
+/// The bodies are skipped over, not parsed.
+#[cfg(feature = "compiler")]
+fn function_body_ranges(bytes: &[u8]) -> Arc<[Range<usize>]> {
+    use wasmer_compiler::wasmparser::{Parser, Payload};
+
+    Parser::new(0)
+        .parse_all(bytes)
+        .filter_map(|payload| match payload {
+            Ok(Payload::CodeSectionEntry(body)) => {
+                let reader = body.get_binary_reader();
+                let start = reader.original_position();
+                Some(start..start + reader.bytes_remaining())
+            }
+            _ => None,
+        })
+        .collect()
+}
@@ -9,7 +9,7 @@
 //! ```
 use std::marker::PhantomData;
 
-use crate::externals::function::{DynamicFunction, VMDynamicFunction};
+use crate::externals::function::{DynamicFunction, FunctionLabel, Relabel, VMDynamicFunction};
 use crate::{FromToNativeWasmType, Function, RuntimeError, Store, WasmTypeList};
 use std::panic::{catch_unwind, AssertUnwindSafe};
 use wasmer_engine::ExportFunction;
@@ -21,6 +21,10 @@ use wasmer_vm::{VMDynamicFunctionContext, VMFunctionBody, VMFunctionEnvironment,
 pub struct NativeFunc<Args = (), Rets = ()> {
     store: Store,
     exported: ExportFunction,
+    /// The label of the `Function` this was obtained from, kept for
+    /// when it is converted back into a `Function`.
+    label: FunctionLabel,
+    relabel: Option<Relabel>,
     _phantom: PhantomData<(Args, Rets)>,
 }
 
@@ -31,10 +35,17 @@ where
     Args: WasmTypeList,
     Rets: WasmTypeList,
 {
-    pub(crate) fn new(store: Store, exported: ExportFunction) -> Self {
+    pub(crate) fn new(
+        store: Store,
+        exported: ExportFunction,
+        label: FunctionLabel,
+        relabel: Option<Relabel>,
+    ) -> Self {
         Self {
             store,
             exported,
+            label,
+            relabel,
             _phantom: PhantomData,
         }
     }
@@ -95,6 +106,8 @@ impl<Args: WasmTypeList, Rets: WasmTypeList> Clone for NativeFunc<Args, Rets> {
         Self {
             store: self.store.clone(),
             exported,
+            label: self.label.clone(),
+            relabel: self.relabel,
             _phantom: PhantomData,
         }
     }
@@ -116,10 +129,7 @@ where
     Rets: WasmTypeList,
 {
     fn from(other: NativeFunc<Args, Rets>) -> Self {
-        Self {
-            store: other.store,
-            exported: other.exported,
-        }
+        Self::from_export(other.store, other.exported, other.label, other.relabel)
     }
 }
 
@@ -191,22 +201,37 @@ macro_rules! impl_native_traits {
                 else {
                     match self.arg_kind() {
                         VMFunctionKind::Static => {
+                            // The host function reports its errors by raising a
+                            // trap, so it must run where traps are caught, just
+                            // like it does when called from Wasm.
+                            let mut args = Some(( $( $x ),* ));
                             let results = catch_unwind(AssertUnwindSafe(|| unsafe {
-                                let f = std::mem::transmute::<_, unsafe extern "C" fn( VMFunctionEnvironment, $( $x, )*) -> Rets::CStruct>(self.address());
-                                // We always pass the vmctx
-                                f( self.vmctx(), $( $x, )* )
-                            })).map_err(|e| RuntimeError::new(format!("{:?}", e)))?;
+                                wasmer_vm::catch_traps_with_result(&self.store, || {
+                                    let ( $( $x ),* ) = args.take().unwrap();
+                                    let f = std::mem::transmute::<_, unsafe extern "C" fn( VMFunctionEnvironment, $( $x, )*) -> Rets::CStruct>(self.address());
+                                    // We always pass the vmctx
+                                    f( self.vmctx(), $( $x, )* )
+                                })
+                            })).map_err(|e| RuntimeError::new(format!("{:?}", e)))?
+                            .map_err(RuntimeError::from_trap)?;
                             Ok(Rets::from_c_struct(results))
                         },
                         VMFunctionKind::Dynamic => {
                             let params_list = [ $( $x.to_native().to_value() ),* ];
-                            let results = {
-                                type VMContextWithEnv = VMDynamicFunctionContext<DynamicFunction<std::ffi::c_void>>;
-                                unsafe {
-                                    let ctx = self.vmctx().host_env as *mut VMContextWithEnv;
-                                    (*ctx).ctx.call(&params_list)?
-                                }
-                            };
+                            type VMContextWithEnv = VMDynamicFunctionContext<DynamicFunction<std::ffi::c_void>>;
+                            let ctx = unsafe { &(*(self.vmctx().host_env as *mut VMContextWithEnv)).ctx };
+                            let results = ctx.call(&ctx.caller(), &params_list)?;
+                            // Like when the function is called from Wasm, the
+                            // results must be checked before being written.
+                            let return_types = results.iter().map(|ret| ret.ty()).collect::<Vec<_>>();
+                            if return_types != Rets::wasm_types() {
+                                return Err(RuntimeError::new(format!(
+                                    "Dynamic function{} returned wrong signature. Expected {:?} but got {:?}",
+                                    ctx.label().describe(""),
+                                    Rets::wasm_types(),
+                                    return_types
+                                )));
+                            }
                             let mut rets_list_array = Rets::empty_array();
                             let mut_rets = rets_list_array.as_mut() as *mut [i128] as *mut i128;
                             for (i, ret) in results.iter().enumerate() {
@@ -1,12 +1,76 @@
+use crate::env::SharedEnv;
+use crate::instance::InstantiationEvent;
+use crate::limits::ResourceLimiter;
 use crate::tunables::BaseTunables;
+use crate::Pages;
 use loupe::MemoryUsage;
 use std::any::Any;
 use std::fmt;
-use std::sync::{Arc, RwLock};
+use std::panic::PanicInfo;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use thiserror::Error;
 #[cfg(all(feature = "compiler", feature = "engine"))]
 use wasmer_compiler::CompilerConfig;
 use wasmer_engine::{is_wasm_pc, Engine, Tunables};
-use wasmer_vm::{init_traps, TrapHandler, TrapHandlerFn};
+use wasmer_vm::{init_panic_hook, init_traps, Trap, TrapHandler, TrapHandlerFn};
+
+/// A function observing the traps of the instances of a [`Store`], see
+/// [`Store::set_trap_observer`].
+pub type TrapObserverFn = dyn Fn(&Trap) + Send + Sync;
+
+/// A function observing the instantiations of the modules of a
+/// [`Store`], see [`Store::set_instantiation_observer`].
+pub type InstantiationObserverFn = dyn Fn(&InstantiationEvent<'_>) + Send + Sync;
+
+/// A function replacing the panic hook of the process for the panics
+/// happening during the calls into wasm of a [`Store`], see
+/// [`Store::set_panic_hook`].
+pub type PanicHookFn = dyn Fn(&PanicInfo<'_>) + Send + Sync;
+
+/// The error returned when creating a host function, memory, table or
+/// global in a [`Store`] that has been frozen with [`Store::freeze`].
+///
+/// It is returned as is by the `try_new*` constructors of functions.
+/// [`Table::new`] and the `try_new*` constructors of globals wrap it in
+/// a [`RuntimeError`], from which it can be recovered with
+/// [`RuntimeError::downcast`], and [`Memory::new`] reports
+/// [`MemoryError::StoreFrozen`] instead.
+///
+/// [`Table::new`]: crate::Table::new
+/// [`RuntimeError`]: crate::RuntimeError
+/// [`Memory::new`]: crate::Memory::new
+/// [`MemoryError::StoreFrozen`]: crate::MemoryError::StoreFrozen
+///
+/// [`RuntimeError::downcast`]: crate::RuntimeError::downcast
+#[derive(Error, Debug, Clone, Copy, PartialEq, Eq)]
+#[error("the store is frozen, no new definitions can be created in it")]
+pub struct StoreFrozen;
+
+/// The memory used by the instances alive in a [`Store`], as reported
+/// by [`Store::report_memory_usage`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoreMemoryReport {
+    /// The number of instances alive in the store.
+    pub instances: usize,
+    /// The size of the memories of the instances, in bytes, at their
+    /// current number of pages.
+    pub memory_bytes: usize,
+    /// The size of the elements of the tables of the instances.
+    pub table_bytes: usize,
+    /// The size of the globals of the instances.
+    pub global_bytes: usize,
+    /// The size of the executable code of the modules of the instances,
+    /// counted once per module.
+    pub code_bytes: usize,
+}
+
+impl StoreMemoryReport {
+    /// Returns the total number of bytes.
+    pub fn total(&self) -> usize {
+        self.memory_bytes + self.table_bytes + self.global_bytes + self.code_bytes
+    }
+}
 
 /// The store represents all global state that can be manipulated by
 /// WebAssembly programs. It consists of the runtime representation
@@ -24,6 +88,26 @@ pub struct Store {
     tunables: Arc<dyn Tunables + Send + Sync>,
     #[loupe(skip)]
     trap_handler: Arc<RwLock<Option<Box<TrapHandlerFn>>>>,
+    #[loupe(skip)]
+    trap_observer: Arc<RwLock<Option<Arc<TrapObserverFn>>>>,
+    #[loupe(skip)]
+    instantiation_observer: Arc<RwLock<Option<Arc<InstantiationObserverFn>>>>,
+    #[loupe(skip)]
+    panic_hook: Arc<RwLock<Option<Arc<PanicHookFn>>>>,
+    /// The size of the stack wasm runs on, `0` meaning the stack of the
+    /// calling thread.
+    #[loupe(skip)]
+    wasm_stack_size: Arc<AtomicUsize>,
+    #[loupe(skip)]
+    catch_host_panics: Arc<AtomicBool>,
+    #[loupe(skip)]
+    capture_backtraces: Arc<AtomicBool>,
+    #[loupe(skip)]
+    limiter: Arc<ResourceLimiter>,
+    #[loupe(skip)]
+    frozen: Arc<AtomicBool>,
+    #[loupe(skip)]
+    shared_envs: Arc<Mutex<Vec<Arc<dyn Any + Send + Sync>>>>,
 }
 
 impl Store {
@@ -41,6 +125,266 @@ impl Store {
         *m = handler;
     }
 
+    /// Set the function observing the traps of this store.
+    ///
+    /// The observer is called on the trapping thread with every trap
+    /// raised while running wasm entered through this store, before the
+    /// trap is turned into a [`RuntimeError`]. Traps of other stores
+    /// aren't observed. A trap that a host function called from wasm
+    /// lets through is observed again, as the error of the host
+    /// function, when it reaches the outer call.
+    ///
+    /// [`RuntimeError`]: crate::RuntimeError
+    pub fn set_trap_observer(&self, observer: Option<Box<TrapObserverFn>>) {
+        *self.trap_observer.write().unwrap() = observer.map(Arc::from);
+    }
+
+    /// Set the function observing the instantiations of this store.
+    ///
+    /// The observer is called on the instantiating thread once per
+    /// [`Instance::new`] (or any other way of creating an instance) of
+    /// a module of this store, whether it succeeds or fails, after the
+    /// start function ran. It is given the module and the imports it
+    /// was instantiated with, but not the instance, which it can't
+    /// reach.
+    ///
+    /// [`Instance::new`]: crate::Instance::new
+    pub fn set_instantiation_observer(&self, observer: Option<Box<InstantiationObserverFn>>) {
+        *self.instantiation_observer.write().unwrap() = observer.map(Arc::from);
+    }
+
+    /// Returns the instantiation observer, if any.
+    pub(crate) fn instantiation_observer(&self) -> Option<Arc<InstantiationObserverFn>> {
+        self.instantiation_observer.read().unwrap().clone()
+    }
+
+    /// Set the panic hook of the calls into wasm made through this store.
+    ///
+    /// The hook is called instead of the panic hook of the process
+    /// (see [`std::panic::set_hook`]) with the panics that happen on a
+    /// thread while it runs wasm entered through this store, typically
+    /// the panics of host functions. It only replaces the reporting of
+    /// the panic: the panic then unwinds, and is resumed or caught (see
+    /// [`Store::set_catch_host_panics`]) as usual. Panics happening
+    /// outside of these calls, for example on other threads, still go to
+    /// the hook of the process.
+    ///
+    /// When calls into wasm are nested, for example when a host function
+    /// calls an instance of another store, the hook of the innermost
+    /// store that has one is used.
+    ///
+    /// The first time a hook is set, a hook forwarding to the hooks of
+    /// the stores is installed on top of the hook of the process, once
+    /// for all. Replacing the hook of the process afterwards disables
+    /// the hooks of the stores.
+    pub fn set_panic_hook(&self, hook: Option<Box<PanicHookFn>>) {
+        if hook.is_some() {
+            init_panic_hook();
+        }
+        *self.panic_hook.write().unwrap() = hook.map(Arc::from);
+    }
+
+    /// Set the size (in bytes) of the stack wasm code runs on.
+    ///
+    /// When set, every thread entering wasm through this store runs it on
+    /// a dedicated stack of that size, protected by a guard page. Overflowing
+    /// it, for example with unbounded recursion, results in a [`RuntimeError`]
+    /// with a [`TrapCode::StackOverflow`] trap code. Reentrant calls (wasm
+    /// calling the host calling wasm again) run on the same stack, so the
+    /// limit applies to all of them together.
+    ///
+    /// When `None` (the default), wasm runs directly on the stack of the
+    /// calling thread. Dedicated stacks are only supported on Unix platforms;
+    /// elsewhere this setting is ignored.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is `Some(0)`, as wasm can't run on an empty stack.
+    ///
+    /// [`RuntimeError`]: crate::RuntimeError
+    /// [`TrapCode::StackOverflow`]: crate::TrapCode::StackOverflow
+    pub fn set_wasm_stack_size(&self, size: Option<usize>) {
+        assert_ne!(size, Some(0), "the wasm stack size can't be 0");
+        // 0 stands for `None`, which `Some(0)` can't be mistaken for.
+        self.wasm_stack_size
+            .store(size.unwrap_or(0), Ordering::SeqCst);
+    }
+
+    /// Returns the size of the stack wasm code runs on, if any.
+    ///
+    /// See [`Store::set_wasm_stack_size`].
+    pub fn wasm_stack_size(&self) -> Option<usize> {
+        match self.wasm_stack_size.load(Ordering::SeqCst) {
+            0 => None,
+            size => Some(size),
+        }
+    }
+
+    /// Set whether panics of host functions called from wasm are caught.
+    ///
+    /// By default, such a panic unwinds through the wasm frames and is
+    /// resumed in the host code that called into wasm. When catching
+    /// is enabled, the panic is stopped in the host function instead,
+    /// and the wasm caller traps with a [`RuntimeError`] whose
+    /// [`RuntimeError::host_panic`] returns the panic message.
+    ///
+    /// [`RuntimeError`]: crate::RuntimeError
+    /// [`RuntimeError::host_panic`]: crate::RuntimeError::host_panic
+    pub fn set_catch_host_panics(&self, catch: bool) {
+        self.catch_host_panics.store(catch, Ordering::SeqCst);
+    }
+
+    /// Returns whether panics of host functions are caught.
+    ///
+    /// See [`Store::set_catch_host_panics`].
+    pub fn catch_host_panics(&self) -> bool {
+        self.catch_host_panics.load(Ordering::SeqCst)
+    }
+
+    /// Set whether traps capture a backtrace.
+    ///
+    /// By default, every trap walks the native stack to report the
+    /// wasm frames it went through in [`RuntimeError::trace`]. Workloads
+    /// that trap often and never look at where traps happen can turn
+    /// this off: traps raised by wasm code or by the runtime then keep
+    /// their trap code, but report an empty trace.
+    ///
+    /// Modules instantiated while this is off also skip registering
+    /// the information needed to symbolicate their frames, which makes
+    /// instantiating them cheaper. Their frames are left out of the
+    /// traces captured after turning this back on, until one of their
+    /// instances is created with it on.
+    ///
+    /// [`RuntimeError::trace`]: crate::RuntimeError::trace
+    pub fn set_capture_backtraces(&self, capture: bool) {
+        self.capture_backtraces.store(capture, Ordering::SeqCst);
+    }
+
+    /// Returns whether traps capture a backtrace.
+    ///
+    /// See [`Store::set_capture_backtraces`].
+    pub fn capture_backtraces(&self) -> bool {
+        self.capture_backtraces.load(Ordering::SeqCst)
+    }
+
+    /// Set the maximum number of instances alive at the same time in
+    /// this store, or `None` (the default) for no limit.
+    ///
+    /// Instantiating a module beyond the limit fails with
+    /// [`InstantiationError::ResourceLimitExceeded`]. An instance stops
+    /// counting once it is dropped, along with everything exported from
+    /// it.
+    ///
+    /// [`InstantiationError::ResourceLimitExceeded`]: crate::InstantiationError::ResourceLimitExceeded
+    pub fn set_max_instances(&self, max: Option<usize>) {
+        self.limiter.set_max_instances(max);
+    }
+
+    /// Returns the maximum number of instances alive at the same time.
+    ///
+    /// See [`Store::set_max_instances`].
+    pub fn max_instances(&self) -> Option<usize> {
+        self.limiter.max_instances()
+    }
+
+    /// Returns the number of instances currently alive in this store.
+    pub fn live_instances(&self) -> usize {
+        self.limiter.instances()
+    }
+
+    /// Set the maximum number of memory pages of all the instances alive
+    /// in this store together, or `None` (the default) for no limit.
+    ///
+    /// Instantiating a module whose memories would exceed the limit
+    /// fails with [`InstantiationError::ResourceLimitExceeded`], and
+    /// growing a memory beyond it fails as if the memory had reached
+    /// its maximum. The pages of a memory stop counting once it is
+    /// dropped. Memories created by the host aren't counted.
+    ///
+    /// [`InstantiationError::ResourceLimitExceeded`]: crate::InstantiationError::ResourceLimitExceeded
+    pub fn set_max_memory_pages(&self, max: Option<Pages>) {
+        self.limiter.set_max_memory_pages(max);
+    }
+
+    /// Returns the maximum number of memory pages of all the instances.
+    ///
+    /// See [`Store::set_max_memory_pages`].
+    pub fn max_memory_pages(&self) -> Option<Pages> {
+        self.limiter.max_memory_pages()
+    }
+
+    /// Returns the number of memory pages currently committed by the
+    /// instances of this store.
+    pub fn committed_memory_pages(&self) -> Pages {
+        self.limiter.memory_pages()
+    }
+
+    /// Returns the memory used right now by the instances alive in this
+    /// store.
+    ///
+    /// The memories, tables and globals that the instances of this
+    /// store created are counted, including when they are imported by
+    /// the instances of other stores, for as long as they are alive.
+    /// The ones the host created with [`Memory::new`], [`Table::new`]
+    /// or [`Global::new`], and the ones imported from other stores,
+    /// aren't. The code of a module is counted once, however many of
+    /// its instances are alive.
+    ///
+    /// [`Memory::new`]: crate::Memory::new
+    /// [`Table::new`]: crate::Table::new
+    /// [`Global::new`]: crate::Global::new
+    pub fn report_memory_usage(&self) -> StoreMemoryReport {
+        self.limiter.memory_report()
+    }
+
+    pub(crate) fn limiter(&self) -> &Arc<ResourceLimiter> {
+        &self.limiter
+    }
+
+    /// Freeze the store, so that no new host functions, memories,
+    /// tables or globals can be created in it.
+    ///
+    /// This is meant to be called once an embedder is done setting up
+    /// the imports of its instances, to catch code that defines new
+    /// objects afterwards. Freezing can't be undone, and applies to all
+    /// the clones of the store.
+    ///
+    /// Everything created before freezing keeps working: functions can
+    /// still be called, and memories, tables and globals read, written
+    /// and grown. Modules can still be compiled and instantiated, since
+    /// their memories, tables and globals belong to their instances.
+    ///
+    /// After freezing, [`Memory::new`] returns
+    /// [`MemoryError::StoreFrozen`], [`Table::new`] and the `try_new*`
+    /// constructors of [`Global`] return an error wrapping
+    /// [`StoreFrozen`], and the `try_new*` constructors of [`Function`]
+    /// return [`StoreFrozen`]. Their infallible counterparts panic.
+    ///
+    /// [`Memory::new`]: crate::Memory::new
+    /// [`MemoryError::StoreFrozen`]: crate::MemoryError::StoreFrozen
+    /// [`Table::new`]: crate::Table::new
+    /// [`Function`]: crate::Function
+    /// [`Global`]: crate::Global
+    pub fn freeze(&self) {
+        self.frozen.store(true, Ordering::SeqCst);
+    }
+
+    /// Returns whether the store has been frozen.
+    ///
+    /// See [`Store::freeze`].
+    pub fn is_frozen(&self) -> bool {
+        self.frozen.load(Ordering::SeqCst)
+    }
+
+    /// Returns an error if the store has been frozen.
+    pub(crate) fn check_not_frozen(&self) -> Result<(), StoreFrozen> {
+        if self.is_frozen() {
+            Err(StoreFrozen)
+        } else {
+            Ok(())
+        }
+    }
+
     /// Creates a new `Store` with a specific [`Engine`] and [`Tunables`].
     pub fn new_with_tunables<E>(engine: &E, tunables: impl Tunables + Send + Sync + 'static) -> Self
     where
@@ -54,9 +398,54 @@ impl Store {
             engine: engine.cloned(),
             tunables: Arc::new(tunables),
             trap_handler: Arc::new(RwLock::new(None)),
+            trap_observer: Arc::new(RwLock::new(None)),
+            instantiation_observer: Arc::new(RwLock::new(None)),
+            panic_hook: Arc::new(RwLock::new(None)),
+            wasm_stack_size: Arc::new(AtomicUsize::new(0)),
+            catch_host_panics: Arc::new(AtomicBool::new(false)),
+            capture_backtraces: Arc::new(AtomicBool::new(true)),
+            limiter: Arc::new(ResourceLimiter::new()),
+            frozen: Arc::new(AtomicBool::new(false)),
+            shared_envs: Arc::new(Mutex::new(Vec::new())),
         }
     }
 
+    /// Registers `env` in the store, to be shared by several host
+    /// functions.
+    ///
+    /// The environment is allocated once, and every function created
+    /// with a clone of the returned [`SharedEnv`] references it. It is
+    /// freed once the store and these functions are dropped.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer::{Function, SharedEnv, Store};
+    /// # use std::sync::Mutex;
+    /// # let store = Store::default();
+    /// #
+    /// let counter = store.register_env(Mutex::new(0));
+    ///
+    /// let increment = Function::new_native_with_env(
+    ///     &store,
+    ///     counter.clone(),
+    ///     |counter: &SharedEnv<Mutex<i32>>| *counter.lock().unwrap() += 1,
+    /// );
+    /// let get = Function::new_native_with_env(
+    ///     &store,
+    ///     counter.clone(),
+    ///     |counter: &SharedEnv<Mutex<i32>>| *counter.lock().unwrap(),
+    /// );
+    /// ```
+    pub fn register_env<T>(&self, env: T) -> SharedEnv<T>
+    where
+        T: Send + Sync + 'static,
+    {
+        let env = Arc::new(env);
+        self.shared_envs.lock().unwrap().push(env.clone());
+        SharedEnv::new(env)
+    }
+
     /// Returns the [`Tunables`].
     pub fn tunables(&self) -> &dyn Tunables {
         self.tunables.as_ref()
@@ -94,6 +483,38 @@ unsafe impl TrapHandler for Store {
             false
         }
     }
+
+    fn wasm_stack_size(&self) -> Option<usize> {
+        Self::wasm_stack_size(self)
+    }
+
+    fn catch_host_panics(&self) -> bool {
+        Self::catch_host_panics(self)
+    }
+
+    fn capture_backtraces(&self) -> bool {
+        Self::capture_backtraces(self)
+    }
+
+    fn observe_trap(&self, trap: &Trap) {
+        // The observer may replace itself.
+        let observer = self.trap_observer.read().unwrap().clone();
+        if let Some(observer) = observer {
+            observer(trap);
+        }
+    }
+
+    fn handle_panic(&self, info: &PanicInfo<'_>) -> bool {
+        // The hook may replace itself.
+        let hook = self.panic_hook.read().unwrap().clone();
+        match hook {
+            Some(hook) => {
+                hook(info);
+                true
+            }
+            None => false,
+        }
+    }
 }
 
 // This is required to be able to set the trap_handler in the
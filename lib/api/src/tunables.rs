@@ -30,6 +30,9 @@ pub struct BaseTunables {
 
     /// The size in bytes of the offset guard for dynamic heaps.
     pub dynamic_memory_offset_guard_size: u64,
+
+    /// The largest number of elements a table can be created with.
+    pub max_table_elements: u32,
 }
 
 impl BaseTunables {
@@ -65,31 +68,84 @@ impl BaseTunables {
             static_memory_bound,
             static_memory_offset_guard_size,
             dynamic_memory_offset_guard_size,
+            max_table_elements: 10_000_000,
         }
     }
-}
 
-impl Tunables for BaseTunables {
-    /// Get a `MemoryStyle` for the provided `MemoryType`
-    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+    /// Picks the `MemoryStyle` of `memory` given the sizes of its offset
+    /// guard when it is static or dynamic.
+    ///
+    /// This is what [`Tunables::memory_style`] does with the guard
+    /// sizes returned by [`Tunables::static_memory_guard_size`] and
+    /// [`Tunables::dynamic_memory_guard_size`]. Tunables wrapping
+    /// `BaseTunables` can use it to pick the guard of each memory.
+    ///
+    /// A memory is only static if its bound and guard can be reserved
+    /// in the address space of the host, which isn't the case of large
+    /// guards on 32-bit hosts: such memories are dynamic instead.
+    pub fn memory_style_with_guard_sizes(
+        &self,
+        memory: &MemoryType,
+        static_memory_guard_size: u64,
+        dynamic_memory_guard_size: u64,
+    ) -> MemoryStyle {
         // A heap with a maximum that doesn't exceed the static memory bound specified by the
         // tunables make it static.
         //
         // If the module doesn't declare an explicit maximum treat it as 4GiB.
         let maximum = memory.maximum.unwrap_or_else(Pages::max_value);
-        if maximum <= self.static_memory_bound {
+        let reservation =
+            (self.static_memory_bound.bytes().0 as u64).checked_add(static_memory_guard_size);
+        if maximum <= self.static_memory_bound
+            && reservation.map_or(false, |size| size <= isize::MAX as u64)
+        {
             MemoryStyle::Static {
                 // Bound can be larger than the maximum for performance reasons
                 bound: self.static_memory_bound,
-                offset_guard_size: self.static_memory_offset_guard_size,
+                offset_guard_size: static_memory_guard_size,
             }
         } else {
             MemoryStyle::Dynamic {
-                offset_guard_size: self.dynamic_memory_offset_guard_size,
+                offset_guard_size: dynamic_memory_guard_size,
             }
         }
     }
 
+    /// Rejects tables whose initial size is above `max_table_elements`,
+    /// before anything gets allocated for them.
+    fn check_table_size(&self, ty: &TableType) -> Result<(), String> {
+        if ty.minimum > self.max_table_elements {
+            return Err(format!(
+                "Table minimum ({}) is larger than the limit of {} elements",
+                ty.minimum, self.max_table_elements
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Tunables for BaseTunables {
+    /// Get a `MemoryStyle` for the provided `MemoryType`
+    fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+        self.memory_style_with_guard_sizes(
+            memory,
+            self.static_memory_guard_size(memory),
+            self.dynamic_memory_guard_size(memory),
+        )
+    }
+
+    /// The size of the offset guard of static memories,
+    /// `static_memory_offset_guard_size`.
+    fn static_memory_guard_size(&self, _memory: &MemoryType) -> u64 {
+        self.static_memory_offset_guard_size
+    }
+
+    /// The size of the offset guard of dynamic memories,
+    /// `dynamic_memory_offset_guard_size`.
+    fn dynamic_memory_guard_size(&self, _memory: &MemoryType) -> u64 {
+        self.dynamic_memory_offset_guard_size
+    }
+
     /// Get a [`TableStyle`] for the provided [`TableType`].
     fn table_style(&self, _table: &TableType) -> TableStyle {
         TableStyle::CallerChecksSignature
@@ -128,6 +184,7 @@ impl Tunables for BaseTunables {
         ty: &TableType,
         style: &TableStyle,
     ) -> Result<Arc<dyn Table>, String> {
+        self.check_table_size(ty)?;
         Ok(Arc::new(LinearTable::new(&ty, &style)?))
     }
 
@@ -142,6 +199,7 @@ impl Tunables for BaseTunables {
         style: &TableStyle,
         vm_definition_location: NonNull<VMTableDefinition>,
     ) -> Result<Arc<dyn Table>, String> {
+        self.check_table_size(ty)?;
         Ok(Arc::new(LinearTable::from_definition(
             &ty,
             &style,
@@ -160,6 +218,7 @@ mod tests {
             static_memory_bound: Pages(2048),
             static_memory_offset_guard_size: 128,
             dynamic_memory_offset_guard_size: 256,
+            max_table_elements: 1024,
         };
 
         // No maximum
@@ -192,4 +251,159 @@ mod tests {
             s => panic!("Unexpected memory style: {:?}", s),
         }
     }
+
+    #[test]
+    fn memory_style_guard_sizes() {
+        let small = BaseTunables {
+            static_memory_bound: Pages(16),
+            static_memory_offset_guard_size: 0x1000,
+            dynamic_memory_offset_guard_size: 0,
+            max_table_elements: 1024,
+        };
+        let large = BaseTunables {
+            static_memory_offset_guard_size: 0x1_0000_0000,
+            dynamic_memory_offset_guard_size: 0x8000_0000,
+            ..small.clone()
+        };
+        let static_memory = MemoryType::new(1, Some(16), false);
+        let dynamic_memory = MemoryType::new(1, None, false);
+
+        assert_eq!(small.static_memory_guard_size(&static_memory), 0x1000);
+        assert_eq!(
+            small.memory_style(&static_memory),
+            MemoryStyle::Static {
+                bound: Pages(16),
+                offset_guard_size: 0x1000,
+            }
+        );
+        assert_eq!(
+            small.memory_style(&dynamic_memory),
+            MemoryStyle::Dynamic {
+                offset_guard_size: 0
+            }
+        );
+
+        assert_eq!(
+            large.static_memory_guard_size(&static_memory),
+            0x1_0000_0000
+        );
+        assert_eq!(
+            large.memory_style(&dynamic_memory),
+            MemoryStyle::Dynamic {
+                offset_guard_size: 0x8000_0000
+            }
+        );
+        // A 4 GiB guard can't be reserved on 32-bit hosts, where the
+        // memory is checked dynamically instead.
+        #[cfg(target_pointer_width = "64")]
+        let expected = MemoryStyle::Static {
+            bound: Pages(16),
+            offset_guard_size: 0x1_0000_0000,
+        };
+        #[cfg(target_pointer_width = "32")]
+        let expected = MemoryStyle::Dynamic {
+            offset_guard_size: 0x8000_0000,
+        };
+        assert_eq!(large.memory_style(&static_memory), expected);
+    }
+
+    #[test]
+    fn memory_style_guard_sizes_per_memory() {
+        /// Tunables reserving a large guard only for memories that can
+        /// grow past one page.
+        #[derive(MemoryUsage)]
+        struct PerMemoryTunables(BaseTunables);
+
+        impl PerMemoryTunables {
+            fn guard_size(memory: &MemoryType) -> u64 {
+                if memory.maximum == Some(Pages(1)) {
+                    0
+                } else {
+                    0x1_0000
+                }
+            }
+        }
+
+        impl Tunables for PerMemoryTunables {
+            fn memory_style(&self, memory: &MemoryType) -> MemoryStyle {
+                self.0.memory_style_with_guard_sizes(
+                    memory,
+                    self.static_memory_guard_size(memory),
+                    self.dynamic_memory_guard_size(memory),
+                )
+            }
+
+            fn static_memory_guard_size(&self, memory: &MemoryType) -> u64 {
+                Self::guard_size(memory)
+            }
+
+            fn dynamic_memory_guard_size(&self, memory: &MemoryType) -> u64 {
+                Self::guard_size(memory)
+            }
+
+            fn table_style(&self, table: &TableType) -> TableStyle {
+                self.0.table_style(table)
+            }
+
+            fn create_host_memory(
+                &self,
+                ty: &MemoryType,
+                style: &MemoryStyle,
+            ) -> Result<Arc<dyn Memory>, MemoryError> {
+                self.0.create_host_memory(ty, style)
+            }
+
+            unsafe fn create_vm_memory(
+                &self,
+                ty: &MemoryType,
+                style: &MemoryStyle,
+                vm_definition_location: NonNull<VMMemoryDefinition>,
+            ) -> Result<Arc<dyn Memory>, MemoryError> {
+                self.0.create_vm_memory(ty, style, vm_definition_location)
+            }
+
+            fn create_host_table(
+                &self,
+                ty: &TableType,
+                style: &TableStyle,
+            ) -> Result<Arc<dyn Table>, String> {
+                self.0.create_host_table(ty, style)
+            }
+
+            unsafe fn create_vm_table(
+                &self,
+                ty: &TableType,
+                style: &TableStyle,
+                vm_definition_location: NonNull<VMTableDefinition>,
+            ) -> Result<Arc<dyn Table>, String> {
+                self.0.create_vm_table(ty, style, vm_definition_location)
+            }
+        }
+
+        let tunables: &dyn Tunables = &PerMemoryTunables(BaseTunables {
+            static_memory_bound: Pages(16),
+            static_memory_offset_guard_size: 0x1000,
+            dynamic_memory_offset_guard_size: 0x1000,
+            max_table_elements: 1024,
+        });
+        // Memories without a maximum are dynamic.
+        assert_eq!(
+            tunables.memory_style(&MemoryType::new(1, None, false)),
+            MemoryStyle::Dynamic {
+                offset_guard_size: 0x1_0000
+            }
+        );
+        assert_eq!(
+            tunables
+                .memory_style(&MemoryType::new(1, Some(1), false))
+                .offset_guard_size(),
+            0
+        );
+        assert_eq!(
+            tunables
+                .memory_style(&MemoryType::new(1, Some(16), false))
+                .offset_guard_size(),
+            0x1_0000
+        );
+    }
 }
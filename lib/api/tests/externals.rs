@@ -1,4 +1,9 @@
 use anyhow::Result;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
 use wasmer::*;
 
 #[test]
@@ -58,6 +63,161 @@ fn global_set() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn global_try_new_rejects_other_stores() -> Result<()> {
+    let store = Store::default();
+    let other_store = Store::default();
+    let function = Function::new_native(&other_store, || {});
+
+    assert!(Global::try_new(&store, Value::FuncRef(Some(function.clone()))).is_err());
+    assert!(Global::try_new_mut(&store, Value::FuncRef(Some(function.clone()))).is_err());
+    let global = Global::try_new(&other_store, Value::FuncRef(Some(function)))?;
+    assert!(global.get().unwrap_funcref().is_some());
+
+    Ok(())
+}
+
+#[test]
+fn global_with_validator() -> Result<()> {
+    let store = Store::default();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let seen_in_validator = seen.clone();
+    let level = Global::with_validator(
+        &store,
+        GlobalType::new(Type::I32, Mutability::Var),
+        Value::I32(2),
+        move |old, new| {
+            seen_in_validator
+                .lock()
+                .unwrap()
+                .push((old.unwrap_i32(), new.unwrap_i32()));
+            match new.unwrap_i32() {
+                0..=5 => Ok(()),
+                level => Err(format!("log level {} is out of range", level)),
+            }
+        },
+    )?;
+
+    level.set(Value::I32(5))?;
+    assert_eq!(level.get(), Value::I32(5));
+
+    let error = level.set(Value::I32(6)).unwrap_err();
+    assert_eq!(error.message(), "log level 6 is out of range");
+    assert_eq!(level.get(), Value::I32(5));
+
+    // The validator is attached to the global, not to the handle.
+    let handle = level.clone();
+    assert!(handle.set(Value::I32(-1)).is_err());
+    assert_eq!(level.get(), Value::I32(5));
+    assert_eq!(*seen.lock().unwrap(), vec![(2, 5), (5, 6), (5, -1)]);
+
+    // The type is checked before the validator runs.
+    assert!(level.set(Value::I64(1)).is_err());
+    assert_eq!(seen.lock().unwrap().len(), 3);
+
+    assert!(Global::with_validator(
+        &store,
+        GlobalType::new(Type::I32, Mutability::Var),
+        Value::I64(2),
+        |_, _| Ok(()),
+    )
+    .is_err());
+
+    Ok(())
+}
+
+#[test]
+fn global_clone_into() -> Result<()> {
+    let store = Store::default();
+    let other_store = Store::default();
+    let global = Global::new_mut(&store, Value::I64(7));
+    global.set(Value::I64(8))?;
+
+    let cloned = global.clone_into(&other_store)?;
+    assert!(Store::same(cloned.store(), &other_store));
+    assert_eq!(cloned.get(), Value::I64(8));
+    assert_eq!(cloned.ty(), &GlobalType::new(Type::I64, Mutability::Var));
+
+    // The clone is independent of the original.
+    cloned.set(Value::I64(9))?;
+    assert_eq!(global.get(), Value::I64(8));
+
+    let function = Function::new_native(&store, || {});
+    let global = Global::new(&store, Value::FuncRef(Some(function)));
+    assert!(global.clone_into(&other_store).is_err());
+    Ok(())
+}
+
+#[test]
+fn global_on_host_set() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (global $g (export "g") (mut i32) (i32.const 0))
+            (func (export "set_from_wasm") (param i32)
+                (global.set $g (local.get 0))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let global = instance.exports.get_global("g")?;
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    let seen_in_callback = seen.clone();
+    global.on_host_set(move |value| seen_in_callback.lock().unwrap().push(value.unwrap_i32()));
+
+    global.set(Value::I32(1))?;
+    assert_eq!(*seen.lock().unwrap(), vec![1]);
+
+    // Setting through another handle to the same global also notifies.
+    let other = instance.exports.get_global("g")?.clone();
+    other.set(Value::I32(2))?;
+    assert_eq!(*seen.lock().unwrap(), vec![1, 2]);
+
+    // A set with the wrong type fails and doesn't notify.
+    assert!(global.set(Value::I64(3)).is_err());
+    assert_eq!(*seen.lock().unwrap(), vec![1, 2]);
+
+    // Writes done by wasm aren't observed.
+    let set_from_wasm: NativeFunc<i32, ()> =
+        instance.exports.get_native_function("set_from_wasm")?;
+    set_from_wasm.call(4)?;
+    assert_eq!(global.get(), Value::I32(4));
+    assert_eq!(*seen.lock().unwrap(), vec![1, 2]);
+
+    // Immutable globals can't be set, so the callback never fires.
+    let constant = Global::new(&store, Value::I32(10));
+    let seen_in_callback = seen.clone();
+    constant.on_host_set(move |value| seen_in_callback.lock().unwrap().push(value.unwrap_i32()));
+    assert!(constant.set(Value::I32(20)).is_err());
+    assert_eq!(*seen.lock().unwrap(), vec![1, 2]);
+
+    Ok(())
+}
+
+#[test]
+fn global_on_host_set_gets_the_value_that_was_set() -> Result<()> {
+    let store = Store::default();
+    let global = Global::new_mut(&store, Value::I32(0));
+    let seen = Arc::new(Mutex::new(Vec::new()));
+
+    // The first callback sets the global again before the second one
+    // runs, which must still see the value of the outer set.
+    let reset = global.clone();
+    global.on_host_set(move |value| {
+        if value.unwrap_i32() != 0 {
+            reset.set(Value::I32(0)).unwrap();
+        }
+    });
+    let seen_in_callback = seen.clone();
+    global.on_host_set(move |value| seen_in_callback.lock().unwrap().push(value.unwrap_i32()));
+
+    global.set(Value::I32(7))?;
+    assert_eq!(global.get(), Value::I32(0));
+    assert_eq!(*seen.lock().unwrap(), vec![0, 7]);
+
+    Ok(())
+}
+
 #[test]
 fn table_new() -> Result<()> {
     let store = Store::default();
@@ -82,6 +242,57 @@ fn table_new() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn table_new_null() -> Result<()> {
+    let store = Store::default();
+    let table = Table::new_null(&store, TableType::new(Type::FuncRef, 16, Some(32)))?;
+    assert_eq!(table.size(), 16);
+    assert!(table.iter().all(|val| val.unwrap_funcref().is_none()));
+
+    let table = Table::new_null(&store, TableType::new(Type::ExternRef, 8, None))?;
+    assert_eq!(table.size(), 8);
+    assert!(table.iter().all(|val| val.unwrap_externref().is_null()));
+
+    // The table can then be filled in.
+    let table = Table::new_null(&store, TableType::new(Type::FuncRef, 2, None))?;
+    let f = Function::new_native(&store, || {});
+    table.set(1, Value::FuncRef(Some(f)))?;
+    assert!(table.get(0).unwrap().unwrap_funcref().is_none());
+    assert!(table.get(1).unwrap().unwrap_funcref().is_some());
+    Ok(())
+}
+
+#[test]
+fn table_new_too_large() -> Result<()> {
+    let store = Store::default();
+    let table_type = TableType {
+        ty: Type::FuncRef,
+        minimum: u32::MAX - 1,
+        maximum: None,
+    };
+    let error = Table::new(&store, table_type, Value::FuncRef(None)).unwrap_err();
+    assert!(error.message().contains("limit"));
+
+    let tunables = BaseTunables {
+        max_table_elements: 10,
+        ..BaseTunables::for_target(&Target::default())
+    };
+    let store = Store::new_with_tunables(&**store.engine(), tunables);
+    let table_type = TableType {
+        ty: Type::FuncRef,
+        minimum: 10,
+        maximum: None,
+    };
+    let table = Table::new(&store, table_type, Value::FuncRef(None))?;
+    assert_eq!(table.size(), 10);
+    let table_type = TableType {
+        minimum: 11,
+        ..table_type
+    };
+    assert!(Table::new(&store, table_type, Value::FuncRef(None)).is_err());
+    Ok(())
+}
+
 #[test]
 #[ignore]
 fn table_get() -> Result<()> {
@@ -127,6 +338,73 @@ fn table_grow() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn table_grow_beyond_maximum() -> Result<()> {
+    let store = Store::default();
+    let table_type = TableType::new(Type::FuncRef, 2, Some(10));
+    let table = Table::new(&store, table_type, Value::FuncRef(None))?;
+
+    // Growing up to the maximum succeeds.
+    assert_eq!(table.grow(8, Value::FuncRef(None))?, 2);
+    assert_eq!(table.size(), 10);
+    assert_eq!(table.grow(0, Value::FuncRef(None))?, 10);
+
+    // Growing past it reports the limit.
+    let err = table.grow(1, Value::FuncRef(None)).unwrap_err();
+    assert!(err.is::<TableGrowBeyondMaximum>());
+    assert_eq!(
+        err.downcast::<TableGrowBeyondMaximum>().unwrap(),
+        TableGrowBeyondMaximum {
+            current: 10,
+            maximum: 10,
+            requested: 1,
+        }
+    );
+    let err = table.grow(u32::MAX, Value::FuncRef(None)).unwrap_err();
+    assert!(err.is::<TableGrowBeyondMaximum>());
+    assert_eq!(table.size(), 10);
+
+    // Without a maximum, the table grows until it can't be indexed.
+    let table_type = TableType::new(Type::FuncRef, 0, None);
+    let table = Table::new(&store, table_type, Value::FuncRef(None))?;
+    assert_eq!(table.grow(100, Value::FuncRef(None))?, 0);
+    let err = table.grow(u32::MAX, Value::FuncRef(None)).unwrap_err();
+    assert!(!err.is::<TableGrowBeyondMaximum>());
+    assert_eq!(table.size(), 100);
+
+    Ok(())
+}
+
+#[test]
+fn table_iter() -> Result<()> {
+    let store = Store::default();
+    let table_type = TableType {
+        ty: Type::FuncRef,
+        minimum: 3,
+        maximum: None,
+    };
+    let f = Function::new_native(&store, |num: i32| num + 1);
+    let table = Table::new(&store, table_type, Value::FuncRef(None))?;
+    table.set(1, Value::FuncRef(Some(f.clone())))?;
+
+    let elements = table.iter();
+    table.grow(2, Value::FuncRef(Some(f.clone())))?;
+    // The iterator doesn't see the elements added after its creation.
+    let elements = elements.collect::<Vec<Val>>();
+    assert_eq!(elements.len(), 3);
+    assert!(matches!(elements[0], Value::FuncRef(None)));
+    match &elements[1] {
+        Value::FuncRef(Some(g)) => assert_eq!(g.ty(), f.ty()),
+        _ => panic!("expected a function"),
+    }
+    assert!(matches!(elements[2], Value::FuncRef(None)));
+
+    let elements = table.to_vec();
+    assert_eq!(elements.len(), 5);
+    assert!(matches!(elements[4], Value::FuncRef(Some(_))));
+    Ok(())
+}
+
 #[test]
 #[ignore]
 fn table_copy() -> Result<()> {
@@ -172,7 +450,467 @@ fn memory_grow() -> Result<()> {
     let bad_desc = MemoryType::new(Pages(15), Some(Pages(10)), false);
     let bad_result = Memory::new(&store, bad_desc);
 
-    assert!(matches!(bad_result, Err(MemoryError::InvalidMemory { .. })));
+    assert!(matches!(bad_result, Err(MemoryError::InvalidMemory { .. })));
+
+    Ok(())
+}
+
+#[test]
+fn memory_try_grow() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), Some(Pages(3)), false))?;
+
+    assert_eq!(memory.try_grow(Pages(2)), Some(Pages(1)));
+    assert_eq!(memory.size(), Pages(3));
+    // Growing by nothing always succeeds, like `memory.grow 0`.
+    assert_eq!(memory.try_grow(Pages(0)), Some(Pages(3)));
+
+    assert_eq!(memory.try_grow(Pages(1)), None);
+    assert_eq!(memory.size(), Pages(3));
+    // `grow` reports the same failure as an error, for `?`.
+    assert!(matches!(
+        memory.grow(Pages(1)),
+        Err(MemoryError::CouldNotGrow { .. })
+    ));
+    assert_eq!(memory.size(), Pages(3));
+
+    Ok(())
+}
+
+#[test]
+fn memory_grow_and_view() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), Some(Pages(4)), false))?;
+    memory.view::<u32>()[0].set(7);
+
+    let (size, view) = memory.grow_and_view::<u32>(Pages(2))?;
+    assert_eq!(size, Pages(3));
+    assert_eq!(memory.size(), Pages(3));
+    assert_eq!(view.len(), Pages(3).bytes().0 / 4);
+    // The new pages are zeroed and writable through the view.
+    let first_new = Pages(1).bytes().0 / 4;
+    assert!(view[first_new..].iter().all(|cell| cell.get() == 0));
+    view[first_new].set(42);
+    view[view.len() - 1].set(43);
+    assert_eq!(view[0].get(), 7);
+    assert_eq!(memory.read_struct::<u32>(Pages(1).bytes().0 as u64)?, 42);
+    assert_eq!(memory.read_struct::<u32>(memory.data_size() - 4)?, 43);
+
+    assert!(matches!(
+        memory.grow_and_view::<u8>(Pages(2)),
+        Err(MemoryError::CouldNotGrow { .. })
+    ));
+    assert_eq!(memory.size(), Pages(3));
+
+    Ok(())
+}
+
+#[test]
+fn memory_new_with_style() -> Result<()> {
+    let store = Store::default();
+    let ty = MemoryType::new(Pages(1), None, false);
+
+    // A static memory reserves its bound up front, so it never moves
+    // and can't grow past the bound.
+    let static_style = vm::MemoryStyle::Static {
+        bound: Pages(4),
+        offset_guard_size: 0,
+    };
+    let memory = Memory::new_with_style(&store, ty, static_style.clone())?;
+    assert_eq!(memory.style(), &static_style);
+    let base = memory.data_ptr();
+    assert_eq!(memory.grow(Pages(3))?, Pages(1));
+    assert_eq!(memory.data_ptr(), base);
+    assert!(matches!(
+        memory.grow(Pages(1)),
+        Err(MemoryError::CouldNotGrow { .. })
+    ));
+    assert_eq!(memory.size(), Pages(4));
+
+    // A dynamic memory only reserves what it uses and grows as long as
+    // the memory type allows it.
+    let dynamic_style = vm::MemoryStyle::Dynamic {
+        offset_guard_size: 0,
+    };
+    let memory = Memory::new_with_style(&store, ty, dynamic_style.clone())?;
+    assert_eq!(memory.style(), &dynamic_style);
+    assert_eq!(memory.grow(Pages(7))?, Pages(1));
+    assert_eq!(memory.size(), Pages(8));
+    memory.view::<u8>()[8 * WASM_PAGE_SIZE - 1].set(42);
+    assert_eq!(memory.view::<u8>()[8 * WASM_PAGE_SIZE - 1].get(), 42);
+
+    // The static bound must cover the minimum.
+    let too_small = vm::MemoryStyle::Static {
+        bound: Pages(1),
+        offset_guard_size: 0,
+    };
+    let result = Memory::new_with_style(&store, MemoryType::new(Pages(2), None, false), too_small);
+    assert!(matches!(result, Err(MemoryError::InvalidMemory { .. })));
+
+    // A 4GiB reservation can't be made on 32-bit hosts.
+    #[cfg(target_pointer_width = "32")]
+    {
+        let huge = vm::MemoryStyle::Static {
+            bound: Pages::max_value(),
+            offset_guard_size: 0x8000_0000,
+        };
+        let result = Memory::new_with_style(&store, ty, huge);
+        assert!(matches!(result, Err(MemoryError::Region(_))));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn memory_with_data() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+
+    unsafe {
+        memory.with_data_mut(|data| {
+            for (i, byte) in data.iter_mut().take(4).enumerate() {
+                *byte = 0xa0 + i as u8;
+            }
+        });
+    }
+
+    let (len, head) = unsafe { memory.with_data(|data| (data.len(), data[..5].to_vec())) };
+    assert_eq!(len, WASM_PAGE_SIZE);
+    assert_eq!(head, vec![0xa0, 0xa1, 0xa2, 0xa3, 0]);
+
+    Ok(())
+}
+
+#[test]
+fn memory_prefault() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+    memory.grow(Pages(2))?;
+    let size = memory.data_size();
+    memory.write_slice(WASM_PAGE_SIZE as u64 + 7, &[1u8, 2, 3])?;
+
+    // Ranges that don't start or end on a page boundary are fine.
+    memory.prefault(WASM_PAGE_SIZE as u64 - 1..size - 1)?;
+    memory.prefault(0..size)?;
+    memory.prefault(size..size)?;
+    let snapshot = memory.snapshot(0..size)?;
+    let written = WASM_PAGE_SIZE + 7..WASM_PAGE_SIZE + 10;
+    assert_eq!(snapshot[written.clone()], [1, 2, 3]);
+    assert!(snapshot
+        .iter()
+        .enumerate()
+        .all(|(i, &byte)| written.contains(&i) || byte == 0));
+
+    assert_eq!(
+        memory.prefault(0..size + 1),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn memory_view_checked_access() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+    let view = memory.view::<u32>();
+    let length = view.len();
+
+    view.write(1, 0x0403_0201)?;
+    assert_eq!(view.read(1)?, 0x0403_0201);
+    assert_eq!(memory.view::<u8>()[5].get(), 0x02);
+    assert_eq!(
+        view.read(length),
+        Err(MemoryViewError::OutOfBounds {
+            index: length,
+            length
+        })
+    );
+
+    // A view whose base is one byte past a 4 bytes boundary.
+    let misaligned: MemoryView<u32> =
+        unsafe { MemoryView::new(memory.data_ptr().add(1) as *mut u32, 4) };
+    match misaligned.read(0) {
+        Err(MemoryViewError::Misaligned { align: 4, .. }) => {}
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert!(misaligned.write(2, 0).is_err());
+    Ok(())
+}
+
+#[test]
+fn memory_zero() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(2), None, false))?;
+    memory.grow(Pages(1))?;
+    let view = memory.view::<u8>();
+    for (i, cell) in view.iter().enumerate() {
+        cell.set(i as u8 | 1);
+    }
+
+    memory.zero()?;
+
+    assert_eq!(memory.size(), Pages(3));
+    assert!(memory.view::<u8>().iter().all(|cell| cell.get() == 0));
+
+    // The memory is still usable after being reset.
+    memory.view::<u8>()[WASM_PAGE_SIZE].set(7);
+    assert_eq!(memory.view::<u8>()[WASM_PAGE_SIZE].get(), 7);
+    Ok(())
+}
+
+#[test]
+fn memory_is_shared() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), Some(Pages(2)), false))?;
+    assert!(!memory.is_shared());
+
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), Some(Pages(2)), true))?;
+    assert!(memory.is_shared());
+    assert_eq!(memory.is_shared(), memory.ty().shared);
+    Ok(())
+}
+
+#[test]
+fn memory_cstr() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+
+    memory.write_cstr(16, "hello")?;
+    assert_eq!(memory.read_cstr(16, 5)?, "hello");
+    assert_eq!(memory.read_cstr(16, 100)?, "hello");
+    assert_eq!(memory.read_cstr(21, 100)?, "");
+    assert_eq!(
+        memory.write_cstr(16, "a\0b"),
+        Err(MemoryAccessError::InteriorNul)
+    );
+
+    // The terminator must be within `max_len` bytes of the string.
+    assert_eq!(memory.read_cstr(16, 4), Err(MemoryAccessError::NulNotFound));
+
+    // Invalid UTF-8 is only accepted by the lossy variant.
+    memory.view::<u8>()[17].set(0xff);
+    assert_eq!(
+        memory.read_cstr(16, 100),
+        Err(MemoryAccessError::NonUtf8String)
+    );
+    assert_eq!(memory.read_cstr_lossy(16, 100)?, "h\u{fffd}llo");
+
+    // A string whose terminator is the last byte of the memory.
+    let size = memory.data_size();
+    memory.write_cstr(size - 4, "end")?;
+    assert_eq!(memory.read_cstr(size - 4, 100)?, "end");
+    assert_eq!(memory.read_cstr(size - 1, 100)?, "");
+    assert_eq!(
+        memory.write_cstr(size - 3, "end"),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+
+    // A string running to the end of the memory without a terminator.
+    memory.view::<u8>()[size as usize - 1].set(b'!');
+    assert_eq!(
+        memory.read_cstr(size - 4, 100),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    assert_eq!(
+        memory.read_cstr(size, 100),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    assert_eq!(
+        memory.read_cstr(size + 1, 100),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    Ok(())
+}
+
+#[test]
+fn memory_struct_round_trip() -> Result<()> {
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    #[repr(C)]
+    struct Header {
+        magic: u32,
+        flags: u16,
+        kind: u8,
+        version: u8,
+        length: u64,
+    }
+
+    unsafe impl ValueType for Header {}
+
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+    let header = Header {
+        magic: 0xdead_beef,
+        flags: 0x1234,
+        kind: 7,
+        version: 2,
+        length: u64::MAX - 1,
+    };
+
+    memory.write_struct(24, &header)?;
+    assert_eq!(memory.read_struct::<Header>(24)?, header);
+    // Fields are laid out as in a C struct, in the host byte order.
+    assert_eq!(memory.read_struct::<u32>(24)?, 0xdead_beef);
+    assert_eq!(memory.read_struct::<u16>(28)?, 0x1234);
+    assert_eq!(memory.read_struct::<u64>(32)?, u64::MAX - 1);
+
+    let size = memory.data_size();
+    memory.write_struct(size - 16, &header)?;
+    assert_eq!(memory.read_struct::<Header>(size - 16)?, header);
+    assert_eq!(
+        memory.write_struct(size - 8, &header),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    assert_eq!(
+        memory.read_struct::<Header>(size - 8),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    assert_eq!(
+        memory.read_struct::<Header>(u64::MAX - 7),
+        Err(MemoryAccessError::Overflow)
+    );
+    assert_eq!(
+        memory.write_struct(4, &header),
+        Err(MemoryAccessError::Unaligned)
+    );
+    assert_eq!(
+        memory.read_struct::<Header>(12),
+        Err(MemoryAccessError::Unaligned)
+    );
+    Ok(())
+}
+
+#[test]
+fn memory_slice_round_trip() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+    let values = [1u32, 0xdead_beef, u32::MAX, 42];
+
+    // A length-prefixed array, as guests commonly pass them.
+    memory.write_struct(64, &(values.len() as u32))?;
+    memory.write_slice(68, &values)?;
+    let len = memory.read_struct::<u32>(64)? as usize;
+    assert_eq!(memory.read_slice::<u32>(68, len)?, values);
+    assert_eq!(memory.read_slice::<u32>(72, 2)?, &values[1..3]);
+    assert_eq!(memory.read_struct::<u32>(72)?, 0xdead_beef);
+
+    // Reading nothing gives an empty array.
+    assert_eq!(memory.read_slice::<u32>(68, 0)?, Vec::<u32>::new());
+    memory.write_slice::<u32>(68, &[])?;
+    assert_eq!(memory.read_slice::<u32>(68, len)?, values);
+
+    let size = memory.data_size();
+    memory.write_slice(size - 16, &values)?;
+    assert_eq!(memory.read_slice::<u32>(size - 16, 4)?, values);
+    assert_eq!(memory.read_slice::<u32>(size, 0)?, Vec::<u32>::new());
+    assert_eq!(
+        memory.read_slice::<u32>(size - 12, 4),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    assert_eq!(
+        memory.write_slice(size - 12, &values),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    assert_eq!(
+        memory.read_slice::<u32>(u64::MAX - 3, 2),
+        Err(MemoryAccessError::Overflow)
+    );
+    assert_eq!(
+        memory.read_slice::<u64>(8, usize::MAX),
+        Err(MemoryAccessError::Overflow)
+    );
+    assert_eq!(
+        memory.read_slice::<u32>(66, 1),
+        Err(MemoryAccessError::Unaligned)
+    );
+    Ok(())
+}
+
+#[test]
+fn memory_atomic_wait_notify() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(1, Some(1), true))?;
+
+    assert_eq!(
+        memory.atomic_notify(2, 1),
+        Err(MemoryAccessError::Unaligned)
+    );
+    assert_eq!(
+        memory.atomic_wait64(4, 0, None),
+        Err(MemoryAccessError::Unaligned)
+    );
+    assert_eq!(
+        memory.atomic_notify(WASM_PAGE_SIZE as u64, 1),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    assert_eq!(
+        memory.atomic_wait32(0, 1, None)?,
+        AtomicWaitResult::NotEqual
+    );
+    assert_eq!(
+        memory.atomic_wait64(0, 0, Some(Duration::from_millis(10)))?,
+        AtomicWaitResult::TimedOut
+    );
+
+    let waiter = {
+        let memory = memory.clone();
+        thread::spawn(move || memory.atomic_wait32(8, 0, None))
+    };
+    // The waiter may not be waiting yet, so notify until it's woken.
+    while memory.atomic_notify(8, 1)? == 0 {
+        thread::yield_now();
+    }
+    assert_eq!(waiter.join().unwrap()?, AtomicWaitResult::Ok);
+
+    // Nobody can wait on a non-shared memory.
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    assert_eq!(
+        memory.atomic_wait32(0, 0, None)?,
+        AtomicWaitResult::TimedOut
+    );
+    assert_eq!(memory.atomic_notify(0, 1)?, 0);
+
+    Ok(())
+}
+
+#[test]
+fn memory_snapshot() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+    let view = memory.view::<u8>();
+    view[0].set(1);
+    view[1].set(2);
+    view[WASM_PAGE_SIZE - 1].set(3);
+
+    assert_eq!(memory.snapshot(0..3)?, vec![1, 2, 0]);
+
+    // An empty range is valid, even at the end of the memory.
+    assert_eq!(memory.snapshot(1..1)?, Vec::<u8>::new());
+    assert_eq!(
+        memory.snapshot(memory.data_size()..memory.data_size())?,
+        Vec::<u8>::new()
+    );
+
+    // A range ending exactly at the end of the memory is valid.
+    assert_eq!(
+        memory.snapshot(memory.data_size() - 2..memory.data_size())?,
+        vec![0, 3]
+    );
+
+    // A range past the end of the memory is rejected.
+    assert_eq!(
+        memory.snapshot(memory.data_size() - 2..memory.data_size() + 1),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+    assert_eq!(
+        memory.snapshot(memory.data_size() + 1..memory.data_size() + 2),
+        Err(MemoryAccessError::HeapOutOfBounds)
+    );
+
+    // The snapshot is not affected by later writes.
+    let snapshot = memory.snapshot(0..1)?;
+    view[0].set(4);
+    assert_eq!(snapshot, vec![1]);
 
     Ok(())
 }
@@ -374,6 +1112,46 @@ fn native_function_works() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn native_dynamic_host_function_works() -> Result<()> {
+    let store = Store::default();
+    let function = Function::new(
+        &store,
+        FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]),
+        |args| {
+            Ok(vec![Value::I32(
+                args[0].unwrap_i32() * args[1].unwrap_i32(),
+            )])
+        },
+    );
+    let native_function: NativeFunc<(i32, i32), i32> = function.native()?;
+    assert_eq!(native_function.call(6, 7)?, 42);
+
+    // The signature is checked when converting...
+    assert!(function.native::<(i32, i32), i64>().is_err());
+    assert!(function.native::<i32, i32>().is_err());
+
+    // ... and the returned values when calling.
+    let function = Function::new(&store, FunctionType::new(vec![], vec![Type::I32]), |_| {
+        Ok(vec![Value::I64(1), Value::I64(2)])
+    });
+    let native_function: NativeFunc<(), i32> = function.native()?;
+    assert!(native_function
+        .call()
+        .unwrap_err()
+        .message()
+        .contains("wrong signature"));
+
+    // Errors returned by the host function are passed along.
+    let function = Function::new(&store, FunctionType::new(vec![Type::I32], vec![]), |_| {
+        Err(RuntimeError::new("host error"))
+    });
+    let native_function: NativeFunc<i32, ()> = function.native()?;
+    assert_eq!(native_function.call(1).unwrap_err().message(), "host error");
+
+    Ok(())
+}
+
 #[test]
 fn function_outlives_instance() -> Result<()> {
     let store = Store::default();
@@ -400,6 +1178,231 @@ fn function_outlives_instance() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn function_prepared_call() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+  (func (export "sum") (param i32 i32) (result i32)
+    local.get 0
+    local.get 1
+    i32.add)
+  (func (export "spread") (param i32) (result i32 i64 i32)
+    local.get 0
+    local.get 0
+    i64.extend_i32_s
+    local.get 0
+    i32.const 1
+    i32.add)
+  (func (export "count") (param i32 i32 i32))
+  (func (export "fail") (param i32) (result i32)
+    unreachable))
+"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    // More params than results.
+    let sum = instance.exports.get_function("sum")?;
+    let mut prepared = sum.prepare_call()?;
+    let mut results = [Value::I32(0)];
+    for i in 0..100 {
+        let params = [Value::I32(i), Value::I32(i * 2)];
+        prepared.call(&params, &mut results)?;
+        assert_eq!(&results[..], &*sum.call(&params)?);
+        assert_eq!(results[0], Value::I32(i * 3));
+    }
+
+    // More results than params.
+    let spread = instance.exports.get_function("spread")?;
+    let mut prepared = spread.prepare_call()?;
+    let mut results = [Value::I32(0), Value::I64(0), Value::I32(0)];
+    for i in 0..100 {
+        let params = [Value::I32(i)];
+        prepared.call(&params, &mut results)?;
+        assert_eq!(&results[..], &*spread.call(&params)?);
+        assert_eq!(
+            results,
+            [Value::I32(i), Value::I64(i as i64), Value::I32(i + 1)]
+        );
+    }
+
+    // No results at all.
+    let count = instance.exports.get_function("count")?;
+    let mut prepared = count.prepare_call()?;
+    prepared.call(&[Value::I32(1), Value::I32(2), Value::I32(3)], &mut [])?;
+
+    // Arity and type mismatches are still reported.
+    assert!(prepared.call(&[Value::I32(1)], &mut []).is_err());
+    assert!(prepared
+        .call(&[Value::I32(1), Value::I32(2), Value::I64(3)], &mut [])
+        .is_err());
+    assert!(prepared
+        .call(
+            &[Value::I32(1), Value::I32(2), Value::I32(3)],
+            &mut [Value::I32(0)]
+        )
+        .is_err());
+
+    // Traps don't leave the prepared call unusable.
+    let fail = instance.exports.get_function("fail")?;
+    let mut prepared = fail.prepare_call()?;
+    let mut results = [Value::I32(0)];
+    assert!(prepared.call(&[Value::I32(1)], &mut results).is_err());
+    assert!(prepared.call(&[Value::I32(1)], &mut results).is_err());
+    assert_eq!(prepared.function(), fail);
+
+    Ok(())
+}
+
+#[test]
+fn function_call_coerced() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+  (func (export "wide") (param i64) (result i64)
+    local.get 0)
+  (func (export "narrow") (param i32) (result i32)
+    local.get 0)
+  (func (export "float") (param f32 f64) (result f64)
+    local.get 0
+    f64.promote_f32
+    local.get 1
+    f64.add))
+"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let wide = instance.exports.get_function("wide")?;
+    let narrow = instance.exports.get_function("narrow")?;
+    let float = instance.exports.get_function("float")?;
+
+    // `call` stays strict.
+    assert!(wide.call(&[Value::I32(-1)]).is_err());
+
+    // An i32 is extended to an i64 as asked.
+    assert_eq!(
+        &*wide.call_coerced(&[Value::I32(-1)], IntegerExtension::Signed)?,
+        &[Value::I64(-1)]
+    );
+    assert_eq!(
+        &*wide.call_coerced(&[Value::I32(-1)], IntegerExtension::Unsigned)?,
+        &[Value::I64(0xffff_ffff)]
+    );
+
+    // An i64 fits in an i32 only if the integer is kept.
+    assert_eq!(
+        &*narrow.call_coerced(&[Value::I64(-5)], IntegerExtension::Signed)?,
+        &[Value::I32(-5)]
+    );
+    assert_eq!(
+        &*narrow.call_coerced(&[Value::I64(0xffff_ffff)], IntegerExtension::Unsigned)?,
+        &[Value::I32(-1)]
+    );
+    assert!(narrow
+        .call_coerced(&[Value::I64(0xffff_ffff)], IntegerExtension::Signed)
+        .is_err());
+    assert!(narrow
+        .call_coerced(&[Value::I64(-1)], IntegerExtension::Unsigned)
+        .is_err());
+
+    // Floats are never coerced to integers.
+    let err = narrow
+        .call_coerced(&[Value::F64(2.5)], IntegerExtension::Signed)
+        .unwrap_err();
+    assert_eq!(
+        err.message(),
+        "cannot coerce parameter 0 from F64(2.5) to I32"
+    );
+    assert!(narrow
+        .call_coerced(&[Value::F64(2.0)], IntegerExtension::Signed)
+        .is_err());
+
+    // Integers and floats become floats when they are exact.
+    assert_eq!(
+        &*float.call_coerced(&[Value::I32(3), Value::F32(0.5)], IntegerExtension::Signed)?,
+        &[Value::F64(3.5)]
+    );
+    assert_eq!(
+        &*float.call_coerced(
+            &[Value::F64(0.5), Value::I64(1 << 40)],
+            IntegerExtension::Signed
+        )?,
+        &[Value::F64(1099511627776.5)]
+    );
+    assert!(float
+        .call_coerced(
+            &[Value::I32((1 << 24) + 1), Value::F64(0.0)],
+            IntegerExtension::Signed
+        )
+        .is_err());
+    assert!(float
+        .call_coerced(
+            &[Value::F64(0.1), Value::F64(0.0)],
+            IntegerExtension::Signed
+        )
+        .is_err());
+    assert!(float
+        .call_coerced(
+            &[Value::F32(0.0), Value::I64((1 << 53) + 1)],
+            IntegerExtension::Signed
+        )
+        .is_err());
+
+    // Arity mismatches are reported as by `call`.
+    assert!(wide.call_coerced(&[], IntegerExtension::Signed).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn function_call_with_metrics() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+  (import "host" "sleep" (func $sleep))
+  (import "host" "reenter" (func $reenter))
+  (func (export "run")
+    call $sleep
+    call $reenter)
+  (func (export "inner")
+    call $sleep))
+"#;
+    let module = Module::new(&store, wat)?;
+
+    #[derive(WasmerEnv, Clone, Default)]
+    struct Env {
+        #[wasmer(export)]
+        inner: LazyInit<Function>,
+    }
+
+    let sleep = Function::new_native(&store, || thread::sleep(Duration::from_millis(50)));
+    let reenter = Function::new_native_with_env(&store, Env::default(), |env: &Env| {
+        env.inner_ref().unwrap().call(&[]).unwrap();
+    });
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "sleep" => sleep,
+                "reenter" => reenter,
+            },
+        },
+    )?;
+
+    let (_, metrics) = instance
+        .exports
+        .get_function("inner")?
+        .call_with_metrics(&[])?;
+    assert!(metrics.host_import_time >= Duration::from_millis(50));
+    assert!(metrics.host_import_time <= metrics.wall_time);
+
+    // The nested call to `sleep` made through `reenter` is accounted
+    // for by `reenter` only.
+    let (_, metrics) = instance
+        .exports
+        .get_function("run")?
+        .call_with_metrics(&[])?;
+    assert!(metrics.host_import_time >= Duration::from_millis(100));
+    assert!(metrics.host_import_time <= metrics.wall_time);
+    Ok(())
+}
+
 #[test]
 fn weak_instance_ref_externs_after_instance() -> Result<()> {
     let store = Store::default();
@@ -456,3 +1459,724 @@ fn manually_generate_wasmer_env() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn dynamic_functions_free_their_context() -> Result<()> {
+    let store = Store::default();
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        token: Arc<()>,
+    }
+
+    // Both the environment and the closure of a dynamic function are
+    // freed once the function and the instances importing it are gone.
+    let token = Arc::new(());
+    let module = Module::new(
+        &store,
+        r#"(module (import "host" "f" (func $f)) (func (export "run") (call $f)))"#,
+    )?;
+    for _ in 0..1000 {
+        let env = Env {
+            token: token.clone(),
+        };
+        let closure_token = token.clone();
+        let f = Function::new_with_env(
+            &store,
+            FunctionType::new(vec![], vec![]),
+            env,
+            move |_, _| {
+                let _ = &closure_token;
+                Ok(vec![])
+            },
+        );
+        let instance = Instance::new(&module, &imports! { "host" => { "f" => f } })?;
+        instance.exports.get_function("run")?.call(&[])?;
+    }
+    assert_eq!(Arc::strong_count(&token), 1);
+
+    Ok(())
+}
+
+#[test]
+fn infallible_native_function_matches_native_function() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "multiply" (func $multiply (param i32 i32) (result i32)))
+            (import "env" "checked_div" (func $checked_div (param i32 i32) (result i32)))
+            (func (export "multiply") (param i32 i32) (result i32)
+                (call $multiply (local.get 0) (local.get 1)))
+            (func (export "checked_div") (param i32 i32) (result i32)
+                (call $checked_div (local.get 0) (local.get 1))))"#,
+    )?;
+
+    fn multiply(a: i32, b: i32) -> i32 {
+        a.wrapping_mul(b)
+    }
+    fn checked_div(a: i32, b: i32) -> Result<i32, RuntimeError> {
+        a.checked_div(b)
+            .ok_or_else(|| RuntimeError::new("division failed"))
+    }
+
+    let regular = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "multiply" => Function::new_native(&store, multiply),
+                "checked_div" => Function::new_native(&store, checked_div),
+            },
+        },
+    )?;
+    let (infallible_multiply, infallible_checked_div) = unsafe {
+        (
+            Function::new_native_infallible(&store, multiply),
+            Function::new_native_infallible(&store, checked_div),
+        )
+    };
+    let infallible = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "multiply" => infallible_multiply,
+                "checked_div" => infallible_checked_div,
+            },
+        },
+    )?;
+
+    for instance in &[&regular, &infallible] {
+        let multiply: NativeFunc<(i32, i32), i32> =
+            instance.exports.get_native_function("multiply")?;
+        let checked_div: NativeFunc<(i32, i32), i32> =
+            instance.exports.get_native_function("checked_div")?;
+        assert_eq!(multiply.call(6, 7)?, 42);
+        assert_eq!(multiply.call(i32::MAX, 2)?, -2);
+        assert_eq!(checked_div.call(42, 6)?, 7);
+
+        // Errors returned by the host function are still turned into traps.
+        let err = checked_div.call(1, 0).unwrap_err();
+        assert_eq!(err.message(), "division failed");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn memory_and_table_identity() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let other_memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    assert!(Memory::same(&memory, &memory.clone()));
+    assert!(!Memory::same(&memory, &other_memory));
+
+    let table = Table::new(
+        &store,
+        TableType::new(Type::FuncRef, 1, None),
+        Value::FuncRef(None),
+    )?;
+    let other_table = Table::new(
+        &store,
+        TableType::new(Type::FuncRef, 1, None),
+        Value::FuncRef(None),
+    )?;
+    assert!(Table::same(&table, &table.clone()));
+    assert!(!Table::same(&table, &other_table));
+
+    // Imported externs are re-exported as they are.
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "memory" (memory 1))
+            (import "env" "table" (table 1 funcref))
+            (export "memory" (memory 0))
+            (export "table" (table 0)))"#,
+    )?;
+    let import_object = imports! {
+        "env" => {
+            "memory" => memory.clone(),
+            "table" => table.clone(),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    assert!(memory.same(instance.exports.get_memory("memory")?));
+    assert!(table.same(instance.exports.get_table("table")?));
+    assert!(!other_memory.same(instance.exports.get_memory("memory")?));
+
+    Ok(())
+}
+
+#[test]
+fn frozen_store_rejects_new_definitions() -> Result<()> {
+    let store = Store::default();
+    let sum = Function::new_native(&store, |a: i32, b: i32| a + b);
+    let global = Global::new_mut(&store, Value::I32(1));
+    let memory = Memory::new(&store, MemoryType::new(1, Some(2), false))?;
+
+    assert!(!store.is_frozen());
+    store.freeze();
+    assert!(store.is_frozen());
+    assert!(store.clone().is_frozen());
+
+    let result = panic::catch_unwind(AssertUnwindSafe(|| Function::new_native(&store, || {})));
+    assert!(result.is_err());
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        Function::new(&store, FunctionType::new(vec![], vec![]), |_| Ok(vec![]))
+    }));
+    assert!(result.is_err());
+    let result = panic::catch_unwind(AssertUnwindSafe(|| Global::new(&store, Value::I32(1))));
+    assert!(result.is_err());
+    assert!(matches!(
+        Function::try_new_native(&store, || {}),
+        Err(StoreFrozen)
+    ));
+    assert!(matches!(
+        Function::try_new(&store, FunctionType::new(vec![], vec![]), |_| Ok(vec![])),
+        Err(StoreFrozen)
+    ));
+    let error = Global::try_new_mut(&store, Value::I32(1)).unwrap_err();
+    assert_eq!(error.downcast::<StoreFrozen>().unwrap(), StoreFrozen);
+    assert_eq!(
+        Memory::new(&store, MemoryType::new(1, None, false)).unwrap_err(),
+        MemoryError::StoreFrozen
+    );
+    let error = Table::new(
+        &store,
+        TableType::new(Type::FuncRef, 1, None),
+        Value::FuncRef(None),
+    )
+    .unwrap_err();
+    assert!(error.is::<StoreFrozen>());
+
+    // What was defined before freezing keeps working.
+    let result = sum.call(&[Value::I32(1), Value::I32(2)])?;
+    assert_eq!(result[0], Value::I32(3));
+    global.set(Value::I32(2))?;
+    assert_eq!(global.get(), Value::I32(2));
+    memory.grow(1)?;
+
+    // And so does instantiating modules.
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "sum" (func $sum (param i32 i32) (result i32)))
+            (func (export "run") (result i32)
+                (call $sum (i32.const 20) (i32.const 22))))"#,
+    )?;
+    let import_object = imports! {
+        "env" => {
+            "sum" => sum,
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+    assert_eq!(run.call()?, 42);
+
+    Ok(())
+}
+
+#[test]
+fn nan_payloads_are_preserved() -> Result<()> {
+    let store = Store::default();
+    // Signaling NaNs with a payload, which a round-trip through the x87
+    // floating point registers would quiet.
+    let f32_nan = f32::from_bits(0x7fa0_0001);
+    let f64_nan = f64::from_bits(0x7ff4_0000_0000_0001);
+
+    let global_f32 = Global::new_mut(&store, Value::F32(0.0));
+    global_f32.set(Value::F32(f32_nan))?;
+    assert_eq!(global_f32.get().unwrap_f32().to_bits(), 0x7fa0_0001);
+    let global_f64 = Global::new_mut(&store, Value::F64(f64_nan));
+    assert_eq!(
+        global_f64.get().unwrap_f64().to_bits(),
+        0x7ff4_0000_0000_0001
+    );
+
+    let module = Module::new(
+        &store,
+        r#"(module
+            (func (export "id_f32") (param f32) (result f32) (local.get 0))
+            (func (export "id_f64") (param f64) (result f64) (local.get 0)))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let id_f32 = instance.exports.get_function("id_f32")?;
+    let result = id_f32.call(&[Value::F32(f32_nan)])?;
+    assert_eq!(result[0].unwrap_f32().to_bits(), 0x7fa0_0001);
+    let id_f64 = instance.exports.get_function("id_f64")?;
+    let result = id_f64.call(&[Value::F64(f64_nan)])?;
+    assert_eq!(result[0].unwrap_f64().to_bits(), 0x7ff4_0000_0000_0001);
+
+    Ok(())
+}
+
+#[test]
+fn memory_on_grow_observes_guest_growth() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "grow") (param i32) (result i32)
+                (memory.grow (local.get 0))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?.clone();
+
+    let observed = Arc::new(Mutex::new(Vec::new()));
+    let observed_clone = observed.clone();
+    let _observer = memory.on_grow(move |old, new, base| {
+        observed_clone
+            .lock()
+            .unwrap()
+            .push((old, new, base as usize));
+    })?;
+    // Growing the memory from its own callback is rejected.
+    let reentrant = Arc::new(Mutex::new(None));
+    let reentrant_clone = reentrant.clone();
+    let memory_clone = memory.clone();
+    let reentrant_observer = memory.on_grow(move |_, _, _| {
+        *reentrant_clone.lock().unwrap() = Some(memory_clone.grow(1).is_err());
+    })?;
+
+    let grow = instance.exports.get_native_function::<i32, i32>("grow")?;
+    assert_eq!(grow.call(2)?, 1);
+    assert_eq!(
+        *observed.lock().unwrap(),
+        vec![(Pages(1), Pages(3), memory.data_ptr() as usize)]
+    );
+    assert_eq!(*reentrant.lock().unwrap(), Some(true));
+    assert_eq!(memory.size(), Pages(3));
+
+    // Growing by zero pages doesn't change the memory.
+    assert_eq!(grow.call(0)?, 3);
+    memory.grow(1)?;
+    assert_eq!(observed.lock().unwrap().len(), 2);
+    assert_eq!(observed.lock().unwrap()[1].1, Pages(4));
+
+    // Dropping the handle unregisters the callback, and with it the
+    // memory it holds.
+    *reentrant.lock().unwrap() = None;
+    drop(reentrant_observer);
+    memory.grow(1)?;
+    assert_eq!(*reentrant.lock().unwrap(), None);
+    assert_eq!(observed.lock().unwrap().len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn memory_grow_observer_outlives_memory() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let grown = Arc::new(AtomicBool::new(false));
+    let grown_clone = grown.clone();
+    let observer = memory.on_grow(move |_, _, _| grown_clone.store(true, Ordering::SeqCst))?;
+    memory.grow(1)?;
+    assert!(grown.load(Ordering::SeqCst));
+
+    // The handle doesn't keep the memory alive.
+    drop(memory);
+    assert_eq!(Arc::strong_count(&grown), 1);
+    drop(observer);
+
+    Ok(())
+}
+
+#[test]
+fn memory_on_grow_panic_is_a_host_panic() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "grow") (param i32) (result i32)
+                (memory.grow (local.get 0))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?.clone();
+    memory
+        .on_grow(|_, _, _| panic!("observer failed"))?
+        .detach();
+    let grow = instance.exports.get_native_function::<i32, i32>("grow")?;
+
+    // The panic unwinds through the wasm frames to the caller.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| grow.call(1)));
+    assert!(result.is_err());
+    assert_eq!(memory.size(), Pages(2));
+
+    // Or becomes a trap if the store catches host panics.
+    store.set_catch_host_panics(true);
+    let error = grow.call(1).unwrap_err();
+    assert_eq!(error.host_panic(), Some("observer failed"));
+    assert_eq!(memory.size(), Pages(3));
+
+    Ok(())
+}
+
+#[test]
+fn memory_with_pages() -> Result<()> {
+    let store = Store::default();
+
+    let memory = Memory::with_pages(&store, 2, Some(10), false)?;
+    assert_eq!(
+        memory.ty(),
+        MemoryType::new(Pages(2), Some(Pages(10)), false)
+    );
+    assert_eq!(memory.size(), Pages(2));
+
+    let shared = Memory::with_pages(&store, 1, Some(1), true)?;
+    assert!(shared.is_shared());
+
+    assert!(matches!(
+        Memory::with_pages(&store, 3, Some(2), false),
+        Err(MemoryError::InvalidMemory { .. })
+    ));
+    assert!(matches!(
+        Memory::with_pages(&store, 1, None, true),
+        Err(MemoryError::InvalidMemory { .. })
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn dynamic_function_unchecked_results() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "split" (func $split (param i64) (result i32 i32 f64)))
+            (func (export "run") (param i64) (result i32 i32 f64)
+                (call $split (local.get 0))))"#,
+    )?;
+    let signature = FunctionType::new(vec![Type::I64], vec![Type::I32, Type::I32, Type::F64]);
+    let split = |args: &[Val]| {
+        let value = args[0].unwrap_i64();
+        Ok(vec![
+            Value::I32(value as i32),
+            Value::I32((value >> 32) as i32),
+            Value::F64(value as f64),
+        ])
+    };
+
+    let checked = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "split" => Function::new(&store, &signature, split),
+            },
+        },
+    )?;
+    let unchecked = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "split" => unsafe { Function::new_dynamic_unchecked(&store, &signature, split) },
+            },
+        },
+    )?;
+
+    let checked = checked.exports.get_function("run")?;
+    let unchecked = unchecked.exports.get_function("run")?;
+    for value in &[0, 1, -1, 0x1234_5678_9abc_def0, i64::MIN] {
+        let args = [Value::I64(*value)];
+        assert_eq!(checked.call(&args)?, unchecked.call(&args)?);
+    }
+
+    // Errors are still reported.
+    let failing = unsafe {
+        Function::new_dynamic_unchecked(&store, &signature, |_| Err(RuntimeError::new("nope")))
+    };
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "split" => failing,
+            },
+        },
+    )?;
+    let error = instance
+        .exports
+        .get_function("run")?
+        .call(&[Value::I64(0)])
+        .unwrap_err();
+    assert_eq!(error.message(), "nope");
+
+    Ok(())
+}
+
+#[test]
+fn labeled_dynamic_function_errors() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "print" (func $print (param i32) (result i32)))
+            (import "env" "flush" (func $flush (param i32) (result i32)))
+            (func (export "print") (param i32) (result i32) (call $print (local.get 0)))
+            (func (export "flush") (param i32) (result i32) (call $flush (local.get 0))))"#,
+    )?;
+    let signature = FunctionType::new(vec![Type::I32], vec![Type::I32]);
+    let print =
+        Function::new(&store, &signature, |_| Ok(vec![Value::I64(0)])).with_label("env::print");
+    let flush = Function::new(&store, &signature, |args| Ok(vec![args[0].clone()]))
+        .with_label("env::flush");
+    assert_eq!(print.label().as_deref(), Some("env::print"));
+    assert!(format!("{:?}", print).contains("env::print"));
+
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "print" => print.clone(),
+                "flush" => flush,
+            },
+        },
+    )?;
+    assert_eq!(
+        instance
+            .exports
+            .get_function("flush")?
+            .call(&[Value::I32(1)])?
+            .to_vec(),
+        vec![Value::I32(1)]
+    );
+    let error = instance
+        .exports
+        .get_function("print")?
+        .call(&[Value::I32(1)])
+        .unwrap_err();
+    assert_eq!(
+        error.message(),
+        "Dynamic function `env::print` returned wrong signature. Expected [I32] but got [I64]"
+    );
+
+    // The label is also reported when calling the function natively.
+    let error = print.native::<i32, i32>()?.call(1).unwrap_err();
+    assert!(error.message().contains("`env::print`"), "{}", error);
+    let error = print.native::<i64, i32>().unwrap_err();
+    assert!(error.message().contains("`env::print`"), "{}", error);
+
+    // Converting the native function back keeps the label.
+    let round_trip: Function = print.native::<i32, i32>()?.into();
+    assert_eq!(round_trip.label().as_deref(), Some("env::print"));
+
+    // The functions exported by an instance have no label.
+    assert_eq!(instance.exports.get_function("print")?.label(), None);
+
+    // Labeling a clone leaves the original function unlabeled.
+    let unlabeled = Function::new(&store, &signature, |_| Ok(vec![Value::I64(0)]));
+    let relabeled = unlabeled.clone().with_label("env::other");
+    assert_eq!(unlabeled.label(), None);
+    assert_eq!(relabeled.label().as_deref(), Some("env::other"));
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "print" => unlabeled,
+                "flush" => relabeled,
+            },
+        },
+    )?;
+    let error = instance
+        .exports
+        .get_function("print")?
+        .call(&[Value::I32(1)])
+        .unwrap_err();
+    assert_eq!(
+        error.message(),
+        "Dynamic function returned wrong signature. Expected [I32] but got [I64]"
+    );
+    let error = instance
+        .exports
+        .get_function("flush")?
+        .call(&[Value::I32(1)])
+        .unwrap_err();
+    assert!(error.message().contains("`env::other`"), "{}", error);
+
+    Ok(())
+}
+
+#[test]
+fn function_from_raw_parts() -> Result<()> {
+    use wasmer::vm::{VMContext, VMFunctionBody, VMFunctionKind};
+
+    extern "C" fn mul_add(_vmctx: *mut VMContext, a: i32, b: i64, c: f64) -> f64 {
+        a as f64 * b as f64 + c
+    }
+
+    let store = Store::default();
+    let signature = FunctionType::new(vec![Type::I32, Type::I64, Type::F64], vec![Type::F64]);
+    let function = unsafe {
+        Function::from_raw_parts(
+            &store,
+            &signature,
+            mul_add as *const VMFunctionBody,
+            std::ptr::null_mut(),
+            VMFunctionKind::Static,
+        )
+    };
+    assert_eq!(function.ty(), &signature);
+
+    // Called from the host.
+    let native = function.native::<(i32, i64, f64), f64>()?;
+    assert_eq!(native.call(3, 4, 0.5)?, 12.5);
+
+    // Called from Wasm.
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "mul_add" (func $mul_add (param i32 i64 f64) (result f64)))
+            (func (export "run") (param i32 i64 f64) (result f64)
+                (call $mul_add (local.get 0) (local.get 1) (local.get 2))))"#,
+    )?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "mul_add" => function,
+            },
+        },
+    )?;
+    let run = instance.exports.get_function("run")?;
+    assert_eq!(
+        run.call(&[Value::I32(-2), Value::I64(5), Value::F64(1.0)])?
+            .to_vec(),
+        vec![Value::F64(-9.0)]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn store_shared_env() -> Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    struct Counter {
+        value: Mutex<i32>,
+        dropped: Arc<AtomicBool>,
+    }
+
+    impl Drop for Counter {
+        fn drop(&mut self) {
+            self.dropped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    let dropped = Arc::new(AtomicBool::new(false));
+    let store = Store::default();
+    let counter = store.register_env(Counter {
+        value: Mutex::new(0),
+        dropped: dropped.clone(),
+    });
+
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "add" (func $add (param i32)))
+            (import "env" "double" (func $double))
+            (import "env" "get" (func $get (result i32)))
+            (func (export "run") (result i32)
+                (call $add (i32.const 3))
+                (call $double)
+                (call $add (i32.const 1))
+                (call $get)))"#,
+    )?;
+    let imports = imports! {
+        "env" => {
+            "add" => Function::new_native_with_env(
+                &store,
+                counter.clone(),
+                |counter: &SharedEnv<Counter>, n: i32| *counter.value.lock().unwrap() += n,
+            ),
+            "double" => Function::new_native_with_env(
+                &store,
+                counter.clone(),
+                |counter: &SharedEnv<Counter>| *counter.value.lock().unwrap() *= 2,
+            ),
+            "get" => Function::new_native_with_env(
+                &store,
+                counter.clone(),
+                |counter: &SharedEnv<Counter>| *counter.value.lock().unwrap(),
+            ),
+        },
+    };
+    let instance = Instance::new(&module, &imports)?;
+    let run = instance.exports.get_native_function::<(), i32>("run")?;
+
+    assert_eq!(run.call()?, 7);
+    assert_eq!(run.call()?, 15);
+    assert_eq!(*counter.value.lock().unwrap(), 15);
+
+    // The functions and the store keep the environment alive.
+    drop(counter);
+    drop(run);
+    drop(instance);
+    drop(imports);
+    drop(module);
+    assert!(!dropped.load(Ordering::SeqCst));
+    drop(store);
+    assert!(dropped.load(Ordering::SeqCst));
+
+    Ok(())
+}
+
+#[test]
+fn memory_view_copy_to_vec_and_fill() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+    let view = memory.view::<u16>();
+
+    for (i, cell) in view[10..14].iter().enumerate() {
+        cell.set(i as u16 + 1);
+    }
+    assert_eq!(view.copy_to_vec(10..14), vec![1, 2, 3, 4]);
+    assert_eq!(view.copy_to_vec(10..10), Vec::<u16>::new());
+
+    view.fill(11..13, 0xabcd);
+    assert_eq!(view.copy_to_vec(9..15), vec![0, 1, 0xabcd, 0xabcd, 4, 0]);
+
+    let length = view.len();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| view.fill(length - 1..length + 1, 0)));
+    assert!(result.is_err());
+    assert_eq!(view[length - 1].get(), 0);
+    Ok(())
+}
+
+#[test]
+fn memory_access_log() -> Result<()> {
+    let store = Store::default();
+    let memory = Memory::new(&store, MemoryType::new(Pages(1), None, false))?;
+    let accesses = Arc::new(Mutex::new(Vec::new()));
+    let log = accesses.clone();
+    memory.enable_access_log(move |access| {
+        log.lock()
+            .unwrap()
+            .push((access.kind, access.offset, access.len));
+    })?;
+
+    // Accesses through any handle to the memory are logged.
+    let other = memory.clone();
+    other.write_struct(16, &7u64)?;
+    memory.write_cstr(100, "hello")?;
+    assert_eq!(memory.read_cstr(100, 64)?, "hello");
+    assert_eq!(memory.read_struct::<u64>(16)?, 7);
+    memory.snapshot(32..40)?;
+    // Failed accesses aren't.
+    assert!(memory.snapshot(0..memory.data_size() + 1).is_err());
+
+    assert_eq!(
+        *accesses.lock().unwrap(),
+        vec![
+            (MemoryAccessKind::Write, 16, 8),
+            (MemoryAccessKind::Write, 100, 6),
+            (MemoryAccessKind::Read, 100, 6),
+            (MemoryAccessKind::Read, 16, 8),
+            (MemoryAccessKind::Read, 32, 8),
+        ]
+    );
+
+    memory.disable_access_log()?;
+    memory.snapshot(0..1)?;
+    assert_eq!(accesses.lock().unwrap().len(), 5);
+    Ok(())
+}
@@ -37,3 +37,933 @@ fn exports_work_after_multiple_instances_have_been_freed() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn store_limits_live_instances() -> Result<()> {
+    let store = Store::default();
+    store.set_max_instances(Some(2));
+    let module = Module::new(&store, "(module (memory 1))")?;
+
+    let import_object = ImportObject::new();
+    let instance1 = Instance::new(&module, &import_object)?;
+    let _instance2 = Instance::new(&module, &import_object)?;
+    assert_eq!(store.live_instances(), 2);
+    assert!(matches!(
+        Instance::new(&module, &import_object),
+        Err(InstantiationError::ResourceLimitExceeded(_))
+    ));
+
+    drop(instance1);
+    assert_eq!(store.live_instances(), 1);
+    let _instance3 = Instance::new(&module, &import_object)?;
+
+    Ok(())
+}
+
+#[test]
+fn store_limits_memory_pages() -> Result<()> {
+    let store = Store::default();
+    store.set_max_memory_pages(Some(Pages(3)));
+    let module = Module::new(&store, "(module (memory (export \"mem\") 2))")?;
+
+    let import_object = ImportObject::new();
+    let instance = Instance::new(&module, &import_object)?;
+    assert_eq!(store.committed_memory_pages(), Pages(2));
+    assert!(matches!(
+        Instance::new(&module, &import_object),
+        Err(InstantiationError::ResourceLimitExceeded(_))
+    ));
+
+    let memory = instance.exports.get_memory("mem")?.clone();
+    memory.grow(1)?;
+    assert_eq!(store.committed_memory_pages(), Pages(3));
+    assert!(memory.grow(1).is_err());
+
+    drop(instance);
+    drop(memory);
+    assert_eq!(store.committed_memory_pages(), Pages(0));
+    let _instance = Instance::new(&module, &import_object)?;
+
+    Ok(())
+}
+
+#[test]
+fn store_memory_usage_report() -> Result<()> {
+    let store = Store::default();
+    assert_eq!(store.report_memory_usage(), StoreMemoryReport::default());
+    let module1 = Module::new(
+        &store,
+        "(module (memory 1) (table 10 funcref) (global (mut i32) (i32.const 0)))",
+    )?;
+    let module2 = Module::new(
+        &store,
+        "(module (memory (export \"mem\") 2) (func (export \"f\")))",
+    )?;
+    let import_object = ImportObject::new();
+
+    let instance1 = Instance::new(&module1, &import_object)?;
+    let one = store.report_memory_usage();
+    assert_eq!(one.instances, 1);
+    assert_eq!(one.memory_bytes, Pages(1).bytes().0);
+    assert!(one.table_bytes >= 10);
+    assert!(one.global_bytes > 0);
+    assert!(one.code_bytes > 0);
+
+    let instance2 = Instance::new(&module2, &import_object)?;
+    let instance3 = Instance::new(&module2, &import_object)?;
+    let three = store.report_memory_usage();
+    assert_eq!(three.instances, 3);
+    assert_eq!(three.memory_bytes, Pages(5).bytes().0);
+    assert_eq!(three.table_bytes, one.table_bytes);
+    assert!(three.code_bytes > one.code_bytes);
+    assert!(three.total() > one.total());
+
+    // The instances of another store aren't counted, even if they
+    // import a memory of this one.
+    let other_store = Store::default();
+    let other_module = Module::new(
+        &other_store,
+        "(module (import \"env\" \"mem\" (memory 1)) (table 5 funcref))",
+    )?;
+    let memory = instance2.exports.get_memory("mem")?.clone();
+    let other_instance = Instance::new(&other_module, &imports! { "env" => { "mem" => memory } })?;
+    assert_eq!(store.report_memory_usage(), three);
+    assert_eq!(other_store.report_memory_usage().memory_bytes, 0);
+    drop(other_instance);
+
+    // The code of a module is counted until its last instance is gone.
+    drop(instance3);
+    let two = store.report_memory_usage();
+    assert_eq!(two.instances, 2);
+    assert_eq!(two.memory_bytes, Pages(3).bytes().0);
+    assert_eq!(two.code_bytes, three.code_bytes);
+    drop(instance2);
+    drop(instance1);
+    assert_eq!(store.report_memory_usage(), StoreMemoryReport::default());
+
+    Ok(())
+}
+
+#[test]
+fn dynamic_import_reads_caller_memory() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"
+    (module
+      (import "env" "print" (func $print (param i32 i32)))
+      (memory 1)
+      (data (i32.const 16) "hello caller")
+      (func (export "run")
+        (call $print (i32.const 16) (i32.const 12))))
+"#,
+    )?;
+
+    let printed = Arc::new(Mutex::new(String::new()));
+    let print = {
+        let printed = printed.clone();
+        Function::new_with_caller(
+            &store,
+            FunctionType::new(vec![Type::I32, Type::I32], vec![]),
+            move |caller, args| {
+                let memory = caller
+                    .memory(0)
+                    .ok_or_else(|| RuntimeError::new("the caller has no memory"))?;
+                let (ptr, len) = (args[0].unwrap_i32() as usize, args[1].unwrap_i32() as usize);
+                let bytes = memory.view::<u8>()[ptr..ptr + len]
+                    .iter()
+                    .map(|cell| cell.get())
+                    .collect::<Vec<_>>();
+                *printed.lock().unwrap() = String::from_utf8(bytes).unwrap();
+                Ok(vec![])
+            },
+        )
+    };
+
+    let import_object = imports! {
+        "env" => {
+            "print" => print,
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    instance.exports.get_function("run")?.call(&[])?;
+    assert_eq!(*printed.lock().unwrap(), "hello caller");
+
+    Ok(())
+}
+
+#[test]
+fn import_global_mutability_mismatch() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, r#"(module (global (import "env" "g") (mut i32)))"#)?;
+    let import_object = imports! {
+        "env" => {
+            "g" => Global::new(&store, Value::I32(1)),
+        },
+    };
+
+    let error = Instance::new(&module, &import_object).unwrap_err();
+    match &error {
+        InstantiationError::Link(LinkError::Import(
+            module,
+            name,
+            ImportError::IncompatibleType(expected, provided, mismatch),
+        )) => {
+            assert_eq!((module.as_str(), name.as_str()), ("env", "g"));
+            assert_eq!(
+                expected,
+                &ExternType::Global(GlobalType::new(Type::I32, Mutability::Var))
+            );
+            assert_eq!(
+                provided,
+                &ExternType::Global(GlobalType::new(Type::I32, Mutability::Const))
+            );
+            assert_eq!(*mismatch, ExternTypeMismatch::Mutability);
+        }
+        _ => panic!("Unexpected error: {:?}", error),
+    }
+    assert!(error
+        .to_string()
+        .starts_with("Error while importing env::g"));
+    assert!(error.to_string().ends_with("the mutability differs"));
+    Ok(())
+}
+
+#[test]
+fn import_table_element_type_mismatch() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, r#"(module (table (import "env" "t") 1 funcref))"#)?;
+    let table_type = TableType::new(Type::ExternRef, 1, None);
+    let table = Table::new(&store, table_type, Value::null())?;
+    let import_object = imports! {
+        "env" => {
+            "t" => table,
+        },
+    };
+
+    let error = Instance::new(&module, &import_object).unwrap_err();
+    match &error {
+        InstantiationError::Link(LinkError::Import(
+            module,
+            name,
+            ImportError::IncompatibleType(expected, provided, mismatch),
+        )) => {
+            assert_eq!((module.as_str(), name.as_str()), ("env", "t"));
+            assert_eq!(
+                expected,
+                &ExternType::Table(TableType::new(Type::FuncRef, 1, None))
+            );
+            assert_eq!(provided, &ExternType::Table(table_type));
+            assert_eq!(*mismatch, ExternTypeMismatch::ElementType);
+        }
+        _ => panic!("Unexpected error: {:?}", error),
+    }
+    assert!(error.to_string().ends_with("the element type differs"));
+    Ok(())
+}
+
+// The default tunables only compile modules for static memories on
+// 64-bit hosts.
+#[cfg(target_pointer_width = "64")]
+#[test]
+fn import_memory_style_mismatch() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, r#"(module (memory (import "env" "m") 1))"#)?;
+    let expected = store
+        .tunables()
+        .memory_style(&MemoryType::new(Pages(1), None, false));
+    assert!(matches!(expected, vm::MemoryStyle::Static { .. }));
+
+    let styles = vec![
+        // The static bound is smaller than the expected one.
+        vm::MemoryStyle::Static {
+            bound: Pages(4),
+            offset_guard_size: expected.offset_guard_size(),
+        },
+        // A dynamic memory can't stand for a static one, whatever its
+        // offset guard.
+        vm::MemoryStyle::Dynamic {
+            offset_guard_size: 0x8000_0000,
+        },
+    ];
+    for style in styles {
+        let memory = Memory::new_with_style(
+            &store,
+            MemoryType::new(Pages(1), None, false),
+            style.clone(),
+        )?;
+        let import_object = imports! {
+            "env" => {
+                "m" => memory,
+            },
+        };
+
+        let error = Instance::new(&module, &import_object).unwrap_err();
+        match &error {
+            InstantiationError::Link(LinkError::Import(
+                module,
+                name,
+                ImportError::IncompatibleMemoryStyle(expected_style, provided_style),
+            )) => {
+                assert_eq!((module.as_str(), name.as_str()), ("env", "m"));
+                assert_eq!(expected_style, &expected);
+                assert_eq!(provided_style, &style);
+            }
+            _ => panic!("Unexpected error: {:?}", error),
+        }
+    }
+
+    // A memory with the expected style can be imported.
+    let memory = Memory::new_with_style(&store, MemoryType::new(Pages(1), None, false), expected)?;
+    let import_object = imports! {
+        "env" => {
+            "m" => memory,
+        },
+    };
+    Instance::new(&module, &import_object)?;
+    Ok(())
+}
+
+#[test]
+fn deferred_start_function() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+        (global $started (export "started") (mut i32) (i32.const 0))
+        (func $start
+            (global.set $started (i32.add (global.get $started) (i32.const 1))))
+        (start $start)
+    )"#;
+    let module = Module::new(&store, wat)?;
+
+    let instance = Instance::new(&module, &imports! {})?;
+    let started = instance.exports.get_global("started")?;
+    assert_eq!(started.get(), Value::I32(1));
+
+    let instance = Instance::new_deferring_start(&module, &imports! {})?;
+    let started = instance.exports.get_global("started")?;
+    assert_eq!(started.get(), Value::I32(0));
+    instance.invoke_start_function()?;
+    assert_eq!(started.get(), Value::I32(1));
+
+    // Without a start function, there is nothing to run.
+    let module = Module::new(&store, "(module)")?;
+    let instance = Instance::new_deferring_start(&module, &imports! {})?;
+    instance.invoke_start_function()?;
+    Ok(())
+}
+
+#[test]
+fn exported_functions_with_prefix() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+        (func (export "test_b"))
+        (func (export "helper"))
+        (func (export "test_a") unreachable)
+        (global (export "test_global") i32 (i32.const 0))
+        (func (export "a_test_c"))
+        (func (export "test_c"))
+    )"#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let tests = instance.exports.get_functions_with_prefix("test_");
+    let names = tests
+        .iter()
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["test_b", "test_a", "test_c"]);
+    let failures = tests
+        .iter()
+        .filter(|(_, function)| function.call(&[]).is_err())
+        .map(|(name, _)| name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(failures, vec!["test_a"]);
+
+    assert!(instance
+        .exports
+        .get_functions_with_prefix("bench_")
+        .is_empty());
+    Ok(())
+}
+
+#[test]
+fn import_object_with_defaults_for() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "answer" (func $answer (result i32)))
+            (import "env" "memory" (memory 1))
+            (import "env" "answer" (func (result i32)))
+            (import "env" "missing" (func))
+            (import "other" "global" (global i32))
+            (func (export "run") (result i32) (call $answer)))"#,
+    )?;
+
+    let answer = Function::new_native(&store, || 42);
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let other_store = Store::default();
+    let foreign_global = Global::new(&other_store, Value::I32(1));
+    let calls = std::cell::Cell::new(0);
+    let provider = |import: &ImportType| {
+        calls.set(calls.get() + 1);
+        match (import.module(), import.name()) {
+            ("env", "answer") => Some(answer.clone().into()),
+            ("env", "memory") => Some(memory.clone().into()),
+            ("other", "global") => Some(foreign_global.clone().into()),
+            _ => None,
+        }
+    };
+
+    // The duplicated import is only resolved once, and the unresolved
+    // imports, including the one from another store, are reported
+    // together.
+    let err = ImportObject::with_defaults_for(&module, &store, provider).unwrap_err();
+    assert_eq!(calls.get(), 4);
+    let unresolved = err
+        .imports
+        .iter()
+        .map(|import| (import.module(), import.name()))
+        .collect::<Vec<_>>();
+    assert_eq!(unresolved, vec![("env", "missing"), ("other", "global")]);
+    assert_eq!(
+        err.to_string(),
+        "unresolved imports: `env`.`missing`, `other`.`global`"
+    );
+
+    let global = Global::new(&store, Value::I32(1));
+    let import_object = ImportObject::with_defaults_for(&module, &store, |import| {
+        match (import.module(), import.name()) {
+            ("env", "answer") => Some(answer.clone().into()),
+            ("env", "memory") => Some(memory.clone().into()),
+            ("env", "missing") => Some(Function::new_native(&store, || {}).into()),
+            ("other", "global") => Some(global.clone().into()),
+            _ => None,
+        }
+    })?;
+    let instance = Instance::new(&module, &import_object)?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+
+    Ok(())
+}
+
+#[test]
+fn instance_builder_uses_provided_externs() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory (export "memory") 1 2)
+            (table (export "table") 1 1 funcref)
+            (global (export "global") (mut i32) (i32.const 7))
+            (func (export "store") (param i32 i32)
+                (i32.store (local.get 0) (local.get 1)))
+            (func (export "grow") (result i32)
+                (memory.grow (i32.const 1)))
+            (func (export "size") (result i32)
+                (memory.size)))"#,
+    )?;
+
+    let memory = Memory::new(&store, MemoryType::new(1, Some(2), false))?;
+    let table = Table::new_null(&store, TableType::new(ValType::FuncRef, 1, Some(1)))?;
+    let global = Global::new_mut(&store, Value::I32(0));
+    let instance = InstanceBuilder::new(&module)
+        .memory(MemoryIndex::from_u32(0), &memory)
+        .table(TableIndex::from_u32(0), &table)
+        .global(GlobalIndex::from_u32(0), &global)
+        .build(&imports! {})?;
+    assert!(instance.exports.get_memory("memory")?.same(&memory));
+    assert!(instance.exports.get_table("table")?.same(&table));
+    assert!(instance.exports.get_global("global")?.same(&global));
+    assert_eq!(global.get(), Value::I32(7));
+
+    // The instance writes to and grows the provided memory.
+    let store_fn: NativeFunc<(i32, i32), ()> = instance.exports.get_native_function("store")?;
+    store_fn.call(8, 42)?;
+    assert_eq!(memory.view::<u32>()[2].get(), 42);
+    let grow: NativeFunc<(), i32> = instance.exports.get_native_function("grow")?;
+    assert_eq!(grow.call()?, 1);
+    assert_eq!(memory.size(), Pages(2));
+    store_fn.call(65536, 43)?;
+    assert_eq!(memory.view::<u32>()[16384].get(), 43);
+
+    // The externs that aren't provided are created as usual.
+    let instance = InstanceBuilder::new(&module).build(&imports! {})?;
+    assert!(!instance.exports.get_memory("memory")?.same(&memory));
+
+    // Incompatible externs are refused.
+    let larger = Memory::new(&store, MemoryType::new(1, Some(3), false))?;
+    let result = InstanceBuilder::new(&module)
+        .memory(MemoryIndex::from_u32(0), &larger)
+        .build(&imports! {});
+    assert!(matches!(
+        result,
+        Err(InstantiationError::Link(LinkError::Override(_)))
+    ));
+    let growable = Table::new_null(&store, TableType::new(ValType::FuncRef, 1, None))?;
+    let result = InstanceBuilder::new(&module)
+        .table(TableIndex::from_u32(0), &growable)
+        .build(&imports! {});
+    assert!(matches!(
+        result,
+        Err(InstantiationError::Link(LinkError::Override(_)))
+    ));
+    let instance_memory = instance.exports.get_memory("memory")?;
+    let result = InstanceBuilder::new(&module)
+        .memory(MemoryIndex::from_u32(0), instance_memory)
+        .build(&imports! {});
+    assert!(matches!(
+        result,
+        Err(InstantiationError::Link(LinkError::Override(_)))
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn default_memory_is_found_without_export() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory 2)
+            (data (i32.const 8) "hi")
+            (func (export "store") (param i32 i32)
+                (i32.store8 (local.get 0) (local.get 1))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    assert!(instance.exports.get_memory("memory").is_err());
+
+    let memory = instance.default_memory().unwrap();
+    assert_eq!(memory.size(), Pages(2));
+    let view = memory.view::<u8>();
+    assert_eq!(view[8].get(), b'h');
+    let store_byte = instance
+        .exports
+        .get_native_function::<(i32, i32), ()>("store")?;
+    store_byte.call(16, 42)?;
+    assert_eq!(view[16].get(), 42);
+
+    // The memory outlives the instance.
+    drop(instance);
+    assert_eq!(memory.view::<u8>()[16].get(), 42);
+
+    let module = Module::new(&store, "(module (func (export \"run\")))")?;
+    let instance = Instance::new(&module, &imports! {})?;
+    assert!(instance.default_memory().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn reset_and_restore_globals() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (global $count (export "count") (mut i32) (i32.const 5))
+            (global $hidden (mut i64) (i64.const 7))
+            (global (export "const") i32 (i32.const 1))
+            (global $ref (export "ref") (mut funcref) (ref.func $bump))
+            (func $bump (export "bump")
+                (global.set $count (i32.add (global.get $count) (i32.const 1)))
+                (global.set $hidden (i64.add (global.get $hidden) (i64.const 1)))
+                (global.set $ref (ref.null func)))
+            (func (export "hidden") (result i64) (global.get $hidden)))"#,
+    )?;
+    let instance = InstanceBuilder::new(&module)
+        .keep_initial_globals(true)
+        .build(&imports! {})?;
+    let count = instance.exports.get_global("count")?;
+    let reference = instance.exports.get_global("ref")?;
+    let bump = instance.exports.get_native_function::<(), ()>("bump")?;
+    let hidden = instance.exports.get_native_function::<(), i64>("hidden")?;
+
+    bump.call()?;
+    assert_eq!(count.get(), Value::I32(6));
+    assert_eq!(hidden.call()?, 8);
+    assert!(reference.get().unwrap_funcref().is_none());
+
+    let snapshot = instance.snapshot_globals();
+    bump.call()?;
+    assert_eq!(count.get(), Value::I32(7));
+    instance.restore_globals(&snapshot)?;
+    assert_eq!(count.get(), Value::I32(6));
+    assert_eq!(hidden.call()?, 8);
+
+    instance.reset_globals()?;
+    assert_eq!(count.get(), Value::I32(5));
+    assert_eq!(hidden.call()?, 7);
+    // The reference is restored too.
+    let function = reference.get().unwrap_funcref().clone().unwrap();
+    function.call(&[])?;
+    assert_eq!(count.get(), Value::I32(6));
+
+    // A snapshot only applies to the instance it was taken from.
+    let other = Instance::new(&module, &imports! {})?;
+    assert!(other.restore_globals(&snapshot).is_err());
+    // The initial globals are only kept on request.
+    assert!(other.reset_globals().is_err());
+
+    Ok(())
+}
+
+#[test]
+fn globals_snapshot_and_apply() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (global $a (export "a") (mut i32) (i32.const 1))
+            (global $b (export "b") (mut f64) (f64.const 2))
+            (global $hidden (mut i32) (i32.const 3))
+            (global (export "const") i64 (i64.const 4))
+            (func (export "bump")
+                (global.set $a (i32.add (global.get $a) (i32.const 1)))
+                (global.set $b (f64.add (global.get $b) (f64.const 1)))
+                (global.set $hidden (i32.add (global.get $hidden) (i32.const 1)))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let bump = instance.exports.get_native_function::<(), ()>("bump")?;
+    let a = instance.exports.get_global("a")?;
+    let b = instance.exports.get_global("b")?;
+
+    bump.call()?;
+    let snapshot = instance.globals_snapshot();
+    assert_eq!(
+        snapshot,
+        vec![
+            ("a".to_string(), Value::I32(2)),
+            ("b".to_string(), Value::F64(3.0)),
+        ]
+    );
+
+    bump.call()?;
+    assert_eq!(a.get(), Value::I32(3));
+    instance.apply_globals(&snapshot)?;
+    assert_eq!(a.get(), Value::I32(2));
+    assert_eq!(b.get(), Value::F64(3.0));
+
+    // The snapshot applies to other instances of the module too.
+    let other = Instance::new(&module, &imports! {})?;
+    other.apply_globals(&snapshot)?;
+    assert_eq!(other.globals_snapshot(), snapshot);
+
+    // Immutable globals are skipped.
+    instance.apply_globals(&[
+        ("const".to_string(), Value::I64(5)),
+        ("a".to_string(), Value::I32(10)),
+    ])?;
+    assert_eq!(instance.exports.get_global("const")?.get(), Value::I64(4));
+    assert_eq!(a.get(), Value::I32(10));
+
+    // Unknown names and mismatched types fail without setting anything.
+    let err = instance
+        .apply_globals(&[
+            ("a".to_string(), Value::I32(20)),
+            ("missing".to_string(), Value::I32(0)),
+        ])
+        .unwrap_err();
+    assert_eq!(
+        err.message(),
+        "the instance exports no global named `missing`"
+    );
+    let err = instance
+        .apply_globals(&[
+            ("a".to_string(), Value::I32(20)),
+            ("b".to_string(), Value::I32(0)),
+        ])
+        .unwrap_err();
+    assert_eq!(
+        err.message(),
+        "the global `b` has type F64 but the value has type I32"
+    );
+    assert!(instance
+        .apply_globals(&[("bump".to_string(), Value::I32(0))])
+        .is_err());
+    assert_eq!(a.get(), Value::I32(10));
+
+    Ok(())
+}
+
+#[test]
+fn apply_globals_validates_every_value_first() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "level" (global $level (mut i32)))
+            (global (export "a") (mut i32) (i32.const 1))
+            (export "level" (global $level)))"#,
+    )?;
+    let level = Global::with_validator(
+        &store,
+        GlobalType::new(Type::I32, Mutability::Var),
+        Value::I32(2),
+        |_old, new| match new.unwrap_i32() {
+            0..=5 => Ok(()),
+            level => Err(format!("invalid log level {}", level)),
+        },
+    )?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "env" => {
+                "level" => level.clone(),
+            },
+        },
+    )?;
+    let a = instance.exports.get_global("a")?;
+
+    let err = instance
+        .apply_globals(&[
+            ("a".to_string(), Value::I32(20)),
+            ("level".to_string(), Value::I32(9)),
+        ])
+        .unwrap_err();
+    assert!(err.message().contains("invalid log level 9"), "{}", err);
+    assert_eq!(a.get(), Value::I32(1));
+    assert_eq!(level.get(), Value::I32(2));
+
+    instance.apply_globals(&[
+        ("a".to_string(), Value::I32(20)),
+        ("level".to_string(), Value::I32(4)),
+    ])?;
+    assert_eq!(a.get(), Value::I32(20));
+    assert_eq!(level.get(), Value::I32(4));
+
+    Ok(())
+}
+
+#[test]
+fn data_segment_out_of_bounds() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "memory" (memory 1))
+            (data (i32.const 0) "ok")
+            (data "passive")
+            (data (i32.const 65534) "too long"))"#,
+    )?;
+    let memory = Memory::new(&store, MemoryType::new(1, None, false))?;
+    let imports = imports! {
+        "env" => {
+            "memory" => memory.clone(),
+        },
+    };
+
+    let error = Instance::new(&module, &imports).unwrap_err();
+    assert!(error.to_string().contains("out of bounds memory access"));
+    match error {
+        InstantiationError::DataSegmentOutOfBounds(DataSegmentOutOfBounds {
+            segment,
+            memory_index,
+            offset,
+            len,
+            memory_size,
+        }) => {
+            assert_eq!(segment, DataIndex::from_u32(2));
+            assert_eq!(memory_index, MemoryIndex::from_u32(0));
+            assert_eq!(offset, 65534);
+            assert_eq!(len, 8);
+            assert_eq!(memory_size, WASM_PAGE_SIZE);
+        }
+        error => panic!("unexpected error: {}", error),
+    }
+
+    // The segments before the failing one have been written.
+    assert_eq!(memory.view::<u8>()[0].get(), b'o');
+    assert_eq!(memory.view::<u8>()[1].get(), b'k');
+    assert_eq!(memory.view::<u8>()[65534].get(), 0);
+
+    Ok(())
+}
+
+#[test]
+fn host_envs_are_dropped_with_the_instance_and_functions() -> Result<()> {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    struct Resource {
+        dropped: Arc<AtomicBool>,
+    }
+
+    impl Drop for Resource {
+        fn drop(&mut self) {
+            assert!(!self.dropped.swap(true, Ordering::SeqCst));
+        }
+    }
+
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        resource: Arc<Resource>,
+        #[wasmer(export)]
+        memory: LazyInit<Memory>,
+    }
+
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "read" (func $read (result i32)))
+            (import "env" "write" (func $write (param i32)))
+            (memory (export "memory") 1)
+            (func (export "run") (result i32)
+                (call $write (i32.const 42))
+                (call $read)))"#,
+    )?;
+
+    let dropped = Arc::new(AtomicBool::new(false));
+    let env = Env {
+        resource: Arc::new(Resource {
+            dropped: dropped.clone(),
+        }),
+        memory: LazyInit::new(),
+    };
+    let read = Function::new_native_with_env(&store, env.clone(), |env: &Env| {
+        env.memory_ref().unwrap().view::<i32>()[0].get()
+    });
+    let write = Function::new_native_with_env(&store, env, |env: &Env, value: i32| {
+        env.memory_ref().unwrap().view::<i32>()[0].set(value)
+    });
+    let import_object = imports! {
+        "env" => {
+            "read" => read.clone(),
+            "write" => write.clone(),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    let run: NativeFunc<(), i32> = instance.exports.get_native_function("run")?;
+    assert_eq!(run.call()?, 42);
+
+    // The functions own their environment, and the instance its clones.
+    drop(import_object);
+    drop(read);
+    drop(write);
+    assert!(!dropped.load(Ordering::SeqCst));
+    drop(instance);
+    assert!(!dropped.load(Ordering::SeqCst));
+    drop(run);
+    assert!(dropped.load(Ordering::SeqCst));
+
+    Ok(())
+}
+
+#[test]
+fn store_instantiation_observer() -> Result<()> {
+    use std::sync::{Arc, Mutex};
+
+    let store = Store::default();
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let observed = seen.clone();
+    store.set_instantiation_observer(Some(Box::new(move |event: &InstantiationEvent| {
+        observed.lock().unwrap().push((
+            event.module.name().map(str::to_string),
+            event
+                .imports
+                .iter()
+                .map(|resolved| {
+                    format!(
+                        "{}.{}: {:?} from host: {}",
+                        resolved.import.module(),
+                        resolved.import.name(),
+                        resolved.ty,
+                        resolved.from_host
+                    )
+                })
+                .collect::<Vec<_>>(),
+            event.error.is_some(),
+        ));
+    })));
+
+    let module = Module::new(
+        &store,
+        r#"(module $audited (import "env" "tick" (func)) (memory 1))"#,
+    )?;
+    let import_object = imports! {
+        "env" => { "tick" => Function::new_native(&store, || {}) },
+    };
+    let _instance1 = Instance::new(&module, &import_object)?;
+    let _instance2 = Instance::new(&module, &import_object)?;
+    assert!(Instance::new(&module, &imports! {}).is_err());
+
+    let tick = ExternType::Function(FunctionType::new(vec![], vec![]));
+    let expected = (
+        Some("audited".to_string()),
+        vec![format!("env.tick: {:?} from host: true", Some(tick))],
+        false,
+    );
+    // The import of the failed instantiation wasn't provided.
+    let failed = (
+        Some("audited".to_string()),
+        vec!["env.tick: None from host: false".to_string()],
+        true,
+    );
+    assert_eq!(
+        *seen.lock().unwrap(),
+        vec![expected.clone(), expected, failed]
+    );
+
+    // Instances of other stores aren't observed.
+    let other_store = Store::default();
+    let other_module = Module::new(&other_store, "(module)")?;
+    let _instance3 = Instance::new(&other_module, &imports! {})?;
+    store.set_instantiation_observer(None);
+    let _instance4 = Instance::new(&module, &import_object)?;
+    assert_eq!(seen.lock().unwrap().len(), 3);
+
+    Ok(())
+}
+
+#[test]
+fn passive_data_segments_from_the_host() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory (export "memory") 1)
+            (data (i32.const 0) "active")
+            (data "passive"))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let memory = instance.exports.get_memory("memory")?;
+
+    instance.memory_init(1, 100, 0, 7)?;
+    assert_eq!(memory.snapshot(100..107)?, b"passive");
+    instance.memory_init(1, 200, 3, 4)?;
+    assert_eq!(memory.snapshot(200..204)?, b"sive");
+
+    // Out of bounds ranges copy nothing.
+    let page = memory.data_size();
+    for (dst, src, len) in [
+        (page - 3, 0, 4),
+        (page + 1, 0, 0),
+        (1 << 32, 0, 0),
+        (0, 4, 4),
+    ] {
+        let error = instance.memory_init(1, dst, src, len).unwrap_err();
+        assert_eq!(error.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+    }
+    assert_eq!(memory.snapshot(page - 3..page)?, vec![0; 3]);
+
+    // Active segments aren't passive, nor are segments that don't exist.
+    assert_eq!(
+        instance.memory_init(0, 0, 0, 0).unwrap_err().message(),
+        "the module has no passive data segment 0"
+    );
+    assert!(instance.data_drop(2).is_err());
+
+    instance.data_drop(1)?;
+    instance.data_drop(1)?;
+    assert_eq!(
+        instance.memory_init(1, 0, 0, 0).unwrap_err().message(),
+        "the passive data segment 1 has been dropped"
+    );
+    assert_eq!(memory.snapshot(0..6)?, b"active");
+
+    Ok(())
+}
@@ -157,6 +157,177 @@ fn exports() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn exported_functions_of_type() -> Result<()> {
+    let store = Store::default();
+    // `$t1` and `$t2` are distinct type entries with the same structure.
+    let wat = r#"(module
+    (type $t1 (func))
+    (type $t2 (func))
+    (import "host" "hook" (func $hook (type $t1)))
+    (func (export "init_a") (type $t1))
+    (func (export "add") (param i32 i32) (result i32) (i32.const 0))
+    (func (export "init_b") (type $t2))
+    (func (export "start") (param i32))
+    (memory (export "init_memory") 1)
+    (export "hook" (func $hook))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let void_to_void = FunctionType::new(vec![], vec![]);
+
+    assert_eq!(
+        module.exported_functions_of_type(&void_to_void),
+        vec![
+            ExportType::new("init_a", void_to_void.clone()),
+            ExportType::new("init_b", void_to_void.clone()),
+            ExportType::new("hook", void_to_void.clone()),
+        ]
+    );
+    assert_eq!(
+        module.exported_functions_of_type(&FunctionType::new(
+            vec![Type::I32, Type::I32],
+            vec![Type::I32]
+        )),
+        vec![ExportType::new(
+            "add",
+            FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32])
+        )]
+    );
+    assert!(module
+        .exported_functions_of_type(&FunctionType::new(vec![Type::I64], vec![]))
+        .is_empty());
+
+    let import_object = imports! {
+        "host" => {
+            "hook" => Function::new_native(&store, || {}),
+        },
+    };
+    let instance = Instance::new(&module, &import_object)?;
+    let names = instance
+        .exports
+        .get_functions_of_type(&void_to_void)
+        .into_iter()
+        .map(|(name, function)| {
+            assert_eq!(function.ty(), &void_to_void);
+            name.as_str()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec!["init_a", "init_b", "hook"]);
+
+    Ok(())
+}
+
+#[test]
+fn module_wasm_bytes() -> Result<()> {
+    let store = Store::default();
+    let bytes = wat2wasm(br#"(module (func (export "f")))"#)?.into_owned();
+
+    let module = Module::new_retaining_bytes(&store, &bytes)?;
+    assert_eq!(module.wasm_bytes(), Some(&bytes[..]));
+    // Clones share the retained bytes.
+    assert_eq!(module.clone().wasm_bytes(), Some(&bytes[..]));
+
+    let module = Module::new(&store, &bytes)?;
+    assert_eq!(module.wasm_bytes(), None);
+
+    Ok(())
+}
+
+#[test]
+fn module_recompile() -> Result<()> {
+    let store = Store::default();
+    let other_store = Store::default();
+    let wat = r#"(module $adder
+        (func (export "add") (param i32 i32) (result i32)
+            (i32.add (local.get 0) (local.get 1))))"#;
+
+    let module = Module::new_retaining_bytes(&store, wat)?;
+    let recompiled = module.recompile(&other_store)?;
+    assert!(Store::same(recompiled.store(), &other_store));
+    assert_eq!(recompiled.name(), Some("adder"));
+    assert_eq!(recompiled.wasm_bytes(), module.wasm_bytes());
+
+    let instance = Instance::new(&recompiled, &imports! {})?;
+    let add = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("add")?;
+    assert_eq!(add.call(1, 2)?, 3);
+
+    // A module without its Wasm binary can't be recompiled.
+    let module = Module::new(&store, wat)?;
+    assert!(matches!(
+        module.recompile(&other_store),
+        Err(RecompileError::BytesNotRetained)
+    ));
+    Ok(())
+}
+
+#[cfg(all(feature = "universal", feature = "cranelift"))]
+#[test]
+fn module_recompile_onto_another_engine() -> Result<()> {
+    let store = Store::default();
+    let engine = Universal::new(Cranelift::default()).engine();
+    let other_store = Store::new(&engine);
+    assert_ne!(store.engine().id(), other_store.engine().id());
+
+    let wat = r#"(module
+        (func (export "answer") (result i32) (i32.const 42)))"#;
+    let module = Module::new_retaining_bytes(&store, wat)?;
+    let recompiled = module.recompile(&other_store)?;
+    assert!(Store::same(recompiled.store(), &other_store));
+
+    let instance = Instance::new(&recompiled, &imports! {})?;
+    let answer = instance.exports.get_native_function::<(), i32>("answer")?;
+    assert_eq!(answer.call()?, 42);
+    Ok(())
+}
+
+#[test]
+fn module_new_with_roots() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+        (func $helper (result i32) i32.const 40)
+        (func $unused (result i32) i32.const 1)
+        (func (export "kept") (result i32)
+            call $helper
+            i32.const 2
+            i32.add)
+        (func (export "pruned") (result i32)
+            call $unused))"#;
+    let module = Module::new_with_roots(&store, wat, &["kept"])?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let kept = instance.exports.get_function("kept")?;
+    assert_eq!(&*kept.call(&[])?, &[Val::I32(42)]);
+
+    let pruned = instance.exports.get_function("pruned")?;
+    let err = pruned.call(&[]).unwrap_err();
+    assert_eq!(err.message(), "function pruned");
+    assert_eq!(err.to_trap(), Some(TrapCode::FunctionPruned));
+
+    assert!(Module::new_with_roots(&store, wat, &["missing"]).is_err());
+    Ok(())
+}
+
+#[test]
+fn module_from_reader() -> Result<()> {
+    use std::io::Cursor;
+
+    let store = Store::default();
+    let wasm = wat2wasm(br#"(module (func (export "answer") (result i32) i32.const 42))"#)?;
+    let module = Module::from_reader(&store, Cursor::new(&wasm[..]))?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let answer: NativeFunc<(), i32> = instance.exports.get_native_function("answer")?;
+    assert_eq!(answer.call()?, 42);
+
+    let module = Module::from_reader(&store, Cursor::new(r#"(module (memory (export "m") 1))"#))?;
+    assert_eq!(module.exports().count(), 1);
+
+    // Streams shorter than the magic number are parsed as text.
+    assert!(Module::from_reader(&store, Cursor::new(b"\0as")).is_err());
+    Ok(())
+}
+
 #[test]
 fn calling_host_functions_with_negative_values_works() -> Result<()> {
     let store = Store::default();
@@ -246,3 +417,600 @@ fn calling_host_functions_with_negative_values_works() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn module_validate_with_features() -> Result<()> {
+    let store = Store::default();
+    let wasm = wat2wasm(
+        br#"(module
+            (func (export "splat") (param i32) (result v128)
+                (i32x4.splat (local.get 0))))"#,
+    )?;
+
+    let mut features = Features::new();
+    features.simd(false);
+    let error = Module::validate_with_features(&store, &wasm, &features).unwrap_err();
+    match error {
+        CompileError::Validate(message) => assert!(message.contains("SIMD"), "{}", message),
+        error => panic!("unexpected error: {:?}", error),
+    }
+
+    features.simd(true);
+    Module::validate_with_features(&store, &wasm, &features)?;
+    Ok(())
+}
+
+#[test]
+fn module_signatures_are_released_on_drop() -> Result<()> {
+    let store = Store::default();
+    let initial_count = store.engine().signature_count();
+
+    let wat_with_params = |count: usize| {
+        format!(
+            r#"(module
+                (type $t (func (param {})))
+                (table 1 funcref)
+                (func (export "call") (call_indirect (type $t) {} (i32.const 0))))"#,
+            "i32 ".repeat(count),
+            "(i32.const 0) ".repeat(count)
+        )
+    };
+
+    // Every module registers a signature no other module uses.
+    for count in 1..=32 {
+        let module = Module::new(&store, wat_with_params(count))?;
+        let instance = Instance::new(&module, &imports! {})?;
+        assert!(store.engine().signature_count() > initial_count);
+        drop(instance);
+        drop(module);
+        assert_eq!(store.engine().signature_count(), initial_count);
+    }
+
+    // Signatures shared between live modules stay registered.
+    let module = Module::new(&store, wat_with_params(1))?;
+    let other = Module::new(&store, wat_with_params(1))?;
+    let count = store.engine().signature_count();
+    drop(module);
+    assert_eq!(store.engine().signature_count(), count);
+    drop(other);
+    assert_eq!(store.engine().signature_count(), initial_count);
+    Ok(())
+}
+
+#[test]
+fn module_signatures_outlive_the_module_in_exports() -> Result<()> {
+    let store = Store::default();
+    let initial_count = store.engine().signature_count();
+
+    let module = Module::new(
+        &store,
+        r#"(module
+            (type $t (func (param i32 i64) (result i64)))
+            (table (export "table") 1 funcref)
+            (elem (i32.const 0) $add)
+            (func $add (export "add") (type $t)
+                (i64.add (i64.extend_i32_u (local.get 0)) (local.get 1))))"#,
+    )?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let add = instance.exports.get_function("add")?.clone();
+    let table = instance.exports.get_table("table")?.clone();
+    drop(instance);
+    drop(module);
+    assert!(store.engine().signature_count() > initial_count);
+
+    assert_eq!(
+        add.call(&[Value::I32(1), Value::I64(2)])?.to_vec(),
+        vec![Value::I64(3)]
+    );
+    // Reading the table looks the signature of the func ref up.
+    assert!(matches!(table.get(0), Some(Val::FuncRef(Some(_)))));
+
+    // A module registering the same signature later gets the same
+    // index, so the indirect call matches.
+    let caller = Module::new(
+        &store,
+        r#"(module
+            (type $t (func (param i32 i64) (result i64)))
+            (import "env" "table" (table 1 funcref))
+            (func (export "call") (result i64)
+                (call_indirect (type $t) (i32.const 1) (i64.const 2) (i32.const 0))))"#,
+    )?;
+    let caller = Instance::new(
+        &caller,
+        &imports! {
+            "env" => {
+                "table" => table.clone(),
+            },
+        },
+    )?;
+    assert_eq!(
+        caller.exports.get_function("call")?.call(&[])?.to_vec(),
+        vec![Value::I64(3)]
+    );
+
+    drop(caller);
+    drop(table);
+    drop(add);
+    assert_eq!(store.engine().signature_count(), initial_count);
+    Ok(())
+}
+
+#[test]
+fn module_call_export() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+        (global $offset (import "env" "offset") i32)
+        (func (export "add") (param i32 i32) (result i32)
+            (i32.add (global.get $offset) (i32.add (local.get 0) (local.get 1))))
+        (memory (export "memory") 1))"#;
+    let module = Module::new(&store, wat)?;
+    let imports = imports! {
+        "env" => {
+            "offset" => Global::new(&store, Val::I32(10)),
+        }
+    };
+
+    let results = module.call_export(&imports, "add", &[Val::I32(1), Val::I32(2)])?;
+    assert_eq!(results.to_vec(), vec![Val::I32(13)]);
+
+    let error = module.call_export(&imports, "sub", &[]).unwrap_err();
+    assert!(error.message().contains("Missing export sub"), "{}", error);
+
+    let error = module.call_export(&imports, "memory", &[]).unwrap_err();
+    assert!(
+        error.message().contains("Incompatible Export Type"),
+        "{}",
+        error
+    );
+
+    let error = module
+        .call_export(&imports! {}, "add", &[Val::I32(1), Val::I32(2)])
+        .unwrap_err();
+    assert!(
+        error.message().contains("failed to instantiate the module"),
+        "{}",
+        error
+    );
+    Ok(())
+}
+
+#[test]
+fn module_start_function() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, "(module (func))")?;
+    assert_eq!(module.start_function(), None);
+
+    let wat = r#"(module
+        (import "host" "init" (func))
+        (func $start)
+        (start $start)
+    )"#;
+    let module = Module::new(&store, wat)?;
+    assert_eq!(module.start_function(), Some(FunctionIndex::from_u32(1)));
+    Ok(())
+}
+
+#[test]
+fn wat_syntax_error_position() -> Result<()> {
+    let store = Store::default();
+    let wat = "(module\n  (func (result i32)\n    (i32.const nope)))";
+    match Module::new(&store, wat) {
+        Err(CompileError::Wasm(WasmError::Parse {
+            message,
+            line,
+            column,
+        })) => {
+            assert!(!message.is_empty());
+            assert_eq!(line, 3);
+            assert!(column > 1);
+        }
+        other => panic!("expected a wat parse error, got {:?}", other),
+    }
+    Ok(())
+}
+
+#[test]
+fn module_import_and_export_counts() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, "(module)")?;
+    assert_eq!(module.num_imports(), 0);
+    assert_eq!(module.num_exports(), 0);
+    assert_eq!(module.num_imported_functions(), 0);
+
+    let wat = r#"(module
+        (import "host" "f1" (func))
+        (import "host" "f2" (func (param i32)))
+        (import "host" "table" (table 1 funcref))
+        (import "host" "memory" (memory 1))
+        (import "host" "g1" (global i32))
+        (import "host" "g2" (global (mut i64)))
+        (import "host" "g3" (global f32))
+        (func (export "run"))
+        (export "memory" (memory 0))
+        (export "g1" (global 0))
+    )"#;
+    let module = Module::new(&store, wat)?;
+    assert_eq!(module.num_imports(), module.imports().count());
+    assert_eq!(module.num_exports(), module.exports().count());
+    assert_eq!(
+        module.num_imported_functions(),
+        module.imports().functions().count()
+    );
+    assert_eq!(
+        module.num_imported_tables(),
+        module.imports().tables().count()
+    );
+    assert_eq!(
+        module.num_imported_memories(),
+        module.imports().memories().count()
+    );
+    assert_eq!(
+        module.num_imported_globals(),
+        module.imports().globals().count()
+    );
+    assert_eq!(
+        (
+            module.num_imports(),
+            module.num_imported_functions(),
+            module.num_imported_tables(),
+            module.num_imported_memories(),
+            module.num_imported_globals(),
+            module.num_exports(),
+        ),
+        (7, 2, 1, 1, 3, 3)
+    );
+    Ok(())
+}
+
+#[test]
+fn module_data_segments() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "base" (global $base i32))
+            (memory 1)
+            (data (i32.const 8) "active")
+            (data "passive!")
+            (data (global.get $base) "imported"))"#,
+    )?;
+    let memory_index = MemoryIndex::from_u32(0);
+    assert_eq!(
+        module.data_segments(),
+        vec![
+            DataSegmentInfo {
+                mode: DataSegmentMode::Active {
+                    memory_index,
+                    offset: Some(8),
+                },
+                len: 6,
+            },
+            DataSegmentInfo {
+                mode: DataSegmentMode::Passive,
+                len: 8,
+            },
+            // The value of the global isn't known before instantiation.
+            DataSegmentInfo {
+                mode: DataSegmentMode::Active {
+                    memory_index,
+                    offset: None,
+                },
+                len: 8,
+            },
+        ]
+    );
+
+    let module = Module::new(&store, "(module)")?;
+    assert!(module.data_segments().is_empty());
+    Ok(())
+}
+
+#[test]
+fn module_manifest() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module $calc
+            (import "env" "log" (func $log (param i32)))
+            (import "env" "memory" (memory 1))
+            (table 2 funcref)
+            (global $counter (mut i32) (i32.const 0))
+            (func $add (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1)))
+            (export "counter" (global $counter)))"#,
+    )?;
+
+    let manifest = module.manifest();
+    assert_eq!(manifest.name.as_deref(), Some("calc"));
+    assert_eq!(manifest.imports.len(), 2);
+    assert_eq!(manifest.exports.len(), 2);
+    assert!(manifest.memories.is_empty());
+    assert_eq!(
+        manifest.tables,
+        vec![TableType::new(Type::FuncRef, 2, None)]
+    );
+    assert_eq!(
+        manifest.globals,
+        vec![GlobalType::new(Type::I32, Mutability::Var)]
+    );
+    assert!(!manifest.has_start_function);
+
+    let json = serde_json::to_value(&manifest)?;
+    assert_eq!(json["name"], "calc");
+    assert_eq!(json["imports"][0]["module"], "env");
+    assert_eq!(json["imports"][0]["name"], "log");
+    assert_eq!(
+        json["imports"][0]["ty"]["Function"]["params"],
+        serde_json::json!(["I32"])
+    );
+    assert_eq!(json["imports"][1]["name"], "memory");
+    assert_eq!(json["exports"][0]["name"], "add");
+    assert_eq!(
+        json["exports"][0]["ty"]["Function"]["results"],
+        serde_json::json!(["I32"])
+    );
+    assert_eq!(json["exports"][1]["name"], "counter");
+    assert!(json["exports"][1]["ty"]["Global"].is_object());
+
+    let parsed: ModuleManifest = serde_json::from_value(json)?;
+    assert_eq!(parsed, manifest);
+    Ok(())
+}
+
+#[test]
+fn serialize_errors_are_structured() -> Result<()> {
+    struct FailingWriter;
+
+    impl std::io::Write for FailingWriter {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "the cache is gone",
+            ))
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let store = Store::default();
+    let module = Module::new(&store, "(module (func (export \"run\")))")?;
+
+    match module.serialize_to_writer(FailingWriter) {
+        Err(SerializeError::Io(error)) => {
+            assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe)
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    let serialized = module.serialize()?;
+    let mut written = Vec::new();
+    module.serialize_to_writer(&mut written)?;
+    assert_eq!(written, serialized);
+
+    let mut small = vec![0; serialized.len() - 1];
+    match module.serialize_into(&mut small) {
+        Err(SerializeError::Capacity {
+            required,
+            available,
+        }) => {
+            assert_eq!(required, serialized.len());
+            assert_eq!(available, serialized.len() - 1);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+    let mut buffer = vec![0; serialized.len() + 16];
+    assert_eq!(module.serialize_into(&mut buffer)?, serialized.len());
+    assert_eq!(&buffer[..serialized.len()], &serialized[..]);
+
+    Ok(())
+}
+
+#[test]
+fn memory_footprint_grows_with_the_module() -> Result<()> {
+    let store = Store::default();
+    let function = r#"
+        (func (param i32) (result i32)
+            (local.get 0)
+            (i32.mul (local.get 0))
+            (i32.add (i32.const 7))
+            (i32.xor (local.get 0))
+            (i32.rotl (i32.const 3)))"#;
+    let small = Module::new(&store, format!("(module {})", function))?.memory_footprint();
+    let large =
+        Module::new(&store, format!("(module {})", function.repeat(32)))?.memory_footprint();
+
+    assert!(small.code_bytes > 0);
+    assert!(large.code_bytes > 8 * small.code_bytes);
+    assert!(large.frame_info_bytes > 8 * small.frame_info_bytes);
+    assert!(large.metadata_bytes > small.metadata_bytes);
+    assert!(large.total() > small.total());
+
+    let data = Module::new(
+        &store,
+        r#"(module (memory 1) (data (i32.const 0) "0123456789"))"#,
+    )?;
+    assert!(data.memory_footprint().data_initializer_bytes >= 10);
+    assert_eq!(small.data_initializer_bytes, 0);
+
+    Ok(())
+}
+
+#[test]
+fn metadata_is_serialized_with_the_module() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(&store, "(module)")?;
+    let clone = module.clone();
+    assert_eq!(module.metadata("signature"), None);
+
+    module.set_metadata("signature", vec![1, 2, 3])?;
+    assert_eq!(clone.metadata("signature"), Some(vec![1, 2, 3]));
+
+    let serialized = module.serialize()?;
+    let deserialized = unsafe { Module::deserialize(&store, &serialized)? };
+    assert_eq!(deserialized.metadata("signature"), Some(vec![1, 2, 3]));
+    assert_eq!(deserialized.metadata("missing"), None);
+
+    Ok(())
+}
+
+#[test]
+fn required_proposals_reports_the_used_proposals() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+        (memory 1)
+        (func (export "splat_sum") (param i32) (result i32 i32)
+            (i32x4.extract_lane 2 (i32x4.splat (local.get 0)))
+            (local.get 0)))"#;
+
+    let module = Module::new(&store, wat)?;
+    assert_eq!(
+        module.required_proposals(),
+        vec![WasmProposal::Simd, WasmProposal::MultiValue]
+    );
+
+    // The proposals are serialized along with the module.
+    let serialized = module.serialize()?;
+    let deserialized = unsafe { Module::deserialize(&store, &serialized)? };
+    assert_eq!(
+        deserialized.required_proposals(),
+        vec![WasmProposal::Simd, WasmProposal::MultiValue]
+    );
+
+    let module = Module::new(&store, "(module (memory 1))")?;
+    assert_eq!(module.required_proposals(), vec![]);
+
+    // Reference types don't imply bulk memory, nor the reverse.
+    let module = Module::new(
+        &store,
+        r#"(module
+            (table 1 funcref)
+            (func (export "get") (result funcref) (table.get 0 (i32.const 0))))"#,
+    )?;
+    assert_eq!(
+        module.required_proposals(),
+        vec![WasmProposal::ReferenceTypes]
+    );
+    let module = Module::new(
+        &store,
+        r#"(module
+            (memory 1)
+            (func (export "clear") (memory.fill (i32.const 0) (i32.const 0) (i32.const 8))))"#,
+    )?;
+    assert_eq!(module.required_proposals(), vec![WasmProposal::BulkMemory]);
+
+    // Multi-value block types count too.
+    let module = Module::new(
+        &store,
+        r#"(module
+            (func (export "sum") (result i32)
+                (block (result i32 i32) (i32.const 1) (i32.const 2))
+                (i32.add)))"#,
+    )?;
+    assert_eq!(module.required_proposals(), vec![WasmProposal::MultiValue]);
+    Ok(())
+}
+
+#[test]
+fn local_function_indices() -> Result<()> {
+    let store = Store::default();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "env" "a" (func))
+            (import "env" "b" (func (param i32)))
+            (func)
+            (func)
+            (func))"#,
+    )?;
+    assert_eq!(module.num_imported_functions(), 2);
+    assert_eq!(module.num_local_functions(), 3);
+    assert_eq!(
+        module.local_function_index(FunctionIndex::from_u32(0)),
+        None
+    );
+    assert_eq!(
+        module.local_function_index(FunctionIndex::from_u32(1)),
+        None
+    );
+    assert_eq!(
+        module.local_function_index(FunctionIndex::from_u32(2)),
+        Some(LocalFunctionIndex::from_u32(0))
+    );
+    assert_eq!(
+        module.local_function_index(FunctionIndex::from_u32(4)),
+        Some(LocalFunctionIndex::from_u32(2))
+    );
+    assert_eq!(
+        module.local_function_index(FunctionIndex::from_u32(5)),
+        None
+    );
+
+    let module = Module::new(&store, r#"(module (import "env" "a" (func)))"#)?;
+    assert_eq!(module.num_imported_functions(), 1);
+    assert_eq!(module.num_local_functions(), 0);
+    assert_eq!(
+        module.local_function_index(FunctionIndex::from_u32(0)),
+        None
+    );
+    Ok(())
+}
+
+#[test]
+fn function_wasm_bodies() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+        (import "env" "log" (func $log (param i32)))
+        (func (param i32) (result i32) (local i64)
+            local.get 0
+            i32.const 1
+            i32.add)
+        (func (param i32)
+            local.get 0
+            call $log))"#;
+    let module = Module::new_retaining_bytes(&store, wat)?;
+
+    // The imported function has no body: the first body is function 1.
+    assert_eq!(
+        module.local_function_index(FunctionIndex::from_u32(0)),
+        None
+    );
+    let first = module
+        .local_function_index(FunctionIndex::from_u32(1))
+        .unwrap();
+    assert_eq!(
+        module.function_wasm_body(first),
+        Some(
+            &[
+                0x01, 0x01, 0x7e, // 1 group of locals: 1 i64
+                0x20, 0x00, // local.get 0
+                0x41, 0x01, // i32.const 1
+                0x6a, // i32.add
+                0x0b, // end
+            ][..]
+        )
+    );
+    assert_eq!(
+        module.function_wasm_body(LocalFunctionIndex::from_u32(1)),
+        Some(
+            &[
+                0x00, // no locals
+                0x20, 0x00, // local.get 0
+                0x10, 0x00, // call 0
+                0x0b, // end
+            ][..]
+        )
+    );
+    assert_eq!(
+        module.function_wasm_body(LocalFunctionIndex::from_u32(2)),
+        None
+    );
+
+    let module = Module::new(&store, wat)?;
+    assert_eq!(
+        module.function_wasm_body(LocalFunctionIndex::from_u32(0)),
+        None
+    );
+    Ok(())
+}
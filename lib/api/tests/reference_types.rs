@@ -122,6 +122,72 @@ fn func_ref_passed_and_called() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn func_ref_round_trip_through_dynamic_function() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (import "env" "identity" (func $identity (param funcref) (result funcref)))
+    (func (export "round_trip") (param funcref) (result funcref)
+          (call $identity (local.get 0)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let imports = imports! {
+        "env" => {
+            "identity" => Function::new(&store, FunctionType::new([Type::FuncRef], [Type::FuncRef]), |values| -> Result<Vec<_>, _> {
+                Ok(vec![values[0].clone()])
+            }),
+        },
+    };
+    let instance = Instance::new(&module, &imports)?;
+    let round_trip = instance.exports.get_function("round_trip")?;
+
+    let sum = Function::new_native(&store, |a: i32, b: i32| a + b);
+    let results = round_trip.call(&[Value::FuncRef(Some(sum))])?;
+    let f: NativeFunc<(i32, i32), i32> = results[0].unwrap_funcref().as_ref().unwrap().native()?;
+    assert_eq!(f.call(3, 4)?, 7);
+
+    // A null reference stays null, and isn't confused with index 0.
+    let results = round_trip.call(&[Value::FuncRef(None)])?;
+    assert!(results[0].unwrap_funcref().is_none());
+
+    Ok(())
+}
+
+#[cfg(feature = "experimental-reference-types-extern-ref")]
+#[test]
+fn extern_ref_round_trip_through_dynamic_function() -> Result<()> {
+    let store = Store::default();
+    let wat = r#"(module
+    (import "env" "identity" (func $identity (param externref) (result externref)))
+    (func (export "round_trip") (param externref) (result externref)
+          (call $identity (local.get 0)))
+)"#;
+    let module = Module::new(&store, wat)?;
+    let imports = imports! {
+        "env" => {
+            "identity" => Function::new(&store, FunctionType::new([Type::ExternRef], [Type::ExternRef]), |values| -> Result<Vec<_>, _> {
+                Ok(vec![values[0].clone()])
+            }),
+        },
+    };
+    let instance = Instance::new(&module, &imports)?;
+    let round_trip = instance.exports.get_function("round_trip")?;
+
+    let er = ExternRef::new(42u32);
+    {
+        let results = round_trip.call(&[Value::ExternRef(er.clone())])?;
+        assert_eq!(results[0].unwrap_externref().downcast::<u32>(), Some(&42));
+        // Only the argument and the result hold a reference.
+        assert_eq!(er.strong_count(), 2);
+    }
+    assert_eq!(er.strong_count(), 1);
+
+    let results = round_trip.call(&[Value::ExternRef(ExternRef::null())])?;
+    assert!(results[0].unwrap_externref().is_null());
+
+    Ok(())
+}
+
 #[cfg(feature = "experimental-reference-types-extern-ref")]
 #[test]
 fn extern_ref_passed_and_returned() -> Result<()> {
@@ -5,7 +5,7 @@ use super::trap::wasm_trap_t;
 use crate::ordered_resolver::OrderedResolver;
 use std::mem;
 use std::sync::Arc;
-use wasmer_api::{Extern, Instance, InstantiationError};
+use wasmer_api::{Extern, Instance, InstantiationError, RuntimeError};
 
 /// Opaque type representing a WebAssembly instance.
 #[allow(non_camel_case_types)]
@@ -73,11 +73,26 @@ pub unsafe extern "C" fn wasm_instance_new(
             return None;
         }
 
+        // A data segment that doesn't fit in its memory traps, as in the
+        // WebAssembly spec.
+        Err(error @ InstantiationError::DataSegmentOutOfBounds(_)) => {
+            let trap: Box<wasm_trap_t> = Box::new(RuntimeError::new(error.to_string()).into());
+            *traps = Box::into_raw(trap);
+
+            return None;
+        }
+
         Err(InstantiationError::HostEnvInitialization(error)) => {
             crate::error::update_last_error(error);
 
             return None;
         }
+
+        Err(error @ InstantiationError::ResourceLimitExceeded(_)) => {
+            crate::error::update_last_error(error);
+
+            return None;
+        }
     };
 
     Some(Box::new(wasm_instance_t { inner: instance }))
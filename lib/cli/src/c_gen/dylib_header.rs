@@ -0,0 +1,145 @@
+//! Generate a header file declaring the exported functions of the object
+//! file produced by the Dylib engine.
+
+use super::{generate_c, CStatement, CType};
+use wasmer_compiler::{Symbol, SymbolRegistry};
+use wasmer_types::{ExportIndex, FunctionType, Type};
+use wasmer_vm::ModuleInfo;
+
+/// Map a Wasm value type to the C type used to pass it around.
+fn wasm_type_to_ctype(ty: Type) -> Option<CType> {
+    match ty {
+        Type::I32 => Some(CType::I32),
+        Type::I64 => Some(CType::I64),
+        Type::F32 => Some(CType::F32),
+        Type::F64 => Some(CType::F64),
+        Type::ExternRef | Type::FuncRef => Some(CType::void_ptr()),
+        // There is no portable C type for 128-bit vectors.
+        Type::V128 => None,
+    }
+}
+
+/// The C prototype of a compiled function, which takes the `vmctx` as
+/// its first argument.
+fn function_ctype(func_type: &FunctionType) -> Result<CType, String> {
+    let mut arguments = vec![CType::void_ptr()];
+    for param in func_type.params() {
+        arguments.push(
+            wasm_type_to_ctype(*param)
+                .ok_or_else(|| format!("parameters of type `{}` are not supported", param))?,
+        );
+    }
+    let return_value = match func_type.results() {
+        [] => None,
+        [result] => Some(Box::new(wasm_type_to_ctype(*result).ok_or_else(|| {
+            format!("results of type `{}` are not supported", result)
+        })?)),
+        _ => return Err("functions with multiple results are not supported".to_string()),
+    };
+    Ok(CType::Function {
+        arguments,
+        return_value,
+    })
+}
+
+/// Generate the header file that goes with the generated object file.
+///
+/// Exports that can't be expressed in C are listed in a comment
+/// instead of being declared.
+pub fn generate_header_file(
+    module_info: &ModuleInfo,
+    symbol_registry: &dyn SymbolRegistry,
+) -> String {
+    let mut c_statements = vec![CStatement::LiteralConstant {
+        value: "#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n".to_string(),
+    }];
+
+    for (name, export) in module_info.exports.iter() {
+        let function_index = match export {
+            ExportIndex::Function(function_index) => *function_index,
+            _ => continue,
+        };
+        let declaration = module_info
+            .local_func_index(function_index)
+            .ok_or_else(|| "re-exported imports are not supported".to_string())
+            .and_then(|local_index| {
+                let func_type = &module_info.signatures[module_info.functions[function_index]];
+                Ok(CStatement::Declaration {
+                    name: symbol_registry.symbol_to_name(Symbol::LocalFunction(local_index)),
+                    is_extern: false,
+                    is_const: false,
+                    ctype: function_ctype(func_type)?,
+                    definition: None,
+                })
+            });
+        match declaration {
+            Ok(declaration) => {
+                c_statements.push(CStatement::LiteralConstant {
+                    value: format!("// Export {:?}.\n", name),
+                });
+                c_statements.push(declaration);
+            }
+            Err(reason) => c_statements.push(CStatement::LiteralConstant {
+                value: format!("// Export {:?} is skipped: {}.\n", name, reason),
+            }),
+        }
+    }
+
+    c_statements.push(CStatement::LiteralConstant {
+        value: "\n#ifdef __cplusplus\n}\n#endif\n".to_string(),
+    });
+
+    generate_c(&c_statements)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use wasmer_types::entity::EntityRef;
+    use wasmer_types::FunctionIndex;
+
+    struct TestRegistry;
+
+    impl SymbolRegistry for TestRegistry {
+        fn symbol_to_name(&self, symbol: Symbol) -> String {
+            match symbol {
+                Symbol::LocalFunction(index) => format!("wasmer_function_{}", index.as_u32()),
+                _ => unimplemented!(),
+            }
+        }
+
+        fn name_to_symbol(&self, _name: &str) -> Option<Symbol> {
+            unimplemented!()
+        }
+    }
+
+    #[test]
+    fn declares_exported_functions() {
+        let mut module_info = ModuleInfo::new();
+        let add = module_info.signatures.push(FunctionType::new(
+            vec![Type::I32, Type::F64],
+            vec![Type::I64],
+        ));
+        let pair = module_info
+            .signatures
+            .push(FunctionType::new(vec![], vec![Type::I32, Type::I32]));
+        module_info.functions.push(add);
+        module_info.functions.push(pair);
+        module_info.exports.insert(
+            "add".to_string(),
+            ExportIndex::Function(FunctionIndex::new(0)),
+        );
+        module_info.exports.insert(
+            "pair".to_string(),
+            ExportIndex::Function(FunctionIndex::new(1)),
+        );
+
+        let header = generate_header_file(&module_info, &TestRegistry);
+        assert!(header
+            .contains("// Export \"add\".\nlong long wasmer_function_0(void*, int, double);\n"));
+        assert!(header.contains(
+            "// Export \"pair\" is skipped: functions with multiple results are not supported.\n"
+        ));
+        assert!(!header.contains("wasmer_function_1"));
+    }
+}
@@ -12,7 +12,7 @@ pub struct Compile {
     #[structopt(name = "FILE", parse(from_os_str))]
     path: PathBuf,
 
-    /// Output file
+    /// Output file, or output directory when compiling for several targets
     #[structopt(name = "OUTPUT PATH", short = "o", parse(from_os_str))]
     output: PathBuf,
 
@@ -20,15 +20,31 @@ pub struct Compile {
     #[structopt(name = "HEADER PATH", long = "header", parse(from_os_str))]
     header_path: Option<PathBuf>,
 
-    /// Compilation Target triple
-    #[structopt(long = "target")]
-    target_triple: Option<Triple>,
+    /// Compilation Target triple. Can be repeated to compile for several
+    /// targets, writing one artifact per target to the output directory
+    #[structopt(long = "target", number_of_values = 1)]
+    target_triples: Vec<Triple>,
+
+    /// When compiling for several targets, stop at the first target that
+    /// fails instead of compiling for the other ones
+    #[structopt(long = "fail-fast")]
+    fail_fast: bool,
 
     #[structopt(flatten)]
     store: StoreOptions,
 
     #[structopt(short = "m", multiple = true)]
     cpu_features: Vec<CpuFeature>,
+
+    /// Emit a relocatable object file and a header declaring the exported
+    /// functions, instead of a shared object (Dylib engine only)
+    #[structopt(long = "emit-object")]
+    emit_object: bool,
+
+    /// Print the number of compiled functions and the size of the code,
+    /// trampolines and custom sections of the artifact (Universal engine only)
+    #[structopt(long = "stats")]
+    stats: bool,
 }
 
 impl Compile {
@@ -60,10 +76,8 @@ impl Compile {
         })
     }
 
-    fn inner_execute(&self) -> Result<()> {
-        let target = self
-            .target_triple
-            .as_ref()
+    fn target(&self, target_triple: Option<&Triple>) -> Target {
+        target_triple
             .map(|target_triple| {
                 let mut features = self
                     .cpu_features
@@ -75,9 +89,30 @@ impl Compile {
                 features |= CpuFeature::SSE2;
                 Target::new(target_triple.clone(), features)
             })
-            .unwrap_or_default();
+            .unwrap_or_default()
+    }
+
+    fn inner_execute(&self) -> Result<()> {
+        if self.target_triples.len() > 1 {
+            return self.compile_for_targets();
+        }
+        let target = self.target(self.target_triples.first());
         let (store, engine_type, compiler_type) =
             self.store.get_store_for_target(target.clone())?;
+        if self.emit_object {
+            #[cfg(feature = "dylib")]
+            if engine_type == EngineType::Dylib {
+                return self.emit_object(target);
+            }
+            bail!("`--emit-object` is only supported by the Dylib engine");
+        }
+        #[cfg(feature = "universal")]
+        let stats_supported = engine_type == EngineType::Universal;
+        #[cfg(not(feature = "universal"))]
+        let stats_supported = false;
+        if self.stats && !stats_supported {
+            bail!("`--stats` is only supported by the Universal engine");
+        }
         let output_filename = self
             .output
             .file_stem()
@@ -105,6 +140,11 @@ impl Compile {
             self.output.display(),
         );
 
+        #[cfg(feature = "universal")]
+        if self.stats {
+            Self::print_stats(&module, "")?;
+        }
+
         #[cfg(feature = "staticlib")]
         if engine_type == EngineType::Staticlib {
             let artifact: &wasmer_engine_staticlib::StaticlibArtifact =
@@ -118,16 +158,7 @@ impl Compile {
                 metadata_length,
             );
 
-            let header_path = self.header_path.as_ref().cloned().unwrap_or_else(|| {
-                let mut hp = PathBuf::from(
-                    self.path
-                        .file_stem()
-                        .map(|fs| fs.to_string_lossy().to_string())
-                        .unwrap_or_else(|| "wasm_out".to_string()),
-                );
-                hp.set_extension("h");
-                hp
-            });
+            let header_path = self.header_path();
             // for C code
             let mut header = std::fs::OpenOptions::new()
                 .create(true)
@@ -144,4 +175,172 @@ impl Compile {
         }
         Ok(())
     }
+
+    /// Compiles the module for every target, writing the artifacts to
+    /// the output directory. The source is parsed once and validated
+    /// once per distinct feature set of the targets, only the code
+    /// generation runs per target.
+    fn compile_for_targets(&self) -> Result<()> {
+        if self.emit_object {
+            bail!("`--emit-object` only supports a single target");
+        }
+        if self.header_path.is_some() {
+            bail!("`--header` only supports a single target");
+        }
+        std::fs::create_dir_all(&self.output).with_context(|| {
+            format!(
+                "failed to create the output directory `{}`",
+                self.output.display()
+            )
+        })?;
+        let wasm_bytes = std::fs::read(&self.path)?;
+        #[cfg(feature = "wat")]
+        let wasm_bytes = wat2wasm(&wasm_bytes)?.to_vec();
+        let file_stem = self
+            .path
+            .file_stem()
+            .map(|fs| fs.to_string_lossy().to_string())
+            .unwrap_or_else(|| "wasm_out".to_string());
+
+        let mut validated = Vec::new();
+        let mut failed = Vec::new();
+        for target_triple in &self.target_triples {
+            match self.compile_for_target(target_triple, &wasm_bytes, &file_stem, &mut validated) {
+                Ok(output) => eprintln!(
+                    "✔ File compiled successfully for `{}` to `{}`.",
+                    target_triple,
+                    output.display(),
+                ),
+                Err(e) if self.fail_fast => {
+                    return Err(
+                        e.context(format!("failed to compile for target `{}`", target_triple))
+                    );
+                }
+                Err(e) => {
+                    warning!("failed to compile for target `{}`: {:#}", target_triple, e);
+                    failed.push(target_triple.to_string());
+                }
+            }
+        }
+        if !failed.is_empty() {
+            bail!("failed to compile for the targets: {}", failed.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Compiles the module for a single target of
+    /// [`Compile::compile_for_targets`], returning the artifact path.
+    ///
+    /// `validated` holds the feature sets the module was validated
+    /// with, for the previous targets.
+    fn compile_for_target(
+        &self,
+        target_triple: &Triple,
+        wasm_bytes: &[u8],
+        file_stem: &str,
+        validated: &mut Vec<Features>,
+    ) -> Result<PathBuf> {
+        let target = self.target(Some(target_triple));
+        // Unsupported targets are reported before generating any code.
+        let features = self.store.check_target(&target)?;
+        let (store, engine_type, _compiler_type) = self.store.get_store_for_target(target)?;
+        #[cfg(feature = "staticlib")]
+        if engine_type == EngineType::Staticlib {
+            bail!("the Staticlib engine only supports a single target");
+        }
+        #[cfg(feature = "universal")]
+        let stats_supported = engine_type == EngineType::Universal;
+        #[cfg(not(feature = "universal"))]
+        let stats_supported = false;
+        if self.stats && !stats_supported {
+            bail!("`--stats` is only supported by the Universal engine");
+        }
+        if !validated.contains(&features) {
+            Module::validate(&store, wasm_bytes)?;
+            validated.push(features);
+        }
+        // The module has been validated with the features of the target.
+        let module = unsafe { Module::from_binary_unchecked(&store, wasm_bytes)? };
+        let extension = Self::get_recommend_extension(&engine_type, target_triple)?;
+        let output = self
+            .output
+            .join(format!("{}.{}.{}", file_stem, target_triple, extension));
+        module.serialize_to_file(&output)?;
+
+        #[cfg(feature = "universal")]
+        if self.stats {
+            Self::print_stats(&module, &format!("target={} ", target_triple))?;
+        }
+        Ok(output)
+    }
+
+    /// Prints the compilation stats of a Universal `module`, after
+    /// `prefix`.
+    #[cfg(feature = "universal")]
+    fn print_stats(module: &Module, prefix: &str) -> Result<()> {
+        let artifact: &wasmer_engine_universal::UniversalArtifact =
+            module.artifact().as_ref().downcast_ref().context(
+                "Engine type is Universal but could not downcast artifact into UniversalArtifact",
+            )?;
+        let stats = artifact
+            .stats()
+            .context("the artifact has no compilation stats")?;
+        println!(
+            "{}functions={} code_bytes={} trampoline_bytes={} custom_section_bytes={}",
+            prefix,
+            stats.function_count,
+            stats.function_code_bytes,
+            stats.trampoline_bytes,
+            stats.custom_section_bytes
+        );
+        Ok(())
+    }
+
+    fn header_path(&self) -> PathBuf {
+        self.header_path.as_ref().cloned().unwrap_or_else(|| {
+            let mut hp = PathBuf::from(
+                self.path
+                    .file_stem()
+                    .map(|fs| fs.to_string_lossy().to_string())
+                    .unwrap_or_else(|| "wasm_out".to_string()),
+            );
+            hp.set_extension("h");
+            hp
+        })
+    }
+
+    /// Compiles the module with the Dylib engine into a relocatable object
+    /// file, without linking it, along with a header declaring its exports.
+    #[cfg(feature = "dylib")]
+    fn emit_object(&self, target: Target) -> Result<()> {
+        let (engine, compiler_type) = self.store.get_dylib_engine_for_target(target.clone())?;
+        println!("Engine: {}", EngineType::Dylib.to_string());
+        println!("Compiler: {}", compiler_type.to_string());
+        println!("Target: {}", target.triple());
+
+        let wasm_bytes = std::fs::read(&self.path)?;
+        #[cfg(feature = "wat")]
+        let wasm_bytes = wat2wasm(&wasm_bytes)?;
+        engine.validate(&wasm_bytes)?;
+        let tunables = BaseTunables::for_target(&target);
+        let (metadata, object) =
+            wasmer_engine_dylib::DylibArtifact::generate_object(&engine, &wasm_bytes, &tunables)?;
+        std::fs::write(&self.output, &object)?;
+        eprintln!(
+            "✔ Object file compiled successfully to `{}`.",
+            self.output.display(),
+        );
+
+        let header_file_src = crate::c_gen::dylib_header::generate_header_file(
+            &metadata.compile_info.module,
+            &metadata.get_symbol_registry(),
+        );
+        let header_path = self.header_path();
+        std::fs::write(&header_path, header_file_src)?;
+        eprintln!(
+            "✔ Header file generated successfully at `{}`.",
+            header_path.display(),
+        );
+        Ok(())
+    }
 }
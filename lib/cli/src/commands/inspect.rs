@@ -6,34 +6,38 @@ use structopt::StructOpt;
 use wasmer::*;
 
 #[derive(Debug, StructOpt)]
-/// The options for the `wasmer validate` subcommand
+/// The options for the `wasmer inspect` subcommand
 pub struct Inspect {
-    /// File to validate as WebAssembly
+    /// File to inspect: a WebAssembly module, or an artifact compiled
+    /// with `wasmer compile`
     #[structopt(name = "FILE", parse(from_os_str))]
     path: PathBuf,
 
+    /// Print the manifest of the module as JSON
+    #[structopt(long = "json")]
+    json: bool,
+
     #[structopt(flatten)]
     store: StoreOptions,
 }
 
 impl Inspect {
-    /// Runs logic for the `validate` subcommand
+    /// Runs logic for the `inspect` subcommand
     pub fn execute(&self) -> Result<()> {
         self.inner_execute()
             .context(format!("failed to inspect `{}`", self.path.display()))
     }
+
     fn inner_execute(&self) -> Result<()> {
-        let (store, _engine_type, _compiler_type) = self.store.get_store()?;
         let module_contents = std::fs::read(&self.path)?;
-        let module = Module::new(&store, &module_contents)?;
-        println!(
-            "Type: {}",
-            if !is_wasm(&module_contents) {
-                "wat"
-            } else {
-                "wasm"
-            }
-        );
+        let (module, kind) = self.get_module(&module_contents)?;
+        let manifest = module.manifest();
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&manifest)?);
+            return Ok(());
+        }
+
+        println!("Type: {}", kind);
         println!("Size: {}", ByteSize(module_contents.len() as _));
         println!("Imports:");
         println!("  Functions:");
@@ -69,6 +73,60 @@ impl Inspect {
         for f in module.exports().globals() {
             println!("    \"{}\": {}", f.name(), f.ty());
         }
+        println!("Definitions:");
+        println!("  Memories:");
+        for ty in &manifest.memories {
+            println!("    {}", ty);
+        }
+        println!("  Tables:");
+        for ty in &manifest.tables {
+            println!("    {}", ty);
+        }
+        println!("  Globals:");
+        for ty in &manifest.globals {
+            println!("    {}", ty);
+        }
+        if let Some(index) = module.start_function() {
+            match module.function_name(index) {
+                Some(name) => println!("Start function: {}", name),
+                None => println!("Start function: #{}", index.as_u32()),
+            }
+        }
+        println!("Proposals used:");
+        for proposal in module.required_proposals() {
+            println!("  {}", proposal);
+        }
+        println!("Custom sections:");
+        for name in &manifest.custom_sections {
+            println!("  {}", name);
+        }
         Ok(())
     }
+
+    /// Loads the module, deserializing it if it is a compiled artifact,
+    /// and describes what kind of file it comes from.
+    fn get_module(&self, contents: &[u8]) -> Result<(Module, &'static str)> {
+        #[cfg(feature = "dylib")]
+        {
+            if wasmer_engine_dylib::DylibArtifact::is_deserializable(contents) {
+                let engine = wasmer_engine_dylib::Dylib::headless().engine();
+                let store = Store::new(&engine);
+                let module = unsafe { Module::deserialize_from_file(&store, &self.path)? };
+                return Ok((module, "dylib artifact"));
+            }
+        }
+        #[cfg(feature = "universal")]
+        {
+            if wasmer_engine_universal::UniversalArtifact::is_deserializable(contents) {
+                let engine = wasmer_engine_universal::Universal::headless().engine();
+                let store = Store::new(&engine);
+                let module = unsafe { Module::deserialize(&store, contents)? };
+                return Ok((module, "universal artifact"));
+            }
+        }
+        let (store, _engine_type, _compiler_type) = self.store.get_store()?;
+        let module = Module::new(&store, contents)?;
+        let kind = if is_wasm(contents) { "wasm" } else { "wat" };
+        Ok((module, kind))
+    }
 }
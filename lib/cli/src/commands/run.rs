@@ -13,9 +13,12 @@ use wasmer_cache::{Cache, FileSystemCache, Hash};
 
 use structopt::StructOpt;
 
+mod trap;
 #[cfg(feature = "wasi")]
 mod wasi;
 
+use trap::{catch_host_panic, TrapReport};
+
 #[cfg(feature = "wasi")]
 use wasi::Wasi;
 
@@ -46,6 +49,10 @@ pub struct Run {
     #[structopt(long = "cache-key", hidden = true)]
     cache_key: Option<String>,
 
+    /// Report whether the module was loaded from the cache
+    #[structopt(long = "verbose", short = "v")]
+    verbose: bool,
+
     #[structopt(flatten)]
     store: StoreOptions,
 
@@ -94,7 +101,8 @@ impl Run {
         // Do we want to invoke a function?
         if let Some(ref invoke) = self.invoke {
             let imports = imports! {};
-            let instance = Instance::new(&module, &imports)?;
+            let instance =
+                Instance::new(&module, &imports).map_err(TrapReport::from_instantiation)?;
             let result = self.invoke_function(&instance, &invoke, &self.args)?;
             println!(
                 "{}",
@@ -196,9 +204,9 @@ impl Run {
 
         // Try to instantiate the wasm file, with no provided imports
         let imports = imports! {};
-        let instance = Instance::new(&module, &imports)?;
+        let instance = Instance::new(&module, &imports).map_err(TrapReport::from_instantiation)?;
         let start: Function = self.try_find_function(&instance, "_start", &[])?;
-        start.call(&[])?;
+        catch_host_panic(|| start.call(&[]))?.map_err(TrapReport::from)?;
 
         Ok(())
     }
@@ -260,14 +268,21 @@ impl Run {
         // as it takes space and the speedup is minimal.
         let mut cache = self.get_cache(engine_type, compiler_type)?;
         // Try to get the hash from the provided `--cache-key`, otherwise
-        // generate one from the provided file `.wasm` contents.
+        // generate one from the provided file `.wasm` contents and the
+        // store flags, so that a module compiled with different features
+        // or compiler options is never reused.
         let hash = self
             .cache_key
             .as_ref()
             .and_then(|key| Hash::from_str(&key).ok())
-            .unwrap_or_else(|| Hash::generate(&contents));
+            .unwrap_or_else(|| Hash::generate(&[contents, &self.store.cache_key()[..]].concat()));
         match unsafe { cache.load(&store, hash) } {
-            Ok(module) => Ok(module),
+            Ok(module) => {
+                if self.verbose {
+                    eprintln!("cache hit: loaded module {}", hash.to_string());
+                }
+                Ok(module)
+            }
             Err(e) => {
                 match e {
                     DeserializeError::Io(_) => {
@@ -277,8 +292,12 @@ impl Run {
                         warning!("cached module is corrupted: {}", err);
                     }
                 }
+                if self.verbose {
+                    eprintln!("cache miss: compiling module {}", hash.to_string());
+                }
                 let module = Module::new(&store, &contents)?;
-                // Store the compiled Module in cache
+                // Store the compiled Module in cache, overwriting any
+                // corrupted entry
                 cache.store(hash, &module)?;
                 Ok(module)
             }
@@ -416,6 +435,6 @@ impl Run {
                 )),
             })
             .collect::<Result<Vec<_>>>()?;
-        Ok(func.call(&invoke_args)?)
+        Ok(catch_host_panic(|| func.call(&invoke_args))?.map_err(TrapReport::from)?)
     }
 }
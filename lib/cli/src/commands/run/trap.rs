@@ -0,0 +1,75 @@
+//! Rendering of the errors raised while running a module.
+
+use anyhow::{anyhow, Error, Result};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use wasmer::{FrameInfo, InstantiationError, RuntimeError};
+
+/// A [`RuntimeError`] rendered with the kind of trap, the guest
+/// function it happened in and the wasm backtrace.
+#[derive(Debug)]
+pub struct TrapReport(RuntimeError);
+
+impl From<RuntimeError> for TrapReport {
+    fn from(error: RuntimeError) -> Self {
+        Self(error)
+    }
+}
+
+impl TrapReport {
+    /// Renders the traps of a start function, leaving the other
+    /// instantiation errors untouched.
+    pub fn from_instantiation(error: InstantiationError) -> Error {
+        match error {
+            InstantiationError::Start(error) => Self(error).into(),
+            error => error.into(),
+        }
+    }
+}
+
+fn function_name(frame: &FrameInfo) -> &str {
+    frame.function_name().unwrap_or("<unnamed>")
+}
+
+impl fmt::Display for TrapReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let error = &self.0;
+        match error.clone().to_trap() {
+            Some(trap_code) => write!(f, "wasm trap: {} ({:?})", trap_code.message(), trap_code)?,
+            None => write!(f, "runtime error: {}", error.message())?,
+        }
+        let trace = error.trace();
+        if let Some(frame) = trace.first() {
+            write!(f, " in function `{}`", function_name(frame))?;
+        }
+        for frame in trace {
+            write!(
+                f,
+                "\n    at {} ({}[{}]:0x{:x})",
+                function_name(frame),
+                frame.module_name(),
+                frame.func_index(),
+                frame.module_offset()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for TrapReport {}
+
+/// Runs `f`, turning a panic of a host function into an error that
+/// is labeled distinctly from wasm traps.
+///
+/// Host panics are resumed once the stack has unwound out of wasm, so
+/// they can be caught here.
+pub fn catch_host_panic<T>(f: impl FnOnce() -> T) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| payload.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "<unknown panic payload>".to_string());
+        anyhow!("host function panicked: {}", message)
+    })
+}
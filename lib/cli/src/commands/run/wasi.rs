@@ -1,3 +1,4 @@
+use super::trap::{catch_host_panic, TrapReport};
 use crate::utils::{parse_envvar, parse_mapdir};
 use anyhow::{Context, Result};
 use std::collections::BTreeSet;
@@ -73,10 +74,10 @@ impl Wasi {
 
         let mut wasi_env = wasi_state_builder.finalize()?;
         let resolver = wasi_env.import_object_for_all_wasi_versions(&module)?;
-        let instance = Instance::new(&module, &resolver)?;
+        let instance = Instance::new(&module, &resolver).map_err(TrapReport::from_instantiation)?;
 
         let start = instance.exports.get_function("_start")?;
-        let result = start.call(&[]);
+        let result = catch_host_panic(|| start.call(&[]))?;
 
         match result {
             Ok(_) => Ok(()),
@@ -87,7 +88,7 @@ impl Wasi {
                         std::process::exit(exit_code as _);
                     }
                     Ok(err) => err.into(),
-                    Err(err) => err.into(),
+                    Err(err) => TrapReport::from(err).into(),
                 };
                 Err(err)
             }
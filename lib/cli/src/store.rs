@@ -385,6 +385,32 @@ impl StoreOptions {
         Ok((store, engine_type, compiler_type))
     }
 
+    /// Checks that the selected compiler supports `target`, and returns
+    /// the features modules are compiled with for it.
+    pub fn check_target(&self, target: &Target) -> Result<Features> {
+        let (compiler_config, _compiler_type) = self.compiler.get_compiler_config()?;
+        compiler_config.check_target(target)?;
+        self.compiler
+            .get_features(compiler_config.default_features_for_target(target))
+    }
+
+    /// Gets the Dylib engine for a given target, with the compiler name selected
+    #[cfg(feature = "dylib")]
+    pub fn get_dylib_engine_for_target(
+        &self,
+        target: Target,
+    ) -> Result<(wasmer_engine_dylib::DylibEngine, CompilerType)> {
+        let (compiler_config, compiler_type) = self.compiler.get_compiler_config()?;
+        let features = self
+            .compiler
+            .get_features(compiler_config.default_features_for_target(&target))?;
+        let engine = wasmer_engine_dylib::Dylib::new(compiler_config)
+            .target(target)
+            .features(features)
+            .engine();
+        Ok((engine, compiler_type))
+    }
+
     fn get_engine_with_compiler(
         &self,
         target: Target,
@@ -423,6 +449,32 @@ impl StoreOptions {
     }
 }
 
+#[cfg(feature = "cache")]
+impl StoreOptions {
+    /// Returns the bytes of the options that change the compiled code,
+    /// to key the module cache with along with the module. The engine
+    /// and the compiler aren't part of it: they select the cache
+    /// directory and the file extension.
+    ///
+    /// Each option is a byte, in a fixed order, so that the key doesn't
+    /// depend on how the options are printed.
+    pub fn cache_key(&self) -> Vec<u8> {
+        let features = &self.compiler.features;
+        [
+            features.simd,
+            features.threads,
+            features.reference_types,
+            features.multi_value,
+            features.bulk_memory,
+            features.all,
+            self.compiler.enable_verifier,
+        ]
+        .iter()
+        .map(|&option| option as u8)
+        .collect()
+    }
+}
+
 // If we don't have a compiler, but we have an engine
 #[cfg(all(not(feature = "compiler"), feature = "engine"))]
 impl StoreOptions {
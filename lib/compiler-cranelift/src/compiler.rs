@@ -14,6 +14,7 @@ use crate::translator::{
     CraneliftUnwindInfo, FuncTranslator,
 };
 use cranelift_codegen::ir;
+use cranelift_codegen::isa::TargetIsa;
 use cranelift_codegen::print_errors::pretty_error;
 use cranelift_codegen::{binemit, Context};
 #[cfg(feature = "unwind")]
@@ -24,13 +25,14 @@ use std::sync::Arc;
 use wasmer_compiler::CompileError;
 use wasmer_compiler::{CallingConvention, ModuleTranslationState, Target};
 use wasmer_compiler::{
-    Compilation, CompileModuleInfo, CompiledFunction, CompiledFunctionFrameInfo,
+    Compilation, CompileModuleInfo, CompileProgress, CompiledFunction, CompiledFunctionFrameInfo,
     CompiledFunctionUnwindInfo, Compiler, Dwarf, FunctionBinaryReader, FunctionBody,
     FunctionBodyData, MiddlewareBinaryReader, ModuleMiddleware, ModuleMiddlewareChain,
-    SectionIndex,
+    OptimizationHints, OptimizationLevel, SectionIndex,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{FunctionIndex, LocalFunctionIndex, SignatureIndex};
+use wasmer_vm::ModuleInfo;
 
 /// A compiler that compiles a WebAssembly module with Cranelift, translating the Wasm to Cranelift IR,
 /// optimizing it and then translating to assembly.
@@ -51,12 +53,64 @@ impl CraneliftCompiler {
     }
 }
 
+/// The ISAs to compile the functions of a module with.
+///
+/// Functions hinted with an optimization level, see
+/// [`OptimizationHints`], are compiled with an ISA of their own, and
+/// the others with the ISA of the configured level.
+struct FunctionIsas {
+    default: Box<dyn TargetIsa>,
+    hints: OptimizationHints,
+    hinted: Vec<(OptimizationLevel, Box<dyn TargetIsa>)>,
+}
+
+impl FunctionIsas {
+    fn new(config: &Cranelift, target: &Target, module: &ModuleInfo) -> Self {
+        let hints = OptimizationHints::from_module(module);
+        for ignored in hints.ignored() {
+            tracing::warn!("ignoring optimization hint: {}", ignored);
+        }
+        let hinted = hints
+            .levels()
+            .into_iter()
+            .map(|level| (level, config.isa_with_opt_level(target, &level.into())))
+            .collect();
+        Self {
+            default: config.isa(target),
+            hints,
+            hinted,
+        }
+    }
+
+    /// The ISA of the functions without a hint.
+    fn default(&self) -> &dyn TargetIsa {
+        &*self.default
+    }
+
+    /// The ISA to compile the function `index` with.
+    fn get(&self, index: LocalFunctionIndex) -> &dyn TargetIsa {
+        match self.hints.get(index) {
+            Some(level) => self
+                .hinted
+                .iter()
+                .find(|(hinted_level, _)| *hinted_level == level)
+                .map(|(_, isa)| &**isa)
+                .expect("an ISA is created for every hinted level"),
+            None => self.default(),
+        }
+    }
+}
+
 impl Compiler for CraneliftCompiler {
     /// Get the middlewares for this compiler
     fn get_middlewares(&self) -> &[Arc<dyn ModuleMiddleware>] {
         &self.config.middlewares
     }
 
+    fn deterministic_id(&self) -> String {
+        format!("cranelift-{:?}", self.config)
+    }
+
     /// Compile the module using Cranelift, producing a compilation result with
     /// associated relocations.
     fn compile_module(
@@ -66,11 +120,31 @@ impl Compiler for CraneliftCompiler {
         module_translation_state: &ModuleTranslationState,
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
     ) -> Result<Compilation, CompileError> {
-        let isa = self.config().isa(target);
+        self.compile_module_with_progress(
+            target,
+            compile_info,
+            module_translation_state,
+            function_body_inputs,
+            &CompileProgress::ignore(),
+        )
+    }
+
+    /// Compile the module using Cranelift, reporting every compiled
+    /// function to `progress`.
+    fn compile_module_with_progress(
+        &self,
+        target: &Target,
+        compile_info: &CompileModuleInfo,
+        module_translation_state: &ModuleTranslationState,
+        function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+        progress: &CompileProgress,
+    ) -> Result<Compilation, CompileError> {
+        let module = &compile_info.module;
+        let isas = FunctionIsas::new(self.config(), target, module);
+        let isa = isas.default();
         let frontend_config = isa.frontend_config();
         let memory_styles = &compile_info.memory_styles;
         let table_styles = &compile_info.table_styles;
-        let module = &compile_info.module;
         let signatures = module
             .signatures
             .iter()
@@ -102,102 +176,114 @@ impl Compiler for CraneliftCompiler {
             }
         };
 
-        let functions = function_body_inputs
-            .iter()
-            .collect::<Vec<(LocalFunctionIndex, &FunctionBodyData<'_>)>>()
-            .par_iter()
-            .map_init(FuncTranslator::new, |func_translator, (i, input)| {
-                let func_index = module.func_index(*i);
-                let mut context = Context::new();
-                let mut func_env = FuncEnvironment::new(
-                    isa.frontend_config(),
-                    module,
-                    &signatures,
-                    &memory_styles,
-                    &table_styles,
-                );
-                context.func.name = get_function_name(func_index);
-                context.func.signature = signatures[module.functions[func_index]].clone();
-                // if generate_debug_info {
-                //     context.func.collect_debug_info();
-                // }
-                let mut reader =
-                    MiddlewareBinaryReader::new_with_offset(input.data, input.module_offset);
-                reader.set_middleware_chain(
-                    self.config
-                        .middlewares
-                        .generate_function_middleware_chain(*i),
-                );
+        let compile_function = |func_translator: &mut FuncTranslator,
+                                i: &LocalFunctionIndex,
+                                input: &FunctionBodyData<'_>|
+         -> Result<CompiledFunction, CompileError> {
+            let func_index = module.func_index(*i);
+            let isa = isas.get(*i);
+            let mut context = Context::new();
+            let mut func_env = FuncEnvironment::new(
+                isa.frontend_config(),
+                module,
+                &signatures,
+                &memory_styles,
+                &table_styles,
+            );
+            context.func.name = get_function_name(func_index);
+            context.func.signature = signatures[module.functions[func_index]].clone();
+            // if generate_debug_info {
+            //     context.func.collect_debug_info();
+            // }
+            let mut reader =
+                MiddlewareBinaryReader::new_with_offset(input.data, input.module_offset);
+            reader.set_middleware_chain(
+                self.config
+                    .middlewares
+                    .generate_function_middleware_chain(*i),
+            );
 
-                func_translator.translate(
-                    module_translation_state,
-                    &mut reader,
-                    &mut context.func,
-                    &mut func_env,
-                    *i,
-                )?;
+            func_translator.translate(
+                module_translation_state,
+                &mut reader,
+                &mut context.func,
+                &mut func_env,
+                *i,
+            )?;
 
-                let mut code_buf: Vec<u8> = Vec::new();
-                let mut reloc_sink = RelocSink::new(&module, func_index);
-                let mut trap_sink = TrapSink::new();
-                let mut stackmap_sink = binemit::NullStackMapSink {};
-                context
-                    .compile_and_emit(
-                        &*isa,
-                        &mut code_buf,
-                        &mut reloc_sink,
-                        &mut trap_sink,
-                        &mut stackmap_sink,
-                    )
-                    .map_err(|error| {
-                        CompileError::Codegen(pretty_error(&context.func, Some(&*isa), error))
-                    })?;
+            let mut code_buf: Vec<u8> = Vec::new();
+            let mut reloc_sink = RelocSink::new(&module, func_index);
+            let mut trap_sink = TrapSink::new();
+            let mut stackmap_sink = binemit::NullStackMapSink {};
+            context
+                .compile_and_emit(
+                    &*isa,
+                    &mut code_buf,
+                    &mut reloc_sink,
+                    &mut trap_sink,
+                    &mut stackmap_sink,
+                )
+                .map_err(|error| {
+                    CompileError::Codegen(pretty_error(&context.func, Some(&*isa), error))
+                })?;
 
-                let unwind_info = match compiled_function_unwind_info(&*isa, &context)? {
-                    #[cfg(feature = "unwind")]
-                    CraneliftUnwindInfo::FDE(fde) => {
-                        if let Some((dwarf_frametable, cie_id)) = &dwarf_frametable {
-                            dwarf_frametable
-                                .lock()
-                                .expect("Can't write into DWARF frametable")
-                                .add_fde(
-                                    *cie_id,
-                                    fde.to_fde(Address::Symbol {
-                                        // The symbol is the kind of relocation.
-                                        // "0" is used for functions
-                                        symbol: WriterRelocate::FUNCTION_SYMBOL,
-                                        // We use the addend as a way to specify the
-                                        // function index
-                                        addend: i.index() as _,
-                                    }),
-                                );
-                            // The unwind information is inserted into the dwarf section
-                            Some(CompiledFunctionUnwindInfo::Dwarf)
-                        } else {
-                            None
-                        }
+            let unwind_info = match compiled_function_unwind_info(&*isa, &context)? {
+                #[cfg(feature = "unwind")]
+                CraneliftUnwindInfo::FDE(fde) => {
+                    if let Some((dwarf_frametable, cie_id)) = &dwarf_frametable {
+                        dwarf_frametable
+                            .lock()
+                            .expect("Can't write into DWARF frametable")
+                            .add_fde(
+                                *cie_id,
+                                fde.to_fde(Address::Symbol {
+                                    // The symbol is the kind of relocation.
+                                    // "0" is used for functions
+                                    symbol: WriterRelocate::FUNCTION_SYMBOL,
+                                    // We use the addend as a way to specify the
+                                    // function index
+                                    addend: i.index() as _,
+                                }),
+                            );
+                        // The unwind information is inserted into the dwarf section
+                        Some(CompiledFunctionUnwindInfo::Dwarf)
+                    } else {
+                        None
                     }
-                    other => other.maybe_into_to_windows_unwind(),
-                };
+                }
+                other => other.maybe_into_to_windows_unwind(),
+            };
 
-                let range = reader.range();
-                let address_map = get_function_address_map(&context, range, code_buf.len(), &*isa);
+            let range = reader.range();
+            let address_map = get_function_address_map(&context, range, code_buf.len(), &*isa);
 
-                // We transform the Cranelift JumpTable's into compiler JumpTables
-                let func_jt_offsets = transform_jump_table(context.func.jt_offsets);
+            // We transform the Cranelift JumpTable's into compiler JumpTables
+            let func_jt_offsets = transform_jump_table(context.func.jt_offsets);
 
-                Ok(CompiledFunction {
-                    body: FunctionBody {
-                        body: code_buf,
-                        unwind_info,
-                    },
-                    jt_offsets: func_jt_offsets,
-                    relocations: reloc_sink.func_relocs,
-                    frame_info: CompiledFunctionFrameInfo {
-                        address_map,
-                        traps: trap_sink.traps,
-                    },
-                })
+            Ok(CompiledFunction {
+                body: FunctionBody {
+                    body: code_buf,
+                    unwind_info,
+                },
+                jt_offsets: func_jt_offsets,
+                relocations: reloc_sink.func_relocs,
+                frame_info: CompiledFunctionFrameInfo {
+                    address_map,
+                    traps: trap_sink.traps,
+                },
+            })
+        };
+
+        let functions = function_body_inputs
+            .iter()
+            .collect::<Vec<(LocalFunctionIndex, &FunctionBodyData<'_>)>>()
+            .par_iter()
+            .map_init(FuncTranslator::new, |func_translator, (i, input)| {
+                progress.check_deadline()?;
+                let function = compile_function(func_translator, i, input)
+                    .map_err(|error| error.in_function(*i))?;
+                progress.function_compiled();
+                Ok(function)
             })
             .collect::<Result<Vec<_>, CompileError>>()?
             .into_iter()
@@ -226,16 +312,17 @@ impl Compiler for CraneliftCompiler {
         let (custom_sections, dwarf) = (PrimaryMap::new(), None);
 
         // function call trampolines (only for local functions, by signature)
-        let function_call_trampolines = module
-            .signatures
-            .values()
-            .collect::<Vec<_>>()
+        // Structurally equal signatures share the same trampoline.
+        let (distinct_signatures, signature_groups) = module.distinct_signatures();
+        let distinct_trampolines = distinct_signatures
             .par_iter()
-            .map_init(FunctionBuilderContext::new, |mut cx, sig| {
-                make_trampoline_function_call(&*isa, &mut cx, sig)
+            .map_init(FunctionBuilderContext::new, |mut cx, sig_index| {
+                make_trampoline_function_call(&*isa, &mut cx, &module.signatures[*sig_index])
             })
-            .collect::<Result<Vec<FunctionBody>, CompileError>>()?
-            .into_iter()
+            .collect::<Result<Vec<FunctionBody>, CompileError>>()?;
+        let function_call_trampolines = signature_groups
+            .values()
+            .map(|group| distinct_trampolines[*group].clone())
             .collect::<PrimaryMap<SignatureIndex, FunctionBody>>();
 
         use wasmer_vm::VMOffsets;
@@ -261,3 +348,38 @@ impl Compiler for CraneliftCompiler {
         ))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::CraneliftOptLevel;
+    use cranelift_codegen::settings::OptLevel;
+    use wasmer_compiler::OPTIMIZATION_HINTS_SECTION;
+    use wasmer_types::FunctionType;
+
+    #[test]
+    fn hinted_functions_get_the_isa_of_their_level() {
+        let mut module = ModuleInfo::new();
+        let signature = module.signatures.push(FunctionType::new(vec![], vec![]));
+        for _ in 0..3 {
+            module.functions.push(signature);
+        }
+        // Function 0 isn't optimized, function 1 is optimized for speed
+        // and size, function 2 has no hint.
+        let section = module
+            .custom_sections_data
+            .push(Arc::from(&[0, 0, 1, 2][..]));
+        module
+            .custom_sections
+            .insert(OPTIMIZATION_HINTS_SECTION.into(), section);
+
+        let mut config = Cranelift::default();
+        config.opt_level(CraneliftOptLevel::Speed);
+        let isas = FunctionIsas::new(&config, &Target::default(), &module);
+        let opt_level = |index| isas.get(LocalFunctionIndex::new(index)).flags().opt_level();
+        assert_eq!(opt_level(0), OptLevel::None);
+        assert_eq!(opt_level(1), OptLevel::SpeedAndSize);
+        assert_eq!(opt_level(2), OptLevel::Speed);
+        assert_eq!(isas.default().flags().opt_level(), OptLevel::Speed);
+    }
+}
@@ -4,7 +4,8 @@ use cranelift_codegen::settings::{self, Configurable};
 use loupe::MemoryUsage;
 use std::sync::Arc;
 use wasmer_compiler::{
-    Architecture, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target,
+    Architecture, CompileError, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware,
+    OptimizationLevel, Target,
 };
 
 // Runtime Environment
@@ -23,6 +24,16 @@ pub enum CraneliftOptLevel {
     SpeedAndSize,
 }
 
+impl From<OptimizationLevel> for CraneliftOptLevel {
+    fn from(level: OptimizationLevel) -> Self {
+        match level {
+            OptimizationLevel::None => Self::None,
+            OptimizationLevel::Speed => Self::Speed,
+            OptimizationLevel::SpeedAndSize => Self::SpeedAndSize,
+        }
+    }
+}
+
 /// Global configuration options used to create an
 /// `wasmer_engine::Engine` and customize its behavior.
 ///
@@ -68,6 +79,16 @@ impl Cranelift {
 
     /// Generates the ISA for the provided target
     pub fn isa(&self, target: &Target) -> Box<dyn TargetIsa> {
+        self.isa_with_opt_level(target, &self.opt_level)
+    }
+
+    /// Generates the ISA for the provided target, optimizing with
+    /// `opt_level` instead of the configured level.
+    pub(crate) fn isa_with_opt_level(
+        &self,
+        target: &Target,
+        opt_level: &CraneliftOptLevel,
+    ) -> Box<dyn TargetIsa> {
         let mut builder =
             lookup(target.triple().clone()).expect("construct Cranelift ISA for triple");
         // Cpu Features
@@ -118,11 +139,15 @@ impl Cranelift {
             builder.enable("has_lzcnt").expect("should be valid flag");
         }
 
-        builder.finish(self.flags())
+        builder.finish(self.flags_with_opt_level(opt_level))
     }
 
     /// Generates the flags for the compiler
     pub fn flags(&self) -> settings::Flags {
+        self.flags_with_opt_level(&self.opt_level)
+    }
+
+    fn flags_with_opt_level(&self, opt_level: &CraneliftOptLevel) -> settings::Flags {
         let mut flags = settings::builder();
 
         // There are two possible traps for division, and this way
@@ -151,7 +176,7 @@ impl Cranelift {
         flags
             .set(
                 "opt_level",
-                match self.opt_level {
+                match opt_level {
                     CraneliftOptLevel::None => "none",
                     CraneliftOptLevel::Speed => "speed",
                     CraneliftOptLevel::SpeedAndSize => "speed_and_size",
@@ -202,6 +227,24 @@ impl CompilerConfig for Cranelift {
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>) {
         self.middlewares.push(middleware);
     }
+
+    /// Checks that Cranelift has an ISA for the target, and that x86_64
+    /// targets have SSE2, which [`Cranelift::isa`] requires.
+    fn check_target(&self, target: &Target) -> Result<(), CompileError> {
+        let triple = target.triple();
+        if lookup(triple.clone()).is_err() {
+            return Err(CompileError::UnsupportedTarget(triple.to_string()));
+        }
+        if triple.architecture == Architecture::X86_64
+            && !target.cpu_features().contains(CpuFeature::SSE2)
+        {
+            return Err(CompileError::UnsupportedTarget(format!(
+                "{} without SSE2",
+                triple
+            )));
+        }
+        Ok(())
+    }
 }
 
 impl Default for Cranelift {
@@ -12,9 +12,9 @@ use rayon::iter::ParallelBridge;
 use rayon::prelude::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use std::sync::Arc;
 use wasmer_compiler::{
-    Compilation, CompileError, CompileModuleInfo, Compiler, CustomSection, CustomSectionProtection,
-    Dwarf, FunctionBodyData, ModuleMiddleware, ModuleTranslationState, RelocationTarget,
-    SectionBody, SectionIndex, Symbol, SymbolRegistry, Target,
+    Compilation, CompileError, CompileModuleInfo, CompileProgress, Compiler, CustomSection,
+    CustomSectionProtection, Dwarf, FunctionBodyData, ModuleMiddleware, ModuleTranslationState,
+    RelocationTarget, SectionBody, SectionIndex, Symbol, SymbolRegistry, Target,
 };
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::{FunctionIndex, LocalFunctionIndex, SignatureIndex};
@@ -107,6 +107,9 @@ impl LLVMCompiler {
             },
         );
 
+        // Unlike `compile_module`, this emits a trampoline for every
+        // signature, even structurally equal ones: each one needs its own
+        // symbol in the object, and they aren't aliased yet.
         let trampolines_bitcode = compile_info.module.signatures.iter().par_bridge().map_init(
             || {
                 let target_machine = self.config().target_machine(target);
@@ -193,6 +196,10 @@ impl Compiler for LLVMCompiler {
         &self.config.middlewares
     }
 
+    fn deterministic_id(&self) -> String {
+        format!("llvm-{:?}", self.config)
+    }
+
     fn experimental_native_compile_module<'data, 'module>(
         &self,
         target: &Target,
@@ -222,6 +229,25 @@ impl Compiler for LLVMCompiler {
         compile_info: &'module CompileModuleInfo,
         module_translation: &ModuleTranslationState,
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
+    ) -> Result<Compilation, CompileError> {
+        self.compile_module_with_progress(
+            target,
+            compile_info,
+            module_translation,
+            function_body_inputs,
+            &CompileProgress::ignore(),
+        )
+    }
+
+    /// Compile the module using LLVM, reporting every compiled function
+    /// to `progress`.
+    fn compile_module_with_progress<'data, 'module>(
+        &self,
+        target: &Target,
+        compile_info: &'module CompileModuleInfo,
+        module_translation: &ModuleTranslationState,
+        function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
+        progress: &CompileProgress,
     ) -> Result<Compilation, CompileError> {
         //let data = Arc::new(Mutex::new(0));
         let memory_styles = &compile_info.memory_styles;
@@ -246,16 +272,21 @@ impl Compiler for LLVMCompiler {
                 |func_translator, (i, input)| {
                     // TODO: remove (to serialize)
                     //let _data = data.lock().unwrap();
-                    func_translator.translate(
-                        module,
-                        module_translation,
-                        i,
-                        input,
-                        self.config(),
-                        memory_styles,
-                        &table_styles,
-                        &ShortNames {},
-                    )
+                    progress.check_deadline()?;
+                    let function = func_translator
+                        .translate(
+                            module,
+                            module_translation,
+                            i,
+                            input,
+                            self.config(),
+                            memory_styles,
+                            &table_styles,
+                            &ShortNames {},
+                        )
+                        .map_err(|error| error.in_function(*i))?;
+                    progress.function_compiled();
+                    Ok(function)
                 },
             )
             .collect::<Result<Vec<_>, CompileError>>()?
@@ -328,21 +359,24 @@ impl Compiler for LLVMCompiler {
             None
         };
 
-        let function_call_trampolines = module
-            .signatures
-            .values()
-            .collect::<Vec<_>>()
+        // Structurally equal signatures share the same trampoline.
+        let (distinct_signatures, signature_groups) = module.distinct_signatures();
+        let distinct_trampolines = distinct_signatures
             .par_iter()
             .map_init(
                 || {
                     let target_machine = self.config().target_machine(target);
                     FuncTrampoline::new(target_machine)
                 },
-                |func_trampoline, sig| func_trampoline.trampoline(sig, self.config(), ""),
+                |func_trampoline, sig_index| {
+                    func_trampoline.trampoline(&module.signatures[*sig_index], self.config(), "")
+                },
             )
-            .collect::<Vec<_>>()
-            .into_iter()
-            .collect::<Result<PrimaryMap<_, _>, CompileError>>()?;
+            .collect::<Result<Vec<_>, CompileError>>()?;
+        let function_call_trampolines = signature_groups
+            .values()
+            .map(|group| distinct_trampolines[*group].clone())
+            .collect::<PrimaryMap<SignatureIndex, _>>();
 
         let dynamic_function_trampolines = module
             .imported_function_types()
@@ -9,7 +9,7 @@ use loupe::MemoryUsage;
 use std::fmt::Debug;
 use std::sync::Arc;
 use target_lexicon::Architecture;
-use wasmer_compiler::{Compiler, CompilerConfig, ModuleMiddleware, Target, Triple};
+use wasmer_compiler::{CompileError, Compiler, CompilerConfig, ModuleMiddleware, Target, Triple};
 use wasmer_types::{FunctionType, LocalFunctionIndex};
 
 /// The InkWell ModuleInfo type
@@ -66,6 +66,12 @@ impl LLVM {
     }
 
     /// The optimization levels when optimizing the IR.
+    ///
+    /// Every function is optimized at this level: the per-function
+    /// hints of the [`OPTIMIZATION_HINTS_SECTION`] custom section are
+    /// ignored by this compiler.
+    ///
+    /// [`OPTIMIZATION_HINTS_SECTION`]: wasmer_compiler::OPTIMIZATION_HINTS_SECTION
     pub fn opt_level(&mut self, opt_level: LLVMOptLevel) -> &mut Self {
         self.opt_level = opt_level;
         self
@@ -219,6 +225,15 @@ impl CompilerConfig for LLVM {
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>) {
         self.middlewares.push(middleware);
     }
+
+    /// Checks that the target is one of the architectures
+    /// [`LLVM::target_machine`] initializes.
+    fn check_target(&self, target: &Target) -> Result<(), CompileError> {
+        match target.triple().architecture {
+            Architecture::X86_64 | Architecture::X86_32(_) | Architecture::Aarch64(_) => Ok(()),
+            _ => Err(CompileError::UnsupportedTarget(target.triple().to_string())),
+        }
+    }
 }
 
 impl Default for LLVM {
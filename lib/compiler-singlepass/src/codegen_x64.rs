@@ -1238,6 +1238,15 @@ impl<'a> FuncGen<'a> {
         value_size: usize,
         cb: F,
     ) -> Result<(), CodegenError> {
+        // Only the first memory is addressed by the generated code.
+        if memarg.memory != 0 {
+            return Err(CodegenError {
+                message: format!(
+                    "memory access to memory {} is not supported by singlepass",
+                    memarg.memory
+                ),
+            });
+        }
         let need_check = match self.memory_styles[MemoryIndex::new(0)] {
             MemoryStyle::Static { .. } => false,
             MemoryStyle::Dynamic { .. } => true,
@@ -13,17 +13,26 @@ use rayon::prelude::{IntoParallelIterator, ParallelIterator};
 use std::sync::Arc;
 use wasmer_compiler::TrapInformation;
 use wasmer_compiler::{
-    Architecture, CompileModuleInfo, CompilerConfig, FunctionBinaryReader, MiddlewareBinaryReader,
-    ModuleMiddleware, ModuleMiddlewareChain, ModuleTranslationState, OperatingSystem, Target,
+    Compilation, CompileError, CompileProgress, CompiledFunction, Compiler, SectionIndex,
+};
+use wasmer_compiler::{
+    CompileModuleInfo, CompilerConfig, FunctionBinaryReader, MiddlewareBinaryReader,
+    ModuleMiddleware, ModuleMiddlewareChain, ModuleTranslationState, Target,
 };
-use wasmer_compiler::{Compilation, CompileError, CompiledFunction, Compiler, SectionIndex};
 use wasmer_compiler::{FunctionBody, FunctionBodyData};
 use wasmer_types::entity::{EntityRef, PrimaryMap};
-use wasmer_types::{FunctionIndex, FunctionType, LocalFunctionIndex, MemoryIndex, TableIndex};
+use wasmer_types::{
+    FunctionIndex, FunctionType, LocalFunctionIndex, MemoryIndex, SignatureIndex, TableIndex,
+};
 use wasmer_vm::{ModuleInfo, TrapCode, VMOffsets};
 
 /// A compiler that compiles a WebAssembly module with Singlepass.
 /// It does the compilation in one pass
+///
+/// It doesn't optimize the code, so it ignores the per-function hints of
+/// the [`OPTIMIZATION_HINTS_SECTION`] custom section.
+///
+/// [`OPTIMIZATION_HINTS_SECTION`]: wasmer_compiler::OPTIMIZATION_HINTS_SECTION
 #[derive(MemoryUsage)]
 pub struct SinglepassCompiler {
     config: Singlepass,
@@ -47,23 +56,39 @@ impl Compiler for SinglepassCompiler {
         &self.config.middlewares
     }
 
+    fn deterministic_id(&self) -> String {
+        format!("singlepass-{:?}", self.config)
+    }
+
     /// Compile the module using Singlepass, producing a compilation result with
     /// associated relocations.
     fn compile_module(
+        &self,
+        target: &Target,
+        compile_info: &CompileModuleInfo,
+        module_translation: &ModuleTranslationState,
+        function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+    ) -> Result<Compilation, CompileError> {
+        self.compile_module_with_progress(
+            target,
+            compile_info,
+            module_translation,
+            function_body_inputs,
+            &CompileProgress::ignore(),
+        )
+    }
+
+    /// Compile the module using Singlepass, reporting every compiled
+    /// function to `progress`.
+    fn compile_module_with_progress(
         &self,
         target: &Target,
         compile_info: &CompileModuleInfo,
         _module_translation: &ModuleTranslationState,
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'_>>,
+        progress: &CompileProgress,
     ) -> Result<Compilation, CompileError> {
-        if target.triple().operating_system == OperatingSystem::Windows {
-            return Err(CompileError::UnsupportedTarget(
-                OperatingSystem::Windows.to_string(),
-            ));
-        }
-        if let Architecture::X86_32(arch) = target.triple().architecture {
-            return Err(CompileError::UnsupportedTarget(arch.to_string()));
-        }
+        self.config.check_target(target)?;
         if compile_info.features.multi_value {
             return Err(CompileError::UnsupportedFeature("multivalue".to_string()));
         }
@@ -81,61 +106,71 @@ impl Compiler for SinglepassCompiler {
             .collect::<Vec<_>>()
             .into_iter()
             .collect();
+        let compile_function = |i: LocalFunctionIndex,
+                                input: &FunctionBodyData<'_>|
+         -> Result<CompiledFunction, CompileError> {
+            let middleware_chain = self
+                .config
+                .middlewares
+                .generate_function_middleware_chain(i);
+            let mut reader =
+                MiddlewareBinaryReader::new_with_offset(input.data, input.module_offset);
+            reader.set_middleware_chain(middleware_chain);
+
+            // This local list excludes arguments.
+            let mut locals = vec![];
+            let num_locals = reader.read_local_count()?;
+            for _ in 0..num_locals {
+                let (count, ty) = reader.read_local_decl()?;
+                for _ in 0..count {
+                    locals.push(ty);
+                }
+            }
+
+            let mut generator = FuncGen::new(
+                module,
+                &self.config,
+                &vmoffsets,
+                &memory_styles,
+                &table_styles,
+                i,
+                &locals,
+            )
+            .map_err(to_compile_error)?;
+
+            while generator.has_control_frames() {
+                generator.set_srcloc(reader.original_position() as u32);
+                let op = reader.read_operator()?;
+                generator.feed_operator(op).map_err(to_compile_error)?;
+            }
+
+            Ok(generator.finalize(&input))
+        };
+
         let functions = function_body_inputs
             .iter()
             .collect::<Vec<(LocalFunctionIndex, &FunctionBodyData<'_>)>>()
             .into_par_iter_if_rayon()
             .map(|(i, input)| {
-                let middleware_chain = self
-                    .config
-                    .middlewares
-                    .generate_function_middleware_chain(i);
-                let mut reader =
-                    MiddlewareBinaryReader::new_with_offset(input.data, input.module_offset);
-                reader.set_middleware_chain(middleware_chain);
-
-                // This local list excludes arguments.
-                let mut locals = vec![];
-                let num_locals = reader.read_local_count()?;
-                for _ in 0..num_locals {
-                    let (count, ty) = reader.read_local_decl()?;
-                    for _ in 0..count {
-                        locals.push(ty);
-                    }
-                }
-
-                let mut generator = FuncGen::new(
-                    module,
-                    &self.config,
-                    &vmoffsets,
-                    &memory_styles,
-                    &table_styles,
-                    i,
-                    &locals,
-                )
-                .map_err(to_compile_error)?;
-
-                while generator.has_control_frames() {
-                    generator.set_srcloc(reader.original_position() as u32);
-                    let op = reader.read_operator()?;
-                    generator.feed_operator(op).map_err(to_compile_error)?;
-                }
-
-                Ok(generator.finalize(&input))
+                progress.check_deadline()?;
+                let function = compile_function(i, input).map_err(|error| error.in_function(i))?;
+                progress.function_compiled();
+                Ok(function)
             })
             .collect::<Result<Vec<CompiledFunction>, CompileError>>()?
             .into_iter()
             .collect::<PrimaryMap<LocalFunctionIndex, CompiledFunction>>();
 
-        let function_call_trampolines = module
-            .signatures
-            .values()
-            .collect::<Vec<_>>()
+        // Structurally equal signatures share the same trampoline.
+        let (distinct_signatures, signature_groups) = module.distinct_signatures();
+        let distinct_trampolines = distinct_signatures
             .into_par_iter_if_rayon()
-            .map(gen_std_trampoline)
-            .collect::<Vec<_>>()
-            .into_iter()
-            .collect::<PrimaryMap<_, _>>();
+            .map(|sig_index| gen_std_trampoline(&module.signatures[sig_index]))
+            .collect::<Vec<_>>();
+        let function_call_trampolines = signature_groups
+            .values()
+            .map(|group| distinct_trampolines[*group].clone())
+            .collect::<PrimaryMap<SignatureIndex, FunctionBody>>();
 
         let dynamic_function_trampolines = module
             .imported_function_types()
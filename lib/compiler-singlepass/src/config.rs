@@ -4,7 +4,10 @@
 use crate::compiler::SinglepassCompiler;
 use loupe::MemoryUsage;
 use std::sync::Arc;
-use wasmer_compiler::{Compiler, CompilerConfig, CpuFeature, ModuleMiddleware, Target};
+use wasmer_compiler::{
+    Architecture, CompileError, Compiler, CompilerConfig, CpuFeature, ModuleMiddleware,
+    OperatingSystem, Target,
+};
 use wasmer_types::Features;
 
 #[derive(Debug, Clone, MemoryUsage)]
@@ -54,6 +57,10 @@ impl CompilerConfig for Singlepass {
         // PIC code.
     }
 
+    fn canonicalize_nans(&mut self, enable: bool) {
+        self.enable_nan_canonicalization = enable;
+    }
+
     /// Transform it into the compiler
     fn compiler(self: Box<Self>) -> Box<dyn Compiler> {
         Box::new(SinglepassCompiler::new(*self))
@@ -70,6 +77,21 @@ impl CompilerConfig for Singlepass {
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>) {
         self.middlewares.push(middleware);
     }
+
+    /// Singlepass only generates code for the System V ABI, on x86_64.
+    /// The operating system is checked first, so a 32-bit Windows
+    /// target is reported as unsupported because of Windows.
+    fn check_target(&self, target: &Target) -> Result<(), CompileError> {
+        if target.triple().operating_system == OperatingSystem::Windows {
+            return Err(CompileError::UnsupportedTarget(
+                OperatingSystem::Windows.to_string(),
+            ));
+        }
+        if let Architecture::X86_32(arch) = target.triple().architecture {
+            return Err(CompileError::UnsupportedTarget(arch.to_string()));
+        }
+        Ok(())
+    }
 }
 
 impl Default for Singlepass {
@@ -3,11 +3,13 @@
 
 use crate::lib::std::vec::Vec;
 use crate::sourceloc::SourceLoc;
+use crate::FunctionBody;
 use loupe::MemoryUsage;
 #[cfg(feature = "enable-rkyv")]
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
+use wasmer_types::SignatureIndex;
 
 /// Single source location to generated address mapping.
 #[cfg_attr(feature = "enable-serde", derive(Serialize, Deserialize))]
@@ -51,3 +53,57 @@ pub struct FunctionAddressMap {
     /// Generated function body length.
     pub body_len: usize,
 }
+
+impl FunctionAddressMap {
+    /// The address map of the function call trampoline for `signature`.
+    ///
+    /// The whole body is attributed to [`SourceLoc::trampoline`], so
+    /// profilers and symbolizers can recognize the trampoline instead
+    /// of attributing it to a function of the module.
+    pub fn trampoline(signature: SignatureIndex, body: &FunctionBody) -> Self {
+        let srcloc = SourceLoc::trampoline(signature);
+        let body_len = body.body.len();
+        Self {
+            instructions: [InstructionAddressMap {
+                srcloc,
+                code_offset: 0,
+                code_len: body_len,
+            }]
+            .to_vec(),
+            start_srcloc: srcloc,
+            end_srcloc: srcloc,
+            body_offset: 0,
+            body_len,
+        }
+    }
+
+    /// The signature of the trampoline this address map belongs to, if
+    /// it was created with [`FunctionAddressMap::trampoline`].
+    pub fn trampoline_signature(&self) -> Option<SignatureIndex> {
+        self.start_srcloc.trampoline_signature()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trampoline() {
+        let signature = SignatureIndex::from_u32(7);
+        let body = FunctionBody {
+            body: vec![0x90; 16],
+            unwind_info: None,
+        };
+        let address_map = FunctionAddressMap::trampoline(signature, &body);
+        assert_eq!(address_map.trampoline_signature(), Some(signature));
+        assert_eq!(address_map.body_len, 16);
+        assert_eq!(address_map.instructions.len(), 1);
+        assert_eq!(
+            address_map.instructions[0].srcloc.trampoline_signature(),
+            Some(signature)
+        );
+        assert_eq!(address_map.instructions[0].code_len, 16);
+        assert_eq!(FunctionAddressMap::default().trampoline_signature(), None);
+    }
+}
@@ -11,6 +11,9 @@ use crate::translator::ModuleMiddleware;
 use crate::FunctionBodyData;
 use crate::ModuleTranslationState;
 use crate::SectionIndex;
+use core::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(feature = "std")]
+use core::time::Duration;
 use loupe::MemoryUsage;
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::{Features, FunctionIndex, LocalFunctionIndex, SignatureIndex};
@@ -66,6 +69,17 @@ pub trait CompilerConfig {
 
     /// Pushes a middleware onto the back of the middleware chain.
     fn push_middleware(&mut self, middleware: Arc<dyn ModuleMiddleware>);
+
+    /// Checks that the compiler can generate code for `target`, to
+    /// report it before compiling anything.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`CompileError::UnsupportedTarget`] if the compiler
+    /// doesn't support the target.
+    fn check_target(&self, _target: &Target) -> Result<(), CompileError> {
+        Ok(())
+    }
 }
 
 impl<T> From<T> for Box<dyn CompilerConfig + 'static>
@@ -77,6 +91,32 @@ where
     }
 }
 
+/// Validates a module against the given `features`.
+///
+/// Using a feature that is disabled is reported as a
+/// `CompileError::Validate` naming the feature.
+pub fn validate_module(features: &Features, data: &[u8]) -> Result<(), CompileError> {
+    let mut validator = Validator::new();
+    let wasm_features = WasmFeatures {
+        bulk_memory: features.bulk_memory,
+        threads: features.threads,
+        reference_types: features.reference_types,
+        multi_value: features.multi_value,
+        simd: features.simd,
+        tail_call: features.tail_call,
+        module_linking: features.module_linking,
+        multi_memory: features.multi_memory,
+        memory64: features.memory64,
+        exceptions: features.exceptions,
+        deterministic_only: false,
+    };
+    validator.wasm_features(wasm_features);
+    validator
+        .validate_all(data)
+        .map_err(|e| CompileError::Validate(format!("{}", e)))?;
+    Ok(())
+}
+
 /// An implementation of a Compiler from parsed WebAssembly module to Compiled native code.
 pub trait Compiler: Send + MemoryUsage {
     /// Validates a module.
@@ -87,30 +127,26 @@ pub trait Compiler: Send + MemoryUsage {
         features: &Features,
         data: &'data [u8],
     ) -> Result<(), CompileError> {
-        let mut validator = Validator::new();
-        let wasm_features = WasmFeatures {
-            bulk_memory: features.bulk_memory,
-            threads: features.threads,
-            reference_types: features.reference_types,
-            multi_value: features.multi_value,
-            simd: features.simd,
-            tail_call: features.tail_call,
-            module_linking: features.module_linking,
-            multi_memory: features.multi_memory,
-            memory64: features.memory64,
-            exceptions: features.exceptions,
-            deterministic_only: false,
-        };
-        validator.wasm_features(wasm_features);
-        validator
-            .validate_all(data)
-            .map_err(|e| CompileError::Validate(format!("{}", e)))?;
-        Ok(())
+        validate_module(features, data)
     }
 
     /// Compiles a parsed module.
     ///
     /// It returns the [`Compilation`] or a [`CompileError`].
+    ///
+    /// # Determinism
+    ///
+    /// Compilers must be deterministic: compiling the same module with
+    /// the same compiler configuration, for the same [`Target`] (triple
+    /// and CPU features), must produce the same [`Compilation`], down to
+    /// the bytes of the function bodies. This holds across processes and
+    /// machines, whatever the number of threads compiling in parallel,
+    /// so it can be relied on for reproducible builds.
+    ///
+    /// Note that a [`Target`] detected on the host, like the default
+    /// one, has the CPU features of that host: pin them to get the same
+    /// code on different machines. Middlewares are part of the
+    /// configuration, and must be deterministic too.
     fn compile_module<'data, 'module>(
         &self,
         target: &Target,
@@ -120,6 +156,35 @@ pub trait Compiler: Send + MemoryUsage {
         function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
     ) -> Result<Compilation, CompileError>;
 
+    /// Compiles a parsed module like [`Compiler::compile_module`],
+    /// reporting every local function it compiles to `progress`.
+    ///
+    /// Compilers should call [`CompileProgress::check_deadline`] before
+    /// compiling each function, and give up with its error.
+    ///
+    /// The default implementation reports all the functions at once,
+    /// after compiling the module, and only checks the deadline before
+    /// and after compiling it.
+    fn compile_module_with_progress<'data, 'module>(
+        &self,
+        target: &Target,
+        module: &'module CompileModuleInfo,
+        module_translation: &ModuleTranslationState,
+        // The list of function bodies
+        function_body_inputs: PrimaryMap<LocalFunctionIndex, FunctionBodyData<'data>>,
+        progress: &CompileProgress,
+    ) -> Result<Compilation, CompileError> {
+        let num_functions = function_body_inputs.len();
+        progress.check_deadline()?;
+        let compilation =
+            self.compile_module(target, module, module_translation, function_body_inputs)?;
+        for _ in 0..num_functions {
+            progress.function_compiled();
+        }
+        progress.check_deadline()?;
+        Ok(compilation)
+    }
+
     /// Compiles a module into a native object file.
     ///
     /// It returns the bytes as a `&[u8]` or a [`CompileError`].
@@ -139,6 +204,16 @@ pub trait Compiler: Send + MemoryUsage {
 
     /// Get the middlewares for this compiler
     fn get_middlewares(&self) -> &[Arc<dyn ModuleMiddleware>];
+
+    /// Returns a string identifying the compiler and every setting that
+    /// affects the code it generates, for example to key a cache of
+    /// compiled artifacts.
+    ///
+    /// The default implementation only identifies the type of the
+    /// compiler, so compilers with settings should override it.
+    fn deterministic_id(&self) -> String {
+        core::any::type_name::<Self>().into()
+    }
 }
 
 /// The kinds of wasmer_types objects that might be found in a native object file.
@@ -167,3 +242,75 @@ pub trait SymbolRegistry: Send + Sync {
     /// This function is the inverse of [`SymbolRegistry::symbol_to_name`]
     fn name_to_symbol(&self, name: &str) -> Option<Symbol>;
 }
+
+/// Reports the progress of the compilation of the local functions of
+/// a module to a callback.
+///
+/// The callback is called with the number of functions compiled so far
+/// and the total number of functions. When a compiler compiles the
+/// functions in parallel, it is called concurrently from the compiling
+/// threads, and its calls may be out of order: only one of them
+/// reports that all the functions are compiled.
+///
+/// It also carries the deadline of the compilation, if any, see
+/// [`CompileProgress::with_deadline`].
+pub struct CompileProgress<'a> {
+    callback: Option<&'a (dyn Fn(usize, usize) + Sync)>,
+    total: usize,
+    compiled: AtomicUsize,
+    #[cfg(feature = "std")]
+    deadline: Option<(std::time::Instant, Duration)>,
+}
+
+impl<'a> CompileProgress<'a> {
+    /// Creates a `CompileProgress` reporting the compilation of `total`
+    /// functions to `callback`.
+    pub fn new(total: usize, callback: &'a (dyn Fn(usize, usize) + Sync)) -> Self {
+        Self {
+            callback: Some(callback),
+            total,
+            compiled: AtomicUsize::new(0),
+            #[cfg(feature = "std")]
+            deadline: None,
+        }
+    }
+
+    /// Creates a `CompileProgress` reporting to nothing.
+    pub fn ignore() -> Self {
+        Self {
+            callback: None,
+            total: 0,
+            compiled: AtomicUsize::new(0),
+            #[cfg(feature = "std")]
+            deadline: None,
+        }
+    }
+
+    /// Makes [`CompileProgress::check_deadline`] fail once `timeout` has
+    /// elapsed since `start`.
+    #[cfg(feature = "std")]
+    pub fn with_deadline(mut self, start: std::time::Instant, timeout: Duration) -> Self {
+        self.deadline = Some((start, timeout));
+        self
+    }
+
+    /// Returns a [`CompileError::Timeout`] if the deadline of the
+    /// compilation is past.
+    pub fn check_deadline(&self) -> Result<(), CompileError> {
+        #[cfg(feature = "std")]
+        if let Some((start, timeout)) = self.deadline {
+            if start.elapsed() > timeout {
+                return Err(CompileError::Timeout(timeout));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports that one more function is compiled.
+    pub fn function_compiled(&self) {
+        if let Some(callback) = self.callback {
+            let compiled = self.compiled.fetch_add(1, Ordering::SeqCst) + 1;
+            callback(compiled, self.total);
+        }
+    }
+}
@@ -1,6 +1,11 @@
+use crate::lib::std::boxed::Box;
 use crate::lib::std::string::String;
+use crate::limits::CompilationLimit;
+use core::time::Duration;
 #[cfg(feature = "std")]
 use thiserror::Error;
+use wasmer_types::LocalFunctionIndex;
+use wasmer_vm::ModuleInfo;
 
 // Compilation Errors
 //
@@ -40,6 +45,92 @@ pub enum CompileError {
     /// Insufficient resources available for execution.
     #[cfg_attr(feature = "std", error("Insufficient resources: {0}"))]
     Resource(String),
+
+    /// Compiling a specific function of the module failed.
+    #[cfg_attr(
+        feature = "std",
+        error(
+            "Failed to compile function {}{}: {}",
+            .index.as_u32(),
+            .name.as_ref().map(|name| format!(" ({})", name)).unwrap_or_default(),
+            .error
+        )
+    )]
+    Function {
+        /// The index of the local function that failed to compile.
+        index: LocalFunctionIndex,
+        /// The name of the function, if the module's name section provides one.
+        name: Option<String>,
+        /// The error that made the function fail to compile.
+        error: Box<CompileError>,
+    },
+
+    /// The module exceeds one of the
+    /// [`CompilationLimits`](crate::CompilationLimits) of the engine.
+    #[cfg_attr(
+        feature = "std",
+        error("The module exceeds the limit of {limit} {which}: it has {actual}")
+    )]
+    ExceedsLimits {
+        /// The limit that is exceeded.
+        which: CompilationLimit,
+        /// The value of the limit.
+        limit: u64,
+        /// The value found in the module.
+        actual: u64,
+    },
+
+    /// The compilation ran past its deadline, and was abandoned.
+    #[cfg_attr(
+        feature = "std",
+        error("The compilation exceeded its deadline of {0:?}")
+    )]
+    Timeout(Duration),
+}
+
+impl CompileError {
+    /// Attributes this error to the local function at `index`.
+    ///
+    /// Errors that are already attributed to a function are returned
+    /// unchanged, so this can safely be applied at several layers.
+    pub fn in_function(self, index: LocalFunctionIndex) -> Self {
+        match self {
+            Self::Function { .. } => self,
+            error => Self::Function {
+                index,
+                name: None,
+                error: Box::new(error),
+            },
+        }
+    }
+
+    /// Fills in the name of the function this error is attributed to,
+    /// looking it up in the name section of `module`.
+    pub fn with_function_name(self, module: &ModuleInfo) -> Self {
+        match self {
+            Self::Function {
+                index,
+                name: None,
+                error,
+            } => Self::Function {
+                index,
+                name: module
+                    .function_names
+                    .get(&module.func_index(index))
+                    .cloned(),
+                error,
+            },
+            error => error,
+        }
+    }
+
+    /// Returns the local function this error is attributed to, if any.
+    pub fn function_index(&self) -> Option<LocalFunctionIndex> {
+        match self {
+            Self::Function { index, .. } => Some(*index),
+            _ => None,
+        }
+    }
 }
 
 impl From<WasmError> for CompileError {
@@ -91,6 +182,20 @@ pub enum WasmError {
         offset: usize,
     },
 
+    /// The WebAssembly text format couldn't be parsed.
+    #[cfg_attr(
+        feature = "std",
+        error("Error when parsing wat at {line}:{column}: {message}")
+    )]
+    Parse {
+        /// A string describing the syntax error.
+        message: String,
+        /// The line of the error, starting at 1.
+        line: usize,
+        /// The column of the error, starting at 1.
+        column: usize,
+    },
+
     /// A feature used by the WebAssembly code is not supported by the embedding environment.
     ///
     /// Embedding environments may have their own limitations and feature restrictions.
@@ -133,6 +238,24 @@ pub type WasmResult<T> = Result<T, WasmError>;
 mod tests {
     use super::*;
 
+    #[test]
+    fn compile_error_can_be_attributed_to_a_function() {
+        let index = LocalFunctionIndex::from_u32(3);
+        let error = CompileError::Codegen("boom".to_string()).in_function(index);
+        assert_eq!(error.function_index(), Some(index));
+
+        // Attributing twice keeps the innermost function.
+        let error = error.in_function(LocalFunctionIndex::from_u32(7));
+        match error {
+            CompileError::Function { index, name, error } => {
+                assert_eq!(index.as_u32(), 3);
+                assert_eq!(name, None);
+                assert!(matches!(*error, CompileError::Codegen(ref message) if message == "boom"));
+            }
+            err => panic!("Unexpected error: {:?}", err),
+        }
+    }
+
     #[test]
     fn middleware_error_can_be_created() {
         let msg = String::from("Something went wrong");
@@ -55,7 +55,11 @@ mod compiler;
 mod error;
 mod function;
 mod jump_table;
+mod limits;
 mod module;
+mod optimization;
+mod proposals;
+mod pruning;
 mod relocation;
 mod target;
 mod trap;
@@ -68,7 +72,9 @@ mod sourceloc;
 
 pub use crate::address_map::{FunctionAddressMap, InstructionAddressMap};
 #[cfg(feature = "translator")]
-pub use crate::compiler::{Compiler, CompilerConfig, Symbol, SymbolRegistry};
+pub use crate::compiler::{
+    validate_module, CompileProgress, Compiler, CompilerConfig, Symbol, SymbolRegistry,
+};
 pub use crate::error::{
     CompileError, MiddlewareError, ParseCpuFeatureError, WasmError, WasmResult,
 };
@@ -77,7 +83,13 @@ pub use crate::function::{
     Functions,
 };
 pub use crate::jump_table::{JumpTable, JumpTableOffsets};
+pub use crate::limits::{CompilationLimit, CompilationLimits};
 pub use crate::module::CompileModuleInfo;
+pub use crate::optimization::{OptimizationHints, OptimizationLevel, OPTIMIZATION_HINTS_SECTION};
+pub use crate::proposals::WasmProposal;
+#[cfg(feature = "translator")]
+pub use crate::pruning::prune_functions;
+pub use crate::pruning::{pruned_functions, PRUNED_FUNCTIONS_SECTION};
 pub use crate::relocation::{Relocation, RelocationKind, RelocationTarget, Relocations};
 pub use crate::section::{CustomSection, CustomSectionProtection, SectionBody, SectionIndex};
 pub use crate::sourceloc::SourceLoc;
@@ -95,6 +107,7 @@ pub use crate::trap::TrapInformation;
 pub use crate::unwind::CompiledFunctionUnwindInfo;
 
 pub use wasmer_types::Features;
+pub use wasmer_vm::libcalls::LibCall;
 
 #[cfg(feature = "translator")]
 /// wasmparser is exported as a module to slim compiler dependencies
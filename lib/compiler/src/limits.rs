@@ -0,0 +1,111 @@
+//! Limits on the structure of the modules to compile.
+
+use crate::error::CompileError;
+use crate::lib::std::fmt;
+use wasmer_vm::ModuleInfo;
+
+/// Limits on the structure of a module, checked after it is
+/// translated and before it is compiled.
+///
+/// They protect embedders compiling untrusted modules from modules
+/// whose structure alone exhausts resources, such as millions of
+/// functions or huge function bodies, much more cheaply than letting
+/// the compiler run out of memory. Every limit is unset by default.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompilationLimits {
+    /// The maximum number of functions, imported ones included.
+    pub max_functions: Option<u32>,
+    /// The maximum size of a single function body, in bytes.
+    pub max_function_body_size: Option<u32>,
+    /// The maximum initial number of elements of a table.
+    pub max_table_elements: Option<u32>,
+    /// The maximum initial number of pages of a memory.
+    pub max_memory_pages: Option<u32>,
+    /// The maximum number of globals, imported ones included.
+    pub max_globals: Option<u32>,
+}
+
+/// One of the [`CompilationLimits`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompilationLimit {
+    /// [`CompilationLimits::max_functions`].
+    Functions,
+    /// [`CompilationLimits::max_function_body_size`].
+    FunctionBodySize,
+    /// [`CompilationLimits::max_table_elements`].
+    TableElements,
+    /// [`CompilationLimits::max_memory_pages`].
+    MemoryPages,
+    /// [`CompilationLimits::max_globals`].
+    Globals,
+}
+
+impl fmt::Display for CompilationLimit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Functions => "functions",
+            Self::FunctionBodySize => "bytes per function body",
+            Self::TableElements => "table elements",
+            Self::MemoryPages => "memory pages",
+            Self::Globals => "globals",
+        })
+    }
+}
+
+impl CompilationLimits {
+    /// Checks `module`, whose local functions have bodies of
+    /// `function_body_sizes` bytes, against the limits.
+    pub fn check(
+        &self,
+        module: &ModuleInfo,
+        function_body_sizes: impl IntoIterator<Item = usize>,
+    ) -> Result<(), CompileError> {
+        fn check_limit(
+            which: CompilationLimit,
+            limit: Option<u32>,
+            actual: impl IntoIterator<Item = u64>,
+        ) -> Result<(), CompileError> {
+            let limit = match limit {
+                Some(limit) => u64::from(limit),
+                None => return Ok(()),
+            };
+            match actual.into_iter().find(|actual| *actual > limit) {
+                Some(actual) => Err(CompileError::ExceedsLimits {
+                    which,
+                    limit,
+                    actual,
+                }),
+                None => Ok(()),
+            }
+        }
+
+        check_limit(
+            CompilationLimit::Functions,
+            self.max_functions,
+            Some(module.functions.len() as u64),
+        )?;
+        check_limit(
+            CompilationLimit::Globals,
+            self.max_globals,
+            Some(module.globals.len() as u64),
+        )?;
+        check_limit(
+            CompilationLimit::TableElements,
+            self.max_table_elements,
+            module.tables.values().map(|table| u64::from(table.minimum)),
+        )?;
+        check_limit(
+            CompilationLimit::MemoryPages,
+            self.max_memory_pages,
+            module
+                .memories
+                .values()
+                .map(|memory| u64::from(memory.minimum.0)),
+        )?;
+        check_limit(
+            CompilationLimit::FunctionBodySize,
+            self.max_function_body_size,
+            function_body_sizes.into_iter().map(|size| size as u64),
+        )
+    }
+}
@@ -0,0 +1,229 @@
+//! Per-function optimization hints.
+//!
+//! A toolchain can ask for some functions of a module to be optimized
+//! differently than the rest, for example to compile the few hot
+//! functions of a module with every optimization while compiling the
+//! rest as fast as possible. The hints are stored in the
+//! [`OPTIMIZATION_HINTS_SECTION`] custom section of the module.
+//!
+//! Only the Cranelift compiler honors the hints. The LLVM compiler
+//! optimizes every function at its configured level, and Singlepass
+//! doesn't optimize: both ignore the section.
+
+use crate::lib::std::string::String;
+use crate::lib::std::vec::Vec;
+use wasmer_types::entity::{EntityRef, SecondaryMap};
+use wasmer_types::{FunctionIndex, LocalFunctionIndex};
+use wasmer_vm::ModuleInfo;
+
+/// The name of the custom section holding the optimization hints.
+///
+/// The section is a sequence of entries, each made of a function index
+/// (in the function index space of the module, encoded as a LEB128
+/// `u32`) followed by one byte for the [`OptimizationLevel`] of the
+/// function: `0` for `None`, `1` for `Speed` and `2` for
+/// `SpeedAndSize`.
+pub const OPTIMIZATION_HINTS_SECTION: &str = "wasmer.optimization_hints";
+
+/// How much a function is optimized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptimizationLevel {
+    /// No optimizations, to minimize compilation time.
+    None,
+    /// Generate the fastest possible code.
+    Speed,
+    /// Generate the fastest possible code, while also reducing its size.
+    SpeedAndSize,
+}
+
+/// The optimization levels requested for the functions of a module.
+#[derive(Debug, Clone, Default)]
+pub struct OptimizationHints {
+    levels: SecondaryMap<LocalFunctionIndex, Option<OptimizationLevel>>,
+    ignored: Vec<String>,
+}
+
+impl OptimizationHints {
+    /// Reads the hints from the [`OPTIMIZATION_HINTS_SECTION`] of
+    /// `module`, if any.
+    ///
+    /// Hints that can't be honored, such as hints for functions that
+    /// don't exist or that are imported, are ignored, and described in
+    /// [`OptimizationHints::ignored`] so compilers can warn about them.
+    pub fn from_module(module: &ModuleInfo) -> Self {
+        let mut hints = Self::default();
+        let data = match module.custom_sections(OPTIMIZATION_HINTS_SECTION).next() {
+            Some(data) => data,
+            None => return hints,
+        };
+
+        let mut data = &data[..];
+        while !data.is_empty() {
+            let (index, level) = match read_u32_leb128(&mut data)
+                .and_then(|index| data.split_first().map(|(level, _)| (index, *level)))
+            {
+                Some(entry) => entry,
+                None => {
+                    hints
+                        .ignored
+                        .push("the optimization hints section is truncated".into());
+                    break;
+                }
+            };
+            data = &data[1..];
+
+            let level = match level {
+                0 => OptimizationLevel::None,
+                1 => OptimizationLevel::Speed,
+                2 => OptimizationLevel::SpeedAndSize,
+                _ => {
+                    hints.ignored.push(format!(
+                        "unknown optimization level {} for function {}",
+                        level, index
+                    ));
+                    continue;
+                }
+            };
+            let function_index = FunctionIndex::from_u32(index);
+            if function_index.index() >= module.functions.len() {
+                hints.ignored.push(format!(
+                    "optimization hint for function {}, which doesn't exist",
+                    index
+                ));
+                continue;
+            }
+            match module.local_func_index(function_index) {
+                Some(local_index) => hints.levels[local_index] = Some(level),
+                None => hints.ignored.push(format!(
+                    "optimization hint for function {}, which is imported",
+                    index
+                )),
+            }
+        }
+        hints
+    }
+
+    /// Returns the optimization level requested for a function, if any.
+    pub fn get(&self, index: LocalFunctionIndex) -> Option<OptimizationLevel> {
+        self.levels[index]
+    }
+
+    /// Returns the distinct optimization levels requested by the hints.
+    pub fn levels(&self) -> Vec<OptimizationLevel> {
+        let mut levels = Vec::new();
+        for level in self.levels.values().flatten() {
+            if !levels.contains(level) {
+                levels.push(*level);
+            }
+        }
+        levels
+    }
+
+    /// Describes the hints that were ignored.
+    pub fn ignored(&self) -> &[String] {
+        &self.ignored
+    }
+}
+
+/// Reads an unsigned LEB128 `u32` from the start of `data`, advancing it.
+///
+/// Like wasmparser, this rejects encodings longer than 5 bytes, and a
+/// fifth byte with bits set above the 32 bits of a `u32`.
+pub(crate) fn read_u32_leb128(data: &mut &[u8]) -> Option<u32> {
+    let mut result = 0u32;
+    for (i, &byte) in data.iter().enumerate().take(5) {
+        if i == 4 && byte & 0xf0 != 0 {
+            return None;
+        }
+        result |= u32::from(byte & 0x7f) << (7 * i);
+        if byte & 0x80 == 0 {
+            *data = &data[i + 1..];
+            return Some(result);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lib::std::sync::Arc;
+    use wasmer_types::{FunctionType, ImportIndex};
+
+    fn module_with_hints(hints: &[u8]) -> ModuleInfo {
+        let mut module = ModuleInfo::new();
+        let signature = module.signatures.push(FunctionType::new(vec![], vec![]));
+        for _ in 0..4 {
+            module.functions.push(signature);
+        }
+        module.num_imported_functions = 1;
+        module.imports.insert(
+            ("env".into(), "f".into(), 0),
+            ImportIndex::Function(FunctionIndex::new(0)),
+        );
+        let section = module.custom_sections_data.push(Arc::from(hints));
+        module
+            .custom_sections
+            .insert(OPTIMIZATION_HINTS_SECTION.into(), section);
+        module
+    }
+
+    #[test]
+    fn no_hints() {
+        let module = ModuleInfo::new();
+        let hints = OptimizationHints::from_module(&module);
+        assert!(hints.levels().is_empty());
+        assert!(hints.ignored().is_empty());
+    }
+
+    #[test]
+    fn hints_request_levels_per_function() {
+        // Function 1 is optimized for speed, function 3 isn't optimized,
+        // the hints for function 0 (imported) and 99 (missing) are ignored.
+        let module = module_with_hints(&[1, 1, 3, 0, 0, 2, 99, 1]);
+        let hints = OptimizationHints::from_module(&module);
+        assert_eq!(hints.ignored().len(), 2);
+
+        // A mock backend compiling every local function, counting the
+        // levels it is asked to compile with.
+        let default = OptimizationLevel::SpeedAndSize;
+        let (mut none, mut speed, mut speed_and_size) = (0, 0, 0);
+        for index in 0..module.functions.len() - module.num_imported_functions {
+            match hints.get(LocalFunctionIndex::new(index)).unwrap_or(default) {
+                OptimizationLevel::None => none += 1,
+                OptimizationLevel::Speed => speed += 1,
+                OptimizationLevel::SpeedAndSize => speed_and_size += 1,
+            }
+        }
+        assert_eq!((none, speed, speed_and_size), (1, 1, 1));
+        assert_eq!(
+            hints.levels(),
+            vec![OptimizationLevel::Speed, OptimizationLevel::None]
+        );
+    }
+
+    #[test]
+    fn malformed_hints_are_ignored() {
+        let module = module_with_hints(&[1, 7, 0x80]);
+        let hints = OptimizationHints::from_module(&module);
+        assert_eq!(hints.get(LocalFunctionIndex::new(0)), None);
+        assert_eq!(hints.get(LocalFunctionIndex::new(1)), None);
+        assert_eq!(hints.ignored().len(), 2);
+    }
+
+    #[test]
+    fn leb128_rejects_bits_above_u32() {
+        let mut data = &[0xff, 0xff, 0xff, 0xff, 0x0f, 0x2a][..];
+        assert_eq!(read_u32_leb128(&mut data), Some(u32::max_value()));
+        assert_eq!(data, &[0x2a]);
+
+        assert_eq!(
+            read_u32_leb128(&mut &[0xff, 0xff, 0xff, 0xff, 0x1f][..]),
+            None
+        );
+        assert_eq!(
+            read_u32_leb128(&mut &[0x80, 0x80, 0x80, 0x80, 0x80, 0x00][..]),
+            None
+        );
+    }
+}
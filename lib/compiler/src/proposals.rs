@@ -0,0 +1,449 @@
+//! Detection of the WebAssembly proposals a module uses.
+//!
+//! The [`Features`] of an engine say which proposals a module may use.
+//! The translator records which ones it actually uses, which is what
+//! matters to know whether it runs on another runtime.
+
+use crate::lib::std::fmt;
+#[cfg(feature = "translator")]
+use crate::translator::wptype_to_type;
+use wasmer_types::Features;
+#[cfg(feature = "translator")]
+use wasmer_types::Type;
+#[cfg(feature = "translator")]
+use wasmparser::{Operator, TypeOrFuncType};
+
+/// A WebAssembly proposal, enabled by one of the flags of [`Features`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum WasmProposal {
+    /// The threads proposal: shared memories and atomic instructions.
+    Threads,
+    /// The reference types proposal.
+    ReferenceTypes,
+    /// The fixed-width SIMD proposal.
+    Simd,
+    /// The bulk memory operations proposal.
+    BulkMemory,
+    /// The multi-value proposal.
+    MultiValue,
+    /// The tail call proposal.
+    TailCall,
+    /// The module linking proposal.
+    ModuleLinking,
+    /// The multi-memory proposal.
+    MultiMemory,
+    /// The 64-bit memory proposal.
+    Memory64,
+    /// The exception handling proposal.
+    Exceptions,
+}
+
+impl WasmProposal {
+    /// All the proposals, in the order of the flags of [`Features`].
+    pub const ALL: [Self; 10] = [
+        Self::Threads,
+        Self::ReferenceTypes,
+        Self::Simd,
+        Self::BulkMemory,
+        Self::MultiValue,
+        Self::TailCall,
+        Self::ModuleLinking,
+        Self::MultiMemory,
+        Self::Memory64,
+        Self::Exceptions,
+    ];
+
+    /// Returns whether `features` enables this proposal.
+    pub fn is_enabled(self, features: &Features) -> bool {
+        let mut features = features.clone();
+        *self.flag(&mut features)
+    }
+
+    pub(crate) fn flag(self, features: &mut Features) -> &mut bool {
+        match self {
+            Self::Threads => &mut features.threads,
+            Self::ReferenceTypes => &mut features.reference_types,
+            Self::Simd => &mut features.simd,
+            Self::BulkMemory => &mut features.bulk_memory,
+            Self::MultiValue => &mut features.multi_value,
+            Self::TailCall => &mut features.tail_call,
+            Self::ModuleLinking => &mut features.module_linking,
+            Self::MultiMemory => &mut features.multi_memory,
+            Self::Memory64 => &mut features.memory64,
+            Self::Exceptions => &mut features.exceptions,
+        }
+    }
+}
+
+impl fmt::Display for WasmProposal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Threads => "threads",
+            Self::ReferenceTypes => "reference types",
+            Self::Simd => "SIMD",
+            Self::BulkMemory => "bulk memory",
+            Self::MultiValue => "multi-value",
+            Self::TailCall => "tail call",
+            Self::ModuleLinking => "module linking",
+            Self::MultiMemory => "multi-memory",
+            Self::Memory64 => "64-bit memory",
+            Self::Exceptions => "exceptions",
+        })
+    }
+}
+
+/// Returns the proposal that introduced the value type `ty`, if any.
+#[cfg(feature = "translator")]
+pub(crate) fn type_proposal(ty: Type) -> Option<WasmProposal> {
+    match ty {
+        Type::V128 => Some(WasmProposal::Simd),
+        Type::ExternRef | Type::FuncRef => Some(WasmProposal::ReferenceTypes),
+        Type::I32 | Type::I64 | Type::F32 | Type::F64 => None,
+    }
+}
+
+/// Returns the proposal that introduced `operator`, if any.
+///
+/// The operators taking a memory or a table index aren't attributed to
+/// multi-memory or reference types when the index isn't 0: a module
+/// can't use such an index without declaring several memories or
+/// tables, which is recorded with the declarations.
+#[cfg(feature = "translator")]
+pub(crate) fn operator_proposal(operator: &Operator) -> Option<WasmProposal> {
+    match operator {
+        Operator::Block { ty } | Operator::Loop { ty } | Operator::If { ty } => match ty {
+            TypeOrFuncType::FuncType(_) => Some(WasmProposal::MultiValue),
+            TypeOrFuncType::Type(ty) => wptype_to_type(*ty).ok().and_then(type_proposal),
+        },
+        Operator::MemoryInit { .. }
+        | Operator::DataDrop { .. }
+        | Operator::MemoryCopy { .. }
+        | Operator::MemoryFill { .. }
+        | Operator::TableInit { .. }
+        | Operator::ElemDrop { .. }
+        | Operator::TableCopy { .. } => Some(WasmProposal::BulkMemory),
+        Operator::TypedSelect { .. }
+        | Operator::RefNull { .. }
+        | Operator::RefIsNull { .. }
+        | Operator::RefFunc { .. }
+        | Operator::TableGet { .. }
+        | Operator::TableSet { .. }
+        | Operator::TableGrow { .. }
+        | Operator::TableSize { .. }
+        | Operator::TableFill { .. } => Some(WasmProposal::ReferenceTypes),
+        Operator::ReturnCall { .. } | Operator::ReturnCallIndirect { .. } => {
+            Some(WasmProposal::TailCall)
+        }
+        Operator::Try { .. }
+        | Operator::Catch { .. }
+        | Operator::Throw { .. }
+        | Operator::Rethrow { .. }
+        | Operator::Unwind { .. }
+        | Operator::Delegate { .. }
+        | Operator::CatchAll { .. } => Some(WasmProposal::Exceptions),
+        Operator::AtomicFence { .. }
+        | Operator::I32AtomicLoad { .. }
+        | Operator::I32AtomicLoad16U { .. }
+        | Operator::I32AtomicLoad8U { .. }
+        | Operator::I32AtomicRmw16AddU { .. }
+        | Operator::I32AtomicRmw16AndU { .. }
+        | Operator::I32AtomicRmw16CmpxchgU { .. }
+        | Operator::I32AtomicRmw16OrU { .. }
+        | Operator::I32AtomicRmw16SubU { .. }
+        | Operator::I32AtomicRmw16XchgU { .. }
+        | Operator::I32AtomicRmw16XorU { .. }
+        | Operator::I32AtomicRmw8AddU { .. }
+        | Operator::I32AtomicRmw8AndU { .. }
+        | Operator::I32AtomicRmw8CmpxchgU { .. }
+        | Operator::I32AtomicRmw8OrU { .. }
+        | Operator::I32AtomicRmw8SubU { .. }
+        | Operator::I32AtomicRmw8XchgU { .. }
+        | Operator::I32AtomicRmw8XorU { .. }
+        | Operator::I32AtomicRmwAdd { .. }
+        | Operator::I32AtomicRmwAnd { .. }
+        | Operator::I32AtomicRmwCmpxchg { .. }
+        | Operator::I32AtomicRmwOr { .. }
+        | Operator::I32AtomicRmwSub { .. }
+        | Operator::I32AtomicRmwXchg { .. }
+        | Operator::I32AtomicRmwXor { .. }
+        | Operator::I32AtomicStore { .. }
+        | Operator::I32AtomicStore16 { .. }
+        | Operator::I32AtomicStore8 { .. }
+        | Operator::I64AtomicLoad { .. }
+        | Operator::I64AtomicLoad16U { .. }
+        | Operator::I64AtomicLoad32U { .. }
+        | Operator::I64AtomicLoad8U { .. }
+        | Operator::I64AtomicRmw16AddU { .. }
+        | Operator::I64AtomicRmw16AndU { .. }
+        | Operator::I64AtomicRmw16CmpxchgU { .. }
+        | Operator::I64AtomicRmw16OrU { .. }
+        | Operator::I64AtomicRmw16SubU { .. }
+        | Operator::I64AtomicRmw16XchgU { .. }
+        | Operator::I64AtomicRmw16XorU { .. }
+        | Operator::I64AtomicRmw32AddU { .. }
+        | Operator::I64AtomicRmw32AndU { .. }
+        | Operator::I64AtomicRmw32CmpxchgU { .. }
+        | Operator::I64AtomicRmw32OrU { .. }
+        | Operator::I64AtomicRmw32SubU { .. }
+        | Operator::I64AtomicRmw32XchgU { .. }
+        | Operator::I64AtomicRmw32XorU { .. }
+        | Operator::I64AtomicRmw8AddU { .. }
+        | Operator::I64AtomicRmw8AndU { .. }
+        | Operator::I64AtomicRmw8CmpxchgU { .. }
+        | Operator::I64AtomicRmw8OrU { .. }
+        | Operator::I64AtomicRmw8SubU { .. }
+        | Operator::I64AtomicRmw8XchgU { .. }
+        | Operator::I64AtomicRmw8XorU { .. }
+        | Operator::I64AtomicRmwAdd { .. }
+        | Operator::I64AtomicRmwAnd { .. }
+        | Operator::I64AtomicRmwCmpxchg { .. }
+        | Operator::I64AtomicRmwOr { .. }
+        | Operator::I64AtomicRmwSub { .. }
+        | Operator::I64AtomicRmwXchg { .. }
+        | Operator::I64AtomicRmwXor { .. }
+        | Operator::I64AtomicStore { .. }
+        | Operator::I64AtomicStore16 { .. }
+        | Operator::I64AtomicStore32 { .. }
+        | Operator::I64AtomicStore8 { .. }
+        | Operator::MemoryAtomicNotify { .. }
+        | Operator::MemoryAtomicWait32 { .. }
+        | Operator::MemoryAtomicWait64 { .. } => Some(WasmProposal::Threads),
+        Operator::F32x4Abs { .. }
+        | Operator::F32x4Add { .. }
+        | Operator::F32x4Ceil { .. }
+        | Operator::F32x4ConvertI32x4S { .. }
+        | Operator::F32x4ConvertI32x4U { .. }
+        | Operator::F32x4DemoteF64x2Zero { .. }
+        | Operator::F32x4Div { .. }
+        | Operator::F32x4Eq { .. }
+        | Operator::F32x4ExtractLane { .. }
+        | Operator::F32x4Floor { .. }
+        | Operator::F32x4Ge { .. }
+        | Operator::F32x4Gt { .. }
+        | Operator::F32x4Le { .. }
+        | Operator::F32x4Lt { .. }
+        | Operator::F32x4Max { .. }
+        | Operator::F32x4Min { .. }
+        | Operator::F32x4Mul { .. }
+        | Operator::F32x4Ne { .. }
+        | Operator::F32x4Nearest { .. }
+        | Operator::F32x4Neg { .. }
+        | Operator::F32x4PMax { .. }
+        | Operator::F32x4PMin { .. }
+        | Operator::F32x4ReplaceLane { .. }
+        | Operator::F32x4Splat { .. }
+        | Operator::F32x4Sqrt { .. }
+        | Operator::F32x4Sub { .. }
+        | Operator::F32x4Trunc { .. }
+        | Operator::F64x2Abs { .. }
+        | Operator::F64x2Add { .. }
+        | Operator::F64x2Ceil { .. }
+        | Operator::F64x2ConvertLowI32x4S { .. }
+        | Operator::F64x2ConvertLowI32x4U { .. }
+        | Operator::F64x2Div { .. }
+        | Operator::F64x2Eq { .. }
+        | Operator::F64x2ExtractLane { .. }
+        | Operator::F64x2Floor { .. }
+        | Operator::F64x2Ge { .. }
+        | Operator::F64x2Gt { .. }
+        | Operator::F64x2Le { .. }
+        | Operator::F64x2Lt { .. }
+        | Operator::F64x2Max { .. }
+        | Operator::F64x2Min { .. }
+        | Operator::F64x2Mul { .. }
+        | Operator::F64x2Ne { .. }
+        | Operator::F64x2Nearest { .. }
+        | Operator::F64x2Neg { .. }
+        | Operator::F64x2PMax { .. }
+        | Operator::F64x2PMin { .. }
+        | Operator::F64x2PromoteLowF32x4 { .. }
+        | Operator::F64x2ReplaceLane { .. }
+        | Operator::F64x2Splat { .. }
+        | Operator::F64x2Sqrt { .. }
+        | Operator::F64x2Sub { .. }
+        | Operator::F64x2Trunc { .. }
+        | Operator::I16x8Abs { .. }
+        | Operator::I16x8Add { .. }
+        | Operator::I16x8AddSatS { .. }
+        | Operator::I16x8AddSatU { .. }
+        | Operator::I16x8AllTrue { .. }
+        | Operator::I16x8Bitmask { .. }
+        | Operator::I16x8Eq { .. }
+        | Operator::I16x8ExtAddPairwiseI8x16S { .. }
+        | Operator::I16x8ExtAddPairwiseI8x16U { .. }
+        | Operator::I16x8ExtMulHighI8x16S { .. }
+        | Operator::I16x8ExtMulHighI8x16U { .. }
+        | Operator::I16x8ExtMulLowI8x16S { .. }
+        | Operator::I16x8ExtMulLowI8x16U { .. }
+        | Operator::I16x8ExtendHighI8x16S { .. }
+        | Operator::I16x8ExtendHighI8x16U { .. }
+        | Operator::I16x8ExtendLowI8x16S { .. }
+        | Operator::I16x8ExtendLowI8x16U { .. }
+        | Operator::I16x8ExtractLaneS { .. }
+        | Operator::I16x8ExtractLaneU { .. }
+        | Operator::I16x8GeS { .. }
+        | Operator::I16x8GeU { .. }
+        | Operator::I16x8GtS { .. }
+        | Operator::I16x8GtU { .. }
+        | Operator::I16x8LeS { .. }
+        | Operator::I16x8LeU { .. }
+        | Operator::I16x8LtS { .. }
+        | Operator::I16x8LtU { .. }
+        | Operator::I16x8MaxS { .. }
+        | Operator::I16x8MaxU { .. }
+        | Operator::I16x8MinS { .. }
+        | Operator::I16x8MinU { .. }
+        | Operator::I16x8Mul { .. }
+        | Operator::I16x8NarrowI32x4S { .. }
+        | Operator::I16x8NarrowI32x4U { .. }
+        | Operator::I16x8Ne { .. }
+        | Operator::I16x8Neg { .. }
+        | Operator::I16x8Q15MulrSatS { .. }
+        | Operator::I16x8ReplaceLane { .. }
+        | Operator::I16x8RoundingAverageU { .. }
+        | Operator::I16x8Shl { .. }
+        | Operator::I16x8ShrS { .. }
+        | Operator::I16x8ShrU { .. }
+        | Operator::I16x8Splat { .. }
+        | Operator::I16x8Sub { .. }
+        | Operator::I16x8SubSatS { .. }
+        | Operator::I16x8SubSatU { .. }
+        | Operator::I32x4Abs { .. }
+        | Operator::I32x4Add { .. }
+        | Operator::I32x4AllTrue { .. }
+        | Operator::I32x4Bitmask { .. }
+        | Operator::I32x4DotI16x8S { .. }
+        | Operator::I32x4Eq { .. }
+        | Operator::I32x4ExtAddPairwiseI16x8S { .. }
+        | Operator::I32x4ExtAddPairwiseI16x8U { .. }
+        | Operator::I32x4ExtMulHighI16x8S { .. }
+        | Operator::I32x4ExtMulHighI16x8U { .. }
+        | Operator::I32x4ExtMulLowI16x8S { .. }
+        | Operator::I32x4ExtMulLowI16x8U { .. }
+        | Operator::I32x4ExtendHighI16x8S { .. }
+        | Operator::I32x4ExtendHighI16x8U { .. }
+        | Operator::I32x4ExtendLowI16x8S { .. }
+        | Operator::I32x4ExtendLowI16x8U { .. }
+        | Operator::I32x4ExtractLane { .. }
+        | Operator::I32x4GeS { .. }
+        | Operator::I32x4GeU { .. }
+        | Operator::I32x4GtS { .. }
+        | Operator::I32x4GtU { .. }
+        | Operator::I32x4LeS { .. }
+        | Operator::I32x4LeU { .. }
+        | Operator::I32x4LtS { .. }
+        | Operator::I32x4LtU { .. }
+        | Operator::I32x4MaxS { .. }
+        | Operator::I32x4MaxU { .. }
+        | Operator::I32x4MinS { .. }
+        | Operator::I32x4MinU { .. }
+        | Operator::I32x4Mul { .. }
+        | Operator::I32x4Ne { .. }
+        | Operator::I32x4Neg { .. }
+        | Operator::I32x4ReplaceLane { .. }
+        | Operator::I32x4Shl { .. }
+        | Operator::I32x4ShrS { .. }
+        | Operator::I32x4ShrU { .. }
+        | Operator::I32x4Splat { .. }
+        | Operator::I32x4Sub { .. }
+        | Operator::I32x4TruncSatF32x4S { .. }
+        | Operator::I32x4TruncSatF32x4U { .. }
+        | Operator::I32x4TruncSatF64x2SZero { .. }
+        | Operator::I32x4TruncSatF64x2UZero { .. }
+        | Operator::I64x2Abs { .. }
+        | Operator::I64x2Add { .. }
+        | Operator::I64x2AllTrue { .. }
+        | Operator::I64x2Bitmask { .. }
+        | Operator::I64x2Eq { .. }
+        | Operator::I64x2ExtMulHighI32x4S { .. }
+        | Operator::I64x2ExtMulHighI32x4U { .. }
+        | Operator::I64x2ExtMulLowI32x4S { .. }
+        | Operator::I64x2ExtMulLowI32x4U { .. }
+        | Operator::I64x2ExtendHighI32x4S { .. }
+        | Operator::I64x2ExtendHighI32x4U { .. }
+        | Operator::I64x2ExtendLowI32x4S { .. }
+        | Operator::I64x2ExtendLowI32x4U { .. }
+        | Operator::I64x2ExtractLane { .. }
+        | Operator::I64x2GeS { .. }
+        | Operator::I64x2GtS { .. }
+        | Operator::I64x2LeS { .. }
+        | Operator::I64x2LtS { .. }
+        | Operator::I64x2Mul { .. }
+        | Operator::I64x2Ne { .. }
+        | Operator::I64x2Neg { .. }
+        | Operator::I64x2ReplaceLane { .. }
+        | Operator::I64x2Shl { .. }
+        | Operator::I64x2ShrS { .. }
+        | Operator::I64x2ShrU { .. }
+        | Operator::I64x2Splat { .. }
+        | Operator::I64x2Sub { .. }
+        | Operator::I8x16Abs { .. }
+        | Operator::I8x16Add { .. }
+        | Operator::I8x16AddSatS { .. }
+        | Operator::I8x16AddSatU { .. }
+        | Operator::I8x16AllTrue { .. }
+        | Operator::I8x16Bitmask { .. }
+        | Operator::I8x16Eq { .. }
+        | Operator::I8x16ExtractLaneS { .. }
+        | Operator::I8x16ExtractLaneU { .. }
+        | Operator::I8x16GeS { .. }
+        | Operator::I8x16GeU { .. }
+        | Operator::I8x16GtS { .. }
+        | Operator::I8x16GtU { .. }
+        | Operator::I8x16LeS { .. }
+        | Operator::I8x16LeU { .. }
+        | Operator::I8x16LtS { .. }
+        | Operator::I8x16LtU { .. }
+        | Operator::I8x16MaxS { .. }
+        | Operator::I8x16MaxU { .. }
+        | Operator::I8x16MinS { .. }
+        | Operator::I8x16MinU { .. }
+        | Operator::I8x16NarrowI16x8S { .. }
+        | Operator::I8x16NarrowI16x8U { .. }
+        | Operator::I8x16Ne { .. }
+        | Operator::I8x16Neg { .. }
+        | Operator::I8x16Popcnt { .. }
+        | Operator::I8x16ReplaceLane { .. }
+        | Operator::I8x16RoundingAverageU { .. }
+        | Operator::I8x16Shl { .. }
+        | Operator::I8x16ShrS { .. }
+        | Operator::I8x16ShrU { .. }
+        | Operator::I8x16Shuffle { .. }
+        | Operator::I8x16Splat { .. }
+        | Operator::I8x16Sub { .. }
+        | Operator::I8x16SubSatS { .. }
+        | Operator::I8x16SubSatU { .. }
+        | Operator::I8x16Swizzle { .. }
+        | Operator::V128And { .. }
+        | Operator::V128AndNot { .. }
+        | Operator::V128AnyTrue { .. }
+        | Operator::V128Bitselect { .. }
+        | Operator::V128Const { .. }
+        | Operator::V128Load { .. }
+        | Operator::V128Load16Lane { .. }
+        | Operator::V128Load16Splat { .. }
+        | Operator::V128Load16x4S { .. }
+        | Operator::V128Load16x4U { .. }
+        | Operator::V128Load32Lane { .. }
+        | Operator::V128Load32Splat { .. }
+        | Operator::V128Load32Zero { .. }
+        | Operator::V128Load32x2S { .. }
+        | Operator::V128Load32x2U { .. }
+        | Operator::V128Load64Lane { .. }
+        | Operator::V128Load64Splat { .. }
+        | Operator::V128Load64Zero { .. }
+        | Operator::V128Load8Lane { .. }
+        | Operator::V128Load8Splat { .. }
+        | Operator::V128Load8x8S { .. }
+        | Operator::V128Load8x8U { .. }
+        | Operator::V128Not { .. }
+        | Operator::V128Or { .. }
+        | Operator::V128Store { .. }
+        | Operator::V128Store16Lane { .. }
+        | Operator::V128Store32Lane { .. }
+        | Operator::V128Store64Lane { .. }
+        | Operator::V128Store8Lane { .. }
+        | Operator::V128Xor { .. } => Some(WasmProposal::Simd),
+        _ => None,
+    }
+}
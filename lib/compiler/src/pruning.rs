@@ -0,0 +1,284 @@
+//! Pruning of the functions of a module that can't be reached from a
+//! set of exported roots.
+//!
+//! Embedders often call only a few of the exports of a large module.
+//! [`prune_functions`] rewrites such a module so that the functions that
+//! can't be reached from those exports are replaced by stubs that trap,
+//! which makes the module faster to compile and its artifact smaller.
+//! The pruned functions are recorded in the [`PRUNED_FUNCTIONS_SECTION`]
+//! custom section, so that a trap in one of them can be reported as
+//! such.
+
+use crate::lib::std::vec::Vec;
+use crate::optimization::read_u32_leb128;
+use wasmer_types::FunctionIndex;
+use wasmer_vm::ModuleInfo;
+#[cfg(feature = "translator")]
+use {
+    crate::lib::std::collections::HashSet,
+    crate::{WasmError, WasmResult},
+    wasmparser::{ElementItem, ExternalKind, ImportSectionEntryType, Operator, Parser, Payload},
+};
+
+/// The name of the custom section listing the pruned functions.
+///
+/// The section is a sequence of function indices, in the function index
+/// space of the module, each encoded as a LEB128 `u32`.
+pub const PRUNED_FUNCTIONS_SECTION: &str = "wasmer.pruned_functions";
+
+/// The body of a pruned function: no locals, `unreachable`, `end`.
+#[cfg(feature = "translator")]
+const PRUNED_BODY: [u8; 3] = [0x00, 0x00, 0x0b];
+
+/// The id of the code section.
+#[cfg(feature = "translator")]
+const CODE_SECTION_ID: u8 = 10;
+
+/// Returns the functions of `module` listed in its
+/// [`PRUNED_FUNCTIONS_SECTION`], if any.
+pub fn pruned_functions(module: &ModuleInfo) -> Vec<FunctionIndex> {
+    let mut functions = Vec::new();
+    if let Some(data) = module.custom_sections(PRUNED_FUNCTIONS_SECTION).next() {
+        let mut data = &data[..];
+        while let Some(index) = read_u32_leb128(&mut data) {
+            functions.push(FunctionIndex::from_u32(index));
+        }
+    }
+    functions
+}
+
+/// Rewrites the validated Wasm binary `wasm` so that only the functions
+/// reachable from the exported functions named in `roots` keep their
+/// body. The body of every other function is replaced with a stub that
+/// traps.
+///
+/// A function is reachable if it's called or referenced (with
+/// `ref.func`) by a reachable function. The start function, the
+/// functions referenced by element segments (which can be called
+/// indirectly) and by global initializers are always reachable.
+///
+/// # Errors
+///
+/// Returns an error if a root isn't the name of an exported function,
+/// or if `wasm` can't be parsed.
+#[cfg(feature = "translator")]
+pub fn prune_functions(wasm: &[u8], roots: &[&str]) -> WasmResult<Vec<u8>> {
+    let mut num_imported_functions = 0;
+    let mut reachable = HashSet::new();
+    let mut exports = Vec::new();
+    let mut callees: Vec<Vec<u32>> = Vec::new();
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        match payload? {
+            Payload::ImportSection(imports) => {
+                for import in imports {
+                    if let ImportSectionEntryType::Function(_) = import?.ty {
+                        num_imported_functions += 1;
+                    }
+                }
+            }
+            Payload::ExportSection(section) => {
+                for export in section {
+                    let export = export?;
+                    if let ExternalKind::Function = export.kind {
+                        exports.push((export.field, export.index));
+                    }
+                }
+            }
+            Payload::StartSection { func, .. } => {
+                reachable.insert(func);
+            }
+            Payload::ElementSection(elements) => {
+                for element in elements {
+                    for item in element?.items.get_items_reader()? {
+                        if let ElementItem::Func(index) = item? {
+                            reachable.insert(index);
+                        }
+                    }
+                }
+            }
+            Payload::GlobalSection(globals) => {
+                for global in globals {
+                    for operator in global?.init_expr.get_operators_reader() {
+                        if let Operator::RefFunc { function_index } = operator? {
+                            reachable.insert(function_index);
+                        }
+                    }
+                }
+            }
+            Payload::CodeSectionEntry(body) => {
+                let mut called = Vec::new();
+                for operator in body.get_operators_reader()? {
+                    match operator? {
+                        Operator::Call { function_index }
+                        | Operator::ReturnCall { function_index }
+                        | Operator::RefFunc { function_index } => called.push(function_index),
+                        _ => {}
+                    }
+                }
+                callees.push(called);
+            }
+            _ => {}
+        }
+    }
+
+    for root in roots {
+        match exports.iter().find(|(name, _)| name == root) {
+            Some((_, index)) => reachable.insert(*index),
+            None => {
+                return Err(WasmError::Generic(format!(
+                    "there is no exported function named `{}` to keep",
+                    root
+                )))
+            }
+        };
+    }
+
+    // Walk the call graph from the roots.
+    let mut worklist: Vec<u32> = reachable.iter().copied().collect();
+    while let Some(index) = worklist.pop() {
+        let local_index = match index.checked_sub(num_imported_functions) {
+            Some(local_index) => local_index as usize,
+            None => continue,
+        };
+        for &callee in callees.get(local_index).into_iter().flatten() {
+            if reachable.insert(callee) {
+                worklist.push(callee);
+            }
+        }
+    }
+
+    let pruned: Vec<bool> = (0..callees.len() as u32)
+        .map(|local_index| !reachable.contains(&(local_index + num_imported_functions)))
+        .collect();
+    rewrite_code_section(wasm, num_imported_functions, &pruned)
+}
+
+/// Copies `wasm`, replacing the bodies of the functions that are
+/// `pruned`, by local function index, by stubs, and appends the
+/// [`PRUNED_FUNCTIONS_SECTION`].
+#[cfg(feature = "translator")]
+fn rewrite_code_section(
+    wasm: &[u8],
+    num_imported_functions: u32,
+    pruned: &[bool],
+) -> WasmResult<Vec<u8>> {
+    fn truncated() -> WasmError {
+        WasmError::Generic("the Wasm binary is truncated".into())
+    }
+
+    // Skip the magic number and version.
+    let mut output = wasm.get(..8).ok_or_else(truncated)?.to_vec();
+    let mut sections = &wasm[8..];
+    while let Some((&id, rest)) = sections.split_first() {
+        let mut rest = rest;
+        let size = read_u32_leb128(&mut rest).ok_or_else(truncated)? as usize;
+        let contents = rest.get(..size).ok_or_else(truncated)?;
+        sections = &rest[size..];
+
+        if id != CODE_SECTION_ID {
+            write_section(&mut output, id, contents);
+            continue;
+        }
+        let mut entries = contents;
+        let count = read_u32_leb128(&mut entries).ok_or_else(truncated)?;
+        let mut code = Vec::with_capacity(contents.len());
+        write_u32_leb128(&mut code, count);
+        for local_index in 0..count {
+            let size = read_u32_leb128(&mut entries).ok_or_else(truncated)? as usize;
+            let body = entries.get(..size).ok_or_else(truncated)?;
+            entries = &entries[size..];
+            let body = if pruned.get(local_index as usize) == Some(&true) {
+                &PRUNED_BODY[..]
+            } else {
+                body
+            };
+            write_u32_leb128(&mut code, body.len() as u32);
+            code.extend_from_slice(body);
+        }
+        write_section(&mut output, id, &code);
+    }
+
+    let mut section = Vec::new();
+    write_u32_leb128(&mut section, PRUNED_FUNCTIONS_SECTION.len() as u32);
+    section.extend_from_slice(PRUNED_FUNCTIONS_SECTION.as_bytes());
+    for (local_index, _) in pruned.iter().enumerate().filter(|(_, &pruned)| pruned) {
+        write_u32_leb128(&mut section, local_index as u32 + num_imported_functions);
+    }
+    write_section(&mut output, 0, &section);
+    Ok(output)
+}
+
+#[cfg(feature = "translator")]
+fn write_section(output: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    output.push(id);
+    write_u32_leb128(output, contents.len() as u32);
+    output.extend_from_slice(contents);
+}
+
+#[cfg(feature = "translator")]
+fn write_u32_leb128(output: &mut Vec<u8>, mut value: u32) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            output.push(byte);
+            return;
+        }
+        output.push(byte | 0x80);
+    }
+}
+
+#[cfg(all(test, feature = "translator"))]
+mod tests {
+    use super::*;
+    use crate::lib::std::sync::Arc;
+
+    /// `(func $a (call $b)) (func $b) (func $c (call $a)) (export "a" (func $a)) (export "c" (func $c))`
+    const MODULE: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, // header
+        0x01, 0x04, 0x01, 0x60, 0x00, 0x00, // type section: [] -> []
+        0x03, 0x04, 0x03, 0x00, 0x00, 0x00, // function section
+        0x07, 0x09, 0x02, 0x01, b'a', 0x00, 0x00, 0x01, b'c', 0x00, 0x02, // exports
+        0x0a, 0x0e, 0x03, // code section
+        0x04, 0x00, 0x10, 0x01, 0x0b, // $a
+        0x02, 0x00, 0x0b, // $b
+        0x04, 0x00, 0x10, 0x00, 0x0b, // $c
+    ];
+
+    fn pruned_of(wasm: &[u8]) -> Vec<FunctionIndex> {
+        let mut module = ModuleInfo::new();
+        for payload in Parser::new(0).parse_all(wasm) {
+            if let Payload::CustomSection { name, data, .. } = payload.unwrap() {
+                let section = module.custom_sections_data.push(Arc::from(data));
+                module.custom_sections.insert(name.into(), section);
+            }
+        }
+        pruned_functions(&module)
+    }
+
+    #[test]
+    fn prunes_functions_unreachable_from_roots() {
+        let pruned = prune_functions(MODULE, &["a"]).unwrap();
+        assert_eq!(pruned_of(&pruned), vec![FunctionIndex::from_u32(2)]);
+        // Only the body of `$c` changed.
+        assert_eq!(&pruned[..31], &MODULE[..31]);
+        assert_eq!(
+            &pruned[31..46],
+            &[
+                0x0a, 0x0d, 0x03, // code section
+                0x04, 0x00, 0x10, 0x01, 0x0b, // $a
+                0x02, 0x00, 0x0b, // $b
+                0x03, 0x00, 0x00, 0x0b, // $c, pruned
+            ]
+        );
+
+        let pruned = prune_functions(MODULE, &["c"]).unwrap();
+        assert!(pruned_of(&pruned).is_empty());
+    }
+
+    #[test]
+    fn unknown_roots_are_rejected() {
+        assert!(prune_functions(MODULE, &["b"]).is_err());
+    }
+}
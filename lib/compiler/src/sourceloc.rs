@@ -13,11 +13,17 @@ use loupe::MemoryUsage;
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 #[cfg(feature = "enable-serde")]
 use serde::{Deserialize, Serialize};
+use wasmer_types::SignatureIndex;
 
 /// A source location.
 ///
 /// The default source location uses the all-ones bit pattern `!0`. It is used for instructions
 /// that can't be given a real source location.
+///
+/// Source locations with the high bit set are reserved for code that
+/// doesn't come from the WebAssembly module at all: they denote the
+/// function call trampoline of the signature stored in the low bits.
+/// See [`SourceLoc::trampoline`].
 #[cfg_attr(
     feature = "enable-serde",
     derive(Serialize, Deserialize),
@@ -46,8 +52,33 @@ impl SourceLoc {
     pub fn bits(self) -> u32 {
         self.0
     }
+
+    /// Create the source location of the function call trampoline for
+    /// `signature`.
+    pub fn trampoline(signature: SignatureIndex) -> Self {
+        let index = signature.as_u32();
+        assert!(
+            index < !TRAMPOLINE_BIT,
+            "signature index doesn't fit in a trampoline source location"
+        );
+        Self(TRAMPOLINE_BIT | index)
+    }
+
+    /// Decode the signature of a source location created with
+    /// [`SourceLoc::trampoline`], or `None` for any other source
+    /// location.
+    pub fn trampoline_signature(self) -> Option<SignatureIndex> {
+        if self.is_default() || self.0 & TRAMPOLINE_BIT == 0 {
+            None
+        } else {
+            Some(SignatureIndex::from_u32(self.0 & !TRAMPOLINE_BIT))
+        }
+    }
 }
 
+/// The bit marking the source locations of trampolines.
+const TRAMPOLINE_BIT: u32 = 1 << 31;
+
 impl Default for SourceLoc {
     fn default() -> Self {
         Self(!0)
@@ -58,6 +89,8 @@ impl fmt::Display for SourceLoc {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         if self.is_default() {
             write!(f, "0x-")
+        } else if let Some(signature) = self.trampoline_signature() {
+            write!(f, "trampoline({})", signature.as_u32())
         } else {
             write!(f, "0x{:04x}", self.0)
         }
@@ -68,6 +101,7 @@ impl fmt::Display for SourceLoc {
 mod tests {
     use super::SourceLoc;
     use crate::lib::std::string::ToString;
+    use wasmer_types::SignatureIndex;
 
     #[test]
     fn display() {
@@ -75,5 +109,21 @@ mod tests {
         assert_eq!(SourceLoc::new(0).to_string(), "0x0000");
         assert_eq!(SourceLoc::new(16).to_string(), "0x0010");
         assert_eq!(SourceLoc::new(0xabcdef).to_string(), "0xabcdef");
+        assert_eq!(
+            SourceLoc::trampoline(SignatureIndex::from_u32(3)).to_string(),
+            "trampoline(3)"
+        );
+    }
+
+    #[test]
+    fn trampoline() {
+        let loc = SourceLoc::trampoline(SignatureIndex::from_u32(42));
+        assert_eq!(loc.bits(), 0x8000_002a);
+        assert_eq!(
+            loc.trampoline_signature(),
+            Some(SignatureIndex::from_u32(42))
+        );
+        assert_eq!(SourceLoc::new(42).trampoline_signature(), None);
+        assert_eq!(SourceLoc::default().trampoline_signature(), None);
     }
 }
@@ -1,23 +1,26 @@
 // This file contains code from external sources.
 // Attributions: https://github.com/wasmerio/wasmer/blob/master/ATTRIBUTIONS.md
 
+use super::middleware::MiddlewareBinaryReader;
 use super::module::translate_module;
+use super::sections::wptype_to_type;
 use super::state::ModuleTranslationState;
 use crate::lib::std::borrow::ToOwned;
 use crate::lib::std::string::ToString;
 use crate::lib::std::{boxed::Box, string::String, vec::Vec};
+use crate::proposals::{operator_proposal, type_proposal};
 use crate::wasmparser::{Operator, Range, Type};
-use crate::{WasmError, WasmResult};
+use crate::{WasmError, WasmProposal, WasmResult};
 use std::convert::{TryFrom, TryInto};
 use std::sync::Arc;
 use wasmer_types::entity::PrimaryMap;
-use wasmer_types::FunctionType;
 use wasmer_types::{
     CustomSectionIndex, DataIndex, DataInitializer, DataInitializerLocation, ElemIndex,
     ExportIndex, FunctionIndex, GlobalIndex, GlobalInit, GlobalType, ImportIndex,
     LocalFunctionIndex, MemoryIndex, MemoryType, SignatureIndex, TableIndex, TableInitializer,
     TableType,
 };
+use wasmer_types::{FunctionType, Type as WasmType};
 use wasmer_vm::ModuleInfo;
 
 /// Contains function data: bytecode and its offset in the module.
@@ -101,10 +104,53 @@ impl<'data> ModuleEnvironment<'data> {
     pub fn translate(mut self, data: &'data [u8]) -> WasmResult<ModuleInfoTranslation<'data>> {
         assert!(self.result.module_translation_state.is_none());
         let module_translation_state = translate_module(data, &mut self)?;
+        self.declare_used_proposals_of_declarations();
         self.result.module_translation_state = Some(module_translation_state);
         Ok(self.result)
     }
 
+    /// Records that the module uses `proposal`.
+    pub(crate) fn declare_used_proposal(&mut self, proposal: WasmProposal) {
+        *proposal.flag(&mut self.result.module.used_features) = true;
+    }
+
+    /// Records the proposals used by the types, tables, memories and
+    /// globals of the module, once they are all declared.
+    fn declare_used_proposals_of_declarations(&mut self) {
+        let module = &self.result.module;
+        let mut used = Vec::new();
+        for signature in module.signatures.values() {
+            if signature.results().len() > 1 {
+                used.push(WasmProposal::MultiValue);
+            }
+            let types = signature.params().iter().chain(signature.results());
+            used.extend(types.copied().filter_map(type_proposal));
+        }
+        if module.tables.len() > 1
+            || module
+                .tables
+                .values()
+                .any(|table| table.ty == WasmType::ExternRef)
+        {
+            used.push(WasmProposal::ReferenceTypes);
+        }
+        if module.memories.len() > 1 {
+            used.push(WasmProposal::MultiMemory);
+        }
+        if module.memories.values().any(|memory| memory.shared) {
+            used.push(WasmProposal::Threads);
+        }
+        used.extend(
+            module
+                .globals
+                .values()
+                .filter_map(|global| type_proposal(global.ty)),
+        );
+        for proposal in used {
+            self.declare_used_proposal(proposal);
+        }
+    }
+
     pub(crate) fn declare_export(&mut self, export: ExportIndex, name: &str) -> WasmResult<()> {
         self.result
             .module
@@ -402,6 +448,19 @@ impl<'data> ModuleEnvironment<'data> {
         body_bytes: &'data [u8],
         body_offset: usize,
     ) -> WasmResult<()> {
+        let mut reader = MiddlewareBinaryReader::new_with_offset(body_bytes, body_offset);
+        for _ in 0..reader.read_local_count()? {
+            let (_, ty) = reader.read_local_decl()?;
+            if let Some(proposal) = wptype_to_type(ty).ok().and_then(type_proposal) {
+                self.declare_used_proposal(proposal);
+            }
+        }
+        while !reader.eof() {
+            if let Some(proposal) = operator_proposal(&reader.read_operator()?) {
+                self.declare_used_proposal(proposal);
+            }
+        }
+
         self.result.function_body_inputs.push(FunctionBodyData {
             data: body_bytes,
             module_offset: body_offset,
@@ -418,6 +477,7 @@ impl<'data> ModuleEnvironment<'data> {
 
     pub(crate) fn declare_data_initialization(
         &mut self,
+        data_index: DataIndex,
         memory_index: MemoryIndex,
         base: Option<GlobalIndex>,
         offset: usize,
@@ -425,6 +485,7 @@ impl<'data> ModuleEnvironment<'data> {
     ) -> WasmResult<()> {
         self.result.data_initializers.push(DataInitializer {
             location: DataInitializerLocation {
+                data_index,
                 memory_index,
                 base,
                 offset,
@@ -485,6 +546,12 @@ impl<'data> ModuleEnvironment<'data> {
         Ok(())
     }
 
+    /// Provides the offset of the contents of the code section.
+    pub(crate) fn declare_code_section_offset(&mut self, offset: usize) -> WasmResult<()> {
+        self.result.module.code_section_offset = offset;
+        Ok(())
+    }
+
     /// Indicates that a custom section has been found in the wasm file
     pub(crate) fn custom_section(&mut self, name: &'data str, data: &'data [u8]) -> WasmResult<()> {
         let custom_section = CustomSectionIndex::from_u32(
@@ -10,7 +10,7 @@ use super::sections::{
     parse_start_section, parse_table_section, parse_type_section,
 };
 use super::state::ModuleTranslationState;
-use crate::WasmResult;
+use crate::{WasmProposal, WasmResult};
 use wasmparser::{NameSectionReader, Parser, Payload};
 
 /// Translate a sequence of bytes forming a valid Wasm binary into a
@@ -61,7 +61,9 @@ pub fn translate_module<'data>(
                 parse_element_section(elements, environ)?;
             }
 
-            Payload::CodeSectionStart { .. } => {}
+            Payload::CodeSectionStart { range, .. } => {
+                environ.declare_code_section_offset(range.start)?;
+            }
             Payload::CodeSectionEntry(code) => {
                 let mut code = code.get_binary_reader();
                 let size = code.bytes_remaining();
@@ -78,6 +80,7 @@ pub fn translate_module<'data>(
             }
 
             Payload::DataCountSection { count, .. } => {
+                environ.declare_used_proposal(WasmProposal::BulkMemory);
                 environ.reserve_passive_data(count)?;
             }
 
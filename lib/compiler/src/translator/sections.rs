@@ -13,7 +13,7 @@
 use super::environ::ModuleEnvironment;
 use super::state::ModuleTranslationState;
 use crate::wasm_unsupported;
-use crate::{WasmError, WasmResult};
+use crate::{WasmError, WasmProposal, WasmResult};
 use core::convert::TryFrom;
 use std::boxed::Box;
 use std::collections::HashMap;
@@ -368,10 +368,11 @@ pub fn parse_element_section<'data>(
                 )?
             }
             ElementKind::Passive => {
+                environ.declare_used_proposal(WasmProposal::BulkMemory);
                 let index = ElemIndex::from_u32(index as u32);
                 environ.declare_passive_element(index, segments)?;
             }
-            ElementKind::Declared => (),
+            ElementKind::Declared => environ.declare_used_proposal(WasmProposal::ReferenceTypes),
         }
     }
     Ok(())
@@ -405,6 +406,7 @@ pub fn parse_data_section<'data>(
                     }
                 };
                 environ.declare_data_initialization(
+                    DataIndex::from_u32(index as u32),
                     MemoryIndex::from_u32(memory_index),
                     base,
                     offset,
@@ -412,6 +414,7 @@ pub fn parse_data_section<'data>(
                 )?;
             }
             DataKind::Passive => {
+                environ.declare_used_proposal(WasmProposal::BulkMemory);
                 let index = DataIndex::from_u32(index as u32);
                 environ.declare_passive_data(index, data)?;
             }
@@ -40,8 +40,8 @@ use wasmer_types::{
     TableIndex,
 };
 use wasmer_vm::{
-    FuncDataRegistry, FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMFunctionBody,
-    VMSharedSignatureIndex, VMTrampoline,
+    FuncDataRegistry, FunctionBodyPtr, MemoryStyle, ModuleInfo, SignatureRegistration,
+    SignatureRegistry, TableStyle, VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// A compiled Wasm module, ready to be instantiated.
@@ -55,6 +55,7 @@ pub struct DylibArtifact {
     finished_dynamic_function_trampolines: BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     func_data_registry: Arc<FuncDataRegistry>,
     signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+    signature_registration: Arc<SignatureRegistration>,
     frame_info_registration: Mutex<Option<GlobalFrameInfoRegistration>>,
 }
 
@@ -154,15 +155,18 @@ impl DylibArtifact {
         ))
     }
 
-    /// Compile a data buffer into a `DylibArtifact`, which may
-    /// then be instantiated.
+    /// Compile a data buffer into a relocatable object file, without
+    /// linking it into a shared object.
+    ///
+    /// The returned metadata holds the module information and names
+    /// the symbols defined by the object file.
     #[cfg(feature = "compiler")]
-    pub fn new(
+    pub fn generate_object(
         engine: &DylibEngine,
         data: &[u8],
         tunables: &dyn Tunables,
-    ) -> Result<Self, CompileError> {
-        let mut engine_inner = engine.inner_mut();
+    ) -> Result<(ModuleMetadata, Vec<u8>), CompileError> {
+        let engine_inner = engine.inner();
         let target = engine.target();
         let compiler = engine_inner.compiler()?;
         let (compile_info, function_body_inputs, data_initializers, module_translation) =
@@ -220,27 +224,17 @@ impl DylibArtifact {
             &metadata_binary,
         );
 
-        let filepath = match maybe_obj_bytes {
-            Some(obj_bytes) => {
-                let obj_bytes = obj_bytes?;
-                let file = tempfile::Builder::new()
-                    .prefix("wasmer_dylib_")
-                    .suffix(".o")
-                    .tempfile()
-                    .map_err(to_compile_error)?;
-
-                // Re-open it.
-                let (mut file, filepath) = file.keep().map_err(to_compile_error)?;
-                file.write(&obj_bytes).map_err(to_compile_error)?;
-                filepath
-            }
+        let obj_bytes = match maybe_obj_bytes {
+            Some(obj_bytes) => obj_bytes?,
             None => {
-                let compilation = compiler.compile_module(
-                    &target,
-                    &compile_info,
-                    module_translation.as_ref().unwrap(),
-                    function_body_inputs,
-                )?;
+                let compilation = compiler
+                    .compile_module(
+                        &target,
+                        &compile_info,
+                        module_translation.as_ref().unwrap(),
+                        function_body_inputs,
+                    )
+                    .map_err(|error| error.with_function_name(&compile_info.module))?;
                 let mut obj = get_object_for_target(&target_triple).map_err(to_compile_error)?;
                 emit_data(
                     &mut obj,
@@ -251,19 +245,36 @@ impl DylibArtifact {
                 .map_err(to_compile_error)?;
                 emit_compilation(&mut obj, compilation, &symbol_registry, &target_triple)
                     .map_err(to_compile_error)?;
-                let file = tempfile::Builder::new()
-                    .prefix("wasmer_dylib_")
-                    .suffix(".o")
-                    .tempfile()
-                    .map_err(to_compile_error)?;
+                obj.write().map_err(to_compile_error)?
+            }
+        };
 
-                // Re-open it.
-                let (mut file, filepath) = file.keep().map_err(to_compile_error)?;
-                let obj_bytes = obj.write().map_err(to_compile_error)?;
+        Ok((metadata, obj_bytes))
+    }
 
-                file.write(&obj_bytes).map_err(to_compile_error)?;
-                filepath
-            }
+    /// Compile a data buffer into a `DylibArtifact`, which may
+    /// then be instantiated.
+    #[cfg(feature = "compiler")]
+    pub fn new(
+        engine: &DylibEngine,
+        data: &[u8],
+        tunables: &dyn Tunables,
+    ) -> Result<Self, CompileError> {
+        let (metadata, obj_bytes) = Self::generate_object(engine, data, tunables)?;
+        let mut engine_inner = engine.inner_mut();
+        let target_triple = engine.target().triple();
+
+        let filepath = {
+            let file = tempfile::Builder::new()
+                .prefix("wasmer_dylib_")
+                .suffix(".o")
+                .tempfile()
+                .map_err(to_compile_error)?;
+
+            // Re-open it.
+            let (mut file, filepath) = file.keep().map_err(to_compile_error)?;
+            file.write(&obj_bytes).map_err(to_compile_error)?;
+            filepath
         };
 
         let shared_filepath = {
@@ -379,6 +390,10 @@ impl DylibArtifact {
                 .into_boxed_slice(),
             func_data_registry: Arc::new(FuncDataRegistry::new()),
             signatures: signatures.into_boxed_slice(),
+            signature_registration: Arc::new(SignatureRegistration::new(
+                Arc::new(SignatureRegistry::new()),
+                Vec::new(),
+            )),
             frame_info_registration: Mutex::new(None),
         })
     }
@@ -462,15 +477,15 @@ impl DylibArtifact {
         // );
 
         // Compute indices into the shared signature table.
-        let signatures = {
-            metadata
-                .compile_info
-                .module
-                .signatures
-                .values()
-                .map(|sig| engine_inner.signatures().register(sig))
-                .collect::<PrimaryMap<_, _>>()
-        };
+        let signature_registration = Arc::new(SignatureRegistration::register(
+            engine_inner.signatures().clone(),
+            metadata.compile_info.module.signatures.values(),
+        ));
+        let signatures = signature_registration
+            .indices()
+            .iter()
+            .copied()
+            .collect::<PrimaryMap<SignatureIndex, _>>();
 
         engine_inner.add_library(lib);
 
@@ -484,6 +499,7 @@ impl DylibArtifact {
                 .into_boxed_slice(),
             func_data_registry: engine_inner.func_data().clone(),
             signatures: signatures.into_boxed_slice(),
+            signature_registration,
             frame_info_registration: Mutex::new(None),
         })
     }
@@ -761,6 +777,10 @@ impl Artifact for DylibArtifact {
         &self.signatures
     }
 
+    fn signature_registration(&self) -> Option<Arc<SignatureRegistration>> {
+        Some(self.signature_registration.clone())
+    }
+
     fn func_data_registry(&self) -> &FuncDataRegistry {
         &self.func_data_registry
     }
@@ -46,6 +46,19 @@ impl Dylib {
         self
     }
 
+    /// Make every floating-point operation produce the canonical NaN
+    /// instead of an architecture-specific one
+    ///
+    /// This makes the results of float operations deterministic across
+    /// architectures. It has no effect on a headless engine, which
+    /// doesn't compile code.
+    pub fn canonicalize_nans(mut self, enable: bool) -> Self {
+        if let Some(compiler_config) = &mut self.compiler_config {
+            compiler_config.canonicalize_nans(enable);
+        }
+        self
+    }
+
     /// Build the `DylibEngine` for this configuration
     pub fn engine(self) -> DylibEngine {
         if let Some(_compiler_config) = self.compiler_config {
@@ -36,7 +36,7 @@ impl DylibEngine {
         Self {
             inner: Arc::new(Mutex::new(DylibEngineInner {
                 compiler: Some(compiler),
-                signatures: SignatureRegistry::new(),
+                signatures: Arc::new(SignatureRegistry::new()),
                 func_data: Arc::new(FuncDataRegistry::new()),
                 prefixer: None,
                 features,
@@ -69,7 +69,7 @@ impl DylibEngine {
                 compiler: None,
                 #[cfg(feature = "compiler")]
                 features: Features::default(),
-                signatures: SignatureRegistry::new(),
+                signatures: Arc::new(SignatureRegistry::new()),
                 func_data: Arc::new(FuncDataRegistry::new()),
                 prefixer: None,
                 is_cross_compiling: false,
@@ -120,6 +120,12 @@ impl Engine for DylibEngine {
         compiler.signatures().register(func_type)
     }
 
+    /// Register many signatures at once
+    fn register_signatures(&self, func_types: &[FunctionType]) -> Vec<VMSharedSignatureIndex> {
+        let compiler = self.inner();
+        compiler.signatures().register_many(func_types)
+    }
+
     fn register_function_metadata(&self, func_data: VMCallerCheckedAnyfunc) -> VMFuncRef {
         let compiler = self.inner();
         compiler.func_data().register(func_data)
@@ -131,6 +137,10 @@ impl Engine for DylibEngine {
         compiler.signatures().lookup(sig)
     }
 
+    fn signature_count(&self) -> usize {
+        self.inner().signatures().len()
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
         self.inner().validate(binary)
@@ -241,7 +251,7 @@ pub struct DylibEngineInner {
 
     /// The signature registry is used mainly to operate with trampolines
     /// performantly.
-    signatures: SignatureRegistry,
+    signatures: Arc<SignatureRegistry>,
 
     /// The backing storage of `VMFuncRef`s. This centralized store ensures that 2
     /// functions with the same `VMCallerCheckedAnyfunc` will have the same `VMFuncRef`.
@@ -307,7 +317,7 @@ impl DylibEngineInner {
     }
 
     /// Shared signature registry.
-    pub fn signatures(&self) -> &SignatureRegistry {
+    pub fn signatures(&self) -> &Arc<SignatureRegistry> {
         &self.signatures
     }
 
@@ -28,8 +28,8 @@ use wasmer_types::{
     TableIndex,
 };
 use wasmer_vm::{
-    FuncDataRegistry, FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMSharedSignatureIndex,
-    VMTrampoline,
+    FuncDataRegistry, FunctionBodyPtr, MemoryStyle, ModuleInfo, SignatureRegistration, TableStyle,
+    VMSharedSignatureIndex, VMTrampoline,
 };
 
 /// A compiled wasm module, ready to be instantiated.
@@ -42,6 +42,7 @@ pub struct StaticlibArtifact {
     finished_function_call_trampolines: BoxedSlice<SignatureIndex, VMTrampoline>,
     finished_dynamic_function_trampolines: BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+    signature_registration: Arc<SignatureRegistration>,
     func_data_registry: Arc<FuncDataRegistry>,
     /// Length of the serialized metadata
     metadata_length: usize,
@@ -227,12 +228,14 @@ impl StaticlibArtifact {
         let obj_bytes = if let Some(obj_bytes) = maybe_obj_bytes {
             obj_bytes?
         } else {
-            let compilation = compiler.compile_module(
-                &target,
-                &metadata.compile_info,
-                module_translation.as_ref().unwrap(),
-                function_body_inputs,
-            )?;
+            let compilation = compiler
+                .compile_module(
+                    &target,
+                    &metadata.compile_info,
+                    module_translation.as_ref().unwrap(),
+                    function_body_inputs,
+                )
+                .map_err(|error| error.with_function_name(&metadata.compile_info.module))?;
             // there's an ordering issue, but we can update function_body_lengths here.
             /*
             // We construct the function body lengths
@@ -273,14 +276,15 @@ impl StaticlibArtifact {
             PrimaryMap::new();
         let finished_dynamic_function_trampolines: PrimaryMap<FunctionIndex, FunctionBodyPtr> =
             PrimaryMap::new();
-        let signature_registry = engine_inner.signatures();
-        let signatures = metadata
-            .compile_info
-            .module
-            .signatures
-            .values()
-            .map(|sig| signature_registry.register(sig))
-            .collect::<PrimaryMap<_, _>>();
+        let signature_registration = Arc::new(SignatureRegistration::register(
+            engine_inner.signatures().clone(),
+            metadata.compile_info.module.signatures.values(),
+        ));
+        let signatures = signature_registration
+            .indices()
+            .iter()
+            .copied()
+            .collect::<PrimaryMap<SignatureIndex, _>>();
 
         let symbol_registry = metadata.get_symbol_registry();
         Ok(Self {
@@ -292,6 +296,7 @@ impl StaticlibArtifact {
             finished_dynamic_function_trampolines: finished_dynamic_function_trampolines
                 .into_boxed_slice(),
             signatures: signatures.into_boxed_slice(),
+            signature_registration,
             func_data_registry: engine_inner.func_data().clone(),
             metadata_length,
             symbol_registry,
@@ -334,6 +339,7 @@ impl StaticlibArtifact {
         let signature_registry = engine_inner.signatures();
         let func_data_registry = engine_inner.func_data().clone();
         let mut sig_map: BTreeMap<SignatureIndex, VMSharedSignatureIndex> = BTreeMap::new();
+        let mut registered = Vec::new();
 
         let num_imported_functions = metadata.compile_info.module.num_imported_functions;
         // set up the imported functions first...
@@ -341,6 +347,7 @@ impl StaticlibArtifact {
             let sig_idx = metadata.compile_info.module.functions[FunctionIndex::new(i)];
             let func_type = &metadata.compile_info.module.signatures[sig_idx];
             let vm_shared_idx = signature_registry.register(&func_type);
+            registered.push(vm_shared_idx);
             sig_map.insert(sig_idx, vm_shared_idx);
         }
         // read finished functions in order now...
@@ -350,6 +357,7 @@ impl StaticlibArtifact {
             let sig_idx = metadata.compile_info.module.functions[func_idx];
             let func_type = &metadata.compile_info.module.signatures[sig_idx];
             let vm_shared_idx = signature_registry.register(&func_type);
+            registered.push(vm_shared_idx);
             sig_map.insert(sig_idx, vm_shared_idx);
 
             byte_buffer[0..WORD_SIZE]
@@ -412,6 +420,10 @@ impl StaticlibArtifact {
             finished_dynamic_function_trampolines: finished_dynamic_function_trampolines
                 .into_boxed_slice(),
             signatures: signatures.into_boxed_slice(),
+            signature_registration: Arc::new(SignatureRegistration::new(
+                signature_registry.clone(),
+                registered,
+            )),
             func_data_registry,
             metadata_length: 0,
             symbol_registry,
@@ -478,6 +490,10 @@ impl Artifact for StaticlibArtifact {
         &self.signatures
     }
 
+    fn signature_registration(&self) -> Option<Arc<SignatureRegistration>> {
+        Some(self.signature_registration.clone())
+    }
+
     fn func_data_registry(&self) -> &FuncDataRegistry {
         &self.func_data_registry
     }
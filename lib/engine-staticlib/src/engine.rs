@@ -30,7 +30,7 @@ impl StaticlibEngine {
         Self {
             inner: Arc::new(Mutex::new(StaticlibEngineInner {
                 compiler: Some(compiler),
-                signatures: SignatureRegistry::new(),
+                signatures: Arc::new(SignatureRegistry::new()),
                 func_data: Arc::new(FuncDataRegistry::new()),
                 prefixer: None,
                 features,
@@ -60,7 +60,7 @@ impl StaticlibEngine {
                 compiler: None,
                 #[cfg(feature = "compiler")]
                 features: Features::default(),
-                signatures: SignatureRegistry::new(),
+                signatures: Arc::new(SignatureRegistry::new()),
                 func_data: Arc::new(FuncDataRegistry::new()),
                 prefixer: None,
             })),
@@ -109,6 +109,12 @@ impl Engine for StaticlibEngine {
         compiler.signatures().register(func_type)
     }
 
+    /// Register many signatures at once
+    fn register_signatures(&self, func_types: &[FunctionType]) -> Vec<VMSharedSignatureIndex> {
+        let compiler = self.inner();
+        compiler.signatures().register_many(func_types)
+    }
+
     fn register_function_metadata(&self, func_data: VMCallerCheckedAnyfunc) -> VMFuncRef {
         let compiler = self.inner();
         compiler.func_data().register(func_data)
@@ -120,6 +126,10 @@ impl Engine for StaticlibEngine {
         compiler.signatures().lookup(sig)
     }
 
+    fn signature_count(&self) -> usize {
+        self.inner().signatures().len()
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
         self.inner().validate(binary)
@@ -190,7 +200,7 @@ pub struct StaticlibEngineInner {
 
     /// The signature registry is used mainly to operate with trampolines
     /// performantly.
-    signatures: SignatureRegistry,
+    signatures: Arc<SignatureRegistry>,
 
     /// The backing storage of `VMFuncRef`s. This centralized store ensures that 2
     /// functions with the same `VMCallerCheckedAnyfunc` will have the same `VMFuncRef`.
@@ -246,7 +256,7 @@ impl StaticlibEngineInner {
     }
 
     /// Shared signature registry.
-    pub fn signatures(&self) -> &SignatureRegistry {
+    pub fn signatures(&self) -> &Arc<SignatureRegistry> {
         &self.signatures
     }
 
@@ -0,0 +1,86 @@
+//! Analysis of WebAssembly modules without compiling them.
+
+use wasmer_compiler::wasmparser::Operator;
+use wasmer_compiler::{
+    CompileError, FunctionBinaryReader, MiddlewareBinaryReader, ModuleInfoTranslation,
+};
+use wasmer_types::FunctionIndex;
+
+/// Diagnostics about a module, gathered by
+/// [`UniversalEngine::analyze`](crate::UniversalEngine::analyze).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleDiagnostics {
+    /// The diagnostics of the local functions of the module, in order.
+    pub functions: Vec<FunctionDiagnostics>,
+}
+
+/// Diagnostics about a local function of a module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FunctionDiagnostics {
+    /// The index of the function in the module.
+    pub index: FunctionIndex,
+    /// The name of the function, from the name section.
+    pub name: Option<String>,
+    /// The size of the function body, in bytes.
+    pub body_size: usize,
+    /// The number of locals declared by the function, parameters
+    /// excluded.
+    pub num_locals: u32,
+    /// The number of operators in the function body, a rough estimate
+    /// of how much work compiling it takes.
+    pub num_operators: usize,
+    /// The deepest nesting of blocks, loops and ifs in the function
+    /// body.
+    pub max_block_depth: usize,
+}
+
+impl ModuleDiagnostics {
+    pub(crate) fn new(translation: &ModuleInfoTranslation) -> Result<Self, CompileError> {
+        let module = &translation.module;
+        let functions = translation
+            .function_body_inputs
+            .iter()
+            .map(|(local_index, body)| {
+                let index = module.func_index(local_index);
+                let mut reader =
+                    MiddlewareBinaryReader::new_with_offset(body.data, body.module_offset);
+
+                let mut num_locals = 0;
+                for _ in 0..reader.read_local_count()? {
+                    let (count, _) = reader.read_local_decl()?;
+                    num_locals += count;
+                }
+
+                let mut num_operators = 0;
+                let mut depth: usize = 0;
+                let mut max_block_depth: usize = 0;
+                while !reader.eof() {
+                    match reader.read_operator()? {
+                        Operator::Block { .. } | Operator::Loop { .. } | Operator::If { .. } => {
+                            depth += 1;
+                            max_block_depth = max_block_depth.max(depth);
+                        }
+                        Operator::End => depth = depth.saturating_sub(1),
+                        _ => {}
+                    }
+                    num_operators += 1;
+                }
+
+                Ok(FunctionDiagnostics {
+                    index,
+                    name: module.function_names.get(&index).cloned(),
+                    body_size: body.data.len(),
+                    num_locals,
+                    num_operators,
+                    max_block_depth,
+                })
+            })
+            .collect::<Result<_, CompileError>>()?;
+        Ok(Self { functions })
+    }
+
+    /// Returns the number of local functions of the module.
+    pub fn num_functions(&self) -> usize {
+        self.functions.len()
+    }
+}
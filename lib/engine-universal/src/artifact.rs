@@ -2,34 +2,68 @@
 //! done as separate steps.
 
 use crate::engine::{UniversalEngine, UniversalEngineInner};
-use crate::link::link_module;
-#[cfg(feature = "compiler")]
-use crate::serialize::SerializableCompilation;
-use crate::serialize::SerializableModule;
+use crate::link::{link_module_through_alias, verify_relocations};
+use crate::serialize::{SerializableCompilation, SerializableModule, OMITTED_TRAMPOLINE};
+use enumset::EnumSet;
 use loupe::MemoryUsage;
+use std::collections::BTreeMap;
+#[cfg(feature = "compiler")]
+use std::collections::BTreeSet;
+use std::slice;
 use std::sync::{Arc, Mutex};
-use wasmer_compiler::{CompileError, Features, Triple};
+use std::time::{Duration, Instant};
+use wasmer_compiler::{
+    CompileError, CpuFeature, Features, FunctionAddressMap, JumpTableOffsets, TrapInformation,
+    Triple,
+};
 #[cfg(feature = "compiler")]
-use wasmer_compiler::{CompileModuleInfo, ModuleEnvironment, ModuleMiddlewareChain};
+use wasmer_compiler::{
+    CompileModuleInfo, CompileProgress, ModuleEnvironment, ModuleMiddlewareChain,
+};
 use wasmer_engine::{
-    register_frame_info, Artifact, DeserializeError, FunctionExtent, GlobalFrameInfoRegistration,
-    SerializeError,
+    register_frame_info, register_frame_info_without_address_maps, register_trap_info, Artifact,
+    DeserializeError, FunctionExtent, GlobalFrameInfoRegistration, ModuleFootprint, SerializeError,
+    VerifyError,
 };
 #[cfg(feature = "compiler")]
 use wasmer_engine::{Engine, Tunables};
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
+#[cfg(feature = "compiler")]
+use wasmer_types::ExportIndex;
 use wasmer_types::{
     FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer, SignatureIndex,
     TableIndex,
 };
 use wasmer_vm::{
-    FuncDataRegistry, FunctionBodyPtr, MemoryStyle, ModuleInfo, TableStyle, VMSharedSignatureIndex,
-    VMTrampoline,
+    FuncDataRegistry, FunctionBodyPtr, MemoryStyle, ModuleInfo, SignatureRegistration, TableStyle,
+    VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
 };
 
 const SERIALIZED_METADATA_LENGTH_OFFSET: usize = 22;
 const SERIALIZED_METADATA_CONTENT_OFFSET: usize = 32;
 
+/// The tag of the extension marking an artifact whose functions were
+/// compiled without address maps. It has no payload.
+const NO_ADDRESS_MAPS_EXTENSION: u64 = 1;
+
+/// The tag of the extensions holding the metadata of the embedder, one
+/// per key. The payload is the LEB128 length of the key, the key, and
+/// the value.
+const METADATA_EXTENSION: u64 = 2;
+
+/// The tag of the extension holding the blake3 hash of the wasm binary
+/// the artifact was compiled from.
+const SOURCE_HASH_EXTENSION: u64 = 3;
+
+/// The tag of the extension holding the CPU features the artifact was
+/// compiled for. The payload is the LEB128 bits of the feature set.
+const CPU_FEATURES_EXTENSION: u64 = 4;
+
+/// The tag of the extension holding the blake3 hash of the serialized
+/// artifact, from the metadata length to the start of this extension,
+/// which is always the last one.
+const CHECKSUM_EXTENSION: u64 = 5;
+
 /// A compiled wasm module, ready to be instantiated.
 #[derive(MemoryUsage)]
 pub struct UniversalArtifact {
@@ -39,13 +73,119 @@ pub struct UniversalArtifact {
     finished_function_call_trampolines: BoxedSlice<SignatureIndex, VMTrampoline>,
     finished_dynamic_function_trampolines: BoxedSlice<FunctionIndex, FunctionBodyPtr>,
     signatures: BoxedSlice<SignatureIndex, VMSharedSignatureIndex>,
+    signature_registration: Arc<SignatureRegistration>,
     func_data_registry: Arc<FuncDataRegistry>,
     frame_info_registration: Mutex<Option<GlobalFrameInfoRegistration>>,
     finished_function_lengths: BoxedSlice<LocalFunctionIndex, usize>,
+    /// The address and length of the executable code: the functions,
+    /// the trampolines and the executable custom sections.
+    #[loupe(skip)]
+    executable_code: (usize, usize),
+    /// Whether the frame infos have address maps to symbolicate traps.
+    address_maps: bool,
+    /// Whether the source lines of the module are read when its frame
+    /// information is registered.
+    source_lines: bool,
+    #[loupe(skip)]
+    stats: Option<CompilationStats>,
+    /// The metadata attached by the embedder.
+    #[loupe(skip)]
+    metadata: Mutex<BTreeMap<String, Vec<u8>>>,
+    /// The hash of the wasm binary, if it was recorded.
+    source_hash: Option<[u8; 32]>,
+    /// The CPU features the code was compiled for, if they were recorded.
+    #[loupe(skip)]
+    cpu_features: Option<EnumSet<CpuFeature>>,
+    /// Whether the serialized artifact carries its own checksum.
+    checksum: bool,
+}
+
+/// Metrics about the compilation of a [`UniversalArtifact`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CompilationStats {
+    /// The time it took to translate, compile and link the module.
+    pub wall_time: Duration,
+    /// The size of the machine code of the module's local functions.
+    pub function_code_bytes: usize,
+    /// The size of the machine code of the function call and dynamic
+    /// function trampolines.
+    pub trampoline_bytes: usize,
+    /// The size of the custom sections, such as unwind information or
+    /// constants used by the functions.
+    pub custom_section_bytes: usize,
+    /// The number of local functions that were compiled.
+    pub function_count: usize,
+    /// How `wall_time` splits between the phases of the compilation.
+    pub phases: PhaseTimings,
+}
+
+/// The time spent in each phase of the compilation of a
+/// [`UniversalArtifact`], see [`UniversalArtifact::phase_timings`].
+///
+/// The phases are consecutive, so their sum is the
+/// [`wall_time`](CompilationStats::wall_time) of the compilation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PhaseTimings {
+    /// Validating and translating the wasm binary, and preparing the
+    /// module for the compiler.
+    pub translate: Duration,
+    /// Generating the machine code of the functions, and of the
+    /// trampolines for the compilers that emit them alongside.
+    pub codegen: Duration,
+    /// Selecting the trampolines shared by identical signatures and
+    /// gathering the compiled code for the artifact.
+    pub trampolines: Duration,
+    /// Allocating the code in executable memory, applying the
+    /// relocations and registering the signatures.
+    pub link: Duration,
+    /// Making the code executable and registering its unwind
+    /// information.
+    pub publish: Duration,
+}
+
+impl PhaseTimings {
+    /// Returns the total time spent in the phases.
+    pub fn total(&self) -> Duration {
+        self.translate + self.codegen + self.trampolines + self.link + self.publish
+    }
+}
+
+#[cfg(feature = "compiler")]
+impl CompilationStats {
+    fn new(compilation: &SerializableCompilation, phases: PhaseTimings) -> Self {
+        let function_code_bytes = compilation
+            .function_bodies
+            .values()
+            .map(|body| body.body.len())
+            .sum();
+        let trampoline_bytes = compilation
+            .function_call_trampolines
+            .iter()
+            .chain(compilation.dynamic_function_trampolines.values())
+            .map(|body| body.body.len())
+            .sum();
+        let custom_section_bytes = compilation
+            .custom_sections
+            .values()
+            .map(|section| section.bytes.len())
+            .sum();
+        Self {
+            wall_time: phases.total(),
+            function_code_bytes,
+            trampoline_bytes,
+            custom_section_bytes,
+            function_count: compilation.function_bodies.len(),
+            phases,
+        }
+    }
 }
 
 impl UniversalArtifact {
-    const MAGIC_HEADER: &'static [u8; 22] = b"\0wasmer-universal\0\0\0\0\0";
+    /// The last byte of the magic header is the version of the layout of
+    /// the serialized `SerializableModule`. It is bumped whenever that
+    /// layout changes, so that artifacts serialized with another layout
+    /// are rejected instead of being misread.
+    const MAGIC_HEADER: &'static [u8; 22] = b"\0wasmer-universal\0\0\0\0\x03";
 
     /// Check if the provided bytes look like a serialized `UniversalArtifact`.
     pub fn is_deserializable(bytes: &[u8]) -> bool {
@@ -59,11 +199,19 @@ impl UniversalArtifact {
         data: &[u8],
         tunables: &dyn Tunables,
     ) -> Result<Self, CompileError> {
+        let start = Instant::now();
         let environ = ModuleEnvironment::new();
         let mut inner_engine = engine.inner_mut();
         let features = inner_engine.features();
 
         let translation = environ.translate(data).map_err(CompileError::Wasm)?;
+        inner_engine.compilation_limits().check(
+            &translation.module,
+            translation
+                .function_body_inputs
+                .values()
+                .map(|body| body.data.len()),
+        )?;
 
         let compiler = inner_engine.compiler()?;
 
@@ -89,18 +237,82 @@ impl UniversalArtifact {
             memory_styles,
             table_styles,
         };
+        #[cfg(feature = "source-hash")]
+        let source_hash = if inner_engine.source_hash() {
+            Some(*blake3::hash(data).as_bytes())
+        } else {
+            None
+        };
+        #[cfg(not(feature = "source-hash"))]
+        let source_hash = None;
+        let translated = Instant::now();
+
+        let progress_callback = inner_engine.compile_progress();
+        let mut progress = match &progress_callback {
+            Some(callback) => {
+                CompileProgress::new(translation.function_body_inputs.len(), &**callback)
+            }
+            None => CompileProgress::ignore(),
+        };
+        if let Some(timeout) = inner_engine.compile_deadline() {
+            progress = progress.with_deadline(start, timeout);
+        }
+        // Nothing is allocated before the code is published, so giving up
+        // leaves nothing behind.
+        progress.check_deadline()?;
 
         // Compile the Module
-        let compilation = compiler.compile_module(
-            &engine.target(),
-            &compile_info,
-            // SAFETY: Calling `unwrap` is correct since
-            // `environ.translate()` above will write some data into
-            // `module_translation_state`.
-            translation.module_translation_state.as_ref().unwrap(),
-            translation.function_body_inputs,
-        )?;
-        let function_call_trampolines = compilation.get_function_call_trampolines();
+        let compilation = compiler
+            .compile_module_with_progress(
+                &engine.target(),
+                &compile_info,
+                // SAFETY: Calling `unwrap` is correct since
+                // `environ.translate()` above will write some data into
+                // `module_translation_state`.
+                translation.module_translation_state.as_ref().unwrap(),
+                translation.function_body_inputs,
+                &progress,
+            )
+            .map_err(|error| error.with_function_name(&compile_info.module))?;
+        let compiled = Instant::now();
+        progress.check_deadline()?;
+        let (distinct_signatures, signature_groups) = compile_info.module.distinct_signatures();
+        let signature_trampolines = compilation.get_function_call_trampolines();
+        // Only the exported functions can be called from the host.
+        let kept_groups = if inner_engine.exported_trampolines_only() {
+            let module = &compile_info.module;
+            Some(
+                module
+                    .exports
+                    .values()
+                    .filter_map(|export| match export {
+                        ExportIndex::Function(index) => {
+                            Some(signature_groups[module.functions[*index]])
+                        }
+                        _ => None,
+                    })
+                    .collect::<BTreeSet<_>>(),
+            )
+        } else {
+            None
+        };
+        let is_kept = |group: usize| {
+            kept_groups
+                .as_ref()
+                .map_or(true, |kept| kept.contains(&group))
+        };
+        let mut kept_indices = vec![OMITTED_TRAMPOLINE; distinct_signatures.len()];
+        let mut function_call_trampolines = Vec::new();
+        for (group, sig_index) in distinct_signatures.into_iter().enumerate() {
+            if is_kept(group) {
+                kept_indices[group] = function_call_trampolines.len() as u32;
+                function_call_trampolines.push(signature_trampolines[sig_index].clone());
+            }
+        }
+        let function_call_trampoline_indices = signature_groups
+            .values()
+            .map(|group| kept_indices[*group])
+            .collect();
         let dynamic_function_trampolines = compilation.get_dynamic_function_trampolines();
 
         let data_initializers = translation
@@ -110,7 +322,13 @@ impl UniversalArtifact {
             .collect::<Vec<_>>()
             .into_boxed_slice();
 
-        let frame_infos = compilation.get_frame_info();
+        let mut frame_infos = compilation.get_frame_info();
+        let address_maps = inner_engine.debug_info();
+        if !address_maps {
+            for frame_info in frame_infos.values_mut() {
+                frame_info.address_map = Default::default();
+            }
+        }
 
         let serializable_compilation = SerializableCompilation {
             function_bodies: compilation.get_function_bodies(),
@@ -118,6 +336,7 @@ impl UniversalArtifact {
             function_jt_offsets: compilation.get_jt_offsets(),
             function_frame_info: frame_infos,
             function_call_trampolines,
+            function_call_trampoline_indices,
             dynamic_function_trampolines,
             custom_sections: compilation.get_custom_sections(),
             custom_section_relocations: compilation.get_custom_section_relocations(),
@@ -128,7 +347,25 @@ impl UniversalArtifact {
             compile_info,
             data_initializers,
         };
-        Self::from_parts(&mut inner_engine, serializable)
+        let gathered = Instant::now();
+        let (mut artifact, linked) = Self::from_parts_timed(&mut inner_engine, serializable)?;
+        let published = Instant::now();
+        artifact.address_maps = address_maps;
+        artifact.source_hash = source_hash;
+        artifact.cpu_features = Some(*engine.target().cpu_features());
+        artifact.checksum = inner_engine.artifact_checksum();
+        let phases = PhaseTimings {
+            translate: translated - start,
+            codegen: compiled - translated,
+            trampolines: gathered - compiled,
+            link: linked - gathered,
+            publish: published - linked,
+        };
+        artifact.stats = Some(CompilationStats::new(
+            &artifact.serializable.compilation,
+            phases,
+        ));
+        Ok(artifact)
     }
 
     /// Compile a data buffer into a `UniversalArtifact`, which may then be instantiated.
@@ -148,7 +385,71 @@ impl UniversalArtifact {
         universal: &UniversalEngine,
         bytes: &[u8],
     ) -> Result<Self, DeserializeError> {
+        let checksum_required = universal.inner().artifact_checksum();
+        let (serializable, extensions) = Self::deserialize_serializable(bytes, checksum_required)?;
+        check_cpu_features(&extensions)?;
+        let mut artifact = Self::from_parts(&mut universal.inner_mut(), serializable)
+            .map_err(DeserializeError::Compiler)?;
+        artifact.apply_extensions(&extensions)?;
+        Ok(artifact)
+    }
+
+    /// Deserialize a UniversalArtifact, verifying that every relocation
+    /// stays within the function body or custom section it patches, and
+    /// points to an existing target, before linking it.
+    ///
+    /// Relocations are written directly into executable memory, so this
+    /// should be preferred when loading artifacts from a cache that is
+    /// not fully trusted. Invalid relocations are reported as
+    /// [`DeserializeError::CorruptedBinary`].
+    ///
+    /// # Safety
+    /// This function is unsafe because rkyv reads directly without validating
+    /// the data. Only the relocations are checked.
+    pub unsafe fn deserialize_checked(
+        universal: &UniversalEngine,
+        bytes: &[u8],
+    ) -> Result<Self, DeserializeError> {
+        let checksum_required = universal.inner().artifact_checksum();
+        let (serializable, extensions) = Self::deserialize_serializable(bytes, checksum_required)?;
+        check_cpu_features(&extensions)?;
+        let compilation = &serializable.compilation;
+        verify_relocations(
+            &compilation.function_bodies,
+            &compilation.function_jt_offsets,
+            &compilation.function_relocations,
+            &compilation.custom_sections,
+            &compilation.custom_section_relocations,
+        )
+        .map_err(DeserializeError::CorruptedBinary)?;
+        let mut artifact = Self::from_parts(&mut universal.inner_mut(), serializable)
+            .map_err(DeserializeError::Compiler)?;
+        artifact.apply_extensions(&extensions)?;
+        Ok(artifact)
+    }
+
+    /// Read the `SerializableModule` out of a serialized `UniversalArtifact`,
+    /// along with its extensions.
+    ///
+    /// The artifact must carry a checksum if `checksum_required`.
+    ///
+    /// # Safety
+    /// See [`UniversalArtifact::deserialize`].
+    #[allow(clippy::type_complexity)]
+    unsafe fn deserialize_serializable(
+        bytes: &[u8],
+        checksum_required: bool,
+    ) -> Result<(SerializableModule, Vec<(u64, &[u8])>), DeserializeError> {
         if !Self::is_deserializable(bytes) {
+            let version_offset = Self::MAGIC_HEADER.len() - 1;
+            if bytes.len() > version_offset
+                && bytes.starts_with(&Self::MAGIC_HEADER[..version_offset])
+            {
+                return Err(DeserializeError::Incompatible(format!(
+                    "The artifact was serialized with format version {}, but version {} is expected",
+                    bytes[version_offset], Self::MAGIC_HEADER[version_offset]
+                )));
+            }
             return Err(DeserializeError::Incompatible(
                 "The provided bytes are not wasmer-universal".to_string(),
             ));
@@ -159,14 +460,60 @@ impl UniversalArtifact {
         let metadata_len = leb128::read::unsigned(&mut inner_bytes).map_err(|_e| {
             DeserializeError::CorruptedBinary("Can't read metadata size".to_string())
         })?;
+        let available = bytes
+            .len()
+            .saturating_sub(SERIALIZED_METADATA_CONTENT_OFFSET);
+        if metadata_len > available as u64 {
+            return Err(DeserializeError::CorruptedBinary(
+                "Metadata size is larger than the provided bytes".to_string(),
+            ));
+        }
         let metadata_slice: &[u8] = std::slice::from_raw_parts(
             &bytes[SERIALIZED_METADATA_CONTENT_OFFSET] as *const u8,
             metadata_len as usize,
         );
 
+        let extensions =
+            read_extensions(&bytes[SERIALIZED_METADATA_CONTENT_OFFSET + metadata_len as usize..])?;
+        verify_checksum(bytes, &extensions, checksum_required)?;
+
         let serializable = SerializableModule::deserialize(metadata_slice)?;
-        Self::from_parts(&mut universal.inner_mut(), serializable)
-            .map_err(DeserializeError::Compiler)
+        check_trampoline_indices(&serializable)?;
+        Ok((serializable, extensions))
+    }
+
+    /// Restores what the extensions of a serialized artifact describe.
+    fn apply_extensions(&mut self, extensions: &[(u64, &[u8])]) -> Result<(), DeserializeError> {
+        for &(tag, mut payload) in extensions {
+            match tag {
+                NO_ADDRESS_MAPS_EXTENSION => self.address_maps = false,
+                METADATA_EXTENSION => {
+                    let corrupted =
+                        || DeserializeError::CorruptedBinary("Can't read the metadata".to_string());
+                    let key_len = leb128::read::unsigned(&mut payload).map_err(|_| corrupted())?;
+                    if key_len > payload.len() as u64 {
+                        return Err(corrupted());
+                    }
+                    let (key, value) = payload.split_at(key_len as usize);
+                    let key = String::from_utf8(key.to_vec()).map_err(|_| corrupted())?;
+                    self.metadata.get_mut().unwrap().insert(key, value.to_vec());
+                }
+                SOURCE_HASH_EXTENSION => {
+                    let mut hash = [0; 32];
+                    if payload.len() != hash.len() {
+                        return Err(DeserializeError::CorruptedBinary(
+                            "Can't read the source hash".to_string(),
+                        ));
+                    }
+                    hash.copy_from_slice(payload);
+                    self.source_hash = Some(hash);
+                }
+                CPU_FEATURES_EXTENSION => self.cpu_features = Some(read_cpu_features(payload)?),
+                CHECKSUM_EXTENSION => self.checksum = true,
+                _ => {}
+            }
+        }
+        Ok(())
     }
 
     /// Construct a `UniversalArtifact` from component parts.
@@ -174,6 +521,15 @@ impl UniversalArtifact {
         inner_engine: &mut UniversalEngineInner,
         serializable: SerializableModule,
     ) -> Result<Self, CompileError> {
+        Self::from_parts_timed(inner_engine, serializable).map(|(artifact, _)| artifact)
+    }
+
+    /// Like [`UniversalArtifact::from_parts`], but also returns when the
+    /// code was linked and started to be published.
+    fn from_parts_timed(
+        inner_engine: &mut UniversalEngineInner,
+        serializable: SerializableModule,
+    ) -> Result<(Self, Instant), CompileError> {
         let (
             finished_functions,
             finished_function_call_trampolines,
@@ -183,31 +539,36 @@ impl UniversalArtifact {
             &serializable.compile_info.module,
             &serializable.compilation.function_bodies,
             &serializable.compilation.function_call_trampolines,
+            &serializable.compilation.function_call_trampoline_indices,
             &serializable.compilation.dynamic_function_trampolines,
             &serializable.compilation.custom_sections,
         )?;
 
-        link_module(
+        link_module_through_alias(
             &serializable.compile_info.module,
             &finished_functions,
             &serializable.compilation.function_jt_offsets,
-            serializable.compilation.function_relocations.clone(),
+            &serializable.compilation.function_relocations,
             &custom_sections,
             &serializable.compilation.custom_section_relocations,
-        );
+            inner_engine.code_write_offset(),
+            inner_engine.libcalls(),
+            inner_engine.relocation_rewriter(),
+        )
+        .map_err(|error| CompileError::Codegen(error.to_string()))?;
 
         // Compute indices into the shared signature table.
-        let signatures = {
-            let signature_registry = inner_engine.signatures();
-            serializable
-                .compile_info
-                .module
-                .signatures
-                .values()
-                .map(|sig| signature_registry.register(sig))
-                .collect::<PrimaryMap<_, _>>()
-        };
+        let signature_registration = Arc::new(SignatureRegistration::register(
+            inner_engine.signatures().clone(),
+            serializable.compile_info.module.signatures.values(),
+        ));
+        let signatures = signature_registration
+            .indices()
+            .iter()
+            .copied()
+            .collect::<PrimaryMap<SignatureIndex, _>>();
 
+        let linked = Instant::now();
         let eh_frame = match &serializable.compilation.debug {
             Some(debug) => {
                 let eh_frame_section_size = serializable.compilation.custom_sections
@@ -221,6 +582,7 @@ impl UniversalArtifact {
             }
             None => None,
         };
+        let executable_code = inner_engine.executable_code();
         // Make all code compiled thus far executable.
         inner_engine.publish_compiled_code();
 
@@ -243,16 +605,342 @@ impl UniversalArtifact {
         let signatures = signatures.into_boxed_slice();
         let func_data_registry = inner_engine.func_data().clone();
 
-        Ok(Self {
+        let artifact = Self {
             serializable,
             finished_functions,
             finished_function_call_trampolines,
             finished_dynamic_function_trampolines,
             signatures,
+            signature_registration,
             frame_info_registration: Mutex::new(None),
+            address_maps: true,
+            source_lines: inner_engine.source_lines(),
             finished_function_lengths,
+            executable_code,
             func_data_registry,
-        })
+            stats: None,
+            metadata: Mutex::new(BTreeMap::new()),
+            source_hash: None,
+            cpu_features: None,
+            checksum: false,
+        };
+        Ok((artifact, linked))
+    }
+
+    /// Verifies that the artifact is self-consistent, independently of
+    /// any import: the relocations stay within the bodies they patch,
+    /// every signature referenced by a function exists, and there is
+    /// exactly one compiled function, trampoline and signature for
+    /// each function and signature of the module.
+    ///
+    /// This is much cheaper than instantiating the artifact, which
+    /// makes it suitable to scan a cache of artifacts for corrupted
+    /// entries.
+    pub fn verify(&self) -> Result<(), VerifyError> {
+        let module = &self.serializable.compile_info.module;
+        let compilation = &self.serializable.compilation;
+
+        for (index, signature) in module.functions.iter() {
+            if module.signatures.get(*signature).is_none() {
+                return Err(VerifyError::UnknownSignature(index, *signature));
+            }
+        }
+
+        let check = |map: &'static str, expected: usize, found: usize| {
+            if expected == found {
+                Ok(())
+            } else {
+                Err(VerifyError::MapMismatch {
+                    map,
+                    expected,
+                    found,
+                })
+            }
+        };
+        let imported_functions = module.num_imported_functions;
+        let local_functions = module
+            .functions
+            .len()
+            .checked_sub(imported_functions)
+            .ok_or(VerifyError::MapMismatch {
+                map: "functions, including the imported ones,",
+                expected: imported_functions,
+                found: module.functions.len(),
+            })?;
+        check(
+            "function bodies",
+            local_functions,
+            compilation.function_bodies.len(),
+        )?;
+        check(
+            "function relocation lists",
+            local_functions,
+            compilation.function_relocations.len(),
+        )?;
+        check(
+            "jump table offsets",
+            local_functions,
+            compilation.function_jt_offsets.len(),
+        )?;
+        check(
+            "function frame infos",
+            local_functions,
+            compilation.function_frame_info.len(),
+        )?;
+        check(
+            "finished functions",
+            local_functions,
+            self.finished_functions.len(),
+        )?;
+        check(
+            "function call trampoline indices",
+            module.signatures.len(),
+            compilation.function_call_trampoline_indices.len(),
+        )?;
+        check(
+            "finished function call trampolines",
+            module.signatures.len(),
+            self.finished_function_call_trampolines.len(),
+        )?;
+        check(
+            "dynamic function trampolines",
+            imported_functions,
+            compilation.dynamic_function_trampolines.len(),
+        )?;
+        check(
+            "finished dynamic function trampolines",
+            imported_functions,
+            self.finished_dynamic_function_trampolines.len(),
+        )?;
+        check(
+            "shared signatures",
+            module.signatures.len(),
+            self.signatures.len(),
+        )?;
+
+        for (signature, trampoline) in compilation.function_call_trampoline_indices.iter() {
+            if *trampoline != OMITTED_TRAMPOLINE
+                && *trampoline as usize >= compilation.function_call_trampolines.len()
+            {
+                return Err(VerifyError::UnknownTrampoline(signature, *trampoline));
+            }
+        }
+
+        verify_relocations(
+            &compilation.function_bodies,
+            &compilation.function_jt_offsets,
+            &compilation.function_relocations,
+            &compilation.custom_sections,
+            &compilation.custom_section_relocations,
+        )
+        .map_err(VerifyError::Relocation)
+    }
+
+    /// Returns metrics about the compilation of this artifact.
+    ///
+    /// This is `None` for artifacts that were not compiled by
+    /// [`UniversalArtifact::new`], such as deserialized ones.
+    pub fn stats(&self) -> Option<&CompilationStats> {
+        self.stats.as_ref()
+    }
+
+    /// Returns the time spent in each phase of the compilation of this
+    /// artifact.
+    ///
+    /// Like [`UniversalArtifact::stats`], this is `None` for artifacts
+    /// that were not compiled by [`UniversalArtifact::new`].
+    pub fn phase_timings(&self) -> Option<&PhaseTimings> {
+        self.stats.as_ref().map(|stats| &stats.phases)
+    }
+
+    /// Returns the CPU features the code of the artifact was compiled
+    /// for.
+    ///
+    /// Deserializing the artifact on a host lacking any of them fails
+    /// with [`DeserializeError::IncompatibleCpuFeatures`]. This is
+    /// `None` for artifacts serialized before the features were
+    /// recorded, which are not checked.
+    pub fn cpu_features(&self) -> Option<EnumSet<CpuFeature>> {
+        self.cpu_features
+    }
+
+    /// Returns the machine code of the local function `index`, as it
+    /// is published in memory.
+    pub fn function_body_bytes(&self, index: LocalFunctionIndex) -> Option<&[u8]> {
+        let ptr = self.finished_functions.get(index)?;
+        let len = self.finished_function_lengths[index];
+        // Safety: the code stays mapped and readable for as long as the
+        // engine that published it, which outlives the artifact.
+        Some(unsafe { slice::from_raw_parts(**ptr as *const u8, len) })
+    }
+
+    /// Returns the trap sites of the local function `index`: the
+    /// offsets in its machine code of the instructions that may trap,
+    /// with the trap each one raises, in ascending order of offset.
+    ///
+    /// The trap sites are serialized with the artifact. Some compilers
+    /// don't use trap sites for every trap, for example LLVM raises
+    /// its traps by calling into the runtime, so the list may be
+    /// incomplete or empty.
+    pub fn function_traps(&self, index: LocalFunctionIndex) -> Option<&[TrapInformation]> {
+        self.serializable
+            .compilation
+            .function_frame_info
+            .get(index)
+            .map(|frame_info| &*frame_info.traps)
+    }
+
+    /// Returns the offsets of the jump tables of the local function
+    /// `index` in its machine code, which the relocations targeting
+    /// them are resolved with when the artifact is linked.
+    ///
+    /// The offsets are serialized with the artifact. They are empty for
+    /// a function without jump tables, and for compilers that emit
+    /// their jump tables inline, next to the branch using them, without
+    /// relocations: this is the case of every compiler but the legacy
+    /// x86 backend of Cranelift.
+    pub fn jump_table_offsets(&self, index: LocalFunctionIndex) -> Option<&JumpTableOffsets> {
+        self.serializable.compilation.function_jt_offsets.get(index)
+    }
+
+    /// Overwrites the machine code of the local function `index` with
+    /// `new_body`, in place, through the code memory of `engine`, which
+    /// must be the engine the artifact was created with.
+    ///
+    /// The code is never mapped writable and executable at once: when
+    /// the engine uses dual mapped code, it is written through a
+    /// temporary writable alias, otherwise its pages are made writable,
+    /// through the [`CodeAllocator`](crate::CodeAllocator) if there is
+    /// one, and executable again once written.
+    ///
+    /// `new_body` must fit in the bytes of the current body, which are
+    /// returned by [`UniversalArtifact::function_body_bytes`]. The
+    /// remaining bytes, if any, are left as they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no such function, if `new_body`
+    /// doesn't fit, if the code wasn't allocated by `engine`, or if it
+    /// can't be made writable.
+    ///
+    /// # Safety
+    ///
+    /// - No thread may be executing the function, or calling it, while
+    ///   it is being patched.
+    /// - Unless the code is dual mapped, its pages aren't executable
+    ///   while it is being patched. They are shared with the
+    ///   neighbouring functions, trampolines and executable custom
+    ///   sections of the artifact, so no thread may be executing any
+    ///   of them either.
+    /// - `new_body` must be position-independent machine code for the
+    ///   target of the artifact, following the calling convention and
+    ///   the signature of the function. Relocations aren't applied to
+    ///   it, and the trap and unwind information of the function still
+    ///   describe the old body.
+    ///
+    /// All the instances of the artifact run the patched code. With the
+    /// `code-integrity` feature, the function is reported as corrupted
+    /// by the engine from then on.
+    pub unsafe fn patch_function(
+        &self,
+        engine: &UniversalEngine,
+        index: LocalFunctionIndex,
+        new_body: &[u8],
+    ) -> Result<(), String> {
+        let current = self
+            .function_body_bytes(index)
+            .ok_or_else(|| format!("no local function with index {}", index.as_u32()))?;
+        if new_body.len() > current.len() {
+            return Err(format!(
+                "the new body of {} bytes doesn't fit in the {} bytes of the function",
+                new_body.len(),
+                current.len()
+            ));
+        }
+        engine
+            .inner_mut()
+            .patch_code(current.as_ptr() as *mut u8, new_body)
+    }
+
+    /// Serializes the contents of the artifact as JSON, to inspect them
+    /// without the runtime.
+    ///
+    /// This format is meant for diagnostics only: it can't be
+    /// deserialized. The module information is spelled out, while the
+    /// machine code of the functions is left opaque, in hexadecimal.
+    #[cfg(feature = "debug-format")]
+    pub fn serialize_debug(&self) -> Result<String, SerializeError> {
+        use serde_json::json;
+
+        fn hex(bytes: &[u8]) -> String {
+            bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+        }
+        fn to_strings<T: ToString>(values: impl Iterator<Item = T>) -> Vec<String> {
+            values.map(|value| value.to_string()).collect()
+        }
+
+        let module = &self.serializable.compile_info.module;
+        let compilation = &self.serializable.compilation;
+        let functions = compilation
+            .function_bodies
+            .iter()
+            .map(|(index, body)| {
+                let function_index = module.func_index(index);
+                json!({
+                    "index": function_index.as_u32(),
+                    "name": module.function_names.get(&function_index),
+                    "signature": module.signatures[module.functions[function_index]].to_string(),
+                    "body_size": body.body.len(),
+                    "relocations": compilation.function_relocations[index].len(),
+                    "body": hex(&body.body),
+                })
+            })
+            .collect::<Vec<_>>();
+        let custom_sections = compilation
+            .custom_sections
+            .values()
+            .map(|section| {
+                json!({
+                    "protection": format!("{:?}", section.protection),
+                    "size": section.bytes.len(),
+                    "relocations": section.relocations.len(),
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let imports = module
+            .imports
+            .keys()
+            .map(|(module, field, _)| format!("{}.{}", module, field))
+            .collect::<Vec<_>>();
+        let exports = module
+            .exports
+            .iter()
+            .map(|(name, index)| (name.clone(), format!("{:?}", index)))
+            .collect::<BTreeMap<_, _>>();
+
+        let debug = json!({
+            "name": module.name,
+            "features": format!("{:?}", self.serializable.compile_info.features),
+            "cpu_features": self.cpu_features.map(|features| to_strings(features.iter())),
+            "source_hash": self.source_hash.as_ref().map(|hash| hex(&hash[..])),
+            "address_maps": self.address_maps,
+            "checksum": self.checksum,
+            "imports": imports,
+            "exports": exports,
+            "signatures": to_strings(module.signatures.values()),
+            "memories": to_strings(module.memories.values()),
+            "tables": to_strings(module.tables.values()),
+            "globals": to_strings(module.globals.values()),
+            "function_count": functions.len(),
+            "functions": functions,
+            "function_call_trampolines": compilation.function_call_trampolines.len(),
+            "dynamic_function_trampolines": compilation.dynamic_function_trampolines.len(),
+            "custom_sections": custom_sections,
+            "data_initializers": self.serializable.data_initializers.len(),
+            "metadata": self.metadata.lock().unwrap().keys().cloned().collect::<Vec<_>>(),
+        });
+        serde_json::to_string_pretty(&debug).map_err(|e| SerializeError::Generic(e.to_string()))
     }
 
     /// Get the default extension when serializing this artifact
@@ -261,6 +949,47 @@ impl UniversalArtifact {
         // stands for “Wasm Universal”.
         "wasmu"
     }
+
+    /// Returns the memory range of each local function, to register
+    /// their frame information.
+    fn function_extents(&self) -> BoxedSlice<LocalFunctionIndex, FunctionExtent> {
+        self.finished_functions
+            .values()
+            .copied()
+            .zip(self.finished_function_lengths.values().copied())
+            .map(|(ptr, length)| FunctionExtent { ptr, length })
+            .collect::<PrimaryMap<LocalFunctionIndex, _>>()
+            .into_boxed_slice()
+    }
+
+    /// Returns the memory range and the address map of each function
+    /// call trampoline, to register their frame information.
+    ///
+    /// The address maps are made from the trampolines as they were
+    /// emitted, so serialized artifacts don't carry them. Trampolines
+    /// shared by several signatures are attributed to the first one.
+    fn trampoline_extents(&self) -> Vec<(FunctionExtent, FunctionAddressMap)> {
+        let compilation = &self.serializable.compilation;
+        let mut signatures = vec![None; compilation.function_call_trampolines.len()];
+        for (signature, &index) in compilation.function_call_trampoline_indices.iter() {
+            if index != OMITTED_TRAMPOLINE && signatures[index as usize].is_none() {
+                signatures[index as usize] = Some(signature);
+            }
+        }
+        signatures
+            .into_iter()
+            .zip(compilation.function_call_trampolines.iter())
+            .filter_map(|(signature, body)| {
+                let signature = signature?;
+                let ptr = self.finished_function_call_trampolines[signature] as usize;
+                let extent = FunctionExtent {
+                    ptr: FunctionBodyPtr(ptr as *const VMFunctionBody),
+                    length: body.body.len(),
+                };
+                Some((extent, FunctionAddressMap::trampoline(signature, body)))
+            })
+            .collect()
+    }
 }
 
 impl Artifact for UniversalArtifact {
@@ -279,35 +1008,141 @@ impl Artifact for UniversalArtifact {
     fn register_frame_info(&self) {
         let mut info = self.frame_info_registration.lock().unwrap();
 
-        if info.is_some() {
+        if let Some(registration) = &*info {
+            // Only the trap information may have been registered.
+            if self.address_maps && !registration.has_address_maps() {
+                registration
+                    .add_address_maps(self.serializable.compilation.function_frame_info.clone());
+                if self.source_lines {
+                    registration.add_source_lines();
+                }
+            }
             return;
         }
 
-        let finished_function_extents = self
-            .finished_functions
-            .values()
-            .copied()
-            .zip(self.finished_function_lengths.values().copied())
-            .map(|(ptr, length)| FunctionExtent { ptr, length })
-            .collect::<PrimaryMap<LocalFunctionIndex, _>>()
-            .into_boxed_slice();
+        let register = if self.address_maps {
+            register_frame_info
+        } else {
+            register_frame_info_without_address_maps
+        };
+        *info = register(
+            self.serializable.compile_info.module.clone(),
+            &self.function_extents(),
+            self.serializable.compilation.function_frame_info.clone(),
+        );
+        if let Some(registration) = info.as_mut() {
+            registration.add_trampolines(self.trampoline_extents());
+            if self.address_maps && self.source_lines {
+                registration.add_source_lines();
+            }
+        }
+    }
 
-        let frame_infos = &self.serializable.compilation.function_frame_info;
-        *info = register_frame_info(
+    fn register_trap_info(&self) {
+        let mut info = self.frame_info_registration.lock().unwrap();
+
+        if info.is_some() {
+            return;
+        }
+
+        *info = register_trap_info(
             self.serializable.compile_info.module.clone(),
-            &finished_function_extents,
-            frame_infos.clone(),
+            &self.function_extents(),
+            &self.serializable.compilation.function_frame_info,
         );
+        if let Some(registration) = info.as_mut() {
+            registration.add_trampolines(self.trampoline_extents());
+        }
+    }
+
+    fn warm_up(&self) {
+        self.register_frame_info();
+
+        // Read a byte of every page of code, including the trampolines
+        // and the executable custom sections, so that the first calls
+        // don't fault them in.
+        let page_size = region::page::size();
+        let (start, length) = self.executable_code;
+        for page in (start & !(page_size - 1)..start + length).step_by(page_size) {
+            // Safety: the code stays mapped and readable for as long as
+            // the engine that published it, and whole pages are mapped.
+            unsafe { std::ptr::read_volatile(page as *const u8) };
+        }
     }
 
     fn features(&self) -> &Features {
         &self.serializable.compile_info.features
     }
 
+    fn source_hash(&self) -> Option<[u8; 32]> {
+        self.source_hash
+    }
+
+    #[cfg(feature = "source-hash")]
+    fn verify_source(&self, wasm: &[u8]) -> bool {
+        match &self.source_hash {
+            // Comparing a `blake3::Hash` takes constant time.
+            Some(hash) => blake3::hash(wasm) == *hash,
+            None => false,
+        }
+    }
+
+    /// The metadata is kept apart from the compiled code and the
+    /// custom sections of the wasm module.
+    fn set_metadata(&self, key: &str, value: Vec<u8>) -> Result<(), SerializeError> {
+        self.metadata.lock().unwrap().insert(key.to_string(), value);
+        Ok(())
+    }
+
+    fn get_metadata(&self, key: &str) -> Option<Vec<u8>> {
+        self.metadata.lock().unwrap().get(key).cloned()
+    }
+
     fn data_initializers(&self) -> &[OwnedDataInitializer] {
         &*self.serializable.data_initializers
     }
 
+    /// The code is counted once more in the metadata, which keeps a
+    /// copy of it to serialize the artifact.
+    fn memory_footprint(&self) -> ModuleFootprint {
+        let compilation = &self.serializable.compilation;
+        let code_bytes = compilation
+            .function_bodies
+            .values()
+            .chain(compilation.function_call_trampolines.iter())
+            .chain(compilation.dynamic_function_trampolines.values())
+            .map(|body| body.body.len())
+            .chain(
+                compilation
+                    .custom_sections
+                    .values()
+                    .map(|section| section.bytes.len()),
+            )
+            .sum();
+        let frame_info_bytes = loupe::size_of_val(&compilation.function_frame_info);
+        let data_initializer_bytes = self
+            .serializable
+            .data_initializers
+            .iter()
+            .map(|initializer| initializer.data.len())
+            .chain(
+                self.serializable
+                    .compile_info
+                    .module
+                    .passive_data
+                    .values()
+                    .map(|data| data.len()),
+            )
+            .sum::<usize>();
+        let total = loupe::size_of_val(self);
+        ModuleFootprint {
+            code_bytes,
+            frame_info_bytes,
+            data_initializer_bytes,
+            metadata_bytes: total.saturating_sub(frame_info_bytes + data_initializer_bytes),
+        }
+    }
+
     fn memory_styles(&self) -> &PrimaryMap<MemoryIndex, MemoryStyle> {
         &self.serializable.compile_info.memory_styles
     }
@@ -332,6 +1167,10 @@ impl Artifact for UniversalArtifact {
         &self.signatures
     }
 
+    fn signature_registration(&self) -> Option<Arc<SignatureRegistration>> {
+        Some(self.signature_registration.clone())
+    }
+
     fn func_data_registry(&self) -> &FuncDataRegistry {
         &self.func_data_registry
     }
@@ -348,10 +1187,213 @@ impl Artifact for UniversalArtifact {
         let offset = pad_and_extend::<SerializableModule>(&mut serialized, &serialized_data);
         assert_eq!(offset, SERIALIZED_METADATA_CONTENT_OFFSET);
 
+        let mut extensions: Vec<(u64, &[u8])> = vec![];
+        if !self.address_maps {
+            extensions.push((NO_ADDRESS_MAPS_EXTENSION, &[]));
+        }
+        if let Some(hash) = &self.source_hash {
+            extensions.push((SOURCE_HASH_EXTENSION, hash));
+        }
+        let mut cpu_features = vec![];
+        if let Some(features) = self.cpu_features {
+            leb128::write::unsigned(&mut cpu_features, features.as_u64())
+                .expect("Should write number");
+            extensions.push((CPU_FEATURES_EXTENSION, &cpu_features));
+        }
+        let metadata = self
+            .metadata
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, value)| {
+                let mut payload = vec![];
+                leb128::write::unsigned(&mut payload, key.len() as u64)
+                    .expect("Should write number");
+                payload.extend_from_slice(key.as_bytes());
+                payload.extend_from_slice(value);
+                payload
+            })
+            .collect::<Vec<_>>();
+        extensions.extend(
+            metadata
+                .iter()
+                .map(|payload| (METADATA_EXTENSION, payload.as_slice())),
+        );
+        if self.checksum {
+            // Filled in once the rest of the artifact is written.
+            extensions.push((CHECKSUM_EXTENSION, &[0; 32]));
+        }
+        write_extensions(&mut serialized, &extensions);
+        // Without the `artifact-checksum` feature, no artifact has one.
+        #[cfg(feature = "artifact-checksum")]
+        {
+            if self.checksum {
+                let checksum_offset = serialized.len() - 32;
+                let checksum =
+                    blake3::hash(&serialized[SERIALIZED_METADATA_LENGTH_OFFSET..checksum_offset]);
+                serialized[checksum_offset..].copy_from_slice(checksum.as_bytes());
+            }
+        }
+
         Ok(serialized)
     }
 }
 
+/// Appends the extensions region to a serialized artifact.
+///
+/// The region follows the metadata. It holds a LEB128 length followed
+/// by the extensions, each made of a LEB128 tag, a LEB128 length and
+/// the payload. Extensions carry additive metadata only: a deserializer
+/// skips the tags it doesn't know, so that newer versions can add
+/// metadata without breaking older ones.
+fn write_extensions(serialized: &mut Vec<u8>, extensions: &[(u64, &[u8])]) {
+    let mut region = vec![];
+    for (tag, payload) in extensions {
+        leb128::write::unsigned(&mut region, *tag).expect("Should write number");
+        leb128::write::unsigned(&mut region, payload.len() as u64).expect("Should write number");
+        region.extend_from_slice(payload);
+    }
+    leb128::write::unsigned(serialized, region.len() as u64).expect("Should write number");
+    serialized.extend(region);
+}
+
+/// Checks the checksum of the serialized artifact `bytes`, before
+/// anything else is read out of it.
+///
+/// The checksum is verified if any extension is tagged with
+/// [`CHECKSUM_EXTENSION`], which must then be the last one, or if
+/// `required`, in which case an artifact without one is rejected. This
+/// way, corrupting the tag doesn't disable the verification. Without
+/// the `artifact-checksum` feature, artifacts with a checksum are
+/// rejected as incompatible, since it can't be verified.
+fn verify_checksum(
+    bytes: &[u8],
+    extensions: &[(u64, &[u8])],
+    required: bool,
+) -> Result<(), DeserializeError> {
+    let corrupted = |message: &str| Err(DeserializeError::CorruptedBinary(message.to_string()));
+    let checksum = match extensions.last() {
+        Some(&(CHECKSUM_EXTENSION, checksum)) => checksum,
+        _ if extensions.iter().any(|&(tag, _)| tag == CHECKSUM_EXTENSION) => {
+            return corrupted("The checksum of the artifact isn't its last extension");
+        }
+        _ if required => return corrupted("The artifact has no checksum"),
+        _ => return Ok(()),
+    };
+    if extensions[..extensions.len() - 1]
+        .iter()
+        .any(|&(tag, _)| tag == CHECKSUM_EXTENSION)
+    {
+        return corrupted("The artifact has more than one checksum");
+    }
+    #[cfg(feature = "artifact-checksum")]
+    {
+        // The extensions borrow from `bytes`.
+        let checksum_offset = checksum.as_ptr() as usize - bytes.as_ptr() as usize;
+        let expected = blake3::hash(&bytes[SERIALIZED_METADATA_LENGTH_OFFSET..checksum_offset]);
+        if expected.as_bytes()[..] != *checksum {
+            return corrupted("The checksum of the artifact doesn't match its contents");
+        }
+        Ok(())
+    }
+    #[cfg(not(feature = "artifact-checksum"))]
+    {
+        let _ = checksum;
+        Err(DeserializeError::Incompatible(
+            "Verifying the checksum of the artifact needs the `artifact-checksum` feature"
+                .to_string(),
+        ))
+    }
+}
+
+/// Reads the payload of the CPU features extension.
+fn read_cpu_features(mut payload: &[u8]) -> Result<EnumSet<CpuFeature>, DeserializeError> {
+    let bits = leb128::read::unsigned(&mut payload).map_err(|_| {
+        DeserializeError::CorruptedBinary("Can't read the CPU features".to_string())
+    })?;
+    EnumSet::try_from_u64(bits).ok_or_else(|| {
+        DeserializeError::Incompatible("The binary requires unknown CPU features".to_string())
+    })
+}
+
+/// Checks that the host has the CPU features the extensions require,
+/// before any of the code is published.
+fn check_cpu_features(extensions: &[(u64, &[u8])]) -> Result<(), DeserializeError> {
+    for &(tag, payload) in extensions {
+        if tag != CPU_FEATURES_EXTENSION {
+            continue;
+        }
+        let required = read_cpu_features(payload)?;
+        let available = CpuFeature::for_host();
+        if !available.is_superset(required) {
+            return Err(DeserializeError::IncompatibleCpuFeatures {
+                required,
+                available,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that every signature of the module has a trampoline index,
+/// pointing to one of the distinct trampolines of the compilation or
+/// marking it as omitted, before linking looks them up.
+fn check_trampoline_indices(serializable: &SerializableModule) -> Result<(), DeserializeError> {
+    let compilation = &serializable.compilation;
+    let indices = &compilation.function_call_trampoline_indices;
+    let signature_count = serializable.compile_info.module.signatures.len();
+    if indices.len() != signature_count {
+        return Err(DeserializeError::CorruptedBinary(format!(
+            "{} function call trampoline indices for {} signatures",
+            indices.len(),
+            signature_count
+        )));
+    }
+    let trampoline_count = compilation.function_call_trampolines.len();
+    for (signature, &index) in indices.iter() {
+        if index != OMITTED_TRAMPOLINE && index as usize >= trampoline_count {
+            return Err(DeserializeError::CorruptedBinary(format!(
+                "the function call trampoline {} of signature {} is out of bounds ({} trampolines)",
+                index,
+                signature.as_u32(),
+                trampoline_count
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Reads the extensions region written by [`write_extensions`].
+///
+/// Artifacts serialized before extensions existed have no region at
+/// all, which is read as no extensions.
+fn read_extensions(mut bytes: &[u8]) -> Result<Vec<(u64, &[u8])>, DeserializeError> {
+    fn corrupted<E>(_: E) -> DeserializeError {
+        DeserializeError::CorruptedBinary("Can't read the extensions".to_string())
+    }
+    fn take<'a>(bytes: &mut &'a [u8], len: u64) -> Result<&'a [u8], DeserializeError> {
+        if len > bytes.len() as u64 {
+            return Err(corrupted(()));
+        }
+        let (taken, rest) = bytes.split_at(len as usize);
+        *bytes = rest;
+        Ok(taken)
+    }
+
+    let mut extensions = vec![];
+    if bytes.is_empty() {
+        return Ok(extensions);
+    }
+    let region_len = leb128::read::unsigned(&mut bytes).map_err(corrupted)?;
+    let mut region = take(&mut bytes, region_len)?;
+    while !region.is_empty() {
+        let tag = leb128::read::unsigned(&mut region).map_err(corrupted)?;
+        let len = leb128::read::unsigned(&mut region).map_err(corrupted)?;
+        extensions.push((tag, take(&mut region, len)?));
+    }
+    Ok(extensions)
+}
+
 /// It pads the data with the desired alignment
 pub fn pad_and_extend<T>(prev_data: &mut Vec<u8>, data: &[u8]) -> usize {
     let align = std::mem::align_of::<T>();
@@ -367,7 +1409,506 @@ pub fn pad_and_extend<T>(prev_data: &mut Vec<u8>, data: &[u8]) -> usize {
 
 #[cfg(test)]
 mod tests {
-    use super::pad_and_extend;
+    use super::UniversalArtifact;
+    use super::{
+        check_trampoline_indices, pad_and_extend, read_extensions, verify_checksum,
+        write_extensions, CHECKSUM_EXTENSION, SOURCE_HASH_EXTENSION,
+    };
+    use crate::serialize::{SerializableCompilation, SerializableModule};
+    use crate::UniversalEngine;
+    use enumset::EnumSet;
+    use std::sync::Arc;
+    use wasmer_compiler::{
+        CompileModuleInfo, CpuFeature, Features, FunctionBody, Relocation, RelocationKind,
+        RelocationTarget,
+    };
+    use wasmer_engine::{is_wasm_pc, Artifact, DeserializeError, VerifyError, FRAME_INFO};
+    use wasmer_types::entity::{EntityRef, PrimaryMap, SecondaryMap};
+    use wasmer_types::{FunctionIndex, FunctionType, LocalFunctionIndex, SignatureIndex};
+    use wasmer_vm::ModuleInfo;
+
+    /// Builds a module with a single 16 bytes function that calls
+    /// itself through a relocation at `offset`.
+    fn serializable_with_call_at(offset: u32) -> SerializableModule {
+        SerializableModule {
+            compilation: SerializableCompilation {
+                function_bodies: vec![FunctionBody {
+                    body: vec![0xcc; 16],
+                    unwind_info: None,
+                }]
+                .into_iter()
+                .collect(),
+                function_relocations: vec![vec![Relocation {
+                    kind: RelocationKind::X86CallPCRel4,
+                    reloc_target: RelocationTarget::LocalFunc(LocalFunctionIndex::new(0)),
+                    offset,
+                    addend: 0,
+                }]]
+                .into_iter()
+                .collect(),
+                function_jt_offsets: vec![SecondaryMap::new()].into_iter().collect(),
+                function_frame_info: vec![Default::default()].into_iter().collect(),
+                function_call_trampolines: vec![],
+                function_call_trampoline_indices: PrimaryMap::new(),
+                dynamic_function_trampolines: PrimaryMap::new(),
+                custom_sections: PrimaryMap::new(),
+                custom_section_relocations: PrimaryMap::new(),
+                debug: None,
+            },
+            compile_info: CompileModuleInfo {
+                features: Features::default(),
+                module: Arc::new(ModuleInfo::new()),
+                memory_styles: PrimaryMap::new(),
+                table_styles: PrimaryMap::new(),
+            },
+            data_initializers: Box::new([]),
+        }
+    }
+
+    fn serialize_with_call_at(offset: u32) -> Vec<u8> {
+        let engine = UniversalEngine::headless();
+        let artifact = UniversalArtifact::from_parts(
+            &mut engine.inner_mut(),
+            serializable_with_call_at(offset),
+        )
+        .expect("valid artifact");
+        artifact.serialize().unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "compiler")]
+    fn compilation_stats_sum_body_lengths() {
+        use super::{CompilationStats, PhaseTimings};
+        use std::time::Duration;
+        use wasmer_compiler::{CustomSection, CustomSectionProtection, SectionBody};
+
+        let mut compilation = serializable_with_call_at(4).compilation;
+        compilation.function_bodies.push(FunctionBody {
+            body: vec![0xcc; 32],
+            unwind_info: None,
+        });
+        compilation.function_call_trampolines.push(FunctionBody {
+            body: vec![0xcc; 8],
+            unwind_info: None,
+        });
+        compilation.dynamic_function_trampolines.push(FunctionBody {
+            body: vec![0xcc; 4],
+            unwind_info: None,
+        });
+        compilation.custom_sections.push(CustomSection {
+            protection: CustomSectionProtection::Read,
+            bytes: SectionBody::new_with_vec(vec![0; 64]),
+            relocations: vec![],
+        });
+
+        let phases = PhaseTimings {
+            translate: Duration::from_millis(1),
+            codegen: Duration::from_millis(2),
+            trampolines: Duration::from_millis(3),
+            link: Duration::from_millis(4),
+            publish: Duration::from_millis(5),
+        };
+        let stats = CompilationStats::new(&compilation, phases);
+        assert_eq!(
+            stats,
+            CompilationStats {
+                wall_time: Duration::from_millis(15),
+                function_code_bytes: 16 + 32,
+                trampoline_bytes: 8 + 4,
+                custom_section_bytes: 64,
+                function_count: 2,
+                phases,
+            }
+        );
+    }
+
+    #[test]
+    fn deserialized_artifact_has_no_stats() {
+        let engine = UniversalEngine::headless();
+        let artifact =
+            unsafe { UniversalArtifact::deserialize(&engine, &serialize_with_call_at(4)) }.unwrap();
+        assert!(artifact.stats().is_none());
+    }
+
+    #[test]
+    fn deserialize_checked_rejects_out_of_bounds_relocation() {
+        let valid = serialize_with_call_at(4);
+        let engine = UniversalEngine::headless();
+        assert!(unsafe { UniversalArtifact::deserialize_checked(&engine, &valid) }.is_ok());
+
+        // Locate the relocation offset in the blob by comparing it with
+        // one that only differs by that offset.
+        let other = serialize_with_call_at(5);
+        assert_eq!(valid.len(), other.len());
+        let position = valid
+            .iter()
+            .zip(&other)
+            .position(|(a, b)| a != b)
+            .expect("the relocation offset is serialized");
+        assert_eq!(&valid[position..position + 4], &4u32.to_le_bytes());
+
+        // Make the 4 bytes patched by the relocation end past the body.
+        for offset in &[13u32, 16, u32::MAX] {
+            let mut corrupted = valid.clone();
+            corrupted[position..position + 4].copy_from_slice(&offset.to_le_bytes());
+            match unsafe { UniversalArtifact::deserialize_checked(&engine, &corrupted) } {
+                Err(DeserializeError::CorruptedBinary(_)) => {}
+                Err(e) => panic!("Unexpected error: {:?}", e),
+                Ok(_) => panic!("Relocation at offset {} was not rejected", offset),
+            }
+        }
+
+        // A relocation that ends exactly at the end of the body is fine.
+        let mut corrupted = valid;
+        corrupted[position..position + 4].copy_from_slice(&12u32.to_le_bytes());
+        assert!(unsafe { UniversalArtifact::deserialize_checked(&engine, &corrupted) }.is_ok());
+    }
+
+    /// Builds an artifact whose module declares the function of
+    /// `serializable_with_call_at`, with the signature `signature`.
+    fn artifact_with_signature(engine: &UniversalEngine, signature: u32) -> UniversalArtifact {
+        let mut serializable = serializable_with_call_at(4);
+        let mut module = ModuleInfo::new();
+        module.signatures.push(FunctionType::new(vec![], vec![]));
+        module.functions.push(SignatureIndex::from_u32(signature));
+        serializable.compile_info.module = Arc::new(module);
+        let compilation = &mut serializable.compilation;
+        compilation.function_call_trampolines.push(FunctionBody {
+            body: vec![0xc3],
+            unwind_info: None,
+        });
+        compilation.function_call_trampoline_indices.push(0);
+        UniversalArtifact::from_parts(&mut engine.inner_mut(), serializable)
+            .expect("the artifact can be linked")
+    }
+
+    #[test]
+    fn trampoline_indices_are_checked() {
+        let mut serializable = serializable_with_call_at(4);
+        let mut module = ModuleInfo::new();
+        module.signatures.push(FunctionType::new(vec![], vec![]));
+        serializable.compile_info.module = Arc::new(module);
+        let compilation = &mut serializable.compilation;
+        compilation.function_call_trampolines.push(FunctionBody {
+            body: vec![0xc3],
+            unwind_info: None,
+        });
+
+        // Every signature needs an index.
+        assert!(matches!(
+            check_trampoline_indices(&serializable),
+            Err(DeserializeError::CorruptedBinary(_))
+        ));
+
+        serializable
+            .compilation
+            .function_call_trampoline_indices
+            .push(0);
+        check_trampoline_indices(&serializable).unwrap();
+
+        serializable.compilation.function_call_trampoline_indices[SignatureIndex::new(0)] =
+            super::OMITTED_TRAMPOLINE;
+        check_trampoline_indices(&serializable).unwrap();
+
+        serializable.compilation.function_call_trampoline_indices[SignatureIndex::new(0)] = 1;
+        assert!(matches!(
+            check_trampoline_indices(&serializable),
+            Err(DeserializeError::CorruptedBinary(_))
+        ));
+    }
+
+    #[test]
+    fn older_format_versions_are_rejected() {
+        let mut serialized = serialize_with_call_at(4);
+        let version_offset = UniversalArtifact::MAGIC_HEADER.len() - 1;
+        serialized[version_offset] = 0;
+        let engine = UniversalEngine::headless();
+        match unsafe { UniversalArtifact::deserialize(&engine, &serialized) } {
+            Err(DeserializeError::Incompatible(message)) => {
+                assert!(message.contains("format version 0"), "{}", message)
+            }
+            Err(e) => panic!("Unexpected error: {:?}", e),
+            Ok(_) => panic!("the artifact shouldn't load"),
+        }
+    }
+
+    #[test]
+    fn verify_consistent_artifact() {
+        let engine = UniversalEngine::headless();
+        artifact_with_signature(&engine, 0).verify().unwrap();
+    }
+
+    #[test]
+    fn verify_rejects_tampered_signature_map() {
+        let engine = UniversalEngine::headless();
+        match artifact_with_signature(&engine, 1).verify() {
+            Err(VerifyError::UnknownSignature(function, signature)) => {
+                assert_eq!(function, FunctionIndex::new(0));
+                assert_eq!(signature, SignatureIndex::new(1));
+            }
+            other => panic!("Unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn verify_rejects_missing_function_bodies() {
+        let engine = UniversalEngine::headless();
+        // The module of `serializable_with_call_at` declares no
+        // function, but the compilation has a body for one.
+        let artifact =
+            UniversalArtifact::from_parts(&mut engine.inner_mut(), serializable_with_call_at(4))
+                .unwrap();
+        assert!(matches!(
+            artifact.verify(),
+            Err(VerifyError::MapMismatch {
+                map: "function bodies",
+                expected: 0,
+                found: 1,
+            })
+        ));
+    }
+
+    #[test]
+    fn verify_checksum_requires_a_single_last_checksum() {
+        let bytes = vec![0; 64];
+        let checksum = &bytes[32..];
+        assert!(verify_checksum(&bytes, &[], false).is_ok());
+        assert!(matches!(
+            verify_checksum(&bytes, &[], true),
+            Err(DeserializeError::CorruptedBinary(_))
+        ));
+        assert!(matches!(
+            verify_checksum(
+                &bytes,
+                &[(CHECKSUM_EXTENSION, checksum), (SOURCE_HASH_EXTENSION, &[])],
+                false
+            ),
+            Err(DeserializeError::CorruptedBinary(_))
+        ));
+        assert!(matches!(
+            verify_checksum(
+                &bytes,
+                &[
+                    (CHECKSUM_EXTENSION, checksum),
+                    (CHECKSUM_EXTENSION, checksum)
+                ],
+                false
+            ),
+            Err(DeserializeError::CorruptedBinary(_))
+        ));
+    }
+
+    #[test]
+    fn unknown_extensions_are_skipped() {
+        let mut serialized = serialize_with_call_at(4);
+        let metadata_end = serialized.len() - 1;
+        assert_eq!(
+            read_extensions(&serialized[metadata_end..]).unwrap(),
+            vec![]
+        );
+
+        // An artifact written by a future version, with an extension
+        // this version doesn't know about.
+        serialized.truncate(metadata_end);
+        write_extensions(&mut serialized, &[(0xdead, b"future metadata")]);
+        assert_eq!(
+            read_extensions(&serialized[metadata_end..]).unwrap(),
+            vec![(0xdead, &b"future metadata"[..])]
+        );
+        let engine = UniversalEngine::headless();
+        assert!(unsafe { UniversalArtifact::deserialize(&engine, &serialized) }.is_ok());
+
+        // An artifact written before extensions existed.
+        serialized.truncate(metadata_end);
+        assert!(unsafe { UniversalArtifact::deserialize(&engine, &serialized) }.is_ok());
+
+        // A truncated extension is reported.
+        write_extensions(&mut serialized, &[(0xdead, b"future metadata")]);
+        serialized.pop();
+        assert!(matches!(
+            unsafe { UniversalArtifact::deserialize(&engine, &serialized) },
+            Err(DeserializeError::CorruptedBinary(_))
+        ));
+    }
+
+    #[test]
+    fn metadata_round_trips_through_serialization() {
+        let engine = UniversalEngine::headless();
+        let artifact =
+            UniversalArtifact::from_parts(&mut engine.inner_mut(), serializable_with_call_at(4))
+                .unwrap();
+        assert_eq!(artifact.get_metadata("manifest"), None);
+        artifact.set_metadata("signature", vec![1, 2, 3]).unwrap();
+        artifact
+            .set_metadata("manifest", b"draft".to_vec())
+            .unwrap();
+        artifact
+            .set_metadata("manifest", b"{\"version\": 2}".to_vec())
+            .unwrap();
+        artifact.set_metadata("empty", vec![]).unwrap();
+
+        let serialized = artifact.serialize().unwrap();
+        let artifact = unsafe { UniversalArtifact::deserialize(&engine, &serialized) }.unwrap();
+        assert_eq!(artifact.get_metadata("signature"), Some(vec![1, 2, 3]));
+        assert_eq!(
+            artifact.get_metadata("manifest"),
+            Some(b"{\"version\": 2}".to_vec())
+        );
+        assert_eq!(artifact.get_metadata("empty"), Some(vec![]));
+        assert_eq!(artifact.get_metadata("missing"), None);
+    }
+
+    #[test]
+    fn cpu_features_are_checked_on_deserialization() {
+        let engine = UniversalEngine::headless();
+        let mut artifact =
+            UniversalArtifact::from_parts(&mut engine.inner_mut(), serializable_with_call_at(4))
+                .unwrap();
+        assert_eq!(artifact.cpu_features(), None);
+
+        artifact.cpu_features = Some(CpuFeature::for_host());
+        let serialized = artifact.serialize().unwrap();
+        let artifact = unsafe { UniversalArtifact::deserialize(&engine, &serialized) }.unwrap();
+        assert_eq!(artifact.cpu_features(), Some(CpuFeature::for_host()));
+
+        let missing = EnumSet::all() - CpuFeature::for_host();
+        if missing.is_empty() {
+            // The host has every feature, none can be lacking.
+            return;
+        }
+        let mut artifact =
+            UniversalArtifact::from_parts(&mut engine.inner_mut(), serializable_with_call_at(4))
+                .unwrap();
+        artifact.cpu_features = Some(CpuFeature::for_host() | missing);
+        let serialized = artifact.serialize().unwrap();
+        for result in vec![
+            unsafe { UniversalArtifact::deserialize(&engine, &serialized) },
+            unsafe { UniversalArtifact::deserialize_checked(&engine, &serialized) },
+        ] {
+            match result {
+                Err(DeserializeError::IncompatibleCpuFeatures {
+                    required,
+                    available,
+                }) => {
+                    assert_eq!(required, CpuFeature::for_host() | missing);
+                    assert_eq!(available, CpuFeature::for_host());
+                }
+                Err(e) => panic!("unexpected error: {}", e),
+                Ok(_) => panic!("the artifact shouldn't load"),
+            }
+        }
+    }
+
+    #[test]
+    fn function_body_bytes_and_patch_function() {
+        let engine = UniversalEngine::headless();
+        let artifact =
+            UniversalArtifact::from_parts(&mut engine.inner_mut(), serializable_with_call_at(4))
+                .unwrap();
+        let index = LocalFunctionIndex::new(0);
+        let body = artifact.function_body_bytes(index).unwrap();
+        assert_eq!(body.len(), 16);
+        assert_eq!(&body[..4], &[0xcc; 4]);
+        assert_eq!(
+            artifact.function_body_bytes(LocalFunctionIndex::new(1)),
+            None
+        );
+
+        // `mov eax, 42; ret`, padded with `int3` to the original length.
+        let mut stub = vec![0xb8, 0x2a, 0x00, 0x00, 0x00, 0xc3];
+        stub.resize(16, 0xcc);
+        unsafe { artifact.patch_function(&engine, index, &stub) }.unwrap();
+        assert_eq!(artifact.function_body_bytes(index), Some(&stub[..]));
+
+        #[cfg(target_arch = "x86_64")]
+        {
+            let body = artifact.function_body_bytes(index).unwrap();
+            let f: extern "C" fn() -> i32 = unsafe { std::mem::transmute(body.as_ptr()) };
+            assert_eq!(f(), 42);
+        }
+
+        let too_long = vec![0xcc; 17];
+        assert!(unsafe { artifact.patch_function(&engine, index, &too_long) }.is_err());
+        assert!(
+            unsafe { artifact.patch_function(&engine, LocalFunctionIndex::new(1), &stub) }.is_err()
+        );
+        let other_engine = UniversalEngine::headless();
+        assert!(unsafe { artifact.patch_function(&other_engine, index, &stub) }.is_err());
+        assert_eq!(artifact.function_body_bytes(index), Some(&stub[..]));
+    }
+
+    #[cfg(feature = "debug-format")]
+    #[test]
+    fn serialize_debug_is_json() {
+        let engine = UniversalEngine::headless();
+        let artifact = artifact_with_signature(&engine, 0);
+        let debug = artifact.serialize_debug().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&debug).unwrap();
+
+        assert_eq!(value["function_count"], 1);
+        let function = &value["functions"][0];
+        assert_eq!(function["signature"], "[] -> []");
+        assert_eq!(function["body_size"], 16);
+        assert_eq!(function["relocations"], 1);
+        assert_eq!(function["body"], "cc".repeat(16));
+        assert_eq!(value["function_call_trampolines"], 1);
+    }
+
+    #[test]
+    fn warm_up_registers_frame_info() {
+        let engine = UniversalEngine::headless();
+        let artifact = artifact_with_signature(&engine, 0);
+        let pc = artifact
+            .function_body_bytes(LocalFunctionIndex::new(0))
+            .unwrap()
+            .as_ptr() as usize;
+        assert!(!is_wasm_pc(pc));
+        // The code warmed up spans the function and its trampoline.
+        let (start, length) = artifact.executable_code;
+        assert!(start <= pc && pc + 16 <= start + length);
+        let trampoline = artifact.finished_function_call_trampolines[SignatureIndex::new(0)];
+        assert!((start..start + length).contains(&(trampoline as usize)));
+
+        artifact.warm_up();
+        assert!(is_wasm_pc(pc));
+        // Warming up again, or instantiating, keeps the registration.
+        artifact.warm_up();
+        assert!(is_wasm_pc(pc));
+
+        drop(artifact);
+        assert!(!is_wasm_pc(pc));
+    }
+
+    #[test]
+    fn trampoline_frames_are_reported_as_trampolines() {
+        let engine = UniversalEngine::headless();
+        let artifact = artifact_with_signature(&engine, 0);
+        artifact.register_frame_info();
+
+        let trampoline = artifact.finished_function_call_trampolines[SignatureIndex::new(0)];
+        let frame = FRAME_INFO
+            .read()
+            .unwrap()
+            .lookup_frame_info(trampoline as usize)
+            .expect("the trampoline is registered");
+        assert_eq!(frame.trampoline_signature(), Some(SignatureIndex::new(0)));
+        assert_eq!(frame.function_name(), None);
+        assert_eq!(frame.module_offset(), 0);
+        assert!(!is_wasm_pc(trampoline as usize));
+
+        let function = artifact
+            .function_body_bytes(LocalFunctionIndex::new(0))
+            .unwrap()
+            .as_ptr() as usize;
+        let frame = FRAME_INFO
+            .read()
+            .unwrap()
+            .lookup_frame_info(function)
+            .expect("the function is registered");
+        assert_eq!(frame.trampoline_signature(), None);
+        assert_eq!(frame.func_index(), 0);
+        assert!(is_wasm_pc(function));
+    }
 
     #[test]
     fn test_pad_and_extend() {
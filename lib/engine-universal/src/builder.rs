@@ -1,5 +1,9 @@
-use crate::UniversalEngine;
-use wasmer_compiler::{CompilerConfig, Features, Target};
+use crate::{CodeAllocator, LibCallTable, RelocationRewriter, UniversalEngine};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use wasmer_compiler::{CompilationLimits, CompilerConfig, Features, Target};
+use wasmer_vm::{LibCall, SignatureRegistry};
 
 /// The Universal builder
 pub struct Universal {
@@ -7,6 +11,20 @@ pub struct Universal {
     compiler_config: Option<Box<dyn CompilerConfig>>,
     target: Option<Target>,
     features: Option<Features>,
+    cache_dir: Option<PathBuf>,
+    dual_mapped_code: bool,
+    code_allocator: Option<Arc<dyn CodeAllocator>>,
+    debug_info: bool,
+    source_hash: bool,
+    source_lines: bool,
+    artifact_checksum: bool,
+    exported_trampolines_only: bool,
+    compilation_limits: CompilationLimits,
+    compile_progress: Option<Arc<dyn Fn(usize, usize) + Send + Sync>>,
+    compile_deadline: Option<Duration>,
+    libcalls: LibCallTable,
+    relocation_rewriter: Option<Arc<dyn RelocationRewriter>>,
+    signature_registry: Option<Arc<SignatureRegistry>>,
 }
 
 impl Universal {
@@ -19,6 +37,20 @@ impl Universal {
             compiler_config: Some(compiler_config.into()),
             target: None,
             features: None,
+            cache_dir: None,
+            dual_mapped_code: false,
+            code_allocator: None,
+            debug_info: true,
+            source_hash: false,
+            source_lines: false,
+            artifact_checksum: false,
+            exported_trampolines_only: false,
+            compilation_limits: CompilationLimits::default(),
+            compile_progress: None,
+            compile_deadline: None,
+            libcalls: LibCallTable::new(),
+            relocation_rewriter: None,
+            signature_registry: None,
         }
     }
 
@@ -28,9 +60,36 @@ impl Universal {
             compiler_config: None,
             target: None,
             features: None,
+            cache_dir: None,
+            dual_mapped_code: false,
+            code_allocator: None,
+            debug_info: true,
+            source_hash: false,
+            source_lines: false,
+            artifact_checksum: false,
+            exported_trampolines_only: false,
+            compilation_limits: CompilationLimits::default(),
+            compile_progress: None,
+            compile_deadline: None,
+            libcalls: LibCallTable::new(),
+            relocation_rewriter: None,
+            signature_registry: None,
         }
     }
 
+    /// Returns whether the engine built from this configuration is
+    /// headless, without a compiler.
+    ///
+    /// This is the case of [`Universal::headless`], and of any
+    /// configuration when the `compiler` feature is disabled. Compiling
+    /// a module with a headless engine fails with a
+    /// [`CompileError::Codegen`], while deserializing one works.
+    ///
+    /// [`CompileError::Codegen`]: wasmer_compiler::CompileError::Codegen
+    pub fn is_headless(&self) -> bool {
+        cfg!(not(feature = "compiler")) || self.compiler_config.is_none()
+    }
+
     /// Set the target
     pub fn target(mut self, target: Target) -> Self {
         self.target = Some(target);
@@ -43,16 +102,258 @@ impl Universal {
         self
     }
 
+    /// Make every floating-point operation produce the canonical NaN
+    /// instead of an architecture-specific one
+    ///
+    /// This makes the results of float operations deterministic across
+    /// architectures. It has no effect on a headless engine, which
+    /// doesn't compile code.
+    pub fn canonicalize_nans(mut self, enable: bool) -> Self {
+        if let Some(compiler_config) = &mut self.compiler_config {
+            compiler_config.canonicalize_nans(enable);
+        }
+        self
+    }
+
+    /// Set the directory where compiled modules are cached
+    ///
+    /// Compiling a module that is already in the cache deserializes
+    /// it instead. The directory must be trusted. Entries are keyed by
+    /// the compiler, its settings and the settings of the engine, and
+    /// are only reused if they were compiled for the memory and table
+    /// styles the tunables pick, so engines configured differently can
+    /// share the directory.
+    pub fn cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Never map the compiled code both writable and executable
+    ///
+    /// The code is mapped read-execute from the start, and written
+    /// through a separate read-write alias that is unmapped once the
+    /// code is published. This is only supported on Linux: compiling or
+    /// deserializing modules fails on other platforms.
+    pub fn dual_mapped_code(mut self, enable: bool) -> Self {
+        self.dual_mapped_code = enable;
+        self
+    }
+
+    /// Get the memory of the compiled code from `allocator`, and make
+    /// it executable through it
+    ///
+    /// This applies to compiled and deserialized modules alike, and
+    /// takes precedence over [`dual_mapped_code`](Universal::dual_mapped_code).
+    /// By default the code is written to anonymous memory maps.
+    pub fn code_allocator(mut self, allocator: impl CodeAllocator + 'static) -> Self {
+        self.code_allocator = Some(Arc::new(allocator));
+        self
+    }
+
+    /// Keep the address maps of the compiled functions (the default)
+    ///
+    /// The address maps link the generated code back to the offsets in
+    /// the wasm module, to symbolicate the frames of backtraces. They
+    /// account for a good share of a serialized artifact: without them
+    /// traps keep their trap code, but report no frames.
+    pub fn debug_info(mut self, enable: bool) -> Self {
+        self.debug_info = enable;
+        self
+    }
+
+    /// Record the hash of the wasm binary of the compiled modules
+    ///
+    /// The hash is serialized with the artifacts, so that a deployer can
+    /// check that an artifact was compiled from a given wasm binary
+    /// with [`Artifact::verify_source`](wasmer_engine::Artifact::verify_source).
+    #[cfg(feature = "source-hash")]
+    pub fn source_hash(mut self, enable: bool) -> Self {
+        self.source_hash = enable;
+        self
+    }
+
+    /// Resolve the frames of traps to the lines of the original source
+    ///
+    /// The lines are read from the DWARF debug information embedded in
+    /// the custom sections of the modules, once per module when its
+    /// frame information is registered, and reported by
+    /// [`FrameInfo::source_location`](wasmer_engine::FrameInfo::source_location).
+    /// This needs the address maps of [`debug_info`](Universal::debug_info).
+    /// It is disabled by default, as reading the debug information of
+    /// a large module is expensive.
+    pub fn source_lines(mut self, enable: bool) -> Self {
+        self.source_lines = enable;
+        self
+    }
+
+    /// Make the serialized artifacts carry a checksum of their contents
+    ///
+    /// Deserializing an artifact with a checksum verifies it before
+    /// reading anything else out of the artifact, so that a truncated or
+    /// corrupted artifact fails with
+    /// [`DeserializeError::CorruptedBinary`](wasmer_engine::DeserializeError::CorruptedBinary)
+    /// instead of being linked into executable memory. Artifacts keep
+    /// their checksum when they are deserialized and serialized again.
+    ///
+    /// An engine with checksums enabled also requires them: deserializing
+    /// an artifact without a checksum fails with
+    /// [`DeserializeError::CorruptedBinary`](wasmer_engine::DeserializeError::CorruptedBinary).
+    #[cfg(feature = "artifact-checksum")]
+    pub fn artifact_checksum(mut self, enable: bool) -> Self {
+        self.artifact_checksum = enable;
+        self
+    }
+
+    /// Only keep the call trampolines of the signatures of the exported
+    /// functions
+    ///
+    /// A call trampoline lets the host call a function with a given
+    /// signature, with `Function::call` or a native function. The
+    /// trampolines of the signatures of no exported function are
+    /// dropped from the compiled artifacts, which makes them smaller
+    /// for modules with many internal signatures.
+    ///
+    /// Calling a function from the host through the trampoline of its
+    /// signature when it was dropped raises a trap instead, whose
+    /// message says that the trampoline was omitted from the artifact.
+    /// Currently only exported functions are called through the
+    /// trampolines of their artifact: the functions read from a table
+    /// don't use them.
+    pub fn exported_trampolines_only(mut self, enable: bool) -> Self {
+        self.exported_trampolines_only = enable;
+        self
+    }
+
+    /// Reject the modules exceeding `limits` before compiling them
+    ///
+    /// The modules are checked right after being translated, so that
+    /// a module with an absurd structure fails with
+    /// [`CompileError::ExceedsLimits`](wasmer_compiler::CompileError::ExceedsLimits)
+    /// instead of exhausting resources during compilation. There are no
+    /// limits by default.
+    pub fn compilation_limits(mut self, limits: CompilationLimits) -> Self {
+        self.compilation_limits = limits;
+        self
+    }
+
+    /// Report the progress of the compilation of every module to
+    /// `callback`
+    ///
+    /// The callback is called with the number of local functions
+    /// compiled so far and the total number of local functions, each
+    /// time a function is compiled. Compilers compiling the functions
+    /// in parallel call it concurrently from their threads, so the calls
+    /// may be out of order: only one of them reports that all the
+    /// functions are compiled. The callback must not use the engine.
+    pub fn compile_progress(
+        mut self,
+        callback: impl Fn(usize, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.compile_progress = Some(Arc::new(callback));
+        self
+    }
+
+    /// Give up compiling a module once `timeout` has elapsed since the
+    /// compilation started
+    ///
+    /// The deadline is checked between the phases of the compilation,
+    /// and before compiling each function, so a single huge function
+    /// can overrun it. A compilation past its deadline fails with
+    /// [`CompileError::Timeout`](wasmer_compiler::CompileError::Timeout),
+    /// before any code memory is allocated. There is no deadline by
+    /// default.
+    pub fn compile_deadline(mut self, timeout: Duration) -> Self {
+        self.compile_deadline = Some(timeout);
+        self
+    }
+
+    /// Resolve `libcall` to the function at `address` instead of the
+    /// implementation of the runtime
+    ///
+    /// The libcalls are resolved once, when the engine is built, and
+    /// every module the engine compiles or deserializes calls the
+    /// override.
+    ///
+    /// # Safety
+    ///
+    /// The function must honor the signature and calling convention of
+    /// the libcall it replaces, see [`LibCallTable::set`].
+    pub unsafe fn libcall(mut self, libcall: LibCall, address: usize) -> Self {
+        self.libcalls.set(libcall, address);
+        self
+    }
+
+    /// Pass the target of every relocation through `rewriter` before
+    /// it is patched into the code
+    ///
+    /// This applies to compiled and deserialized modules alike. By
+    /// default the relocations are applied as they are.
+    pub fn relocation_rewriter(mut self, rewriter: impl RelocationRewriter + 'static) -> Self {
+        self.relocation_rewriter = Some(Arc::new(rewriter));
+        self
+    }
+
+    /// Register the signatures in `registry` instead of a registry of
+    /// the engine's own
+    ///
+    /// Engines sharing a registry give identical function types the
+    /// same signature index, so that the functions of one engine can be
+    /// called indirectly from the instances of another, for example
+    /// through a shared table. The registry of an existing engine is
+    /// returned by [`UniversalEngine::signature_registry`].
+    pub fn signature_registry(mut self, registry: Arc<SignatureRegistry>) -> Self {
+        self.signature_registry = Some(registry);
+        self
+    }
+
     /// Build the `UniversalEngine` for this configuration
     #[cfg(feature = "compiler")]
-    pub fn engine(self) -> UniversalEngine {
+    pub fn engine(mut self) -> UniversalEngine {
+        let dual_mapped_code = self.dual_mapped_code;
+        let code_allocator = self.code_allocator.take();
+        let debug_info = self.debug_info;
+        let source_hash = self.source_hash;
+        let source_lines = self.source_lines;
+        let artifact_checksum = self.artifact_checksum;
+        let exported_trampolines_only = self.exported_trampolines_only;
+        let compilation_limits = self.compilation_limits;
+        let compile_progress = self.compile_progress.take();
+        let compile_deadline = self.compile_deadline;
+        let libcalls = std::mem::take(&mut self.libcalls);
+        let relocation_rewriter = self.relocation_rewriter.take();
+        let signature_registry = self.signature_registry.take();
+        let engine = self.engine_with_compiler();
+        engine.set_dual_mapped_code(dual_mapped_code);
+        engine.set_code_allocator(code_allocator);
+        engine.set_debug_info(debug_info);
+        engine.set_source_hash(source_hash);
+        engine.set_source_lines(source_lines);
+        engine.set_artifact_checksum(artifact_checksum);
+        engine.set_exported_trampolines_only(exported_trampolines_only);
+        engine.set_compilation_limits(compilation_limits);
+        engine.set_compile_progress(compile_progress);
+        engine.set_compile_deadline(compile_deadline);
+        engine.set_libcalls(libcalls);
+        engine.set_relocation_rewriter(relocation_rewriter);
+        if let Some(registry) = signature_registry {
+            engine.set_signature_registry(registry);
+        }
+        engine
+    }
+
+    #[cfg(feature = "compiler")]
+    fn engine_with_compiler(self) -> UniversalEngine {
         let target = self.target.unwrap_or_default();
         if let Some(compiler_config) = self.compiler_config {
             let features = self
                 .features
                 .unwrap_or_else(|| compiler_config.default_features_for_target(&target));
             let compiler = compiler_config.compiler();
-            UniversalEngine::new(compiler, target, features)
+            let mut engine = UniversalEngine::new(compiler, target, features);
+            if let Some(cache_dir) = self.cache_dir {
+                engine.set_cache_dir(cache_dir);
+            }
+            engine
         } else {
             UniversalEngine::headless()
         }
@@ -61,6 +362,16 @@ impl Universal {
     /// Build the `UniversalEngine` for this configuration
     #[cfg(not(feature = "compiler"))]
     pub fn engine(self) -> UniversalEngine {
-        UniversalEngine::headless()
+        let engine = UniversalEngine::headless();
+        engine.set_dual_mapped_code(self.dual_mapped_code);
+        engine.set_code_allocator(self.code_allocator);
+        engine.set_source_lines(self.source_lines);
+        engine.set_artifact_checksum(self.artifact_checksum);
+        engine.set_libcalls(self.libcalls);
+        engine.set_relocation_rewriter(self.relocation_rewriter);
+        if let Some(registry) = self.signature_registry {
+            engine.set_signature_registry(registry);
+        }
+        engine
     }
 }
@@ -0,0 +1,151 @@
+//! An on-disk cache of compiled artifacts, consulted by
+//! `UniversalEngine::compile`.
+
+use crate::engine::UniversalEngineInner;
+use crate::{UniversalArtifact, UniversalEngine};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use wasmer_compiler::{CompileError, Target};
+use wasmer_engine::{Artifact, Tunables};
+
+/// Distinguishes the temporary files written concurrently by the
+/// same process.
+static NEXT_TEMPORARY_FILE: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory holding serialized artifacts, keyed by the hash of the
+/// Wasm binary and the settings of the engine it was compiled with.
+///
+/// The memory and table styles picked by the tunables are checked when
+/// an entry is loaded instead, since they depend on the module.
+pub(crate) struct ArtifactCache {
+    dir: PathBuf,
+}
+
+impl ArtifactCache {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// Computes the key of `binary` when compiled for `target` by an
+    /// engine with the settings of `inner`. The key is also the file
+    /// name of the entry.
+    pub(crate) fn key(
+        &self,
+        binary: &[u8],
+        target: &Target,
+        inner: &UniversalEngineInner,
+    ) -> Result<String, CompileError> {
+        // Only explicit fields are hashed, not their `Debug` output,
+        // which isn't guaranteed to be stable. Variable length fields
+        // are prefixed with their length so they can't run together.
+        let mut hasher = blake3::Hasher::new();
+        let mut update_len_prefixed = |bytes: &[u8]| {
+            hasher.update(&(bytes.len() as u64).to_le_bytes());
+            hasher.update(bytes);
+        };
+        update_len_prefixed(crate::VERSION.as_bytes());
+        update_len_prefixed(target.triple().to_string().as_bytes());
+        update_len_prefixed(inner.compiler()?.deterministic_id().as_bytes());
+        hasher.update(&target.cpu_features().as_u64().to_le_bytes());
+        let features = inner.features();
+        hasher.update(&[
+            features.threads as u8,
+            features.reference_types as u8,
+            features.simd as u8,
+            features.bulk_memory as u8,
+            features.multi_value as u8,
+            features.tail_call as u8,
+            features.module_linking as u8,
+            features.multi_memory as u8,
+            features.memory64 as u8,
+            features.exceptions as u8,
+        ]);
+        hasher.update(&[
+            inner.debug_info() as u8,
+            inner.source_hash() as u8,
+            inner.artifact_checksum() as u8,
+            inner.exported_trampolines_only() as u8,
+        ]);
+        let limits = inner.compilation_limits();
+        for limit in &[
+            limits.max_functions,
+            limits.max_function_body_size,
+            limits.max_table_elements,
+            limits.max_memory_pages,
+            limits.max_globals,
+        ] {
+            // No `u32` limit is `u64::MAX`, which stands for no limit.
+            hasher.update(&limit.map_or(u64::MAX, u64::from).to_le_bytes());
+        }
+        // The libcalls are resolved when an artifact is loaded, so only
+        // which ones are overridden matters, not the addresses.
+        let overridden = inner.libcalls().overridden().collect::<Vec<_>>();
+        hasher.update(&(overridden.len() as u64).to_le_bytes());
+        for libcall in overridden {
+            hasher.update(&(libcall as u64).to_le_bytes());
+        }
+        hasher.update(binary);
+        Ok(format!(
+            "{}.{}",
+            hasher.finalize().to_hex(),
+            UniversalArtifact::get_default_extension(target.triple())
+        ))
+    }
+
+    /// Loads the artifact stored under `key`, if its code was compiled
+    /// for the memory and table styles `tunables` pick.
+    ///
+    /// Missing entries, entries that can't be deserialized (for example
+    /// because they were truncated), and entries compiled with other
+    /// tunables are reported as `None` so the caller compiles the module
+    /// again.
+    pub(crate) fn load(
+        &self,
+        engine: &UniversalEngine,
+        key: &str,
+        tunables: &dyn Tunables,
+    ) -> Option<UniversalArtifact> {
+        let bytes = fs::read(self.dir.join(key)).ok()?;
+        // Safety: the cache directory is trusted by whoever configured
+        // it on the engine, and the relocations are verified anyway.
+        let artifact = unsafe { UniversalArtifact::deserialize_checked(engine, &bytes) }.ok()?;
+        let module = artifact.module_ref();
+        let memory_styles_match = module.memories.iter().all(|(index, ty)| {
+            artifact.memory_styles().get(index) == Some(&tunables.memory_style(ty))
+        });
+        let table_styles_match = module.tables.iter().all(|(index, ty)| {
+            artifact.table_styles().get(index) == Some(&tunables.table_style(ty))
+        });
+        if memory_styles_match && table_styles_match {
+            Some(artifact)
+        } else {
+            None
+        }
+    }
+
+    /// Stores `artifact` under `key`.
+    ///
+    /// The entry is written to a temporary file first and then renamed
+    /// into place, so concurrent readers never observe a partially
+    /// written entry and concurrent writers of the same key simply
+    /// replace each other's identical artifact.
+    pub(crate) fn store(&self, key: &str, artifact: &UniversalArtifact) -> io::Result<()> {
+        let bytes = artifact
+            .serialize()
+            .map_err(|error| io::Error::new(io::ErrorKind::Other, error))?;
+        fs::create_dir_all(&self.dir)?;
+        let temporary = self.dir.join(format!(
+            "{}.{}-{}.tmp",
+            key,
+            std::process::id(),
+            NEXT_TEMPORARY_FILE.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::write(&temporary, &bytes)?;
+        fs::rename(&temporary, self.dir.join(key)).map_err(|error| {
+            let _ = fs::remove_file(&temporary);
+            error
+        })
+    }
+}
@@ -4,7 +4,13 @@
 //! Memory management for executable code.
 use crate::unwind::UnwindRegistry;
 use loupe::MemoryUsage;
+use std::mem;
+use std::ptr::{self, NonNull};
+use std::slice;
+use std::sync::Arc;
 use wasmer_compiler::{CompiledFunctionUnwindInfo, CustomSection, FunctionBody};
+#[cfg(feature = "code-integrity")]
+use wasmer_types::{entity::EntityRef, LocalFunctionIndex};
 use wasmer_vm::{Mmap, VMFunctionBody};
 
 /// The optimal alignment for functions.
@@ -18,12 +24,133 @@ const ARCH_FUNCTION_ALIGNMENT: usize = 16;
 ///
 const DATA_SECTION_ALIGNMENT: usize = 64;
 
+/// Obtains the memory of a [`CodeMemory`] and makes it executable.
+///
+/// By default the code is written to an anonymous memory map, which
+/// is made read-execute when the code is published. An allocator can
+/// get the memory elsewhere, for example from a `memfd` or from a
+/// pre-allocated pool, where anonymous executable mappings aren't
+/// allowed.
+///
+/// # Safety
+///
+/// The compiled code runs from the memory returned by
+/// [`allocate`](CodeAllocator::allocate). The memory must stay at the
+/// same address until it is deallocated, and the ranges passed to
+/// [`publish`](CodeAllocator::publish) must then be executable and
+/// keep their contents.
+pub unsafe trait CodeAllocator: Send + Sync {
+    /// Allocates `len` bytes of readable and writable memory, aligned
+    /// to the page size. `len` is a non-zero multiple of the page size.
+    fn allocate(&self, len: usize) -> Result<NonNull<u8>, String>;
+
+    /// Makes the `len` bytes at `ptr` readable and executable.
+    ///
+    /// # Safety
+    ///
+    /// The range is page aligned and within memory returned by
+    /// [`allocate`](CodeAllocator::allocate), with the code written to
+    /// it.
+    unsafe fn publish(&self, ptr: NonNull<u8>, len: usize) -> Result<(), String>;
+
+    /// Makes the `len` bytes at `ptr` readable and writable again, to
+    /// patch published code. They are published again once patched.
+    ///
+    /// The default implementation changes the protection of the pages
+    /// with `region::protect`.
+    ///
+    /// # Safety
+    ///
+    /// The range is page aligned and was published, and none of its
+    /// code runs until it is published again.
+    unsafe fn make_writable(&self, ptr: NonNull<u8>, len: usize) -> Result<(), String> {
+        region::protect(ptr.as_ptr(), len, region::Protection::READ_WRITE)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Releases the `len` bytes at `ptr`.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` and `len` are the address and length of memory returned by
+    /// [`allocate`](CodeAllocator::allocate), whose code doesn't run
+    /// anymore.
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, len: usize);
+}
+
+/// Memory obtained from a [`CodeAllocator`], released when dropped.
+struct AllocatedCode {
+    allocator: Arc<dyn CodeAllocator>,
+    ptr: NonNull<u8>,
+    len: usize,
+}
+
+// The memory is only accessed through `&mut self`, or through the
+// slices handed out by `CodeMemory::allocate`.
+unsafe impl Send for AllocatedCode {}
+unsafe impl Sync for AllocatedCode {}
+
+impl AllocatedCode {
+    fn new(allocator: Arc<dyn CodeAllocator>, len: usize) -> Result<Self, String> {
+        let ptr = if len == 0 {
+            NonNull::dangling()
+        } else {
+            allocator.allocate(len)?
+        };
+        Ok(Self {
+            allocator,
+            ptr,
+            len,
+        })
+    }
+
+    fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for AllocatedCode {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            unsafe { self.allocator.deallocate(self.ptr, self.len) };
+        }
+    }
+}
+
 /// Memory manager for executable code.
 #[derive(MemoryUsage)]
 pub struct CodeMemory {
     unwind_registry: UnwindRegistry,
+    /// The unwind registries of the previous allocations of a
+    /// reservation, each one published with its own allocation.
+    previous_unwind_registries: Vec<UnwindRegistry>,
     mmap: Mmap,
+    /// The memory, when the code is written through an alias. `mmap`
+    /// is empty then.
+    #[loupe(skip)]
+    dual_mapping: Option<DualMapping>,
+    dual_mapped: bool,
+    /// The allocator to get the memory from instead of `mmap`, see
+    /// [`CodeMemory::with_allocator`].
+    #[loupe(skip)]
+    allocator: Option<Arc<dyn CodeAllocator>>,
+    /// The memory, when it comes from `allocator`.
+    #[loupe(skip)]
+    allocated: Option<AllocatedCode>,
+    /// Whether `mmap` is a reservation that allocations are appended
+    /// to, see [`CodeMemory::with_reservation`].
+    reserved: bool,
+    /// The number of bytes of the reservation that are allocated.
+    used: usize,
+    start_of_executable_pages: usize,
     start_of_nonexecutable_pages: usize,
+    /// The address and length of the allocated functions.
+    #[cfg(feature = "code-integrity")]
+    functions: Vec<(usize, usize)>,
+    /// The checksums of the functions, computed when publishing them.
+    #[cfg(feature = "code-integrity")]
+    #[loupe(skip)]
+    checksums: Vec<blake3::Hash>,
 }
 
 impl CodeMemory {
@@ -31,23 +158,101 @@ impl CodeMemory {
     pub fn new() -> Self {
         Self {
             unwind_registry: UnwindRegistry::new(),
+            previous_unwind_registries: Vec::new(),
             mmap: Mmap::new(),
+            dual_mapping: None,
+            dual_mapped: false,
+            allocator: None,
+            allocated: None,
+            reserved: false,
+            used: 0,
+            start_of_executable_pages: 0,
             start_of_nonexecutable_pages: 0,
+            #[cfg(feature = "code-integrity")]
+            functions: Vec::new(),
+            #[cfg(feature = "code-integrity")]
+            checksums: Vec::new(),
+        }
+    }
+
+    /// Create a new `CodeMemory` instance that never maps a page both
+    /// writable and executable.
+    ///
+    /// The code is mapped read-execute from the start, and written
+    /// through a read-write alias of the same pages, which is unmapped
+    /// when the code is published. Until then, the code must be patched
+    /// through the alias, see [`CodeMemory::write_offset`].
+    ///
+    /// This is only supported on Linux: allocating fails on other
+    /// platforms.
+    pub fn new_dual_mapped() -> Self {
+        Self {
+            dual_mapped: true,
+            ..Self::new()
         }
     }
 
-    /// Mutably get the UnwindRegistry.
+    /// Create a new `CodeMemory` instance that reserves `reservation`
+    /// bytes of address space up front, and commits them as code is
+    /// allocated.
+    ///
+    /// Every call to [`CodeMemory::allocate`] appends to the
+    /// reservation, so the code allocated and published before stays
+    /// where it is: pointers to it remain valid for as long as the
+    /// `CodeMemory` is alive. Allocating fails once the reservation is
+    /// exhausted.
+    pub fn with_reservation(reservation: usize) -> Result<Self, String> {
+        let reservation = round_up(reservation, region::page::size());
+        Ok(Self {
+            mmap: Mmap::accessible_reserved(0, reservation)?,
+            reserved: true,
+            ..Self::new()
+        })
+    }
+
+    /// Create a new `CodeMemory` instance that gets its memory from
+    /// `allocator`, and makes the code executable through it when it is
+    /// published.
+    ///
+    /// Like with [`CodeMemory::new`], [`CodeMemory::allocate`] can only
+    /// be called once.
+    pub fn with_allocator(allocator: Arc<dyn CodeAllocator>) -> Self {
+        Self {
+            allocator: Some(allocator),
+            ..Self::new()
+        }
+    }
+
+    /// Returns what to add to the address of the allocated code to get
+    /// the address where it can be written, until it is published.
+    ///
+    /// This is 0 unless the `CodeMemory` is dual mapped.
+    pub fn write_offset(&self) -> usize {
+        self.dual_mapping
+            .as_ref()
+            .map_or(0, DualMapping::write_offset)
+    }
+
+    /// Mutably get the UnwindRegistry of the code allocated last.
+    ///
+    /// Each allocation of a reservation gets its own registry, so the
+    /// unwind information of every allocation can be published.
     pub fn unwind_registry_mut(&mut self) -> &mut UnwindRegistry {
         &mut self.unwind_registry
     }
 
     /// Allocate a single contiguous block of memory for the functions and custom sections, and copy the data in place.
+    ///
+    /// Unless the `CodeMemory` was created
+    /// [`with_reservation`](CodeMemory::with_reservation), this can only
+    /// be called once.
+    #[allow(clippy::type_complexity)]
     pub fn allocate(
         &mut self,
         functions: &[&FunctionBody],
         executable_sections: &[&CustomSection],
         data_sections: &[&CustomSection],
-    ) -> Result<(Vec<&mut [VMFunctionBody]>, Vec<&mut [u8]>, Vec<&mut [u8]>), String> {
+    ) -> Result<(Vec<&[VMFunctionBody]>, Vec<&[u8]>, Vec<&[u8]>), String> {
         let mut function_result = vec![];
         let mut data_section_result = vec![];
         let mut executable_section_result = vec![];
@@ -64,7 +269,7 @@ impl CodeMemory {
         // - data section body size
         // -- padding between data sections
 
-        let total_len = round_up(
+        let executable_len = round_up(
             functions.iter().fold(0, |acc, func| {
                 round_up(
                     acc + Self::function_allocation_size(func),
@@ -74,19 +279,56 @@ impl CodeMemory {
                 round_up(acc + exec.bytes.len(), ARCH_FUNCTION_ALIGNMENT)
             }),
             page_size,
-        ) + data_sections.iter().fold(0, |acc, data| {
-            round_up(acc + data.bytes.len(), DATA_SECTION_ALIGNMENT)
-        });
+        );
+        let total_len = executable_len
+            + data_sections.iter().fold(0, |acc, data| {
+                round_up(acc + data.bytes.len(), DATA_SECTION_ALIGNMENT)
+            });
 
-        // 2. Allocate the pages. Mark them all read-write.
+        // 2. Allocate the pages. Mark them all read-write, or, when dual
+        // mapped, map the executable pages read-execute and the data
+        // pages read-write, and write them all through an alias.
 
-        self.mmap = Mmap::with_at_least(total_len)?;
+        let (mut buf, write_offset) = if self.dual_mapped {
+            self.dual_mapping = Some(DualMapping::new(
+                executable_len,
+                round_up(total_len, page_size),
+            )?);
+            let mapping = self.dual_mapping.as_mut().unwrap();
+            let write_offset = mapping.write_offset();
+            (mapping.alias_mut(), write_offset)
+        } else if let Some(allocator) = &self.allocator {
+            self.allocated = Some(AllocatedCode::new(
+                allocator.clone(),
+                round_up(total_len, page_size),
+            )?);
+            (self.allocated.as_mut().unwrap().as_mut_slice(), 0)
+        } else if self.reserved {
+            let start = self.used;
+            let len = round_up(total_len, page_size);
+            if len > self.mmap.len() - start {
+                return Err(format!(
+                    "the code memory reservation of {} bytes is exhausted",
+                    self.mmap.len()
+                ));
+            }
+            self.mmap.make_accessible(start, len)?;
+            if start > 0 {
+                let previous = mem::replace(&mut self.unwind_registry, UnwindRegistry::new());
+                self.previous_unwind_registries.push(previous);
+            }
+            self.used += len;
+            self.start_of_executable_pages = start;
+            (&mut self.mmap.as_mut_slice()[start..start + len], 0)
+        } else {
+            self.mmap = Mmap::with_at_least(total_len)?;
+            (self.mmap.as_mut_slice(), 0)
+        };
 
         // 3. Determine where the pointers to each function, executable section
         // or data section are. Copy the functions. Collect the addresses of each and return them.
 
         let mut bytes = 0;
-        let mut buf = self.mmap.as_mut_slice();
         for func in functions {
             let len = round_up(
                 Self::function_allocation_size(func),
@@ -96,8 +338,12 @@ impl CodeMemory {
             buf = next_buf;
             bytes += len;
 
-            let vmfunc = Self::copy_function(&mut self.unwind_registry, func, func_buf);
+            let vmfunc =
+                Self::copy_function(&mut self.unwind_registry, func, func_buf, write_offset);
             assert_eq!(vmfunc.as_ptr() as usize % ARCH_FUNCTION_ALIGNMENT, 0);
+            #[cfg(feature = "code-integrity")]
+            self.functions
+                .push((vmfunc.as_ptr() as usize, vmfunc.len()));
             function_result.push(vmfunc);
         }
         for section in executable_sections {
@@ -108,10 +354,10 @@ impl CodeMemory {
             buf = next_buf;
             bytes += len;
             s[..section.len()].copy_from_slice(section.as_slice());
-            executable_section_result.push(s);
+            executable_section_result.push(unsafe { unaliased(s, write_offset) });
         }
 
-        self.start_of_nonexecutable_pages = bytes;
+        self.start_of_nonexecutable_pages = self.start_of_executable_pages + bytes;
 
         if !data_sections.is_empty() {
             // Data sections have different page permissions from the executable
@@ -126,7 +372,7 @@ impl CodeMemory {
                 let (s, next_buf) = buf.split_at_mut(len);
                 buf = next_buf;
                 s[..section.len()].copy_from_slice(section.as_slice());
-                data_section_result.push(s);
+                data_section_result.push(unsafe { unaliased(s, write_offset) });
             }
         }
 
@@ -139,20 +385,155 @@ impl CodeMemory {
 
     /// Apply the page permissions.
     pub fn publish(&mut self) {
-        if self.mmap.is_empty() || self.start_of_nonexecutable_pages == 0 {
+        #[cfg(feature = "code-integrity")]
+        {
+            self.checksums = self
+                .functions
+                .iter()
+                .map(|&(address, len)| Self::checksum(address, len))
+                .collect();
+        }
+        if let Some(mapping) = &mut self.dual_mapping {
+            // The code is already executable, the alias it was written
+            // through is no longer needed.
+            mapping.unmap_alias();
+            return;
+        }
+        if let Some(allocated) = &self.allocated {
+            let len = round_up(self.start_of_nonexecutable_pages, region::page::size());
+            if len > 0 {
+                unsafe { allocated.allocator.publish(allocated.ptr, len) }
+                    .expect("unable to make memory readonly and executable");
+            }
+            return;
+        }
+        if self.mmap.is_empty()
+            || self.start_of_nonexecutable_pages == self.start_of_executable_pages
+        {
             return;
         }
         assert!(self.mmap.len() >= self.start_of_nonexecutable_pages);
         unsafe {
             region::protect(
-                self.mmap.as_mut_ptr(),
-                self.start_of_nonexecutable_pages,
+                self.mmap.as_mut_ptr().add(self.start_of_executable_pages),
+                self.start_of_nonexecutable_pages - self.start_of_executable_pages,
                 region::Protection::READ_EXECUTE,
             )
         }
         .expect("unable to make memory readonly and executable");
     }
 
+    /// Checks that the bodies of the functions haven't changed since they
+    /// were published, to diagnose stray writes to the code.
+    ///
+    /// Functions are numbered in the order they were passed to
+    /// [`CodeMemory::allocate`]. Nothing is checked before the code is
+    /// published, as it is still being patched.
+    ///
+    /// # Errors
+    ///
+    /// Returns the functions whose bodies changed.
+    #[cfg(feature = "code-integrity")]
+    pub fn verify_integrity(&self) -> Result<(), Vec<LocalFunctionIndex>> {
+        let corrupted = self
+            .functions
+            .iter()
+            .zip(&self.checksums)
+            .enumerate()
+            .filter(|(_, (&(address, len), checksum))| Self::checksum(address, len) != **checksum)
+            .map(|(index, _)| LocalFunctionIndex::new(index))
+            .collect::<Vec<_>>();
+        if corrupted.is_empty() {
+            Ok(())
+        } else {
+            Err(corrupted)
+        }
+    }
+
+    /// Computes the checksum of the `len` bytes of code at `address`.
+    #[cfg(feature = "code-integrity")]
+    fn checksum(address: usize, len: usize) -> blake3::Hash {
+        // Safety: the code is readable for as long as `self` is alive.
+        blake3::hash(unsafe { slice::from_raw_parts(address as *const u8, len) })
+    }
+
+    /// Returns the address and length of the executable code allocated
+    /// last: its functions, trampolines and executable custom sections,
+    /// which are contiguous.
+    pub fn executable_code(&self) -> (usize, usize) {
+        let base = if let Some(mapping) = &self.dual_mapping {
+            mapping.memory().0
+        } else if let Some(allocated) = &self.allocated {
+            allocated.ptr.as_ptr() as usize
+        } else {
+            self.mmap.as_ptr() as usize
+        };
+        (
+            base + self.start_of_executable_pages,
+            self.start_of_nonexecutable_pages - self.start_of_executable_pages,
+        )
+    }
+
+    /// Returns whether the `len` bytes at `address` are within the
+    /// memory of this `CodeMemory`.
+    pub(crate) fn contains(&self, address: usize, len: usize) -> bool {
+        let (start, memory_len) = if let Some(mapping) = &self.dual_mapping {
+            mapping.memory()
+        } else if let Some(allocated) = &self.allocated {
+            (allocated.ptr.as_ptr() as usize, allocated.len)
+        } else {
+            (self.mmap.as_ptr() as usize, self.mmap.len())
+        };
+        address >= start && address + len <= start + memory_len
+    }
+
+    /// Overwrites the published code at `address` with `bytes`, and
+    /// flushes the instruction cache.
+    ///
+    /// When dual mapped, the code is written through a temporary
+    /// read-write alias of its pages, which stay read-execute.
+    /// Otherwise its pages are made read-write, through the allocator
+    /// if there is one, and published again once written. No page is
+    /// ever both writable and executable.
+    ///
+    /// # Safety
+    ///
+    /// `address..address + bytes.len()` must be published code of this
+    /// `CodeMemory`. Unless it is dual mapped, the pages holding it
+    /// aren't executable while it is written. They are shared with the
+    /// neighbouring functions, trampolines and executable custom
+    /// sections, so no thread may be executing any code on these pages.
+    pub(crate) unsafe fn patch(&mut self, address: *mut u8, bytes: &[u8]) -> Result<(), String> {
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        let page_size = region::page::size();
+        let start = address as usize & !(page_size - 1);
+        let len = round_up(address as usize + bytes.len(), page_size) - start;
+        if let Some(mapping) = &self.dual_mapping {
+            mapping.write(address as usize - mapping.memory().0, bytes)?;
+        } else if let Some(allocated) = &self.allocated {
+            let pages = NonNull::new_unchecked(start as *mut u8);
+            allocated
+                .allocator
+                .make_writable(pages, len)
+                .map_err(|e| format!("unable to make the code writable: {}", e))?;
+            ptr::copy_nonoverlapping(bytes.as_ptr(), address, bytes.len());
+            allocated
+                .allocator
+                .publish(pages, len)
+                .map_err(|e| format!("unable to make the code executable again: {}", e))?;
+        } else {
+            region::protect(start as *const u8, len, region::Protection::READ_WRITE)
+                .map_err(|e| format!("unable to make the code writable: {}", e))?;
+            ptr::copy_nonoverlapping(bytes.as_ptr(), address, bytes.len());
+            region::protect(start as *const u8, len, region::Protection::READ_EXECUTE)
+                .map_err(|e| format!("unable to make the code executable again: {}", e))?;
+        }
+        flush_instruction_cache(address, bytes.len());
+        Ok(())
+    }
+
     /// Calculates the allocation size of the given compiled function.
     fn function_allocation_size(func: &FunctionBody) -> usize {
         match &func.unwind_info {
@@ -166,21 +547,24 @@ impl CodeMemory {
         }
     }
 
-    /// Copies the data of the compiled function to the given buffer.
+    /// Copies the data of the compiled function to the given buffer,
+    /// located `write_offset` bytes after the address the function is
+    /// executed at.
     ///
     /// This will also add the function to the current function table.
     fn copy_function<'a>(
         registry: &mut UnwindRegistry,
         func: &FunctionBody,
         buf: &'a mut [u8],
-    ) -> &'a mut [VMFunctionBody] {
+        write_offset: usize,
+    ) -> &'a [VMFunctionBody] {
         assert_eq!(buf.as_ptr() as usize % ARCH_FUNCTION_ALIGNMENT, 0);
 
         let func_len = func.body.len();
 
         let (body, remainder) = buf.split_at_mut(func_len);
         body.copy_from_slice(&func.body);
-        let vmfunc = Self::view_as_mut_vmfunc_slice(body);
+        let vmfunc = Self::view_as_vmfunc_slice(unsafe { unaliased(body, write_offset) });
 
         if let Some(CompiledFunctionUnwindInfo::WindowsX64(info)) = &func.unwind_info {
             // Windows unwind information is written following the function body
@@ -202,12 +586,27 @@ impl CodeMemory {
         vmfunc
     }
 
-    /// Convert mut a slice from u8 to VMFunctionBody.
-    fn view_as_mut_vmfunc_slice(slice: &mut [u8]) -> &mut [VMFunctionBody] {
-        let byte_ptr: *mut [u8] = slice;
-        let body_ptr = byte_ptr as *mut [VMFunctionBody];
-        unsafe { &mut *body_ptr }
+    /// Convert a slice from u8 to VMFunctionBody.
+    fn view_as_vmfunc_slice(slice: &[u8]) -> &[VMFunctionBody] {
+        let byte_ptr: *const [u8] = slice;
+        let body_ptr = byte_ptr as *const [VMFunctionBody];
+        unsafe { &*body_ptr }
+    }
+}
+
+/// Makes sure the instructions at `address` are fetched again after
+/// they were modified.
+unsafe fn flush_instruction_cache(address: *mut u8, len: usize) {
+    #[cfg(target_arch = "aarch64")]
+    {
+        extern "C" {
+            fn __clear_cache(start: *mut std::os::raw::c_char, end: *mut std::os::raw::c_char);
+        }
+        __clear_cache(address as *mut _, address.add(len) as *mut _);
     }
+    // The instruction cache is coherent with the data cache on x86.
+    #[cfg(not(target_arch = "aarch64"))]
+    let _ = (address, len);
 }
 
 fn round_up(size: usize, multiple: usize) -> usize {
@@ -215,6 +614,209 @@ fn round_up(size: usize, multiple: usize) -> usize {
     (size + (multiple - 1)) & !(multiple - 1)
 }
 
+/// Returns the slice `write_offset` bytes before `slice`, where the
+/// memory written through `slice` is mapped for execution.
+///
+/// # Safety
+///
+/// The memory must be mapped there for as long as `slice` is borrowed.
+unsafe fn unaliased<'a>(slice: &'a [u8], write_offset: usize) -> &'a [u8] {
+    slice::from_raw_parts(
+        (slice.as_ptr() as usize).wrapping_sub(write_offset) as *const u8,
+        slice.len(),
+    )
+}
+
+/// Memory mapped twice: once where the code runs, the executable pages
+/// being read-execute and the data pages read-write, and once
+/// read-write, as an alias to write the code through.
+///
+/// The file backing them stays open, to patch the code through a new
+/// alias once the first one is unmapped.
+#[cfg(target_os = "linux")]
+struct DualMapping {
+    memory: *mut u8,
+    alias: *mut u8,
+    len: usize,
+    fd: libc::c_int,
+}
+
+// The mappings are only accessed through `&mut self`, or through the
+// slices handed out by `CodeMemory::allocate`.
+#[cfg(target_os = "linux")]
+unsafe impl Send for DualMapping {}
+#[cfg(target_os = "linux")]
+unsafe impl Sync for DualMapping {}
+
+#[cfg(target_os = "linux")]
+impl DualMapping {
+    /// Maps `len` bytes, the first `executable_len` of which are
+    /// executable. Both must be multiples of the page size.
+    fn new(executable_len: usize, len: usize) -> Result<Self, String> {
+        let mut mapping = Self {
+            memory: std::ptr::null_mut(),
+            alias: std::ptr::null_mut(),
+            len,
+            fd: -1,
+        };
+        if len == 0 {
+            return Ok(mapping);
+        }
+        let last_error = || std::io::Error::last_os_error().to_string();
+        unsafe {
+            let fd = libc::memfd_create(
+                b"wasmer-code\0".as_ptr() as *const libc::c_char,
+                libc::MFD_CLOEXEC,
+            );
+            if fd < 0 {
+                return Err(last_error());
+            }
+            mapping.fd = fd;
+            let result = (|| {
+                if libc::ftruncate(fd, len as libc::off_t) != 0 {
+                    return Err(last_error());
+                }
+                mapping.alias = Self::map(fd, len, libc::PROT_READ | libc::PROT_WRITE)?;
+                mapping.memory = Self::map(fd, len, libc::PROT_READ)?;
+                let data_len = len - executable_len;
+                if (executable_len > 0
+                    && libc::mprotect(
+                        mapping.memory as *mut libc::c_void,
+                        executable_len,
+                        libc::PROT_READ | libc::PROT_EXEC,
+                    ) != 0)
+                    || (data_len > 0
+                        && libc::mprotect(
+                            mapping.memory.add(executable_len) as *mut libc::c_void,
+                            data_len,
+                            libc::PROT_READ | libc::PROT_WRITE,
+                        ) != 0)
+                {
+                    return Err(last_error());
+                }
+                Ok(())
+            })();
+            // Dropping the mapping on failure closes the file.
+            result.map(|()| mapping)
+        }
+    }
+
+    unsafe fn map(fd: libc::c_int, len: usize, protection: libc::c_int) -> Result<*mut u8, String> {
+        let ptr = libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            protection,
+            libc::MAP_SHARED,
+            fd,
+            0,
+        );
+        if ptr == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().to_string());
+        }
+        Ok(ptr as *mut u8)
+    }
+
+    /// Returns the address and length of the memory the code runs from.
+    fn memory(&self) -> (usize, usize) {
+        (self.memory as usize, self.len)
+    }
+
+    /// Writes `bytes` at `offset` through a temporary read-write alias
+    /// of the pages holding them.
+    unsafe fn write(&self, offset: usize, bytes: &[u8]) -> Result<(), String> {
+        let page_size = region::page::size();
+        let start = offset & !(page_size - 1);
+        let len = round_up(offset + bytes.len(), page_size) - start;
+        let alias = libc::mmap(
+            std::ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            self.fd,
+            start as libc::off_t,
+        );
+        if alias == libc::MAP_FAILED {
+            return Err(format!(
+                "unable to map the code writable: {}",
+                std::io::Error::last_os_error()
+            ));
+        }
+        ptr::copy_nonoverlapping(
+            bytes.as_ptr(),
+            (alias as *mut u8).add(offset - start),
+            bytes.len(),
+        );
+        libc::munmap(alias, len);
+        Ok(())
+    }
+
+    fn write_offset(&self) -> usize {
+        if self.alias.is_null() {
+            return 0;
+        }
+        (self.alias as usize).wrapping_sub(self.memory as usize)
+    }
+
+    fn alias_mut(&mut self) -> &mut [u8] {
+        if self.alias.is_null() {
+            return &mut [];
+        }
+        unsafe { slice::from_raw_parts_mut(self.alias, self.len) }
+    }
+
+    fn unmap_alias(&mut self) {
+        if !self.alias.is_null() {
+            unsafe { libc::munmap(self.alias as *mut libc::c_void, self.len) };
+            self.alias = std::ptr::null_mut();
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for DualMapping {
+    fn drop(&mut self) {
+        self.unmap_alias();
+        if !self.memory.is_null() {
+            unsafe { libc::munmap(self.memory as *mut libc::c_void, self.len) };
+        }
+        if self.fd >= 0 {
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+/// Dual mapping is only implemented on Linux, so it can't be created
+/// elsewhere.
+#[cfg(not(target_os = "linux"))]
+enum DualMapping {}
+
+#[cfg(not(target_os = "linux"))]
+impl DualMapping {
+    fn new(_executable_len: usize, _len: usize) -> Result<Self, String> {
+        Err("dual mapped code memory is only supported on Linux".to_string())
+    }
+
+    fn memory(&self) -> (usize, usize) {
+        match *self {}
+    }
+
+    unsafe fn write(&self, _offset: usize, _bytes: &[u8]) -> Result<(), String> {
+        match *self {}
+    }
+
+    fn write_offset(&self) -> usize {
+        match *self {}
+    }
+
+    fn alias_mut(&mut self) -> &mut [u8] {
+        match *self {}
+    }
+
+    fn unmap_alias(&mut self) {
+        match *self {}
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::CodeMemory;
@@ -222,4 +824,240 @@ mod tests {
         fn _assert_send_sync<T: Send + Sync>() {}
         _assert_send_sync::<CodeMemory>();
     }
+
+    /// Returns the permissions of the mapping containing `address`, as
+    /// listed in `/proc/self/maps`.
+    #[cfg(target_os = "linux")]
+    fn permissions(address: usize) -> Option<String> {
+        let maps = std::fs::read_to_string("/proc/self/maps").unwrap();
+        maps.lines().find_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mut range = fields.next()?.split('-');
+            let start = usize::from_str_radix(range.next()?, 16).ok()?;
+            let end = usize::from_str_radix(range.next()?, 16).ok()?;
+            let permissions = fields.next()?;
+            if (start..end).contains(&address) {
+                Some(permissions.to_string())
+            } else {
+                None
+            }
+        })
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn dual_mapped_code_is_never_writable() {
+        use wasmer_compiler::{CustomSection, CustomSectionProtection, FunctionBody, SectionBody};
+
+        let function = FunctionBody {
+            body: vec![0xc3; 32],
+            unwind_info: None,
+        };
+        let data = CustomSection {
+            protection: CustomSectionProtection::Read,
+            bytes: SectionBody::new_with_vec(vec![7; 16]),
+            relocations: vec![],
+        };
+        let mut code_memory = CodeMemory::new_dual_mapped();
+        let (function, data) = {
+            let (functions, _, data_sections) =
+                code_memory.allocate(&[&function], &[], &[&data]).unwrap();
+            (
+                functions[0].as_ptr() as usize,
+                data_sections[0].as_ptr() as usize,
+            )
+        };
+        let alias = function.wrapping_add(code_memory.write_offset());
+        assert_ne!(alias, function);
+
+        // The code is executable and not writable even before being
+        // published, and it's written through an alias that is never
+        // executable.
+        assert_eq!(permissions(function).unwrap(), "r-xs");
+        assert_eq!(permissions(alias).unwrap(), "rw-s");
+        assert_eq!(unsafe { *(function as *const u8) }, 0xc3);
+        assert_eq!(permissions(data).unwrap(), "rw-s");
+        assert_eq!(unsafe { *(data as *const u8) }, 7);
+
+        code_memory.publish();
+        assert_eq!(permissions(function).unwrap(), "r-xs");
+        assert_eq!(permissions(alias), None);
+        assert_eq!(code_memory.write_offset(), 0);
+
+        // Patching the published code goes through a temporary alias,
+        // the code stays executable and not writable.
+        unsafe { code_memory.patch(function as *mut u8, &[0x90; 4]) }.unwrap();
+        assert_eq!(
+            unsafe { *(function as *const [u8; 5]) },
+            [0x90, 0x90, 0x90, 0x90, 0xc3]
+        );
+        assert_eq!(permissions(function).unwrap(), "r-xs");
+        assert!(code_memory.contains(function, 32));
+        assert!(!code_memory.contains(data + 4096, 1));
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn reserved_code_memory_keeps_published_code_in_place() {
+        use wasmer_compiler::FunctionBody;
+
+        // `mov eax, 42; ret`
+        let answer = FunctionBody {
+            body: vec![0xb8, 42, 0, 0, 0, 0xc3],
+            unwind_info: None,
+        };
+        let filler = FunctionBody {
+            body: vec![0xc3; 4096],
+            unwind_info: None,
+        };
+        let page_size = region::page::size();
+        let mut code_memory = CodeMemory::with_reservation(64 * page_size).unwrap();
+        let first = code_memory.allocate(&[&answer], &[], &[]).unwrap().0[0].as_ptr();
+        code_memory.publish();
+
+        let mut last = first;
+        for _ in 0..32 {
+            let next = code_memory.allocate(&[&filler], &[], &[]).unwrap().0[0].as_ptr();
+            code_memory.publish();
+            assert!(next > last);
+            last = next;
+        }
+
+        let answer: extern "C" fn() -> i32 = unsafe { std::mem::transmute(first) };
+        assert_eq!(answer(), 42);
+
+        // The reservation is exhausted, the code stays in place.
+        let too_large = FunctionBody {
+            body: vec![0xc3; 64 * page_size],
+            unwind_info: None,
+        };
+        assert!(code_memory.allocate(&[&too_large], &[], &[]).is_err());
+        assert_eq!(answer(), 42);
+    }
+
+    #[test]
+    fn reserved_code_memory_publishes_unwind_info_per_allocation() {
+        use wasmer_compiler::FunctionBody;
+
+        let function = FunctionBody {
+            body: vec![0xc3],
+            unwind_info: None,
+        };
+        let mut code_memory = CodeMemory::with_reservation(4 * region::page::size()).unwrap();
+        for _ in 0..2 {
+            code_memory.allocate(&[&function], &[], &[]).unwrap();
+            code_memory.publish();
+            code_memory.unwind_registry_mut().publish(None).unwrap();
+        }
+        assert_eq!(code_memory.previous_unwind_registries.len(), 1);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn code_allocator_provides_and_publishes_the_code() {
+        use super::CodeAllocator;
+        use std::ptr::NonNull;
+        use std::sync::{Arc, Mutex};
+        use wasmer_compiler::FunctionBody;
+        use wasmer_vm::Mmap;
+
+        #[derive(Debug, PartialEq)]
+        enum Request {
+            Allocate(usize),
+            Publish(usize),
+            Deallocate(usize),
+        }
+
+        /// Allocates memory maps, and records the requests.
+        #[derive(Default)]
+        struct RecordingAllocator {
+            maps: Mutex<Vec<Mmap>>,
+            requests: Mutex<Vec<Request>>,
+        }
+
+        unsafe impl CodeAllocator for RecordingAllocator {
+            fn allocate(&self, len: usize) -> Result<NonNull<u8>, String> {
+                self.requests.lock().unwrap().push(Request::Allocate(len));
+                let mut mmap = Mmap::with_at_least(len)?;
+                let ptr = NonNull::new(mmap.as_mut_ptr()).unwrap();
+                self.maps.lock().unwrap().push(mmap);
+                Ok(ptr)
+            }
+
+            unsafe fn publish(&self, ptr: NonNull<u8>, len: usize) -> Result<(), String> {
+                self.requests.lock().unwrap().push(Request::Publish(len));
+                region::protect(ptr.as_ptr(), len, region::Protection::READ_EXECUTE)
+                    .map_err(|e| e.to_string())
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, len: usize) {
+                self.requests.lock().unwrap().push(Request::Deallocate(len));
+                self.maps
+                    .lock()
+                    .unwrap()
+                    .retain(|mmap| mmap.as_ptr() != ptr.as_ptr() as *const u8);
+            }
+        }
+
+        // `mov eax, 42; ret`
+        let answer = FunctionBody {
+            body: vec![0xb8, 42, 0, 0, 0, 0xc3],
+            unwind_info: None,
+        };
+        let page_size = region::page::size();
+        let allocator = Arc::new(RecordingAllocator::default());
+        let mut code_memory = CodeMemory::with_allocator(allocator.clone());
+        let function = code_memory.allocate(&[&answer], &[], &[]).unwrap().0[0].as_ptr();
+        code_memory.publish();
+
+        let answer: extern "C" fn() -> i32 = unsafe { std::mem::transmute(function) };
+        assert_eq!(answer(), 42);
+        assert!(allocator.maps.lock().unwrap()[0]
+            .as_slice()
+            .as_ptr_range()
+            .contains(&(function as *const u8)));
+
+        drop(code_memory);
+        assert_eq!(
+            *allocator.requests.lock().unwrap(),
+            vec![
+                Request::Allocate(page_size),
+                Request::Publish(page_size),
+                Request::Deallocate(page_size),
+            ]
+        );
+        assert!(allocator.maps.lock().unwrap().is_empty());
+    }
+
+    #[cfg(feature = "code-integrity")]
+    #[test]
+    fn verify_integrity_reports_clobbered_functions() {
+        use wasmer_compiler::FunctionBody;
+        use wasmer_types::{entity::EntityRef, LocalFunctionIndex};
+
+        let functions = (0..3)
+            .map(|_| FunctionBody {
+                body: vec![0xc3; 32],
+                unwind_info: None,
+            })
+            .collect::<Vec<_>>();
+        let mut code_memory = CodeMemory::new();
+        let second = {
+            let (allocated, _, _) = code_memory
+                .allocate(&functions.iter().collect::<Vec<_>>(), &[], &[])
+                .unwrap();
+            allocated[1].as_ptr() as *mut u8
+        };
+        code_memory.publish();
+        assert_eq!(code_memory.verify_integrity(), Ok(()));
+
+        unsafe {
+            region::protect(second, 1, region::Protection::READ_WRITE_EXECUTE).unwrap();
+            *second.add(7) = 0x90;
+        }
+        assert_eq!(
+            code_memory.verify_integrity(),
+            Err(vec![LocalFunctionIndex::new(1)])
+        );
+    }
 }
@@ -1,22 +1,40 @@
 //! Universal compilation.
 
-use crate::{CodeMemory, UniversalArtifact};
+#[cfg(feature = "compiler")]
+use crate::analysis::ModuleDiagnostics;
+#[cfg(feature = "compiler")]
+use crate::cache::ArtifactCache;
+use crate::serialize::OMITTED_TRAMPOLINE;
+use crate::{CodeAllocator, CodeMemory, LibCallTable, RelocationRewriter, UniversalArtifact};
 use loupe::MemoryUsage;
+#[cfg(feature = "compiler")]
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 #[cfg(feature = "compiler")]
-use wasmer_compiler::Compiler;
+use std::time::Duration;
+#[cfg(feature = "compiler")]
+use wasmer_compiler::{CompilationLimits, Compiler, ModuleEnvironment};
 use wasmer_compiler::{
     CompileError, CustomSection, CustomSectionProtection, FunctionBody, SectionIndex, Target,
 };
-use wasmer_engine::{Artifact, DeserializeError, Engine, EngineId, FunctionExtent, Tunables};
+use wasmer_engine::{
+    Artifact, DeserializeError, Engine, EngineId, FunctionExtent, RuntimeError, Tunables,
+};
 use wasmer_types::entity::PrimaryMap;
 use wasmer_types::Features;
 use wasmer_types::{FunctionIndex, FunctionType, LocalFunctionIndex, SignatureIndex};
 use wasmer_vm::{
-    FuncDataRegistry, FunctionBodyPtr, ModuleInfo, SectionBodyPtr, SignatureRegistry,
-    VMCallerCheckedAnyfunc, VMFuncRef, VMFunctionBody, VMSharedSignatureIndex, VMTrampoline,
+    raise_user_trap, FuncDataRegistry, FunctionBodyPtr, ModuleInfo, SectionBodyPtr,
+    SignatureRegistry, VMCallerCheckedAnyfunc, VMContext, VMFuncRef, VMFunctionBody,
+    VMSharedSignatureIndex, VMTrampoline,
 };
 
+/// The callback reporting the progress of a compilation, called with
+/// the number of functions compiled so far and the total number of
+/// functions.
+#[cfg(feature = "compiler")]
+pub(crate) type CompileProgressCallback = dyn Fn(usize, usize) + Send + Sync;
+
 /// A WebAssembly `Universal` Engine.
 #[derive(Clone, MemoryUsage)]
 pub struct UniversalEngine {
@@ -24,6 +42,10 @@ pub struct UniversalEngine {
     /// The target for the compiler
     target: Arc<Target>,
     engine_id: EngineId,
+    /// The on-disk cache of compiled artifacts, if any
+    #[cfg(feature = "compiler")]
+    #[loupe(skip)]
+    cache: Option<Arc<ArtifactCache>>,
 }
 
 impl UniversalEngine {
@@ -34,15 +56,33 @@ impl UniversalEngine {
             inner: Arc::new(Mutex::new(UniversalEngineInner {
                 compiler: Some(compiler),
                 code_memory: vec![],
-                signatures: SignatureRegistry::new(),
+                dual_mapped_code: false,
+                code_allocator: None,
+                debug_info: true,
+                source_hash: false,
+                source_lines: false,
+                artifact_checksum: false,
+                exported_trampolines_only: false,
+                compilation_limits: CompilationLimits::default(),
+                compile_progress: None,
+                compile_deadline: None,
+                libcalls: LibCallTable::new(),
+                relocation_rewriter: None,
+                signatures: Arc::new(SignatureRegistry::new()),
                 func_data: Arc::new(FuncDataRegistry::new()),
                 features,
             })),
             target: Arc::new(target),
             engine_id: EngineId::default(),
+            cache: None,
         }
     }
 
+    /// Cache the compiled artifacts in `dir`.
+    pub(crate) fn set_cache_dir(&mut self, dir: PathBuf) {
+        self.cache = Some(Arc::new(ArtifactCache::new(dir)));
+    }
+
     /// Create a headless `UniversalEngine`
     ///
     /// A headless engine is an engine without any compiler attached.
@@ -62,15 +102,160 @@ impl UniversalEngine {
                 #[cfg(feature = "compiler")]
                 compiler: None,
                 code_memory: vec![],
-                signatures: SignatureRegistry::new(),
+                dual_mapped_code: false,
+                code_allocator: None,
+                #[cfg(feature = "compiler")]
+                debug_info: true,
+                #[cfg(feature = "compiler")]
+                source_hash: false,
+                source_lines: false,
+                artifact_checksum: false,
+                #[cfg(feature = "compiler")]
+                exported_trampolines_only: false,
+                #[cfg(feature = "compiler")]
+                compilation_limits: CompilationLimits::default(),
+                #[cfg(feature = "compiler")]
+                compile_progress: None,
+                #[cfg(feature = "compiler")]
+                compile_deadline: None,
+                libcalls: LibCallTable::new(),
+                relocation_rewriter: None,
+                signatures: Arc::new(SignatureRegistry::new()),
                 func_data: Arc::new(FuncDataRegistry::new()),
                 features: Features::default(),
             })),
             target: Arc::new(Target::default()),
             engine_id: EngineId::default(),
+            #[cfg(feature = "compiler")]
+            cache: None,
         }
     }
 
+    /// Returns whether this engine is headless, that is, whether it
+    /// has no compiler and can only load serialized modules.
+    ///
+    /// See [`UniversalEngine::headless`].
+    pub fn is_headless(&self) -> bool {
+        #[cfg(feature = "compiler")]
+        {
+            self.inner().compiler.is_none()
+        }
+        #[cfg(not(feature = "compiler"))]
+        {
+            true
+        }
+    }
+
+    /// Validate and translate a WebAssembly binary, and report
+    /// diagnostics about its functions, without compiling them.
+    ///
+    /// No code is generated nor published: this is meant for tools
+    /// that want to check modules cheaply.
+    #[cfg(feature = "compiler")]
+    pub fn analyze(&self, binary: &[u8]) -> Result<ModuleDiagnostics, CompileError> {
+        self.inner().validate(binary)?;
+        let translation = ModuleEnvironment::new()
+            .translate(binary)
+            .map_err(CompileError::Wasm)?;
+        ModuleDiagnostics::new(&translation)
+    }
+
+    /// Map the compiled code read-execute from the start, and write it
+    /// through a separate alias, so that it is never writable.
+    ///
+    /// See [`CodeMemory::new_dual_mapped`].
+    pub(crate) fn set_dual_mapped_code(&self, enable: bool) {
+        self.inner_mut().dual_mapped_code = enable;
+    }
+
+    /// Get the memory of the code from `allocator`.
+    ///
+    /// See [`CodeMemory::with_allocator`].
+    pub(crate) fn set_code_allocator(&self, allocator: Option<Arc<dyn CodeAllocator>>) {
+        self.inner_mut().code_allocator = allocator;
+    }
+
+    /// Keep the address maps of the functions compiled from now on.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn set_debug_info(&self, enable: bool) {
+        self.inner_mut().debug_info = enable;
+    }
+
+    /// Record the hash of the wasm binary of the modules compiled from
+    /// now on.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn set_source_hash(&self, enable: bool) {
+        self.inner_mut().source_hash = enable;
+    }
+
+    /// Read the source lines of the artifacts created from now on, to
+    /// report them in the frames of traps.
+    pub(crate) fn set_source_lines(&self, enable: bool) {
+        self.inner_mut().source_lines = enable;
+    }
+
+    /// Make the modules compiled from now on carry a checksum when
+    /// serialized, and require one from the modules deserialized.
+    pub(crate) fn set_artifact_checksum(&self, enable: bool) {
+        self.inner_mut().artifact_checksum = enable;
+    }
+
+    /// Only keep the call trampolines of the signatures of the exported
+    /// functions in the modules compiled from now on.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn set_exported_trampolines_only(&self, enable: bool) {
+        self.inner_mut().exported_trampolines_only = enable;
+    }
+
+    /// Reject the modules exceeding `limits` before compiling them.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn set_compilation_limits(&self, limits: CompilationLimits) {
+        self.inner_mut().compilation_limits = limits;
+    }
+
+    /// Report the progress of the compilation of the functions of the
+    /// modules compiled from now on to `callback`.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn set_compile_progress(&self, callback: Option<Arc<CompileProgressCallback>>) {
+        self.inner_mut().compile_progress = callback;
+    }
+
+    /// Give up the compilations started from now on once `timeout` has
+    /// elapsed.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn set_compile_deadline(&self, timeout: Option<Duration>) {
+        self.inner_mut().compile_deadline = timeout;
+    }
+
+    /// Link the artifacts created from now on against `libcalls`.
+    pub(crate) fn set_libcalls(&self, libcalls: LibCallTable) {
+        self.inner_mut().libcalls = libcalls;
+    }
+
+    /// Pass the relocation targets of the artifacts created from now on
+    /// through `rewriter`.
+    pub(crate) fn set_relocation_rewriter(&self, rewriter: Option<Arc<dyn RelocationRewriter>>) {
+        self.inner_mut().relocation_rewriter = rewriter;
+    }
+
+    /// Register the signatures in `registry`, which other engines may
+    /// share.
+    ///
+    /// This must be done before any signature is registered, as the
+    /// indices handed out by the previous registry become meaningless.
+    pub(crate) fn set_signature_registry(&self, registry: Arc<SignatureRegistry>) {
+        self.inner_mut().signatures = registry;
+    }
+
+    /// Returns the registry of the signatures of this engine.
+    ///
+    /// Passing it to
+    /// [`Universal::signature_registry`](crate::Universal::signature_registry)
+    /// builds an engine sharing the signature indices of this one.
+    pub fn signature_registry(&self) -> Arc<SignatureRegistry> {
+        self.inner().signatures().clone()
+    }
+
     pub(crate) fn inner(&self) -> std::sync::MutexGuard<'_, UniversalEngineInner> {
         self.inner.lock().unwrap()
     }
@@ -92,6 +277,12 @@ impl Engine for UniversalEngine {
         compiler.signatures().register(func_type)
     }
 
+    /// Register many signatures at once
+    fn register_signatures(&self, func_types: &[FunctionType]) -> Vec<VMSharedSignatureIndex> {
+        let compiler = self.inner();
+        compiler.signatures().register_many(func_types)
+    }
+
     fn register_function_metadata(&self, func_data: VMCallerCheckedAnyfunc) -> VMFuncRef {
         let compiler = self.inner();
         compiler.func_data().register(func_data)
@@ -103,6 +294,10 @@ impl Engine for UniversalEngine {
         compiler.signatures().lookup(sig)
     }
 
+    fn signature_count(&self) -> usize {
+        self.inner().signatures().len()
+    }
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {
         self.inner().validate(binary)
@@ -115,7 +310,18 @@ impl Engine for UniversalEngine {
         binary: &[u8],
         tunables: &dyn Tunables,
     ) -> Result<Arc<dyn Artifact>, CompileError> {
-        Ok(Arc::new(UniversalArtifact::new(&self, binary, tunables)?))
+        let cache = match &self.cache {
+            Some(cache) => cache,
+            None => return Ok(Arc::new(UniversalArtifact::new(&self, binary, tunables)?)),
+        };
+        let key = cache.key(binary, &self.target, &self.inner())?;
+        if let Some(artifact) = cache.load(&self, &key, tunables) {
+            return Ok(Arc::new(artifact));
+        }
+        let artifact = UniversalArtifact::new(&self, binary, tunables)?;
+        // The module compiled fine, so failing to cache it is not an error.
+        let _ = cache.store(&key, &artifact);
+        Ok(Arc::new(artifact))
     }
 
     /// Compile a WebAssembly binary
@@ -156,9 +362,47 @@ pub struct UniversalEngineInner {
     /// The code memory is responsible of publishing the compiled
     /// functions to memory.
     code_memory: Vec<CodeMemory>,
+    /// Whether the code memory is dual mapped.
+    dual_mapped_code: bool,
+    /// The allocator to get the code memory from, if any.
+    #[loupe(skip)]
+    code_allocator: Option<Arc<dyn CodeAllocator>>,
+    /// Whether compiled artifacts keep their address maps.
+    #[cfg(feature = "compiler")]
+    debug_info: bool,
+    /// Whether compiled artifacts record the hash of their wasm binary.
+    #[cfg(feature = "compiler")]
+    source_hash: bool,
+    /// Whether artifacts read the source lines of their module.
+    source_lines: bool,
+    /// Whether compiled artifacts carry a checksum when serialized,
+    /// and deserialized artifacts are required to carry one.
+    artifact_checksum: bool,
+    /// Whether compiled artifacts only keep the call trampolines of the
+    /// signatures of their exported functions.
+    #[cfg(feature = "compiler")]
+    exported_trampolines_only: bool,
+    /// The limits on the structure of the modules to compile.
+    #[cfg(feature = "compiler")]
+    #[loupe(skip)]
+    compilation_limits: CompilationLimits,
+    /// The callback reporting the progress of compilations, if any.
+    #[cfg(feature = "compiler")]
+    #[loupe(skip)]
+    compile_progress: Option<Arc<CompileProgressCallback>>,
+    /// How long a compilation may run before it is abandoned, if bounded.
+    #[cfg(feature = "compiler")]
+    #[loupe(skip)]
+    compile_deadline: Option<Duration>,
+    /// The addresses the libcalls of the artifacts resolve to.
+    #[loupe(skip)]
+    libcalls: LibCallTable,
+    /// The hook rewriting the relocation targets, if any.
+    #[loupe(skip)]
+    relocation_rewriter: Option<Arc<dyn RelocationRewriter>>,
     /// The signature registry is used mainly to operate with trampolines
     /// performantly.
-    signatures: SignatureRegistry,
+    signatures: Arc<SignatureRegistry>,
     /// The backing storage of `VMFuncRef`s. This centralized store ensures that 2
     /// functions with the same `VMCallerCheckedAnyfunc` will have the same `VMFuncRef`.
     /// It also guarantees that the `VMFuncRef`s stay valid until the engine is dropped.
@@ -195,13 +439,72 @@ impl UniversalEngineInner {
         &self.features
     }
 
+    /// Whether compiled artifacts keep their address maps.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn debug_info(&self) -> bool {
+        self.debug_info
+    }
+
+    /// Whether compiled artifacts record the hash of their wasm binary.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn source_hash(&self) -> bool {
+        self.source_hash
+    }
+
+    /// Whether artifacts read the source lines of their module.
+    pub(crate) fn source_lines(&self) -> bool {
+        self.source_lines
+    }
+
+    /// Whether compiled artifacts carry a checksum when serialized,
+    /// and deserialized artifacts are required to carry one.
+    pub(crate) fn artifact_checksum(&self) -> bool {
+        self.artifact_checksum
+    }
+
+    /// Whether compiled artifacts only keep the call trampolines of the
+    /// signatures of their exported functions.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn exported_trampolines_only(&self) -> bool {
+        self.exported_trampolines_only
+    }
+
+    /// The limits on the structure of the modules to compile.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn compilation_limits(&self) -> &CompilationLimits {
+        &self.compilation_limits
+    }
+
+    /// The callback reporting the progress of compilations, if any.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn compile_progress(&self) -> Option<Arc<CompileProgressCallback>> {
+        self.compile_progress.clone()
+    }
+
+    /// How long a compilation may run before it is abandoned, if bounded.
+    #[cfg(feature = "compiler")]
+    pub(crate) fn compile_deadline(&self) -> Option<Duration> {
+        self.compile_deadline
+    }
+
+    /// The addresses the libcalls of the artifacts resolve to.
+    pub(crate) fn libcalls(&self) -> &LibCallTable {
+        &self.libcalls
+    }
+
+    /// The hook rewriting the relocation targets, if any.
+    pub(crate) fn relocation_rewriter(&self) -> Option<&dyn RelocationRewriter> {
+        self.relocation_rewriter.as_deref()
+    }
+
     /// Allocate compiled functions into memory
     #[allow(clippy::type_complexity)]
     pub(crate) fn allocate(
         &mut self,
         _module: &ModuleInfo,
         functions: &PrimaryMap<LocalFunctionIndex, FunctionBody>,
-        function_call_trampolines: &PrimaryMap<SignatureIndex, FunctionBody>,
+        function_call_trampolines: &[FunctionBody],
+        function_call_trampoline_indices: &PrimaryMap<SignatureIndex, u32>,
         dynamic_function_trampolines: &PrimaryMap<FunctionIndex, FunctionBody>,
         custom_sections: &PrimaryMap<SectionIndex, CustomSection>,
     ) -> Result<
@@ -215,13 +518,20 @@ impl UniversalEngineInner {
     > {
         let function_bodies = functions
             .values()
-            .chain(function_call_trampolines.values())
+            .chain(function_call_trampolines.iter())
             .chain(dynamic_function_trampolines.values())
             .collect::<Vec<_>>();
         let (executable_sections, data_sections): (Vec<_>, _) = custom_sections
             .values()
             .partition(|section| section.protection == CustomSectionProtection::ReadExecute);
-        self.code_memory.push(CodeMemory::new());
+        self.code_memory
+            .push(if let Some(allocator) = &self.code_allocator {
+                CodeMemory::with_allocator(allocator.clone())
+            } else if self.dual_mapped_code {
+                CodeMemory::new_dual_mapped()
+            } else {
+                CodeMemory::new()
+            });
 
         let (mut allocated_functions, allocated_executable_sections, allocated_data_sections) =
             self.code_memory
@@ -247,16 +557,20 @@ impl UniversalEngineInner {
             })
             .collect::<PrimaryMap<LocalFunctionIndex, _>>();
 
-        let mut allocated_function_call_trampolines: PrimaryMap<SignatureIndex, VMTrampoline> =
-            PrimaryMap::new();
-        for ptr in allocated_functions
+        let distinct_function_call_trampolines = allocated_functions
             .drain(0..function_call_trampolines.len())
-            .map(|slice| slice.as_ptr())
-        {
-            let trampoline =
-                unsafe { std::mem::transmute::<*const VMFunctionBody, VMTrampoline>(ptr) };
-            allocated_function_call_trampolines.push(trampoline);
-        }
+            .map(|slice| unsafe {
+                std::mem::transmute::<*const VMFunctionBody, VMTrampoline>(slice.as_ptr())
+            })
+            .collect::<Vec<_>>();
+        // Signatures that are structurally equal share the same trampoline.
+        let allocated_function_call_trampolines = function_call_trampoline_indices
+            .values()
+            .map(|index| match *index {
+                OMITTED_TRAMPOLINE => omitted_trampoline as VMTrampoline,
+                index => distinct_function_call_trampolines[index as usize],
+            })
+            .collect::<PrimaryMap<SignatureIndex, VMTrampoline>>();
 
         let allocated_dynamic_function_trampolines = allocated_functions
             .drain(..)
@@ -288,6 +602,38 @@ impl UniversalEngineInner {
         ))
     }
 
+    /// Returns what to add to the address of the code allocated last to
+    /// get the address where it can be patched, until it is published.
+    pub(crate) fn code_write_offset(&self) -> usize {
+        self.code_memory.last().map_or(0, CodeMemory::write_offset)
+    }
+
+    /// Returns the address and length of the executable code allocated
+    /// last, see [`CodeMemory::executable_code`].
+    pub(crate) fn executable_code(&self) -> (usize, usize) {
+        self.code_memory
+            .last()
+            .map_or((0, 0), CodeMemory::executable_code)
+    }
+
+    /// Overwrites the published code at `address` with `bytes`, through
+    /// the code memory holding it, see [`CodeMemory::patch`].
+    ///
+    /// # Safety
+    ///
+    /// See [`CodeMemory::patch`].
+    pub(crate) unsafe fn patch_code(
+        &mut self,
+        address: *mut u8,
+        bytes: &[u8],
+    ) -> Result<(), String> {
+        self.code_memory
+            .iter_mut()
+            .find(|memory| memory.contains(address as usize, bytes.len()))
+            .ok_or_else(|| "the code wasn't allocated by this engine".to_string())?
+            .patch(address, bytes)
+    }
+
     /// Make memory containing compiled code executable.
     pub(crate) fn publish_compiled_code(&mut self) {
         self.code_memory.last_mut().unwrap().publish();
@@ -307,7 +653,7 @@ impl UniversalEngineInner {
     }
 
     /// Shared signature registry.
-    pub fn signatures(&self) -> &SignatureRegistry {
+    pub fn signatures(&self) -> &Arc<SignatureRegistry> {
         &self.signatures
     }
 
@@ -316,3 +662,21 @@ impl UniversalEngineInner {
         &self.func_data
     }
 }
+
+/// The call trampoline of the signatures whose trampoline was omitted,
+/// see `Universal::exported_trampolines_only`.
+///
+/// Calling a function through it raises a trap saying that the
+/// trampoline was omitted, rather than jumping to missing code. The
+/// signatures of the exported functions keep their trampoline, so it
+/// is only reached by calling a function that isn't exported through
+/// the trampolines of its artifact.
+unsafe extern "C" fn omitted_trampoline(
+    _vmctx: *mut VMContext,
+    _callee: *const VMFunctionBody,
+    _values: *mut u128,
+) {
+    raise_user_trap(Box::new(RuntimeError::new(
+        "the call trampoline of this signature was omitted from the artifact",
+    )))
+}
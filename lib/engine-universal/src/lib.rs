@@ -24,19 +24,25 @@
     )
 )]
 
+#[cfg(feature = "compiler")]
+mod analysis;
 mod artifact;
 mod builder;
+#[cfg(feature = "compiler")]
+mod cache;
 mod code_memory;
 mod engine;
 mod link;
 mod serialize;
 mod unwind;
 
-pub use crate::artifact::UniversalArtifact;
+#[cfg(feature = "compiler")]
+pub use crate::analysis::{FunctionDiagnostics, ModuleDiagnostics};
+pub use crate::artifact::{CompilationStats, PhaseTimings, UniversalArtifact};
 pub use crate::builder::Universal;
-pub use crate::code_memory::CodeMemory;
+pub use crate::code_memory::{CodeAllocator, CodeMemory};
 pub use crate::engine::UniversalEngine;
-pub use crate::link::link_module;
+pub use crate::link::{link_module, verify_relocations, LibCallTable, RelocationRewriter};
 
 /// Version number of this crate.
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -1,86 +1,594 @@
 //! Linking for Universal-compiled code.
 
+#[cfg(feature = "parallel")]
+use rayon::prelude::{IntoParallelRefIterator, ParallelIterator};
 use std::ptr::write_unaligned;
 use wasmer_compiler::{
-    JumpTable, JumpTableOffsets, Relocation, RelocationKind, RelocationTarget, Relocations,
-    SectionIndex,
+    CustomSection, FunctionBody, JumpTable, JumpTableOffsets, Relocation, RelocationKind,
+    RelocationTarget, Relocations, SectionIndex,
 };
-use wasmer_engine::FunctionExtent;
+use wasmer_engine::{FunctionExtent, LinkError};
 use wasmer_types::entity::{EntityRef, PrimaryMap};
 use wasmer_types::LocalFunctionIndex;
-use wasmer_vm::ModuleInfo;
-use wasmer_vm::SectionBodyPtr;
+use wasmer_vm::{LibCall, ModuleInfo, SectionBodyPtr};
 
-fn apply_relocation(
+/// Rewrites the addresses that relocations resolve to, before they are
+/// patched into the code.
+///
+/// # Safety
+///
+/// The compiled code jumps to, calls or reads whatever address
+/// [`rewrite`](RelocationRewriter::rewrite) returns. For a function or
+/// a libcall, the new address must be a function with the same
+/// signature and calling convention as the original target, and it
+/// must stay valid for as long as the code can run. It must also be
+/// reachable with the relocation kind: a 4-byte PC-relative relocation
+/// can only reach 2 GiB around the code.
+pub unsafe trait RelocationRewriter: Send + Sync {
+    /// Returns the address that a relocation to `target` resolves to,
+    /// given the `address` it would otherwise resolve to.
+    fn rewrite(&self, target: RelocationTarget, address: usize) -> usize;
+}
+
+/// The addresses that the libcalls of compiled code resolve to.
+///
+/// An engine resolves its table once, and links every module against
+/// it. Individual libcalls can be overridden, for example to replace
+/// `memory.copy` with a custom `memcpy`.
+#[derive(Debug, Clone)]
+pub struct LibCallTable {
+    /// The address of each libcall, in the order of [`LibCall::ALL`].
+    addresses: Vec<usize>,
+}
+
+impl LibCallTable {
+    /// Creates a table resolving every libcall to the implementation
+    /// of the runtime.
+    pub fn new() -> Self {
+        Self {
+            addresses: LibCall::ALL
+                .iter()
+                .map(|libcall| libcall.function_pointer())
+                .collect(),
+        }
+    }
+
+    /// Returns the address that `libcall` resolves to.
+    pub fn get(&self, libcall: LibCall) -> usize {
+        self.addresses[libcall as usize]
+    }
+
+    /// Resolves `libcall` to `address` instead.
+    ///
+    /// # Safety
+    ///
+    /// The compiled code calls `address` as it would call the libcall:
+    /// it must be a function with the same signature and calling
+    /// convention as the implementation of the runtime, and it must
+    /// stay valid for as long as the code can run. The same reachability
+    /// constraints as for a [`RelocationRewriter`] apply.
+    pub unsafe fn set(&mut self, libcall: LibCall, address: usize) {
+        self.addresses[libcall as usize] = address;
+    }
+
+    /// Returns the libcalls resolved to another address than the
+    /// implementation of the runtime.
+    pub(crate) fn overridden(&self) -> impl Iterator<Item = LibCall> + '_ {
+        LibCall::ALL
+            .iter()
+            .copied()
+            .filter(move |libcall| self.get(*libcall) != libcall.function_pointer())
+    }
+}
+
+impl Default for LibCallTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A relocation whose target address is resolved, ready to be applied
+/// to the code at `body`.
+struct ResolvedRelocation<'a> {
     body: usize,
-    r: &Relocation,
+    relocation: &'a Relocation,
+    target: usize,
+}
+
+/// Resolves the target address of a relocation of the code at `body`,
+/// checking that the target exists and that the relocation kind is
+/// supported.
+///
+/// The targets only depend on the base addresses of the functions and
+/// custom sections, never on the result of another relocation, so the
+/// order in which relocations are applied doesn't matter.
+fn resolve_relocation<'a>(
+    body: usize,
+    r: &'a Relocation,
     allocated_functions: &PrimaryMap<LocalFunctionIndex, FunctionExtent>,
     jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
     allocated_sections: &PrimaryMap<SectionIndex, SectionBodyPtr>,
-) {
+    libcalls: &LibCallTable,
+    rewriter: Option<&dyn RelocationRewriter>,
+) -> Result<ResolvedRelocation<'a>, String> {
+    if relocation_size(r.kind).is_none() {
+        return Err(format!(
+            "relocation kind {} is unsupported in the current architecture",
+            r.kind
+        ));
+    }
+    let missing_target = || format!("relocation target {:?} does not exist", r.reloc_target);
     let target_func_address: usize = match r.reloc_target {
-        RelocationTarget::LocalFunc(index) => *allocated_functions[index].ptr as usize,
-        RelocationTarget::LibCall(libcall) => libcall.function_pointer(),
-        RelocationTarget::CustomSection(custom_section) => {
-            *allocated_sections[custom_section] as usize
+        RelocationTarget::LocalFunc(index) => {
+            *allocated_functions
+                .get(index)
+                .ok_or_else(missing_target)?
+                .ptr as usize
         }
+        RelocationTarget::LibCall(libcall) => libcalls.get(libcall),
+        RelocationTarget::CustomSection(custom_section) => **allocated_sections
+            .get(custom_section)
+            .ok_or_else(missing_target)?
+            as usize,
         RelocationTarget::JumpTable(func_index, jt) => {
             let offset = *jt_offsets
                 .get(func_index)
                 .and_then(|ofs| ofs.get(JumpTable::new(jt.index())))
-                .expect("func jump table");
-            *allocated_functions[func_index].ptr as usize + offset as usize
+                .ok_or_else(missing_target)?;
+            *allocated_functions
+                .get(func_index)
+                .ok_or_else(missing_target)?
+                .ptr as usize
+                + offset as usize
         }
     };
+    let target = match rewriter {
+        Some(rewriter) => rewriter.rewrite(r.reloc_target, target_func_address),
+        None => target_func_address,
+    };
+    Ok(ResolvedRelocation {
+        body,
+        relocation: r,
+        target,
+    })
+}
 
+/// Resolves the relocations of the code at `body`.
+fn resolve_relocations<'a>(
+    body: usize,
+    relocations: &'a [Relocation],
+    allocated_functions: &PrimaryMap<LocalFunctionIndex, FunctionExtent>,
+    jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
+    allocated_sections: &PrimaryMap<SectionIndex, SectionBodyPtr>,
+    libcalls: &LibCallTable,
+    rewriter: Option<&dyn RelocationRewriter>,
+) -> Result<Vec<ResolvedRelocation<'a>>, LinkError> {
+    relocations
+        .iter()
+        .map(|r| {
+            resolve_relocation(
+                body,
+                r,
+                allocated_functions,
+                jt_offsets,
+                allocated_sections,
+                libcalls,
+                rewriter,
+            )
+            .map_err(LinkError::Relocation)
+        })
+        .collect()
+}
+
+/// Patches a resolved relocation into the code, through an alias mapped
+/// `write_offset` bytes after it.
+fn apply_relocation(resolved: &ResolvedRelocation, write_offset: usize) {
+    let ResolvedRelocation {
+        body,
+        relocation: r,
+        target: target_func_address,
+    } = *resolved;
     match r.kind {
         #[cfg(target_pointer_width = "64")]
         RelocationKind::Abs8 => unsafe {
             let (reloc_address, reloc_delta) = r.for_address(body, target_func_address as u64);
+            let reloc_address = reloc_address.wrapping_add(write_offset);
             write_unaligned(reloc_address as *mut u64, reloc_delta);
         },
         #[cfg(target_pointer_width = "32")]
         RelocationKind::X86PCRel4 => unsafe {
             let (reloc_address, reloc_delta) = r.for_address(body, target_func_address as u64);
+            let reloc_address = reloc_address.wrapping_add(write_offset);
             write_unaligned(reloc_address as *mut u32, reloc_delta as _);
         },
         #[cfg(target_pointer_width = "64")]
         RelocationKind::X86PCRel8 => unsafe {
             let (reloc_address, reloc_delta) = r.for_address(body, target_func_address as u64);
+            let reloc_address = reloc_address.wrapping_add(write_offset);
             write_unaligned(reloc_address as *mut u64, reloc_delta);
         },
         RelocationKind::X86CallPCRel4 => unsafe {
             let (reloc_address, reloc_delta) = r.for_address(body, target_func_address as u64);
+            let reloc_address = reloc_address.wrapping_add(write_offset);
             write_unaligned(reloc_address as *mut u32, reloc_delta as _);
         },
         RelocationKind::X86PCRelRodata4 => {}
-        kind => panic!(
-            "Relocation kind unsupported in the current architecture {}",
-            kind
-        ),
+        kind => unreachable!("relocation kind {} was checked when resolving", kind),
     }
 }
 
+/// Returns the number of bytes that `apply_relocation` patches for a
+/// relocation of the given kind, or `None` if it can't apply it.
+fn relocation_size(kind: RelocationKind) -> Option<usize> {
+    match kind {
+        #[cfg(target_pointer_width = "64")]
+        RelocationKind::Abs8 | RelocationKind::X86PCRel8 => Some(8),
+        #[cfg(target_pointer_width = "32")]
+        RelocationKind::X86PCRel4 => Some(4),
+        RelocationKind::X86CallPCRel4 => Some(4),
+        RelocationKind::X86PCRelRodata4 => Some(0),
+        _ => None,
+    }
+}
+
+fn verify_relocation(
+    body_len: usize,
+    r: &Relocation,
+    function_bodies: &PrimaryMap<LocalFunctionIndex, FunctionBody>,
+    jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
+    custom_sections: &PrimaryMap<SectionIndex, CustomSection>,
+) -> Result<(), String> {
+    let size =
+        relocation_size(r.kind).ok_or_else(|| format!("unsupported relocation kind {}", r.kind))?;
+    match (r.offset as usize).checked_add(size) {
+        Some(end) if end <= body_len => {}
+        _ => {
+            return Err(format!(
+                "relocation at offset {} of size {} is out of bounds of a body of {} bytes",
+                r.offset, size, body_len
+            ))
+        }
+    }
+
+    let target_exists = match r.reloc_target {
+        RelocationTarget::LocalFunc(index) => function_bodies.get(index).is_some(),
+        RelocationTarget::LibCall(_) => true,
+        RelocationTarget::CustomSection(index) => custom_sections.get(index).is_some(),
+        RelocationTarget::JumpTable(func_index, jt) => jt_offsets
+            .get(func_index)
+            .and_then(|ofs| ofs.get(JumpTable::new(jt.index())))
+            .is_some(),
+    };
+    if !target_exists {
+        return Err(format!(
+            "relocation target {:?} does not exist",
+            r.reloc_target
+        ));
+    }
+    Ok(())
+}
+
+/// Checks that every relocation only patches bytes inside the function
+/// body or custom section it belongs to, and that its target exists.
+///
+/// [`link_module`] applies relocations as they are, so this must be
+/// checked beforehand when they come from an untrusted source.
+pub fn verify_relocations(
+    function_bodies: &PrimaryMap<LocalFunctionIndex, FunctionBody>,
+    jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
+    function_relocations: &Relocations,
+    custom_sections: &PrimaryMap<SectionIndex, CustomSection>,
+    section_relocations: &PrimaryMap<SectionIndex, Vec<Relocation>>,
+) -> Result<(), String> {
+    for (i, section_relocs) in section_relocations.iter() {
+        let body_len = custom_sections
+            .get(i)
+            .ok_or_else(|| format!("relocations for unknown custom section {:?}", i))?
+            .bytes
+            .len();
+        for r in section_relocs {
+            verify_relocation(body_len, r, function_bodies, jt_offsets, custom_sections)?;
+        }
+    }
+    for (i, function_relocs) in function_relocations.iter() {
+        let body_len = function_bodies
+            .get(i)
+            .ok_or_else(|| format!("relocations for unknown function {:?}", i))?
+            .body
+            .len();
+        for r in function_relocs {
+            verify_relocation(body_len, r, function_bodies, jt_offsets, custom_sections)?;
+        }
+    }
+    Ok(())
+}
+
 /// Links a module, patching the allocated functions with the
 /// required relocations and jump tables.
+///
+/// The libcalls resolve to the implementations of the runtime.
+///
+/// With the `parallel` feature, the relocations of the functions are
+/// applied in parallel: each function only patches its own body, so the
+/// result is the same as applying them one after the other.
 pub fn link_module(
-    _module: &ModuleInfo,
+    module: &ModuleInfo,
     allocated_functions: &PrimaryMap<LocalFunctionIndex, FunctionExtent>,
     jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
     function_relocations: Relocations,
     allocated_sections: &PrimaryMap<SectionIndex, SectionBodyPtr>,
     section_relocations: &PrimaryMap<SectionIndex, Vec<Relocation>>,
-) {
+) -> Result<(), LinkError> {
+    link_module_through_alias(
+        module,
+        allocated_functions,
+        jt_offsets,
+        &function_relocations,
+        allocated_sections,
+        section_relocations,
+        0,
+        &LibCallTable::new(),
+        None,
+    )
+}
+
+/// Like [`link_module`], but the code is patched through an alias
+/// mapped `write_offset` bytes after the address it runs at, as with a
+/// dual mapped [`CodeMemory`](crate::CodeMemory), the libcalls resolve
+/// through `libcalls`, and the targets are passed through `rewriter` if
+/// there is one.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn link_module_through_alias(
+    module: &ModuleInfo,
+    allocated_functions: &PrimaryMap<LocalFunctionIndex, FunctionExtent>,
+    jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
+    function_relocations: &Relocations,
+    allocated_sections: &PrimaryMap<SectionIndex, SectionBodyPtr>,
+    section_relocations: &PrimaryMap<SectionIndex, Vec<Relocation>>,
+    write_offset: usize,
+    libcalls: &LibCallTable,
+    rewriter: Option<&dyn RelocationRewriter>,
+) -> Result<(), LinkError> {
+    link_module_with(
+        module,
+        allocated_functions,
+        jt_offsets,
+        function_relocations,
+        allocated_sections,
+        section_relocations,
+        write_offset,
+        libcalls,
+        rewriter,
+        cfg!(feature = "parallel"),
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+#[cfg_attr(not(feature = "parallel"), allow(unused_variables))]
+fn link_module_with(
+    _module: &ModuleInfo,
+    allocated_functions: &PrimaryMap<LocalFunctionIndex, FunctionExtent>,
+    jt_offsets: &PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
+    function_relocations: &Relocations,
+    allocated_sections: &PrimaryMap<SectionIndex, SectionBodyPtr>,
+    section_relocations: &PrimaryMap<SectionIndex, Vec<Relocation>>,
+    write_offset: usize,
+    libcalls: &LibCallTable,
+    rewriter: Option<&dyn RelocationRewriter>,
+    parallel: bool,
+) -> Result<(), LinkError> {
+    // Resolve every relocation before patching anything, so that an
+    // invalid one is reported without leaving the code half linked.
+    let mut resolved_sections = Vec::with_capacity(section_relocations.len());
     for (i, section_relocs) in section_relocations.iter() {
-        let body = *allocated_sections[i] as usize;
-        for r in section_relocs {
-            apply_relocation(body, r, allocated_functions, jt_offsets, allocated_sections);
-        }
+        let body = **allocated_sections.get(i).ok_or_else(|| {
+            LinkError::Relocation(format!("relocations for unknown custom section {:?}", i))
+        })? as usize;
+        resolved_sections.push(resolve_relocations(
+            body,
+            section_relocs,
+            allocated_functions,
+            jt_offsets,
+            allocated_sections,
+            libcalls,
+            rewriter,
+        )?);
     }
+    let mut resolved_functions = Vec::with_capacity(function_relocations.len());
     for (i, function_relocs) in function_relocations.iter() {
-        let body = *allocated_functions[i].ptr as usize;
-        for r in function_relocs {
-            apply_relocation(body, r, allocated_functions, jt_offsets, allocated_sections);
+        let body = *allocated_functions
+            .get(i)
+            .ok_or_else(|| {
+                LinkError::Relocation(format!("relocations for unknown function {:?}", i))
+            })?
+            .ptr as usize;
+        resolved_functions.push(resolve_relocations(
+            body,
+            function_relocs,
+            allocated_functions,
+            jt_offsets,
+            allocated_sections,
+            libcalls,
+            rewriter,
+        )?);
+    }
+
+    for resolved in resolved_sections.iter().flatten() {
+        apply_relocation(resolved, write_offset);
+    }
+    let link_function = |resolved: &Vec<ResolvedRelocation>| {
+        for resolved in resolved {
+            apply_relocation(resolved, write_offset);
+        }
+    };
+    #[cfg(feature = "parallel")]
+    {
+        if parallel {
+            resolved_functions.par_iter().for_each(link_function);
+            return Ok(());
+        }
+    }
+    resolved_functions.iter().for_each(link_function);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_vm::{FunctionBodyPtr, VMFunctionBody};
+
+    const FUNCTIONS: usize = 256;
+    const FUNCTION_SIZE: usize = 256;
+
+    /// Links functions laid out contiguously in `code`, where each one
+    /// calls every function at every 4-byte offset of its body.
+    fn link(code: &mut [u8], parallel: bool) {
+        let base = code.as_mut_ptr();
+        let allocated_functions = (0..FUNCTIONS)
+            .map(|i| FunctionExtent {
+                ptr: FunctionBodyPtr(
+                    unsafe { base.add(i * FUNCTION_SIZE) } as *const VMFunctionBody
+                ),
+                length: FUNCTION_SIZE,
+            })
+            .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+        let function_relocations = (0..FUNCTIONS)
+            .map(|i| {
+                (0..FUNCTION_SIZE / 4)
+                    .map(|j| Relocation {
+                        kind: RelocationKind::X86CallPCRel4,
+                        reloc_target: RelocationTarget::LocalFunc(LocalFunctionIndex::new(
+                            (i * 7 + j) % FUNCTIONS,
+                        )),
+                        offset: (j * 4) as u32,
+                        addend: -4,
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Relocations>();
+
+        link_module_with(
+            &ModuleInfo::new(),
+            &allocated_functions,
+            &PrimaryMap::new(),
+            &function_relocations,
+            &PrimaryMap::new(),
+            &PrimaryMap::new(),
+            0,
+            &LibCallTable::new(),
+            None,
+            parallel,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn linking_patches_every_function() {
+        let mut serial = vec![0; FUNCTIONS * FUNCTION_SIZE];
+        link(&mut serial, false);
+        assert!(serial
+            .chunks(FUNCTION_SIZE)
+            .all(|body| body.iter().any(|&byte| byte != 0)));
+
+        // The parallel path must produce exactly the same code.
+        #[cfg(feature = "parallel")]
+        {
+            let mut parallel = vec![0; FUNCTIONS * FUNCTION_SIZE];
+            link(&mut parallel, true);
+            assert_eq!(serial, parallel);
+        }
+    }
+
+    #[test]
+    fn libcall_table_is_indexed_by_discriminant() {
+        let table = LibCallTable::new();
+        for (index, &libcall) in LibCall::ALL.iter().enumerate() {
+            assert_eq!(libcall as usize, index);
+            assert_eq!(table.get(libcall), libcall.function_pointer());
+        }
+    }
+
+    #[test]
+    fn unknown_target_is_an_error() {
+        let mut code = vec![0; FUNCTION_SIZE];
+        let allocated_functions = std::iter::once(FunctionExtent {
+            ptr: FunctionBodyPtr(code.as_mut_ptr() as *const VMFunctionBody),
+            length: FUNCTION_SIZE,
+        })
+        .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+        let function_relocations = std::iter::once(vec![Relocation {
+            kind: RelocationKind::X86CallPCRel4,
+            reloc_target: RelocationTarget::LocalFunc(LocalFunctionIndex::new(1)),
+            offset: 0,
+            addend: 0,
+        }])
+        .collect::<Relocations>();
+
+        let result = link_module(
+            &ModuleInfo::new(),
+            &allocated_functions,
+            &PrimaryMap::new(),
+            function_relocations,
+            &PrimaryMap::new(),
+            &PrimaryMap::new(),
+        );
+        assert!(matches!(result, Err(LinkError::Relocation(_))));
+    }
+
+    #[test]
+    fn out_of_range_section_target_is_an_error() {
+        let mut code = vec![0u8; FUNCTION_SIZE];
+        let mut section = vec![0u8; 16];
+        let allocated_functions = std::iter::once(FunctionExtent {
+            ptr: FunctionBodyPtr(code.as_mut_ptr() as *const VMFunctionBody),
+            length: FUNCTION_SIZE,
+        })
+        .collect::<PrimaryMap<LocalFunctionIndex, _>>();
+        let allocated_sections = std::iter::once(SectionBodyPtr(section.as_mut_ptr()))
+            .collect::<PrimaryMap<SectionIndex, _>>();
+        // The first relocation is valid, but mustn't be applied since
+        // the second one targets a section that doesn't exist.
+        let function_relocations = std::iter::once(vec![
+            Relocation {
+                kind: RelocationKind::X86CallPCRel4,
+                reloc_target: RelocationTarget::CustomSection(SectionIndex::new(0)),
+                offset: 0,
+                addend: 0,
+            },
+            Relocation {
+                kind: RelocationKind::X86CallPCRel4,
+                reloc_target: RelocationTarget::CustomSection(SectionIndex::new(7)),
+                offset: 4,
+                addend: 0,
+            },
+        ])
+        .collect::<Relocations>();
+        // A section relocating against itself is fine.
+        let section_relocations = std::iter::once(vec![Relocation {
+            kind: RelocationKind::X86CallPCRel4,
+            reloc_target: RelocationTarget::CustomSection(SectionIndex::new(0)),
+            offset: 0,
+            addend: 0,
+        }])
+        .collect::<PrimaryMap<SectionIndex, _>>();
+
+        let result = link_module(
+            &ModuleInfo::new(),
+            &allocated_functions,
+            &PrimaryMap::new(),
+            function_relocations,
+            &allocated_sections,
+            &section_relocations,
+        );
+        match result {
+            Err(LinkError::Relocation(message)) => assert!(
+                message.contains("does not exist"),
+                "unexpected error: {}",
+                message
+            ),
+            other => panic!("unexpected result: {:?}", other),
         }
+        assert!(code.iter().all(|&byte| byte == 0));
+        assert!(section.iter().all(|&byte| byte == 0));
     }
 }
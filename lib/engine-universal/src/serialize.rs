@@ -21,7 +21,12 @@ pub struct SerializableCompilation {
     pub function_relocations: PrimaryMap<LocalFunctionIndex, Vec<Relocation>>,
     pub function_jt_offsets: PrimaryMap<LocalFunctionIndex, JumpTableOffsets>,
     pub function_frame_info: PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>,
-    pub function_call_trampolines: PrimaryMap<SignatureIndex, FunctionBody>,
+    // The distinct function call trampolines, shared by all the
+    // signatures that are structurally equal
+    pub function_call_trampolines: Vec<FunctionBody>,
+    // The index in `function_call_trampolines` of each signature's trampoline,
+    // or `OMITTED_TRAMPOLINE` if it wasn't kept
+    pub function_call_trampoline_indices: PrimaryMap<SignatureIndex, u32>,
     pub dynamic_function_trampolines: PrimaryMap<FunctionIndex, FunctionBody>,
     pub custom_sections: PrimaryMap<SectionIndex, CustomSection>,
     pub custom_section_relocations: PrimaryMap<SectionIndex, Vec<Relocation>>,
@@ -29,6 +34,10 @@ pub struct SerializableCompilation {
     pub debug: Option<Dwarf>,
 }
 
+/// The index of the trampoline of the signatures whose trampoline was
+/// omitted, see `Universal::exported_trampolines_only`.
+pub(crate) const OMITTED_TRAMPOLINE: u32 = u32::MAX;
+
 /// Serializable struct that is able to serialize from and to
 /// a `UniversalArtifactInfo`.
 #[derive(MemoryUsage, Archive, RkyvDeserialize, RkyvSerialize)]
@@ -38,7 +47,16 @@ pub struct SerializableModule {
     pub data_initializers: Box<[OwnedDataInitializer]>,
 }
 
-fn to_serialize_error(err: impl std::error::Error) -> SerializeError {
+/// Converts an error of the serializer into a [`SerializeError`], as an
+/// `Io` one if it was caused by an I/O error.
+fn to_serialize_error(err: impl std::error::Error + 'static) -> SerializeError {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(&err);
+    while let Some(error) = source {
+        if let Some(io_error) = error.downcast_ref::<std::io::Error>() {
+            return SerializeError::Io(std::io::Error::new(io_error.kind(), err.to_string()));
+        }
+        source = error.source();
+    }
     SerializeError::Generic(format!("{}", err))
 }
 
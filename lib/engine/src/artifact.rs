@@ -1,22 +1,50 @@
 use crate::{
-    resolve_imports, InstantiationError, Resolver, RuntimeError, SerializeError, Tunables,
+    resolve_imports, InstanceOverrides, InstantiationError, Resolver, RuntimeError, SerializeError,
+    Tunables,
 };
 use loupe::MemoryUsage;
 use std::any::Any;
+use std::collections::BTreeSet;
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
 use wasmer_compiler::Features;
 use wasmer_types::entity::{BoxedSlice, PrimaryMap};
 use wasmer_types::{
-    DataInitializer, FunctionIndex, LocalFunctionIndex, MemoryIndex, OwnedDataInitializer,
-    SignatureIndex, TableIndex,
+    DataInitializer, FunctionIndex, FunctionType, LocalFunctionIndex, MemoryIndex,
+    OwnedDataInitializer, SignatureIndex, TableIndex,
 };
 use wasmer_vm::{
     FuncDataRegistry, FunctionBodyPtr, InstanceAllocator, InstanceHandle, MemoryStyle, ModuleInfo,
-    TableStyle, TrapHandler, VMSharedSignatureIndex, VMTrampoline,
+    SignatureRegistration, TableStyle, TrapHandler, VMSharedSignatureIndex, VMTrampoline,
 };
 
+/// The memory held by a loaded [`Artifact`], as reported by
+/// [`Artifact::memory_footprint`].
+///
+/// This is what the artifact adds to the resident memory of the
+/// process, which differs from the size of the serialized artifact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ModuleFootprint {
+    /// The size of the executable code: the functions, the trampolines
+    /// and the custom sections.
+    pub code_bytes: usize,
+    /// The size of the frame information, used to symbolicate traps.
+    pub frame_info_bytes: usize,
+    /// The size of the active and passive data segments.
+    pub data_initializer_bytes: usize,
+    /// The size of everything else the artifact retains, such as the
+    /// module information.
+    pub metadata_bytes: usize,
+}
+
+impl ModuleFootprint {
+    /// Returns the total number of bytes.
+    pub fn total(&self) -> usize {
+        self.code_bytes + self.frame_info_bytes + self.data_initializer_bytes + self.metadata_bytes
+    }
+}
+
 /// An `Artifact` is the product that the `Engine`
 /// implementation produce and use.
 ///
@@ -40,9 +68,109 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
     /// This is required to ensure that any traps can be properly symbolicated.
     fn register_frame_info(&self);
 
+    /// Registers the part of the frame information of this `Artifact`
+    /// needed to recognize its traps and report their trap codes, but
+    /// not to symbolicate its frames in backtraces.
+    ///
+    /// This is what [`Artifact::instantiate_with_trap_info`] registers,
+    /// since it is cheaper than the complete frame information, which
+    /// [`Artifact::register_frame_info`] adds afterwards. The default
+    /// implementation registers the complete frame information.
+    fn register_trap_info(&self) {
+        self.register_frame_info();
+    }
+
+    /// Prepares the artifact to be instantiated and called, so that
+    /// the first instance doesn't pay for it.
+    ///
+    /// The signatures of the artifact are already registered when it is
+    /// loaded. This registers its frame information, which is otherwise
+    /// done by the first instantiation, and engines may do more, such
+    /// as faulting in the pages of the compiled code. Calling it more
+    /// than once has no effect.
+    fn warm_up(&self) {
+        self.register_frame_info();
+    }
+
     /// Returns the features for this Artifact
     fn features(&self) -> &Features;
 
+    /// Returns the module, name and type of each function imported by
+    /// this `Artifact`, in import order.
+    ///
+    /// This is what tooling emitting native code needs to declare the
+    /// external symbols a linker has to provide.
+    fn imported_functions(&self) -> Vec<(String, String, FunctionType)> {
+        self.module_ref()
+            .imports()
+            .functions()
+            .map(|import| {
+                (
+                    import.module().to_string(),
+                    import.name().to_string(),
+                    import.ty().clone(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the hash of the wasm binary this `Artifact` was compiled
+    /// from, if the engine recorded it.
+    fn source_hash(&self) -> Option<[u8; 32]> {
+        None
+    }
+
+    /// Checks that this `Artifact` was compiled from the wasm binary
+    /// `wasm`, by comparing its hash with the one recorded at compile
+    /// time.
+    ///
+    /// This is `false` when the engine didn't record the hash.
+    fn verify_source(&self, _wasm: &[u8]) -> bool {
+        false
+    }
+
+    /// Attaches `value` to the artifact under `key`, replacing the
+    /// value already attached under that key, if any.
+    ///
+    /// The metadata is serialized with the artifact, so that embedders
+    /// can keep their own data with it. By default, the artifact can't
+    /// carry metadata and this returns an error.
+    fn set_metadata(&self, _key: &str, _value: Vec<u8>) -> Result<(), SerializeError> {
+        Err(SerializeError::Generic(
+            "this artifact can't carry metadata".to_string(),
+        ))
+    }
+
+    /// Returns the metadata attached under `key`, if any.
+    fn get_metadata(&self, _key: &str) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Returns the memory held by this `Artifact`.
+    ///
+    /// By default, the code and the frame information aren't known,
+    /// and are reported as 0 bytes.
+    fn memory_footprint(&self) -> ModuleFootprint {
+        let data_initializer_bytes = self
+            .data_initializers()
+            .iter()
+            .map(|initializer| initializer.data.len())
+            .chain(
+                self.module_ref()
+                    .passive_data
+                    .values()
+                    .map(|data| data.len()),
+            )
+            .sum();
+        let total = self.size_of_val(&mut BTreeSet::new());
+        ModuleFootprint {
+            code_bytes: 0,
+            frame_info_bytes: 0,
+            data_initializer_bytes,
+            metadata_bytes: total.saturating_sub(data_initializer_bytes),
+        }
+    }
+
     /// Returns the memory styles associated with this `Artifact`.
     fn memory_styles(&self) -> &PrimaryMap<MemoryIndex, MemoryStyle>;
 
@@ -67,6 +195,15 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
     /// Returns the associated VM signatures for this `Artifact`.
     fn signatures(&self) -> &BoxedSlice<SignatureIndex, VMSharedSignatureIndex>;
 
+    /// Returns the registration of the [`Artifact::signatures`], which
+    /// instances keep alive so that the signatures stay registered after
+    /// the artifact is dropped.
+    ///
+    /// Artifacts that don't release their signatures return `None`.
+    fn signature_registration(&self) -> Option<Arc<SignatureRegistration>> {
+        None
+    }
+
     /// Get the func data registry
     fn func_data_registry(&self) -> &FuncDataRegistry;
 
@@ -95,6 +232,77 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
         tunables: &dyn Tunables,
         resolver: &dyn Resolver,
         host_state: Box<dyn Any>,
+    ) -> Result<InstanceHandle, InstantiationError> {
+        self.instantiate_with_host_state(tunables, resolver, Box::new(move |_| host_state))
+    }
+
+    /// Crate an `Instance` from this `Artifact`, whose host state is built
+    /// by `host_state` once the instance exists.
+    ///
+    /// `host_state` is called before the instance is initialized with
+    /// [`Artifact::finish_instantiation`], so before its start function
+    /// runs, but the state can already refer to the instance, for
+    /// example to its exported memories. Exports keep the instance
+    /// alive, so a state holding on to one leaks the instance.
+    ///
+    /// # Safety
+    ///
+    /// See [`InstanceHandle::new`].
+    unsafe fn instantiate_with_host_state(
+        &self,
+        tunables: &dyn Tunables,
+        resolver: &dyn Resolver,
+        host_state: Box<dyn FnOnce(&InstanceHandle) -> Box<dyn Any>>,
+    ) -> Result<InstanceHandle, InstantiationError> {
+        self.instantiate_with_overrides(
+            tunables,
+            resolver,
+            host_state,
+            &InstanceOverrides::default(),
+        )
+    }
+
+    /// Crate an `Instance` from this `Artifact` like
+    /// [`Artifact::instantiate_with_host_state`], using the memories,
+    /// tables and globals of `overrides` for the local ones they are
+    /// provided for.
+    ///
+    /// # Safety
+    ///
+    /// See [`InstanceHandle::new`]. The provided memories and tables
+    /// must own their definitions, like the ones created with
+    /// [`Tunables::create_host_memory`] and
+    /// [`Tunables::create_host_table`], rather than live in another
+    /// instance.
+    unsafe fn instantiate_with_overrides(
+        &self,
+        tunables: &dyn Tunables,
+        resolver: &dyn Resolver,
+        host_state: Box<dyn FnOnce(&InstanceHandle) -> Box<dyn Any>>,
+        overrides: &InstanceOverrides,
+    ) -> Result<InstanceHandle, InstantiationError> {
+        self.register_frame_info();
+        self.instantiate_with_trap_info(tunables, resolver, host_state, overrides)
+    }
+
+    /// Crate an `Instance` from this `Artifact` like
+    /// [`Artifact::instantiate_with_overrides`], but only register the
+    /// trap information of the artifact, see
+    /// [`Artifact::register_trap_info`].
+    ///
+    /// This is cheaper when the backtraces of traps aren't captured.
+    /// Their frames in this artifact are left out of backtraces until
+    /// [`Artifact::register_frame_info`] is called.
+    ///
+    /// # Safety
+    ///
+    /// See [`Artifact::instantiate_with_overrides`].
+    unsafe fn instantiate_with_trap_info(
+        &self,
+        tunables: &dyn Tunables,
+        resolver: &dyn Resolver,
+        host_state: Box<dyn FnOnce(&InstanceHandle) -> Box<dyn Any>>,
+        overrides: &InstanceOverrides,
     ) -> Result<InstanceHandle, InstantiationError> {
         self.preinstantiate()?;
 
@@ -121,22 +329,23 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
 
         let (allocator, memory_definition_locations, table_definition_locations) =
             InstanceAllocator::new(&*module);
-        let finished_memories = tunables
-            .create_memories(&module, self.memory_styles(), &memory_definition_locations)
-            .map_err(InstantiationError::Link)?
-            .into_boxed_slice();
-        let finished_tables = tunables
-            .create_tables(&module, self.table_styles(), &table_definition_locations)
-            .map_err(InstantiationError::Link)?
-            .into_boxed_slice();
-        let finished_globals = tunables
-            .create_globals(&module)
-            .map_err(InstantiationError::Link)?
-            .into_boxed_slice();
+        let (finished_memories, finished_tables, finished_globals) = overrides
+            .create(
+                tunables,
+                &module,
+                self.memory_styles(),
+                self.table_styles(),
+                &memory_definition_locations,
+                &table_definition_locations,
+            )
+            .map_err(InstantiationError::Link)?;
+        let finished_memories = finished_memories.into_boxed_slice();
+        let finished_tables = finished_tables.into_boxed_slice();
+        let finished_globals = finished_globals.into_boxed_slice();
 
-        self.register_frame_info();
+        self.register_trap_info();
 
-        let handle = InstanceHandle::new(
+        let mut handle = InstanceHandle::new(
             allocator,
             module,
             self.finished_functions().clone(),
@@ -147,10 +356,15 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
             imports,
             self.signatures().clone(),
             self.func_data_registry(),
-            host_state,
+            Box::new(()),
             import_function_envs,
         )
         .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))?;
+        if let Some(registration) = self.signature_registration() {
+            handle.set_signature_registration(registration);
+        }
+        let host_state = host_state(&handle);
+        handle.set_host_state(host_state);
         Ok(handle)
     }
 
@@ -163,6 +377,22 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
         &self,
         trap_handler: &dyn TrapHandler,
         handle: &InstanceHandle,
+    ) -> Result<(), InstantiationError> {
+        self.finish_instantiation_without_start(handle)?;
+        handle
+            .invoke_start_function(trap_handler)
+            .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))
+    }
+
+    /// Finishes the instantiation of a just created `InstanceHandle`,
+    /// without invoking its start function.
+    ///
+    /// # Safety
+    ///
+    /// See [`InstanceHandle::finish_instantiation_without_start`].
+    unsafe fn finish_instantiation_without_start(
+        &self,
+        handle: &InstanceHandle,
     ) -> Result<(), InstantiationError> {
         let data_initializers = self
             .data_initializers()
@@ -173,8 +403,8 @@ pub trait Artifact: Send + Sync + Upcastable + MemoryUsage {
             })
             .collect::<Vec<_>>();
         handle
-            .finish_instantiation(trap_handler, &data_initializers)
-            .map_err(|trap| InstantiationError::Start(RuntimeError::from_trap(trap)))
+            .finish_instantiation_without_start(&data_initializers)
+            .map_err(InstantiationError::from)
     }
 }
 
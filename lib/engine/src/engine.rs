@@ -22,14 +22,36 @@ pub trait Engine: MemoryUsage {
     fn target(&self) -> &Target;
 
     /// Register a signature
+    ///
+    /// The registration is never released, so the returned index stays
+    /// valid for the lifetime of the engine.
     fn register_signature(&self, func_type: &FunctionType) -> VMSharedSignatureIndex;
 
+    /// Register many signatures at once.
+    ///
+    /// The returned indices are in the same order as `func_types`, and
+    /// match what individual calls to [`Engine::register_signature`] would
+    /// have produced.
+    fn register_signatures(&self, func_types: &[FunctionType]) -> Vec<VMSharedSignatureIndex> {
+        func_types
+            .iter()
+            .map(|func_type| self.register_signature(func_type))
+            .collect()
+    }
+
     /// Register a function's data.
     fn register_function_metadata(&self, func_data: VMCallerCheckedAnyfunc) -> VMFuncRef;
 
     /// Lookup a signature
     fn lookup_signature(&self, sig: VMSharedSignatureIndex) -> Option<FunctionType>;
 
+    /// The number of distinct signatures currently registered.
+    ///
+    /// Signatures of an artifact are removed once the artifact and all
+    /// its instances are dropped, so this doesn't grow when the same modules keep being
+    /// loaded and dropped.
+    fn signature_count(&self) -> usize;
+
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError>;
 
@@ -1,9 +1,11 @@
 //! The WebAssembly possible errors
 use crate::trap::RuntimeError;
+use enumset::EnumSet;
 use std::io;
 use thiserror::Error;
-use wasmer_compiler::CompileError;
-use wasmer_types::ExternType;
+use wasmer_compiler::{CompileError, CpuFeature};
+use wasmer_types::{ExternType, ExternTypeMismatch, FunctionIndex, SignatureIndex};
+use wasmer_vm::{DataSegmentOutOfBounds, InitializationError, MemoryStyle};
 
 /// The Serialize error can occur when serializing a
 /// compiled Module into a binary.
@@ -12,9 +14,18 @@ pub enum SerializeError {
     /// An IO error
     #[error(transparent)]
     Io(#[from] io::Error),
-    /// A generic serialization error
+    /// A generic serialization error, for example a part of the
+    /// artifact that couldn't be serialized
     #[error("{0}")]
     Generic(String),
+    /// The buffer to serialize into is too small
+    #[error("the serialized artifact takes {required} bytes, but the buffer has {available}")]
+    Capacity {
+        /// The size of the serialized artifact, in bytes.
+        required: usize,
+        /// The size of the buffer, in bytes.
+        available: usize,
+    },
 }
 
 /// The Deserialize error can occur when loading a
@@ -33,12 +44,51 @@ pub enum DeserializeError {
     /// The provided binary is corrupted
     #[error("corrupted binary: {0}")]
     CorruptedBinary(String),
+    /// The binary was compiled for CPU features the host lacks, so
+    /// running its code could raise illegal instructions.
+    #[error("incompatible CPU features: the binary requires {required:?}, but the host only has {available:?}")]
+    IncompatibleCpuFeatures {
+        /// The CPU features the binary was compiled for.
+        required: EnumSet<CpuFeature>,
+        /// The CPU features of the host.
+        available: EnumSet<CpuFeature>,
+    },
     /// The binary was valid, but we got an error when
     /// trying to allocate the required resources.
     #[error(transparent)]
     Compiler(CompileError),
 }
 
+/// An inconsistency found when verifying a compiled module, without
+/// instantiating it.
+#[derive(Error, Debug)]
+pub enum VerifyError {
+    /// A relocation patches bytes outside of its function body or
+    /// custom section, or targets something that doesn't exist.
+    #[error("invalid relocation: {0}")]
+    Relocation(String),
+
+    /// A function has a signature the module doesn't define.
+    #[error("function {0:?} has the undefined signature {1:?}")]
+    UnknownSignature(FunctionIndex, SignatureIndex),
+
+    /// The trampoline of a signature doesn't exist.
+    #[error("the trampoline {1} of signature {0:?} doesn't exist")]
+    UnknownTrampoline(SignatureIndex, u32),
+
+    /// A map of the compiled module doesn't have one entry for each
+    /// function or signature of the module.
+    #[error("expected {expected} {map}, found {found}")]
+    MapMismatch {
+        /// What the map holds.
+        map: &'static str,
+        /// The number of entries the module requires.
+        expected: usize,
+        /// The number of entries in the map.
+        found: usize,
+    },
+}
+
 /// An ImportError.
 ///
 /// Note: this error is not standard to WebAssembly, but it's
@@ -46,14 +96,24 @@ pub enum DeserializeError {
 #[derive(Error, Debug)]
 pub enum ImportError {
     /// Incompatible Import Type.
-    /// This error occurs when the import types mismatch.
-    #[error("incompatible import type. Expected {0:?} but received {1:?}")]
-    IncompatibleType(ExternType, ExternType),
+    /// This error occurs when the import types mismatch: it holds the
+    /// expected type, the provided one, and the part of them that
+    /// differs.
+    #[error("incompatible import type. Expected {0:?} but received {1:?}: the {2} differs")]
+    IncompatibleType(ExternType, ExternType, ExternTypeMismatch),
 
     /// Unknown Import.
     /// This error occurs when an import was expected but not provided.
     #[error("unknown import. Expected {0:?}")]
     UnknownImport(ExternType),
+
+    /// Incompatible Memory Style.
+    /// This error occurs when the provided memory has a smaller static
+    /// bound or offset guard than the expected style, or is dynamic
+    /// while a static one is expected. It holds the expected style and
+    /// the provided one.
+    #[error("incompatible memory style. Expected {0:?} but received {1:?}")]
+    IncompatibleMemoryStyle(MemoryStyle, MemoryStyle),
 }
 
 /// The WebAssembly.LinkError object indicates an error during
@@ -66,7 +126,7 @@ pub enum ImportError {
 #[error("Link error: {0}")]
 pub enum LinkError {
     /// An error occurred when checking the import types.
-    #[error("Error while importing {0:?}.{1:?}: {2}")]
+    #[error("Error while importing {0}::{1}: {2}")]
     Import(String, String, ImportError),
 
     /// A trap ocurred during linking.
@@ -76,6 +136,15 @@ pub enum LinkError {
     /// Insufficient resources available for linking.
     #[error("Insufficient resources: {0}")]
     Resource(String),
+
+    /// A relocation of the compiled code couldn't be applied.
+    #[error("Error while applying a relocation: {0}")]
+    Relocation(String),
+
+    /// A memory, table or global provided for the instance doesn't
+    /// match the module.
+    #[error("Invalid instance override: {0}")]
+    Override(String),
 }
 
 /// An error while instantiating a module.
@@ -94,4 +163,20 @@ pub enum InstantiationError {
     /// A runtime error occured while invoking the start function
     #[error(transparent)]
     Start(RuntimeError),
+
+    /// An active data segment doesn't fit in its memory. The segments
+    /// before it have already been written to their memories.
+    #[error(transparent)]
+    DataSegmentOutOfBounds(DataSegmentOutOfBounds),
+}
+
+impl From<InitializationError> for InstantiationError {
+    fn from(error: InitializationError) -> Self {
+        match error {
+            InitializationError::Trap(trap) => Self::Start(RuntimeError::from_trap(trap)),
+            InitializationError::DataSegmentOutOfBounds(error) => {
+                Self::DataSegmentOutOfBounds(error)
+            }
+        }
+    }
 }
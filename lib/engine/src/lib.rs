@@ -24,16 +24,18 @@ mod artifact;
 mod engine;
 mod error;
 mod export;
+mod overrides;
 mod resolver;
 mod trap;
 mod tunables;
 
-pub use crate::artifact::Artifact;
+pub use crate::artifact::{Artifact, ModuleFootprint};
 pub use crate::engine::{Engine, EngineId};
 pub use crate::error::{
-    DeserializeError, ImportError, InstantiationError, LinkError, SerializeError,
+    DeserializeError, ImportError, InstantiationError, LinkError, SerializeError, VerifyError,
 };
 pub use crate::export::{Export, ExportFunction, ExportFunctionMetadata};
+pub use crate::overrides::InstanceOverrides;
 pub use crate::resolver::{
     resolve_imports, ChainableNamedResolver, NamedResolver, NamedResolverChain, NullResolver,
     Resolver,
@@ -0,0 +1,232 @@
+use crate::error::LinkError;
+use crate::tunables::Tunables;
+use std::collections::BTreeMap;
+use std::ptr::NonNull;
+use std::sync::Arc;
+use wasmer_types::entity::{EntityRef, PrimaryMap};
+use wasmer_types::{
+    ExternType, ExternTypeMismatch, GlobalIndex, LocalGlobalIndex, LocalMemoryIndex,
+    LocalTableIndex, MemoryIndex, TableIndex,
+};
+use wasmer_vm::{Global, Memory, ModuleInfo, Table};
+use wasmer_vm::{MemoryStyle, TableStyle};
+use wasmer_vm::{VMMemoryDefinition, VMTableDefinition};
+
+/// Memories, tables and globals to use for the locally defined ones
+/// of an instance, instead of creating them with the [`Tunables`].
+///
+/// The compiled code of the instance keeps a copy of the definitions
+/// of its local memories and tables, which is never updated when they
+/// are grown from outside of the instance. So that this copy stays
+/// valid, a provided memory must be static, which never moves, and a
+/// provided table must be at its maximum size, which never grows.
+///
+/// The values of the provided globals are overwritten by the global
+/// initializers of the module.
+#[derive(Default, Clone)]
+pub struct InstanceOverrides {
+    /// The memories to use, by index in the module.
+    pub memories: BTreeMap<MemoryIndex, Arc<dyn Memory>>,
+    /// The tables to use, by index in the module.
+    pub tables: BTreeMap<TableIndex, Arc<dyn Table>>,
+    /// The globals to use, by index in the module.
+    pub globals: BTreeMap<GlobalIndex, Arc<Global>>,
+}
+
+impl InstanceOverrides {
+    /// Returns whether nothing is overridden.
+    pub fn is_empty(&self) -> bool {
+        self.memories.is_empty() && self.tables.is_empty() && self.globals.is_empty()
+    }
+
+    /// Checks that every override is for a local entity of `module`,
+    /// of a compatible type.
+    fn check(
+        &self,
+        module: &ModuleInfo,
+        memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
+        table_styles: &PrimaryMap<TableIndex, TableStyle>,
+    ) -> Result<(), LinkError> {
+        for (index, memory) in &self.memories {
+            let declared = module
+                .memories
+                .get(*index)
+                .filter(|_| module.local_memory_index(*index).is_some())
+                .ok_or_else(|| not_local("memory", index.index()))?;
+            if let Some(mismatch) =
+                ExternType::Memory(memory.ty()).mismatch_with(&ExternType::Memory(*declared))
+            {
+                return Err(incompatible("memory", index.index(), mismatch));
+            }
+            let compatible_style = match (memory.style(), &memory_styles[*index]) {
+                (
+                    MemoryStyle::Static { bound, .. },
+                    MemoryStyle::Static {
+                        bound: compiled, ..
+                    },
+                ) => bound >= compiled,
+                (MemoryStyle::Static { .. }, MemoryStyle::Dynamic { .. }) => true,
+                (MemoryStyle::Dynamic { .. }, _) => false,
+            };
+            if !compatible_style
+                || memory.style().offset_guard_size() < memory_styles[*index].offset_guard_size()
+            {
+                return Err(LinkError::Override(format!(
+                    "the memory provided for index {} has the style {:?}, which is not static or \
+                     smaller than the compiled style {:?}",
+                    index.index(),
+                    memory.style(),
+                    memory_styles[*index]
+                )));
+            }
+        }
+
+        for (index, table) in &self.tables {
+            let declared = module
+                .tables
+                .get(*index)
+                .filter(|_| module.local_table_index(*index).is_some())
+                .ok_or_else(|| not_local("table", index.index()))?;
+            let mut ty = *table.ty();
+            ty.minimum = table.size();
+            if let Some(mismatch) =
+                ExternType::Table(ty).mismatch_with(&ExternType::Table(*declared))
+            {
+                return Err(incompatible("table", index.index(), mismatch));
+            }
+            if ty.maximum != Some(ty.minimum) {
+                return Err(LinkError::Override(format!(
+                    "the table provided for index {} can grow, it must be at its maximum size",
+                    index.index()
+                )));
+            }
+            if table.style() != &table_styles[*index] {
+                return Err(LinkError::Override(format!(
+                    "the table provided for index {} has the style {:?} instead of {:?}",
+                    index.index(),
+                    table.style(),
+                    table_styles[*index]
+                )));
+            }
+        }
+
+        for (index, global) in &self.globals {
+            let declared = module
+                .globals
+                .get(*index)
+                .filter(|_| module.local_global_index(*index).is_some())
+                .ok_or_else(|| not_local("global", index.index()))?;
+            if let Some(mismatch) =
+                ExternType::Global(*global.ty()).mismatch_with(&ExternType::Global(*declared))
+            {
+                return Err(incompatible("global", index.index(), mismatch));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Creates the local memories, tables and globals of `module`,
+    /// using the overrides when there are some and `tunables`
+    /// otherwise.
+    ///
+    /// # Safety
+    ///
+    /// The definition locations must come from the
+    /// [`InstanceAllocator`](wasmer_vm::InstanceAllocator) of the
+    /// instance. The provided memories and tables must keep their
+    /// definitions alive for as long as the instance, which is the case
+    /// of the ones created with [`Tunables::create_host_memory`] and
+    /// [`Tunables::create_host_table`].
+    #[allow(clippy::type_complexity)]
+    pub(crate) unsafe fn create(
+        &self,
+        tunables: &dyn Tunables,
+        module: &ModuleInfo,
+        memory_styles: &PrimaryMap<MemoryIndex, MemoryStyle>,
+        table_styles: &PrimaryMap<TableIndex, TableStyle>,
+        memory_definition_locations: &[NonNull<VMMemoryDefinition>],
+        table_definition_locations: &[NonNull<VMTableDefinition>],
+    ) -> Result<
+        (
+            PrimaryMap<LocalMemoryIndex, Arc<dyn Memory>>,
+            PrimaryMap<LocalTableIndex, Arc<dyn Table>>,
+            PrimaryMap<LocalGlobalIndex, Arc<Global>>,
+        ),
+        LinkError,
+    > {
+        if self.is_empty() {
+            return Ok((
+                tunables.create_memories(module, memory_styles, memory_definition_locations)?,
+                tunables.create_tables(module, table_styles, table_definition_locations)?,
+                tunables.create_globals(module)?,
+            ));
+        }
+        self.check(module, memory_styles, table_styles)?;
+
+        let mut memories = PrimaryMap::with_capacity(module.memories.len());
+        for index in module.num_imported_memories..module.memories.len() {
+            let mi = MemoryIndex::new(index);
+            let location = memory_definition_locations[index];
+            let memory = match self.memories.get(&mi) {
+                Some(memory) => {
+                    // The compiled code reads the definition from the
+                    // instance. Static memories never move, so this copy
+                    // stays valid.
+                    *location.as_ptr() = *memory.vmmemory().as_ptr();
+                    memory.clone()
+                }
+                None => tunables
+                    .create_vm_memory(&module.memories[mi], &memory_styles[mi], location)
+                    .map_err(|e| LinkError::Resource(format!("Failed to create memory: {}", e)))?,
+            };
+            memories.push(memory);
+        }
+
+        let mut tables = PrimaryMap::with_capacity(module.tables.len());
+        for index in module.num_imported_tables..module.tables.len() {
+            let ti = TableIndex::new(index);
+            let location = table_definition_locations[index];
+            let table = match self.tables.get(&ti) {
+                Some(table) => {
+                    // Tables at their maximum size never grow, so this
+                    // copy stays valid.
+                    *location.as_ptr() = *table.vmtable().as_ptr();
+                    table.clone()
+                }
+                None => tunables
+                    .create_vm_table(&module.tables[ti], &table_styles[ti], location)
+                    .map_err(LinkError::Resource)?,
+            };
+            tables.push(table);
+        }
+
+        let mut globals = PrimaryMap::with_capacity(module.globals.len());
+        for index in module.num_imported_globals..module.globals.len() {
+            let gi = GlobalIndex::new(index);
+            let global = match self.globals.get(&gi) {
+                Some(global) => global.clone(),
+                None => tunables
+                    .create_global(module.globals[gi])
+                    .map_err(LinkError::Resource)?,
+            };
+            globals.push(global);
+        }
+
+        Ok((memories, tables, globals))
+    }
+}
+
+fn not_local(kind: &str, index: usize) -> LinkError {
+    LinkError::Override(format!(
+        "the module doesn't define a local {} with index {}",
+        kind, index
+    ))
+}
+
+fn incompatible(kind: &str, index: usize, mismatch: ExternTypeMismatch) -> LinkError {
+    LinkError::Override(format!(
+        "the {} provided for index {} has an incompatible {}",
+        kind, index, mismatch
+    ))
+}
@@ -2,9 +2,10 @@
 //! references.
 
 use crate::{Export, ExportFunctionMetadata, ImportError, LinkError};
-use more_asserts::assert_ge;
 use wasmer_types::entity::{BoxedSlice, EntityRef, PrimaryMap};
-use wasmer_types::{ExternType, FunctionIndex, ImportIndex, MemoryIndex, TableIndex};
+use wasmer_types::{
+    ExternType, ExternTypeMismatch, FunctionIndex, ImportIndex, MemoryIndex, TableIndex,
+};
 
 use wasmer_vm::{
     FunctionBodyPtr, ImportFunctionEnv, Imports, MemoryStyle, ModuleInfo, TableStyle,
@@ -153,11 +154,11 @@ pub fn resolve_imports(
             Some(r) => r,
         };
         let export_extern = get_extern_from_export(module, &resolved);
-        if !export_extern.is_compatible_with(&import_extern) {
+        if let Some(mismatch) = export_extern.mismatch_with(&import_extern) {
             return Err(LinkError::Import(
                 module_name.to_string(),
                 field.to_string(),
-                ImportError::IncompatibleType(import_extern, export_extern),
+                ImportError::IncompatibleType(import_extern, export_extern, mismatch),
             ));
         }
         match resolved {
@@ -226,7 +227,11 @@ pub fn resolve_imports(
                         return Err(LinkError::Import(
                             module_name.to_string(),
                             field.to_string(),
-                            ImportError::IncompatibleType(import_extern, export_extern),
+                            ImportError::IncompatibleType(
+                                import_extern,
+                                export_extern,
+                                ExternTypeMismatch::ElementType,
+                            ),
                         ));
                     }
 
@@ -242,24 +247,35 @@ pub fn resolve_imports(
             Export::Memory(ref m) => {
                 match import_index {
                     ImportIndex::Memory(index) => {
-                        // Sanity-check: Ensure that the imported memory has at least
-                        // guard-page protections the importing module expects it to have.
+                        // Ensure that the imported memory has at least the bound and
+                        // guard-page protections the importing module expects it to
+                        // have: its code elides the bounds checks they cover.
                         let export_memory_style = m.style();
                         let import_memory_style = &memory_styles[*index];
-                        if let (
-                            MemoryStyle::Static { bound, .. },
-                            MemoryStyle::Static {
-                                bound: import_bound,
-                                ..
-                            },
-                        ) = (export_memory_style.clone(), &import_memory_style)
+                        let bound_is_compatible = match (export_memory_style, import_memory_style) {
+                            (
+                                MemoryStyle::Static { bound, .. },
+                                MemoryStyle::Static {
+                                    bound: import_bound,
+                                    ..
+                                },
+                            ) => bound >= import_bound,
+                            (MemoryStyle::Dynamic { .. }, MemoryStyle::Static { .. }) => false,
+                            (_, MemoryStyle::Dynamic { .. }) => true,
+                        };
+                        if !bound_is_compatible
+                            || export_memory_style.offset_guard_size()
+                                < import_memory_style.offset_guard_size()
                         {
-                            assert_ge!(bound, *import_bound);
+                            return Err(LinkError::Import(
+                                module_name.to_string(),
+                                field.to_string(),
+                                ImportError::IncompatibleMemoryStyle(
+                                    import_memory_style.clone(),
+                                    export_memory_style.clone(),
+                                ),
+                            ));
                         }
-                        assert_ge!(
-                            export_memory_style.offset_guard_size(),
-                            import_memory_style.offset_guard_size()
-                        );
                     }
                     _ => {
                         // This should never be reached, as we did compatibility
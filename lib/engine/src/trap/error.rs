@@ -18,7 +18,9 @@ enum RuntimeErrorSource {
     Generic(String),
     OOM,
     User(Box<dyn Error + Send + Sync>),
+    CustomTrap(u32, String),
     Trap(TrapCode),
+    HostPanic(String),
 }
 
 impl fmt::Display for RuntimeErrorSource {
@@ -26,15 +28,35 @@ impl fmt::Display for RuntimeErrorSource {
         match self {
             Self::Generic(s) => write!(f, "{}", s),
             Self::User(s) => write!(f, "{}", s),
+            Self::CustomTrap(_, s) => write!(f, "{}", s),
             Self::OOM => write!(f, "Wasmer VM out of memory"),
             Self::Trap(s) => write!(f, "{}", s.message()),
+            Self::HostPanic(s) => write!(f, "host function panicked: {}", s),
         }
     }
 }
 
+/// The raw machine state of a trap raised by generated code, for
+/// integrating with crash reporters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TrapInfo {
+    /// The program counter of the faulting instruction.
+    pub pc: usize,
+    /// The signal number (or, on Windows, the exception code) that was
+    /// raised.
+    pub signal: i32,
+    /// The trap code the fault was resolved to.
+    pub trap_code: TrapCode,
+    /// The offset in the Wasm module of the instruction that trapped,
+    /// if the faulting function is known.
+    pub module_offset: Option<usize>,
+}
+
 struct RuntimeErrorInner {
     /// The source error (this can be a custom user `Error` or a [`TrapCode`])
     source: RuntimeErrorSource,
+    /// The raw trap state, if the error is a trap of generated code.
+    trap_info: Option<TrapInfo>,
     /// The reconstructed Wasm trace (from the native trace and the `GlobalFrameInfo`).
     wasm_trace: Vec<FrameInfo>,
     /// The native backtrace
@@ -85,18 +107,42 @@ impl RuntimeError {
             Trap::OOM { backtrace } => {
                 Self::new_with_trace(&info, None, RuntimeErrorSource::OOM, backtrace)
             }
+            // A panic of a host function, caught on the way out of wasm
+            Trap::HostPanic { message, backtrace } => Self::new_with_trace(
+                &info,
+                None,
+                RuntimeErrorSource::HostPanic(message),
+                backtrace,
+            ),
             // A trap caused by an error on the generated machine code for a Wasm function
             Trap::Wasm {
                 pc,
+                signal,
                 signal_trap,
                 backtrace,
             } => {
-                let code = info
-                    .lookup_trap_info(pc)
-                    .map_or(signal_trap.unwrap_or(TrapCode::StackOverflow), |info| {
-                        info.trap_code
-                    });
-                Self::new_with_trace(&info, Some(pc), RuntimeErrorSource::Trap(code), backtrace)
+                let code = if info.is_pruned_function(pc) {
+                    TrapCode::FunctionPruned
+                } else {
+                    info.lookup_trap_info(pc)
+                        .map_or(signal_trap.unwrap_or(TrapCode::StackOverflow), |info| {
+                            info.trap_code
+                        })
+                };
+                let trap_info = TrapInfo {
+                    pc,
+                    signal,
+                    trap_code: code,
+                    module_offset: info
+                        .lookup_frame_info(pc)
+                        .map(|frame| frame.module_offset()),
+                };
+                Self::new_with_trace(
+                    &info,
+                    Some(trap_info),
+                    RuntimeErrorSource::Trap(code),
+                    backtrace,
+                )
             }
             // A trap triggered manually from the Wasmer runtime
             Trap::Lib {
@@ -106,17 +152,54 @@ impl RuntimeError {
         }
     }
 
+    /// Creates a new `RuntimeError` from a custom user Error, which
+    /// can be recovered with [`RuntimeError::downcast`].
+    pub fn user(error: Box<dyn Error + Send + Sync>) -> Self {
+        Self::from_trap(Trap::User(error))
+    }
+
     /// Raises a custom user Error
     pub fn raise(error: Box<dyn Error + Send + Sync>) -> ! {
         unsafe { raise_user_trap(error) }
     }
 
+    /// Creates a new `RuntimeError` with a trap code defined by the
+    /// embedder, returned by [`RuntimeError::custom_trap_code`].
+    ///
+    /// Returned by a host function, or raised with
+    /// [`RuntimeError::raise_custom_trap`], it unwinds the Wasm frames
+    /// like a trap, and its trace holds the Wasm frames that led to
+    /// the host function.
+    ///
+    /// # Example
+    /// ```
+    /// let trap = wasmer_engine::RuntimeError::custom_trap(42, "quota exceeded");
+    /// assert_eq!(trap.custom_trap_code(), Some(42));
+    /// assert_eq!(trap.message(), "quota exceeded");
+    /// ```
+    pub fn custom_trap<I: Into<String>>(code: u32, message: I) -> Self {
+        let info = FRAME_INFO.read().unwrap();
+        Self::new_with_trace(
+            &info,
+            None,
+            RuntimeErrorSource::CustomTrap(code, message.into()),
+            Backtrace::new_unresolved(),
+        )
+    }
+
+    /// Raises a trap with a code defined by the embedder, see
+    /// [`RuntimeError::custom_trap`].
+    pub fn raise_custom_trap<I: Into<String>>(code: u32, message: I) -> ! {
+        Self::raise(Box::new(Self::custom_trap(code, message)))
+    }
+
     fn new_with_trace(
         info: &GlobalFrameInfo,
-        trap_pc: Option<usize>,
+        trap_info: Option<TrapInfo>,
         source: RuntimeErrorSource,
         native_trace: Backtrace,
     ) -> Self {
+        let trap_pc = trap_info.map(|trap_info| trap_info.pc);
         let frames: Vec<usize> = native_trace
             .frames()
             .iter()
@@ -141,15 +224,18 @@ impl RuntimeError {
             })
             .collect();
 
-        // Let's construct the trace
+        // Let's construct the trace, out of the frames of the functions
+        // of the modules: the trampolines calling them aren't part of it.
         let wasm_trace = frames
             .into_iter()
             .filter_map(|pc| info.lookup_frame_info(pc))
+            .filter(|frame| frame.trampoline_signature().is_none())
             .collect::<Vec<_>>();
 
         Self {
             inner: Arc::new(RuntimeErrorInner {
                 source,
+                trap_info,
                 wasm_trace,
                 native_trace,
             }),
@@ -167,6 +253,12 @@ impl RuntimeError {
         &self.inner.wasm_trace
     }
 
+    /// Returns the raw state of the trap, if this error was raised by a
+    /// fault in generated code.
+    pub fn trap_info(&self) -> Option<&TrapInfo> {
+        self.inner.trap_info.as_ref()
+    }
+
     /// Attempts to downcast the `RuntimeError` to a concrete type.
     pub fn downcast<T: Error + 'static>(self) -> Result<T, Self> {
         match Arc::try_unwrap(self.inner) {
@@ -184,6 +276,15 @@ impl RuntimeError {
 
     /// Returns trap code, if it's a Trap
     pub fn to_trap(self) -> Option<TrapCode> {
+        self.trap_code()
+    }
+
+    /// Returns the [`TrapCode`] describing why the execution trapped,
+    /// if the error is a trap rather than an error raised by the host.
+    ///
+    /// This allows handling some traps, such as
+    /// [`TrapCode::IntegerDivisionByZero`], specially.
+    pub fn trap_code(&self) -> Option<TrapCode> {
         if let RuntimeErrorSource::Trap(trap_code) = self.inner.source {
             Some(trap_code)
         } else {
@@ -191,6 +292,24 @@ impl RuntimeError {
         }
     }
 
+    /// Returns the code of the trap defined by the embedder this error
+    /// was created with, see [`RuntimeError::custom_trap`].
+    pub fn custom_trap_code(&self) -> Option<u32> {
+        match self.inner.source {
+            RuntimeErrorSource::CustomTrap(code, _) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Returns the message of the host function panic this error was
+    /// created from, if any.
+    pub fn host_panic(&self) -> Option<&str> {
+        match &self.inner.source {
+            RuntimeErrorSource::HostPanic(message) => Some(message),
+            _ => None,
+        }
+    }
+
     /// Returns true if the `RuntimeError` is the same as T
     pub fn is<T: Error + 'static>(&self) -> bool {
         match &self.inner.source {
@@ -204,6 +323,7 @@ impl fmt::Debug for RuntimeError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RuntimeError")
             .field("source", &self.inner.source)
+            .field("trap_info", &self.inner.trap_info)
             .field("wasm_trace", &self.inner.wasm_trace)
             .field("native_trace", &self.inner.native_trace)
             .finish()
@@ -236,6 +356,9 @@ impl fmt::Display for RuntimeError {
                 func_index,
                 frame.module_offset()
             )?;
+            if let Some((file, line)) = frame.source_location() {
+                write!(f, " at {}:{}", file, line)?;
+            }
         }
         Ok(())
     }
@@ -10,13 +10,16 @@
 //! let module: ModuleInfo = ...;
 //! FRAME_INFO.register(module, compiled_functions);
 //! ```
+use super::source_lines::SourceLines;
 use loupe::MemoryUsage;
 use std::cmp;
 use std::collections::BTreeMap;
 use std::sync::{Arc, RwLock};
-use wasmer_compiler::{CompiledFunctionFrameInfo, SourceLoc, TrapInformation};
+use wasmer_compiler::{
+    pruned_functions, CompiledFunctionFrameInfo, FunctionAddressMap, SourceLoc, TrapInformation,
+};
 use wasmer_types::entity::{BoxedSlice, EntityRef, PrimaryMap};
-use wasmer_types::LocalFunctionIndex;
+use wasmer_types::{FunctionIndex, LocalFunctionIndex, SignatureIndex};
 use wasmer_vm::{FunctionBodyPtr, ModuleInfo};
 
 lazy_static::lazy_static! {
@@ -44,10 +47,13 @@ pub struct GlobalFrameInfo {
 
 /// Returns whether the `pc`, according to globally registered information,
 /// is a wasm trap or not.
+///
+/// The function call trampolines of the modules are run by the host,
+/// so their `pc`s aren't wasm ones.
 pub fn is_wasm_pc(pc: usize) -> bool {
     let frame_info = FRAME_INFO.read().unwrap();
     let module_info = frame_info.module_info(pc);
-    module_info.is_some()
+    module_info.map_or(false, |module| module.trampoline_info(pc).is_none())
 }
 
 /// An RAII structure used to unregister a module's frame information when the
@@ -63,8 +69,19 @@ pub struct GlobalFrameInfoRegistration {
 struct ModuleInfoFrameInfo {
     start: usize,
     functions: BTreeMap<usize, FunctionInfo>,
+    /// The function call trampolines of the module, keyed by their end
+    /// like `functions`.
+    trampolines: BTreeMap<usize, TrampolineInfo>,
     module: Arc<ModuleInfo>,
     frame_infos: PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>,
+    /// Whether `frame_infos` have address maps to symbolicate frames.
+    address_maps: bool,
+    /// The functions pruned from the module, sorted, read from its
+    /// custom section when it is registered.
+    pruned_functions: Vec<FunctionIndex>,
+    /// The source lines of the module, read from its DWARF debug
+    /// information by [`GlobalFrameInfoRegistration::add_source_lines`].
+    source_lines: Option<SourceLines>,
 }
 
 impl ModuleInfoFrameInfo {
@@ -72,6 +89,14 @@ impl ModuleInfoFrameInfo {
         &self.frame_infos.get(local_index).unwrap()
     }
 
+    /// Returns the source file and line of the instruction at
+    /// `module_offset`, if the module has debug information covering it.
+    fn source_location(&self, module_offset: usize) -> Option<(Arc<str>, u32)> {
+        self.source_lines
+            .as_ref()?
+            .lookup(&self.module, module_offset)
+    }
+
     /// Gets a function given a pc
     fn function_info(&self, pc: usize) -> Option<&FunctionInfo> {
         let (end, func) = self.functions.range(pc..).next()?;
@@ -81,6 +106,16 @@ impl ModuleInfoFrameInfo {
             None
         }
     }
+
+    /// Gets a function call trampoline given a pc
+    fn trampoline_info(&self, pc: usize) -> Option<&TrampolineInfo> {
+        let (end, trampoline) = self.trampolines.range(pc..).next()?;
+        if trampoline.start <= pc && pc < *end {
+            Some(trampoline)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -89,6 +124,12 @@ struct FunctionInfo {
     local_index: LocalFunctionIndex,
 }
 
+#[derive(Debug)]
+struct TrampolineInfo {
+    start: usize,
+    address_map: FunctionAddressMap,
+}
+
 impl GlobalFrameInfo {
     /// Fetches frame information about a program counter in a backtrace.
     ///
@@ -96,6 +137,20 @@ impl GlobalFrameInfo {
     /// module, or returns `None` if no information can be found.
     pub fn lookup_frame_info(&self, pc: usize) -> Option<FrameInfo> {
         let module = self.module_info(pc)?;
+        if !module.address_maps {
+            return None;
+        }
+        if let Some(trampoline) = module.trampoline_info(pc) {
+            let srcloc = trampoline.address_map.start_srcloc;
+            return Some(FrameInfo {
+                module_name: module.module.name(),
+                func_index: u32::MAX,
+                function_name: None,
+                instr: srcloc,
+                func_start: srcloc,
+                source_location: None,
+            });
+        }
         let func = module.function_info(pc)?;
 
         // Use our relative position from the start of the function to find the
@@ -144,6 +199,7 @@ impl GlobalFrameInfo {
             function_name: module.module.function_names.get(&func_index).cloned(),
             instr,
             func_start: instr_map.start_srcloc,
+            source_location: module.source_location(instr.bits() as usize),
         })
     }
 
@@ -158,6 +214,21 @@ impl GlobalFrameInfo {
         Some(&traps[idx])
     }
 
+    /// Returns whether `pc` is in a function that was pruned from its
+    /// module with [`wasmer_compiler::prune_functions`].
+    pub fn is_pruned_function(&self, pc: usize) -> bool {
+        let module = match self.module_info(pc) {
+            Some(module) => module,
+            None => return false,
+        };
+        let func = match module.function_info(pc) {
+            Some(func) => func,
+            None => return false,
+        };
+        let index = module.module.func_index(func.local_index);
+        module.pruned_functions.binary_search(&index).is_ok()
+    }
+
     /// Gets a module given a pc
     fn module_info(&self, pc: usize) -> Option<&ModuleInfoFrameInfo> {
         let (end, module_info) = self.ranges.range(pc..).next()?;
@@ -169,6 +240,85 @@ impl GlobalFrameInfo {
     }
 }
 
+impl GlobalFrameInfoRegistration {
+    /// Registers the function call trampolines of the module, with the
+    /// address maps made by [`FunctionAddressMap::trampoline`], so that
+    /// [`GlobalFrameInfo::lookup_frame_info`] reports their frames as
+    /// trampoline frames rather than leaving them out.
+    ///
+    /// The trampolines are expected to be next to the functions of the
+    /// module. Those that would make its range overlap the range of
+    /// another module are left out.
+    pub fn add_trampolines(&mut self, trampolines: Vec<(FunctionExtent, FunctionAddressMap)>) {
+        let mut info = FRAME_INFO.write().unwrap();
+        let mut module = match info.ranges.remove(&self.key) {
+            Some(module) => module,
+            None => return,
+        };
+        for (extent, address_map) in trampolines {
+            let start = *extent.ptr as usize;
+            let end = start + extent.length;
+            let min = cmp::min(module.start, start);
+            let max = cmp::max(self.key, end);
+            // The first module ending after `min` is the only one that
+            // could overlap.
+            if let Some((_, next)) = info.ranges.range(min..).next() {
+                if next.start <= max {
+                    continue;
+                }
+            }
+            module.start = min;
+            self.key = max;
+            module
+                .trampolines
+                .insert(end, TrampolineInfo { start, address_map });
+        }
+        info.ranges.insert(self.key, module);
+    }
+
+    /// Reads the source lines of the module from its DWARF debug
+    /// information, so that its frames report their
+    /// [`source_location`](FrameInfo::source_location).
+    ///
+    /// The debug information is read once, here, and not when traps
+    /// are symbolicated. Without it, frames have no source location.
+    pub fn add_source_lines(&self) {
+        let module = match FRAME_INFO.read().unwrap().ranges.get(&self.key) {
+            Some(module) => module.module.clone(),
+            None => return,
+        };
+        // Read the debug information without holding the lock.
+        let source_lines = SourceLines::new(&module);
+        let mut info = FRAME_INFO.write().unwrap();
+        if let Some(module) = info.ranges.get_mut(&self.key) {
+            module.source_lines = Some(source_lines);
+        }
+    }
+
+    /// Returns whether the registered frame information has address
+    /// maps, to symbolicate the frames of the module in backtraces.
+    pub fn has_address_maps(&self) -> bool {
+        let info = FRAME_INFO.read().unwrap();
+        info.ranges
+            .get(&self.key)
+            .map_or(false, |module| module.address_maps)
+    }
+
+    /// Replaces the trap information registered by [`register_trap_info`]
+    /// with the complete frame information of the module, so that its
+    /// frames appear in backtraces.
+    pub fn add_address_maps(
+        &self,
+        frame_infos: PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>,
+    ) {
+        let mut info = FRAME_INFO.write().unwrap();
+        if let Some(module) = info.ranges.get_mut(&self.key) {
+            module.frame_infos = frame_infos;
+            module.address_maps = true;
+        }
+    }
+}
+
 impl Drop for GlobalFrameInfoRegistration {
     fn drop(&mut self) {
         if let Ok(mut info) = FRAME_INFO.write() {
@@ -199,6 +349,49 @@ pub fn register(
     module: Arc<ModuleInfo>,
     finished_functions: &BoxedSlice<LocalFunctionIndex, FunctionExtent>,
     frame_infos: PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>,
+) -> Option<GlobalFrameInfoRegistration> {
+    register_impl(module, finished_functions, frame_infos, true)
+}
+
+/// Like [`register`], for a module compiled without address maps.
+///
+/// Traps in the module are still recognized and keep their trap code,
+/// but its frames are left out of backtraces.
+pub fn register_without_address_maps(
+    module: Arc<ModuleInfo>,
+    finished_functions: &BoxedSlice<LocalFunctionIndex, FunctionExtent>,
+    frame_infos: PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>,
+) -> Option<GlobalFrameInfoRegistration> {
+    register_impl(module, finished_functions, frame_infos, false)
+}
+
+/// Like [`register_without_address_maps`], but only keeps the trap
+/// information of `frame_infos`, which is all that is needed to
+/// recognize the traps of the module and report their trap codes.
+///
+/// This is cheaper than registering the complete frame information,
+/// which can be added afterwards with
+/// [`GlobalFrameInfoRegistration::add_address_maps`].
+pub fn register_trap_info(
+    module: Arc<ModuleInfo>,
+    finished_functions: &BoxedSlice<LocalFunctionIndex, FunctionExtent>,
+    frame_infos: &PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>,
+) -> Option<GlobalFrameInfoRegistration> {
+    let frame_infos = frame_infos
+        .values()
+        .map(|info| CompiledFunctionFrameInfo {
+            traps: info.traps.clone(),
+            address_map: Default::default(),
+        })
+        .collect();
+    register_impl(module, finished_functions, frame_infos, false)
+}
+
+fn register_impl(
+    module: Arc<ModuleInfo>,
+    finished_functions: &BoxedSlice<LocalFunctionIndex, FunctionExtent>,
+    frame_infos: PrimaryMap<LocalFunctionIndex, CompiledFunctionFrameInfo>,
+    address_maps: bool,
 ) -> Option<GlobalFrameInfoRegistration> {
     let mut min = usize::max_value();
     let mut max = 0;
@@ -225,6 +418,9 @@ pub fn register(
         return None;
     }
 
+    let mut pruned_functions = pruned_functions(&module);
+    pruned_functions.sort_unstable();
+
     let mut info = FRAME_INFO.write().unwrap();
     // First up assert that our chunk of jit functions doesn't collide with
     // any other known chunks of jit functions...
@@ -241,8 +437,12 @@ pub fn register(
         ModuleInfoFrameInfo {
             start: min,
             functions,
+            trampolines: BTreeMap::new(),
             module,
             frame_infos,
+            address_maps,
+            pruned_functions,
+            source_lines: None,
         },
     );
     assert!(prev.is_none());
@@ -264,6 +464,7 @@ pub struct FrameInfo {
     function_name: Option<String>,
     func_start: SourceLoc,
     instr: SourceLoc,
+    source_location: Option<(Arc<str>, u32)>,
 }
 
 impl FrameInfo {
@@ -312,6 +513,9 @@ impl FrameInfo {
     /// The offset here is the offset from the beginning of the original wasm
     /// module to the instruction that this frame points to.
     pub fn module_offset(&self) -> usize {
+        if self.trampoline_signature().is_some() {
+            return 0;
+        }
         self.instr.bits() as usize
     }
 
@@ -324,4 +528,31 @@ impl FrameInfo {
     pub fn func_offset(&self) -> usize {
         (self.instr.bits() - self.func_start.bits()) as usize
     }
+
+    /// Returns the file and line of the original source this frame's
+    /// program counter was at.
+    ///
+    /// They are resolved from the DWARF debug information that the
+    /// compiler of the source language embedded in the custom sections
+    /// of the module, through its line programs. This returns `None`
+    /// if the module has no debug information covering the frame, or
+    /// if the engine didn't read it, see
+    /// [`GlobalFrameInfoRegistration::add_source_lines`]. Only the
+    /// function index and offsets are known then.
+    pub fn source_location(&self) -> Option<(&str, u32)> {
+        self.source_location
+            .as_ref()
+            .map(|(file, line)| (&**file, *line))
+    }
+
+    /// Returns the signature of the function call trampoline this frame
+    /// is in, or `None` if it is in a function of the module.
+    ///
+    /// Trampolines run the functions of the module on behalf of the
+    /// host, so their frames are told apart to avoid attributing them
+    /// to a function of the module. They have no function index, which
+    /// is reported as `u32::MAX`, and their offsets are 0.
+    pub fn trampoline_signature(&self) -> Option<SignatureIndex> {
+        self.func_start.trampoline_signature()
+    }
 }
@@ -1,7 +1,9 @@
 mod error;
 mod frame_info;
-pub use error::RuntimeError;
+mod source_lines;
+pub use error::{RuntimeError, TrapInfo};
 pub use frame_info::{
-    is_wasm_pc, register as register_frame_info, FrameInfo, FunctionExtent,
-    GlobalFrameInfoRegistration, FRAME_INFO,
+    is_wasm_pc, register as register_frame_info, register_trap_info,
+    register_without_address_maps as register_frame_info_without_address_maps, FrameInfo,
+    FunctionExtent, GlobalFrameInfoRegistration, FRAME_INFO,
 };
@@ -0,0 +1,125 @@
+//! Resolution of the offsets of a WebAssembly module to the lines of
+//! its original source, from the DWARF debug information embedded in
+//! its custom sections by the compiler of the source language.
+
+use gimli::{EndianSlice, LittleEndian, SectionId};
+use std::sync::Arc;
+use wasmer_vm::ModuleInfo;
+
+type Reader<'a> = EndianSlice<'a, LittleEndian>;
+
+/// The rows of the DWARF line programs of a module, sorted by address.
+#[derive(Debug, Default)]
+pub(crate) struct SourceLines {
+    /// The source files the rows refer to.
+    files: Vec<Arc<str>>,
+    /// The address each row starts at, relative to the contents of the
+    /// code section, and its file and line. Rows that end a sequence,
+    /// or have no line, cover no source. Addresses are unique.
+    rows: Vec<(u64, Option<(usize, u32)>)>,
+}
+
+impl SourceLines {
+    /// Reads the line programs of `module`, if it has any.
+    ///
+    /// Debug information that can't be read is ignored: it only covers
+    /// less of the module.
+    pub(crate) fn new(module: &ModuleInfo) -> Self {
+        let mut lines = Self::default();
+        if module.custom_sections.contains_key(".debug_line") {
+            // Stop at the first error, keeping the rows read so far.
+            let _ = lines.read(module);
+            // Several rows can start at the same address: a sequence can
+            // end where another one starts, and the last row of a program
+            // at an address is the one that applies. Keep a single row per
+            // address, preferring the last one covering some source.
+            lines
+                .rows
+                .sort_by_key(|(address, location)| (*address, location.is_some()));
+            lines.rows.dedup_by(|row, kept| {
+                if row.0 != kept.0 {
+                    return false;
+                }
+                *kept = *row;
+                true
+            });
+        }
+        lines
+    }
+
+    fn read(&mut self, module: &ModuleInfo) -> Result<(), gimli::Error> {
+        let dwarf = gimli::Dwarf::load(|id: SectionId| -> Result<Reader, gimli::Error> {
+            let data = match module.custom_sections.get(id.name()) {
+                Some(index) => &module.custom_sections_data[*index][..],
+                None => &[],
+            };
+            Ok(EndianSlice::new(data, LittleEndian))
+        })?;
+
+        let mut units = dwarf.units();
+        while let Some(header) = units.next()? {
+            let unit = dwarf.unit(header)?;
+            let program = match unit.line_program.clone() {
+                Some(program) => program,
+                None => continue,
+            };
+            let mut rows = program.rows();
+            while let Some((header, row)) = rows.next_row()? {
+                let line = row.line().map(u64::from).unwrap_or(0);
+                if row.end_sequence() || line == 0 {
+                    self.rows.push((row.address(), None));
+                    continue;
+                }
+                let file = match row.file(header) {
+                    Some(file) => file,
+                    None => {
+                        self.rows.push((row.address(), None));
+                        continue;
+                    }
+                };
+                let mut path = dwarf
+                    .attr_string(&unit, file.path_name())?
+                    .to_string_lossy()
+                    .into_owned();
+                if let Some(directory) = file.directory(header) {
+                    let directory = dwarf.attr_string(&unit, directory)?.to_string_lossy();
+                    if !directory.is_empty() && !path.starts_with('/') {
+                        path = format!("{}/{}", directory.trim_end_matches('/'), path);
+                    }
+                }
+                let file = match self.files.iter().position(|known| **known == *path) {
+                    Some(file) => file,
+                    None => {
+                        self.files.push(path.into());
+                        self.files.len() - 1
+                    }
+                };
+                self.rows.push((row.address(), Some((file, line as u32))));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the source file and line of the instruction at
+    /// `module_offset` in the Wasm binary of `module`, if the debug
+    /// information covers it.
+    pub(crate) fn lookup(
+        &self,
+        module: &ModuleInfo,
+        module_offset: usize,
+    ) -> Option<(Arc<str>, u32)> {
+        let address = module_offset.checked_sub(module.code_section_offset)? as u64;
+        let row = match self
+            .rows
+            .binary_search_by_key(&address, |(address, _)| *address)
+        {
+            Ok(row) => row,
+            // Before the first row.
+            Err(0) => return None,
+            // In the row starting before `address`.
+            Err(row) => row - 1,
+        };
+        let (file, line) = self.rows[row].1?;
+        Some((self.files[file].clone(), line))
+    }
+}
@@ -16,8 +16,32 @@ use wasmer_vm::{VMMemoryDefinition, VMTableDefinition};
 /// to a foreign implementor of this trait.
 pub trait Tunables: MemoryUsage {
     /// Construct a `MemoryStyle` for the provided `MemoryType`
+    ///
+    /// Implementations should take the size of the offset guard of the
+    /// style from [`Tunables::static_memory_guard_size`] or
+    /// [`Tunables::dynamic_memory_guard_size`], so that overriding these
+    /// is enough to change it.
     fn memory_style(&self, memory: &MemoryType) -> MemoryStyle;
 
+    /// The size in bytes of the offset guard reserved after `memory`
+    /// when it is static.
+    ///
+    /// Larger guards let the compilers elide more bounds checks, at the
+    /// cost of address space. It is used by [`Tunables::memory_style`],
+    /// so it must not call it. The default implementation returns 2 GiB.
+    fn static_memory_guard_size(&self, _memory: &MemoryType) -> u64 {
+        0x8000_0000
+    }
+
+    /// The size in bytes of the offset guard reserved after `memory`
+    /// when it is dynamic.
+    ///
+    /// The same rules as for [`Tunables::static_memory_guard_size`]
+    /// apply. The default implementation returns 64 KiB.
+    fn dynamic_memory_guard_size(&self, _memory: &MemoryType) -> u64 {
+        0x1_0000
+    }
+
     /// Construct a `TableStyle` for the provided `TableType`
     fn table_style(&self, table: &TableType) -> TableStyle;
 
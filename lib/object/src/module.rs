@@ -5,6 +5,7 @@ use object::write::{
 use object::{
     elf, RelocationEncoding, RelocationKind, SectionKind, SymbolFlags, SymbolKind, SymbolScope,
 };
+use std::collections::hash_map::{Entry, HashMap};
 use wasmer_compiler::{
     Architecture, BinaryFormat, Compilation, CustomSectionProtection, Endianness,
     RelocationKind as Reloc, RelocationTarget, SectionIndex, Symbol, SymbolRegistry, Triple,
@@ -219,7 +220,9 @@ pub fn emit_compilation(
         })
         .collect::<PrimaryMap<LocalFunctionIndex, _>>();
 
-    // Add function call trampolines
+    // Add function call trampolines. Structurally equal signatures get
+    // identical trampolines, so their symbols share a single body.
+    let mut trampoline_offsets = HashMap::new();
     for (signature_index, function) in function_call_trampolines.into_iter() {
         let function_name =
             symbol_registry.symbol_to_name(Symbol::FunctionCallTrampoline(signature_index));
@@ -234,7 +237,16 @@ pub fn emit_compilation(
             section: SymbolSection::Section(section_id),
             flags: SymbolFlags::None,
         });
-        obj.add_symbol_data(symbol_id, section_id, &function.body, align);
+        let size = function.body.len() as u64;
+        match trampoline_offsets.entry(function.body) {
+            Entry::Occupied(entry) => {
+                obj.set_symbol_data(symbol_id, section_id, *entry.get(), size)
+            }
+            Entry::Vacant(entry) => {
+                let offset = obj.add_symbol_data(symbol_id, section_id, entry.key(), align);
+                entry.insert(offset);
+            }
+        }
     }
 
     // Add dynamic function trampolines
@@ -1,4 +1,4 @@
-use crate::indexes::{FunctionIndex, GlobalIndex, MemoryIndex, TableIndex};
+use crate::indexes::{DataIndex, FunctionIndex, GlobalIndex, MemoryIndex, TableIndex};
 use crate::lib::std::boxed::Box;
 use loupe::MemoryUsage;
 
@@ -33,6 +33,9 @@ pub struct TableInitializer {
     derive(RkyvSerialize, RkyvDeserialize, Archive)
 )]
 pub struct DataInitializerLocation {
+    /// The index of the data segment in the module.
+    pub data_index: DataIndex,
+
     /// The index of the memory to initialize.
     pub memory_index: MemoryIndex,
 
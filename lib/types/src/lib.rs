@@ -78,15 +78,15 @@ pub use crate::indexes::{
 pub use crate::initializers::{
     DataInitializer, DataInitializerLocation, OwnedDataInitializer, TableInitializer,
 };
-pub use crate::memory_view::{Atomically, MemoryView};
+pub use crate::memory_view::{Atomically, MemoryView, MemoryViewError};
 pub use crate::native::{NativeWasmType, ValueType};
 pub use crate::units::{
     Bytes, PageCountOutOfRange, Pages, WASM_MAX_PAGES, WASM_MIN_PAGES, WASM_PAGE_SIZE,
 };
 pub use crate::values::{Value, WasmValueType};
 pub use types::{
-    ExportType, ExternType, FunctionType, GlobalInit, GlobalType, ImportType, MemoryType,
-    Mutability, TableType, Type, V128,
+    ExportType, ExternType, ExternTypeMismatch, FunctionType, FunctionTypeBuilder, GlobalInit,
+    GlobalType, ImportType, MemoryType, Mutability, TableType, Type, V128,
 };
 
 #[cfg(feature = "enable-rkyv")]
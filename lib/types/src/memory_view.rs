@@ -1,12 +1,15 @@
 use crate::lib::std::cell::Cell;
 use crate::lib::std::marker::PhantomData;
+use crate::lib::std::mem;
 use crate::lib::std::ops::Deref;
-use crate::lib::std::ops::{Bound, RangeBounds};
+use crate::lib::std::ops::{Bound, Range, RangeBounds};
 use crate::lib::std::slice;
 use crate::lib::std::sync::atomic::{
     AtomicI16, AtomicI32, AtomicI64, AtomicI8, AtomicU16, AtomicU32, AtomicU64, AtomicU8,
 };
+use crate::lib::std::vec::Vec;
 use crate::native::ValueType;
+use thiserror::Error;
 
 pub trait Atomic {
     type Output;
@@ -46,6 +49,27 @@ impl Atomicity for Atomically {}
 pub struct NonAtomically;
 impl Atomicity for NonAtomically {}
 
+/// The error returned by the checked accesses of a [`MemoryView`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum MemoryViewError {
+    /// The index is past the end of the view.
+    #[error("index {index} is out of bounds of a view of {length} elements")]
+    OutOfBounds {
+        /// The index that was accessed.
+        index: usize,
+        /// The number of elements in the view.
+        length: usize,
+    },
+    /// The element at the index is not properly aligned for its type.
+    #[error("address {address:#x} is not aligned to {align} bytes")]
+    Misaligned {
+        /// The address of the element.
+        address: usize,
+        /// The alignment required by the type of the element.
+        align: usize,
+    },
+}
+
 /// A view into a memory.
 pub struct MemoryView<'a, T: 'a, A = NonAtomically> {
     ptr: *mut T,
@@ -95,6 +119,68 @@ where
         }
     }
 
+    /// Returns a pointer to the element at `index`, after checking that
+    /// it is in bounds and aligned.
+    fn checked_ptr(&self, index: usize) -> Result<*mut T, MemoryViewError> {
+        if index >= self.length {
+            return Err(MemoryViewError::OutOfBounds {
+                index,
+                length: self.length,
+            });
+        }
+        let ptr = unsafe { self.ptr.add(index) };
+        let align = mem::align_of::<T>();
+        if ptr as usize % align != 0 {
+            return Err(MemoryViewError::Misaligned {
+                address: ptr as usize,
+                align,
+            });
+        }
+        Ok(ptr)
+    }
+
+    /// Reads the element at `index`.
+    ///
+    /// Unlike indexing the view, this returns an error instead of
+    /// panicking when `index` is out of bounds, and checks that the
+    /// element is aligned for `T`. Alignment is checked on every
+    /// target, so that a guest behaves the same everywhere.
+    pub fn read(&self, index: usize) -> Result<T, MemoryViewError> {
+        let ptr = self.checked_ptr(index)?;
+        Ok(unsafe { ptr.read() })
+    }
+
+    /// Writes `value` to the element at `index`.
+    ///
+    /// See [`MemoryView::read`] for the checks that are done.
+    pub fn write(&self, index: usize, value: T) -> Result<(), MemoryViewError> {
+        let ptr = self.checked_ptr(index)?;
+        unsafe { ptr.write(value) };
+        Ok(())
+    }
+
+    /// Copies the elements in `range` into a new vector.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds of the view, like slice
+    /// indexing does.
+    pub fn copy_to_vec(&self, range: Range<usize>) -> Vec<T> {
+        self[range].iter().map(Cell::get).collect()
+    }
+
+    /// Sets the elements in `range` to `value`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `range` is out of bounds of the view, like slice
+    /// indexing does.
+    pub fn fill(&self, range: Range<usize>, value: T) {
+        for cell in &self[range] {
+            cell.set(value);
+        }
+    }
+
     /// Copy the contents of the source slice into this `MemoryView`.
     ///
     /// This function will efficiently copy the memory from within the wasm
@@ -55,6 +55,19 @@ impl Type {
     pub fn is_ref(self) -> bool {
         matches!(self, Self::ExternRef | Self::FuncRef)
     }
+
+    /// The byte encoding this type in the WebAssembly binary format.
+    fn binary_code(self) -> u8 {
+        match self {
+            Self::I32 => 0x7f,
+            Self::I64 => 0x7e,
+            Self::F32 => 0x7d,
+            Self::F64 => 0x7c,
+            Self::V128 => 0x7b,
+            Self::FuncRef => 0x70,
+            Self::ExternRef => 0x6f,
+        }
+    }
 }
 
 impl fmt::Display for Type {
@@ -134,17 +147,71 @@ pub enum ExternType {
     Memory(MemoryType),
 }
 
-fn is_global_compatible(exported: GlobalType, imported: GlobalType) -> bool {
-    let GlobalType {
-        ty: exported_ty,
-        mutability: exported_mutability,
-    } = exported;
-    let GlobalType {
-        ty: imported_ty,
-        mutability: imported_mutability,
-    } = imported;
+/// The part of an [`ExternType`] that prevents it from being provided
+/// for an import requesting another `ExternType`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExternTypeMismatch {
+    /// The kinds of extern differ, e.g. a global is provided for a
+    /// function import.
+    Kind,
+    /// The signatures of the functions differ.
+    Signature,
+    /// The value types of the globals differ.
+    ValueType,
+    /// The mutabilities of the globals differ.
+    Mutability,
+    /// The element types of the tables differ.
+    ElementType,
+    /// The minimum size is smaller than the requested one.
+    Minimum,
+    /// The maximum size is missing or larger than the requested one.
+    Maximum,
+    /// One memory is shared and the other one isn't.
+    Shared,
+}
+
+impl fmt::Display for ExternTypeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Kind => "kind",
+            Self::Signature => "signature",
+            Self::ValueType => "value type",
+            Self::Mutability => "mutability",
+            Self::ElementType => "element type",
+            Self::Minimum => "minimum",
+            Self::Maximum => "maximum",
+            Self::Shared => "shared flag",
+        })
+    }
+}
 
-    exported_ty == imported_ty && imported_mutability == exported_mutability
+/// Returns the `ExternTypeMismatch::Minimum` or `Maximum` mismatch of a
+/// provided extern sized `minimum..maximum`, for an import requesting
+/// `requested_minimum..requested_maximum`, if any.
+fn limits_mismatch<T: PartialOrd>(
+    minimum: T,
+    maximum: Option<T>,
+    requested_minimum: T,
+    requested_maximum: Option<T>,
+) -> Option<ExternTypeMismatch> {
+    if minimum < requested_minimum {
+        return Some(ExternTypeMismatch::Minimum);
+    }
+    match (maximum, requested_maximum) {
+        (_, None) => None,
+        (Some(maximum), Some(requested_maximum)) if maximum <= requested_maximum => None,
+        _ => Some(ExternTypeMismatch::Maximum),
+    }
+}
+
+fn global_mismatch(exported: GlobalType, imported: GlobalType) -> Option<ExternTypeMismatch> {
+    if exported.ty != imported.ty {
+        Some(ExternTypeMismatch::ValueType)
+    } else if exported.mutability != imported.mutability {
+        Some(ExternTypeMismatch::Mutability)
+    } else {
+        None
+    }
 }
 
 fn is_table_element_type_compatible(exported_type: Type, imported_type: Type) -> bool {
@@ -154,42 +221,16 @@ fn is_table_element_type_compatible(exported_type: Type, imported_type: Type) ->
     }
 }
 
-fn is_table_compatible(exported: &TableType, imported: &TableType) -> bool {
-    let TableType {
-        ty: exported_ty,
-        minimum: exported_minimum,
-        maximum: exported_maximum,
-    } = exported;
-    let TableType {
-        ty: imported_ty,
-        minimum: imported_minimum,
-        maximum: imported_maximum,
-    } = imported;
-
-    is_table_element_type_compatible(*exported_ty, *imported_ty)
-        && imported_minimum <= exported_minimum
-        && (imported_maximum.is_none()
-            || (!exported_maximum.is_none()
-                && imported_maximum.unwrap() >= exported_maximum.unwrap()))
-}
-
-fn is_memory_compatible(exported: &MemoryType, imported: &MemoryType) -> bool {
-    let MemoryType {
-        minimum: exported_minimum,
-        maximum: exported_maximum,
-        shared: exported_shared,
-    } = exported;
-    let MemoryType {
-        minimum: imported_minimum,
-        maximum: imported_maximum,
-        shared: imported_shared,
-    } = imported;
-
-    imported_minimum <= exported_minimum
-        && (imported_maximum.is_none()
-            || (!exported_maximum.is_none()
-                && imported_maximum.unwrap() >= exported_maximum.unwrap()))
-        && exported_shared == imported_shared
+fn table_mismatch(exported: &TableType, imported: &TableType) -> Option<ExternTypeMismatch> {
+    if !is_table_element_type_compatible(exported.ty, imported.ty) {
+        return Some(ExternTypeMismatch::ElementType);
+    }
+    limits_mismatch(
+        exported.minimum,
+        exported.maximum,
+        imported.minimum,
+        imported.maximum,
+    )
 }
 
 macro_rules! accessors {
@@ -225,13 +266,25 @@ impl ExternType {
     }
     /// Check if two externs are compatible
     pub fn is_compatible_with(&self, other: &Self) -> bool {
-        match (self, other) {
-            (Self::Function(a), Self::Function(b)) => a == b,
-            (Self::Global(a), Self::Global(b)) => is_global_compatible(*a, *b),
-            (Self::Table(a), Self::Table(b)) => is_table_compatible(a, b),
-            (Self::Memory(a), Self::Memory(b)) => is_memory_compatible(a, b),
+        self.mismatch_with(other).is_none()
+    }
+
+    /// Returns the part of this extern type that prevents it from being
+    /// provided for an import that `requested` the other one, if any.
+    pub fn mismatch_with(&self, requested: &Self) -> Option<ExternTypeMismatch> {
+        match (self, requested) {
+            (Self::Function(a), Self::Function(b)) => {
+                if a == b {
+                    None
+                } else {
+                    Some(ExternTypeMismatch::Signature)
+                }
+            }
+            (Self::Global(a), Self::Global(b)) => global_mismatch(*a, *b),
+            (Self::Table(a), Self::Table(b)) => table_mismatch(a, b),
+            (Self::Memory(a), Self::Memory(b)) => a.mismatch_with(b),
             // The rest of possibilities, are not compatible
-            _ => false,
+            _ => Some(ExternTypeMismatch::Kind),
         }
     }
 }
@@ -268,6 +321,25 @@ impl FunctionType {
         }
     }
 
+    /// Returns a builder adding the parameters and results of a function
+    /// type one at a time.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use wasmer_types::{FunctionType, Type};
+    /// let ty = FunctionType::builder()
+    ///     .param(Type::I32)
+    ///     .param(Type::I64)
+    ///     .result(Type::F32)
+    ///     .build();
+    ///
+    /// assert_eq!(ty, FunctionType::new([Type::I32, Type::I64], [Type::F32]));
+    /// ```
+    pub fn builder() -> FunctionTypeBuilder {
+        FunctionTypeBuilder::default()
+    }
+
     /// Parameter types.
     pub fn params(&self) -> &[Type] {
         &self.params
@@ -277,6 +349,39 @@ impl FunctionType {
     pub fn results(&self) -> &[Type] {
         &self.results
     }
+
+    /// A hash of the parameters and results of this function type, in
+    /// order.
+    ///
+    /// Unlike the [`Hash`] implementation, which depends on the
+    /// [`Hasher`] it is fed to, the canonical hash is the same across
+    /// runs, processes and platforms, so it can be used as a key in
+    /// persistent caches. Equal function types always have the same
+    /// canonical hash.
+    ///
+    /// [`Hasher`]: core::hash::Hasher
+    pub fn canonical_hash(&self) -> u64 {
+        // 64-bit FNV-1a over the binary encoding of the type: the
+        // number of parameters, their value types, then the same for
+        // the results.
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        let mut hash = OFFSET_BASIS;
+        let mut write = |byte: u8| {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(PRIME);
+        };
+        for types in &[&self.params, &self.results] {
+            for &byte in &(types.len() as u32).to_le_bytes() {
+                write(byte);
+            }
+            for ty in types.iter() {
+                write(ty.binary_code());
+            }
+        }
+        hash
+    }
 }
 
 impl fmt::Display for FunctionType {
@@ -297,6 +402,44 @@ impl fmt::Display for FunctionType {
     }
 }
 
+/// A builder for [`FunctionType`]s, created by [`FunctionType::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct FunctionTypeBuilder {
+    params: Vec<Type>,
+    results: Vec<Type>,
+}
+
+impl FunctionTypeBuilder {
+    /// Appends a parameter.
+    pub fn param(mut self, ty: Type) -> Self {
+        self.params.push(ty);
+        self
+    }
+
+    /// Appends several parameters.
+    pub fn params(mut self, types: impl IntoIterator<Item = Type>) -> Self {
+        self.params.extend(types);
+        self
+    }
+
+    /// Appends a result.
+    pub fn result(mut self, ty: Type) -> Self {
+        self.results.push(ty);
+        self
+    }
+
+    /// Appends several results.
+    pub fn results(mut self, types: impl IntoIterator<Item = Type>) -> Self {
+        self.results.extend(types);
+        self
+    }
+
+    /// Builds the function type.
+    pub fn build(self) -> FunctionType {
+        FunctionType::new(self.params, self.results)
+    }
+}
+
 // Macro needed until https://rust-lang.github.io/rfcs/2000-const-generics.html is stable.
 // See https://users.rust-lang.org/t/how-to-implement-trait-for-fixed-size-array-of-any-size/31494
 macro_rules! implement_from_pair_to_functiontype {
@@ -503,6 +646,17 @@ impl TableType {
             maximum,
         }
     }
+
+    /// Returns the minimum number of elements in the table.
+    pub fn minimum(&self) -> u32 {
+        self.minimum
+    }
+
+    /// Returns the maximum number of elements in the table, or `None`
+    /// if the table is unbounded.
+    pub fn maximum(&self) -> Option<u32> {
+        self.maximum
+    }
 }
 
 impl fmt::Display for TableType {
@@ -549,6 +703,50 @@ impl MemoryType {
             shared,
         }
     }
+
+    /// Returns the minimum number of pages in the memory.
+    pub fn minimum_pages(&self) -> Pages {
+        self.minimum
+    }
+
+    /// Returns the maximum number of pages in the memory, or `None` if
+    /// the memory is unbounded.
+    pub fn maximum_pages(&self) -> Option<Pages> {
+        self.maximum
+    }
+
+    /// Checks whether a memory of this type can be provided for an import
+    /// that `requested` a memory of the given type.
+    ///
+    /// This follows the import matching rules of the WebAssembly spec:
+    ///
+    /// * this memory must have at least the `requested` minimum number
+    ///   of pages;
+    /// * if `requested` declares a maximum, this memory must declare a
+    ///   maximum too, and it can't be larger than the requested one. When
+    ///   `requested` has no maximum any maximum, or none, is accepted;
+    /// * both memories must agree on being shared or not.
+    pub fn is_compatible_with(&self, requested: &Self) -> bool {
+        self.mismatch_with(requested).is_none()
+    }
+
+    /// Returns why a memory of this type can't be provided for an import
+    /// that `requested` a memory of the given type, if it can't.
+    fn mismatch_with(&self, requested: &Self) -> Option<ExternTypeMismatch> {
+        limits_mismatch(
+            self.minimum,
+            self.maximum,
+            requested.minimum,
+            requested.maximum,
+        )
+        .or_else(|| {
+            if self.shared == requested.shared {
+                None
+            } else {
+                Some(ExternTypeMismatch::Shared)
+            }
+        })
+    }
 }
 
 impl fmt::Display for MemoryType {
@@ -677,4 +875,109 @@ mod tests {
         assert_eq!(ty.params().len(), 9);
         assert_eq!(ty.results().len(), 9);
     }
+
+    #[test]
+    fn functiontype_builder() {
+        assert_eq!(
+            FunctionType::builder().build(),
+            FunctionType::from(VOID_TO_VOID)
+        );
+        assert_eq!(
+            FunctionType::builder()
+                .param(Type::V128)
+                .param(Type::I64)
+                .result(Type::I32)
+                .build(),
+            FunctionType::new(&[Type::V128, Type::I64][..], &[Type::I32][..])
+        );
+        assert_eq!(
+            FunctionType::builder()
+                .results([Type::I32; 9].iter().copied())
+                .params(vec![Type::V128; 9])
+                .build(),
+            FunctionType::from(NINE_V128_TO_NINE_I32)
+        );
+    }
+
+    #[test]
+    fn functiontype_canonical_hash() {
+        let ty: FunctionType = V128_I64_TO_I32.into();
+        assert_eq!(
+            ty.canonical_hash(),
+            FunctionType::new([Type::V128, Type::I64], [Type::I32]).canonical_hash()
+        );
+        // The hash doesn't depend on the process it is computed in.
+        assert_eq!(
+            FunctionType::from(VOID_TO_VOID).canonical_hash(),
+            0xa8c7_f832_281a_39c5
+        );
+
+        let different: [FunctionType; 6] = [
+            VOID_TO_VOID.into(),
+            I32_I32_TO_VOID.into(),
+            V128_I64_TO_I32.into(),
+            ([Type::I64, Type::V128], [Type::I32]).into(),
+            ([Type::V128], [Type::I64, Type::I32]).into(),
+            ([Type::FuncRef], [Type::ExternRef]).into(),
+        ];
+        for (i, a) in different.iter().enumerate() {
+            for b in &different[i + 1..] {
+                assert_ne!(a.canonical_hash(), b.canonical_hash(), "{} vs {}", a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn limits_accessors() {
+        let bounded = MemoryType::new(1, Some(4), false);
+        assert_eq!(bounded.minimum_pages(), Pages(1));
+        assert_eq!(bounded.maximum_pages(), Some(Pages(4)));
+        let unbounded = MemoryType::new(0, None, false);
+        assert_eq!(unbounded.minimum_pages(), Pages(0));
+        assert_eq!(unbounded.maximum_pages(), None);
+
+        let bounded = TableType::new(Type::FuncRef, 2, Some(10));
+        assert_eq!(bounded.minimum(), 2);
+        assert_eq!(bounded.maximum(), Some(10));
+        let unbounded = TableType::new(Type::ExternRef, 3, None);
+        assert_eq!(unbounded.minimum(), 3);
+        assert_eq!(unbounded.maximum(), None);
+    }
+
+    #[test]
+    fn memory_type_compatibility() {
+        let memory = |minimum: u32, maximum: Option<u32>| MemoryType::new(minimum, maximum, false);
+
+        // Minimum: the provided memory must be at least as large.
+        assert!(memory(1, None).is_compatible_with(&memory(1, None)));
+        assert!(memory(2, None).is_compatible_with(&memory(1, None)));
+        assert!(!memory(1, None).is_compatible_with(&memory(2, None)));
+
+        // Unbounded request: any maximum is fine.
+        assert!(memory(1, Some(1)).is_compatible_with(&memory(1, None)));
+        assert!(memory(1, Some(65536)).is_compatible_with(&memory(1, None)));
+
+        // Bounded request: the provided memory must be bounded, and not
+        // by more than what was requested.
+        assert!(!memory(1, None).is_compatible_with(&memory(1, Some(10))));
+        assert!(memory(1, Some(5)).is_compatible_with(&memory(1, Some(10))));
+        assert!(memory(1, Some(10)).is_compatible_with(&memory(1, Some(10))));
+        assert!(!memory(1, Some(11)).is_compatible_with(&memory(1, Some(10))));
+
+        // Both checks apply at the same time.
+        assert!(memory(3, Some(5)).is_compatible_with(&memory(2, Some(10))));
+        assert!(!memory(1, Some(5)).is_compatible_with(&memory(2, Some(10))));
+
+        // Sharedness must match.
+        let shared = MemoryType::new(1, Some(1), true);
+        assert!(shared.is_compatible_with(&shared));
+        assert!(!shared.is_compatible_with(&memory(1, Some(1))));
+        assert!(!memory(1, Some(1)).is_compatible_with(&shared));
+
+        // The check is also what `ExternType` uses.
+        assert!(ExternType::Memory(memory(2, Some(5)))
+            .is_compatible_with(&ExternType::Memory(memory(1, Some(10)))));
+        assert!(!ExternType::Memory(memory(1, None))
+            .is_compatible_with(&ExternType::Memory(memory(1, Some(10)))));
+    }
 }
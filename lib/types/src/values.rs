@@ -107,6 +107,9 @@ where
 
     /// Writes it's value to a given pointer
     ///
+    /// An `externref` is written as a new reference, which is owned by
+    /// whoever reads it back with [`Value::read_value_from`].
+    ///
     /// # Safety
     /// `p` must be:
     /// - Sufficiently aligned for the Rust equivalent of the type in `self`
@@ -115,18 +118,23 @@ where
         match self {
             Self::I32(i) => ptr::write(p as *mut i32, *i),
             Self::I64(i) => ptr::write(p as *mut i64, *i),
-            Self::F32(u) => ptr::write(p as *mut f32, *u),
-            Self::F64(u) => ptr::write(p as *mut f64, *u),
+            // Floats are written as bits so that the payload of NaNs
+            // is preserved.
+            Self::F32(u) => ptr::write(p as *mut u32, u.to_bits()),
+            Self::F64(u) => ptr::write(p as *mut u64, u.to_bits()),
             Self::V128(b) => ptr::write(p as *mut u128, *b),
             Self::FuncRef(Some(b)) => T::write_value_to(b, p),
             Self::FuncRef(None) => ptr::write(p as *mut usize, 0),
-            // TODO(reftypes): review clone here
             Self::ExternRef(extern_ref) => ptr::write(p as *mut ExternRef, extern_ref.clone()),
         }
     }
 
     /// Gets a `Value` given a pointer and a `Type`
     ///
+    /// An `externref` is moved out of `p`: the returned value owns the
+    /// reference stored there, as values passed between the host and
+    /// WebAssembly are owned by the receiving side.
+    ///
     /// # Safety
     /// `p` must be:
     /// - Properly aligned to the specified `ty`'s Rust equivalent
@@ -135,8 +143,8 @@ where
         match ty {
             Type::I32 => Self::I32(ptr::read(p as *const i32)),
             Type::I64 => Self::I64(ptr::read(p as *const i64)),
-            Type::F32 => Self::F32(ptr::read(p as *const f32)),
-            Type::F64 => Self::F64(ptr::read(p as *const f64)),
+            Type::F32 => Self::F32(f32::from_bits(ptr::read(p as *const u32))),
+            Type::F64 => Self::F64(f64::from_bits(ptr::read(p as *const u64))),
             Type::V128 => Self::V128(ptr::read(p as *const u128)),
             Type::FuncRef => {
                 // We do the null check ourselves
@@ -146,10 +154,7 @@ where
                     Self::FuncRef(Some(T::read_value_from(store, p)))
                 }
             }
-            Type::ExternRef => {
-                let extern_ref = (&*(p as *const ExternRef)).clone();
-                Self::ExternRef(extern_ref)
-            }
+            Type::ExternRef => Self::ExternRef(ptr::read(p as *const ExternRef)),
         }
     }
 
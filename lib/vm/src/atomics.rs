@@ -0,0 +1,159 @@
+//! Host-side support for the `memory.atomic.wait32`,
+//! `memory.atomic.wait64` and `memory.atomic.notify` operations of
+//! shared memories.
+//!
+//! Waiters are parked in a process-wide table keyed by the memory and
+//! the address they wait on, so that a notification issued from any
+//! thread wakes the threads waiting on the same location.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+/// The outcome of an atomic wait, with the values returned by
+/// `memory.atomic.wait32` and `memory.atomic.wait64`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[repr(u32)]
+pub enum AtomicWaitResult {
+    /// The thread was woken by a notification.
+    Ok = 0,
+    /// The value in memory didn't match the expected value.
+    NotEqual = 1,
+    /// The timeout expired before a notification arrived.
+    TimedOut = 2,
+}
+
+/// Identifies a waited-on location: the memory, by address of its
+/// runtime representation, and the offset in that memory.
+pub type AtomicWaitKey = (usize, u64);
+
+struct Waiter {
+    woken: AtomicBool,
+}
+
+#[derive(Default)]
+struct WaiterTable {
+    waiters: Mutex<HashMap<AtomicWaitKey, VecDeque<Arc<Waiter>>>>,
+    wakeup: Condvar,
+}
+
+lazy_static::lazy_static! {
+    static ref WAITERS: WaiterTable = WaiterTable::default();
+}
+
+/// Blocks the current thread on `key` until it's notified with
+/// [`atomic_notify`] or `timeout` expires, if `matches` returns
+/// `true`.
+///
+/// `matches` is called while holding the waiter table lock, so that a
+/// notification issued after the value has been changed can't be
+/// missed. A `timeout` of `None` waits forever.
+pub fn atomic_wait(
+    key: AtomicWaitKey,
+    matches: impl FnOnce() -> bool,
+    timeout: Option<Duration>,
+) -> AtomicWaitResult {
+    let mut waiters = WAITERS.waiters.lock().unwrap();
+    if !matches() {
+        return AtomicWaitResult::NotEqual;
+    }
+    let waiter = Arc::new(Waiter {
+        woken: AtomicBool::new(false),
+    });
+    waiters.entry(key).or_default().push_back(waiter.clone());
+
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
+    while !waiter.woken.load(Ordering::SeqCst) {
+        waiters = match deadline {
+            None => WAITERS.wakeup.wait(waiters).unwrap(),
+            Some(deadline) => {
+                let now = Instant::now();
+                if now >= deadline {
+                    // Nobody woke us: leave the queue before giving up.
+                    if let Some(queue) = waiters.get_mut(&key) {
+                        queue.retain(|other| !Arc::ptr_eq(other, &waiter));
+                        if queue.is_empty() {
+                            waiters.remove(&key);
+                        }
+                    }
+                    return AtomicWaitResult::TimedOut;
+                }
+                WAITERS
+                    .wakeup
+                    .wait_timeout(waiters, deadline - now)
+                    .unwrap()
+                    .0
+            }
+        };
+    }
+    AtomicWaitResult::Ok
+}
+
+/// Wakes up to `count` threads waiting on `key`, in the order they
+/// started waiting, and returns how many were woken.
+pub fn atomic_notify(key: AtomicWaitKey, count: u32) -> u32 {
+    let mut waiters = WAITERS.waiters.lock().unwrap();
+    let queue = match waiters.get_mut(&key) {
+        Some(queue) => queue,
+        None => return 0,
+    };
+    let mut woken = 0;
+    while woken < count {
+        match queue.pop_front() {
+            Some(waiter) => waiter.woken.store(true, Ordering::SeqCst),
+            None => break,
+        }
+        woken += 1;
+    }
+    if queue.is_empty() {
+        waiters.remove(&key);
+    }
+    if woken > 0 {
+        WAITERS.wakeup.notify_all();
+    }
+    woken
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn wait_on_mismatching_value() {
+        assert_eq!(
+            atomic_wait((1, 0), || false, None),
+            AtomicWaitResult::NotEqual
+        );
+    }
+
+    #[test]
+    fn wait_times_out() {
+        assert_eq!(
+            atomic_wait((2, 0), || true, Some(Duration::from_millis(10))),
+            AtomicWaitResult::TimedOut
+        );
+        assert_eq!(atomic_notify((2, 0), 1), 0);
+    }
+
+    #[test]
+    fn notify_wakes_waiting_threads() {
+        let key = (3, 8);
+        let handles: Vec<_> = (0..3)
+            .map(|_| thread::spawn(move || atomic_wait(key, || true, None)))
+            .collect();
+        // The threads may not be waiting yet, so keep notifying one at a
+        // time until all of them have been woken.
+        let mut woken = 0;
+        while woken < 3 {
+            let notified = atomic_notify(key, 1);
+            assert!(notified <= 1);
+            woken += notified;
+            thread::yield_now();
+        }
+        for handle in handles {
+            assert_eq!(handle.join().unwrap(), AtomicWaitResult::Ok);
+        }
+    }
+}
@@ -114,9 +114,9 @@ impl VMTable {
         self.from.style()
     }
 
-    /// Returns whether or not the two `VMTable`s refer to the same Memory.
+    /// Returns whether or not the two `VMTable`s refer to the same Table.
     pub fn same(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.from, &other.from)
+        same_allocation(&self.from, &other.from)
     }
 
     /// Converts the stored instance ref into a strong `InstanceRef` if it is weak.
@@ -171,7 +171,7 @@ impl VMMemory {
 
     /// Returns whether or not the two `VMMemory`s refer to the same Memory.
     pub fn same(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.from, &other.from)
+        same_allocation(&self.from, &other.from)
     }
 
     /// Converts the stored instance ref into a strong `InstanceRef` if it is weak.
@@ -234,3 +234,11 @@ impl From<VMGlobal> for VMExtern {
         Self::Global(global)
     }
 }
+
+/// Returns whether `a` and `b` point to the same allocation.
+///
+/// Unlike `Arc::ptr_eq`, this ignores the vtables, which aren't unique:
+/// the same type can get a vtable per codegen unit.
+fn same_allocation<T: ?Sized>(a: &Arc<T>, b: &Arc<T>) -> bool {
+    Arc::as_ptr(a) as *const () == Arc::as_ptr(b) as *const ()
+}
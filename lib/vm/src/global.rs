@@ -1,8 +1,10 @@
 use crate::vmcontext::VMGlobalDefinition;
 use loupe::MemoryUsage;
+use std::any::Any;
 use std::cell::UnsafeCell;
+use std::fmt;
 use std::ptr::NonNull;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 use wasmer_types::{GlobalType, Mutability, Type, Value, WasmValueType};
 
@@ -14,6 +16,48 @@ pub struct Global {
     vm_global_definition: Box<UnsafeCell<VMGlobalDefinition>>,
     // used to synchronize gets/sets
     lock: Mutex<()>,
+    #[loupe(skip)]
+    host_set_hooks: HostSetHooks,
+    #[loupe(skip)]
+    validator: Validator,
+}
+
+/// A hook that [`Global::set`] runs after writing a value, see
+/// [`Global::on_host_set`].
+///
+/// It is called with the `Value<T>` that was set.
+pub type GlobalHostSetFn = dyn Fn(&dyn Any) + Send + Sync;
+
+/// Callbacks run after every successful [`Global::set`].
+#[derive(Default)]
+struct HostSetHooks(Mutex<Vec<Arc<GlobalHostSetFn>>>);
+
+impl fmt::Debug for HostSetHooks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("HostSetHooks")
+            .field("len", &self.0.lock().unwrap().len())
+            .finish()
+    }
+}
+
+/// A check that [`Global::set`] runs before writing a value, see
+/// [`Global::set_validator`].
+///
+/// It is called with the global, whose lock is held, and the
+/// `Value<T>` being set, and returns the reason why the value is
+/// rejected, if it is.
+pub type GlobalValidatorFn = dyn Fn(&Global, &dyn Any) -> Result<(), String> + Send + Sync;
+
+/// The check run by [`Global::set`], if any.
+#[derive(Default)]
+struct Validator(Mutex<Option<Arc<GlobalValidatorFn>>>);
+
+impl fmt::Debug for Validator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Validator")
+            .field("set", &self.0.lock().unwrap().is_some())
+            .finish()
+    }
 }
 
 /// # Safety
@@ -40,6 +84,11 @@ pub enum GlobalError {
         /// The type that we were asked to use it as.
         found: Type,
     },
+
+    /// The error returned when the validator of the global rejects a
+    /// value, with the reason it gave.
+    #[error("{0}")]
+    ValueRejected(String),
 }
 
 impl Global {
@@ -49,6 +98,8 @@ impl Global {
             ty: global_type,
             vm_global_definition: Box::new(UnsafeCell::new(VMGlobalDefinition::new())),
             lock: Mutex::new(()),
+            host_set_hooks: HostSetHooks::default(),
+            validator: Validator::default(),
         }
     }
 
@@ -70,23 +121,31 @@ impl Global {
     // be removed.
     pub fn get<T: WasmValueType>(&self, store: &dyn std::any::Any) -> Value<T> {
         let _global_guard = self.lock.lock().unwrap();
-        unsafe {
-            let definition = &*self.vm_global_definition.get();
-            match self.ty().ty {
-                Type::I32 => Value::I32(definition.to_i32()),
-                Type::I64 => Value::I64(definition.to_i64()),
-                Type::F32 => Value::F32(definition.to_f32()),
-                Type::F64 => Value::F64(definition.to_f64()),
-                Type::V128 => Value::V128(definition.to_u128()),
-                Type::ExternRef => Value::ExternRef(definition.to_externref().into()),
-                Type::FuncRef => {
-                    let p = definition.to_u128() as i128;
-                    if p as usize == 0 {
-                        Value::FuncRef(None)
-                    } else {
-                        let v = T::read_value_from(store, &p);
-                        Value::FuncRef(Some(v))
-                    }
+        unsafe { self.get_unchecked(store) }
+    }
+
+    /// Get a value from the global (unchecked)
+    ///
+    /// # Safety
+    /// The caller should ensure that this global is synchronized, for
+    /// example by calling this from its validator. Otherwise, use `get`
+    /// instead.
+    pub unsafe fn get_unchecked<T: WasmValueType>(&self, store: &dyn std::any::Any) -> Value<T> {
+        let definition = &*self.vm_global_definition.get();
+        match self.ty().ty {
+            Type::I32 => Value::I32(definition.to_i32()),
+            Type::I64 => Value::I64(definition.to_i64()),
+            Type::F32 => Value::F32(definition.to_f32()),
+            Type::F64 => Value::F64(definition.to_f64()),
+            Type::V128 => Value::V128(definition.to_u128()),
+            Type::ExternRef => Value::ExternRef(definition.to_externref().into()),
+            Type::FuncRef => {
+                let p = definition.to_u128() as i128;
+                if p as usize == 0 {
+                    Value::FuncRef(None)
+                } else {
+                    let v = T::read_value_from(store, &p);
+                    Value::FuncRef(Some(v))
                 }
             }
         }
@@ -96,8 +155,37 @@ impl Global {
     ///
     /// # Safety
     /// The caller should check that the `val` comes from the same store as this global.
-    pub unsafe fn set<T: WasmValueType>(&self, val: Value<T>) -> Result<(), GlobalError> {
+    pub unsafe fn set<T: WasmValueType + Clone>(&self, val: Value<T>) -> Result<(), GlobalError> {
+        {
+            let _global_guard = self.lock.lock().unwrap();
+            self.check(&val)?;
+            self.set_unchecked(val.clone())?;
+        }
+
+        // The hooks run without holding any lock so they are free to
+        // read or even set this global again. They get the value that
+        // was set rather than reading the global, which another thread
+        // may have set since.
+        let hooks = self.host_set_hooks.0.lock().unwrap().clone();
+        for hook in hooks {
+            hook(&val);
+        }
+        Ok(())
+    }
+
+    /// Checks that [`Global::set`] would accept `val`, without setting
+    /// it.
+    ///
+    /// The validator of the global, if any, is called with the current
+    /// value, which may have changed by the time `val` is set.
+    pub fn validate<T: WasmValueType>(&self, val: &Value<T>) -> Result<(), GlobalError> {
         let _global_guard = self.lock.lock().unwrap();
+        self.check(val)
+    }
+
+    /// The checks of [`Global::set`], run with the lock of the global
+    /// held.
+    fn check<T: WasmValueType>(&self, val: &Value<T>) -> Result<(), GlobalError> {
         if self.ty().mutability != Mutability::Var {
             return Err(GlobalError::ImmutableGlobalCannotBeSet);
         }
@@ -107,7 +195,27 @@ impl Global {
                 found: val.ty(),
             });
         }
-        self.set_unchecked(val)
+        let validator = self.validator.0.lock().unwrap().clone();
+        if let Some(validator) = validator {
+            validator(self, val).map_err(GlobalError::ValueRejected)?;
+        }
+        Ok(())
+    }
+
+    /// Registers a hook that runs after every successful call to [`Global::set`].
+    ///
+    /// Writes done by generated code go straight to the
+    /// [`VMGlobalDefinition`] and don't run these hooks.
+    pub fn on_host_set(&self, hook: Arc<GlobalHostSetFn>) {
+        self.host_set_hooks.0.lock().unwrap().push(hook);
+    }
+
+    /// Sets the check that [`Global::set`] runs on every value before
+    /// writing it, replacing the previous one, if any.
+    ///
+    /// Writes done by generated code don't run it.
+    pub fn set_validator(&self, validator: Arc<GlobalValidatorFn>) {
+        *self.validator.0.lock().unwrap() = Some(validator);
     }
 
     /// Set a value from the global (unchecked)
@@ -122,8 +230,9 @@ impl Global {
         match val {
             Value::I32(i) => *definition.as_i32_mut() = i,
             Value::I64(i) => *definition.as_i64_mut() = i,
-            Value::F32(f) => *definition.as_f32_mut() = f,
-            Value::F64(f) => *definition.as_f64_mut() = f,
+            // Store the bits so that the payload of NaNs is preserved.
+            Value::F32(f) => *definition.as_u32_mut() = f.to_bits(),
+            Value::F64(f) => *definition.as_u64_mut() = f.to_bits(),
             Value::V128(x) => *definition.as_bytes_mut() = x.to_ne_bytes(),
             Value::ExternRef(r) => {
                 let extern_ref = definition.as_externref_mut();
@@ -26,7 +26,7 @@ use crate::vmcontext::{
     VMMemoryDefinition, VMMemoryImport, VMSharedSignatureIndex, VMTableDefinition, VMTableImport,
     VMTrampoline,
 };
-use crate::{FunctionBodyPtr, ModuleInfo, VMOffsets};
+use crate::{FunctionBodyPtr, ModuleInfo, SignatureRegistration, VMOffsets};
 use crate::{VMFunction, VMGlobal, VMMemory, VMTable};
 use loupe::{MemoryUsage, MemoryUsageTracker};
 use memoffset::offset_of;
@@ -41,6 +41,7 @@ use std::mem;
 use std::ptr::{self, NonNull};
 use std::slice;
 use std::sync::Arc;
+use thiserror::Error;
 use wasmer_types::entity::{packed_option::ReservedValue, BoxedSlice, EntityRef, PrimaryMap};
 use wasmer_types::{
     DataIndex, DataInitializer, ElemIndex, ExportIndex, FunctionIndex, GlobalIndex, GlobalInit,
@@ -48,6 +49,47 @@ use wasmer_types::{
     SignatureIndex, TableIndex, TableInitializer,
 };
 
+/// An active data segment that doesn't fit in its memory, found while
+/// initializing an instance.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error(
+    "out of bounds memory access: data segment {} of {} bytes at offset {} doesn't fit in memory {} of {} bytes",
+    segment.index(),
+    len,
+    offset,
+    memory_index.index(),
+    memory_size
+)]
+pub struct DataSegmentOutOfBounds {
+    /// The index of the data segment in the module.
+    pub segment: DataIndex,
+    /// The index of the memory the segment initializes.
+    pub memory_index: MemoryIndex,
+    /// The offset in the memory where the segment starts.
+    pub offset: usize,
+    /// The length of the segment, in bytes.
+    pub len: usize,
+    /// The size of the memory, in bytes.
+    pub memory_size: usize,
+}
+
+/// An error while applying the initializers of an instance, or while
+/// invoking its start function.
+#[derive(Debug)]
+pub enum InitializationError {
+    /// A table initializer was out of bounds, or the start function
+    /// trapped.
+    Trap(Trap),
+    /// An active data segment doesn't fit in its memory.
+    DataSegmentOutOfBounds(DataSegmentOutOfBounds),
+}
+
+impl From<Trap> for InitializationError {
+    fn from(trap: Trap) -> Self {
+        Self::Trap(trap)
+    }
+}
+
 /// The function pointer to call with data and an [`Instance`] pointer to
 /// finish initializing the host env.
 pub type ImportInitializerFuncPtr<ResultErr = *mut ffi::c_void> =
@@ -98,6 +140,10 @@ pub(crate) struct Instance {
     /// Hosts can store arbitrary per-instance information here.
     host_state: Box<dyn Any>,
 
+    /// Keeps the signatures of the module registered while this
+    /// instance, or a function exported from it, is alive.
+    signature_registration: Option<Arc<SignatureRegistration>>,
+
     /// Functions to operate on host environments in the imports
     /// and pointers to the environments.
     ///
@@ -323,11 +369,13 @@ impl Instance {
     }
 
     /// Return the indexed `VMMemoryDefinition`.
+    ///
+    /// It is read from the memory itself, which is up to date even when
+    /// the memory was provided to the instance and grown from outside.
     fn memory(&self, index: LocalMemoryIndex) -> VMMemoryDefinition {
-        unsafe { *self.memory_ptr(index).as_ref() }
+        unsafe { *self.memories[index].vmmemory().as_ref() }
     }
 
-    #[allow(dead_code)]
     /// Set the indexed memory to `VMMemoryDefinition`.
     fn set_memory(&self, index: LocalMemoryIndex, mem: &VMMemoryDefinition) {
         unsafe {
@@ -476,7 +524,14 @@ impl Instance {
             .memories
             .get(memory_index)
             .unwrap_or_else(|| panic!("no memory for index {}", memory_index.index()));
-        mem.grow(delta.into())
+        let result = mem.grow(delta.into());
+        // A memory provided to the instance has its own definition, the
+        // copy of the instance is refreshed.
+        let definition = mem.vmmemory();
+        if definition != self.memory_ptr(memory_index) {
+            self.set_memory(memory_index, unsafe { definition.as_ref() });
+        }
+        result
     }
 
     /// Grow imported memory by the specified amount of pages.
@@ -837,6 +892,12 @@ impl Instance {
         passive_data.remove(&data_index);
     }
 
+    /// Returns whether the given passive data segment hasn't been
+    /// dropped yet.
+    pub(crate) fn has_passive_data(&self, data_index: DataIndex) -> bool {
+        self.passive_data.borrow().contains_key(&data_index)
+    }
+
     /// Get a table by index regardless of whether it is locally-defined or an
     /// imported, foreign table.
     pub(crate) fn get_table(&self, table_index: TableIndex) -> &dyn Table {
@@ -930,6 +991,7 @@ impl InstanceHandle {
                 passive_elements: Default::default(),
                 passive_data,
                 host_state,
+                signature_registration: None,
                 funcrefs,
                 imported_function_envs,
                 vmctx: VMContext {},
@@ -1017,19 +1079,45 @@ impl InstanceHandle {
         &self,
         trap_handler: &dyn TrapHandler,
         data_initializers: &[DataInitializer<'_>],
-    ) -> Result<(), Trap> {
+    ) -> Result<(), InitializationError> {
+        self.finish_instantiation_without_start(data_initializers)?;
+
+        // The WebAssembly spec specifies that the start function is
+        // invoked automatically at instantiation time.
+        Ok(self.invoke_start_function(trap_handler)?)
+    }
+
+    /// Like [`InstanceHandle::finish_instantiation`], but leaves the
+    /// start function to a later call to
+    /// [`InstanceHandle::invoke_start_function`].
+    ///
+    /// The data segments are applied in order. As required by the
+    /// WebAssembly spec, when a segment doesn't fit in its memory, the
+    /// segments before it have already been written, which is visible
+    /// if the memory is shared with another instance or the host.
+    ///
+    /// # Safety
+    ///
+    /// Only safe to call immediately after instantiation.
+    pub unsafe fn finish_instantiation_without_start(
+        &self,
+        data_initializers: &[DataInitializer<'_>],
+    ) -> Result<(), InitializationError> {
         let instance = self.instance().as_ref();
 
         // Apply the initializers.
         initialize_tables(instance)?;
-        initialize_memories(instance, data_initializers)?;
-
-        // The WebAssembly spec specifies that the start function is
-        // invoked automatically at instantiation time.
-        instance.invoke_start_function(trap_handler)?;
+        initialize_memories(instance, data_initializers)
+            .map_err(InitializationError::DataSegmentOutOfBounds)?;
         Ok(())
     }
 
+    /// Invoke the WebAssembly start function of the instance, if one is
+    /// present.
+    pub fn invoke_start_function(&self, trap_handler: &dyn TrapHandler) -> Result<(), Trap> {
+        self.instance().as_ref().invoke_start_function(trap_handler)
+    }
+
     /// Return a reference to the vmctx used by compiled wasm code.
     pub fn vmctx(&self) -> &VMContext {
         self.instance().as_ref().vmctx()
@@ -1162,6 +1250,27 @@ impl InstanceHandle {
         self.instance().as_ref().host_state()
     }
 
+    /// Replaces the custom state attached to this instance.
+    ///
+    /// # Safety
+    ///
+    /// The previous state must not be borrowed, and the instance must not
+    /// be used from another thread while its state is replaced.
+    pub unsafe fn set_host_state(&mut self, host_state: Box<dyn Any>) {
+        self.instance.as_mut_unchecked().host_state = host_state;
+    }
+
+    /// Keeps the given signature registration alive as long as this
+    /// instance is.
+    ///
+    /// # Safety
+    ///
+    /// The instance must not be used from another thread while the
+    /// registration is replaced.
+    pub unsafe fn set_signature_registration(&mut self, registration: Arc<SignatureRegistration>) {
+        self.instance.as_mut_unchecked().signature_registration = Some(registration);
+    }
+
     /// Return the memory index for the given `VMMemoryDefinition` in this instance.
     pub fn memory_index(&self, memory: &VMMemoryDefinition) -> LocalMemoryIndex {
         self.instance().as_ref().memory_index(memory)
@@ -1226,6 +1335,35 @@ impl InstanceHandle {
         self.instance().as_ref().get_local_table(index)
     }
 
+    /// Performs the `memory.init` operation.
+    ///
+    /// Returns an error if the destination range is out of the bounds of
+    /// the memory or the source range out of the bounds of the data
+    /// segment, a dropped segment being empty.
+    pub fn memory_init(
+        &self,
+        memory_index: MemoryIndex,
+        data_index: DataIndex,
+        dst: u32,
+        src: u32,
+        len: u32,
+    ) -> Result<(), Trap> {
+        self.instance()
+            .as_ref()
+            .memory_init(memory_index, data_index, dst, src, len)
+    }
+
+    /// Performs the `data.drop` operation.
+    pub fn data_drop(&self, data_index: DataIndex) {
+        self.instance().as_ref().data_drop(data_index)
+    }
+
+    /// Returns whether the given passive data segment hasn't been
+    /// dropped yet.
+    pub fn has_passive_data(&self, data_index: DataIndex) -> bool {
+        self.instance().as_ref().has_passive_data(data_index)
+    }
+
     /// Initializes the host environments.
     ///
     /// # Safety
@@ -1377,16 +1515,23 @@ fn initialize_passive_elements(instance: &Instance) {
 fn initialize_memories(
     instance: &Instance,
     data_initializers: &[DataInitializer<'_>],
-) -> Result<(), Trap> {
+) -> Result<(), DataSegmentOutOfBounds> {
     for init in data_initializers {
         let memory = instance.get_memory(init.location.memory_index);
+        let memory_size: usize = memory.current_length.try_into().unwrap();
 
         let start = get_memory_init_start(init, instance);
         if start
             .checked_add(init.data.len())
-            .map_or(true, |end| end > memory.current_length.try_into().unwrap())
+            .map_or(true, |end| end > memory_size)
         {
-            return Err(Trap::lib(TrapCode::HeapAccessOutOfBounds));
+            return Err(DataSegmentOutOfBounds {
+                segment: init.location.data_index,
+                memory_index: init.location.memory_index,
+                offset: start,
+                len: init.data.len(),
+                memory_size,
+            });
         }
 
         unsafe {
@@ -1408,8 +1553,8 @@ fn initialize_globals(instance: &Instance) {
             match initializer {
                 GlobalInit::I32Const(x) => *(*to).as_i32_mut() = *x,
                 GlobalInit::I64Const(x) => *(*to).as_i64_mut() = *x,
-                GlobalInit::F32Const(x) => *(*to).as_f32_mut() = *x,
-                GlobalInit::F64Const(x) => *(*to).as_f64_mut() = *x,
+                GlobalInit::F32Const(x) => *(*to).as_u32_mut() = x.to_bits(),
+                GlobalInit::F64Const(x) => *(*to).as_u64_mut() = x.to_bits(),
                 GlobalInit::V128Const(x) => *(*to).as_bytes_mut() = *x.bytes(),
                 GlobalInit::GetGlobal(x) => {
                     let from: VMGlobalDefinition =
@@ -21,6 +21,7 @@
     )
 )]
 
+mod atomics;
 mod export;
 mod func_data_registry;
 mod global;
@@ -38,19 +39,23 @@ mod vmoffsets;
 
 pub mod libcalls;
 
+pub use crate::atomics::{atomic_notify, atomic_wait, AtomicWaitKey, AtomicWaitResult};
 pub use crate::export::*;
 pub use crate::func_data_registry::{FuncDataRegistry, VMFuncRef};
 pub use crate::global::*;
 pub use crate::imports::Imports;
 pub use crate::instance::{
-    ImportFunctionEnv, ImportInitializerFuncPtr, InstanceAllocator, InstanceHandle,
-    WeakOrStrongInstanceRef,
+    DataSegmentOutOfBounds, ImportFunctionEnv, ImportInitializerFuncPtr, InitializationError,
+    InstanceAllocator, InstanceHandle, WeakOrStrongInstanceRef,
 };
-pub use crate::memory::{LinearMemory, Memory, MemoryError, MemoryStyle};
-pub use crate::mmap::Mmap;
+pub use crate::memory::{
+    LinearMemory, Memory, MemoryAccess, MemoryAccessKind, MemoryAccessLogFn, MemoryError,
+    MemoryGrowObserverFn, MemoryStyle,
+};
+pub use crate::mmap::{prefault, Mmap};
 pub use crate::module::{ExportsIterator, ImportsIterator, ModuleInfo};
 pub use crate::probestack::PROBESTACK;
-pub use crate::sig_registry::SignatureRegistry;
+pub use crate::sig_registry::{SignatureRegistration, SignatureRegistry};
 pub use crate::table::{LinearTable, Table, TableElement, TableStyle};
 pub use crate::trap::*;
 pub use crate::vmcontext::{
@@ -100,3 +105,12 @@ impl std::ops::Deref for SectionBodyPtr {
         &self.0
     }
 }
+
+/// # Safety
+/// The section data is only patched while linking, before the pointer is
+/// shared, so this is essentially a usize.
+unsafe impl Send for SectionBodyPtr {}
+/// # Safety
+/// The section data is only patched while linking, before the pointer is
+/// shared, so this is essentially a usize.
+unsafe impl Sync for SectionBodyPtr {}
@@ -40,7 +40,7 @@
 use crate::func_data_registry::VMFuncRef;
 use crate::probestack::PROBESTACK;
 use crate::table::{RawTableElement, TableElement};
-use crate::trap::{raise_lib_trap, Trap, TrapCode};
+use crate::trap::{raise_lib_trap, resume_panic, Trap, TrapCode};
 use crate::vmcontext::VMContext;
 use crate::VMExternRef;
 use loupe::MemoryUsage;
@@ -48,6 +48,7 @@ use loupe::MemoryUsage;
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
 use wasmer_types::{
     DataIndex, ElemIndex, FunctionIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
     TableIndex, Type,
@@ -157,10 +158,15 @@ pub unsafe extern "C" fn wasmer_vm_memory32_grow(
     let instance = (&*vmctx).instance();
     let memory_index = LocalMemoryIndex::from_u32(memory_index);
 
-    instance
-        .memory_grow(memory_index, delta)
-        .map(|pages| pages.0)
-        .unwrap_or(u32::max_value())
+    // The grow observers of the memory are host code that may panic,
+    // which must not unwind through this function.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        instance.memory_grow(memory_index, delta)
+    }));
+    match result {
+        Ok(result) => result.map(|pages| pages.0).unwrap_or(u32::max_value()),
+        Err(panic) => resume_panic(panic),
+    }
 }
 
 /// Implementation of memory.grow for imported 32-bit memories.
@@ -177,10 +183,14 @@ pub unsafe extern "C" fn wasmer_vm_imported_memory32_grow(
     let instance = (&*vmctx).instance();
     let memory_index = MemoryIndex::from_u32(memory_index);
 
-    instance
-        .imported_memory_grow(memory_index, delta)
-        .map(|pages| pages.0)
-        .unwrap_or(u32::max_value())
+    // See `wasmer_vm_memory32_grow`.
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        instance.imported_memory_grow(memory_index, delta)
+    }));
+    match result {
+        Ok(result) => result.map(|pages| pages.0).unwrap_or(u32::max_value()),
+        Err(panic) => resume_panic(panic),
+    }
 }
 
 /// Implementation of memory.size for locally-defined 32-bit memories.
@@ -786,6 +796,41 @@ pub enum LibCall {
 }
 
 impl LibCall {
+    /// All the libcalls.
+    pub const ALL: [Self; 31] = [
+        Self::CeilF32,
+        Self::CeilF64,
+        Self::FloorF32,
+        Self::FloorF64,
+        Self::NearestF32,
+        Self::NearestF64,
+        Self::TruncF32,
+        Self::TruncF64,
+        Self::Memory32Size,
+        Self::ImportedMemory32Size,
+        Self::TableCopy,
+        Self::TableInit,
+        Self::TableFill,
+        Self::TableSize,
+        Self::ImportedTableSize,
+        Self::TableGet,
+        Self::ImportedTableGet,
+        Self::TableSet,
+        Self::ImportedTableSet,
+        Self::TableGrow,
+        Self::ImportedTableGrow,
+        Self::FuncRef,
+        Self::ElemDrop,
+        Self::Memory32Copy,
+        Self::ImportedMemory32Copy,
+        Self::Memory32Fill,
+        Self::ImportedMemory32Fill,
+        Self::Memory32Init,
+        Self::DataDrop,
+        Self::RaiseTrap,
+        Self::Probestack,
+    ];
+
     /// The function pointer to a libcall
     pub fn function_pointer(self) -> usize {
         match self {
@@ -7,19 +7,20 @@
 
 use crate::mmap::Mmap;
 use crate::vmcontext::VMMemoryDefinition;
+use backtrace::Backtrace;
 use loupe::MemoryUsage;
-use more_asserts::assert_ge;
 #[cfg(feature = "enable-rkyv")]
 use rkyv::{Archive, Deserialize as RkyvDeserialize, Serialize as RkyvSerialize};
 use serde::{Deserialize, Serialize};
 use std::borrow::BorrowMut;
-use std::cell::UnsafeCell;
-use std::convert::TryInto;
+use std::cell::{RefCell, UnsafeCell};
+use std::convert::{TryFrom, TryInto};
 use std::fmt;
-use std::ptr::NonNull;
-use std::sync::Mutex;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use thiserror::Error;
-use wasmer_types::{Bytes, MemoryType, Pages};
+use wasmer_types::{Bytes, MemoryType, Pages, WASM_PAGE_SIZE};
 
 /// Error type describing things that can go wrong when operating on Wasm Memories.
 #[derive(Error, Debug, Clone, PartialEq, Hash)]
@@ -58,6 +59,10 @@ pub enum MemoryError {
         /// The number of pages requested as the maximum amount of memory.
         max_allowed: Pages,
     },
+    /// The memory was created in a store that has been frozen, so no
+    /// new definitions can be created in it.
+    #[error("the store is frozen, no new definitions can be created in it")]
+    StoreFrozen,
     /// A user defined error value, used for error cases not listed above.
     #[error("A user-defined error occurred: {0}")]
     Generic(String),
@@ -102,6 +107,56 @@ impl MemoryStyle {
     }
 }
 
+/// A function observing the growth of a memory, see
+/// [`Memory::add_grow_observer`].
+///
+/// It is called with the previous size, the new size and the new base
+/// address of the memory.
+pub type MemoryGrowObserverFn = dyn Fn(Pages, Pages, *mut u8) + Send + Sync;
+
+/// Whether an access to a memory reads or writes it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MemoryAccessKind {
+    /// The bytes are read.
+    Read,
+    /// The bytes are written.
+    Write,
+}
+
+/// An access of the host to a memory, reported to its access log, see
+/// [`Memory::set_access_log`].
+#[derive(Debug, Clone)]
+pub struct MemoryAccess {
+    /// Whether the bytes are read or written.
+    pub kind: MemoryAccessKind,
+    /// The offset of the first byte accessed in the memory.
+    pub offset: u64,
+    /// The number of bytes accessed.
+    pub len: u64,
+    /// The stack of the host code accessing the memory.
+    ///
+    /// It is captured unresolved, call [`Backtrace::resolve`] to get
+    /// the symbols of its frames.
+    pub backtrace: Backtrace,
+}
+
+impl MemoryAccess {
+    /// Describes an access of the caller to a memory, capturing its
+    /// backtrace.
+    pub fn new(kind: MemoryAccessKind, offset: u64, len: u64) -> Self {
+        Self {
+            kind,
+            offset,
+            len,
+            backtrace: Backtrace::new_unresolved(),
+        }
+    }
+}
+
+/// A function recording the accesses of the host to a memory, see
+/// [`Memory::set_access_log`].
+pub type MemoryAccessLogFn = dyn Fn(MemoryAccess) + Send + Sync;
+
 /// Trait for implementing Wasm Memory used by Wasmer.
 pub trait Memory: fmt::Debug + Send + Sync + MemoryUsage {
     /// Returns the memory type for this memory.
@@ -110,6 +165,14 @@ pub trait Memory: fmt::Debug + Send + Sync + MemoryUsage {
     /// Returns the memory style for this memory.
     fn style(&self) -> &MemoryStyle;
 
+    /// Returns whether the memory is backed by an allocation that can
+    /// be shared between multiple threads.
+    ///
+    /// The default implementation reports the declared type.
+    fn is_shared(&self) -> bool {
+        self.ty().shared
+    }
+
     /// Returns the number of allocated wasm pages.
     fn size(&self) -> Pages;
 
@@ -120,6 +183,73 @@ pub trait Memory: fmt::Debug + Send + Sync + MemoryUsage {
     ///
     /// The pointer returned in [`VMMemoryDefinition`] must be valid for the lifetime of this memory.
     fn vmmemory(&self) -> NonNull<VMMemoryDefinition>;
+
+    /// Reset all the accessible bytes of the memory to zero.
+    ///
+    /// The size of the memory is unchanged. The default implementation
+    /// writes zeros over the whole memory.
+    fn zero(&self) -> Result<(), MemoryError> {
+        unsafe {
+            let definition = self.vmmemory().as_ref();
+            ptr::write_bytes(definition.base, 0, definition.current_length as usize);
+        }
+        Ok(())
+    }
+
+    /// Registers `observer` to be called after every successful growth
+    /// of the memory, whether it is grown by the host or by wasm code.
+    ///
+    /// The observer runs on the thread that grew the memory, and must
+    /// not grow the memory itself: doing so fails with an error.
+    ///
+    /// If an observer panics, the memory stays grown, the remaining
+    /// observers aren't called and the panic propagates to the code
+    /// that grew the memory. When that is wasm code, the panic is
+    /// carried across the wasm frames like a panic in a host function.
+    ///
+    /// Returns an id to unregister the observer with
+    /// [`Memory::remove_grow_observer`].
+    ///
+    /// The default implementation doesn't support observers and
+    /// returns an error.
+    fn add_grow_observer(&self, observer: Box<MemoryGrowObserverFn>) -> Result<u64, MemoryError> {
+        drop(observer);
+        Err(MemoryError::Generic(
+            "this memory doesn't support grow observers".to_string(),
+        ))
+    }
+
+    /// Unregisters the grow observer `id` returned by
+    /// [`Memory::add_grow_observer`]. Unknown ids are ignored.
+    ///
+    /// The default implementation does nothing, since it never
+    /// registers observers.
+    fn remove_grow_observer(&self, id: u64) {
+        let _ = id;
+    }
+
+    /// Sets the function the host reports its accesses to the memory
+    /// to, or stops reporting them if `log` is `None`.
+    ///
+    /// Accesses of wasm code aren't reported.
+    ///
+    /// The default implementation doesn't support access logs and
+    /// returns an error.
+    fn set_access_log(&self, log: Option<Arc<MemoryAccessLogFn>>) -> Result<(), MemoryError> {
+        drop(log);
+        Err(MemoryError::Generic(
+            "this memory doesn't support access logs".to_string(),
+        ))
+    }
+
+    /// Returns the function the host reports its accesses to the memory
+    /// to, if any.
+    ///
+    /// This is called on every access of the host that can be logged,
+    /// so it should be cheap when there is no access log.
+    fn access_log(&self) -> Option<Arc<MemoryAccessLogFn>> {
+        None
+    }
 }
 
 /// A linear memory instance.
@@ -144,6 +274,14 @@ pub struct LinearMemory {
     /// The owned memory definition used by the generated code
     vm_memory_definition: VMMemoryDefinitionOwnership,
 
+    /// The functions called after the memory grows.
+    #[loupe(skip)]
+    grow_observers: GrowObservers,
+
+    /// The function the host reports its accesses to.
+    #[loupe(skip)]
+    access_log: AccessLog,
+
     // Records whether we're using a bounds-checking strategy which requires
     // handlers to catch trapping accesses.
     pub(crate) needs_signal_handlers: bool,
@@ -174,6 +312,55 @@ unsafe impl Send for LinearMemory {}
 /// This is correct because all internal mutability is protected by a mutex.
 unsafe impl Sync for LinearMemory {}
 
+/// The grow observers of a [`LinearMemory`].
+#[derive(Default)]
+struct GrowObservers {
+    /// The id of the next registered observer.
+    next_id: AtomicU64,
+    observers: RwLock<Vec<(u64, Arc<MemoryGrowObserverFn>)>>,
+}
+
+impl fmt::Debug for GrowObservers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GrowObservers")
+            .field("len", &self.observers.read().unwrap().len())
+            .finish()
+    }
+}
+
+/// The access log of a [`LinearMemory`].
+#[derive(Default)]
+struct AccessLog {
+    /// Whether `log` is set, so that accesses don't take the lock when
+    /// logging is disabled, which it is most of the time.
+    enabled: AtomicBool,
+    log: RwLock<Option<Arc<MemoryAccessLogFn>>>,
+}
+
+impl fmt::Debug for AccessLog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessLog")
+            .field("enabled", &self.enabled.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+thread_local! {
+    /// The addresses of the memories whose grow observers are running on
+    /// this thread, to reject growing them again from an observer.
+    static NOTIFYING_GROWTH: RefCell<Vec<usize>> = RefCell::new(Vec::new());
+}
+
+/// Removes a memory from [`NOTIFYING_GROWTH`] on drop, even if one of
+/// its observers panics.
+struct NotifyingGrowth;
+
+impl Drop for NotifyingGrowth {
+    fn drop(&mut self) {
+        NOTIFYING_GROWTH.with(|memories| memories.borrow_mut().pop());
+    }
+}
+
 #[derive(Debug, MemoryUsage)]
 struct WasmMmap {
     // Our OS allocation of mmap'd memory.
@@ -236,12 +423,12 @@ impl LinearMemory {
             }
         }
 
-        let offset_guard_bytes = style.offset_guard_size() as usize;
+        let offset_guard_size = style.offset_guard_size();
 
         // If we have an offset guard, or if we're doing the static memory
         // allocation strategy, we need signal handlers to catch out of bounds
         // acceses.
-        let needs_signal_handlers = offset_guard_bytes > 0
+        let needs_signal_handlers = offset_guard_size > 0
             || match style {
                 MemoryStyle::Dynamic { .. } => false,
                 MemoryStyle::Static { .. } => true,
@@ -250,12 +437,33 @@ impl LinearMemory {
         let minimum_pages = match style {
             MemoryStyle::Dynamic { .. } => memory.minimum,
             MemoryStyle::Static { bound, .. } => {
-                assert_ge!(*bound, memory.minimum);
+                if *bound < memory.minimum || *bound > Pages::max_value() {
+                    return Err(MemoryError::InvalidMemory {
+                        reason: format!(
+                            "the static bound ({} pages) must be between the minimum ({} pages) and {} pages",
+                            bound.0,
+                            memory.minimum.0,
+                            Pages::max_value().0
+                        ),
+                    });
+                }
                 *bound
             }
         };
-        let minimum_bytes = minimum_pages.bytes().0;
-        let request_bytes = minimum_bytes.checked_add(offset_guard_bytes).unwrap();
+        // The reservation is computed in 64 bits so that a large static
+        // bound is reported as an error on 32-bit hosts instead of
+        // overflowing.
+        let (request_bytes, offset_guard_bytes) = (minimum_pages.0 as u64)
+            .checked_mul(WASM_PAGE_SIZE as u64)
+            .and_then(|bytes| bytes.checked_add(offset_guard_size))
+            .and_then(|bytes| usize::try_from(bytes).ok())
+            .and_then(|bytes| Some((bytes, usize::try_from(offset_guard_size).ok()?)))
+            .ok_or_else(|| {
+                MemoryError::Region(format!(
+                    "a reservation of {} pages and a {} bytes offset guard doesn't fit in the address space",
+                    minimum_pages.0, offset_guard_size
+                ))
+            })?;
         let mapped_pages = memory.minimum;
         let mapped_bytes = mapped_pages.bytes();
 
@@ -290,6 +498,8 @@ impl LinearMemory {
             },
             memory: *memory,
             style: style.clone(),
+            grow_observers: GrowObservers::default(),
+            access_log: AccessLog::default(),
         })
     }
 
@@ -323,6 +533,11 @@ impl Memory for LinearMemory {
         &self.style
     }
 
+    /// Returns whether the memory is shared.
+    fn is_shared(&self) -> bool {
+        self.memory.shared
+    }
+
     /// Returns the number of allocated wasm pages.
     fn size(&self) -> Pages {
         // TODO: investigate this function for race conditions
@@ -338,11 +553,89 @@ impl Memory for LinearMemory {
     /// Returns `None` if memory can't be grown by the specified amount
     /// of wasm pages.
     fn grow(&self, delta: Pages) -> Result<Pages, MemoryError> {
+        let key = self as *const Self as usize;
+        if NOTIFYING_GROWTH.with(|memories| memories.borrow().contains(&key)) {
+            return Err(MemoryError::Generic(
+                "the memory can't be grown from one of its grow observers".to_string(),
+            ));
+        }
+
+        let (prev_pages, new_pages, base) = self.resize(delta)?;
+        if new_pages != prev_pages {
+            // The lock is released, so that observers can use the memory.
+            let observers = self.grow_observers.observers.read().unwrap().clone();
+            if !observers.is_empty() {
+                NOTIFYING_GROWTH.with(|memories| memories.borrow_mut().push(key));
+                let _guard = NotifyingGrowth;
+                for (_, observer) in observers {
+                    observer(prev_pages, new_pages, base);
+                }
+            }
+        }
+        Ok(prev_pages)
+    }
+
+    /// Return a `VMMemoryDefinition` for exposing the memory to compiled wasm code.
+    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
+        let _mmap_guard = self.mmap.lock().unwrap();
+        unsafe { self.get_vm_memory_definition() }
+    }
+
+    /// Reset all the accessible bytes of the memory to zero, by giving
+    /// the pages back to the OS rather than writing zeros over them.
+    fn zero(&self) -> Result<(), MemoryError> {
+        let mut mmap_guard = self.mmap.lock().unwrap();
+        let mmap = mmap_guard.borrow_mut();
+        let accessible_bytes = mmap.size.bytes().0;
+        mmap.alloc
+            .zero(0, accessible_bytes)
+            .map_err(MemoryError::Region)
+    }
+
+    fn add_grow_observer(&self, observer: Box<MemoryGrowObserverFn>) -> Result<u64, MemoryError> {
+        let id = self.grow_observers.next_id.fetch_add(1, Ordering::Relaxed);
+        self.grow_observers
+            .observers
+            .write()
+            .unwrap()
+            .push((id, Arc::from(observer)));
+        Ok(id)
+    }
+
+    fn remove_grow_observer(&self, id: u64) {
+        self.grow_observers
+            .observers
+            .write()
+            .unwrap()
+            .retain(|(observer_id, _)| *observer_id != id);
+    }
+
+    fn set_access_log(&self, log: Option<Arc<MemoryAccessLogFn>>) -> Result<(), MemoryError> {
+        let mut current = self.access_log.log.write().unwrap();
+        self.access_log
+            .enabled
+            .store(log.is_some(), Ordering::Release);
+        *current = log;
+        Ok(())
+    }
+
+    fn access_log(&self) -> Option<Arc<MemoryAccessLogFn>> {
+        if !self.access_log.enabled.load(Ordering::Acquire) {
+            return None;
+        }
+        self.access_log.log.read().unwrap().clone()
+    }
+}
+
+impl LinearMemory {
+    /// Grows the memory by `delta` pages, returning the previous size,
+    /// the new size and the new base address.
+    fn resize(&self, delta: Pages) -> Result<(Pages, Pages, *mut u8), MemoryError> {
         let mut mmap_guard = self.mmap.lock().unwrap();
         let mmap = mmap_guard.borrow_mut();
         // Optimization of memory.grow 0 calls.
         if delta.0 == 0 {
-            return Ok(mmap.size);
+            return Ok((mmap.size, mmap.size, mmap.alloc.as_mut_ptr()));
         }
 
         let new_pages = mmap
@@ -379,6 +672,15 @@ impl Memory for LinearMemory {
         let new_bytes = new_pages.bytes().0;
 
         if new_bytes > mmap.alloc.len() - self.offset_guard_size {
+            // Static memories are never moved: compiled code relies on
+            // their base and bound staying put.
+            if let MemoryStyle::Static { .. } = self.style {
+                return Err(MemoryError::CouldNotGrow {
+                    current: mmap.size,
+                    attempted_delta: delta,
+                });
+            }
+
             // If the new size is within the declared maximum, but needs more memory than we
             // have on hand, it's a dynamic heap and it can move.
             let guard_bytes = self.offset_guard_size;
@@ -414,12 +716,6 @@ impl Memory for LinearMemory {
             md.base = mmap.alloc.as_mut_ptr() as _;
         }
 
-        Ok(prev_pages)
-    }
-
-    /// Return a `VMMemoryDefinition` for exposing the memory to compiled wasm code.
-    fn vmmemory(&self) -> NonNull<VMMemoryDefinition> {
-        let _mmap_guard = self.mmap.lock().unwrap();
-        unsafe { self.get_vm_memory_definition() }
+        Ok((prev_pages, new_pages, mmap.alloc.as_mut_ptr()))
     }
 }
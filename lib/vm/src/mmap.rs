@@ -228,6 +228,77 @@ impl Mmap {
         Ok(())
     }
 
+    /// Reset the accessible memory starting at `start` and extending for
+    /// `len` bytes to zero.
+    ///
+    /// Rather than writing zeros, the pages are given back to the OS and
+    /// faulted in again, zero-filled, when they are next accessed.
+    /// `start` and `len` must be native page-size multiples and describe a
+    /// range within `self`'s accessible memory.
+    #[cfg(not(target_os = "windows"))]
+    pub fn zero(&mut self, start: usize, len: usize) -> Result<(), String> {
+        let page_size = region::page::size();
+        assert_eq!(start & (page_size - 1), 0);
+        assert_eq!(len & (page_size - 1), 0);
+        assert_le!(len, self.len);
+        assert_le!(start, self.len - len);
+
+        if len == 0 {
+            return Ok(());
+        }
+        let ptr = (self.ptr + start) as *mut libc::c_void;
+
+        // On Linux, private anonymous pages that are dropped read back as
+        // zeros. Elsewhere, fresh pages are mapped over the old ones.
+        #[cfg(target_os = "linux")]
+        let zeroed = unsafe { libc::madvise(ptr, len, libc::MADV_DONTNEED) == 0 };
+        #[cfg(not(target_os = "linux"))]
+        let zeroed = unsafe {
+            libc::mmap(
+                ptr,
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANON | libc::MAP_FIXED,
+                -1,
+                0,
+            ) == ptr
+        };
+
+        if zeroed {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error().to_string())
+        }
+    }
+
+    /// Reset the accessible memory starting at `start` and extending for
+    /// `len` bytes to zero.
+    ///
+    /// Rather than writing zeros, the pages are decommitted and committed
+    /// again, so they are zero-filled when they are next accessed.
+    /// `start` and `len` must be native page-size multiples and describe a
+    /// range within `self`'s accessible memory.
+    #[cfg(target_os = "windows")]
+    pub fn zero(&mut self, start: usize, len: usize) -> Result<(), String> {
+        use winapi::ctypes::c_void;
+        use winapi::um::memoryapi::VirtualFree;
+        use winapi::um::winnt::MEM_DECOMMIT;
+        let page_size = region::page::size();
+        assert_eq!(start & (page_size - 1), 0);
+        assert_eq!(len & (page_size - 1), 0);
+        assert_le!(len, self.len);
+        assert_le!(start, self.len - len);
+
+        if len == 0 {
+            return Ok(());
+        }
+        let ptr = self.ptr as *const u8;
+        if unsafe { VirtualFree(ptr.add(start) as *mut c_void, len, MEM_DECOMMIT) } == 0 {
+            return Err(io::Error::last_os_error().to_string());
+        }
+        self.make_accessible(start, len)
+    }
+
     /// Return the allocated memory as a slice of u8.
     pub fn as_slice(&self) -> &[u8] {
         unsafe { slice::from_raw_parts(self.ptr as *const u8, self.len) }
@@ -259,6 +330,47 @@ impl Mmap {
     }
 }
 
+/// `MADV_POPULATE_WRITE`, available since Linux 5.14. Not every `libc`
+/// release we support exports it.
+#[cfg(target_os = "linux")]
+const MADV_POPULATE_WRITE: libc::c_int = 23;
+
+/// Faults in the pages covering `len` bytes from `ptr` for writing, so
+/// that neither the first read nor the first write to them faults.
+///
+/// On Linux this uses `madvise(MADV_POPULATE_WRITE)`. Where that is not
+/// available, or fails, one byte of each page is read and written back
+/// instead. Only reading would map the shared zero page, and the first
+/// write would still fault. The contents of the memory are never changed.
+///
+/// # Safety
+///
+/// The whole range must be readable and writable, and must not be
+/// concurrently written to.
+pub unsafe fn prefault(ptr: *mut u8, len: usize) {
+    if len == 0 {
+        return;
+    }
+    let page_size = region::page::size();
+    let start = ptr as usize & !(page_size - 1);
+    let end = round_up_to_page_size(ptr as usize + len, page_size);
+
+    #[cfg(target_os = "linux")]
+    {
+        let advised = libc::madvise(start as *mut libc::c_void, end - start, MADV_POPULATE_WRITE);
+        if advised == 0 {
+            return;
+        }
+    }
+
+    // The first and last pages are only partially covered by the range,
+    // but protections apply to whole pages so they are writable too.
+    for page in (start..end).step_by(page_size) {
+        let page = page as *mut u8;
+        ptr::write_volatile(page, ptr::read_volatile(page));
+    }
+}
+
 impl Drop for Mmap {
     #[cfg(not(target_os = "windows"))]
     fn drop(&mut self) {
@@ -297,6 +409,39 @@ fn _assert() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_zero() {
+        let page_size = region::page::size();
+        let mut mmap = Mmap::accessible_reserved(2 * page_size, 4 * page_size).unwrap();
+        for byte in mmap.as_mut_slice()[..2 * page_size].iter_mut() {
+            *byte = 0xaa;
+        }
+        mmap.zero(page_size, page_size).unwrap();
+        assert!(mmap.as_slice()[..page_size]
+            .iter()
+            .all(|&byte| byte == 0xaa));
+        assert!(mmap.as_slice()[page_size..2 * page_size]
+            .iter()
+            .all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn test_prefault() {
+        let page_size = region::page::size();
+        let mut mmap = Mmap::with_at_least(3 * page_size).unwrap();
+        mmap.as_mut_slice()[page_size + 1] = 0xaa;
+
+        unsafe { prefault(mmap.as_mut_ptr().add(1), 2 * page_size) };
+        assert_eq!(mmap.as_slice()[page_size + 1], 0xaa);
+        assert!(mmap.as_slice()[..page_size].iter().all(|&byte| byte == 0));
+
+        // Every page is now writable without changing its neighbours.
+        for page in 0..3 {
+            mmap.as_mut_slice()[page * page_size] = 0xbb;
+        }
+        assert_eq!(mmap.as_slice()[page_size + 1], 0xaa);
+    }
+
     #[test]
     fn test_round_up_to_page_size() {
         assert_eq!(round_up_to_page_size(0, 4096), 0);
@@ -23,10 +23,10 @@ use wasmer_types::entity::{EntityRef, PrimaryMap};
 #[cfg(feature = "enable-rkyv")]
 use wasmer_types::ArchivableIndexMap;
 use wasmer_types::{
-    CustomSectionIndex, DataIndex, ElemIndex, ExportIndex, ExportType, ExternType, FunctionIndex,
-    FunctionType, GlobalIndex, GlobalInit, GlobalType, ImportIndex, ImportType, LocalFunctionIndex,
-    LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex, MemoryType, SignatureIndex,
-    TableIndex, TableInitializer, TableType,
+    CustomSectionIndex, DataIndex, ElemIndex, ExportIndex, ExportType, ExternType, Features,
+    FunctionIndex, FunctionType, GlobalIndex, GlobalInit, GlobalType, ImportIndex, ImportType,
+    LocalFunctionIndex, LocalGlobalIndex, LocalMemoryIndex, LocalTableIndex, MemoryIndex,
+    MemoryType, SignatureIndex, TableIndex, TableInitializer, TableType,
 };
 
 #[derive(Debug, Clone, MemoryUsage)]
@@ -129,6 +129,16 @@ pub struct ModuleInfo {
 
     /// Number of imported globals in the module.
     pub num_imported_globals: usize,
+
+    /// The offset of the contents of the code section in the Wasm
+    /// binary, which the addresses of the DWARF debug information are
+    /// relative to, or 0 if the module has no code section.
+    pub code_section_offset: usize,
+
+    /// The WebAssembly proposals the module uses, as the flags of the
+    /// features enabling them. The translator sets them while it reads
+    /// the module.
+    pub used_features: Features,
 }
 
 /// Mirror version of ModuleInfo that can derive rkyv traits
@@ -155,6 +165,8 @@ pub struct ArchivableModuleInfo {
     num_imported_tables: usize,
     num_imported_memories: usize,
     num_imported_globals: usize,
+    code_section_offset: usize,
+    used_features: Features,
 }
 
 #[cfg(feature = "enable-rkyv")]
@@ -181,6 +193,8 @@ impl From<ModuleInfo> for ArchivableModuleInfo {
             num_imported_tables: it.num_imported_tables,
             num_imported_memories: it.num_imported_memories,
             num_imported_globals: it.num_imported_globals,
+            code_section_offset: it.code_section_offset,
+            used_features: it.used_features,
         }
     }
 }
@@ -210,6 +224,8 @@ impl From<ArchivableModuleInfo> for ModuleInfo {
             num_imported_tables: it.num_imported_tables,
             num_imported_memories: it.num_imported_memories,
             num_imported_globals: it.num_imported_globals,
+            code_section_offset: it.code_section_offset,
+            used_features: it.used_features,
         }
     }
 }
@@ -272,6 +288,8 @@ impl PartialEq for ModuleInfo {
             && self.num_imported_tables == other.num_imported_tables
             && self.num_imported_memories == other.num_imported_memories
             && self.num_imported_globals == other.num_imported_globals
+            && self.code_section_offset == other.code_section_offset
+            && self.used_features == other.used_features
     }
 }
 
@@ -302,6 +320,19 @@ impl ModuleInfo {
             num_imported_globals: 0,
             custom_sections: IndexMap::new(),
             custom_sections_data: PrimaryMap::new(),
+            code_section_offset: 0,
+            used_features: Features {
+                threads: false,
+                reference_types: false,
+                simd: false,
+                bulk_memory: false,
+                multi_value: false,
+                tail_call: false,
+                module_linking: false,
+                multi_memory: false,
+                memory64: false,
+                exceptions: false,
+            },
         }
     }
 
@@ -490,6 +521,28 @@ impl ModuleInfo {
             .take(self.num_imported_functions)
             .map(move |sig_index| self.signatures[*sig_index].clone())
     }
+
+    /// Groups the signatures of the module that are structurally equal.
+    ///
+    /// Returns the index of the first occurrence of each distinct
+    /// signature, along with, for every signature, the position of its
+    /// distinct signature in that list. This lets code generated per
+    /// signature, like trampolines, be generated only once.
+    pub fn distinct_signatures(&self) -> (Vec<SignatureIndex>, PrimaryMap<SignatureIndex, usize>) {
+        let mut positions = HashMap::new();
+        let mut distinct = Vec::new();
+        let groups = self
+            .signatures
+            .iter()
+            .map(|(index, signature)| {
+                *positions.entry(signature).or_insert_with(|| {
+                    distinct.push(index);
+                    distinct.len() - 1
+                })
+            })
+            .collect();
+        (distinct, groups)
+    }
 }
 
 impl fmt::Display for ModuleInfo {
@@ -6,16 +6,20 @@
 
 use crate::vmcontext::VMSharedSignatureIndex;
 use loupe::MemoryUsage;
-use more_asserts::{assert_lt, debug_assert_lt};
+use more_asserts::assert_lt;
 use std::collections::{hash_map, HashMap};
-use std::convert::TryFrom;
-use std::sync::RwLock;
+use std::sync::{Arc, RwLock};
 use wasmer_types::FunctionType;
 
 /// WebAssembly requires that the caller and callee signatures in an indirect
 /// call must match. To implement this efficiently, keep a registry of all
 /// signatures, shared by all instances, so that call sites can just do an
 /// index comparison.
+///
+/// Signatures are reference counted: every registration must eventually be
+/// balanced by an [`SignatureRegistry::unregister`] for the signature to be
+/// removed. Indices are never reused, so a stale index that is still stored
+/// somewhere can't be confused with a signature registered later.
 #[derive(Debug, MemoryUsage)]
 pub struct SignatureRegistry {
     // This structure is stored in an `Engine` and is intended to be shared
@@ -29,7 +33,60 @@ pub struct SignatureRegistry {
 #[derive(Debug, Default, MemoryUsage)]
 struct Inner {
     signature2index: HashMap<FunctionType, VMSharedSignatureIndex>,
-    index2signature: HashMap<VMSharedSignatureIndex, FunctionType>,
+    index2signature: HashMap<VMSharedSignatureIndex, Entry>,
+    /// The index given to the next new signature.
+    next_index: u32,
+}
+
+#[derive(Debug, MemoryUsage)]
+struct Entry {
+    signature: FunctionType,
+    /// The number of registrations not yet unregistered.
+    references: usize,
+}
+
+impl Inner {
+    fn register(&mut self, sig: &FunctionType) -> VMSharedSignatureIndex {
+        match self.signature2index.entry(sig.clone()) {
+            hash_map::Entry::Occupied(entry) => {
+                let sig_id = *entry.get();
+                self.index2signature.get_mut(&sig_id).unwrap().references += 1;
+                sig_id
+            }
+            hash_map::Entry::Vacant(entry) => {
+                // Keep indices under 2**32 -- VMSharedSignatureIndex::new(std::u32::MAX)
+                // is reserved for VMSharedSignatureIndex::default().
+                assert_lt!(
+                    self.next_index,
+                    std::u32::MAX,
+                    "Invariant check: next_index < std::u32::MAX"
+                );
+                let sig_id = VMSharedSignatureIndex::new(self.next_index);
+                self.next_index += 1;
+                entry.insert(sig_id);
+                self.index2signature.insert(
+                    sig_id,
+                    Entry {
+                        signature: sig.clone(),
+                        references: 1,
+                    },
+                );
+                sig_id
+            }
+        }
+    }
+
+    fn unregister(&mut self, idx: VMSharedSignatureIndex) {
+        let entry = match self.index2signature.get_mut(&idx) {
+            Some(entry) => entry,
+            None => return,
+        };
+        entry.references -= 1;
+        if entry.references == 0 {
+            let entry = self.index2signature.remove(&idx).unwrap();
+            self.signature2index.remove(&entry.signature);
+        }
+    }
 }
 
 impl SignatureRegistry {
@@ -41,24 +98,42 @@ impl SignatureRegistry {
     }
 
     /// Register a signature and return its unique index.
+    ///
+    /// Registering a signature that is already registered returns the
+    /// same index, and adds a reference to it.
     pub fn register(&self, sig: &FunctionType) -> VMSharedSignatureIndex {
+        self.inner.write().unwrap().register(sig)
+    }
+
+    /// Register many signatures at once and return their unique indices,
+    /// in the same order.
+    ///
+    /// This takes the registry lock only once. The returned indices are the
+    /// same ones that calling [`SignatureRegistry::register`] for each
+    /// signature would produce, so signatures repeated within the batch
+    /// share the same index.
+    pub fn register_many<'a>(
+        &self,
+        sigs: impl IntoIterator<Item = &'a FunctionType>,
+    ) -> Vec<VMSharedSignatureIndex> {
         let mut inner = self.inner.write().unwrap();
-        let len = inner.signature2index.len();
-        match inner.signature2index.entry(sig.clone()) {
-            hash_map::Entry::Occupied(entry) => *entry.get(),
-            hash_map::Entry::Vacant(entry) => {
-                // Keep `signature_hash` len under 2**32 -- VMSharedSignatureIndex::new(std::u32::MAX)
-                // is reserved for VMSharedSignatureIndex::default().
-                debug_assert_lt!(
-                    len,
-                    std::u32::MAX as usize,
-                    "Invariant check: signature_hash.len() < std::u32::MAX"
-                );
-                let sig_id = VMSharedSignatureIndex::new(u32::try_from(len).unwrap());
-                entry.insert(sig_id);
-                inner.index2signature.insert(sig_id, sig.clone());
-                sig_id
-            }
+        sigs.into_iter().map(|sig| inner.register(sig)).collect()
+    }
+
+    /// Drop a reference to a signature, removing it from the registry
+    /// when no reference is left.
+    ///
+    /// Each call must balance a previous registration of the signature.
+    pub fn unregister(&self, idx: VMSharedSignatureIndex) {
+        self.inner.write().unwrap().unregister(idx)
+    }
+
+    /// Drop a reference to each of the signatures, like calling
+    /// [`SignatureRegistry::unregister`] for each of them.
+    pub fn unregister_many<'a>(&self, idxs: impl IntoIterator<Item = &'a VMSharedSignatureIndex>) {
+        let mut inner = self.inner.write().unwrap();
+        for idx in idxs {
+            inner.unregister(*idx);
         }
     }
 
@@ -72,6 +147,124 @@ impl SignatureRegistry {
             .unwrap()
             .index2signature
             .get(&idx)
-            .cloned()
+            .map(|entry| entry.signature.clone())
+    }
+
+    /// Returns the number of distinct signatures currently registered.
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().index2signature.len()
+    }
+
+    /// Returns whether no signature is registered.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Registrations of signatures in a [`SignatureRegistry`], released
+/// when this is dropped.
+///
+/// An artifact owns the registration of its signatures, and each of its
+/// instances keeps a reference to it, so that the signatures stay
+/// registered as long as some code that may check them is alive, even
+/// after the artifact itself is dropped.
+#[derive(Debug, MemoryUsage)]
+pub struct SignatureRegistration {
+    registry: Arc<SignatureRegistry>,
+    indices: Vec<VMSharedSignatureIndex>,
+}
+
+impl SignatureRegistration {
+    /// Take ownership of registrations previously made in `registry`.
+    ///
+    /// Each index must balance one registration, so an index registered
+    /// several times must be repeated.
+    pub fn new(registry: Arc<SignatureRegistry>, indices: Vec<VMSharedSignatureIndex>) -> Self {
+        Self { registry, indices }
+    }
+
+    /// Register the signatures in `registry` and own the registrations.
+    pub fn register<'a>(
+        registry: Arc<SignatureRegistry>,
+        sigs: impl IntoIterator<Item = &'a FunctionType>,
+    ) -> Self {
+        let indices = registry.register_many(sigs);
+        Self::new(registry, indices)
+    }
+
+    /// The registered indices, in registration order.
+    pub fn indices(&self) -> &[VMSharedSignatureIndex] {
+        &self.indices
+    }
+}
+
+impl Drop for SignatureRegistration {
+    fn drop(&mut self) {
+        self.registry.unregister_many(&self.indices);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wasmer_types::Type;
+
+    #[test]
+    fn register_many_deduplicates_within_batch() {
+        let registry = SignatureRegistry::new();
+        let binary = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+        let nullary = FunctionType::new(vec![], vec![]);
+
+        let indices = registry.register_many(&[binary.clone(), nullary.clone(), binary.clone()]);
+        assert_eq!(indices.len(), 3);
+        assert_eq!(indices[0], indices[2]);
+        assert_ne!(indices[0], indices[1]);
+
+        // The indices match the ones of individual registrations.
+        assert_eq!(registry.register(&binary), indices[0]);
+        assert_eq!(registry.register(&nullary), indices[1]);
+        assert_eq!(registry.lookup(indices[0]), Some(binary));
+    }
+
+    #[test]
+    fn unregister_removes_unreferenced_signatures() {
+        let registry = SignatureRegistry::new();
+        let binary = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+        let nullary = FunctionType::new(vec![], vec![]);
+
+        let first = registry.register(&binary);
+        assert_eq!(registry.register(&binary), first);
+        let other = registry.register(&nullary);
+        assert_eq!(registry.len(), 2);
+
+        // The signature stays as long as it's referenced.
+        registry.unregister(first);
+        assert_eq!(registry.lookup(first), Some(binary.clone()));
+        registry.unregister_many(&[first, other]);
+        assert!(registry.is_empty());
+        assert_eq!(registry.lookup(first), None);
+
+        // Indices are not reused.
+        let second = registry.register(&binary);
+        assert_ne!(second, first);
+        assert_ne!(second, other);
+        assert_eq!(registry.lookup(second), Some(binary));
+    }
+
+    #[test]
+    fn registration_is_released_on_drop() {
+        let registry = Arc::new(SignatureRegistry::new());
+        let binary = FunctionType::new(vec![Type::I32, Type::I32], vec![Type::I32]);
+
+        let registration = Arc::new(SignatureRegistration::register(
+            registry.clone(),
+            &[binary.clone()],
+        ));
+        let index = registration.indices()[0];
+        let user = registration.clone();
+        drop(registration);
+        assert_eq!(registry.lookup(index), Some(binary));
+        drop(user);
+        assert!(registry.is_empty());
     }
 }
@@ -122,6 +122,16 @@ pub enum TableElement {
     FuncRef(VMFuncRef),
 }
 
+impl TableElement {
+    /// Checks if the element is a null reference.
+    pub fn is_null(&self) -> bool {
+        match self {
+            Self::ExternRef(extern_ref) => extern_ref.is_null(),
+            Self::FuncRef(func_ref) => func_ref.is_null(),
+        }
+    }
+}
+
 impl From<TableElement> for RawTableElement {
     fn from(other: TableElement) -> Self {
         match other {
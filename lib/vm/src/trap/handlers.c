@@ -1,7 +1,16 @@
 // This file contains partial code from other sources.
 // Attributions: https://github.com/wasmerio/wasmer/blob/master/ATTRIBUTIONS.md
 
+// `ucontext` is only exposed on macOS when requesting the XSI extensions.
+#if defined(CFG_TARGET_OS_MACOS)
+#define _XOPEN_SOURCE 600
+#endif
+
 #include <setjmp.h>
+#include <stddef.h>
+#if !defined(CFG_TARGET_OS_WINDOWS)
+#include <ucontext.h>
+#endif
 
 // Note that `sigsetjmp` and `siglongjmp` are used here where possible to
 // explicitly pass a 0 argument to `sigsetjmp` that we don't need to preserve
@@ -35,3 +44,41 @@ void wasmer_unwind(void *JmpBuf) {
   platform_jmp_buf *buf = (platform_jmp_buf*) JmpBuf;
   platform_longjmp(*buf, 1);
 }
+
+#if !defined(CFG_TARGET_OS_WINDOWS)
+// The body and payload are handed to the new context through thread locals,
+// since `makecontext` can only portably forward `int` arguments.
+static __thread void (*wasmer_stack_body)(void*);
+static __thread void *wasmer_stack_payload;
+
+static void wasmer_stack_entry(void) {
+  wasmer_stack_body(wasmer_stack_payload);
+}
+
+// Runs `body(payload)` on the stack `[stack, stack + stack_size)` and
+// switches back to the caller stack once it returns.
+//
+// Traps raised while running on that stack `longjmp` straight to the
+// buffer registered by `wasmer_register_setjmp` on the caller stack, so
+// the contexts created here are simply abandoned in that case.
+int wasmer_call_on_stack(
+    void *stack,
+    size_t stack_size,
+    void (*body)(void*),
+    void *payload) {
+  ucontext_t caller, callee;
+  if (getcontext(&callee) != 0) {
+    return 0;
+  }
+  callee.uc_stack.ss_sp = stack;
+  callee.uc_stack.ss_size = stack_size;
+  callee.uc_link = &caller;
+  wasmer_stack_body = body;
+  wasmer_stack_payload = payload;
+  makecontext(&callee, wasmer_stack_entry, 0);
+  if (swapcontext(&caller, &callee) != 0) {
+    return 0;
+  }
+  return 1;
+}
+#endif
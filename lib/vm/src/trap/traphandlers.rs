@@ -12,6 +12,7 @@ use std::cell::{Cell, UnsafeCell};
 use std::error::Error;
 use std::io;
 use std::mem::{self, MaybeUninit};
+use std::panic::{self, PanicInfo};
 use std::ptr;
 use std::sync::Once;
 pub use tls::TlsRestore;
@@ -33,6 +34,13 @@ extern "C" {
         payload: *mut u8,
     ) -> i32;
     fn wasmer_unwind(jmp_buf: *const u8) -> !;
+    #[cfg(unix)]
+    fn wasmer_call_on_stack(
+        stack: *mut u8,
+        stack_size: usize,
+        body: extern "C" fn(*mut u8),
+        payload: *mut u8,
+    ) -> i32;
 }
 
 cfg_if::cfg_if! {
@@ -131,6 +139,8 @@ cfg_if::cfg_if! {
                     // We assume the guard page is 1 page, and pages are 4KiB (or 16KiB in Apple Silicon)
                     if stackaddr - region::page::size() <= addr && addr < stackaddr + stacksize {
                         Some(TrapCode::StackOverflow)
+                    } else if wasm_stack::is_guard_page(addr) {
+                        Some(TrapCode::StackOverflow)
                     } else {
                         Some(TrapCode::HeapAccessOutOfBounds)
                     }
@@ -154,6 +164,7 @@ cfg_if::cfg_if! {
                 // out what to do based on the result of the trap handling.
                 let jmp_buf = info.handle_trap(
                     get_pc(context),
+                    signum,
                     false,
                     maybe_signal_trap,
                     |handler| handler(signum, siginfo, context),
@@ -348,6 +359,7 @@ cfg_if::cfg_if! {
 
                 let jmp_buf = info.handle_trap(
                     pc,
+                    record.ExceptionCode as i32,
                     record.ExceptionCode == EXCEPTION_STACK_OVERFLOW,
                     // TODO: fix the signal trap associated to memory access in Windows
                     None,
@@ -393,6 +405,26 @@ pub fn init_traps(is_wasm_pc: fn(usize) -> bool) {
     });
 }
 
+/// Installs the panic hook that lets the [`TrapHandler`]s of the calls
+/// into wasm handle the panics happening during these calls, see
+/// [`TrapHandler::handle_panic`].
+///
+/// The hook is installed once per process, on top of the hook in place
+/// at that time. Panics that no trap handler handles, including all the
+/// panics happening outside of wasm calls, are passed to that previous
+/// hook.
+pub fn init_panic_hook() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !tls::any(|state| state.trap_handler.handle_panic(info)) {
+                previous(info)
+            }
+        }));
+    });
+}
+
 /// Raises a user-defined trap immediately.
 ///
 /// This function performs as-if a wasm trap was just executed, only the trap
@@ -427,13 +459,24 @@ pub unsafe fn raise_lib_trap(trap: Trap) -> ! {
 /// Carries a Rust panic across wasm code and resumes the panic on the other
 /// side.
 ///
+/// If the [`TrapHandler`] asks to catch host panics, the panic is turned
+/// into a [`Trap::HostPanic`] instead.
+///
 /// # Safety
 ///
 /// Only safe to call when wasm code is on the stack, aka `catch_traps` must
 /// have been previously called and not returned. Additionally no Rust destructors may be on the
 /// stack. They will be skipped and not executed.
 pub unsafe fn resume_panic(payload: Box<dyn Any + Send>) -> ! {
-    tls::with(|info| info.unwrap().unwind_with(UnwindReason::Panic(payload)))
+    tls::with(|info| {
+        let info = info.unwrap();
+        let reason = if info.trap_handler.catch_host_panics() {
+            UnwindReason::LibTrap(Trap::host_panic(payload))
+        } else {
+            UnwindReason::Panic(payload)
+        };
+        info.unwind_with(reason)
+    })
 }
 
 #[cfg(target_os = "windows")]
@@ -464,6 +507,9 @@ pub enum Trap {
     Wasm {
         /// The program counter in generated code where this trap happened.
         pc: usize,
+        /// The signal number (or, on Windows, the exception code) that
+        /// caused the trap.
+        signal: i32,
         /// Native stack backtrace at the time the trap occurred
         backtrace: Backtrace,
         /// Optional trapcode associated to the signal that caused the trap
@@ -487,15 +533,30 @@ pub enum Trap {
         /// Native stack backtrace at the time the OOM occurred
         backtrace: Backtrace,
     },
+
+    /// A panic of a host function, caught because the [`TrapHandler`]
+    /// asked for it.
+    HostPanic {
+        /// The message the host function panicked with.
+        message: String,
+        /// Native stack backtrace at the time the panic was caught
+        backtrace: Backtrace,
+    },
 }
 
 impl Trap {
     /// Construct a new Wasm trap with the given source location and backtrace.
     ///
     /// Internally saves a backtrace when constructed.
-    pub fn wasm(pc: usize, backtrace: Backtrace, signal_trap: Option<TrapCode>) -> Self {
+    pub fn wasm(
+        pc: usize,
+        signal: i32,
+        backtrace: Backtrace,
+        signal_trap: Option<TrapCode>,
+    ) -> Self {
         Trap::Wasm {
             pc,
+            signal,
             backtrace,
             signal_trap,
         }
@@ -505,7 +566,7 @@ impl Trap {
     ///
     /// Internally saves a backtrace when constructed.
     pub fn lib(trap_code: TrapCode) -> Self {
-        let backtrace = Backtrace::new_unresolved();
+        let backtrace = trap_backtrace();
         Trap::Lib {
             trap_code,
             backtrace,
@@ -516,9 +577,36 @@ impl Trap {
     ///
     /// Internally saves a backtrace when constructed.
     pub fn oom() -> Self {
-        let backtrace = Backtrace::new_unresolved();
+        let backtrace = trap_backtrace();
         Trap::OOM { backtrace }
     }
+
+    /// Construct a new host panic trap from the payload of the panic.
+    ///
+    /// Internally saves a backtrace when constructed.
+    pub fn host_panic(payload: Box<dyn Any + Send>) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            message.to_string()
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            "<non-string panic payload>".to_string()
+        };
+        let backtrace = trap_backtrace();
+        Trap::HostPanic { message, backtrace }
+    }
+}
+
+/// Captures the native backtrace of a trap, unless the [`TrapHandler`]
+/// of the current call into wasm doesn't want backtraces.
+fn trap_backtrace() -> Backtrace {
+    let capture =
+        tls::with(|info| info.map_or(true, |info| info.trap_handler.capture_backtraces()));
+    if capture {
+        Backtrace::new_unresolved()
+    } else {
+        Backtrace::from(Vec::new())
+    }
 }
 
 /// Call the wasm function pointed to by `callee`.
@@ -558,13 +646,32 @@ pub unsafe fn catch_traps<F>(trap_handler: &dyn TrapHandler, mut closure: F) ->
 where
     F: FnMut(),
 {
-    return CallThreadState::new(trap_handler).with(|cx| {
-        wasmer_register_setjmp(
+    // Only the outermost wasm entry of a thread switches stacks. Reentrant
+    // calls (wasm calling the host calling wasm again) keep running on the
+    // stack that is already in use, so they all share the same limit.
+    let wasm_stack = match trap_handler.wasm_stack_size() {
+        Some(size) => wasm_stack::enter(size)?,
+        None => None,
+    };
+    let result = CallThreadState::new(trap_handler).with(|cx| match &wasm_stack {
+        Some(stack) => {
+            let mut payload = (stack, &mut closure as *mut F);
+            wasmer_register_setjmp(
+                cx.jmp_buf.as_ptr(),
+                call_closure_on_stack::<F>,
+                &mut payload as *mut (&wasm_stack::WasmStack, *mut F) as *mut u8,
+            )
+        }
+        None => wasmer_register_setjmp(
             cx.jmp_buf.as_ptr(),
             call_closure::<F>,
             &mut closure as *mut F as *mut u8,
-        )
+        ),
     });
+    if let Err(trap) = &result {
+        trap_handler.observe_trap(trap);
+    }
+    return result;
 
     extern "C" fn call_closure<F>(payload: *mut u8)
     where
@@ -572,6 +679,16 @@ where
     {
         unsafe { (*(payload as *mut F))() }
     }
+
+    extern "C" fn call_closure_on_stack<F>(payload: *mut u8)
+    where
+        F: FnMut(),
+    {
+        unsafe {
+            let (stack, closure) = *(payload as *mut (&wasm_stack::WasmStack, *mut F));
+            stack.call(call_closure::<F>, closure as *mut u8)
+        }
+    }
 }
 
 /// Catches any wasm traps that happen within the execution of `closure`,
@@ -622,6 +739,48 @@ pub unsafe trait TrapHandler {
     ///
     /// Returns `true` if `call` returns true, otherwise returns `false`.
     fn custom_trap_handler(&self, call: &dyn Fn(&TrapHandlerFn) -> bool) -> bool;
+
+    /// The size of the dedicated stack wasm code should run on, if any.
+    ///
+    /// When `None` is returned, wasm runs directly on the stack of the
+    /// calling thread.
+    fn wasm_stack_size(&self) -> Option<usize> {
+        None
+    }
+
+    /// Whether panics of host functions called from wasm should be
+    /// caught and reported as a [`Trap::HostPanic`] rather than be
+    /// resumed once out of wasm.
+    fn catch_host_panics(&self) -> bool {
+        false
+    }
+
+    /// Whether traps should capture the native backtrace at the time
+    /// they happen.
+    ///
+    /// Capturing a backtrace walks the stack, which is wasted work for
+    /// callers that never look at where a trap happened.
+    fn capture_backtraces(&self) -> bool {
+        true
+    }
+
+    /// Called with every trap caught by [`catch_traps`], on the thread
+    /// that trapped, once it is out of wasm and before the trap is
+    /// returned.
+    fn observe_trap(&self, _trap: &Trap) {}
+
+    /// Called with the panics happening on the thread while it runs
+    /// this call into wasm, before they unwind, instead of the panic
+    /// hook of the process. Returns whether the panic was handled.
+    ///
+    /// With nested calls, the innermost call is asked first, then the
+    /// calls it is nested in, until one handles the panic. Panics that
+    /// aren't handled go to the panic hook of the process.
+    ///
+    /// This is only called once [`init_panic_hook`] has been called.
+    fn handle_panic(&self, _info: &PanicInfo<'_>) -> bool {
+        false
+    }
 }
 
 enum UnwindReason {
@@ -635,6 +794,7 @@ enum UnwindReason {
     WasmTrap {
         backtrace: Backtrace,
         pc: usize,
+        signal: i32,
         signal_trap: Option<TrapCode>,
     },
 }
@@ -667,8 +827,9 @@ impl<'a> CallThreadState<'a> {
             UnwindReason::WasmTrap {
                 backtrace,
                 pc,
+                signal,
                 signal_trap,
-            } => Err(Trap::wasm(pc, backtrace, signal_trap)),
+            } => Err(Trap::wasm(pc, signal, backtrace, signal_trap)),
             UnwindReason::Panic(panic) => std::panic::resume_unwind(panic),
         }
     }
@@ -683,6 +844,8 @@ impl<'a> CallThreadState<'a> {
     /// Trap handler using our thread-local state.
     ///
     /// * `pc` - the program counter the trap happened at
+    /// * `signal` - the signal number, or exception code on Windows, that
+    ///   caused the trap
     /// * `reset_guard_page` - whether or not to reset the guard page,
     ///   currently Windows specific
     /// * `call_handler` - a closure used to invoke the platform-specific
@@ -700,6 +863,7 @@ impl<'a> CallThreadState<'a> {
     fn handle_trap(
         &self,
         pc: *const u8,
+        signal: i32,
         reset_guard_page: bool,
         signal_trap: Option<TrapCode>,
         call_handler: impl Fn(&TrapHandlerFn) -> bool,
@@ -736,7 +900,11 @@ impl<'a> CallThreadState<'a> {
             self.handling_trap.set(false);
             return ptr::null();
         }
-        let backtrace = Backtrace::new_unresolved();
+        let backtrace = if self.trap_handler.capture_backtraces() {
+            Backtrace::new_unresolved()
+        } else {
+            Backtrace::from(Vec::new())
+        };
         self.reset_guard_page.set(reset_guard_page);
         unsafe {
             (*self.unwind.get())
@@ -745,6 +913,7 @@ impl<'a> CallThreadState<'a> {
                     backtrace,
                     signal_trap,
                     pc: pc as usize,
+                    signal,
                 });
         }
         self.handling_trap.set(false);
@@ -819,6 +988,14 @@ mod tls {
         pub fn get() -> Ptr {
             PTR.with(|p| p.get().0)
         }
+
+        /// Like `get`, but returns a null pointer once the thread local
+        /// has been destroyed, as panics may happen in the destructors
+        /// of other thread locals.
+        #[inline(never)] // see module docs for why this is here
+        pub fn try_get() -> Ptr {
+            PTR.try_with(|p| p.get().0).unwrap_or(ptr::null())
+        }
     }
 
     /// Opaque state used to help control TLS state across stack switches for
@@ -887,6 +1064,20 @@ mod tls {
         Ok(closure())
     }
 
+    /// Calls `closure` with the states configured with `set` that haven't
+    /// returned yet, the innermost first, until it returns `true`.
+    pub fn any(mut closure: impl FnMut(&CallThreadState<'_>) -> bool) -> bool {
+        let mut p = raw::try_get();
+        while !p.is_null() {
+            let state = unsafe { &*p };
+            if closure(state) {
+                return true;
+            }
+            p = state.prev.get();
+        }
+        false
+    }
+
     /// Returns the last pointer configured with `set` above. Panics if `set`
     /// has not been previously called and not returned.
     pub fn with<R>(closure: impl FnOnce(Option<&CallThreadState<'_>>) -> R) -> R {
@@ -1007,3 +1198,161 @@ pub fn lazy_per_thread_init() -> Result<(), Trap> {
         }
     }
 }
+
+/// A module for running wasm on a dedicated stack with a guard page.
+///
+/// The stack is allocated lazily per thread, and reused by every wasm entry
+/// of that thread as long as the requested size doesn't change. Overflowing
+/// it hits the guard page, which the signal handler reports as a
+/// `TrapCode::StackOverflow` rather than crashing the process.
+#[cfg(unix)]
+mod wasm_stack {
+    use super::Trap;
+    use std::cell::{Cell, RefCell};
+    use std::ptr;
+
+    thread_local! {
+        /// The stack owned by the current thread, if already allocated.
+        static STACK: RefCell<Option<Allocation>> = RefCell::new(None);
+
+        /// The `[start, end)` range of the guard page of the current thread
+        /// stack, readable from the signal handler.
+        static GUARD: Cell<(usize, usize)> = Cell::new((0, 0));
+
+        /// Whether wasm is currently running on the stack of this thread.
+        static ACTIVE: Cell<bool> = Cell::new(false);
+    }
+
+    struct Allocation {
+        mmap_ptr: *mut libc::c_void,
+        mmap_size: usize,
+        stack_size: usize,
+    }
+
+    impl Drop for Allocation {
+        fn drop(&mut self) {
+            unsafe {
+                let r = libc::munmap(self.mmap_ptr, self.mmap_size);
+                debug_assert_eq!(r, 0, "munmap failed during thread shutdown");
+            }
+        }
+    }
+
+    /// The stack wasm is running on, released when dropped.
+    pub struct WasmStack {
+        base: *mut u8,
+        size: usize,
+    }
+
+    impl WasmStack {
+        /// Calls `body(payload)` on this stack.
+        ///
+        /// # Safety
+        ///
+        /// Only safe to call from within `wasmer_register_setjmp`, so
+        /// that traps never unwind past the stack switch.
+        pub unsafe fn call(&self, body: extern "C" fn(*mut u8), payload: *mut u8) {
+            if super::wasmer_call_on_stack(self.base, self.size, body, payload) == 0 {
+                // The context couldn't be created, just run on the
+                // current stack instead.
+                body(payload)
+            }
+        }
+    }
+
+    impl Drop for WasmStack {
+        fn drop(&mut self) {
+            ACTIVE.with(|active| active.set(false));
+        }
+    }
+
+    /// Prepares the stack of the current thread to run wasm with `size`
+    /// bytes of stack.
+    ///
+    /// Returns `None` if wasm is already running on it, which happens on
+    /// reentrant calls.
+    pub fn enter(size: usize) -> Result<Option<WasmStack>, Trap> {
+        if ACTIVE.with(|active| active.get()) {
+            return Ok(None);
+        }
+        let page_size = region::page::size();
+        let stack_size = (size + page_size - 1) & !(page_size - 1);
+        STACK.with(|slot| {
+            let mut slot = slot.borrow_mut();
+            match &*slot {
+                Some(allocation) if allocation.stack_size == stack_size => {}
+                _ => {
+                    // Release the previous stack before allocating a new one.
+                    *slot = None;
+                    *slot = Some(allocate(stack_size, page_size)?);
+                }
+            }
+            let allocation = slot.as_ref().unwrap();
+            let guard_start = allocation.mmap_ptr as usize;
+            GUARD.with(|guard| guard.set((guard_start, guard_start + page_size)));
+            ACTIVE.with(|active| active.set(true));
+            Ok(Some(WasmStack {
+                base: (guard_start + page_size) as *mut u8,
+                size: stack_size,
+            }))
+        })
+    }
+
+    fn allocate(stack_size: usize, guard_size: usize) -> Result<Allocation, Trap> {
+        let mmap_size = guard_size + stack_size;
+        unsafe {
+            let ptr = libc::mmap(
+                ptr::null_mut(),
+                mmap_size,
+                libc::PROT_NONE,
+                libc::MAP_PRIVATE | libc::MAP_ANON,
+                -1,
+                0,
+            );
+            if ptr == libc::MAP_FAILED {
+                return Err(Trap::oom());
+            }
+            // The stack grows downwards, so the guard page is kept at the
+            // lowest address and everything above it is made accessible.
+            let stack_ptr = (ptr as usize + guard_size) as *mut libc::c_void;
+            if libc::mprotect(stack_ptr, stack_size, libc::PROT_READ | libc::PROT_WRITE) != 0 {
+                libc::munmap(ptr, mmap_size);
+                return Err(Trap::oom());
+            }
+            Ok(Allocation {
+                mmap_ptr: ptr,
+                mmap_size,
+                stack_size,
+            })
+        }
+    }
+
+    /// Checks whether `addr` lies in the guard page of the stack wasm is
+    /// currently running on.
+    pub fn is_guard_page(addr: usize) -> bool {
+        ACTIVE.with(|active| active.get())
+            && GUARD.with(|guard| {
+                let (start, end) = guard.get();
+                start <= addr && addr < end
+            })
+    }
+}
+
+#[cfg(not(unix))]
+mod wasm_stack {
+    use super::Trap;
+
+    /// Dedicated wasm stacks are not supported on this platform, so wasm
+    /// always runs on the stack of the calling thread.
+    pub enum WasmStack {}
+
+    impl WasmStack {
+        pub unsafe fn call(&self, _body: extern "C" fn(*mut u8), _payload: *mut u8) {
+            match *self {}
+        }
+    }
+
+    pub fn enter(_size: usize) -> Result<Option<WasmStack>, Trap> {
+        Ok(None)
+    }
+}
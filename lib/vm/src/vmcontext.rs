@@ -679,7 +679,8 @@ impl VMGlobalDefinition {
     ///
     /// If this is not an F32 typed global it is unspecified what value is returned.
     pub fn to_f32(&self) -> f32 {
-        unsafe { self.storage.as_f32 }
+        // Going through the bits preserves the payload of NaNs.
+        f32::from_bits(unsafe { self.storage.as_u32 })
     }
 
     /// Return a mutable reference to the value as an f32.
@@ -698,7 +699,8 @@ impl VMGlobalDefinition {
     ///
     /// If this is not an F64 typed global it is unspecified what value is returned.
     pub fn to_f64(&self) -> f64 {
-        unsafe { self.storage.as_f64 }
+        // Going through the bits preserves the payload of NaNs.
+        f64::from_bits(unsafe { self.storage.as_u64 })
     }
 
     /// Return a mutable reference to the value as an f64.
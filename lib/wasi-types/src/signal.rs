@@ -1,3 +1,5 @@
+use std::sync::RwLock;
+
 pub type __wasi_signal_t = u8;
 pub const __WASI_SIGHUP: u8 = 1;
 pub const __WASI_SIGINT: u8 = 2;
@@ -29,3 +31,178 @@ pub const __WASI_SIGWINCH: u8 = 27;
 pub const __WASI_SIGPOLL: u8 = 28;
 pub const __WASI_SIGPWR: u8 = 29;
 pub const __WASI_SIGSYS: u8 = 30;
+
+/// A mapping between the signal numbers of the host and
+/// `__wasi_signal_t`.
+///
+/// The numbers of the host signals differ between platforms, so
+/// [`SignalMapping::host`] builds the mapping of the current
+/// platform. Embedders on platforms it doesn't know about can build
+/// their own with [`SignalMapping::new`] and install it with
+/// [`set_signal_mapping`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignalMapping {
+    pairs: Vec<(i32, __wasi_signal_t)>,
+}
+
+impl SignalMapping {
+    /// Creates a mapping from `(host signal, WASI signal)` pairs.
+    ///
+    /// If a signal appears in several pairs, the first one wins.
+    pub fn new<I>(pairs: I) -> Self
+    where
+        I: IntoIterator<Item = (i32, __wasi_signal_t)>,
+    {
+        Self {
+            pairs: pairs.into_iter().collect(),
+        }
+    }
+
+    /// Creates the mapping of the current platform.
+    ///
+    /// Host signals without a WASI equivalent, and WASI signals
+    /// without a host equivalent, aren't part of it.
+    pub fn host() -> Self {
+        #[cfg(unix)]
+        let pairs: &[(i32, __wasi_signal_t)] = &[
+            (libc::SIGHUP, __WASI_SIGHUP),
+            (libc::SIGINT, __WASI_SIGINT),
+            (libc::SIGQUIT, __WASI_SIGQUIT),
+            (libc::SIGILL, __WASI_SIGILL),
+            (libc::SIGTRAP, __WASI_SIGTRAP),
+            (libc::SIGABRT, __WASI_SIGABRT),
+            (libc::SIGBUS, __WASI_SIGBUS),
+            (libc::SIGFPE, __WASI_SIGFPE),
+            (libc::SIGKILL, __WASI_SIGKILL),
+            (libc::SIGUSR1, __WASI_SIGUSR1),
+            (libc::SIGSEGV, __WASI_SIGSEGV),
+            (libc::SIGUSR2, __WASI_SIGUSR2),
+            (libc::SIGPIPE, __WASI_SIGPIPE),
+            (libc::SIGALRM, __WASI_SIGALRM),
+            (libc::SIGTERM, __WASI_SIGTERM),
+            (libc::SIGCHLD, __WASI_SIGCHLD),
+            (libc::SIGCONT, __WASI_SIGCONT),
+            (libc::SIGSTOP, __WASI_SIGSTOP),
+            (libc::SIGTSTP, __WASI_SIGTSTP),
+            (libc::SIGTTIN, __WASI_SIGTTIN),
+            (libc::SIGTTOU, __WASI_SIGTTOU),
+            (libc::SIGURG, __WASI_SIGURG),
+            (libc::SIGXCPU, __WASI_SIGXCPU),
+            (libc::SIGXFSZ, __WASI_SIGXFSZ),
+            (libc::SIGVTALRM, __WASI_SIGVTALRM),
+            (libc::SIGPROF, __WASI_SIGPROF),
+            (libc::SIGWINCH, __WASI_SIGWINCH),
+            (libc::SIGIO, __WASI_SIGPOLL),
+            #[cfg(any(target_os = "linux", target_os = "android"))]
+            (libc::SIGPWR, __WASI_SIGPWR),
+            (libc::SIGSYS, __WASI_SIGSYS),
+        ];
+        #[cfg(windows)]
+        let pairs: &[(i32, __wasi_signal_t)] = &[
+            (libc::SIGINT, __WASI_SIGINT),
+            (libc::SIGILL, __WASI_SIGILL),
+            (libc::SIGABRT, __WASI_SIGABRT),
+            (libc::SIGFPE, __WASI_SIGFPE),
+            (libc::SIGSEGV, __WASI_SIGSEGV),
+            (libc::SIGTERM, __WASI_SIGTERM),
+        ];
+        #[cfg(not(any(unix, windows)))]
+        let pairs: &[(i32, __wasi_signal_t)] = &[];
+
+        Self::new(pairs.iter().copied())
+    }
+
+    /// Returns the WASI signal of `host_sig`, or `None` if it has no
+    /// WASI equivalent.
+    pub fn to_wasi(&self, host_sig: i32) -> Option<__wasi_signal_t> {
+        self.pairs
+            .iter()
+            .find(|(host, _)| *host == host_sig)
+            .map(|(_, wasi)| *wasi)
+    }
+
+    /// Returns the host signal of `sig`, or `None` if it has no host
+    /// equivalent.
+    pub fn to_host(&self, sig: __wasi_signal_t) -> Option<i32> {
+        self.pairs
+            .iter()
+            .find(|(_, wasi)| *wasi == sig)
+            .map(|(host, _)| *host)
+    }
+}
+
+impl Default for SignalMapping {
+    fn default() -> Self {
+        Self::host()
+    }
+}
+
+lazy_static::lazy_static! {
+    static ref SIGNAL_MAPPING: RwLock<SignalMapping> = RwLock::new(SignalMapping::host());
+}
+
+/// Installs the mapping used by [`wasi_signal_from_host`] and
+/// [`host_signal_from_wasi`], or restores the mapping of the current
+/// platform if `mapping` is `None`.
+pub fn set_signal_mapping(mapping: Option<SignalMapping>) {
+    *SIGNAL_MAPPING.write().unwrap() = mapping.unwrap_or_else(SignalMapping::host);
+}
+
+/// Returns the WASI signal of the host signal `host_sig`, or `None` if
+/// it has no WASI equivalent.
+pub fn wasi_signal_from_host(host_sig: i32) -> Option<__wasi_signal_t> {
+    SIGNAL_MAPPING.read().unwrap().to_wasi(host_sig)
+}
+
+/// Returns the host signal of the WASI signal `sig`, or `None` if it
+/// has no host equivalent.
+pub fn host_signal_from_wasi(sig: __wasi_signal_t) -> Option<i32> {
+    SIGNAL_MAPPING.read().unwrap().to_host(sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn host_mapping() {
+        let mapping = SignalMapping::host();
+        assert_eq!(mapping.to_wasi(libc::SIGINT), Some(__WASI_SIGINT));
+        assert_eq!(mapping.to_wasi(libc::SIGUSR1), Some(__WASI_SIGUSR1));
+        assert_eq!(mapping.to_wasi(libc::SIGIO), Some(__WASI_SIGPOLL));
+        assert_eq!(mapping.to_host(__WASI_SIGTERM), Some(libc::SIGTERM));
+        assert_eq!(mapping.to_host(__WASI_SIGUSR2), Some(libc::SIGUSR2));
+
+        // Every WASI signal known to the host maps back to itself.
+        for sig in __WASI_SIGHUP..=__WASI_SIGSYS {
+            if let Some(host_sig) = mapping.to_host(sig) {
+                assert_eq!(mapping.to_wasi(host_sig), Some(sig));
+            }
+        }
+
+        // Signals without a WASI equivalent.
+        assert_eq!(mapping.to_wasi(0), None);
+        assert_eq!(mapping.to_wasi(1000), None);
+    }
+
+    #[test]
+    fn custom_mapping() {
+        let mapping = SignalMapping::new(vec![(42, __WASI_SIGINT), (43, __WASI_SIGTERM)]);
+        assert_eq!(mapping.to_wasi(42), Some(__WASI_SIGINT));
+        assert_eq!(mapping.to_host(__WASI_SIGTERM), Some(43));
+        assert_eq!(mapping.to_wasi(2), None);
+        assert_eq!(mapping.to_host(__WASI_SIGHUP), None);
+
+        // This is the only test touching the installed mapping.
+        set_signal_mapping(Some(mapping));
+        assert_eq!(wasi_signal_from_host(42), Some(__WASI_SIGINT));
+        assert_eq!(host_signal_from_wasi(__WASI_SIGTERM), Some(43));
+        set_signal_mapping(None);
+        assert_eq!(wasi_signal_from_host(42), SignalMapping::host().to_wasi(42));
+        assert_eq!(
+            host_signal_from_wasi(__WASI_SIGINT),
+            SignalMapping::host().to_host(__WASI_SIGINT)
+        );
+    }
+}
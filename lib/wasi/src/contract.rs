@@ -0,0 +1,96 @@
+use crate::{generate_import_object_from_env, WasiEnv, WasiState, WasiVersion};
+use thiserror::Error;
+use wasmer::{Export, ExternType, FunctionType, Module, Store};
+
+/// An import of a module that a version of WASI doesn't provide, found
+/// by [`check_wasi_contract`].
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ContractViolation {
+    /// The module imports something that the WASI version doesn't
+    /// define, or that isn't a function.
+    #[error("`{namespace}::{name}` is not a function of this version of WASI")]
+    UnknownImport {
+        /// The namespace of the import.
+        namespace: String,
+        /// The name of the import.
+        name: String,
+        /// The type of the import.
+        ty: ExternType,
+    },
+
+    /// The module imports a function of the WASI version with another
+    /// signature.
+    #[error(
+        "`{namespace}::{name}` has type {found} but this version of WASI defines it as {expected}"
+    )]
+    SignatureMismatch {
+        /// The namespace of the import.
+        namespace: String,
+        /// The name of the import.
+        name: String,
+        /// The signature defined by the WASI version.
+        expected: FunctionType,
+        /// The signature of the import.
+        found: FunctionType,
+    },
+}
+
+/// Checks that the WASI imports of `module` are functions that
+/// `version` provides, with the same signatures, to reject a module
+/// before instantiating it.
+///
+/// Only the imports of the namespace of `version` are checked: the
+/// imports of other namespaces, including the one of another version
+/// of WASI, are left to the embedder.
+///
+/// # Errors
+///
+/// Returns all the imports violating the contract, in the order of the
+/// imports of the module.
+pub fn check_wasi_contract(
+    module: &Module,
+    version: WasiVersion,
+) -> Result<(), Vec<ContractViolation>> {
+    let namespace = version.get_namespace_str();
+    // The functions are only created to learn their signatures, in a
+    // store of their own, which may not be frozen.
+    let store = Store::new(module.store().engine().as_ref());
+    let state = WasiState::new("")
+        .build()
+        .expect("the default WASI state can be built");
+    let import_object = generate_import_object_from_env(&store, WasiEnv::new(state), version);
+
+    let violations = module
+        .imports()
+        .filter(|import| import.module() == namespace)
+        .filter_map(|import| {
+            let expected = match import_object.get_export(namespace, import.name()) {
+                Some(Export::Function(function)) => Some(function.vm_function.signature),
+                _ => None,
+            };
+            let violation = match (import.ty(), expected) {
+                (ExternType::Function(found), Some(expected)) if *found == expected => return None,
+                (ExternType::Function(found), Some(expected)) => {
+                    ContractViolation::SignatureMismatch {
+                        namespace: namespace.to_string(),
+                        name: import.name().to_string(),
+                        expected,
+                        found: found.clone(),
+                    }
+                }
+                (ty, _) => ContractViolation::UnknownImport {
+                    namespace: namespace.to_string(),
+                    name: import.name().to_string(),
+                    ty: ty.clone(),
+                },
+            };
+            Some(violation)
+        })
+        .collect::<Vec<_>>();
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
+}
@@ -14,6 +14,7 @@
 
 #[macro_use]
 mod macros;
+mod contract;
 mod ptr;
 mod state;
 mod syscalls;
@@ -21,6 +22,7 @@ mod utils;
 
 use crate::syscalls::*;
 
+pub use crate::contract::{check_wasi_contract, ContractViolation};
 pub use crate::state::{
     Fd, Pipe, Stderr, Stdin, Stdout, WasiFile, WasiFs, WasiFsError, WasiState, WasiStateBuilder,
     WasiStateCreationError, ALL_RIGHTS, VIRTUAL_ROOT_FD,
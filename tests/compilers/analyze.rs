@@ -0,0 +1,71 @@
+use anyhow::Result;
+
+use loupe::MemoryUsage;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use wasmer::*;
+use wasmer_engine_universal::Universal;
+
+/// Counts the functions handed to the compiler.
+#[derive(Debug, MemoryUsage)]
+struct CountCompiledFunctions {
+    #[loupe(skip)]
+    count: Arc<AtomicUsize>,
+}
+
+impl ModuleMiddleware for CountCompiledFunctions {
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        self.count.fetch_add(1, Ordering::SeqCst);
+        Box::new(PassThrough)
+    }
+}
+
+#[derive(Debug)]
+struct PassThrough;
+
+impl FunctionMiddleware for PassThrough {}
+
+#[compiler_test(analyze)]
+fn analyze_reports_functions_without_compiling(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let wasm = wat2wasm(
+        br#"(module
+            (import "env" "f" (func))
+            (func $add (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1)))
+            (func $nested (local i32 i64 i64)
+                (block (loop (if (local.get 0) (then (call 0)))))))"#,
+    )?;
+
+    let count = Arc::new(AtomicUsize::new(0));
+    let mut compiler_config = config.compiler_config(false);
+    compiler_config.push_middleware(Arc::new(CountCompiledFunctions {
+        count: count.clone(),
+    }));
+    let engine = Universal::new(compiler_config).engine();
+
+    let diagnostics = engine.analyze(&wasm)?;
+    assert_eq!(diagnostics.num_functions(), 2);
+    let add = &diagnostics.functions[0];
+    assert_eq!(add.index, FunctionIndex::from_u32(1));
+    assert_eq!(add.name.as_deref(), Some("add"));
+    assert_eq!(add.num_locals, 0);
+    assert_eq!(add.num_operators, 4);
+    assert_eq!(add.max_block_depth, 0);
+    let nested = &diagnostics.functions[1];
+    assert_eq!(nested.num_locals, 3);
+    assert_eq!(nested.max_block_depth, 3);
+    assert!(nested.body_size > add.body_size);
+    // Nothing was handed to the compiler.
+    assert_eq!(count.load(Ordering::SeqCst), 0);
+
+    // Invalid modules are rejected like when compiling.
+    assert!(engine.analyze(b"\0asm\x01\0\0\0\x01").is_err());
+
+    let store = Store::new(&engine);
+    Module::new(&store, &wasm)?;
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+    Ok(())
+}
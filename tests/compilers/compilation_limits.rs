@@ -0,0 +1,68 @@
+use anyhow::Result;
+
+use wasmer::*;
+use wasmer_engine_universal::Universal;
+
+const THREE_FUNCTIONS: &[u8] = br#"(module
+    (import "env" "f" (func))
+    (func (export "a"))
+    (func (export "b")))"#;
+
+#[compiler_test(compilation_limits)]
+fn modules_exceeding_the_function_limit_are_rejected(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let limits = CompilationLimits {
+        max_functions: Some(2),
+        ..Default::default()
+    };
+    let engine = Universal::new(config.compiler_config(false))
+        .compilation_limits(limits)
+        .engine();
+    let store = Store::new(&engine);
+
+    match Module::new(&store, THREE_FUNCTIONS) {
+        Err(CompileError::ExceedsLimits {
+            which: CompilationLimit::Functions,
+            limit: 2,
+            actual: 3,
+        }) => {}
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("the module shouldn't compile"),
+    }
+    Ok(())
+}
+
+#[compiler_test(compilation_limits)]
+fn modules_within_the_limits_compile(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let limits = CompilationLimits {
+        max_functions: Some(3),
+        max_function_body_size: Some(16),
+        max_table_elements: Some(10),
+        max_memory_pages: Some(1),
+        max_globals: Some(1),
+    };
+    let engine = Universal::new(config.compiler_config(false))
+        .compilation_limits(limits)
+        .engine();
+    let store = Store::new(&engine);
+    Module::new(&store, THREE_FUNCTIONS)?;
+
+    let err = Module::new(&store, "(module (memory 2))").unwrap_err();
+    assert!(matches!(
+        err,
+        CompileError::ExceedsLimits {
+            which: CompilationLimit::MemoryPages,
+            ..
+        }
+    ));
+    assert_eq!(
+        err.to_string(),
+        "The module exceeds the limit of 1 memory pages: it has 2"
+    );
+    Ok(())
+}
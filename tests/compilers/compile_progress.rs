@@ -0,0 +1,156 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+
+use wasmer::*;
+use wasmer_engine_universal::Universal;
+
+#[compiler_test(compile_progress)]
+fn compilation_reports_every_function(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let calls = Arc::new(Mutex::new(Vec::new()));
+    let engine = Universal::new(config.compiler_config(false))
+        .compile_progress({
+            let calls = calls.clone();
+            move |compiled, total| calls.lock().unwrap().push((compiled, total))
+        })
+        .engine();
+    let store = Store::new(&engine);
+    Module::new(
+        &store,
+        r#"(module
+            (import "env" "f" (func))
+            (func (export "a"))
+            (func (export "b") (param i32) (result i32) (local.get 0))
+            (func (export "c") (call 0))
+            (func (export "d") (result i64) (i64.const 1)))"#,
+    )?;
+
+    let mut calls = calls.lock().unwrap().clone();
+    // The functions may be compiled in parallel, reporting their
+    // progress out of order.
+    calls.sort_unstable();
+    assert_eq!(calls, vec![(1, 4), (2, 4), (3, 4), (4, 4)]);
+    Ok(())
+}
+
+#[compiler_test(compile_progress)]
+fn compilation_past_deadline_times_out(config: crate::Config) -> Result<()> {
+    use std::ptr::NonNull;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+    use wasmer_engine_universal::CodeAllocator;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+
+    /// Counts the allocations of code memory, without granting any.
+    struct CountingAllocator(Arc<AtomicUsize>);
+
+    unsafe impl CodeAllocator for CountingAllocator {
+        fn allocate(&self, _len: usize) -> Result<NonNull<u8>, String> {
+            self.0.fetch_add(1, Ordering::SeqCst);
+            Err("no code memory in this test".to_string())
+        }
+
+        unsafe fn publish(&self, _ptr: NonNull<u8>, _len: usize) -> Result<(), String> {
+            unreachable!("nothing is allocated")
+        }
+
+        unsafe fn deallocate(&self, _ptr: NonNull<u8>, _len: usize) {
+            unreachable!("nothing is allocated")
+        }
+    }
+
+    let allocations = Arc::new(AtomicUsize::new(0));
+    let compiled = Arc::new(Mutex::new(0));
+    let timeout = Duration::from_micros(1);
+    let engine = Universal::new(config.compiler_config(false))
+        .code_allocator(CountingAllocator(allocations.clone()))
+        .compile_progress({
+            let compiled = compiled.clone();
+            move |_, _| *compiled.lock().unwrap() += 1
+        })
+        .compile_deadline(timeout)
+        .engine();
+    let store = Store::new(&engine);
+
+    let functions = 2000;
+    let mut wat = String::from("(module\n");
+    for i in 0..functions {
+        wat.push_str(&format!(
+            "(func (export \"f{}\") (param i64) (result i64) \
+             (i64.mul (i64.add (local.get 0) (i64.const {})) (local.get 0)))\n",
+            i, i
+        ));
+    }
+    wat.push(')');
+
+    let error = Module::new(&store, &wat).unwrap_err();
+    assert!(
+        matches!(error, CompileError::Timeout(t) if t == timeout),
+        "unexpected error: {:?}",
+        error
+    );
+    // The compilation was abandoned before the end, and before any code
+    // memory was allocated.
+    assert!(*compiled.lock().unwrap() < functions);
+    assert_eq!(allocations.load(Ordering::SeqCst), 0);
+    Ok(())
+}
+
+#[compiler_test(compile_progress)]
+fn compilation_times_out_between_functions(config: crate::Config) -> Result<()> {
+    use std::thread;
+    use std::time::Duration;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+
+    // Each function takes at least `delay` to compile, so that the
+    // deadline expires once some of them are compiled, even when they
+    // are compiled in parallel on many threads.
+    let delay = Duration::from_millis(200);
+    let timeout = Duration::from_secs(1);
+    let compiled = Arc::new(Mutex::new(0));
+    let engine = Universal::new(config.compiler_config(false))
+        .compile_progress({
+            let compiled = compiled.clone();
+            move |_, _| {
+                *compiled.lock().unwrap() += 1;
+                thread::sleep(delay);
+            }
+        })
+        .compile_deadline(timeout)
+        .engine();
+    let store = Store::new(&engine);
+
+    let functions = 2000;
+    let mut wat = String::from("(module\n");
+    for i in 0..functions {
+        wat.push_str(&format!(
+            "(func (export \"f{}\") (result i32) (i32.const {}))\n",
+            i, i
+        ));
+    }
+    wat.push(')');
+
+    let error = Module::new(&store, &wat).unwrap_err();
+    assert!(
+        matches!(error, CompileError::Timeout(t) if t == timeout),
+        "unexpected error: {:?}",
+        error
+    );
+    // The deadline was checked between the functions, rather than
+    // only after the translation of the module.
+    let compiled = *compiled.lock().unwrap();
+    assert!(
+        (1..functions).contains(&compiled),
+        "{} functions compiled",
+        compiled
+    );
+    Ok(())
+}
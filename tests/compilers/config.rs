@@ -21,6 +21,7 @@ pub struct Config {
     pub features: Option<Features>,
     pub middlewares: Vec<Arc<dyn ModuleMiddleware>>,
     pub canonicalize_nans: bool,
+    pub source_lines: bool,
 }
 
 impl Config {
@@ -30,6 +31,7 @@ impl Config {
             engine,
             features: None,
             canonicalize_nans: false,
+            source_lines: false,
             middlewares: vec![],
         }
     }
@@ -46,6 +48,10 @@ impl Config {
         self.canonicalize_nans = canonicalize_nans;
     }
 
+    pub fn set_source_lines(&mut self, source_lines: bool) {
+        self.source_lines = source_lines;
+    }
+
     pub fn store(&self) -> Store {
         let compiler_config = self.compiler_config(self.canonicalize_nans);
         let engine = self.engine(compiler_config);
@@ -71,7 +77,8 @@ impl Config {
             }
             #[cfg(feature = "universal")]
             Engine::Universal => {
-                let mut engine = wasmer_engine_universal::Universal::new(compiler_config);
+                let mut engine = wasmer_engine_universal::Universal::new(compiler_config)
+                    .source_lines(self.source_lines);
                 if let Some(ref features) = self.features {
                     engine = engine.features(features.clone())
                 }
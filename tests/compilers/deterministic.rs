@@ -0,0 +1,79 @@
+use anyhow::Result;
+use std::sync::Arc;
+use wasmer::*;
+use wasmer_compiler::{Compilation, CompileModuleInfo, ModuleEnvironment};
+
+/// Compiles `wasm` with the compiler of `config`, without linking the
+/// result, so that the function bodies can be compared.
+fn compile(config: &crate::Config, wasm: &[u8]) -> Result<Compilation> {
+    let target = Target::default();
+    let compiler_config = config.compiler_config(false);
+    let features = compiler_config.default_features_for_target(&target);
+    let compiler = compiler_config.compiler();
+    let tunables = BaseTunables::for_target(&target);
+
+    let translation = ModuleEnvironment::new().translate(wasm)?;
+    let module = translation.module;
+    let compile_info = CompileModuleInfo {
+        memory_styles: module
+            .memories
+            .values()
+            .map(|ty| tunables.memory_style(ty))
+            .collect(),
+        table_styles: module
+            .tables
+            .values()
+            .map(|ty| tunables.table_style(ty))
+            .collect(),
+        module: Arc::new(module),
+        features,
+    };
+    Ok(compiler.compile_module(
+        &target,
+        &compile_info,
+        translation.module_translation_state.as_ref().unwrap(),
+        translation.function_body_inputs,
+    )?)
+}
+
+#[compiler_test(deterministic)]
+fn test_deterministic_codegen(config: crate::Config) -> Result<()> {
+    // The compilation doesn't depend on the engine.
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let wasm = wat2wasm(
+        br#"
+        (module
+            (import "env" "log" (func $log (param i32 f64)))
+            (memory 1)
+            (func $mix (export "mix") (param i32 i64 f32 f64) (result f64)
+                (local i32 i64 f64)
+                (local.set 4 (i32.add (local.get 0) (i32.const 7)))
+                (local.set 5 (i64.mul (local.get 1) (i64.extend_i32_u (local.get 4))))
+                (local.set 6 (f64.mul (f64.promote_f32 (local.get 2)) (local.get 3)))
+                (call $log (local.get 4) (local.get 6))
+                (i64.store (local.get 0) (local.get 5))
+                (f64.add
+                    (local.get 6)
+                    (f64.convert_i64_s (i64.load (local.get 4)))))
+            (func (export "dispatch") (param i32 i32) (result i32)
+                (block
+                    (block
+                        (block
+                            (br_table 0 1 2 (local.get 0)))
+                        (return (i32.div_u (local.get 1) (local.get 0))))
+                    (drop (call $mix (local.get 1) (i64.const 3) (f32.const 1.5) (f64.const 2)))
+                    (return (local.get 1)))
+                (i32.const -1)))
+        "#,
+    )?;
+
+    // Compiling again in the same process shares nothing with the first
+    // compilation but the inputs.
+    let first = compile(&config, &wasm)?;
+    let second = compile(&config, &wasm)?;
+    assert_eq!(first.get_function_bodies(), second.get_function_bodies());
+    assert_eq!(first, second);
+    Ok(())
+}
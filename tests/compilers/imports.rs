@@ -83,6 +83,60 @@ fn dynamic_function(config: crate::Config) -> Result<()> {
     Ok(())
 }
 
+#[compiler_test(imports)]
+fn dynamic_function_with_multiple_results(config: crate::Config) -> Result<()> {
+    let is_singlepass = config.compiler == crate::Compiler::Singlepass;
+    let store = config.store();
+    let wat = r#"
+        (import "host" "split" (func $split (param i32) (result i32 i32)))
+        (func (export "split") (param i32) (result i32 i32)
+            local.get 0
+            call $split)
+        (func (export "combine") (param i32) (result i32)
+            local.get 0
+            call $split
+            ;; Check the order of the results from wasm too.
+            i32.const 1000
+            i32.mul
+            i32.add)
+    "#;
+    let module = Module::new(&store, wat);
+    if is_singlepass {
+        // Multi-value is disabled by default on singlepass.
+        assert!(module.is_err());
+        return Ok(());
+    }
+    let module = module?;
+
+    let split = Function::new(
+        &store,
+        FunctionType::new(vec![ValType::I32], vec![ValType::I32, ValType::I32]),
+        |values| {
+            let n = values[0].unwrap_i32();
+            Ok(vec![Value::I32(n / 10), Value::I32(n % 10)])
+        },
+    );
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "split" => split,
+            },
+        },
+    )?;
+
+    let results = instance
+        .exports
+        .get_function("split")?
+        .call(&[Value::I32(42)])?;
+    assert_eq!(&*results, &[Value::I32(4), Value::I32(2)]);
+
+    let combine: NativeFunc<i32, i32> = instance.exports.get_native_function("combine")?;
+    assert_eq!(combine.call(42)?, 4 + 2 * 1000);
+
+    Ok(())
+}
+
 #[compiler_test(imports)]
 fn dynamic_function_with_env(config: crate::Config) -> Result<()> {
     let store = config.store();
@@ -432,3 +486,134 @@ fn instance_local_memory_lifetime(config: crate::Config) -> Result<()> {
 
     Ok(())
 }
+
+#[compiler_test(imports)]
+fn instantiate_with_host_state_factory(config: crate::Config) -> Result<()> {
+    use std::any::Any;
+    use wasmer::vm::{InstanceHandle, Memory as _, VMExtern};
+    use wasmer_engine::Artifact;
+
+    /// Returns the base of the exported memory and its first byte.
+    fn memory_state(handle: &InstanceHandle) -> (usize, u8) {
+        match handle.lookup("memory") {
+            Some(VMExtern::Memory(memory)) => unsafe {
+                let definition = memory.from.vmmemory().as_ref();
+                (definition.base as usize, *definition.base)
+            },
+            _ => panic!("expected a memory export"),
+        }
+    }
+
+    let store = config.store();
+    let wat = r#"(module
+        (memory (export "memory") 1)
+        (data (i32.const 0) "\2a"))"#;
+    let module = Module::new(&store, wat)?;
+    let handle = unsafe {
+        let handle = module.artifact().instantiate_with_host_state(
+            store.tunables(),
+            &imports! {},
+            Box::new(|handle: &InstanceHandle| -> Box<dyn Any> { Box::new(memory_state(handle)) }),
+        )?;
+        module.artifact().finish_instantiation(&store, &handle)?;
+        handle
+    };
+
+    // The state was built before the data segments were written.
+    let (base, byte) = *handle
+        .host_state()
+        .downcast_ref::<(usize, u8)>()
+        .expect("the state built by the factory");
+    assert_eq!(byte, 0);
+    assert_eq!(memory_state(&handle), (base, 42));
+    Ok(())
+}
+
+#[compiler_test(imports)]
+fn host_function_reenters_wasm(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    let wat = r#"(module
+        (import "host" "call_callback" (func $call_callback (param i32) (result i32)))
+        (func (export "callback") (param i32) (result i32)
+            (if (i32.eqz (local.get 0))
+                (then unreachable))
+            (i32.add (local.get 0) (i32.const 1)))
+        (func (export "run") (param i32) (result i32)
+            (call $call_callback (local.get 0))))"#;
+
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        #[wasmer(export)]
+        callback: LazyInit<NativeFunc<i32, i32>>,
+        calls: Arc<AtomicUsize>,
+    }
+
+    fn call_callback(env: &Env, value: i32) -> Result<i32, RuntimeError> {
+        env.calls.fetch_add(1, SeqCst);
+        let callback = env.callback_ref().unwrap();
+        // Re-enter wasm twice from within the same host call.
+        let once = callback.call(value)?;
+        callback.call(once)
+    }
+
+    let module = Module::new(&store, wat)?;
+    let env = Env {
+        callback: LazyInit::new(),
+        calls: Arc::new(AtomicUsize::new(0)),
+    };
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "call_callback" => Function::new_native_with_env(&store, env.clone(), call_callback),
+            },
+        },
+    )?;
+    let run: NativeFunc<i32, i32> = instance.exports.get_native_function("run")?;
+
+    // host -> wasm (`run`) -> host (`call_callback`) -> wasm (`callback`)
+    assert_eq!(run.call(40)?, 42);
+    assert_eq!(env.calls.load(SeqCst), 1);
+
+    // A trap in the re-entered wasm goes out through the host function.
+    let err = run.call(0).unwrap_err();
+    assert_eq!(err.trap_code(), Some(TrapCode::UnreachableCodeReached));
+    assert_eq!(env.calls.load(SeqCst), 2);
+
+    // The instance is still usable after the trap.
+    assert_eq!(run.call(1)?, 3);
+    Ok(())
+}
+
+#[compiler_test(imports)]
+fn artifact_imported_functions(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    let store = config.store();
+    let wat = r#"
+        (module
+            (import "env" "log" (func (param i32 i32)))
+            (import "env" "memory" (memory 1))
+            (import "host" "now" (func (result i64)))
+            (func (export "run") (call 0 (i32.const 0) (i32.const 0)))
+        )
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    assert_eq!(
+        module.artifact().imported_functions(),
+        vec![
+            (
+                "env".to_string(),
+                "log".to_string(),
+                FunctionType::new(vec![Type::I32, Type::I32], vec![]),
+            ),
+            (
+                "host".to_string(),
+                "now".to_string(),
+                FunctionType::new(vec![], vec![Type::I64]),
+            ),
+        ]
+    );
+    Ok(())
+}
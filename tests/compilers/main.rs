@@ -5,13 +5,26 @@
 #[macro_use]
 extern crate compiler_test_derive;
 
+#[cfg(feature = "universal")]
+mod analyze;
+#[cfg(feature = "universal")]
+mod compilation_limits;
+#[cfg(feature = "universal")]
+mod compile_progress;
 mod config;
+mod deterministic;
 mod imports;
 mod metering;
 mod middlewares;
+mod multi_memory;
 // mod multi_value_imports;
+mod nan_canonicalization;
 mod native_functions;
+#[cfg(all(feature = "universal", target_arch = "x86_64"))]
+mod relocations;
 mod serialize;
+#[cfg(feature = "universal")]
+mod shared_signatures;
 mod traps;
 mod wasi;
 mod wast;
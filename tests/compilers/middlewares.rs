@@ -88,6 +88,37 @@ impl FunctionMiddleware for Fusion {
     }
 }
 
+#[derive(Debug, MemoryUsage)]
+struct RejectPopcntGen;
+
+#[derive(Debug)]
+struct RejectPopcnt;
+
+impl ModuleMiddleware for RejectPopcntGen {
+    fn generate_function_middleware(&self, _: LocalFunctionIndex) -> Box<dyn FunctionMiddleware> {
+        Box::new(RejectPopcnt)
+    }
+}
+
+impl FunctionMiddleware for RejectPopcnt {
+    fn feed<'a>(
+        &mut self,
+        operator: Operator<'a>,
+        state: &mut MiddlewareReaderState<'a>,
+    ) -> Result<(), MiddlewareError> {
+        match operator {
+            Operator::I32Popcnt => Err(MiddlewareError::new(
+                "RejectPopcnt",
+                "i32.popcnt is not supported",
+            )),
+            operator => {
+                state.push_operator(operator);
+                Ok(())
+            }
+        }
+    }
+}
+
 #[compiler_test(middlewares)]
 fn middleware_basic(mut config: crate::Config) -> Result<()> {
     config.set_middlewares(vec![
@@ -205,3 +236,26 @@ fn middleware_chain_order_2(mut config: crate::Config) -> Result<()> {
     assert_eq!(result, 48);
     Ok(())
 }
+
+#[compiler_test(middlewares)]
+fn middleware_error_names_failing_function(mut config: crate::Config) -> Result<()> {
+    config.set_middlewares(vec![Arc::new(RejectPopcntGen) as Arc<dyn ModuleMiddleware>]);
+    let store = config.store();
+    let wat = r#"(module
+        (func $supported (export "supported") (result i32)
+           (i32.const 1))
+        (func $unsupported (export "unsupported") (param i32) (result i32)
+           (i32.popcnt (local.get 0)))
+)"#;
+    let error = Module::new(&store, wat).unwrap_err();
+
+    assert!(error.to_string().contains("function 1 (unsupported)"));
+    match error {
+        CompileError::Function { index, name, .. } => {
+            assert_eq!(index, LocalFunctionIndex::from_u32(1));
+            assert_eq!(name.as_deref(), Some("unsupported"));
+        }
+        error => panic!("Unexpected error: {:?}", error),
+    }
+    Ok(())
+}
@@ -0,0 +1,97 @@
+//! Testing modules that define more than one memory.
+
+use anyhow::Result;
+use wasmer::*;
+
+fn get_module(store: &Store) -> Result<Module> {
+    let wat = r#"
+        (module
+          (memory $first 1)
+          (memory $second 1)
+          (export "first" (memory $first))
+          (export "second" (memory $second))
+          (data (memory $first) (i32.const 0) "\01\02")
+          (data (memory $second) (i32.const 0) "\2a\2b")
+
+          (func (export "load_second") (param i32) (result i32)
+            (i32.load8_u $second (local.get 0)))
+          (func (export "store_second") (param i32 i32)
+            (i32.store8 $second (local.get 0) (local.get 1))))
+    "#;
+    Ok(Module::new(store, wat)?)
+}
+
+fn multi_memory_config(mut config: crate::Config) -> crate::Config {
+    let mut features = Features::default();
+    features.multi_memory(true);
+    config.set_features(features);
+    config
+}
+
+#[compiler_test(multi_memory)]
+fn data_segments_target_their_memory(config: crate::Config) -> Result<()> {
+    let store = multi_memory_config(config).store();
+    let wat = r#"
+        (module
+          (memory $first 1)
+          (memory $second 1)
+          (export "first" (memory $first))
+          (export "second" (memory $second))
+          (data (memory $first) (i32.const 0) "\01\02")
+          (data (memory $second) (i32.const 0) "\2a\2b"))
+    "#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let first = instance.exports.get_memory("first")?;
+    let second = instance.exports.get_memory("second")?;
+    assert!(!first.same(second));
+    assert_eq!(first.snapshot(0..3)?, vec![1, 2, 0]);
+    assert_eq!(second.snapshot(0..3)?, vec![0x2a, 0x2b, 0]);
+
+    // Writes through one export are not visible through the other.
+    first.view::<u8>()[2].set(3);
+    second.view::<u8>()[2].set(0x2c);
+    assert_eq!(first.snapshot(0..3)?, vec![1, 2, 3]);
+    assert_eq!(second.snapshot(0..3)?, vec![0x2a, 0x2b, 0x2c]);
+
+    // Growing one memory leaves the other untouched.
+    first.grow(1)?;
+    assert_eq!(first.size(), Pages(2));
+    assert_eq!(second.size(), Pages(1));
+
+    Ok(())
+}
+
+#[compiler_test(multi_memory)]
+fn wasm_accesses_second_memory(config: crate::Config) -> Result<()> {
+    let is_singlepass = config.compiler == crate::Compiler::Singlepass;
+    let store = multi_memory_config(config).store();
+    if is_singlepass {
+        // Singlepass only addresses the first memory from generated code.
+        assert!(get_module(&store).is_err());
+        return Ok(());
+    }
+    let module = get_module(&store)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let first = instance.exports.get_memory("first")?;
+    let second = instance.exports.get_memory("second")?;
+    let load_second = instance
+        .exports
+        .get_native_function::<i32, i32>("load_second")?;
+    let store_second = instance
+        .exports
+        .get_native_function::<(i32, i32), ()>("store_second")?;
+
+    assert_eq!(load_second.call(0)?, 0x2a);
+
+    second.view::<u8>()[4].set(7);
+    assert_eq!(load_second.call(4)?, 7);
+
+    store_second.call(8, 9)?;
+    assert_eq!(second.view::<u8>()[8].get(), 9);
+    assert_eq!(first.view::<u8>()[8].get(), 0);
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! Testing that engines can be asked to canonicalize the NaNs produced
+//! by floating-point operations, for deterministic results across
+//! architectures.
+
+use anyhow::Result;
+use wasmer::*;
+
+const CANONICAL_F32_NAN: u32 = 0x7fc0_0000;
+const CANONICAL_F64_NAN: u64 = 0x7ff8_0000_0000_0000;
+
+#[compiler_test(nan_canonicalization)]
+fn canonicalize_nans_on_the_engine(config: crate::Config) -> Result<()> {
+    let compiler_config = config.compiler_config(false);
+    let store = match config.engine {
+        #[cfg(feature = "universal")]
+        crate::Engine::Universal => Store::new(
+            &wasmer_engine_universal::Universal::new(compiler_config)
+                .canonicalize_nans(true)
+                .engine(),
+        ),
+        #[cfg(feature = "dylib")]
+        crate::Engine::Dylib => Store::new(
+            &wasmer_engine_dylib::Dylib::new(compiler_config)
+                .canonicalize_nans(true)
+                .engine(),
+        ),
+        #[allow(unreachable_patterns)]
+        engine => panic!("The {:?} Engine is not enabled", engine),
+    };
+    let wat = r#"
+        (module
+            (func (export "div_f32") (param f32 f32) (result i32)
+                (i32.reinterpret_f32 (f32.div (local.get 0) (local.get 1))))
+            (func (export "add_f32") (param f32 f32) (result i32)
+                (i32.reinterpret_f32 (f32.add (local.get 0) (local.get 1))))
+            (func (export "div_f64") (param f64 f64) (result i64)
+                (i64.reinterpret_f64 (f64.div (local.get 0) (local.get 1))))
+            (func (export "add_f64") (param f64 f64) (result i64)
+                (i64.reinterpret_f64 (f64.add (local.get 0) (local.get 1))))
+        )
+    "#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let div_f32: NativeFunc<(f32, f32), u32> = instance.exports.get_native_function("div_f32")?;
+    let add_f32: NativeFunc<(f32, f32), u32> = instance.exports.get_native_function("add_f32")?;
+    let div_f64: NativeFunc<(f64, f64), u64> = instance.exports.get_native_function("div_f64")?;
+    let add_f64: NativeFunc<(f64, f64), u64> = instance.exports.get_native_function("add_f64")?;
+
+    // 0/0 is a NaN whose sign depends on the architecture, and adding to
+    // a NaN with a payload propagates the payload.
+    let f32_payload_nan = f32::from_bits(0xffa0_0001);
+    let f64_payload_nan = f64::from_bits(0xfff4_0000_0000_0001);
+    assert_eq!(div_f32.call(0.0, 0.0)?, CANONICAL_F32_NAN);
+    assert_eq!(add_f32.call(f32_payload_nan, 1.0)?, CANONICAL_F32_NAN);
+    assert_eq!(div_f64.call(0.0, 0.0)?, CANONICAL_F64_NAN);
+    assert_eq!(add_f64.call(f64_payload_nan, 1.0)?, CANONICAL_F64_NAN);
+
+    // Other results are left untouched.
+    assert_eq!(add_f32.call(1.0, 2.0)?, 3.0f32.to_bits());
+    Ok(())
+}
@@ -0,0 +1,101 @@
+//! Testing the hooks that rewrite the relocations and the libcalls of
+//! the Universal engine. The tests rely on x86_64 libcalls, see below.
+
+use anyhow::Result;
+use std::cell::Cell;
+use wasmer::*;
+use wasmer_compiler::{LibCall, RelocationTarget};
+use wasmer_engine_universal::{RelocationRewriter, Universal};
+
+thread_local! {
+    static FLOOR_CALLS: Cell<u32> = Cell::new(0);
+    static CEIL_CALLS: Cell<u32> = Cell::new(0);
+}
+
+extern "C" fn counting_floor(x: f32) -> f32 {
+    FLOOR_CALLS.with(|calls| calls.set(calls.get() + 1));
+    x.floor()
+}
+
+extern "C" fn counting_ceil(x: f64) -> f64 {
+    CEIL_CALLS.with(|calls| calls.set(calls.get() + 1));
+    x.ceil()
+}
+
+struct CountFloorCalls;
+
+unsafe impl RelocationRewriter for CountFloorCalls {
+    fn rewrite(&self, target: RelocationTarget, address: usize) -> usize {
+        match target {
+            RelocationTarget::LibCall(LibCall::FloorF32) => counting_floor as usize,
+            _ => address,
+        }
+    }
+}
+
+#[compiler_test(relocations)]
+fn rewrite_libcall_relocation(config: crate::Config) -> Result<()> {
+    // Singlepass rounds floats inline, without any libcall.
+    if config.engine != crate::Engine::Universal || config.compiler == crate::Compiler::Singlepass {
+        return Ok(());
+    }
+    // Without SSE4.1 there is no instruction to round floats, so
+    // `f32.floor` is compiled to a libcall.
+    let target = Target::new(Triple::host(), CpuFeature::SSE2 | CpuFeature::SSE3);
+    let engine = Universal::new(config.compiler_config(false))
+        .target(target)
+        .relocation_rewriter(CountFloorCalls)
+        .engine();
+    let store = Store::new(&engine);
+    let wat = r#"
+        (module
+            (func (export "floor") (param f32) (result f32)
+                (f32.floor (local.get 0)))
+        )
+    "#;
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let floor: NativeFunc<f32, f32> = instance.exports.get_native_function("floor")?;
+
+    assert_eq!(floor.call(1.5)?, 1.0);
+    assert_eq!(floor.call(-1.5)?, -2.0);
+    assert_eq!(FLOOR_CALLS.with(Cell::get), 2);
+    Ok(())
+}
+
+#[compiler_test(relocations)]
+fn override_libcall(config: crate::Config) -> Result<()> {
+    // Singlepass rounds floats inline, without any libcall.
+    if config.engine != crate::Engine::Universal || config.compiler == crate::Compiler::Singlepass {
+        return Ok(());
+    }
+    // Without SSE4.1, `f64.ceil` is compiled to a libcall, see above.
+    let target = Target::new(Triple::host(), CpuFeature::SSE2 | CpuFeature::SSE3);
+    let engine = unsafe {
+        Universal::new(config.compiler_config(false))
+            .target(target)
+            .libcall(LibCall::CeilF64, counting_ceil as usize)
+            .engine()
+    };
+    let store = Store::new(&engine);
+    let wat = r#"
+        (module
+            (func (export "ceil") (param f64) (result f64)
+                (f64.ceil (local.get 0)))
+            (func (export "floor") (param f64) (result f64)
+                (f64.floor (local.get 0)))
+        )
+    "#;
+    // Every module linked by the engine calls the override.
+    for _ in 0..2 {
+        let module = Module::new(&store, wat)?;
+        let instance = Instance::new(&module, &imports! {})?;
+        let ceil: NativeFunc<f64, f64> = instance.exports.get_native_function("ceil")?;
+        let floor: NativeFunc<f64, f64> = instance.exports.get_native_function("floor")?;
+
+        assert_eq!(ceil.call(1.5)?, 2.0);
+        assert_eq!(floor.call(1.5)?, 1.0);
+    }
+    assert_eq!(CEIL_CALLS.with(Cell::get), 2);
+    Ok(())
+}
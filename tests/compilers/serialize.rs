@@ -74,3 +74,676 @@ fn test_deserialize(config: crate::Config) -> Result<()> {
     assert_eq!(result.to_vec(), vec![Value::I64(1500)]);
     Ok(())
 }
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_compilation_stats(config: crate::Config) -> Result<()> {
+    use std::time::Instant;
+    use wasmer_engine_universal::UniversalArtifact;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let store = config.store();
+    let wat = r#"
+        (module
+            (func $add (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1)))
+            (func (export "run") (result i32)
+                (call $add (i32.const 1) (i32.const 2)))
+        )
+    "#;
+
+    let start = Instant::now();
+    let module = Module::new(&store, wat)?;
+    let elapsed = start.elapsed();
+    let artifact = module
+        .artifact()
+        .downcast_ref::<UniversalArtifact>()
+        .expect("a universal artifact");
+    let stats = artifact.stats().expect("stats are recorded on compilation");
+    assert_eq!(stats.function_count, 2);
+    assert!(stats.function_code_bytes > 0);
+    assert!(stats.trampoline_bytes > 0);
+    // The compilation happens within `Module::new`.
+    assert!(stats.wall_time <= elapsed, "{:?}", stats.wall_time);
+
+    // Deserialized modules were not compiled, so they have no stats.
+    let serialized_bytes = module.serialize()?;
+    let headless_store = config.headless_store();
+    let deserialized = unsafe { Module::deserialize(&headless_store, &serialized_bytes)? };
+    let artifact = deserialized
+        .artifact()
+        .downcast_ref::<UniversalArtifact>()
+        .expect("a universal artifact");
+    assert!(artifact.stats().is_none());
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_compilation_phase_timings(config: crate::Config) -> Result<()> {
+    use std::time::{Duration, Instant};
+    use wasmer_engine_universal::UniversalArtifact;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let store = config.store();
+    let wat = r#"
+        (module
+            (func $fac (param i64) (result i64)
+                (if (result i64) (i64.eqz (local.get 0))
+                    (then (i64.const 1))
+                    (else (i64.mul (local.get 0)
+                        (call $fac (i64.sub (local.get 0) (i64.const 1)))))))
+            (func (export "run") (result i64)
+                (call $fac (i64.const 5)))
+        )
+    "#;
+
+    let start = Instant::now();
+    let module = Module::new(&store, wat)?;
+    let elapsed = start.elapsed();
+    let artifact = module
+        .artifact()
+        .downcast_ref::<UniversalArtifact>()
+        .expect("a universal artifact");
+    let stats = artifact.stats().expect("stats are recorded on compilation");
+    let phases = artifact.phase_timings().expect("the phases are timed");
+    assert_eq!(phases, &stats.phases);
+
+    // The phases run one after the other within `Module::new`, which
+    // also parses the text format and validates the module.
+    let sum = phases.translate + phases.codegen + phases.trampolines + phases.link + phases.publish;
+    assert!(sum <= elapsed, "{:?} took {:?}", phases, elapsed);
+    assert!(stats.wall_time <= elapsed, "{:?}", stats.wall_time);
+    assert!(phases.codegen > Duration::from_secs(0));
+
+    let serialized_bytes = module.serialize()?;
+    let headless_store = config.headless_store();
+    let deserialized = unsafe { Module::deserialize(&headless_store, &serialized_bytes)? };
+    let artifact = deserialized
+        .artifact()
+        .downcast_ref::<UniversalArtifact>()
+        .expect("a universal artifact");
+    assert!(artifact.phase_timings().is_none());
+    Ok(())
+}
+
+#[compiler_test(serialize)]
+fn test_function_call_trampolines_are_shared(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    // LLVM emits the objects of the Dylib engine itself, with one
+    // trampoline per signature.
+    if config.engine == crate::Engine::Dylib && config.compiler == crate::Compiler::LLVM {
+        return Ok(());
+    }
+    let store = config.store();
+    // Every function uses its own type entry, but there are only two
+    // distinct signatures.
+    let wat = r#"
+        (module
+            (type $t0 (func (param i32 i32) (result i32)))
+            (type $t1 (func (param i32 i32) (result i32)))
+            (type $t2 (func (param i32 i32) (result i32)))
+            (type $t3 (func (param i32 i32) (result i32)))
+            (type $t4 (func (result i64)))
+            (type $t5 (func (result i64)))
+            (func (export "add") (type $t0) (i32.add (local.get 0) (local.get 1)))
+            (func (export "sub") (type $t1) (i32.sub (local.get 0) (local.get 1)))
+            (func (export "mul") (type $t2) (i32.mul (local.get 0) (local.get 1)))
+            (func (export "and") (type $t3) (i32.and (local.get 0) (local.get 1)))
+            (func (export "one") (type $t4) (i64.const 1))
+            (func (export "two") (type $t5) (i64.const 2))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let trampolines = module.artifact().finished_function_call_trampolines();
+    assert_eq!(trampolines.len(), 6);
+    let distinct = trampolines
+        .values()
+        .map(|trampoline| *trampoline as usize)
+        .collect::<std::collections::HashSet<_>>();
+    assert_eq!(distinct.len(), 2);
+
+    // Calls through the shared trampolines still work, also after a
+    // serialization round trip.
+    let serialized_bytes = module.serialize()?;
+    let headless_store = config.headless_store();
+    let deserialized = unsafe { Module::deserialize(&headless_store, &serialized_bytes)? };
+    for module in &[module, deserialized] {
+        let instance = Instance::new(module, &imports! {})?;
+        let exports = &instance.exports;
+        let args = [Val::I32(6), Val::I32(3)];
+        assert_eq!(&*exports.get_function("add")?.call(&args)?, &[Val::I32(9)]);
+        assert_eq!(&*exports.get_function("sub")?.call(&args)?, &[Val::I32(3)]);
+        assert_eq!(&*exports.get_function("mul")?.call(&args)?, &[Val::I32(18)]);
+        assert_eq!(&*exports.get_function("and")?.call(&args)?, &[Val::I32(2)]);
+        assert_eq!(&*exports.get_function("one")?.call(&[])?, &[Val::I64(1)]);
+        assert_eq!(&*exports.get_function("two")?.call(&[])?, &[Val::I64(2)]);
+    }
+    Ok(())
+}
+
+#[compiler_test(serialize)]
+fn test_function_names_survive_serialization(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    let wat = r#"
+        (module
+            (func $faulty
+                unreachable)
+            (func $entry (export "entry")
+                call $faulty)
+        )
+    "#;
+    let module = Module::new(&store, wat)?;
+    let serialized_bytes = module.serialize()?;
+
+    let headless_store = config.headless_store();
+    let module = unsafe { Module::deserialize(&headless_store, &serialized_bytes)? };
+    assert_eq!(
+        module.function_name(FunctionIndex::from_u32(0)),
+        Some("faulty")
+    );
+    assert_eq!(
+        module.function_name(FunctionIndex::from_u32(1)),
+        Some("entry")
+    );
+    assert_eq!(module.function_name(FunctionIndex::from_u32(2)), None);
+
+    let instance = Instance::new(&module, &imports! {})?;
+    let entry = instance.exports.get_function("entry")?;
+    let error = entry.call(&[]).unwrap_err();
+    let names = error
+        .trace()
+        .iter()
+        .map(|frame| frame.function_name())
+        .collect::<Vec<_>>();
+    assert_eq!(names, vec![Some("faulty"), Some("entry")]);
+    assert!(error.to_string().contains("at faulty ("));
+
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_cache_dir(config: crate::Config) -> Result<()> {
+    use wasmer_engine_universal::{Universal, UniversalArtifact};
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let wat = r#"
+        (module
+            (func (export "answer") (result i32)
+                (i32.const 42))
+        )
+    "#;
+    let cache_dir = tempfile::tempdir()?;
+    let compile = || -> Result<(Module, bool)> {
+        let engine = Universal::new(config.compiler_config(false))
+            .cache_dir(cache_dir.path())
+            .engine();
+        let store = Store::new(&engine);
+        let module = Module::new(&store, wat)?;
+        let compiled = module
+            .artifact()
+            .downcast_ref::<UniversalArtifact>()
+            .expect("a universal artifact")
+            .stats()
+            .is_some();
+        Ok((module, compiled))
+    };
+
+    let (_, compiled) = compile()?;
+    assert!(compiled);
+    let entries = std::fs::read_dir(cache_dir.path())?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<Vec<_>>>()?;
+    assert_eq!(entries.len(), 1);
+
+    // The second compilation is served from the cache.
+    let (module, compiled) = compile()?;
+    assert!(!compiled);
+    let instance = Instance::new(&module, &imports! {})?;
+    let answer = instance.exports.get_native_function::<(), i32>("answer")?;
+    assert_eq!(answer.call()?, 42);
+
+    // A corrupt entry is replaced by a fresh compilation.
+    std::fs::write(&entries[0], b"not an artifact")?;
+    let (_, compiled) = compile()?;
+    assert!(compiled);
+    let (_, compiled) = compile()?;
+    assert!(!compiled);
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_cache_dir_separates_settings(config: crate::Config) -> Result<()> {
+    use wasmer_engine_universal::{Universal, UniversalArtifact};
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let wat = r#"
+        (module
+            (memory (export "memory") 1)
+            (func (export "answer") (result i32)
+                (i32.const 42))
+        )
+    "#;
+    let cache_dir = tempfile::tempdir()?;
+    let compile = |debug_info: bool, static_memory_bound: Option<Pages>| -> Result<bool> {
+        let engine = Universal::new(config.compiler_config(false))
+            .debug_info(debug_info)
+            .cache_dir(cache_dir.path())
+            .engine();
+        let mut tunables = BaseTunables::for_target(engine.target());
+        if let Some(bound) = static_memory_bound {
+            tunables.static_memory_bound = bound;
+        }
+        let store = Store::new_with_tunables(&engine, tunables);
+        let module = Module::new(&store, wat)?;
+        let instance = Instance::new(&module, &imports! {})?;
+        let answer = instance.exports.get_native_function::<(), i32>("answer")?;
+        assert_eq!(answer.call()?, 42);
+        Ok(module
+            .artifact()
+            .downcast_ref::<UniversalArtifact>()
+            .expect("a universal artifact")
+            .stats()
+            .is_some())
+    };
+
+    assert!(compile(true, None)?);
+    assert!(!compile(true, None)?);
+
+    // The engine settings are part of the key.
+    assert!(compile(false, None)?);
+    assert!(!compile(false, None)?);
+    assert!(!compile(true, None)?);
+
+    // Code compiled for other memory styles isn't reused. A bound of one
+    // page makes the memory dynamic, unlike the default tunables of
+    // 64-bit hosts.
+    #[cfg(target_pointer_width = "64")]
+    {
+        assert!(compile(true, Some(Pages(1)))?);
+        assert!(!compile(true, Some(Pages(1)))?);
+        assert!(compile(true, None)?);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_deserialize_skips_unknown_extensions(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let store = config.store();
+    let wat = r#"
+        (module
+            (func (export "answer") (result i32)
+                (i32.const 42))
+        )
+    "#;
+    let mut serialized = Module::new(&store, wat)?.serialize()?;
+
+    // Replace the empty extensions region ending the artifact with one
+    // holding an extension of tag 0x7f and payload "abc", as a future
+    // version could write.
+    assert_eq!(serialized.pop(), Some(0));
+    serialized.extend_from_slice(&[5, 0x7f, 3, b'a', b'b', b'c']);
+
+    let headless_store = config.headless_store();
+    let module = unsafe { Module::deserialize(&headless_store, &serialized)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let answer = instance.exports.get_native_function::<(), i32>("answer")?;
+    assert_eq!(answer.call()?, 42);
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_serialize_without_debug_info(config: crate::Config) -> Result<()> {
+    use wasmer_engine_universal::Universal;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let wat = r#"
+        (module $hello_mod
+            (func $sum (export "sum") (param i32 i32) (result i32)
+                (local.get 0)
+                (local.get 1)
+                (i32.add))
+            (func $fail (export "fail")
+                (unreachable))
+            (func (export "run") (call $fail))
+        )
+    "#;
+    let serialize = |debug_info: bool| -> Result<Vec<u8>> {
+        let engine = Universal::new(config.compiler_config(false))
+            .debug_info(debug_info)
+            .engine();
+        let store = Store::new(&engine);
+        Ok(Module::new(&store, wat)?.serialize()?)
+    };
+    let with_debug_info = serialize(true)?;
+    let without_debug_info = serialize(false)?;
+    // LLVM doesn't emit per-instruction address maps to begin with.
+    if config.compiler != crate::Compiler::LLVM {
+        assert!(without_debug_info.len() < with_debug_info.len());
+    }
+
+    let headless_store = config.headless_store();
+    let module = unsafe { Module::deserialize(&headless_store, &without_debug_info)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let sum = instance
+        .exports
+        .get_native_function::<(i32, i32), i32>("sum")?;
+    assert_eq!(sum.call(1, 2)?, 3);
+
+    // Traps keep their trap code, but aren't symbolicated.
+    let e = instance.exports.get_function("run")?.call(&[]).unwrap_err();
+    assert_eq!(e.trap_code(), Some(TrapCode::UnreachableCodeReached));
+    assert!(e.trace().is_empty());
+
+    // The artifact compiled with debug info still symbolicates them.
+    let module = unsafe { Module::deserialize(&headless_store, &with_debug_info)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let e = instance.exports.get_function("run")?.call(&[]).unwrap_err();
+    assert_eq!(e.trace().len(), 2);
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_serialize_with_source_hash(config: crate::Config) -> Result<()> {
+    use wasmer_engine_universal::Universal;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let wasm = wat2wasm(br#"(module (func (export "answer") (result i32) (i32.const 42)))"#)?;
+    let engine = Universal::new(config.compiler_config(false))
+        .source_hash(true)
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(&store, &wasm)?;
+    assert!(module.verify_source(&wasm));
+    let hash = module.source_hash().unwrap();
+    let serialized = module.serialize()?;
+
+    let headless_store = config.headless_store();
+    let module = unsafe { Module::deserialize(&headless_store, &serialized)? };
+    assert!(module.verify_source(&wasm));
+    assert_eq!(module.source_hash(), Some(hash));
+    let mut tampered = wasm.to_vec();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(!module.verify_source(&tampered));
+
+    // Without the option, there is nothing to verify against.
+    let module = Module::new(&config.store(), &wasm)?;
+    assert!(!module.verify_source(&wasm));
+    assert_eq!(module.source_hash(), None);
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_serialize_with_checksum(config: crate::Config) -> Result<()> {
+    use wasmer_engine_universal::Universal;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let engine = Universal::new(config.compiler_config(false))
+        .artifact_checksum(true)
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(
+        &store,
+        r#"(module (func (export "answer") (result i32) (i32.const 42)))"#,
+    )?;
+    let serialized = module.serialize()?;
+
+    // The checksum is kept through a round trip.
+    let headless_store = config.headless_store();
+    let module = unsafe { Module::deserialize(&headless_store, &serialized)? };
+    assert_eq!(module.serialize()?, serialized);
+
+    let mut corrupted = serialized.clone();
+    let middle = corrupted.len() / 2;
+    corrupted[middle] ^= 0x40;
+    match unsafe { Module::deserialize(&headless_store, &corrupted) } {
+        Err(DeserializeError::CorruptedBinary(message)) => {
+            assert!(message.contains("checksum"), "{}", message)
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("the corrupted artifact shouldn't deserialize"),
+    }
+
+    // Corrupting the tag of the checksum, the last extension, doesn't
+    // disable the verification: a checksum is either verified or
+    // required by the engine.
+    let mut untagged = serialized.clone();
+    let tag_offset = untagged.len() - 32 - 2;
+    assert_eq!(untagged[tag_offset], 5);
+    untagged[tag_offset] = 0x7f;
+    let checksum_store = Store::new(&Universal::headless().artifact_checksum(true).engine());
+    match unsafe { Module::deserialize(&checksum_store, &untagged) } {
+        Err(DeserializeError::CorruptedBinary(message)) => {
+            assert!(message.contains("checksum"), "{}", message)
+        }
+        Err(e) => panic!("unexpected error: {}", e),
+        Ok(_) => panic!("the artifact without checksum shouldn't deserialize"),
+    }
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_function_traps(config: crate::Config) -> Result<()> {
+    use wasmer_engine_universal::UniversalArtifact;
+    use wasmer_types::entity::EntityRef;
+    use wasmer_types::LocalFunctionIndex;
+
+    // LLVM raises its traps by calling into the runtime, without trap
+    // sites.
+    if config.engine != crate::Engine::Universal || config.compiler == crate::Compiler::LLVM {
+        return Ok(());
+    }
+    let store = config.store();
+    let wat = r#"
+        (module
+            (func (export "div") (param i32 i32) (result i32)
+                (i32.div_u (local.get 0) (local.get 1)))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let serialized_bytes = module.serialize()?;
+    let headless_store = config.headless_store();
+    let deserialized = unsafe { Module::deserialize(&headless_store, &serialized_bytes)? };
+    for module in &[module, deserialized] {
+        let artifact = module
+            .artifact()
+            .downcast_ref::<UniversalArtifact>()
+            .expect("a universal artifact");
+        let traps = artifact
+            .function_traps(LocalFunctionIndex::new(0))
+            .expect("the function exists");
+        assert!(traps
+            .iter()
+            .any(|trap| trap.trap_code == TrapCode::IntegerDivisionByZero));
+        let body_len = artifact
+            .function_body_bytes(LocalFunctionIndex::new(0))
+            .unwrap()
+            .len();
+        assert!(traps
+            .iter()
+            .all(|trap| (trap.code_offset as usize) < body_len));
+        assert!(artifact
+            .function_traps(LocalFunctionIndex::new(1))
+            .is_none());
+    }
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_jump_table_offsets(config: crate::Config) -> Result<()> {
+    use wasmer_engine_universal::UniversalArtifact;
+    use wasmer_types::entity::EntityRef;
+    use wasmer_types::LocalFunctionIndex;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let store = config.store();
+    let wat = r#"
+        (module
+            (func (export "dispatch") (param i32) (result i32)
+                (block (block (block (block (block (block (block (block
+                    (br_table 0 1 2 3 4 5 6 7 (local.get 0)))
+                    (return (i32.const 10)))
+                    (return (i32.const 11)))
+                    (return (i32.const 12)))
+                    (return (i32.const 13)))
+                    (return (i32.const 14)))
+                    (return (i32.const 15)))
+                    (return (i32.const 16)))
+                (i32.const 17))
+            (func (export "add") (param i32 i32) (result i32)
+                (i32.add (local.get 0) (local.get 1)))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let serialized_bytes = module.serialize()?;
+    let headless_store = config.headless_store();
+    let deserialized = unsafe { Module::deserialize(&headless_store, &serialized_bytes)? };
+    let offsets_of = |module: &Module| {
+        let artifact = module
+            .artifact()
+            .downcast_ref::<UniversalArtifact>()
+            .expect("a universal artifact");
+        let dispatch = LocalFunctionIndex::new(0);
+        let offsets = artifact
+            .jump_table_offsets(dispatch)
+            .expect("the function exists")
+            .values()
+            .copied()
+            .collect::<Vec<_>>();
+        // The compilers of this crate emit their jump tables inline, so
+        // there may be none, but any table lies in the function.
+        let body_len = artifact.function_body_bytes(dispatch).unwrap().len();
+        assert!(offsets.iter().all(|offset| (*offset as usize) < body_len));
+        // A function without `br_table` has no jump table.
+        assert!(artifact
+            .jump_table_offsets(LocalFunctionIndex::new(1))
+            .expect("the function exists")
+            .is_empty());
+        assert!(artifact
+            .jump_table_offsets(LocalFunctionIndex::new(2))
+            .is_none());
+        offsets
+    };
+    assert_eq!(offsets_of(&module), offsets_of(&deserialized));
+
+    let instance = Instance::new(&deserialized, &imports! {})?;
+    let dispatch = instance.exports.get_function("dispatch")?;
+    for (index, expected) in [(0, 10), (3, 13), (7, 17), (100, 17)] {
+        assert_eq!(
+            dispatch.call(&[Val::I32(index)])?.to_vec(),
+            vec![Val::I32(expected)]
+        );
+    }
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_exported_trampolines_only(config: crate::Config) -> Result<()> {
+    use wasmer_engine_universal::Universal;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let wasm = wat2wasm(
+        br#"
+        (module
+          (func $a (param i64) (result i64) (local.get 0))
+          (func $b (param f32 f64) (result f64) (local.get 1))
+          (func $c (param i32 i32 i32) (result i32) (local.get 2))
+          (func $d (param f64 i64 i32 f32))
+          (func (export "sum") (param i32 i32) (result i32)
+            (drop (call $a (i64.const 1)))
+            (drop (call $b (f32.const 1) (f64.const 2)))
+            (call $d (f64.const 1) (i64.const 2) (i32.const 3) (f32.const 4))
+            (i32.add (call $c (i32.const 0) (i32.const 0) (local.get 0)) (local.get 1))))
+        "#,
+    )?;
+    let full_store = Store::new(&Universal::new(config.compiler_config(false)).engine());
+    let full = Module::new(&full_store, &wasm)?.serialize()?;
+    let engine = Universal::new(config.compiler_config(false))
+        .exported_trampolines_only(true)
+        .engine();
+    let store = Store::new(&engine);
+    let module = Module::new(&store, &wasm)?;
+    let serialized = module.serialize()?;
+    assert!(serialized.len() < full.len());
+
+    // The exported function can still be called, in both ways, from the
+    // compiled and the deserialized artifacts.
+    let headless_store = config.headless_store();
+    let deserialized = unsafe { Module::deserialize(&headless_store, &serialized)? };
+    for module in [module, deserialized] {
+        let instance = Instance::new(&module, &imports! {})?;
+        let sum = instance.exports.get_function("sum")?;
+        assert_eq!(
+            sum.call(&[Val::I32(2), Val::I32(3)])?.to_vec(),
+            vec![Val::I32(5)]
+        );
+        let sum = sum.native::<(i32, i32), i32>()?;
+        assert_eq!(sum.call(4, 5)?, 9);
+    }
+    Ok(())
+}
+
+#[cfg(feature = "universal")]
+#[compiler_test(serialize)]
+fn test_headless_engine_only_deserializes(config: crate::Config) -> Result<()> {
+    use wasmer_engine_universal::Universal;
+
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let wat = r#"(module (func (export "answer") (result i32) (i32.const 42)))"#;
+    let compiler = Universal::new(config.compiler_config(false));
+    assert!(!compiler.is_headless());
+    let serialized = Module::new(&Store::new(&compiler.engine()), wat)?.serialize()?;
+
+    let headless = Universal::headless();
+    assert!(headless.is_headless());
+    let engine = headless.engine();
+    assert!(engine.is_headless());
+    let store = Store::new(&engine);
+    match Module::new(&store, wat) {
+        Err(CompileError::Codegen(message)) => assert!(message.contains("headless"), "{}", message),
+        Err(error) => panic!("unexpected error: {}", error),
+        Ok(_) => panic!("a headless engine compiled a module"),
+    }
+
+    let module = unsafe { Module::deserialize(&store, &serialized)? };
+    let instance = Instance::new(&module, &imports! {})?;
+    let answer = instance.exports.get_native_function::<(), i32>("answer")?;
+    assert_eq!(answer.call()?, 42);
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use anyhow::Result;
+
+use wasmer::*;
+use wasmer_engine_universal::Universal;
+
+/// Exports a table holding a function of type `$t`.
+const TABLE_MODULE: &[u8] = br#"(module
+    (type $t (func (result i32)))
+    (table (export "table") 1 funcref)
+    (func $f (type $t) (i32.const 42))
+    (elem (i32.const 0) $f))"#;
+
+/// Calls the function of the imported table, registering another
+/// signature before `$t` so that the indices differ between engines
+/// that don't share a registry.
+const CALLER_MODULE: &[u8] = br#"(module
+    (type $other (func (param f64)))
+    (type $t (func (result i32)))
+    (import "a" "table" (table 1 funcref))
+    (func (param f64))
+    (func (export "call") (result i32)
+        (call_indirect (type $t) (i32.const 0))))"#;
+
+fn call_across(first: &Store, second: &Store) -> Result<Result<i32, RuntimeError>> {
+    let module = Module::new(first, TABLE_MODULE)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let table = instance.exports.get_table("table")?;
+
+    let module = Module::new(second, CALLER_MODULE)?;
+    let imports = imports! {
+        "a" => {
+            "table" => table.clone(),
+        },
+    };
+    let instance = Instance::new(&module, &imports)?;
+    let call: NativeFunc<(), i32> = instance.exports.get_native_function("call")?;
+    Ok(call.call())
+}
+
+#[compiler_test(shared_signatures)]
+fn funcrefs_are_callable_across_engines_sharing_signatures(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let first = Universal::new(config.compiler_config(false)).engine();
+    let second = Universal::new(config.compiler_config(false))
+        .signature_registry(first.signature_registry())
+        .engine();
+    let result = call_across(&Store::new(&first), &Store::new(&second))?;
+    assert_eq!(result?, 42);
+
+    // Identical signatures have the same index in both engines.
+    let ty = FunctionType::new(vec![Type::I64], vec![Type::I32]);
+    assert_eq!(
+        first.register_signature(&ty),
+        second.register_signature(&ty)
+    );
+    Ok(())
+}
+
+#[compiler_test(shared_signatures)]
+fn funcrefs_are_not_callable_across_unrelated_engines(config: crate::Config) -> Result<()> {
+    if config.engine != crate::Engine::Universal {
+        return Ok(());
+    }
+    let first = Universal::new(config.compiler_config(false)).engine();
+    let second = Universal::new(config.compiler_config(false)).engine();
+    let result = call_across(&Store::new(&first), &Store::new(&second))?;
+    let error = result.unwrap_err();
+    assert_eq!(error.to_trap(), Some(TrapCode::BadSignature));
+    Ok(())
+}
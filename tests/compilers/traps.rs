@@ -73,6 +73,112 @@ fn test_trap_trace(config: crate::Config) -> Result<()> {
     Ok(())
 }
 
+/// Encodes `value` as an unsigned LEB128.
+fn uleb128(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Appends a custom section to the Wasm binary `wasm`.
+fn append_custom_section(wasm: &mut Vec<u8>, name: &str, data: &[u8]) {
+    let mut contents = vec![];
+    uleb128(&mut contents, name.len() as u64);
+    contents.extend_from_slice(name.as_bytes());
+    contents.extend_from_slice(data);
+    wasm.push(0);
+    uleb128(wasm, contents.len() as u64);
+    wasm.extend(contents);
+}
+
+/// Appends the DWARF 4 debug information of a compilation unit of
+/// `main.c` to `wasm`, whose line program maps the single byte at
+/// `address`, relative to the code section, to `line`.
+fn append_dwarf(wasm: &mut Vec<u8>, address: u32, line: u8) {
+    // A compilation unit with a name and a line program, no children.
+    let abbrev = [0x01, 0x11, 0x00, 0x03, 0x08, 0x10, 0x17, 0x00, 0x00, 0x00];
+    let mut unit = vec![0x04, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x01];
+    unit.extend_from_slice(b"main.c\0");
+    unit.extend_from_slice(&0u32.to_le_bytes());
+    let mut info = (unit.len() as u32).to_le_bytes().to_vec();
+    info.extend(unit);
+
+    let mut header = vec![0x01, 0x01, 0x01, 0xfb, 0x0e, 0x0d];
+    header.extend_from_slice(&[0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1]);
+    // No include directories, then `main.c` in the compilation directory.
+    header.push(0x00);
+    header.extend_from_slice(b"main.c\0");
+    header.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+    let mut program = vec![0x00, 0x05, 0x02];
+    program.extend_from_slice(&address.to_le_bytes());
+    // Advance the line, emit a row, then end the sequence a byte later.
+    program.extend_from_slice(&[0x03, line - 1, 0x01, 0x02, 0x01, 0x00, 0x01, 0x01]);
+    let mut line_unit = vec![0x04, 0x00];
+    line_unit.extend_from_slice(&(header.len() as u32).to_le_bytes());
+    line_unit.extend(header);
+    line_unit.extend(program);
+    let mut debug_line = (line_unit.len() as u32).to_le_bytes().to_vec();
+    debug_line.extend(line_unit);
+
+    append_custom_section(wasm, ".debug_abbrev", &abbrev);
+    append_custom_section(wasm, ".debug_info", &info);
+    append_custom_section(wasm, ".debug_line", &debug_line);
+}
+
+#[cfg_attr(target_env = "musl", ignore)]
+#[compiler_test(traps)]
+fn test_trap_source_location(mut config: crate::Config) -> Result<()> {
+    let store_without_source_lines = config.store();
+    config.set_source_lines(true);
+    let store = config.store();
+    let wasm = wat2wasm(
+        br#"(module
+            (func (export "run") (call $hello))
+            (func $hello (unreachable)))"#,
+    )?
+    .into_owned();
+    let trace_in = |store: &Store, wasm: &[u8]| -> Result<Vec<FrameInfo>> {
+        let instance = Instance::new(&Module::new(store, wasm)?, &imports! {})?;
+        let run = instance.exports.get_function("run")?;
+        Ok(run.call(&[]).unwrap_err().trace().to_vec())
+    };
+    let trace = |wasm: &[u8]| trace_in(&store, wasm);
+
+    // Without debug information, there is no source to resolve to.
+    let frames = trace(&wasm)?;
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].source_location(), None);
+
+    let code_section_offset = wasmparser::Parser::new(0)
+        .parse_all(&wasm)
+        .find_map(|payload| match payload {
+            Ok(wasmparser::Payload::CodeSectionStart { range, .. }) => Some(range.start),
+            _ => None,
+        })
+        .unwrap();
+    let trap_address = frames[0].module_offset() - code_section_offset;
+    let mut with_dwarf = wasm.clone();
+    append_dwarf(&mut with_dwarf, trap_address as u32, 7);
+
+    // The debug information is only read when the engine asks for it.
+    let frames = trace_in(&store_without_source_lines, &with_dwarf)?;
+    assert_eq!(frames[0].source_location(), None);
+
+    let frames = trace(&with_dwarf)?;
+    assert_eq!(frames[0].func_index(), 1);
+    assert_eq!(frames[0].source_location(), Some(("main.c", 7)));
+    // The caller isn't covered by the line program.
+    assert_eq!(frames[1].func_index(), 0);
+    assert_eq!(frames[1].source_location(), None);
+    Ok(())
+}
+
 #[compiler_test(traps)]
 fn test_trap_trace_cb(config: crate::Config) -> Result<()> {
     let store = config.store();
@@ -147,6 +253,96 @@ fn test_trap_stack_overflow(config: crate::Config) -> Result<()> {
     Ok(())
 }
 
+#[cfg(unix)]
+#[cfg_attr(target_env = "musl", ignore)]
+#[compiler_test(traps)]
+fn test_trap_stack_overflow_on_wasm_stack(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    store.set_wasm_stack_size(Some(512 * 1024));
+    let wat = r#"
+        (module $rec_mod
+            (func $run (export "run") (call $run))
+            (func (export "answer") (result i32) (i32.const 42))
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run_func = instance
+        .exports
+        .get_function("run")
+        .expect("expected function export");
+
+    let e = run_func.call(&[]).err().expect("error calling function");
+    assert_eq!(e.to_trap(), Some(TrapCode::StackOverflow));
+
+    // The stack is reusable after an overflow.
+    let e = run_func.call(&[]).err().expect("error calling function");
+    assert_eq!(e.to_trap(), Some(TrapCode::StackOverflow));
+    let answer: NativeFunc<(), i32> = instance.exports.get_native_function("answer")?;
+    assert_eq!(answer.call()?, 42);
+
+    Ok(())
+}
+
+#[should_panic(expected = "the wasm stack size can't be 0")]
+#[compiler_test(traps)]
+fn test_wasm_stack_size_rejects_zero(config: crate::Config) {
+    let store = config.store();
+    store.set_wasm_stack_size(Some(0));
+}
+
+#[cfg(unix)]
+#[cfg_attr(target_env = "musl", ignore)]
+#[compiler_test(traps)]
+fn test_trap_stack_overflow_on_wasm_stack_reentrant(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    store.set_wasm_stack_size(Some(512 * 1024));
+    let wat = r#"
+        (module $rec_mod
+            (import "" "reenter" (func $reenter))
+            (func $run (export "run") (call $run))
+            (func (export "enter") (call $reenter))
+        )
+    "#;
+
+    #[derive(WasmerEnv, Clone)]
+    struct Env {
+        #[wasmer(export)]
+        run: LazyInit<Function>,
+    }
+
+    let reenter = Function::new_with_env(
+        &store,
+        FunctionType::new(vec![], vec![]),
+        Env {
+            run: LazyInit::new(),
+        },
+        |env, _args| {
+            env.run_ref().unwrap().call(&[])?;
+            Ok(vec![])
+        },
+    );
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "" => {
+                "reenter" => reenter
+            }
+        },
+    )?;
+    let enter_func = instance
+        .exports
+        .get_function("enter")
+        .expect("expected function export");
+
+    let e = enter_func.call(&[]).err().expect("error calling function");
+    assert_eq!(e.to_trap(), Some(TrapCode::StackOverflow));
+
+    Ok(())
+}
+
 #[cfg_attr(target_env = "musl", ignore)]
 #[compiler_test(traps)]
 fn trap_display_pretty(config: crate::Config) -> Result<()> {
@@ -257,12 +453,10 @@ fn trap_start_function_import(config: crate::Config) -> Result<()> {
     .err()
     .unwrap();
     match err {
-        InstantiationError::Link(_) | InstantiationError::HostEnvInitialization(_) => {
-            panic!("It should be a start error")
-        }
         InstantiationError::Start(err) => {
             assert_eq!(err.message(), "user trap");
         }
+        _ => panic!("It should be a start error"),
     }
 
     Ok(())
@@ -358,6 +552,154 @@ fn rust_panic_start_function(config: crate::Config) -> Result<()> {
     Ok(())
 }
 
+#[compiler_test(traps)]
+fn rust_panic_import_caught(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    store.set_catch_host_panics(true);
+    let binary = r#"
+        (module $a
+            (import "" "foo" (func $foo))
+            (import "" "bar" (func $bar (param i32) (result i32)))
+            (func (export "foo") call $foo)
+            (func (export "bar") (result i32) (call $bar (i32.const 1)))
+        )
+    "#;
+
+    let module = Module::new(&store, &binary)?;
+    let sig = FunctionType::new(vec![], vec![]);
+    let func = Function::new(&store, &sig, |_| panic!("this is a panic"));
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "" => {
+                "foo" => func,
+                "bar" => Function::new_native(&store, |x: i32| -> i32 {
+                    panic!("this is panic number {}", x)
+                })
+            }
+        },
+    )?;
+
+    let err = instance.exports.get_function("foo")?.call(&[]).unwrap_err();
+    assert_eq!(err.host_panic(), Some("this is a panic"));
+    assert_eq!(err.message(), "host function panicked: this is a panic");
+    assert!(err.clone().to_trap().is_none());
+
+    let err = instance
+        .exports
+        .get_native_function::<(), i32>("bar")?
+        .call()
+        .unwrap_err();
+    assert_eq!(err.host_panic(), Some("this is panic number 1"));
+    Ok(())
+}
+
+thread_local! {
+    static GLOBAL_HOOK_CALLS: std::cell::Cell<usize> = std::cell::Cell::new(0);
+}
+
+/// Installs a panic hook counting the panics of each thread, before
+/// the hook of the stores is installed on top of it.
+fn count_global_hook_calls() {
+    static INIT: std::sync::Once = std::sync::Once::new();
+    INIT.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            GLOBAL_HOOK_CALLS.with(|calls| calls.set(calls.get() + 1));
+            previous(info)
+        }));
+    });
+}
+
+#[compiler_test(traps)]
+fn rust_panic_import_scoped_hook(config: crate::Config) -> Result<()> {
+    count_global_hook_calls();
+    let global_hook_calls = || GLOBAL_HOOK_CALLS.with(|calls| calls.get());
+    let store = config.store();
+    let scoped_panics = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let panics = scoped_panics.clone();
+    store.set_panic_hook(Some(Box::new(move |info| {
+        panics.lock().unwrap().push(info.to_string());
+    })));
+
+    // A store without a hook, called from a host function of `store`.
+    let inner_store = config.store();
+    let inner_module = Module::new(
+        &inner_store,
+        r#"
+            (module
+                (import "" "foo" (func $foo))
+                (func (export "foo") call $foo)
+            )
+        "#,
+    )?;
+    let inner_instance = Instance::new(
+        &inner_module,
+        &imports! {
+            "" => {
+                "foo" => Function::new_native(&inner_store, || panic!("this is an inner panic")),
+            }
+        },
+    )?;
+    let inner_foo = inner_instance.exports.get_function("foo")?.clone();
+
+    let module = Module::new(
+        &store,
+        r#"
+            (module
+                (import "" "foo" (func $foo))
+                (import "" "nested" (func $nested))
+                (func (export "foo") call $foo)
+                (func (export "nested") call $nested)
+            )
+        "#,
+    )?;
+    let sig = FunctionType::new(vec![], vec![]);
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "" => {
+                "foo" => Function::new_native(&store, || panic!("this is a panic")),
+                "nested" => Function::new(&store, &sig, move |_| {
+                    inner_foo.call(&[]).unwrap();
+                    Ok(vec![])
+                }),
+            }
+        },
+    )?;
+
+    let foo = instance.exports.get_function("foo")?;
+    let err = panic::catch_unwind(AssertUnwindSafe(|| foo.call(&[]))).unwrap_err();
+    assert_eq!(err.downcast_ref::<&'static str>(), Some(&"this is a panic"));
+    assert_eq!(global_hook_calls(), 0);
+
+    let nested = instance.exports.get_function("nested")?;
+    let err = panic::catch_unwind(AssertUnwindSafe(|| nested.call(&[]))).unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<&'static str>(),
+        Some(&"this is an inner panic")
+    );
+    assert_eq!(global_hook_calls(), 0);
+
+    let panics = scoped_panics.lock().unwrap().clone();
+    assert_eq!(panics.len(), 2);
+    assert!(panics[0].contains("this is a panic"));
+    assert!(panics[1].contains("this is an inner panic"));
+
+    // Caught panics still go through the hook of the store.
+    store.set_catch_host_panics(true);
+    let err = foo.call(&[]).unwrap_err();
+    assert_eq!(err.host_panic(), Some("this is a panic"));
+    assert_eq!(scoped_panics.lock().unwrap().len(), 3);
+    assert_eq!(global_hook_calls(), 0);
+
+    // Outside of wasm, panics go to the hook of the process.
+    let _ = panic::catch_unwind(|| panic!("this is a host panic"));
+    assert_eq!(global_hook_calls(), 1);
+    assert_eq!(scoped_panics.lock().unwrap().len(), 3);
+    Ok(())
+}
+
 #[compiler_test(traps)]
 fn mismatched_arguments(config: crate::Config) -> Result<()> {
     let store = config.store();
@@ -471,3 +813,335 @@ fn present_after_module_drop(config: crate::Config) -> Result<()> {
         // assert_eq!(t.trace()[0].func_index(), 0);
     }
 }
+
+#[compiler_test(traps)]
+fn native_func_traps_like_dynamic_call(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    let wat = r#"
+        (module
+            (func (export "run") (param i32) (result i32)
+                unreachable)
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let run = instance.exports.get_function("run")?;
+
+    let dynamic_error = run.call(&[Val::I32(1)]).unwrap_err();
+    let native_error = run.native::<i32, i32>()?.call(1).unwrap_err();
+    assert_eq!(
+        dynamic_error.clone().to_trap(),
+        Some(TrapCode::UnreachableCodeReached)
+    );
+    assert_eq!(
+        native_error.clone().to_trap(),
+        Some(TrapCode::UnreachableCodeReached)
+    );
+    assert_eq!(native_error.message(), dynamic_error.message());
+
+    // Host functions report their errors the same way through a
+    // native call, whether they are native or dynamic.
+    fn fail(_: i32) -> std::result::Result<i32, RuntimeError> {
+        Err(RuntimeError::new("host error"))
+    }
+    let native_host = Function::new_native(&store, fail);
+    let dynamic_host = Function::new(&store, native_host.ty(), |_| {
+        Err(RuntimeError::new("host error"))
+    });
+    for host in &[native_host, dynamic_host] {
+        let error = host.native::<i32, i32>()?.call(1).unwrap_err();
+        assert_eq!(error.message(), "host error");
+    }
+
+    Ok(())
+}
+
+#[compiler_test(traps)]
+#[cfg_attr(target_env = "musl", ignore)]
+fn trap_info_of_out_of_bounds_access(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "load") (result i32)
+                i32.const 0x10000
+                i32.load)
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let load = instance.exports.get_function("load")?;
+
+    let err = load.call(&[]).unwrap_err();
+    let trap_info = *err.trap_info().expect("expected a trap of generated code");
+    assert_ne!(trap_info.pc, 0);
+    assert_ne!(trap_info.signal, 0);
+    assert_eq!(trap_info.trap_code, TrapCode::HeapAccessOutOfBounds);
+    assert_eq!(err.to_trap(), Some(TrapCode::HeapAccessOutOfBounds));
+
+    // Errors raised by the host don't come from a fault.
+    assert!(RuntimeError::new("test").trap_info().is_none());
+    Ok(())
+}
+
+#[compiler_test(traps)]
+#[cfg_attr(target_env = "musl", ignore)]
+fn trap_codes(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    let wat = r#"
+        (module
+            (table 1 funcref)
+            (type $t (func))
+            (func (export "unreachable")
+                unreachable)
+            (func (export "div_by_zero") (result i32)
+                i32.const 1
+                i32.const 0
+                i32.div_s)
+            (func (export "overflow") (result i32)
+                i32.const 0x80000000
+                i32.const -1
+                i32.div_s)
+            (func (export "call_null")
+                i32.const 0
+                call_indirect (type $t))
+            (func $recurse (export "recurse")
+                call $recurse)
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let trap_code = |name: &str| -> Result<Option<TrapCode>> {
+        let func = instance.exports.get_function(name)?;
+        Ok(func.call(&[]).unwrap_err().trap_code())
+    };
+
+    assert_eq!(
+        trap_code("unreachable")?,
+        Some(TrapCode::UnreachableCodeReached)
+    );
+    assert_eq!(
+        trap_code("div_by_zero")?,
+        Some(TrapCode::IntegerDivisionByZero)
+    );
+    assert_eq!(trap_code("overflow")?, Some(TrapCode::IntegerOverflow));
+    assert_eq!(trap_code("call_null")?, Some(TrapCode::IndirectCallToNull));
+    assert_eq!(trap_code("recurse")?, Some(TrapCode::StackOverflow));
+
+    // Errors raised by the host aren't traps.
+    assert_eq!(RuntimeError::new("host error").trap_code(), None);
+    Ok(())
+}
+
+#[compiler_test(traps)]
+#[cfg_attr(target_env = "musl", ignore)]
+fn custom_trap_codes(config: crate::Config) -> Result<()> {
+    #[derive(Debug, PartialEq)]
+    enum HostTrap {
+        QuotaExceeded = 1,
+        Forbidden = 2,
+    }
+
+    let store = config.store();
+    let wat = r#"
+        (module $guest
+            (import "host" "check" (func $check (param i32)))
+            (import "host" "native_check" (func $native_check (param i32)))
+            (func $run (export "run") (param i32)
+                (call $check (local.get 0)))
+            (func (export "run_native") (param i32)
+                (call $native_check (local.get 0))
+                unreachable))
+    "#;
+    let module = Module::new(&store, wat)?;
+    let check = Function::new(
+        &store,
+        FunctionType::new(vec![Type::I32], vec![]),
+        |args| match args[0].unwrap_i32() {
+            0 => Ok(vec![]),
+            _ => Err(RuntimeError::custom_trap(
+                HostTrap::QuotaExceeded as u32,
+                "quota exceeded",
+            )),
+        },
+    );
+    fn native_check(_: i32) {
+        RuntimeError::raise_custom_trap(HostTrap::Forbidden as u32, "forbidden")
+    }
+    let instance = Instance::new(
+        &module,
+        &imports! {
+            "host" => {
+                "check" => check,
+                "native_check" => Function::new_native(&store, native_check),
+            }
+        },
+    )?;
+    let host_trap = |error: &RuntimeError| match error.custom_trap_code() {
+        Some(1) => Some(HostTrap::QuotaExceeded),
+        Some(2) => Some(HostTrap::Forbidden),
+        _ => None,
+    };
+
+    let run = instance.exports.get_function("run")?;
+    run.call(&[Val::I32(0)])?;
+    let error = run.call(&[Val::I32(1)]).unwrap_err();
+    assert_eq!(host_trap(&error), Some(HostTrap::QuotaExceeded));
+    assert_eq!(error.message(), "quota exceeded");
+    assert_eq!(error.trap_code(), None);
+    // The trace holds the Wasm frames that called the host, which only
+    // Cranelift reliably reports for now (see `test_trap_trace_cb`).
+    if config.compiler == crate::Compiler::Cranelift {
+        let trace = error.trace();
+        assert_eq!(trace.len(), 1);
+        assert_eq!(trace[0].module_name(), "guest");
+        assert_eq!(trace[0].function_name(), Some("run"));
+    }
+
+    let error = instance
+        .exports
+        .get_function("run_native")?
+        .call(&[Val::I32(1)])
+        .unwrap_err();
+    assert_eq!(host_trap(&error), Some(HostTrap::Forbidden));
+    assert_eq!(error.message(), "forbidden");
+
+    assert_eq!(RuntimeError::new("host error").custom_trap_code(), None);
+    Ok(())
+}
+
+#[compiler_test(traps)]
+fn trap_without_backtrace(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    store.set_capture_backtraces(false);
+    let wat = r#"
+        (module $hello_mod
+            (table 1 funcref)
+            (func (export "run") (call $hello))
+            (func $hello (unreachable))
+            (func (export "table_oob")
+                i32.const 2
+                i32.const 0
+                i32.const 1
+                table.copy)
+        )
+    "#;
+
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+
+    let e = instance.exports.get_function("run")?.call(&[]).unwrap_err();
+    assert_eq!(e.trap_code(), Some(TrapCode::UnreachableCodeReached));
+    assert!(e.trace().is_empty());
+
+    // Traps raised by the runtime don't capture a backtrace either.
+    let e = instance
+        .exports
+        .get_function("table_oob")?
+        .call(&[])
+        .unwrap_err();
+    assert_eq!(e.trap_code(), Some(TrapCode::TableAccessOutOfBounds));
+    assert!(e.trace().is_empty());
+
+    // The module was instantiated without registering what is needed
+    // to symbolicate its frames, until it is instantiated again.
+    store.set_capture_backtraces(true);
+    let e = instance.exports.get_function("run")?.call(&[]).unwrap_err();
+    if config.engine == crate::Engine::Universal {
+        assert!(e.trace().is_empty());
+    }
+    Instance::new(&module, &imports! {})?;
+    let e = instance.exports.get_function("run")?.call(&[]).unwrap_err();
+    assert_eq!(e.trace().len(), 2);
+    Ok(())
+}
+
+#[compiler_test(traps)]
+fn artifact_instantiate_registers_frame_info(config: crate::Config) -> Result<()> {
+    use wasmer_engine::Artifact;
+
+    let store = config.store();
+    store.set_capture_backtraces(false);
+    let wat = r#"
+        (module $hello_mod
+            (func (export "run") (call $hello))
+            (func $hello (unreachable))
+        )
+    "#;
+    let module = Module::new(&store, wat)?;
+
+    // Embedders instantiating the artifact themselves get the complete
+    // frame information registered, as before.
+    unsafe {
+        module
+            .artifact()
+            .instantiate(store.tunables(), &imports! {}, Box::new(()))?;
+    }
+    let instance = Instance::new(&module, &imports! {})?;
+    store.set_capture_backtraces(true);
+    let e = instance.exports.get_function("run")?.call(&[]).unwrap_err();
+    assert_eq!(e.trace().len(), 2);
+    Ok(())
+}
+
+#[compiler_test(traps)]
+#[cfg_attr(target_env = "musl", ignore)]
+fn out_of_bounds_access_without_backtrace(config: crate::Config) -> Result<()> {
+    let store = config.store();
+    store.set_capture_backtraces(false);
+    let wat = r#"
+        (module
+            (memory 1)
+            (func (export "load") (result i32)
+                i32.const 0x10000
+                i32.load)
+        )
+    "#;
+
+    // Faults of the generated code are still recognized as traps.
+    let module = Module::new(&store, wat)?;
+    let instance = Instance::new(&module, &imports! {})?;
+    let e = instance
+        .exports
+        .get_function("load")?
+        .call(&[])
+        .unwrap_err();
+    assert_eq!(e.trap_code(), Some(TrapCode::HeapAccessOutOfBounds));
+    assert!(e.trace().is_empty());
+    Ok(())
+}
+
+#[compiler_test(traps)]
+fn trap_observer_is_per_store(config: crate::Config) -> Result<()> {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let wat = r#"(module (func (export "run") (unreachable)))"#;
+    let observe = |store: &Store| {
+        let observed = Arc::new(AtomicUsize::new(0));
+        let counter = observed.clone();
+        store.set_trap_observer(Some(Box::new(move |trap| {
+            assert!(matches!(trap, vm::Trap::Wasm { .. }));
+            counter.fetch_add(1, Ordering::SeqCst);
+        })));
+        observed
+    };
+    let store = config.store();
+    let other_store = config.store();
+    let observed = observe(&store);
+    let other_observed = observe(&other_store);
+
+    let instance = Instance::new(&Module::new(&store, wat)?, &imports! {})?;
+    let e = instance.exports.get_function("run")?.call(&[]).unwrap_err();
+    assert_eq!(e.trap_code(), Some(TrapCode::UnreachableCodeReached));
+    assert_eq!(observed.load(Ordering::SeqCst), 1);
+    assert_eq!(other_observed.load(Ordering::SeqCst), 0);
+
+    store.set_trap_observer(None);
+    instance.exports.get_function("run")?.call(&[]).unwrap_err();
+    assert_eq!(observed.load(Ordering::SeqCst), 1);
+    Ok(())
+}
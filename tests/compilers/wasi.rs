@@ -32,3 +32,69 @@ pub fn run_wasi(config: crate::Config, wast_path: &str, base_dir: &str) -> anyho
 
     Ok(())
 }
+
+#[cfg(feature = "wasi")]
+#[compiler_test(wasi)]
+fn wasi_contract_violations(config: crate::Config) -> anyhow::Result<()> {
+    use wasmer::{ExternType, FunctionType, Module, Type};
+    use wasmer_wasi::{check_wasi_contract, ContractViolation, WasiVersion};
+
+    let store = config.store();
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "wasi_snapshot_preview1" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (import "wasi_snapshot_preview1" "proc_exit" (func (param i64)))
+            (import "wasi_snapshot_preview1" "fd_teleport" (func))
+            (import "wasi_snapshot_preview1" "memory" (memory 1))
+            (import "env" "log" (func (param i32))))"#,
+    )?;
+
+    let violations = check_wasi_contract(&module, WasiVersion::Snapshot1).unwrap_err();
+    assert_eq!(
+        violations,
+        vec![
+            ContractViolation::SignatureMismatch {
+                namespace: "wasi_snapshot_preview1".to_string(),
+                name: "proc_exit".to_string(),
+                expected: FunctionType::new(vec![Type::I32], vec![]),
+                found: FunctionType::new(vec![Type::I64], vec![]),
+            },
+            ContractViolation::UnknownImport {
+                namespace: "wasi_snapshot_preview1".to_string(),
+                name: "fd_teleport".to_string(),
+                ty: ExternType::Function(FunctionType::new(vec![], vec![])),
+            },
+            ContractViolation::UnknownImport {
+                namespace: "wasi_snapshot_preview1".to_string(),
+                name: "memory".to_string(),
+                ty: module
+                    .imports()
+                    .find(|import| import.name() == "memory")
+                    .unwrap()
+                    .ty()
+                    .clone(),
+            },
+        ]
+    );
+    assert_eq!(
+        violations[0].to_string(),
+        "`wasi_snapshot_preview1::proc_exit` has type [I64] -> [] \
+         but this version of WASI defines it as [I32] -> []"
+    );
+
+    // The imports of another namespace aren't checked.
+    assert_eq!(check_wasi_contract(&module, WasiVersion::Snapshot0), Ok(()));
+    let module = Module::new(
+        &store,
+        r#"(module
+            (import "wasi_unstable" "fd_write" (func (param i32 i32 i32 i32) (result i32)))
+            (import "wasi_unstable" "proc_exit" (func (param i32))))"#,
+    )?;
+    assert_eq!(check_wasi_contract(&module, WasiVersion::Snapshot0), Ok(()));
+
+    // Checking doesn't create anything in the store of the module.
+    store.freeze();
+    assert_eq!(check_wasi_contract(&module, WasiVersion::Snapshot0), Ok(()));
+    Ok(())
+}
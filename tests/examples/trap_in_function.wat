@@ -0,0 +1,8 @@
+(module
+    (func $faulty
+        unreachable
+    )
+    (func $main (export "_start")
+        call $faulty
+    )
+)
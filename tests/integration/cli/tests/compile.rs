@@ -170,3 +170,142 @@ fn staticlib_engine_works() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn dylib_engine_emits_object_and_header() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir().context("Making a temp dir")?;
+    let operating_dir: PathBuf = temp_dir.path().to_owned();
+    let wasm_path = PathBuf::from(format!("{}/{}", ASSET_PATH, "add.wat"));
+    let object_path = operating_dir.join("add.o");
+    let header_path = operating_dir.join("add.h");
+
+    let output = Command::new(get_wasmer_path())
+        .current_dir(&operating_dir)
+        .arg("compile")
+        .arg(&wasm_path.canonicalize()?)
+        .arg(Compiler::Cranelift.to_flag())
+        .arg(Engine::Dylib.to_flag())
+        .arg("--emit-object")
+        .arg("-o")
+        .arg(&object_path)
+        .arg("--header")
+        .arg(&header_path)
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "wasmer compile failed with: stdout: {}\n\nstderr: {}",
+            std::str::from_utf8(&output.stdout)
+                .expect("stdout is not utf8! need to handle arbitrary bytes"),
+            std::str::from_utf8(&output.stderr)
+                .expect("stderr is not utf8! need to handle arbitrary bytes")
+        );
+    }
+
+    assert!(fs::metadata(&object_path)?.len() > 0);
+    let header = fs::read_to_string(&header_path)?;
+    assert!(
+        header.contains(
+            "// Export \"add\".\nlong long wasmer_function__0(void*, long long, long long);"
+        ),
+        "unexpected header: {}",
+        header
+    );
+
+    Ok(())
+}
+
+#[test]
+fn universal_engine_prints_stats() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir().context("Making a temp dir")?;
+    let wasm_path = PathBuf::from(format!("{}/{}", ASSET_PATH, "add.wat"));
+    let artifact_path = temp_dir.path().join("add.wasmu");
+
+    let output = Command::new(get_wasmer_path())
+        .arg("compile")
+        .arg(&wasm_path.canonicalize()?)
+        .arg(Compiler::Cranelift.to_flag())
+        .arg(Engine::Universal.to_flag())
+        .arg("--stats")
+        .arg("-o")
+        .arg(&artifact_path)
+        .output()?;
+    let stdout = std::str::from_utf8(&output.stdout)
+        .expect("stdout is not utf8! need to handle arbitrary bytes");
+    if !output.status.success() {
+        bail!(
+            "wasmer compile failed with: stdout: {}\n\nstderr: {}",
+            stdout,
+            std::str::from_utf8(&output.stderr)
+                .expect("stderr is not utf8! need to handle arbitrary bytes")
+        );
+    }
+
+    let stats_line = stdout
+        .lines()
+        .find(|line| line.starts_with("functions="))
+        .with_context(|| format!("no stats in the output: {}", stdout))?;
+    let stats = stats_line
+        .split(' ')
+        .map(|field| {
+            let mut parts = field.splitn(2, '=');
+            let key = parts.next().unwrap();
+            let value = parts.next().unwrap().parse::<usize>().unwrap();
+            (key, value)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        stats.iter().map(|(key, _)| *key).collect::<Vec<_>>(),
+        [
+            "functions",
+            "code_bytes",
+            "trampoline_bytes",
+            "custom_section_bytes"
+        ]
+    );
+    // `add.wat` defines a single function.
+    assert_eq!(stats[0].1, 1);
+    assert!(stats[1].1 > 0);
+    assert!(stats[2].1 > 0);
+
+    Ok(())
+}
+
+#[test]
+fn compile_for_multiple_targets() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir().context("Making a temp dir")?;
+    let wasm_path = PathBuf::from(format!("{}/{}", ASSET_PATH, "add.wat"));
+    let output_dir = temp_dir.path().join("out");
+
+    let output = Command::new(get_wasmer_path())
+        .arg("compile")
+        .arg(&wasm_path.canonicalize()?)
+        .arg(Compiler::Cranelift.to_flag())
+        .arg(Engine::Universal.to_flag())
+        .arg("--target")
+        .arg("x86_64-unknown-linux-gnu")
+        .arg("--target")
+        .arg("aarch64-unknown-linux-gnu")
+        .arg("-o")
+        .arg(&output_dir)
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "wasmer compile failed with: stdout: {}\n\nstderr: {}",
+            std::str::from_utf8(&output.stdout)
+                .expect("stdout is not utf8! need to handle arbitrary bytes"),
+            std::str::from_utf8(&output.stderr)
+                .expect("stderr is not utf8! need to handle arbitrary bytes")
+        );
+    }
+
+    for triple in &["x86_64-unknown-linux-gnu", "aarch64-unknown-linux-gnu"] {
+        let artifact_path = output_dir.join(format!("add.{}.wasmu", triple));
+        assert!(
+            fs::metadata(&artifact_path)?.len() > 0,
+            "empty artifact for {}",
+            triple
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+//! CLI tests for the inspect subcommand.
+
+use anyhow::{bail, Context};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output};
+use wasmer_integration_tests_cli::{get_wasmer_path, Compiler, Engine, ASSET_PATH};
+
+fn call_indirect_wat_path() -> String {
+    format!("{}/{}", ASSET_PATH, "call_indirect.wat")
+}
+
+fn check_output(command: &str, output: Output) -> anyhow::Result<Output> {
+    if !output.status.success() {
+        bail!(
+            "wasmer {} failed with: stdout: {}\n\nstderr: {}",
+            command,
+            std::str::from_utf8(&output.stdout)
+                .expect("stdout is not utf8! need to handle arbitrary bytes"),
+            std::str::from_utf8(&output.stderr)
+                .expect("stderr is not utf8! need to handle arbitrary bytes")
+        );
+    }
+    Ok(output)
+}
+
+fn inspect_json(path: impl AsRef<Path>) -> anyhow::Result<serde_json::Value> {
+    let output = Command::new(get_wasmer_path())
+        .arg("inspect")
+        .arg("--json")
+        .arg(path.as_ref())
+        .output()?;
+    let output = check_output("inspect", output)?;
+    Ok(serde_json::from_slice(&output.stdout)?)
+}
+
+#[test]
+fn inspect_json_lists_exports() -> anyhow::Result<()> {
+    let manifest = inspect_json(call_indirect_wat_path())?;
+    let exports = manifest["exports"]
+        .as_array()
+        .expect("the manifest has no exports")
+        .iter()
+        .map(|export| export["name"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(exports, vec!["memory", "dispatch", "multiply", "main"]);
+    assert!(manifest["exports"][0]["ty"]["Memory"].is_object());
+    assert_eq!(manifest["memories"].as_array().unwrap().len(), 1);
+    assert_eq!(manifest["tables"].as_array().unwrap().len(), 1);
+    assert_eq!(manifest["has_start_function"], false);
+    // The module only uses the MVP, whatever the engine enables.
+    assert_eq!(manifest["features"]["bulk_memory"], true);
+    assert_eq!(manifest["used_features"]["bulk_memory"], false);
+
+    Ok(())
+}
+
+#[test]
+fn inspect_artifact_lists_used_proposals() -> anyhow::Result<()> {
+    let temp_dir = tempfile::tempdir().context("Making a temp dir")?;
+    let wat_path = temp_dir.path().join("pair.wat");
+    let artifact_path = temp_dir.path().join("pair.wasmu");
+    fs::write(
+        &wat_path,
+        r#"(module
+            (func (export "pair") (result i32 i32)
+                (i32.const 1)
+                (i32.const 2)))"#,
+    )?;
+
+    let output = Command::new(get_wasmer_path())
+        .arg("compile")
+        .arg(&wat_path)
+        .arg(Compiler::Cranelift.to_flag())
+        .arg(Engine::Universal.to_flag())
+        .arg("-o")
+        .arg(&artifact_path)
+        .output()?;
+    check_output("compile", output)?;
+
+    let manifest = inspect_json(&artifact_path)?;
+    assert_eq!(manifest["exports"][0]["name"], "pair");
+    assert_eq!(manifest["used_features"]["multi_value"], true);
+    assert_eq!(manifest["used_features"]["simd"], false);
+
+    let output = Command::new(get_wasmer_path())
+        .arg("inspect")
+        .arg(&artifact_path)
+        .output()?;
+    let output = check_output("inspect", output)?;
+    let stdout = std::str::from_utf8(&output.stdout)?;
+    assert!(stdout.contains("Type: universal artifact"), "{}", stdout);
+    assert!(
+        stdout.contains("Proposals used:\n  multi-value\nCustom sections:"),
+        "{}",
+        stdout
+    );
+
+    Ok(())
+}
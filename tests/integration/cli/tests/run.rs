@@ -75,3 +75,66 @@ fn run_no_start_wasm_report_error() -> anyhow::Result<()> {
     assert_eq!(result.contains("Can not find any export functions."), true);
     Ok(())
 }
+
+#[test]
+fn run_trap_reports_function_name() -> anyhow::Result<()> {
+    let output = Command::new(WASMER_PATH)
+        .arg("run")
+        .arg(format!("{}/{}", ASSET_PATH, "trap_in_function.wat"))
+        .output()?;
+
+    assert_eq!(output.status.success(), false);
+    let result = std::str::from_utf8(&output.stderr).unwrap().to_string();
+    assert!(
+        result.contains("wasm trap: unreachable"),
+        "unexpected output: {}",
+        result
+    );
+    assert!(
+        result.contains("in function `faulty`"),
+        "unexpected output: {}",
+        result
+    );
+    assert!(
+        result.contains("at main ("),
+        "unexpected output: {}",
+        result
+    );
+    Ok(())
+}
+
+#[test]
+fn run_reuses_cached_module() -> anyhow::Result<()> {
+    let cache_dir = tempfile::tempdir()?;
+    let run = || {
+        Command::new(WASMER_PATH)
+            .env("WASMER_CACHE_DIR", cache_dir.path())
+            .arg("run")
+            .arg("--verbose")
+            .arg(wasi_test_wasm_path())
+            .arg("--")
+            .arg("-e")
+            .arg("print(3 * (4 + 5))")
+            .output()
+    };
+
+    let first = run()?;
+    assert!(first.status.success());
+    let first_stderr = std::str::from_utf8(&first.stderr).unwrap();
+    assert!(
+        first_stderr.contains("cache miss"),
+        "unexpected output: {}",
+        first_stderr
+    );
+
+    let second = run()?;
+    assert!(second.status.success());
+    assert_eq!(std::str::from_utf8(&second.stdout).unwrap(), "27\n");
+    let second_stderr = std::str::from_utf8(&second.stderr).unwrap();
+    assert!(
+        second_stderr.contains("cache hit"),
+        "unexpected output: {}",
+        second_stderr
+    );
+    Ok(())
+}
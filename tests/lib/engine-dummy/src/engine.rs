@@ -72,6 +72,10 @@ impl Engine for DummyEngine {
         self.signatures.lookup(sig)
     }
 
+    fn signature_count(&self) -> usize {
+        self.signatures.len()
+    }
+
     #[cfg(feature = "compiler")]
     /// Validates a WebAssembly module
     fn validate(&self, binary: &[u8]) -> Result<(), CompileError> {